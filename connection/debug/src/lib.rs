@@ -18,14 +18,17 @@ use libfieldmonitor::adapter::spice::SpiceAdapter;
 use libfieldmonitor::adapter::types::Adapter;
 use libfieldmonitor::adapter::vnc::VncAdapter;
 use libfieldmonitor::connection::*;
+use libfieldmonitor::host;
 
 use crate::arbitrary_adapter::DebugArbitraryAdapter;
 use crate::behaviour_preferences::{DebugBehaviour, DebugBehaviourPreferences};
-use crate::preferences::{DebugConfiguration, DebugMode, DebugPreferences};
+use crate::network_shaping::start_proxy;
+use crate::preferences::{DebugAdapterConfig, DebugConfiguration, DebugMode, DebugPreferences};
 use crate::vte_adapter::DebugVteAdapter;
 
 mod arbitrary_adapter;
 mod behaviour_preferences;
+mod network_shaping;
 mod preferences;
 mod vte_adapter;
 
@@ -91,28 +94,33 @@ impl ConnectionProvider for DebugConnectionProvider {
                 .downcast::<DebugPreferences>()
                 .expect("update_connection got invalid widget type");
 
-            configuration = configuration
-                .transform_update_unified(|configuration| {
-                    // Update general config
-                    configuration.set_title(&preferences.title());
-                    configuration.set_mode(preferences.mode());
-                    configuration.set_vnc_adapter_enable(preferences.vnc_adapter_enable());
-                    configuration.set_vnc_host(&preferences.vnc_host());
-                    configuration.set_vnc_user(&preferences.vnc_user());
-                    configuration.set_vnc_password(&preferences.vnc_password());
-                    configuration.set_rdp_adapter_enable(preferences.rdp_adapter_enable());
-                    configuration.set_rdp_host(&preferences.rdp_host());
-                    configuration.set_rdp_user(&preferences.rdp_user());
-                    configuration.set_rdp_password(&preferences.rdp_password());
-                    configuration.set_spice_adapter_enable(preferences.spice_adapter_enable());
-                    configuration.set_spice_host(&preferences.spice_host());
-                    configuration.set_spice_password(&preferences.spice_password());
-                    configuration.set_vte_adapter_enable(preferences.vte_adapter_enable());
-                    configuration.set_custom_adapter_enable(preferences.custom_adapter_enable());
-                    configuration.set_custom_overlayed(preferences.custom_overlayed());
-                    Result::<(), Infallible>::Ok(())
-                })
-                .unwrap();
+            let adapters = DebugAdapterConfig {
+                vnc_adapter_enable: preferences.vnc_adapter_enable(),
+                vnc_host: preferences.vnc_host(),
+                vnc_user: preferences.vnc_user(),
+                vnc_password: preferences.vnc_password(),
+                rdp_adapter_enable: preferences.rdp_adapter_enable(),
+                rdp_host: preferences.rdp_host(),
+                rdp_user: preferences.rdp_user(),
+                rdp_password: preferences.rdp_password(),
+                spice_adapter_enable: preferences.spice_adapter_enable(),
+                spice_host: preferences.spice_host(),
+                spice_password: preferences.spice_password(),
+                vte_adapter_enable: preferences.vte_adapter_enable(),
+                custom_adapter_enable: preferences.custom_adapter_enable(),
+                custom_overlayed: preferences.custom_overlayed(),
+                shaping_enable: preferences.shaping_enable(),
+                shaping_latency_ms: preferences.shaping_latency_ms(),
+                shaping_loss_percent: preferences.shaping_loss_percent(),
+                shaping_bandwidth_kib: preferences.shaping_bandwidth_kib(),
+            };
+
+            configuration = configuration.transform_update_unified(|configuration| {
+                // Update general config
+                configuration.set_title(&preferences.title());
+                configuration.set_mode(preferences.mode());
+                configuration.set_adapters(&adapters)
+            })?;
 
             // Update credentials
             let credentials = preferences.behaviour();
@@ -278,7 +286,8 @@ impl Connection for DebugConnection {
 
             match self.config.load_servers_behaviour() {
                 DebugBehaviour::Ok => {
-                    let mut hm: IndexMap<Cow<_>, Box<dyn ServerConnection>> = IndexMap::new();
+                    let mut hm: IndexMap<Cow<_>, Box<dyn ServerConnection + Send>> =
+                        IndexMap::new();
 
                     match self.config.mode() {
                         DebugMode::Single => {
@@ -538,20 +547,21 @@ impl ServerConnection for DebugConnectionServer {
         if !self.has_adapters {
             return vec![];
         }
+        let config = self.config.adapters();
         let mut adapters = Vec::with_capacity(4);
-        if self.config.vnc_adapter_enable() {
+        if config.vnc_adapter_enable {
             adapters.push((VncAdapter::TAG.into(), VncAdapter::label()));
         }
-        if self.config.rdp_adapter_enable() {
+        if config.rdp_adapter_enable {
             adapters.push((RdpAdapter::TAG.into(), RdpAdapter::label()));
         }
-        if self.config.spice_adapter_enable() {
+        if config.spice_adapter_enable {
             adapters.push((SpiceAdapter::TAG.into(), SpiceAdapter::label()));
         }
-        if self.config.vte_adapter_enable() {
+        if config.vte_adapter_enable {
             adapters.push((DebugVteAdapter::TAG.into(), "VTE".into()));
         }
-        if self.config.custom_adapter_enable() {
+        if config.custom_adapter_enable {
             adapters.push((DebugArbitraryAdapter::TAG.into(), "Arbitrary Widget".into()));
         }
         adapters
@@ -565,32 +575,37 @@ impl ServerConnection for DebugConnectionServer {
         Box::pin(async move {
             match self.config.connect_behaviour() {
                 DebugBehaviour::Ok => {
+                    let config = self.config.adapters();
                     let adapter: Box<dyn Adapter> = match &*tag {
                         VncAdapter::TAG => {
-                            let (host, port) = parse_host_port(self.config.vnc_host())?;
+                            let (host, port) =
+                                shape_network(&config, parse_host_port(&config.vnc_host)?).await?;
                             Box::new(VncAdapter::new(
-                                host.to_string(),
+                                host,
                                 port,
-                                self.config.vnc_user().to_string(),
-                                self.config.vnc_password().into(),
+                                config.vnc_user,
+                                config.vnc_password.into(),
                             ))
                         }
                         RdpAdapter::TAG => {
-                            let (host, port) = parse_host_port(self.config.rdp_host())?;
+                            let (host, port) =
+                                shape_network(&config, parse_host_port(&config.rdp_host)?).await?;
                             Box::new(RdpAdapter::new(
-                                host.to_string(),
+                                host,
                                 port,
-                                self.config.rdp_user().to_string(),
-                                self.config.rdp_password().into(),
+                                config.rdp_user,
+                                config.rdp_password.into(),
                             ))
                         }
                         SpiceAdapter::TAG => {
-                            let (host, port) = parse_host_port(self.config.spice_host())?;
+                            let (host, port) =
+                                shape_network(&config, parse_host_port(&config.spice_host)?)
+                                    .await?;
                             Box::new(SpiceAdapter::new(
-                                host.to_string(),
+                                host,
                                 port,
                                 "".to_string(),
-                                self.config.spice_password().into(),
+                                config.spice_password.into(),
                             ))
                         }
                         DebugVteAdapter::TAG => Box::new(DebugVteAdapter {
@@ -598,7 +613,7 @@ impl ServerConnection for DebugConnectionServer {
                         }),
                         DebugArbitraryAdapter::TAG => Box::new(DebugArbitraryAdapter {
                             mode: self.config.connect_behaviour(),
-                            overlayed: self.config.custom_overlayed(),
+                            overlayed: config.custom_overlayed,
                         }),
                         _ => unimplemented!("invalid tag"),
                     };
@@ -621,7 +636,7 @@ impl ServerConnection for DebugConnectionServer {
         Box::pin(async move {
             sleep(Duration::from_millis(thread_rng().gen_range(50..200))).await;
 
-            let mut hm: IndexMap<Cow<_>, Box<dyn ServerConnection>> = IndexMap::new();
+            let mut hm: IndexMap<Cow<_>, Box<dyn ServerConnection + Send>> = IndexMap::new();
 
             for (name, server) in &self.servers {
                 hm.insert(name.clone(), Box::new(server.clone()));
@@ -632,28 +647,24 @@ impl ServerConnection for DebugConnectionServer {
     }
 }
 
-fn parse_host_port(host: &str) -> ConnectionResult<(&str, u32)> {
-    let mut host_parts = host.split(":");
-    let Some(host) = host_parts.next() else {
-        return Err(ConnectionError::General(
-            Some("invalid host".to_string()),
-            anyhow!("invalid host"),
-        ));
-    };
-    let Some(port) = host_parts.next() else {
-        return Err(ConnectionError::General(
-            Some("invalid host".to_string()),
-            anyhow!("invalid host"),
-        ));
-    };
-    let port: u32 = match port.parse() {
-        Ok(v) => v,
-        Err(e) => {
-            return Err(ConnectionError::General(
-                Some("invalid port".to_string()),
-                e.into(),
-            ));
-        }
-    };
-    Ok((host, port))
+fn parse_host_port(host: &str) -> ConnectionResult<(String, u32)> {
+    host::parse_host_with_port(host)
+        .map(|(host, port)| (host, port.into()))
+        .map_err(|err| ConnectionError::General(Some(err.to_string()), err.into()))
+}
+
+/// Routes the connection to `host` and `port` through a network shaping proxy, if simulating a
+/// bad network is enabled.
+async fn shape_network(
+    config: &DebugAdapterConfig,
+    (host, port): (String, u32),
+) -> ConnectionResult<(String, u32)> {
+    let shaping = config.network_shaping();
+    if !shaping.is_active() {
+        return Ok((host, port));
+    }
+    let local_port = start_proxy(host, port, shaping)
+        .await
+        .map_err(|err| ConnectionError::General(Some(err.to_string()), err.into()))?;
+    Ok(("127.0.0.1".to_string(), local_port.into()))
 }
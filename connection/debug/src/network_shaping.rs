@@ -0,0 +1,160 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Simulated bad network conditions for the remote desktop adapters. Connections are routed
+//! through a local proxy, which delays, throttles and "loses" the forwarded data.
+use std::io;
+use std::net::Shutdown;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_std::future::timeout;
+use async_std::net::{TcpListener, TcpStream};
+use async_std::task::{sleep, spawn};
+use futures::channel::mpsc;
+use futures::{try_join, AsyncReadExt, AsyncWriteExt, StreamExt};
+use log::{debug, warn};
+use rand::{thread_rng, Rng};
+
+/// Size of the chunks data is forwarded in.
+const CHUNK_SIZE: usize = 16 * 1024;
+/// The proxy stops once it had no connections for this long.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+/// Additional delay of lost chunks, on top of twice the latency. Roughly the time TCP takes to
+/// notice the loss and re-send.
+const RETRANSMIT_DELAY: Duration = Duration::from_millis(200);
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NetworkShaping {
+    pub latency: Duration,
+    /// Probability between 0 and 1 that a chunk is lost and has to be re-sent.
+    pub loss: f64,
+    /// Maximum throughput in bytes per second in each direction, 0 for no limit.
+    pub bandwidth: u64,
+}
+
+impl NetworkShaping {
+    pub fn is_active(&self) -> bool {
+        !self.latency.is_zero() || self.loss > 0.0 || self.bandwidth > 0
+    }
+}
+
+/// Starts a proxy on localhost forwarding connections to `host`:`port` with `shaping` applied.
+/// Returns the port the proxy listens on.
+pub async fn start_proxy(host: String, port: u32, shaping: NetworkShaping) -> io::Result<u16> {
+    let port = u16::try_from(port)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "port out of range"))?;
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+    let local_port = listener.local_addr()?.port();
+    debug!("network shaping proxy for {host}:{port} listens on port {local_port}: {shaping:?}");
+
+    spawn(async move {
+        let active = Arc::new(AtomicUsize::new(0));
+        loop {
+            let client = match timeout(IDLE_TIMEOUT, listener.accept()).await {
+                Ok(Ok((client, _))) => client,
+                Ok(Err(err)) => {
+                    warn!("network shaping proxy failed to accept connection: {err}");
+                    break;
+                }
+                Err(_) if active.load(Ordering::Acquire) == 0 => break,
+                Err(_) => continue,
+            };
+            active.fetch_add(1, Ordering::AcqRel);
+            let host = host.clone();
+            let active = active.clone();
+            spawn(async move {
+                if let Err(err) = forward(client, &host, port, shaping).await {
+                    debug!("network shaping proxy connection failed: {err}");
+                }
+                active.fetch_sub(1, Ordering::AcqRel);
+            });
+        }
+        debug!("network shaping proxy on port {local_port} stopped");
+    });
+
+    Ok(local_port)
+}
+
+async fn forward(
+    client: TcpStream,
+    host: &str,
+    port: u16,
+    shaping: NetworkShaping,
+) -> io::Result<()> {
+    let server = TcpStream::connect((host, port)).await?;
+    client.set_nodelay(true)?;
+    server.set_nodelay(true)?;
+    try_join!(
+        shaped_pipe(client.clone(), server.clone(), shaping),
+        shaped_pipe(server, client, shaping)
+    )?;
+    Ok(())
+}
+
+/// Forwards data from `from` to `to`. Reading is throttled to the bandwidth, while the latency
+/// is applied when writing, so that data in flight does not reduce the throughput.
+async fn shaped_pipe(
+    mut from: TcpStream,
+    mut to: TcpStream,
+    shaping: NetworkShaping,
+) -> io::Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded::<(Instant, Vec<u8>)>();
+
+    let read = async move {
+        let mut buf = vec![0; CHUNK_SIZE];
+        loop {
+            let len = from.read(&mut buf).await?;
+            if len == 0 {
+                break;
+            }
+            let lost = shaping.loss > 0.0 && thread_rng().gen_bool(shaping.loss);
+            let mut due = Instant::now() + shaping.latency;
+            if lost {
+                due += shaping.latency * 2 + RETRANSMIT_DELAY;
+            }
+            if sender.unbounded_send((due, buf[..len].to_vec())).is_err() {
+                break;
+            }
+            if shaping.bandwidth > 0 {
+                sleep(Duration::from_secs_f64(
+                    len as f64 / shaping.bandwidth as f64,
+                ))
+                .await;
+            }
+        }
+        io::Result::Ok(())
+    };
+
+    let write = async move {
+        // Chunks are written in order, so a lost chunk also holds up the following ones, as
+        // with TCP.
+        while let Some((due, chunk)) = receiver.next().await {
+            let now = Instant::now();
+            if due > now {
+                sleep(due - now).await;
+            }
+            to.write_all(&chunk).await?;
+        }
+        to.shutdown(Shutdown::Write)?;
+        io::Result::Ok(())
+    };
+
+    try_join!(read, write)?;
+    Ok(())
+}
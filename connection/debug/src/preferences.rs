@@ -16,20 +16,26 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::sync::atomic::AtomicBool;
+use std::time::Duration;
 
 use adw::subclass::prelude::*;
 use glib::clone;
 use gtk::gio::{PropertyAction, SimpleActionGroup};
 use gtk::glib;
 use gtk::prelude::*;
+use log::warn;
 use num_enum::TryFromPrimitive;
+use serde::{Deserialize, Serialize};
 
-use libfieldmonitor::connection::{ConfigAccess, ConfigAccessMut, ConnectionConfiguration};
+use libfieldmonitor::connection::{
+    ConfigAccess, ConfigAccessMut, ConnectionConfiguration, TypedConfigError,
+};
 use libfieldmonitor::impl_primitive_enum_param_spec;
 
 use crate::behaviour_preferences::{DebugBehaviour, DebugBehaviourPreferences};
+use crate::network_shaping::NetworkShaping;
 
 pub(super) trait DebugConfiguration {
     fn title(&self) -> &str;
@@ -44,34 +50,8 @@ pub(super) trait DebugConfiguration {
     fn set_store_session(&mut self, value: &str);
     fn store_persistent(&self) -> &str;
     fn set_store_persistent(&mut self, value: &str);
-    fn vnc_adapter_enable(&self) -> bool;
-    fn set_vnc_adapter_enable(&mut self, value: bool);
-    fn vnc_host(&self) -> &str;
-    fn set_vnc_host(&mut self, value: &str);
-    fn vnc_user(&self) -> &str;
-    fn set_vnc_user(&mut self, value: &str);
-    fn vnc_password(&self) -> &str;
-    fn set_vnc_password(&mut self, value: &str);
-    fn rdp_adapter_enable(&self) -> bool;
-    fn set_rdp_adapter_enable(&mut self, value: bool);
-    fn rdp_host(&self) -> &str;
-    fn set_rdp_host(&mut self, value: &str);
-    fn rdp_user(&self) -> &str;
-    fn set_rdp_user(&mut self, value: &str);
-    fn rdp_password(&self) -> &str;
-    fn set_rdp_password(&mut self, value: &str);
-    fn spice_adapter_enable(&self) -> bool;
-    fn set_spice_adapter_enable(&mut self, value: bool);
-    fn spice_host(&self) -> &str;
-    fn set_spice_host(&mut self, value: &str);
-    fn spice_password(&self) -> &str;
-    fn set_spice_password(&mut self, value: &str);
-    fn vte_adapter_enable(&self) -> bool;
-    fn set_vte_adapter_enable(&mut self, value: bool);
-    fn custom_adapter_enable(&self) -> bool;
-    fn set_custom_adapter_enable(&mut self, value: bool);
-    fn custom_overlayed(&self) -> bool;
-    fn set_custom_overlayed(&mut self, value: bool);
+    fn adapters(&self) -> DebugAdapterConfig;
+    fn set_adapters(&mut self, value: &DebugAdapterConfig) -> Result<(), TypedConfigError>;
 }
 
 impl DebugConfiguration for ConnectionConfiguration {
@@ -132,121 +112,53 @@ impl DebugConfiguration for ConnectionConfiguration {
         self.set_value("store-persistent", value);
     }
 
-    fn vnc_adapter_enable(&self) -> bool {
-        self.get_try_as_bool("vnc-adapter-enable")
-            .unwrap_or_default()
-    }
-
-    fn set_vnc_adapter_enable(&mut self, value: bool) {
-        self.set_value("vnc-adapter-enable", value);
-    }
-
-    fn vnc_host(&self) -> &str {
-        self.get_try_as_str("vnc-host").unwrap_or_default()
-    }
-
-    fn set_vnc_host(&mut self, value: &str) {
-        self.set_value("vnc-host", value);
-    }
-
-    fn vnc_user(&self) -> &str {
-        self.get_try_as_str("vnc-user").unwrap_or_default()
-    }
-
-    fn set_vnc_user(&mut self, value: &str) {
-        self.set_value("vnc-user", value);
-    }
-
-    fn vnc_password(&self) -> &str {
-        self.get_try_as_str("vnc-password").unwrap_or_default()
-    }
-
-    fn set_vnc_password(&mut self, value: &str) {
-        self.set_value("vnc-password", value);
+    fn adapters(&self) -> DebugAdapterConfig {
+        self.typed().unwrap_or_else(|err| {
+            warn!("invalid debug adapter config, using defaults: {err}");
+            Default::default()
+        })
     }
 
-    fn rdp_adapter_enable(&self) -> bool {
-        self.get_try_as_bool("rdp-adapter-enable")
-            .unwrap_or_default()
-    }
-
-    fn set_rdp_adapter_enable(&mut self, value: bool) {
-        self.set_value("rdp-adapter-enable", value);
-    }
-
-    fn rdp_host(&self) -> &str {
-        self.get_try_as_str("rdp-host").unwrap_or_default()
-    }
-
-    fn set_rdp_host(&mut self, value: &str) {
-        self.set_value("rdp-host", value);
-    }
-
-    fn rdp_user(&self) -> &str {
-        self.get_try_as_str("rdp-user").unwrap_or_default()
-    }
-
-    fn set_rdp_user(&mut self, value: &str) {
-        self.set_value("rdp-user", value);
-    }
-
-    fn rdp_password(&self) -> &str {
-        self.get_try_as_str("rdp-password").unwrap_or_default()
-    }
-
-    fn set_rdp_password(&mut self, value: &str) {
-        self.set_value("rdp-password", value);
-    }
-
-    fn spice_adapter_enable(&self) -> bool {
-        self.get_try_as_bool("spice-adapter-enable")
-            .unwrap_or_default()
-    }
-
-    fn set_spice_adapter_enable(&mut self, value: bool) {
-        self.set_value("spice-adapter-enable", value);
-    }
-
-    fn spice_host(&self) -> &str {
-        self.get_try_as_str("spice-host").unwrap_or_default()
-    }
-
-    fn set_spice_host(&mut self, value: &str) {
-        self.set_value("spice-host", value);
-    }
-
-    fn spice_password(&self) -> &str {
-        self.get_try_as_str("spice-password").unwrap_or_default()
-    }
-
-    fn set_spice_password(&mut self, value: &str) {
-        self.set_value("spice-password", value);
-    }
-
-    fn vte_adapter_enable(&self) -> bool {
-        self.get_try_as_bool("vte-adapter-enable")
-            .unwrap_or_default()
-    }
-
-    fn set_vte_adapter_enable(&mut self, value: bool) {
-        self.set_value("vte-adapter-enable", value);
-    }
-
-    fn custom_adapter_enable(&self) -> bool {
-        self.get_try_as_bool("custom-adapter-enable")
-            .unwrap_or_default()
-    }
-
-    fn set_custom_adapter_enable(&mut self, value: bool) {
-        self.set_value("custom-adapter-enable", value);
+    fn set_adapters(&mut self, value: &DebugAdapterConfig) -> Result<(), TypedConfigError> {
+        self.set_typed(value)
     }
+}
 
-    fn custom_overlayed(&self) -> bool {
-        self.get_try_as_bool("custom-overlayed").unwrap_or_default()
-    }
+/// Settings of the adapters the servers of a debug connection offer.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct DebugAdapterConfig {
+    pub vnc_adapter_enable: bool,
+    pub vnc_host: String,
+    pub vnc_user: String,
+    pub vnc_password: String,
+    pub rdp_adapter_enable: bool,
+    pub rdp_host: String,
+    pub rdp_user: String,
+    pub rdp_password: String,
+    pub spice_adapter_enable: bool,
+    pub spice_host: String,
+    pub spice_password: String,
+    pub vte_adapter_enable: bool,
+    pub custom_adapter_enable: bool,
+    pub custom_overlayed: bool,
+    pub shaping_enable: bool,
+    pub shaping_latency_ms: u32,
+    pub shaping_loss_percent: u32,
+    /// Maximum throughput in KiB/s, 0 for no limit.
+    pub shaping_bandwidth_kib: u32,
+}
 
-    fn set_custom_overlayed(&mut self, value: bool) {
-        self.set_value("custom-overlayed", value);
+impl DebugAdapterConfig {
+    pub fn network_shaping(&self) -> NetworkShaping {
+        if !self.shaping_enable {
+            return NetworkShaping::default();
+        }
+        NetworkShaping {
+            latency: Duration::from_millis(self.shaping_latency_ms.into()),
+            loss: f64::from(self.shaping_loss_percent.min(100)) / 100.0,
+            bandwidth: u64::from(self.shaping_bandwidth_kib) * 1024,
+        }
     }
 }
 
@@ -312,6 +224,14 @@ mod imp {
         pub custom_adapter_enable: AtomicBool,
         #[property(get, set)]
         pub custom_overlayed: AtomicBool,
+        #[property(get, set)]
+        pub shaping_enable: AtomicBool,
+        #[property(get, set)]
+        pub shaping_latency_ms: Cell<u32>,
+        #[property(get, set, maximum = 100)]
+        pub shaping_loss_percent: Cell<u32>,
+        #[property(get, set)]
+        pub shaping_bandwidth_kib: Cell<u32>,
     }
 
     #[glib::object_subclass]
@@ -374,20 +294,25 @@ impl DebugPreferences {
                 async move {
                     slf.set_title(existing_configuration.title());
                     slf.set_mode(existing_configuration.mode());
-                    slf.set_vnc_adapter_enable(existing_configuration.vnc_adapter_enable());
-                    slf.set_vnc_host(existing_configuration.vnc_host());
-                    slf.set_vnc_user(existing_configuration.vnc_user());
-                    slf.set_vnc_password(existing_configuration.vnc_password());
-                    slf.set_rdp_adapter_enable(existing_configuration.rdp_adapter_enable());
-                    slf.set_rdp_host(existing_configuration.rdp_host());
-                    slf.set_rdp_user(existing_configuration.rdp_user());
-                    slf.set_rdp_password(existing_configuration.rdp_password());
-                    slf.set_spice_adapter_enable(existing_configuration.spice_adapter_enable());
-                    slf.set_spice_host(existing_configuration.spice_host());
-                    slf.set_spice_password(existing_configuration.spice_password());
-                    slf.set_vte_adapter_enable(existing_configuration.vte_adapter_enable());
-                    slf.set_custom_adapter_enable(existing_configuration.custom_adapter_enable());
-                    slf.set_custom_overlayed(existing_configuration.custom_overlayed());
+                    let adapters = existing_configuration.adapters();
+                    slf.set_vnc_adapter_enable(adapters.vnc_adapter_enable);
+                    slf.set_vnc_host(adapters.vnc_host.as_str());
+                    slf.set_vnc_user(adapters.vnc_user.as_str());
+                    slf.set_vnc_password(adapters.vnc_password.as_str());
+                    slf.set_rdp_adapter_enable(adapters.rdp_adapter_enable);
+                    slf.set_rdp_host(adapters.rdp_host.as_str());
+                    slf.set_rdp_user(adapters.rdp_user.as_str());
+                    slf.set_rdp_password(adapters.rdp_password.as_str());
+                    slf.set_spice_adapter_enable(adapters.spice_adapter_enable);
+                    slf.set_spice_host(adapters.spice_host.as_str());
+                    slf.set_spice_password(adapters.spice_password.as_str());
+                    slf.set_vte_adapter_enable(adapters.vte_adapter_enable);
+                    slf.set_custom_adapter_enable(adapters.custom_adapter_enable);
+                    slf.set_custom_overlayed(adapters.custom_overlayed);
+                    slf.set_shaping_enable(adapters.shaping_enable);
+                    slf.set_shaping_latency_ms(adapters.shaping_latency_ms);
+                    slf.set_shaping_loss_percent(adapters.shaping_loss_percent.min(100));
+                    slf.set_shaping_bandwidth_kib(adapters.shaping_bandwidth_kib);
 
                     slf.imp()
                         .behaviour
@@ -0,0 +1,314 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! A minimal connection provider, meant as a starting point for writing new ones.
+//!
+//! A connection provider is registered in `CONNECTION_PROVIDERS` of the application with its
+//! [`ConnectionProviderConstructor`]. The application then drives it through these steps:
+//!
+//! 1. When the user adds or edits a connection, [`ConnectionProvider::preferences`] creates the
+//!    widget for editing it. Once the user saves, [`ConnectionProvider::update_connection`]
+//!    receives that widget back and writes its values into the configuration. Secrets such as
+//!    passwords are stored with [`ConfigAccessMut::set_secret`] and end up in the keyring.
+//! 2. [`ConnectionProvider::load_connection`] turns a saved configuration into a
+//!    [`Connection`]. This must be quick; anything slow, such as talking to an API, belongs in
+//!    [`Connection::servers`].
+//! 3. Every [`ServerConnection`] lists the adapters (protocols) it can be displayed with.
+//!    When the user opens a server, [`ServerConnection::create_adapter`] creates the adapter,
+//!    which then connects and provides the widget showing the remote screen.
+//!
+//! This provider offers a single VNC server per connection. It is only built with the `devel`
+//! feature. Its test in `tests/lifecycle.rs` shows how providers can be tested with
+//! [`libfieldmonitor::testing`], which needs the `testing` feature of `libfieldmonitor`.
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use futures::future::LocalBoxFuture;
+use gettextrs::gettext;
+use gtk::prelude::*;
+use indexmap::IndexMap;
+use secure_string::SecureString;
+use serde::{Deserialize, Serialize};
+
+use libfieldmonitor::adapter::types::Adapter;
+use libfieldmonitor::adapter::vnc::VncAdapter;
+use libfieldmonitor::connection::*;
+
+use crate::preferences::ExamplePreferences;
+
+mod preferences;
+
+const TAG: &str = "example";
+const ICON: &str = "applications-engineering-symbolic";
+/// Key of the password in the secret store.
+const PASSWORD_KEY: &str = "password";
+const DEFAULT_PORT: u32 = 5900;
+/// Key of the only server of a connection.
+const SERVER_KEY: &str = "server";
+
+/// Everything stored in the configuration, except for secrets. Keeping this in one typed struct
+/// means unknown or missing keys need no special handling.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExampleConfig {
+    pub title: String,
+    pub host: String,
+    pub port: u32,
+    pub user: String,
+}
+
+impl Default for ExampleConfig {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            host: String::new(),
+            port: DEFAULT_PORT,
+            user: String::new(),
+        }
+    }
+}
+
+fn read_config(configuration: &ConnectionConfiguration) -> ExampleConfig {
+    configuration.typed().unwrap_or_else(|err| {
+        log::warn!("invalid example configuration, using defaults: {err}");
+        Default::default()
+    })
+}
+
+pub struct ExampleConnectionProviderConstructor;
+
+impl ConnectionProviderConstructor for ExampleConnectionProviderConstructor {
+    fn new(&self) -> Box<dyn ConnectionProvider> {
+        Box::new(ExampleConnectionProvider)
+    }
+}
+
+pub struct ExampleConnectionProvider;
+
+impl ConnectionProvider for ExampleConnectionProvider {
+    fn tag(&self) -> &'static str {
+        TAG
+    }
+
+    fn title(&self) -> Cow<'static, str> {
+        gettext("Example").into()
+    }
+
+    fn title_plural(&self) -> Cow<str> {
+        gettext("Example Connections").into()
+    }
+
+    fn add_title(&self) -> Cow<str> {
+        gettext("Add Example Connection").into()
+    }
+
+    fn title_for<'a>(&self, config: &'a ConnectionConfiguration) -> Option<&'a str> {
+        config
+            .get_try_as_str("title")
+            .filter(|title| !title.is_empty())
+    }
+
+    fn description(&self) -> Cow<str> {
+        gettext("Connection to a single VNC server, for developers").into()
+    }
+
+    fn icon(&self) -> IconSpec<()> {
+        IconSpec::Named(ICON.into())
+    }
+
+    fn preferences(&self, configuration: Option<&ConnectionConfiguration>) -> gtk::Widget {
+        ExamplePreferences::new(configuration.map(read_config).unwrap_or_default()).upcast()
+    }
+
+    fn update_connection(
+        &self,
+        preferences: gtk::Widget,
+        configuration: DualScopedConnectionConfiguration,
+    ) -> LocalBoxFuture<anyhow::Result<DualScopedConnectionConfiguration>> {
+        Box::pin(async move {
+            let preferences = preferences
+                .downcast::<ExamplePreferences>()
+                .expect("update_connection got invalid widget type");
+            let config = preferences.config()?;
+            let password = preferences.password();
+
+            configuration.transform_update_unified(|configuration| {
+                configuration.set_typed(&config)?;
+                // An empty password field keeps the stored password.
+                if let Some(password) = password.clone() {
+                    configuration.set_secret(PASSWORD_KEY, password);
+                }
+                anyhow::Ok(())
+            })
+        })
+    }
+
+    fn configure_credentials(
+        &self,
+        _server_path: &[String],
+        configuration: &ConnectionConfiguration,
+    ) -> PreferencesGroupOrPage {
+        PreferencesGroupOrPage::Page(ExamplePreferences::new(read_config(configuration)).upcast())
+    }
+
+    fn store_credentials(
+        &self,
+        _server_path: &[String],
+        preferences: gtk::Widget,
+        configuration: DualScopedConnectionConfiguration,
+    ) -> LocalBoxFuture<anyhow::Result<DualScopedConnectionConfiguration>> {
+        self.update_connection(preferences, configuration)
+    }
+
+    fn load_connection(
+        &self,
+        configuration: ConnectionConfiguration,
+    ) -> LocalBoxFuture<ConnectionResult<Box<dyn Connection>>> {
+        Box::pin(async move {
+            let config = read_config(&configuration);
+            if config.host.is_empty() {
+                return Err(ConnectionError::General(
+                    Some(gettext("No host is configured.")),
+                    anyhow!("example connection has no host"),
+                ));
+            }
+            let connection: Box<dyn Connection> = Box::new(ExampleConnection {
+                config,
+                configuration,
+            });
+            Ok(connection)
+        })
+    }
+
+    fn configure_from_options(
+        &self,
+        options: &HashMap<String, String>,
+        password: Option<SecureString>,
+        configuration: DualScopedConnectionConfiguration,
+    ) -> LocalBoxFuture<anyhow::Result<DualScopedConnectionConfiguration>> {
+        let options = options.clone();
+        Box::pin(async move {
+            let host = options
+                .get("host")
+                .cloned()
+                .ok_or_else(|| anyhow!("a host is required"))?;
+            let port = match options.get("port") {
+                Some(port) => port.parse().map_err(|_| anyhow!("invalid port “{port}”"))?,
+                None => DEFAULT_PORT,
+            };
+            let config = ExampleConfig {
+                title: options
+                    .get("title")
+                    .cloned()
+                    .unwrap_or_else(|| host.clone()),
+                host,
+                port,
+                user: options.get("user").cloned().unwrap_or_default(),
+            };
+            configuration.transform_update_unified(|configuration| {
+                configuration.set_typed(&config)?;
+                if let Some(password) = password.clone() {
+                    configuration.set_secret(PASSWORD_KEY, password);
+                }
+                anyhow::Ok(())
+            })
+        })
+    }
+}
+
+pub struct ExampleConnection {
+    config: ExampleConfig,
+    configuration: ConnectionConfiguration,
+}
+
+impl Actionable for ExampleConnection {}
+
+impl Connection for ExampleConnection {
+    fn metadata(&self) -> ConnectionMetadata {
+        ConnectionMetadataBuilder::default()
+            .title(self.config.title.clone())
+            .icon(IconSpec::Named(ICON.into()))
+            .build()
+            .unwrap()
+    }
+
+    fn servers(&self) -> LocalBoxFuture<ConnectionResult<ServerMap>> {
+        Box::pin(async move {
+            let mut servers: ServerMap = IndexMap::new();
+            servers.insert(
+                SERVER_KEY.into(),
+                Box::new(ExampleServer {
+                    config: self.config.clone(),
+                    configuration: self.configuration.clone(),
+                }),
+            );
+            Ok(servers)
+        })
+    }
+}
+
+pub struct ExampleServer {
+    config: ExampleConfig,
+    configuration: ConnectionConfiguration,
+}
+
+impl Actionable for ExampleServer {}
+
+impl ServerConnection for ExampleServer {
+    fn metadata(&self) -> ServerMetadata {
+        ServerMetadataBuilder::default()
+            .title(format!("{}:{}", self.config.host, self.config.port))
+            .host(Some(self.config.host.clone()))
+            .build()
+            .unwrap()
+    }
+
+    fn supported_adapters(&self) -> Vec<(Cow<str>, Cow<str>)> {
+        vec![(VncAdapter::TAG.into(), VncAdapter::label())]
+    }
+
+    fn create_adapter(&self, tag: &str) -> LocalBoxFuture<ConnectionResult<Box<dyn Adapter>>> {
+        let tag = tag.to_string();
+        Box::pin(async move {
+            if tag != VncAdapter::TAG {
+                return Err(ConnectionError::General(
+                    None,
+                    anyhow!("unsupported adapter {tag}"),
+                ));
+            }
+            // Secrets are only read once they are needed, since the keyring may ask the user
+            // to unlock it.
+            let password = match self.configuration.get_secret(PASSWORD_KEY).await {
+                Ok(password) => password.unwrap_or_else(|| SecureString::from("")),
+                Err(err) => {
+                    return Err(ConnectionError::AuthFailed(
+                        Some(gettext("Failed to load password.")),
+                        err,
+                    ));
+                }
+            };
+            let adapter: Box<dyn Adapter> = Box::new(VncAdapter::new(
+                self.config.host.clone(),
+                self.config.port,
+                self.config.user.clone(),
+                password,
+            ));
+            Ok(adapter)
+        })
+    }
+}
@@ -0,0 +1,130 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Preferences of example connections.
+//!
+//! Most providers describe their preferences with a Blueprint template. This page is built in
+//! code instead, to keep the example free of resources.
+use std::cell::OnceCell;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use anyhow::anyhow;
+use gettextrs::gettext;
+use secure_string::SecureString;
+
+use crate::ExampleConfig;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct ExamplePreferences {
+        pub title_entry: OnceCell<adw::EntryRow>,
+        pub host_entry: OnceCell<adw::EntryRow>,
+        pub port_entry: OnceCell<adw::EntryRow>,
+        pub user_entry: OnceCell<adw::EntryRow>,
+        pub password_entry: OnceCell<adw::PasswordEntryRow>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ExamplePreferences {
+        const NAME: &'static str = "ExamplePreferences";
+        type Type = super::ExamplePreferences;
+        type ParentType = adw::PreferencesPage;
+    }
+
+    impl ObjectImpl for ExamplePreferences {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let entry = |title: String| adw::EntryRow::builder().title(title).build();
+            let server_group = adw::PreferencesGroup::new();
+            let title_entry = entry(gettext("Connection Name"));
+            let host_entry = entry(gettext("Hostname"));
+            let port_entry = entry(gettext("Port"));
+            port_entry.set_input_purpose(gtk::InputPurpose::Number);
+            server_group.add(&title_entry);
+            server_group.add(&host_entry);
+            server_group.add(&port_entry);
+
+            let credentials_group = adw::PreferencesGroup::builder()
+                .title(gettext("Credentials"))
+                .build();
+            let user_entry = entry(gettext("Username"));
+            let password_entry = adw::PasswordEntryRow::builder()
+                .title(gettext("Password"))
+                .build();
+            credentials_group.add(&user_entry);
+            credentials_group.add(&password_entry);
+
+            let obj = self.obj();
+            obj.add(&server_group);
+            obj.add(&credentials_group);
+
+            self.title_entry.set(title_entry).unwrap();
+            self.host_entry.set(host_entry).unwrap();
+            self.port_entry.set(port_entry).unwrap();
+            self.user_entry.set(user_entry).unwrap();
+            self.password_entry.set(password_entry).unwrap();
+        }
+    }
+    impl WidgetImpl for ExamplePreferences {}
+    impl PreferencesPageImpl for ExamplePreferences {}
+}
+
+glib::wrapper! {
+    pub struct ExamplePreferences(ObjectSubclass<imp::ExamplePreferences>)
+        @extends gtk::Widget, adw::PreferencesPage,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl ExamplePreferences {
+    pub fn new(config: ExampleConfig) -> Self {
+        let slf: Self = glib::Object::builder().build();
+        let imp = slf.imp();
+        imp.title_entry.get().unwrap().set_text(&config.title);
+        imp.host_entry.get().unwrap().set_text(&config.host);
+        imp.port_entry
+            .get()
+            .unwrap()
+            .set_text(&config.port.to_string());
+        imp.user_entry.get().unwrap().set_text(&config.user);
+        slf
+    }
+
+    /// The configuration entered by the user.
+    pub fn config(&self) -> anyhow::Result<ExampleConfig> {
+        let imp = self.imp();
+        let text = |entry: &OnceCell<adw::EntryRow>| entry.get().unwrap().text().trim().to_string();
+        let port = text(&imp.port_entry);
+        Ok(ExampleConfig {
+            title: text(&imp.title_entry),
+            host: text(&imp.host_entry),
+            port: port
+                .parse()
+                .map_err(|_| anyhow!(gettext("The port must be a number.")))?,
+            user: text(&imp.user_entry),
+        })
+    }
+
+    /// The entered password, if the user entered one.
+    pub fn password(&self) -> Option<SecureString> {
+        let password = self.imp().password_entry.get().unwrap().text();
+        (!password.is_empty()).then(|| SecureString::from(password.as_str()))
+    }
+}
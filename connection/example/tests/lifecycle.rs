@@ -0,0 +1,72 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Runs the example provider through the full lifecycle with the harness of libfieldmonitor.
+use std::collections::HashMap;
+
+use field_monitor_example::ExampleConnectionProviderConstructor;
+use libfieldmonitor::connection::*;
+use libfieldmonitor::testing::{
+    block_on, mock_configuration, run_provider_lifecycle, MemorySecretManager,
+};
+use secure_string::SecureString;
+
+#[test]
+fn lifecycle() {
+    let provider = ExampleConnectionProviderConstructor.new();
+    let options = HashMap::from([
+        ("host".to_string(), "vnc.example.com".to_string()),
+        ("port".to_string(), "5901".to_string()),
+        ("title".to_string(), "Example".to_string()),
+    ]);
+    let configuration = DualScopedConnectionConfiguration::new_unified(mock_configuration(
+        provider.tag(),
+        MemorySecretManager::shared(),
+    ));
+
+    let report = block_on(async {
+        let configuration = provider
+            .configure_from_options(&options, Some(SecureString::from("secret")), configuration)
+            .await
+            .unwrap();
+        assert_eq!(
+            configuration
+                .persistent()
+                .get_secret("password")
+                .await
+                .unwrap()
+                .map(|password| password.unsecure().to_string()),
+            Some("secret".to_string())
+        );
+        run_provider_lifecycle(&*provider, configuration.persistent().clone()).await
+    })
+    .unwrap();
+
+    assert_eq!(report.title.as_deref(), Some("Example"));
+    assert_eq!(report.servers.len(), 1);
+    let server = &report.servers[0];
+    assert_eq!(server.metadata.title, "vnc.example.com:5901");
+    assert_eq!(server.metadata.host.as_deref(), Some("vnc.example.com"));
+    assert_eq!(server.adapters, ["vnc"]);
+}
+
+#[test]
+fn missing_host_is_rejected() {
+    let provider = ExampleConnectionProviderConstructor.new();
+    let configuration = mock_configuration(provider.tag(), MemorySecretManager::shared());
+    assert!(block_on(run_provider_lifecycle(&*provider, configuration)).is_err());
+}
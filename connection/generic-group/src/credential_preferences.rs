@@ -19,14 +19,16 @@
 use std::cell::{Cell, RefCell};
 
 use adw::subclass::prelude::*;
+use gettextrs::gettext;
 use gtk::glib;
 use gtk::glib::clone;
 use gtk::prelude::*;
 
 use libfieldmonitor::connection::ConnectionConfiguration;
 use libfieldmonitor::gtk::FieldMonitorSaveCredentialsButton;
+use secure_string::SecureString;
 
-use crate::preferences::GenericGroupConfiguration;
+use crate::preferences::{GenericGroupConfiguration, ServerAccount};
 use crate::server_config::FinalizedServerConfig;
 use crate::util::clear_editable_if_becoming_not_editable;
 
@@ -47,6 +49,9 @@ mod imp {
         pub(super) user_entry_save_button: TemplateChild<FieldMonitorSaveCredentialsButton>,
         #[template_child]
         pub(super) password_entry_save_button: TemplateChild<FieldMonitorSaveCredentialsButton>,
+        #[template_child]
+        pub(super) add_account_button: TemplateChild<gtk::Button>,
+        pub(super) account_rows: RefCell<Vec<AccountRow>>,
 
         #[property(get, set)]
         pub user: RefCell<String>,
@@ -80,6 +85,8 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
             if !self.use_temporary_credentials.get() {
+                // Additional accounts can only be edited in the server settings.
+                self.add_account_button.set_visible(true);
                 self.user_entry_save_button
                     .bind_property("save_password", &*self.user_entry, "editable")
                     .sync_create()
@@ -137,6 +144,17 @@ impl GenericGroupCredentialPreferences {
         if let Ok(Some(v)) = existing_configuration.password(server).await {
             self.set_password(v.unsecure());
         }
+        if !self.use_temporary_credentials() {
+            for mut account in existing_configuration.accounts(server).unwrap_or_default() {
+                if let Ok(password) = existing_configuration
+                    .account_password(server, &account.id)
+                    .await
+                {
+                    account.password = password;
+                }
+                self.add_account(account);
+            }
+        }
     }
 
     pub fn update_server_config(&self, config: &mut FinalizedServerConfig) {
@@ -150,6 +168,13 @@ impl GenericGroupCredentialPreferences {
         };
         config.user_remember = self.imp().user_entry_save_button.save_password();
         config.password_remember = self.imp().password_entry_save_button.save_password();
+        config.accounts = self
+            .imp()
+            .account_rows
+            .borrow()
+            .iter()
+            .filter_map(AccountRow::to_account)
+            .collect();
     }
 
     pub fn as_incomplete_server_config(&self) -> FinalizedServerConfig {
@@ -159,5 +184,114 @@ impl GenericGroupCredentialPreferences {
     }
 }
 
+impl GenericGroupCredentialPreferences {
+    fn add_account(&self, account: ServerAccount) {
+        let row = AccountRow::new(account);
+        let expander = row.expander.clone();
+        row.remove_button.connect_clicked(clone!(
+            #[weak(rename_to = slf)]
+            self,
+            #[weak]
+            expander,
+            move |_| {
+                slf.remove(&expander);
+                slf.imp()
+                    .account_rows
+                    .borrow_mut()
+                    .retain(|row| row.expander != expander);
+            }
+        ));
+        self.add(&row.expander);
+        self.imp().account_rows.borrow_mut().push(row);
+    }
+}
+
+/// Editor rows for an additional account of a server.
+#[derive(Debug)]
+pub(crate) struct AccountRow {
+    id: String,
+    expander: adw::ExpanderRow,
+    name_entry: adw::EntryRow,
+    user_entry: adw::EntryRow,
+    password_entry: adw::PasswordEntryRow,
+    remove_button: gtk::Button,
+}
+
+impl AccountRow {
+    fn new(account: ServerAccount) -> Self {
+        let expander = adw::ExpanderRow::builder()
+            .title(gettext("Account"))
+            .expanded(account.name.is_empty())
+            .build();
+        let name_entry = adw::EntryRow::builder()
+            .title(gettext("Account Name"))
+            .text(account.name.as_str())
+            .build();
+        let user_entry = adw::EntryRow::builder()
+            .title(gettext("Username"))
+            .text(account.user.as_deref().unwrap_or_default())
+            .build();
+        let password_entry = adw::PasswordEntryRow::builder()
+            .title(gettext("Password"))
+            .text(
+                account
+                    .password
+                    .as_ref()
+                    .map(SecureString::unsecure)
+                    .unwrap_or_default(),
+            )
+            .build();
+        let remove_button = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text(gettext("Remove Account"))
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+
+        name_entry
+            .bind_property("text", &expander, "subtitle")
+            .sync_create()
+            .build();
+
+        expander.add_suffix(&remove_button);
+        expander.add_row(&name_entry);
+        expander.add_row(&user_entry);
+        expander.add_row(&password_entry);
+
+        Self {
+            id: account.id,
+            expander,
+            name_entry,
+            user_entry,
+            password_entry,
+            remove_button,
+        }
+    }
+
+    /// The edited account, `None` if all of its fields are empty.
+    fn to_account(&self) -> Option<ServerAccount> {
+        let non_empty =
+            |text: glib::GString| Some(text.trim().to_string()).filter(|t| !t.is_empty());
+        let user = non_empty(self.user_entry.text());
+        let password = Some(self.password_entry.text().to_string()).filter(|p| !p.is_empty());
+        let name = match non_empty(self.name_entry.text()) {
+            Some(name) => name,
+            None if user.is_none() && password.is_none() => return None,
+            None => user.clone().unwrap_or_else(|| gettext("Account")),
+        };
+        Some(ServerAccount {
+            id: self.id.clone(),
+            name,
+            user,
+            password: password.map(Into::into),
+        })
+    }
+}
+
 #[gtk::template_callbacks]
-impl GenericGroupCredentialPreferences {}
+impl GenericGroupCredentialPreferences {
+    #[template_callback]
+    fn on_add_account_clicked(&self) {
+        self.add_account(ServerAccount::new(String::new()));
+    }
+}
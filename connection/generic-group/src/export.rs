@@ -0,0 +1,182 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Export of servers as connection files for other clients.
+use std::sync::Arc;
+
+use adw::prelude::*;
+use gettextrs::gettext;
+use log::warn;
+
+use crate::load_password;
+use crate::preferences::{GenericGroupConfiguration, ServerType};
+use libfieldmonitor::action_failure::report_action_failure;
+use libfieldmonitor::connection::*;
+use libfieldmonitor::connection_file::{save_connection_file, RdpFile, VvFile};
+use libfieldmonitor::i18n::gettext_f;
+use libfieldmonitor::template::expand_template;
+
+/// Whether the connection to `server` is tunneled through jump hosts or proxies, which other
+/// clients do not know about.
+fn is_tunneled(config: &ConnectionConfiguration, server: &str) -> bool {
+    config
+        .jump_hosts(server)
+        .is_some_and(|hops| !hops.is_empty())
+        || config.proxies(server).is_some_and(|hops| !hops.is_empty())
+}
+
+/// Asks whether the password of `server_title` should be written to the exported file.
+/// Returns `None` if the dialog was cancelled.
+async fn ask_include_password(
+    server_title: &str,
+    tunneled: bool,
+    window: Option<&gtk::Window>,
+) -> Option<bool> {
+    let mut body = gettext(
+        "The password can be included in the file. It is stored unencrypted and anyone with access to the file can read it.",
+    );
+    if tunneled {
+        body.push_str("\n\n");
+        body.push_str(&gettext(
+            "Jump hosts and proxies are not part of the file, the server must be reachable directly.",
+        ));
+    }
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext_f("Export {server}", &[("server", server_title)]))
+        .body(body)
+        .close_response("cancel")
+        .default_response("without")
+        .build();
+    dialog.add_responses(&[
+        ("cancel", &gettext("Cancel")),
+        ("with", &gettext("Include Password")),
+        ("without", &gettext("Without Password")),
+    ]);
+    dialog.set_response_appearance("without", adw::ResponseAppearance::Suggested);
+
+    match dialog.choose_future(window).await.as_str() {
+        "with" => Some(true),
+        "without" => Some(false),
+        _ => None,
+    }
+}
+
+/// Action writing `server` to a connection file: an `.rdp` file for RDP servers and a
+/// virt-viewer `.vv` file for SPICE servers. VNC servers can not be exported.
+pub fn export_action<'a>(
+    config: Arc<ConnectionConfiguration>,
+    server: String,
+    server_type: ServerType,
+) -> ServerAction<'a> {
+    ServerAction::new(
+        Box::new((config, server, server_type)),
+        Box::new(|params, window, toov| {
+            Box::pin(async move {
+                let (config, server, server_type) = *params
+                    .downcast::<(Arc<ConnectionConfiguration>, String, ServerType)>()
+                    .unwrap();
+                let title = config.title(&server).unwrap_or_default();
+                let Some(include_password) =
+                    ask_include_password(&title, is_tunneled(&config, &server), window.as_ref())
+                        .await
+                else {
+                    return false;
+                };
+
+                let host = config
+                    .host(&server)
+                    .as_deref()
+                    .map(expand_template)
+                    .unwrap_or_default();
+                let user = config.user(&server).as_deref().map(expand_template);
+                let password = if include_password {
+                    let user = user.as_deref().unwrap_or_default();
+                    match load_password(&config, &server, None, user, &host).await {
+                        Ok(password) => Some(password),
+                        Err(err) => {
+                            warn!("failed to load password for export of {server}: {err}");
+                            let text = gettext("Failed to load password.");
+                            report_action_failure(&text);
+                            if let Some(toov) = toov {
+                                toov.add_toast(
+                                    adw::Toast::builder().title(&text).timeout(5).build(),
+                                );
+                            }
+                            return false;
+                        }
+                    }
+                } else {
+                    None
+                };
+                let port = config
+                    .port(&server)
+                    .unwrap_or(server_type.default_port())
+                    .get();
+                let (extension, filter_name, contents) = match server_type {
+                    ServerType::Rdp => {
+                        let file = RdpFile {
+                            host,
+                            port,
+                            user,
+                            password,
+                            smartcard: config.smartcard(&server).unwrap_or_default(),
+                            microphone: config.microphone(&server).unwrap_or_default(),
+                        };
+                        ("rdp", gettext("RDP Files"), file.to_file_contents())
+                    }
+                    ServerType::Spice => {
+                        let file = VvFile {
+                            title: title.clone(),
+                            host,
+                            port: Some(port),
+                            user,
+                            password,
+                            ..Default::default()
+                        };
+                        ("vv", gettext("Virt-Viewer Files"), file.to_file_contents())
+                    }
+                    ServerType::Vnc => return false,
+                };
+
+                let result = save_connection_file(
+                    window.as_ref(),
+                    &format!("{title}.{extension}"),
+                    &filter_name,
+                    contents,
+                )
+                .await;
+                let text = match result {
+                    Ok(false) => return false,
+                    Ok(true) => gettext("Connection file exported."),
+                    Err(err) => {
+                        warn!("failed to export connection file of {server}: {err}");
+                        let text = gettext_f(
+                            "Failed to export connection file: {err}",
+                            &[("err", &err.to_string())],
+                        );
+                        report_action_failure(&text);
+                        text
+                    }
+                };
+                if let Some(toov) = toov {
+                    toov.add_toast(adw::Toast::builder().title(&text).timeout(5).build());
+                }
+                false
+            })
+        }),
+    )
+}
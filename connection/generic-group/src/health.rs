@@ -0,0 +1,172 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Health checks determining whether a server is shown as online, and waking up servers that
+//! are not.
+use std::ffi::OsStr;
+use std::num::NonZeroU32;
+
+use adw::gio;
+use adw::prelude::*;
+use log::{debug, warn};
+
+use libfieldmonitor::resolve::check_reachable;
+use libfieldmonitor::template::expand_template;
+
+/// Port magic packets are sent to. Network cards ignore it, but it's commonly used.
+const WOL_PORT: u16 = 9;
+
+/// Seconds to wait for a ping reply.
+const PING_TIMEOUT_SECS: &str = "2";
+
+/// Checks to run for a server. The server is online if all enabled checks succeed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthCheck {
+    /// Whether the host must reply to an ICMP echo request.
+    pub ping: bool,
+    /// Whether the host must accept TCP connections on the port.
+    pub port_check: bool,
+    /// Host to check instead of the host of the server.
+    pub host: Option<String>,
+    /// Port to check instead of the port of the server, eg. SSH even though the server is
+    /// connected to with RDP.
+    pub port: Option<NonZeroU32>,
+}
+
+impl HealthCheck {
+    pub fn is_enabled(&self) -> bool {
+        self.ping || self.port_check
+    }
+
+    /// Runs the enabled checks against the overridden host and port, or `host` and `port` of
+    /// the server. Returns `None` if no check is enabled or the result could not be determined.
+    pub async fn run(&self, host: &str, port: u32) -> Option<bool> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let host = expand_template(self.host.as_deref().unwrap_or(host));
+        let port = self.port.map(NonZeroU32::get).unwrap_or(port);
+
+        if self.ping && !ping(&host).await? {
+            return Some(false);
+        }
+        if self.port_check {
+            let Ok(port) = u16::try_from(port) else {
+                warn!("health check port {port} of {host} is out of range");
+                return None;
+            };
+            if let Err(err) = check_reachable(&host, port).await {
+                debug!("health check of {host}:{port} failed: {err}");
+                return Some(false);
+            }
+        }
+        Some(true)
+    }
+
+    pub(crate) fn from_value(value: &serde_yaml::Value) -> Option<Self> {
+        let flag = |key| {
+            value
+                .get(key)
+                .and_then(serde_yaml::Value::as_bool)
+                .unwrap_or_default()
+        };
+        Some(Self {
+            ping: flag("ping"),
+            port_check: flag("port_check"),
+            host: value
+                .get("host")
+                .and_then(serde_yaml::Value::as_str)
+                .map(ToString::to_string),
+            port: value
+                .get("port")
+                .and_then(serde_yaml::Value::as_u64)
+                .and_then(|v| u32::try_from(v).ok())
+                .and_then(NonZeroU32::new),
+        })
+    }
+
+    pub(crate) fn to_value(&self) -> serde_yaml::Value {
+        let mut map = serde_yaml::Mapping::new();
+        map.insert("ping".into(), self.ping.into());
+        map.insert("port_check".into(), self.port_check.into());
+        if let Some(host) = &self.host {
+            map.insert("host".into(), host.as_str().into());
+        }
+        if let Some(port) = self.port {
+            map.insert("port".into(), port.get().into());
+        }
+        serde_yaml::Value::Mapping(map)
+    }
+}
+
+/// Sends a single ping to `host` using the system's `ping` command. Returns `None` if it could
+/// not be run.
+async fn ping(host: &str) -> Option<bool> {
+    let argv = ["ping", "-c", "1", "-W", PING_TIMEOUT_SECS, "-q", host].map(OsStr::new);
+    let process = match gio::Subprocess::newv(
+        &argv,
+        gio::SubprocessFlags::STDOUT_SILENCE | gio::SubprocessFlags::STDERR_SILENCE,
+    ) {
+        Ok(process) => process,
+        Err(err) => {
+            warn!("failed to run ping: {err}");
+            return None;
+        }
+    };
+    match process.wait_future().await {
+        Ok(()) => Some(process.is_successful()),
+        Err(err) => {
+            warn!("failed to wait for ping: {err}");
+            None
+        }
+    }
+}
+
+/// Parses a MAC address in the format `aa:bb:cc:dd:ee:ff` (`-` is also accepted as separator).
+pub fn parse_mac_address(mac: &str) -> Option<[u8; 6]> {
+    let mut bytes = [0; 6];
+    let mut parts = mac.trim().split([':', '-']);
+    for byte in &mut bytes {
+        let part = parts.next()?;
+        if part.len() != 2 {
+            return None;
+        }
+        *byte = u8::from_str_radix(part, 16).ok()?;
+    }
+    parts.next().is_none().then_some(bytes)
+}
+
+/// Broadcasts a Wake-on-LAN magic packet for the network card with the MAC address `mac` in
+/// the local network.
+pub fn wake_on_lan(mac: [u8; 6]) -> Result<(), glib::Error> {
+    let mut packet = vec![0xff; 6];
+    for _ in 0..16 {
+        packet.extend_from_slice(&mac);
+    }
+    let socket = gio::Socket::new(
+        gio::SocketFamily::Ipv4,
+        gio::SocketType::Datagram,
+        gio::SocketProtocol::Udp,
+    )?;
+    socket.set_broadcast(true);
+    let address = gio::InetSocketAddress::new(
+        &gio::InetAddress::new_from_string("255.255.255.255").unwrap(),
+        WOL_PORT,
+    );
+    socket.send_to(Some(&address), &packet, gio::Cancellable::NONE)?;
+    Ok(())
+}
@@ -0,0 +1,381 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Bulk import of servers from CSV (`name,host,port,protocol,user`) or a plain list of hosts.
+use std::cell::RefCell;
+use std::num::NonZeroU32;
+use std::rc::Rc;
+
+use adw::gio;
+use adw::prelude::*;
+use gettextrs::gettext;
+use libfieldmonitor::host::{self, format_host_port};
+use libfieldmonitor::i18n::gettext_f;
+use log::warn;
+use uuid::Uuid;
+
+use crate::preferences::ServerType;
+use crate::server_config::FinalizedServerConfig;
+
+const DEFAULT_SERVER_TYPE: ServerType = ServerType::Rdp;
+
+/// A parsed line of the import, with its line number (starting at 1).
+struct ImportLine {
+    line: usize,
+    result: Result<FinalizedServerConfig, String>,
+}
+
+fn parse_server_list(input: &str) -> Vec<ImportLine> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .peekable();
+    // The header may follow comments and blank lines.
+    lines.next_if(|(_, line)| is_header(line));
+    lines
+        .map(|(i, line)| ImportLine {
+            line: i + 1,
+            result: parse_line(line),
+        })
+        .collect()
+}
+
+fn is_header(line: &str) -> bool {
+    split_csv_line(line)
+        .first()
+        .is_some_and(|first| first.eq_ignore_ascii_case("name"))
+}
+
+fn parse_line(line: &str) -> Result<FinalizedServerConfig, String> {
+    let fields = split_csv_line(line);
+    if fields.len() > 5 {
+        return Err(gettext_f(
+            "Expected at most 5 columns, found {count}.",
+            &[("count", &fields.len().to_string())],
+        ));
+    }
+    // A line with only a single column is a plain host, optionally with a port.
+    let (name, host) = match fields.len() {
+        1 => (fields[0].as_str(), fields[0].as_str()),
+        _ => (fields[0].as_str(), fields[1].as_str()),
+    };
+    let host_port = host::parse_host_port(host).map_err(|err| err.to_string())?;
+    let field = |idx: usize| {
+        fields
+            .get(idx)
+            .map(String::as_str)
+            .filter(|s| !s.is_empty())
+    };
+
+    let server_type = match field(3) {
+        None => DEFAULT_SERVER_TYPE,
+        Some(protocol) => ServerType::try_from(protocol.to_lowercase()).map_err(|_| {
+            gettext_f(
+                "Unknown protocol “{protocol}”. Supported are rdp, spice and vnc.",
+                &[("protocol", protocol)],
+            )
+        })?,
+    };
+
+    let port = match field(2) {
+        None => host_port
+            .port
+            .and_then(|port| NonZeroU32::new(port.into()))
+            .unwrap_or_else(|| server_type.default_port()),
+        Some(port) => port
+            .parse::<u16>()
+            .ok()
+            .and_then(|port| NonZeroU32::new(port.into()))
+            .ok_or_else(|| gettext_f("Invalid port “{port}”.", &[("port", port)]))?,
+    };
+
+    let user = field(4).map(ToString::to_string);
+    let title = if name.is_empty() { host } else { name };
+
+    Ok(FinalizedServerConfig {
+        key: Uuid::now_v7().to_string(),
+        server_type: Some(server_type),
+        title: title.to_string(),
+        host: host_port.host,
+        port,
+        user_remember: user.is_some(),
+        user,
+        ..FinalizedServerConfig::default()
+    })
+}
+
+/// Splits a CSV line into trimmed fields. Fields may be quoted with `"`, quotes inside quoted
+/// fields are escaped by doubling them.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut current).trim().to_string()),
+            c => current.push(c),
+        }
+    }
+    fields.push(current.trim().to_string());
+    fields
+}
+
+/// Presents a dialog for importing servers in bulk. `on_import` is called with all valid servers
+/// when the user confirms the import.
+pub fn present_import_dialog(
+    parent: &impl IsA<gtk::Widget>,
+    on_import: impl Fn(Vec<FinalizedServerConfig>) + 'static,
+) {
+    let parsed: Rc<RefCell<Vec<ImportLine>>> = Rc::default();
+
+    let input_view = gtk::TextView::builder()
+        .monospace(true)
+        .wrap_mode(gtk::WrapMode::None)
+        .top_margin(12)
+        .bottom_margin(12)
+        .left_margin(12)
+        .right_margin(12)
+        .height_request(120)
+        .css_classes(["card"])
+        .build();
+    let input_label = gtk::Label::builder()
+        .label(gettext(
+            "Paste one server per line as “name,host,port,protocol,user” or just the host name. \
+             Port, protocol and user are optional; the protocol defaults to RDP.",
+        ))
+        .wrap(true)
+        .xalign(0.0)
+        .css_classes(["dim-label"])
+        .build();
+    let open_file_button = gtk::Button::builder()
+        .child(
+            &adw::ButtonContent::builder()
+                .icon_name("document-open-symbolic")
+                .label(gettext("Open CSV File…"))
+                .build(),
+        )
+        .halign(gtk::Align::Start)
+        .build();
+
+    let preview_list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .valign(gtk::Align::Start)
+        .css_classes(["boxed-list"])
+        .build();
+    let preview_group = adw::PreferencesGroup::builder()
+        .title(gettext("Preview"))
+        .build();
+    preview_group.add(&preview_list);
+
+    let content = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(12)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    content.append(&input_label);
+    content.append(&input_view);
+    content.append(&open_file_button);
+    content.append(&preview_group);
+
+    let import_button = gtk::Button::builder()
+        .label(gettext("Import"))
+        .sensitive(false)
+        .css_classes(["suggested-action"])
+        .build();
+    let bottom_bar = gtk::ActionBar::new();
+    bottom_bar.pack_end(&import_button);
+
+    let view = adw::ToolbarView::new();
+    view.add_top_bar(&adw::HeaderBar::new());
+    view.set_content(Some(
+        &gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .propagate_natural_height(true)
+            .child(&adw::Clamp::builder().child(&content).build())
+            .build(),
+    ));
+    view.add_bottom_bar(&bottom_bar);
+
+    let dialog = adw::Dialog::builder()
+        .title(gettext("Import Servers"))
+        .content_width(500)
+        .content_height(600)
+        .child(&view)
+        .build();
+
+    input_view.buffer().connect_changed(glib::clone!(
+        #[strong]
+        parsed,
+        #[weak]
+        preview_list,
+        #[weak]
+        import_button,
+        move |buffer| {
+            let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+            let lines = parse_server_list(&text);
+            update_preview(&preview_list, &lines);
+            import_button.set_sensitive(lines.iter().any(|l| l.result.is_ok()));
+            parsed.replace(lines);
+        }
+    ));
+    update_preview(&preview_list, &[]);
+
+    open_file_button.connect_clicked(glib::clone!(
+        #[weak]
+        input_view,
+        #[weak]
+        dialog,
+        move |_| {
+            glib::spawn_future_local(async move {
+                let filter = gtk::FileFilter::new();
+                filter.set_name(Some(&gettext("CSV Files")));
+                filter.add_mime_type("text/csv");
+                filter.add_suffix("csv");
+                let filters = gio::ListStore::new::<gtk::FileFilter>();
+                filters.append(&filter);
+
+                let file_dialog = gtk::FileDialog::builder()
+                    .title(gettext("Open CSV File"))
+                    .filters(&filters)
+                    .modal(true)
+                    .build();
+                let window = dialog.root().and_downcast::<gtk::Window>();
+                let Ok(file) = file_dialog.open_future(window.as_ref()).await else {
+                    return;
+                };
+                match file.load_contents_future().await {
+                    Ok((content, _)) => {
+                        input_view
+                            .buffer()
+                            .set_text(&String::from_utf8_lossy(&content));
+                    }
+                    Err(err) => {
+                        warn!("failed to read server list: {err}");
+                        dialog.add_toast(
+                            adw::Toast::builder()
+                                .title(gettext("Failed to read the file."))
+                                .timeout(5)
+                                .build(),
+                        );
+                    }
+                }
+            });
+        }
+    ));
+
+    import_button.connect_clicked(glib::clone!(
+        #[weak]
+        dialog,
+        move |_| {
+            let servers = parsed
+                .take()
+                .into_iter()
+                .filter_map(|line| line.result.ok())
+                .collect();
+            on_import(servers);
+            dialog.force_close();
+        }
+    ));
+
+    dialog.present(Some(parent));
+}
+
+fn update_preview(list: &gtk::ListBox, lines: &[ImportLine]) {
+    list.remove_all();
+    if lines.is_empty() {
+        list.append(
+            &adw::ActionRow::builder()
+                .sensitive(false)
+                .title(gettext("Nothing to import yet."))
+                .build(),
+        );
+        return;
+    }
+    for line in lines {
+        let row = match &line.result {
+            Ok(server) => {
+                let user_part = server
+                    .user
+                    .as_deref()
+                    .map(|u| format!("{u}@"))
+                    .unwrap_or_default();
+                adw::ActionRow::builder()
+                    .title(server.title.as_str())
+                    .subtitle(format!(
+                        "{}://{}{}",
+                        server.server_type.map(|s| s.protocol()).unwrap_or_default(),
+                        user_part,
+                        format_host_port(&server.host, server.port)
+                    ))
+                    .build()
+            }
+            Err(err) => {
+                let row = adw::ActionRow::builder()
+                    .title(gettext_f(
+                        "Line {line}",
+                        &[("line", &line.line.to_string())],
+                    ))
+                    .subtitle(err.as_str())
+                    .css_classes(["error"])
+                    .build();
+                row.add_prefix(&gtk::Image::from_icon_name("dialog-error-symbolic"));
+                row
+            }
+        };
+        list.append(&row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_server_list_lines() {
+        for (input, expected) in [
+            ("pve", vec![1]),
+            ("name,host\npve", vec![2]),
+            ("Name,Host,Port\npve\nrdp.example.com", vec![2, 3]),
+            ("# servers\n\nname,host\npve", vec![4]),
+            ("  \nname,host\n\npve", vec![4]),
+            // Only the first line can be a header.
+            ("pve\nname,host", vec![1, 2]),
+            ("# name,host\npve", vec![2]),
+            ("", vec![]),
+        ] {
+            let lines = parse_server_list(input)
+                .into_iter()
+                .map(|line| line.line)
+                .collect::<Vec<_>>();
+            assert_eq!(lines, expected, "{input:?}");
+        }
+    }
+}
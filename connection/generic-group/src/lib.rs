@@ -16,6 +16,9 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 mod credential_preferences;
+mod export;
+mod health;
+mod import;
 mod preferences;
 mod server_config;
 mod server_preferences;
@@ -23,20 +26,40 @@ mod util;
 
 use adw::prelude::*;
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::iter;
 use std::num::NonZeroU32;
-use std::rc::Rc;
+use std::sync::Arc;
 
-use futures::future::LocalBoxFuture;
+use anyhow::anyhow;
+use futures::future::{join_all, LocalBoxFuture};
 use gettextrs::gettext;
 use indexmap::IndexMap;
 use secure_string::SecureString;
+use uuid::Uuid;
 
 use crate::credential_preferences::GenericGroupCredentialPreferences;
-use crate::preferences::{GenericGroupConfiguration, GenericGroupPreferences};
+use crate::export::export_action;
+use crate::health::{parse_mac_address, wake_on_lan};
+use crate::preferences::{
+    GenericGroupConfiguration, GenericGroupPreferences, ServerAccount, ServerType,
+};
 use crate::server_config::FinalizedServerConfig;
+use libfieldmonitor::action_failure::report_action_failure;
+use libfieldmonitor::adapter::port_knock::{knock, KnockSent, PortKnockAdapter};
+use libfieldmonitor::adapter::proxy_chain::{ProxyChain, ProxyChainAdapter};
+use libfieldmonitor::adapter::ssh_tunnel::{SshTunnel, SshTunnelAdapter};
 use libfieldmonitor::adapter::types::Adapter;
+use libfieldmonitor::adapter::web::WebAdapter;
 use libfieldmonitor::config_error;
 use libfieldmonitor::connection::*;
+use libfieldmonitor::host::{format_host_port, parse_host};
+use libfieldmonitor::i18n::gettext_f;
+use libfieldmonitor::password_command::run_password_command;
+use libfieldmonitor::resolve::check_reachable;
+use libfieldmonitor::sftp::{browse_files_action, sftp_uri};
+use libfieldmonitor::template::{expand_template, expand_template_with, lookup_variable};
+use log::warn;
 
 pub struct GenericConnectionProviderConstructor;
 
@@ -96,13 +119,10 @@ impl ConnectionProvider for GenericConnectionProvider {
             configuration.transform_update_separate(
                 |c_session| {
                     c_session.set_connection_title(&preferences.title());
+                    c_session.set_password_command(preferences.password_command().trim());
 
                     for server in server_changes.updates.values() {
-                        c_session.set_server_type(&server.key, server.server_type);
-                        c_session.set_title(&server.key, &server.title);
-                        c_session.set_host(&server.key, &server.host);
-                        c_session.set_port(&server.key, server.port);
-                        store_credentials_session(&server.key, server, c_session)?
+                        store_server_session(server, c_session)?
                     }
 
                     for removal in &server_changes.removes {
@@ -112,13 +132,10 @@ impl ConnectionProvider for GenericConnectionProvider {
                 },
                 |c_persistent| {
                     c_persistent.set_connection_title(&preferences.title());
+                    c_persistent.set_password_command(preferences.password_command().trim());
 
                     for server in server_changes.updates.values() {
-                        c_persistent.set_server_type(&server.key, server.server_type);
-                        c_persistent.set_title(&server.key, &server.title);
-                        c_persistent.set_host(&server.key, &server.host);
-                        c_persistent.set_port(&server.key, server.port);
-                        store_credentials_persistent(&server.key, server, c_persistent)?
+                        store_server_persistent(server, c_persistent)?
                     }
 
                     for removal in &server_changes.removes {
@@ -187,6 +204,179 @@ impl ConnectionProvider for GenericConnectionProvider {
             Ok(c)
         })
     }
+
+    fn supports_copying_servers(&self) -> bool {
+        true
+    }
+
+    fn supports_status_polling(&self) -> bool {
+        true
+    }
+
+    fn tag_aliases(&self) -> &'static [&'static str] {
+        &["rdp", "spice", "vnc"]
+    }
+
+    /// Creates a group with a single server. The server type is taken from the `type` option,
+    /// or the alias the provider was selected with.
+    fn configure_from_options(
+        &self,
+        options: &HashMap<String, String>,
+        password: Option<SecureString>,
+        configuration: DualScopedConnectionConfiguration,
+    ) -> LocalBoxFuture<anyhow::Result<DualScopedConnectionConfiguration>> {
+        let options = options.clone();
+        Box::pin(async move {
+            let type_name = options
+                .get("type")
+                .or_else(|| options.get("provider"))
+                .cloned()
+                .unwrap_or_default();
+            let server_type = ServerType::try_from(type_name.clone()).map_err(|()| {
+                anyhow!("unknown server type “{type_name}”, expected rdp, spice or vnc")
+            })?;
+            let host = parse_host(
+                options
+                    .get("host")
+                    .ok_or_else(|| anyhow!("a host is required"))?,
+            )?;
+            let port = match options.get("port") {
+                Some(port) => port
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(NonZeroU32::new)
+                    .ok_or_else(|| anyhow!("invalid port “{port}”"))?,
+                None => server_type.default_port(),
+            };
+            let user = options.get("user").cloned();
+            let server = FinalizedServerConfig {
+                key: Uuid::now_v7().to_string(),
+                server_type: Some(server_type),
+                title: options
+                    .get("title")
+                    .cloned()
+                    .unwrap_or_else(|| host.clone()),
+                host,
+                port,
+                user_remember: user.is_some(),
+                user,
+                password_remember: password.is_some(),
+                password,
+                ..FinalizedServerConfig::default()
+            };
+
+            configuration.transform_update_separate(
+                |c_session| {
+                    c_session.set_connection_title(&server.title);
+                    store_server_session(&server, c_session)
+                },
+                |c_persistent| {
+                    c_persistent.set_connection_title(&server.title);
+                    store_server_persistent(&server, c_persistent)
+                },
+            )
+        })
+    }
+
+    fn copy_server(
+        &self,
+        server_path: &[String],
+        source: &DualScopedConnectionConfiguration,
+        target: DualScopedConnectionConfiguration,
+    ) -> LocalBoxFuture<anyhow::Result<DualScopedConnectionConfiguration>> {
+        let server = server_path.join("/");
+        let source = source.explicit_clone();
+        Box::pin(async move {
+            let mut copy =
+                FinalizedServerConfig::copy_of(Uuid::now_v7().to_string(), &server, &source)
+                    .await?;
+            if source.session().id() == target.session().id() {
+                copy.title = gettext_f("{title} (Copy)", &[("title", &copy.title)]);
+            }
+
+            target.transform_update_separate(
+                |c_session| store_server_session(&copy, c_session),
+                |c_persistent| store_server_persistent(&copy, c_persistent),
+            )
+        })
+    }
+
+    fn remove_server(
+        &self,
+        server_path: &[String],
+        configuration: DualScopedConnectionConfiguration,
+    ) -> LocalBoxFuture<anyhow::Result<DualScopedConnectionConfiguration>> {
+        let server = server_path.join("/");
+        Box::pin(async move {
+            configuration.transform_update_separate(
+                |c_session| {
+                    c_session.remove_server(&server);
+                    Ok(())
+                },
+                |c_persistent| {
+                    c_persistent.set_password(&server, None);
+                    c_persistent.remove_server(&server);
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+fn store_server_session(
+    server: &FinalizedServerConfig,
+    c_session: &mut ConnectionConfiguration,
+) -> anyhow::Result<()> {
+    c_session.set_server_type(&server.key, server.server_type);
+    c_session.set_title(&server.key, &server.title);
+    c_session.set_host(&server.key, &server.host);
+    c_session.set_port(&server.key, server.port);
+    c_session.set_jump_hosts(&server.key, &server.jump_hosts);
+    c_session.set_proxies(&server.key, &server.proxies);
+    c_session.set_port_knocks(&server.key, &server.port_knocks);
+    c_session.set_accounts(&server.key, &server.accounts);
+    for account in &server.accounts {
+        c_session.set_account_password_session(&server.key, &account.id, account.password.as_ref());
+    }
+    c_session.set_shared_folder(&server.key, server.shared_folder.as_deref());
+    c_session.set_smartcard(&server.key, server.smartcard);
+    c_session.set_microphone(&server.key, server.microphone);
+    c_session.set_kerberos(&server.key, server.kerberos);
+    c_session.set_health_check(&server.key, &server.health_check);
+    c_session.set_mac_address(&server.key, server.mac_address.as_deref());
+    c_session.set_web_url(&server.key, server.web_url.as_deref());
+    store_credentials_session(&server.key, server, c_session)
+}
+
+fn store_server_persistent(
+    server: &FinalizedServerConfig,
+    c_persistent: &mut ConnectionConfiguration,
+) -> anyhow::Result<()> {
+    c_persistent.set_server_type(&server.key, server.server_type);
+    c_persistent.set_title(&server.key, &server.title);
+    c_persistent.set_host(&server.key, &server.host);
+    c_persistent.set_port(&server.key, server.port);
+    c_persistent.set_jump_hosts(&server.key, &server.jump_hosts);
+    c_persistent.set_proxies(&server.key, &server.proxies);
+    c_persistent.set_port_knocks(&server.key, &server.port_knocks);
+    // Forget the passwords of removed accounts.
+    for old in c_persistent.accounts(&server.key).unwrap_or_default() {
+        if !server.accounts.iter().any(|account| account.id == old.id) {
+            c_persistent.set_account_password(&server.key, &old.id, None);
+        }
+    }
+    c_persistent.set_accounts(&server.key, &server.accounts);
+    for account in &server.accounts {
+        c_persistent.set_account_password(&server.key, &account.id, account.password.clone());
+    }
+    c_persistent.set_shared_folder(&server.key, server.shared_folder.as_deref());
+    c_persistent.set_smartcard(&server.key, server.smartcard);
+    c_persistent.set_microphone(&server.key, server.microphone);
+    c_persistent.set_kerberos(&server.key, server.kerberos);
+    c_persistent.set_health_check(&server.key, &server.health_check);
+    c_persistent.set_mac_address(&server.key, server.mac_address.as_deref());
+    c_persistent.set_web_url(&server.key, server.web_url.as_deref());
+    store_credentials_persistent(&server.key, server, c_persistent)
 }
 
 fn store_credentials_session(
@@ -213,7 +403,7 @@ fn store_credentials_persistent(
 #[derive(Clone)]
 pub struct GenericConnection {
     title: String,
-    config: Rc<ConnectionConfiguration>,
+    config: Arc<ConnectionConfiguration>,
 }
 
 impl Actionable for GenericConnection {}
@@ -228,17 +418,21 @@ impl Connection for GenericConnection {
 
     fn servers(&self) -> LocalBoxFuture<ConnectionResult<ServerMap>> {
         Box::pin(async move {
-            let mut hm: IndexMap<_, Box<dyn ServerConnection>> = IndexMap::with_capacity(1);
+            let mut hm: IndexMap<_, Box<dyn ServerConnection + Send>> = IndexMap::with_capacity(1);
 
             let mut keys = self.config.section_keys().collect::<Vec<_>>();
             keys.sort_by_key(|key| self.config.title(key).unwrap_or_default());
 
-            for server in keys {
+            // The checks of all servers run at the same time, so slow hosts don't add up.
+            let online = join_all(keys.iter().map(|server| self.check_health(server))).await;
+
+            for (server, is_online) in keys.into_iter().zip(online) {
                 hm.insert(
                     server.to_string().into(),
                     Box::new(GenericConnectionServer {
                         key: server.to_string(),
                         config: self.config.clone(),
+                        is_online,
                     }),
                 );
             }
@@ -252,57 +446,281 @@ impl GenericConnection {
     fn new(title: String, config: ConnectionConfiguration) -> Self {
         Self {
             title,
-            config: Rc::new(config),
+            config: Arc::new(config),
         }
     }
+
+    /// Runs the health check of `server`, if it has one.
+    async fn check_health(&self, server: &str) -> Option<bool> {
+        let health_check = self.config.health_check(server)?;
+        health_check
+            .run(
+                &self.config.host(server).unwrap_or_default(),
+                self.config
+                    .port(server)
+                    .map(NonZeroU32::get)
+                    .unwrap_or_default(),
+            )
+            .await
+    }
 }
 
+/// The password of `account` of `server`, or of its main credentials if `account` is `None`.
+/// If a password command is configured, it is run instead.
+async fn load_password(
+    config: &ConnectionConfiguration,
+    server: &str,
+    account: Option<&ServerAccount>,
+    user: &str,
+    host: &str,
+) -> ConnectionResult<SecureString> {
+    if let Some(command) = config.password_command() {
+        let title = config.title(server).unwrap_or_default();
+        return run_password_command(
+            command,
+            &[("user", user), ("host", host), ("server", title.as_str())],
+        )
+        .await;
+    }
+    let password = match account {
+        Some(account) => config.account_password(server, &account.id).await,
+        None => config.password(server).await,
+    };
+    match password {
+        Ok(pass) => Ok(pass.unwrap_or_else(|| SecureString::from(""))),
+        Err(err) => Err(ConnectionError::AuthFailed(
+            Some(gettext("Failed to load password.")),
+            err,
+        )),
+    }
+}
+
+/// ID of the account using the main credentials of a server, see [`ServerConnection::accounts`].
+const MAIN_ACCOUNT: &str = "main";
+
 struct GenericConnectionServer {
     key: String,
-    config: Rc<ConnectionConfiguration>,
+    config: Arc<ConnectionConfiguration>,
+    /// Result of the health check, if the server has one.
+    is_online: Option<bool>,
 }
 
-impl Actionable for GenericConnectionServer {}
+impl Actionable for GenericConnectionServer {
+    fn actions(&self) -> Vec<(Cow<'static, str>, Cow<'static, str>)> {
+        let mut actions: Vec<(Cow<'static, str>, Cow<'static, str>)> = Vec::with_capacity(3);
+        if self.config.mac_address(&self.key).is_some() {
+            actions.push(("wake".into(), gettext("Wake-on-LAN").into()));
+        }
+        if self.sftp_uri().is_some() {
+            actions.push((
+                "sftp".into(),
+                gettext("Browse Files on Jump Host (SFTP)").into(),
+            ));
+        }
+        match self.config.server_type(&self.key) {
+            Some(ServerType::Rdp) => {
+                actions.push(("exportrdp".into(), gettext("Export as .rdp File…").into()));
+            }
+            Some(ServerType::Spice) => {
+                actions.push(("exportvv".into(), gettext("Export as .vv File…").into()));
+            }
+            _ => {}
+        }
+        actions
+    }
+
+    fn action<'a>(&self, action_id: &str) -> Option<ServerAction<'a>> {
+        match action_id {
+            "wake" => Some(self.act_wake()),
+            "sftp" => Some(browse_files_action(self.sftp_uri()?)),
+            "exportrdp" => Some(export_action(
+                self.config.clone(),
+                self.key.clone(),
+                ServerType::Rdp,
+            )),
+            "exportvv" => Some(export_action(
+                self.config.clone(),
+                self.key.clone(),
+                ServerType::Spice,
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl GenericConnectionServer {
+    /// The URL of the web interface of the server. `${host}` is replaced by the host of the
+    /// server, in brackets if it's an IPv6 address.
+    fn web_url(&self) -> Option<String> {
+        let url = self.config.web_url(&self.key)?;
+        let host = self
+            .config
+            .host(&self.key)
+            .as_deref()
+            .map(expand_template)
+            .unwrap_or_default();
+        Some(expand_template_with(&url, |name| match name {
+            "host" if host.contains(':') => Some(format!("[{host}]")),
+            "host" => Some(host.clone()),
+            name => lookup_variable(name),
+        }))
+    }
+
+    /// The `sftp://` URI of the first jump host, the only one reachable without a tunnel.
+    fn sftp_uri(&self) -> Option<String> {
+        let hop = self.config.jump_hosts(&self.key)?.into_iter().next()?;
+        Some(sftp_uri(
+            hop.user.as_deref().map(expand_template).as_deref(),
+            &expand_template(&hop.host),
+            u16::try_from(hop.port).ok(),
+        ))
+    }
+
+    fn act_wake<'a>(&self) -> ServerAction<'a> {
+        ServerAction::new(
+            Box::new(self.config.mac_address(&self.key)),
+            Box::new(|params, _window, toov| {
+                Box::pin(async move {
+                    let mac_address = params.downcast::<Option<String>>().unwrap();
+                    let result = match mac_address.as_deref().and_then(parse_mac_address) {
+                        Some(mac) => wake_on_lan(mac).map_err(|err| err.to_string()),
+                        None => Err(gettext("Invalid MAC address.")),
+                    };
+                    let text = match result {
+                        Ok(()) => gettext("Wake-on-LAN packet sent."),
+                        Err(err) => {
+                            warn!("failed to send Wake-on-LAN packet: {err}");
+                            let text = gettext_f(
+                                "Failed to send Wake-on-LAN packet: {err}",
+                                &[("err", &err)],
+                            );
+                            report_action_failure(&text);
+                            text
+                        }
+                    };
+                    if let Some(toov) = toov {
+                        toov.add_toast(adw::Toast::builder().title(&text).timeout(5).build());
+                    }
+                    false
+                })
+            }),
+        )
+    }
+}
 
 impl ServerConnection for GenericConnectionServer {
     fn metadata(&self) -> ServerMetadata {
         let user_part = self
             .config
             .user(&self.key)
-            .map(|u| format!("{u}@"))
+            .map(|u| format!("{}@", expand_template(&u)))
             .unwrap_or_default();
+        let hops = self
+            .config
+            .jump_hosts(&self.key)
+            .unwrap_or_default()
+            .iter()
+            .map(|hop| expand_template(&hop.host))
+            .chain(
+                self.config
+                    .proxies(&self.key)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|proxy| expand_template(&proxy.to_string())),
+            )
+            .collect::<Vec<_>>();
+        let info_lines = if hops.is_empty() {
+            Vec::new()
+        } else {
+            vec![gettext_f("Via {hosts}", &[("hosts", &hops.join(" → "))])]
+        };
         ServerMetadataBuilder::default()
-            .title(self.config.title(&self.key).unwrap_or_default())
+            .title(expand_template(
+                &self.config.title(&self.key).unwrap_or_default(),
+            ))
             .subtitle(Some(format!(
-                "{}://{}{}:{}",
+                "{}://{}{}",
                 self.config
                     .server_type(&self.key)
                     .map(|s| s.protocol())
                     .unwrap_or_default(),
                 user_part,
-                self.config.host(&self.key).unwrap_or_default(),
-                self.config
-                    .port(&self.key)
-                    .map(u32::from)
-                    .unwrap_or_default()
+                format_host_port(
+                    &expand_template(&self.config.host(&self.key).unwrap_or_default()),
+                    self.config
+                        .port(&self.key)
+                        .map(u32::from)
+                        .unwrap_or_default()
+                )
             )))
+            .info_lines(info_lines)
+            .is_online(self.is_online)
+            .host(
+                self.config
+                    .host(&self.key)
+                    .map(|host| expand_template(&host)),
+            )
             .build()
             .unwrap()
     }
 
     fn supported_adapters(&self) -> Vec<(Cow<str>, Cow<str>)> {
-        let server_type = self.config.server_type(&self.key);
-        if let Some(server_type) = server_type {
-            vec![(server_type.tag().into(), server_type.label())]
-        } else {
-            vec![]
+        let mut adapters = Vec::new();
+        if let Some(server_type) = self.config.server_type(&self.key) {
+            adapters.push((server_type.tag().into(), server_type.label()));
+        }
+        if self.config.web_url(&self.key).is_some() {
+            adapters.push((WebAdapter::TAG.into(), WebAdapter::label().into()));
         }
+        adapters
     }
 
     fn create_adapter(
         &self,
         tag: &str,
     ) -> LocalBoxFuture<Result<Box<dyn Adapter>, ConnectionError>> {
+        self.create_adapter_for_account(tag, MAIN_ACCOUNT)
+    }
+
+    fn accounts(&self) -> Vec<(Cow<str>, Cow<str>)> {
+        let accounts = self.config.accounts(&self.key).unwrap_or_default();
+        if accounts.is_empty() {
+            return vec![];
+        }
+        let main_name = self
+            .config
+            .user(&self.key)
+            .map(|user| expand_template(&user))
+            .unwrap_or_else(|| gettext("Default Account"));
+        iter::once((MAIN_ACCOUNT.into(), main_name.into()))
+            .chain(
+                accounts
+                    .into_iter()
+                    .map(|account| (account.id.into(), account.name.into())),
+            )
+            .collect()
+    }
+
+    fn create_adapter_for_account(
+        &self,
+        tag: &str,
+        account: &str,
+    ) -> LocalBoxFuture<Result<Box<dyn Adapter>, ConnectionError>> {
+        if tag == WebAdapter::TAG {
+            let Some(url) = self.web_url() else {
+                return Box::pin(async {
+                    Err(ConnectionError::General(
+                        Some(gettext("This server has no web interface.")),
+                        anyhow!("server has no web interface"),
+                    ))
+                });
+            };
+            return Box::pin(async move {
+                let bx: Box<dyn Adapter> = Box::new(WebAdapter::new(url));
+                Ok(bx)
+            });
+        }
+
         let server_type = self.config.server_type(&self.key);
         assert_eq!(
             tag,
@@ -310,39 +728,165 @@ impl ServerConnection for GenericConnectionServer {
             "unsupported adapter type"
         );
         let server_type = server_type.unwrap();
+        let account = self
+            .config
+            .accounts(&self.key)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|a| a.id == account);
 
         Box::pin(async move {
-            let password = match self.config.password(&self.key).await {
-                Ok(pass) => pass.unwrap_or_else(|| SecureString::from("")),
-                Err(err) => {
-                    return Err(ConnectionError::AuthFailed(
-                        Some(gettext("Failed to load password.")),
+            let host = self
+                .config
+                .host(&self.key)
+                .as_deref()
+                .map(expand_template)
+                .unwrap_or_default();
+            let port = self
+                .config
+                .port(&self.key)
+                .as_ref()
+                .copied()
+                .map(NonZeroU32::get)
+                .unwrap_or_default();
+            let user = match &account {
+                Some(account) => account.user.clone(),
+                None => self.config.user(&self.key),
+            };
+            let user = user.as_deref().map(expand_template).unwrap_or_default();
+
+            let password =
+                load_password(&self.config, &self.key, account.as_ref(), &user, &host).await?;
+            let shared_folder = self
+                .config
+                .shared_folder(&self.key)
+                .as_deref()
+                .map(expand_template);
+            let smartcard = self.config.smartcard(&self.key).unwrap_or_default();
+            let microphone = self.config.microphone(&self.key).unwrap_or_default();
+            let kerberos = self.config.kerberos(&self.key).unwrap_or_default();
+
+            let mut jump_hosts = self.config.jump_hosts(&self.key).unwrap_or_default();
+            for hop in &mut jump_hosts {
+                hop.host = expand_template(&hop.host);
+                hop.user = hop.user.as_deref().map(expand_template);
+                hop.identity_file = hop.identity_file.as_deref().map(expand_template);
+            }
+
+            // The knocks go first, the ports they open may be needed by the checks and tunnels
+            // below.
+            let mut port_knocks = self.config.port_knocks(&self.key).unwrap_or_default();
+            for port_knock in &mut port_knocks {
+                port_knock.host = port_knock.host.as_deref().map(expand_template);
+            }
+            if !port_knocks.is_empty() {
+                knock(&port_knocks, &host).await.map_err(|err| {
+                    ConnectionError::General(
+                        Some(gettext("Failed to send the port knock sequence.")),
                         err,
-                    ));
+                    )
+                })?;
+            }
+
+            let mut proxies = self.config.proxies(&self.key).unwrap_or_default();
+            for proxy in &mut proxies {
+                proxy.host = expand_template(&proxy.host);
+            }
+
+            let adapter: Box<dyn Adapter> = if jump_hosts.is_empty() && proxies.is_empty() {
+                // Jump hosts and proxies resolve the target themselves, so only check direct
+                // connections.
+                let address = match u16::try_from(port) {
+                    Ok(port) => check_reachable(&host, port)
+                        .await
+                        .map_err(|err| {
+                            ConnectionError::General(Some(err.to_string()), anyhow::Error::new(err))
+                        })?
+                        .required_address(),
+                    Err(_) => None,
+                };
+                server_type.new_adapter(
+                    host,
+                    address,
+                    port,
+                    user,
+                    password,
+                    shared_folder,
+                    smartcard,
+                    microphone,
+                    kerberos,
+                )
+            } else {
+                // The SSH tunnel leads to the first proxy if there are any, the proxies then lead
+                // to the server.
+                let tunnel = if jump_hosts.is_empty() {
+                    None
+                } else {
+                    let (tunnel_host, tunnel_port) = match proxies.first() {
+                        Some(proxy) => (proxy.host.clone(), u32::from(proxy.port)),
+                        None => (host.clone(), port),
+                    };
+                    let tunnel = SshTunnel::open(&jump_hosts, &tunnel_host, tunnel_port)
+                        .await
+                        .map_err(|err| {
+                            ConnectionError::General(
+                                Some(gettext("Failed to open SSH tunnel through the jump hosts.")),
+                                err,
+                            )
+                        })?;
+                    if let Some(proxy) = proxies.first_mut() {
+                        proxy.host = tunnel.local_host();
+                        proxy.port = tunnel.local_port();
+                    }
+                    Some(tunnel)
+                };
+                let chain = if proxies.is_empty() {
+                    None
+                } else {
+                    let port = u16::try_from(port)
+                        .map_err(|err| ConnectionError::General(None, anyhow::Error::new(err)))?;
+                    let chain = ProxyChain::open(&proxies, &host, port)
+                        .await
+                        .map_err(|err| {
+                            ConnectionError::General(
+                                Some(gettext("Failed to connect through the proxies.")),
+                                err,
+                            )
+                        })?;
+                    Some(chain)
+                };
+
+                let (local_host, local_port) = match (&chain, &tunnel) {
+                    (Some(chain), _) => (chain.local_host(), chain.local_port()),
+                    (None, Some(tunnel)) => (tunnel.local_host(), tunnel.local_port()),
+                    (None, None) => unreachable!("neither jump hosts nor proxies configured"),
+                };
+                let mut adapter = server_type.new_adapter(
+                    local_host,
+                    None,
+                    u32::from(local_port),
+                    user,
+                    password,
+                    shared_folder,
+                    smartcard,
+                    microphone,
+                    kerberos,
+                );
+                if let Some(chain) = chain {
+                    adapter = Box::new(ProxyChainAdapter::new(chain, adapter));
+                }
+                if let Some(tunnel) = tunnel {
+                    adapter = Box::new(SshTunnelAdapter::new(tunnel, adapter));
                 }
+                adapter
             };
 
-            let bx = server_type.new_adapter(
-                self.config
-                    .host(&self.key)
-                    .as_ref()
-                    .map(ToString::to_string)
-                    .unwrap_or_default(),
-                self.config
-                    .port(&self.key)
-                    .as_ref()
-                    .copied()
-                    .map(NonZeroU32::get)
-                    .unwrap_or_default(),
-                self.config
-                    .user(&self.key)
-                    .as_ref()
-                    .map(ToString::to_string)
-                    .unwrap_or_default(),
-                password,
-            );
-
-            Ok(bx)
+            if port_knocks.is_empty() {
+                Ok(adapter)
+            } else {
+                let bx: Box<dyn Adapter> = Box::new(PortKnockAdapter::new(KnockSent, adapter));
+                Ok(bx)
+            }
         })
     }
 }
@@ -21,19 +21,26 @@ use std::cell::RefCell;
 use std::fmt::{Display, Formatter};
 use std::num::NonZeroU32;
 use std::ops::{Deref, DerefMut};
+use std::time::Duration;
 
-use crate::server_config::{ServerConfigChanges, ServerConfigForRow};
+use crate::health::HealthCheck;
+use crate::import::present_import_dialog;
+use crate::server_config::{FinalizedServerConfig, ServerConfigChanges, ServerConfigForRow};
 use crate::server_preferences::GenericGroupServerPreferences;
 use adw::gio;
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use futures::future::LocalBoxFuture;
 use gettextrs::gettext;
+use libfieldmonitor::adapter::port_knock::{KnockProtocol, PortKnock};
+use libfieldmonitor::adapter::proxy_chain::{ProxyHop, ProxyProtocol};
 use libfieldmonitor::adapter::rdp::RdpAdapter;
 use libfieldmonitor::adapter::spice::SpiceAdapter;
+use libfieldmonitor::adapter::ssh_tunnel::SshJumpHost;
 use libfieldmonitor::adapter::types::Adapter;
 use libfieldmonitor::adapter::vnc::VncAdapter;
 use libfieldmonitor::connection::*;
+use libfieldmonitor::host::format_host_port;
 use libfieldmonitor::i18n::gettext_f;
 use log::warn;
 use secure_string::SecureString;
@@ -41,13 +48,34 @@ use uuid::Uuid;
 
 pub trait GenericGroupConfiguration {
     fn connection_title(&self) -> Option<&str>;
+    /// Command printing the passwords of the servers, used instead of the stored ones.
+    fn password_command(&self) -> Option<&str>;
     fn server_type(&self, server: &str) -> Option<ServerType>;
     fn title(&self, server: &str) -> Option<String>;
     fn host(&self, server: &str) -> Option<String>;
     fn port(&self, server: &str) -> Option<NonZeroU32>;
     fn user(&self, server: &str) -> Option<String>;
     fn password(&self, server: &str) -> LocalBoxFuture<anyhow::Result<Option<SecureString>>>;
+    fn accounts(&self, server: &str) -> Option<Vec<ServerAccount>>;
+    fn account_password(
+        &self,
+        server: &str,
+        account: &str,
+    ) -> LocalBoxFuture<anyhow::Result<Option<SecureString>>>;
+    fn jump_hosts(&self, server: &str) -> Option<Vec<SshJumpHost>>;
+    /// Proxies the server is reached through, after the jump hosts.
+    fn proxies(&self, server: &str) -> Option<Vec<ProxyHop>>;
+    /// Knock sequence sent before connecting to the server.
+    fn port_knocks(&self, server: &str) -> Option<Vec<PortKnock>>;
+    fn shared_folder(&self, server: &str) -> Option<String>;
+    fn smartcard(&self, server: &str) -> Option<bool>;
+    fn microphone(&self, server: &str) -> Option<bool>;
+    fn kerberos(&self, server: &str) -> Option<bool>;
+    fn health_check(&self, server: &str) -> Option<HealthCheck>;
+    fn mac_address(&self, server: &str) -> Option<String>;
+    fn web_url(&self, server: &str) -> Option<String>;
     fn set_connection_title(&mut self, value: &str);
+    fn set_password_command(&mut self, value: &str);
     fn set_server_type(&mut self, server: &str, value: Option<ServerType>);
     fn set_title(&mut self, server: &str, value: &str);
     fn set_host(&mut self, server: &str, value: &str);
@@ -55,6 +83,24 @@ pub trait GenericGroupConfiguration {
     fn set_user(&mut self, server: &str, value: Option<&str>);
     fn set_password(&mut self, server: &str, value: Option<SecureString>);
     fn set_password_session(&mut self, server: &str, value: Option<&SecureString>);
+    fn set_accounts(&mut self, server: &str, value: &[ServerAccount]);
+    fn set_account_password(&mut self, server: &str, account: &str, value: Option<SecureString>);
+    fn set_account_password_session(
+        &mut self,
+        server: &str,
+        account: &str,
+        value: Option<&SecureString>,
+    );
+    fn set_jump_hosts(&mut self, server: &str, value: &[SshJumpHost]);
+    fn set_proxies(&mut self, server: &str, value: &[ProxyHop]);
+    fn set_port_knocks(&mut self, server: &str, value: &[PortKnock]);
+    fn set_shared_folder(&mut self, server: &str, value: Option<&str>);
+    fn set_smartcard(&mut self, server: &str, value: bool);
+    fn set_microphone(&mut self, server: &str, value: bool);
+    fn set_kerberos(&mut self, server: &str, value: bool);
+    fn set_health_check(&mut self, server: &str, value: &HealthCheck);
+    fn set_mac_address(&mut self, server: &str, value: Option<&str>);
+    fn set_web_url(&mut self, server: &str, value: Option<&str>);
     fn remove_server(&mut self, server: &str);
 }
 
@@ -63,6 +109,11 @@ impl GenericGroupConfiguration for ConnectionConfiguration {
         self.get_try_as_str("title")
     }
 
+    fn password_command(&self) -> Option<&str> {
+        self.get_try_as_str("password_command")
+            .filter(|command| !command.trim().is_empty())
+    }
+
     fn server_type(&self, server: &str) -> Option<ServerType> {
         self.with_section(server, |section| {
             section
@@ -110,10 +161,108 @@ impl GenericGroupConfiguration for ConnectionConfiguration {
         })
     }
 
+    fn accounts(&self, server: &str) -> Option<Vec<ServerAccount>> {
+        self.with_section(server, |section| {
+            match section.get("accounts")?.as_serde_value()? {
+                serde_yaml::Value::Sequence(seq) => {
+                    Some(seq.iter().filter_map(account_from_value).collect())
+                }
+                _ => None,
+            }
+        })
+    }
+
+    fn account_password(
+        &self,
+        server: &str,
+        account: &str,
+    ) -> LocalBoxFuture<anyhow::Result<Option<SecureString>>> {
+        let server = server.to_string();
+        let key = account_password_key(account);
+        Box::pin(async move {
+            self.with_section_async(&server, |section| {
+                Box::pin(async move {
+                    if let Some(pw) = section.get_try_as_sec_string(&format!("__session__{key}")) {
+                        return Ok(Some(pw));
+                    }
+                    section.get_secret(&key).await
+                })
+            })
+            .await
+        })
+    }
+
+    fn jump_hosts(&self, server: &str) -> Option<Vec<SshJumpHost>> {
+        self.with_section(server, |section| {
+            match section.get("jump_hosts")?.as_serde_value()? {
+                serde_yaml::Value::Sequence(seq) => {
+                    Some(seq.iter().filter_map(jump_host_from_value).collect())
+                }
+                _ => None,
+            }
+        })
+    }
+
+    fn proxies(&self, server: &str) -> Option<Vec<ProxyHop>> {
+        self.with_section(server, |section| {
+            match section.get("proxies")?.as_serde_value()? {
+                serde_yaml::Value::Sequence(seq) => {
+                    Some(seq.iter().filter_map(proxy_from_value).collect())
+                }
+                _ => None,
+            }
+        })
+    }
+
+    fn port_knocks(&self, server: &str) -> Option<Vec<PortKnock>> {
+        self.with_section(server, |section| {
+            match section.get("port_knocks")?.as_serde_value()? {
+                serde_yaml::Value::Sequence(seq) => {
+                    Some(seq.iter().filter_map(port_knock_from_value).collect())
+                }
+                _ => None,
+            }
+        })
+    }
+
+    fn shared_folder(&self, server: &str) -> Option<String> {
+        self.with_section(server, |section| section.get_try_as_string("shared_folder"))
+    }
+
+    fn smartcard(&self, server: &str) -> Option<bool> {
+        self.with_section(server, |section| section.get_try_as_bool("smartcard"))
+    }
+
+    fn microphone(&self, server: &str) -> Option<bool> {
+        self.with_section(server, |section| section.get_try_as_bool("microphone"))
+    }
+
+    fn kerberos(&self, server: &str) -> Option<bool> {
+        self.with_section(server, |section| section.get_try_as_bool("kerberos"))
+    }
+
+    fn health_check(&self, server: &str) -> Option<HealthCheck> {
+        self.with_section(server, |section| {
+            HealthCheck::from_value(section.get("health_check")?.as_serde_value()?)
+        })
+    }
+
+    fn mac_address(&self, server: &str) -> Option<String> {
+        self.with_section(server, |section| section.get_try_as_string("mac_address"))
+    }
+
+    fn web_url(&self, server: &str) -> Option<String> {
+        self.with_section(server, |section| section.get_try_as_string("web_url"))
+    }
+
     fn set_connection_title(&mut self, value: &str) {
         self.set_value("title", value);
     }
 
+    fn set_password_command(&mut self, value: &str) {
+        self.set_value("password_command", value);
+    }
+
     fn set_server_type(&mut self, server: &str, value: Option<ServerType>) {
         self.with_section_mut(server, |mut section| {
             section.set_value(
@@ -162,6 +311,101 @@ impl GenericGroupConfiguration for ConnectionConfiguration {
         })
     }
 
+    fn set_accounts(&mut self, server: &str, value: &[ServerAccount]) {
+        let value = serde_yaml::Value::Sequence(value.iter().map(account_to_value).collect());
+        self.with_section_mut(server, |mut section| section.set_value("accounts", value));
+    }
+
+    fn set_account_password(&mut self, server: &str, account: &str, value: Option<SecureString>) {
+        self.set_account_password_session(server, account, value.as_ref());
+        let key = account_password_key(account);
+        self.with_section_mut(server, |mut section| match value {
+            None => section.clear_secret(&key),
+            Some(value) => section.set_secret(&key, value),
+        })
+    }
+
+    fn set_account_password_session(
+        &mut self,
+        server: &str,
+        account: &str,
+        value: Option<&SecureString>,
+    ) {
+        let key = format!("__session__{}", account_password_key(account));
+        self.with_section_mut(server, |mut section| match value {
+            None => {
+                section.clear(&key);
+            }
+            Some(value) => {
+                section.set_secure_string(&key, value.clone());
+            }
+        })
+    }
+
+    fn set_jump_hosts(&mut self, server: &str, value: &[SshJumpHost]) {
+        let value = serde_yaml::Value::Sequence(value.iter().map(jump_host_to_value).collect());
+        self.with_section_mut(server, |mut section| section.set_value("jump_hosts", value));
+    }
+
+    fn set_proxies(&mut self, server: &str, value: &[ProxyHop]) {
+        let value = serde_yaml::Value::Sequence(value.iter().map(proxy_to_value).collect());
+        self.with_section_mut(server, |mut section| section.set_value("proxies", value));
+    }
+
+    fn set_port_knocks(&mut self, server: &str, value: &[PortKnock]) {
+        let value = serde_yaml::Value::Sequence(value.iter().map(port_knock_to_value).collect());
+        self.with_section_mut(server, |mut section| {
+            section.set_value("port_knocks", value)
+        });
+    }
+
+    fn set_shared_folder(&mut self, server: &str, value: Option<&str>) {
+        let value = match value {
+            None => serde_yaml::Value::Null,
+            Some(value) => value.into(),
+        };
+        self.with_section_mut(server, |mut section| {
+            section.set_value("shared_folder", value)
+        });
+    }
+
+    fn set_smartcard(&mut self, server: &str, value: bool) {
+        self.with_section_mut(server, |mut section| section.set_value("smartcard", value));
+    }
+
+    fn set_microphone(&mut self, server: &str, value: bool) {
+        self.with_section_mut(server, |mut section| section.set_value("microphone", value));
+    }
+
+    fn set_kerberos(&mut self, server: &str, value: bool) {
+        self.with_section_mut(server, |mut section| section.set_value("kerberos", value));
+    }
+
+    fn set_health_check(&mut self, server: &str, value: &HealthCheck) {
+        let value = value.to_value();
+        self.with_section_mut(server, |mut section| {
+            section.set_value("health_check", value)
+        });
+    }
+
+    fn set_mac_address(&mut self, server: &str, value: Option<&str>) {
+        let value = match value {
+            None => serde_yaml::Value::Null,
+            Some(value) => value.into(),
+        };
+        self.with_section_mut(server, |mut section| {
+            section.set_value("mac_address", value)
+        });
+    }
+
+    fn set_web_url(&mut self, server: &str, value: Option<&str>) {
+        let value = match value {
+            None => serde_yaml::Value::Null,
+            Some(value) => value.into(),
+        };
+        self.with_section_mut(server, |mut section| section.set_value("web_url", value));
+    }
+
     fn remove_server(&mut self, server: &str) {
         if let Some(ConfigValueRef::SerdeValue(serde_yaml::Value::Mapping(_))) = self.get(server) {
             self.clear(server)
@@ -174,6 +418,10 @@ impl<T: GenericGroupConfiguration + ?Sized> GenericGroupConfiguration for Box<T>
         self.deref().connection_title()
     }
 
+    fn password_command(&self) -> Option<&str> {
+        self.deref().password_command()
+    }
+
     fn server_type(&self, server: &str) -> Option<ServerType> {
         self.deref().server_type(server)
     }
@@ -198,10 +446,66 @@ impl<T: GenericGroupConfiguration + ?Sized> GenericGroupConfiguration for Box<T>
         self.deref().password(server)
     }
 
+    fn accounts(&self, server: &str) -> Option<Vec<ServerAccount>> {
+        self.deref().accounts(server)
+    }
+
+    fn account_password(
+        &self,
+        server: &str,
+        account: &str,
+    ) -> LocalBoxFuture<anyhow::Result<Option<SecureString>>> {
+        self.deref().account_password(server, account)
+    }
+
+    fn jump_hosts(&self, server: &str) -> Option<Vec<SshJumpHost>> {
+        self.deref().jump_hosts(server)
+    }
+
+    fn proxies(&self, server: &str) -> Option<Vec<ProxyHop>> {
+        self.deref().proxies(server)
+    }
+
+    fn port_knocks(&self, server: &str) -> Option<Vec<PortKnock>> {
+        self.deref().port_knocks(server)
+    }
+
+    fn shared_folder(&self, server: &str) -> Option<String> {
+        self.deref().shared_folder(server)
+    }
+
+    fn smartcard(&self, server: &str) -> Option<bool> {
+        self.deref().smartcard(server)
+    }
+
+    fn microphone(&self, server: &str) -> Option<bool> {
+        self.deref().microphone(server)
+    }
+
+    fn kerberos(&self, server: &str) -> Option<bool> {
+        self.deref().kerberos(server)
+    }
+
+    fn health_check(&self, server: &str) -> Option<HealthCheck> {
+        self.deref().health_check(server)
+    }
+
+    fn mac_address(&self, server: &str) -> Option<String> {
+        self.deref().mac_address(server)
+    }
+
+    fn web_url(&self, server: &str) -> Option<String> {
+        self.deref().web_url(server)
+    }
+
     fn set_connection_title(&mut self, value: &str) {
         self.deref_mut().set_connection_title(value)
     }
 
+    fn set_password_command(&mut self, value: &str) {
+        self.deref_mut().set_password_command(value)
+    }
+
     fn set_server_type(&mut self, server: &str, value: Option<ServerType>) {
         self.deref_mut().set_server_type(server, value)
     }
@@ -230,11 +534,214 @@ impl<T: GenericGroupConfiguration + ?Sized> GenericGroupConfiguration for Box<T>
         self.deref_mut().set_password_session(server, value)
     }
 
+    fn set_accounts(&mut self, server: &str, value: &[ServerAccount]) {
+        self.deref_mut().set_accounts(server, value)
+    }
+
+    fn set_account_password(&mut self, server: &str, account: &str, value: Option<SecureString>) {
+        self.deref_mut()
+            .set_account_password(server, account, value)
+    }
+
+    fn set_account_password_session(
+        &mut self,
+        server: &str,
+        account: &str,
+        value: Option<&SecureString>,
+    ) {
+        self.deref_mut()
+            .set_account_password_session(server, account, value)
+    }
+
+    fn set_jump_hosts(&mut self, server: &str, value: &[SshJumpHost]) {
+        self.deref_mut().set_jump_hosts(server, value)
+    }
+
+    fn set_proxies(&mut self, server: &str, value: &[ProxyHop]) {
+        self.deref_mut().set_proxies(server, value)
+    }
+
+    fn set_port_knocks(&mut self, server: &str, value: &[PortKnock]) {
+        self.deref_mut().set_port_knocks(server, value)
+    }
+
+    fn set_shared_folder(&mut self, server: &str, value: Option<&str>) {
+        self.deref_mut().set_shared_folder(server, value)
+    }
+
+    fn set_smartcard(&mut self, server: &str, value: bool) {
+        self.deref_mut().set_smartcard(server, value)
+    }
+
+    fn set_microphone(&mut self, server: &str, value: bool) {
+        self.deref_mut().set_microphone(server, value)
+    }
+
+    fn set_kerberos(&mut self, server: &str, value: bool) {
+        self.deref_mut().set_kerberos(server, value)
+    }
+
+    fn set_health_check(&mut self, server: &str, value: &HealthCheck) {
+        self.deref_mut().set_health_check(server, value)
+    }
+
+    fn set_mac_address(&mut self, server: &str, value: Option<&str>) {
+        self.deref_mut().set_mac_address(server, value)
+    }
+
+    fn set_web_url(&mut self, server: &str, value: Option<&str>) {
+        self.deref_mut().set_web_url(server, value)
+    }
+
     fn remove_server(&mut self, server: &str) {
         self.deref_mut().remove_server(server)
     }
 }
 
+/// An account to log in to a server with, in addition to the main credentials of the server.
+#[derive(Debug, Clone)]
+pub struct ServerAccount {
+    /// Stable ID of the account, its password is stored under it.
+    pub id: String,
+    pub name: String,
+    pub user: Option<String>,
+    /// Only set in server configs that are being edited, the password is stored as a secret.
+    pub password: Option<SecureString>,
+}
+
+impl ServerAccount {
+    pub fn new(name: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            user: None,
+            password: None,
+        }
+    }
+}
+
+fn account_password_key(account: &str) -> String {
+    format!("account_password_{account}")
+}
+
+fn account_from_value(value: &serde_yaml::Value) -> Option<ServerAccount> {
+    Some(ServerAccount {
+        id: value.get("id")?.as_str()?.to_string(),
+        name: value
+            .get("name")
+            .and_then(serde_yaml::Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        user: value
+            .get("user")
+            .and_then(serde_yaml::Value::as_str)
+            .map(ToString::to_string),
+        password: None,
+    })
+}
+
+fn account_to_value(account: &ServerAccount) -> serde_yaml::Value {
+    let mut map = serde_yaml::Mapping::new();
+    map.insert("id".into(), account.id.as_str().into());
+    map.insert("name".into(), account.name.as_str().into());
+    if let Some(user) = &account.user {
+        map.insert("user".into(), user.as_str().into());
+    }
+    serde_yaml::Value::Mapping(map)
+}
+
+fn jump_host_from_value(value: &serde_yaml::Value) -> Option<SshJumpHost> {
+    let mut hop = SshJumpHost::new(value.get("host")?.as_str()?.to_string());
+    if let Some(port) = value
+        .get("port")
+        .and_then(serde_yaml::Value::as_u64)
+        .and_then(|v| u32::try_from(v).ok())
+    {
+        hop.port = port;
+    }
+    hop.user = value
+        .get("user")
+        .and_then(serde_yaml::Value::as_str)
+        .map(ToString::to_string);
+    hop.identity_file = value
+        .get("identity_file")
+        .and_then(serde_yaml::Value::as_str)
+        .map(ToString::to_string);
+    Some(hop)
+}
+
+fn jump_host_to_value(hop: &SshJumpHost) -> serde_yaml::Value {
+    let mut map = serde_yaml::Mapping::new();
+    map.insert("host".into(), hop.host.as_str().into());
+    map.insert("port".into(), hop.port.into());
+    if let Some(user) = &hop.user {
+        map.insert("user".into(), user.as_str().into());
+    }
+    if let Some(identity_file) = &hop.identity_file {
+        map.insert("identity_file".into(), identity_file.as_str().into());
+    }
+    serde_yaml::Value::Mapping(map)
+}
+
+fn proxy_from_value(value: &serde_yaml::Value) -> Option<ProxyHop> {
+    let protocol = match value.get("protocol").and_then(serde_yaml::Value::as_str) {
+        Some(protocol) => ProxyProtocol::try_from(protocol).ok()?,
+        None => ProxyProtocol::default(),
+    };
+    let mut proxy = ProxyHop::new(protocol, value.get("host")?.as_str()?.to_string());
+    if let Some(port) = value
+        .get("port")
+        .and_then(serde_yaml::Value::as_u64)
+        .and_then(|v| u16::try_from(v).ok())
+    {
+        proxy.port = port;
+    }
+    Some(proxy)
+}
+
+fn proxy_to_value(proxy: &ProxyHop) -> serde_yaml::Value {
+    let mut map = serde_yaml::Mapping::new();
+    map.insert("protocol".into(), proxy.protocol.tag().into());
+    map.insert("host".into(), proxy.host.as_str().into());
+    map.insert("port".into(), proxy.port.into());
+    serde_yaml::Value::Mapping(map)
+}
+
+fn port_knock_from_value(value: &serde_yaml::Value) -> Option<PortKnock> {
+    let port = value
+        .get("port")
+        .and_then(serde_yaml::Value::as_u64)
+        .and_then(|v| u16::try_from(v).ok())?;
+    let mut knock = PortKnock::new(port);
+    knock.host = value
+        .get("host")
+        .and_then(serde_yaml::Value::as_str)
+        .map(ToString::to_string);
+    if let Some(protocol) = value.get("protocol").and_then(serde_yaml::Value::as_str) {
+        knock.protocol = KnockProtocol::try_from(protocol).ok()?;
+    }
+    if let Some(delay) = value.get("delay_ms").and_then(serde_yaml::Value::as_u64) {
+        knock.delay = Duration::from_millis(delay);
+    }
+    Some(knock)
+}
+
+fn port_knock_to_value(knock: &PortKnock) -> serde_yaml::Value {
+    let mut map = serde_yaml::Mapping::new();
+    if let Some(host) = &knock.host {
+        map.insert("host".into(), host.as_str().into());
+    }
+    map.insert("port".into(), knock.port.into());
+    map.insert("protocol".into(), knock.protocol.tag().into());
+    map.insert(
+        "delay_ms".into(),
+        u64::try_from(knock.delay.as_millis())
+            .unwrap_or(u64::MAX)
+            .into(),
+    );
+    serde_yaml::Value::Mapping(map)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ServerType {
     Rdp,
@@ -278,6 +785,14 @@ impl ServerType {
         self.tag()
     }
 
+    /// The port servers of this type usually listen on.
+    pub fn default_port(&self) -> NonZeroU32 {
+        match self {
+            ServerType::Rdp => NonZeroU32::new(3389).unwrap(),
+            ServerType::Spice | ServerType::Vnc => NonZeroU32::new(5900).unwrap(),
+        }
+    }
+
     pub fn label(&self) -> Cow<'static, str> {
         match self {
             ServerType::Rdp => RdpAdapter::label(),
@@ -286,17 +801,36 @@ impl ServerType {
         }
     }
 
+    /// `address` is connected to instead of resolving `host`, if set.
     pub fn new_adapter(
         &self,
         host: String,
+        address: Option<String>,
         port: u32,
         user: String,
         password: SecureString,
+        shared_folder: Option<String>,
+        smartcard: bool,
+        microphone: bool,
+        kerberos: bool,
     ) -> Box<dyn Adapter> {
         let bx: Box<dyn Adapter> = match self {
-            ServerType::Rdp => Box::new(RdpAdapter::new(host, port, user, password)),
-            ServerType::Spice => Box::new(SpiceAdapter::new(host, port, user, password)),
-            ServerType::Vnc => Box::new(VncAdapter::new(host, port, user, password)),
+            ServerType::Rdp => Box::new(
+                RdpAdapter::new(host, port, user, password)
+                    .with_address(address)
+                    .with_microphone(microphone)
+                    .with_kerberos(kerberos),
+            ),
+            ServerType::Spice => Box::new(
+                // Connections to generic SPICE servers are not encrypted, so there is no name to
+                // check.
+                SpiceAdapter::new(address.unwrap_or(host), port, user, password)
+                    .with_shared_dir(shared_folder)
+                    .with_smartcard(smartcard),
+            ),
+            ServerType::Vnc => {
+                Box::new(VncAdapter::new(host, port, user, password).with_address(address))
+            }
         };
         bx
     }
@@ -319,6 +853,8 @@ mod imp {
         pub servers_box: TemplateChild<gtk::ListBox>,
         #[property(get, set)]
         pub title: RefCell<String>,
+        #[property(get, set)]
+        pub password_command: RefCell<String>,
         pub server_model_bound: Cell<bool>,
         pub changes: RefCell<ServerConfigChanges>,
         pub config: RefCell<Option<ConnectionConfiguration>>,
@@ -361,6 +897,11 @@ impl GenericGroupPreferences {
                     .connection_title()
                     .unwrap_or_default(),
             );
+            slf.set_password_command(
+                existing_configuration
+                    .password_command()
+                    .unwrap_or_default(),
+            );
 
             let servers: Vec<ServerConfigForRow> = existing_configuration
                 .section_keys()
@@ -442,14 +983,13 @@ impl GenericGroupPreferences {
                     let row = adw::ActionRow::builder()
                         .title(config.title())
                         .subtitle(format!(
-                            "{}://{}{}:{}",
+                            "{}://{}{}",
                             ServerType::try_from(config.server_type())
                                 .ok()
                                 .map(|s| s.protocol())
                                 .unwrap_or_default(),
                             user_part,
-                            config.host(),
-                            config.port()
+                            format_host_port(&config.host(), config.port())
                         ))
                         .activatable_widget(&edit)
                         .build();
@@ -496,43 +1036,7 @@ impl GenericGroupPreferences {
                 #[strong(rename_to = slf)]
                 self,
                 move |editor| {
-                    let cfg = editor.make_config()?;
-                    // Insert or update store
-                    let mut found = false;
-                    for server in slf.imp().server_store.iter::<glib::Object>() {
-                        let server = server.unwrap().downcast::<ServerConfigForRow>().unwrap();
-                        if server.key() == cfg.key {
-                            found = true;
-                            server.set_server_type(cfg.server_type.map(|s| s.to_string()));
-                            server.set_title(&*cfg.title);
-                            server.set_host(&*cfg.host);
-                            server.set_port(u32::from(cfg.port));
-                            server.set_user(cfg.user.as_deref());
-                            break;
-                        }
-                    }
-                    if !found {
-                        let obj = glib::Object::builder::<ServerConfigForRow>()
-                            .property("key", &cfg.key)
-                            .property("server-type", cfg.server_type.map(|s| s.to_string()))
-                            .property("title", &cfg.title)
-                            .property("host", &cfg.host)
-                            .property("port", cfg.port)
-                            .property("user", cfg.user.as_deref())
-                            .build();
-                        if let Some(server_type) = cfg.server_type {
-                            obj.set_server_type(Some(server_type.to_string()));
-                        }
-                        slf.imp().server_store.append(&obj)
-                    }
-                    // Insert into changes
-                    slf.imp()
-                        .changes
-                        .borrow_mut()
-                        .updates
-                        .insert(cfg.key.clone(), cfg);
-
-                    slf.bind_model();
+                    slf.store_server(editor.make_config()?);
                     Some(())
                 }
             ),
@@ -560,6 +1064,61 @@ impl GenericGroupPreferences {
         );
     }
 
+    /// Inserts or updates a server in the list and records it as changed.
+    fn store_server(&self, cfg: FinalizedServerConfig) {
+        // Insert or update store
+        let mut found = false;
+        for server in self.imp().server_store.iter::<glib::Object>() {
+            let server = server.unwrap().downcast::<ServerConfigForRow>().unwrap();
+            if server.key() == cfg.key {
+                found = true;
+                server.set_server_type(cfg.server_type.map(|s| s.to_string()));
+                server.set_title(&*cfg.title);
+                server.set_host(&*cfg.host);
+                server.set_port(u32::from(cfg.port));
+                server.set_user(cfg.user.as_deref());
+                break;
+            }
+        }
+        if !found {
+            let obj = glib::Object::builder::<ServerConfigForRow>()
+                .property("key", &cfg.key)
+                .property("server-type", cfg.server_type.map(|s| s.to_string()))
+                .property("title", &cfg.title)
+                .property("host", &cfg.host)
+                .property("port", cfg.port)
+                .property("user", cfg.user.as_deref())
+                .build();
+            if let Some(server_type) = cfg.server_type {
+                obj.set_server_type(Some(server_type.to_string()));
+            }
+            self.imp().server_store.append(&obj)
+        }
+        // Insert into changes
+        self.imp()
+            .changes
+            .borrow_mut()
+            .updates
+            .insert(cfg.key.clone(), cfg);
+
+        self.bind_model();
+    }
+
+    pub fn import_servers(&self) {
+        present_import_dialog(
+            self,
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |servers| {
+                    for server in servers {
+                        slf.store_server(server);
+                    }
+                }
+            ),
+        );
+    }
+
     fn present_server_window<T>(
         &self,
         is_new: bool,
@@ -638,4 +1197,9 @@ impl GenericGroupPreferences {
     fn on_add_server_clicked(&self) {
         self.add_server();
     }
+
+    #[template_callback]
+    fn on_import_servers_clicked(&self) {
+        self.import_servers();
+    }
 }
@@ -23,9 +23,14 @@ use std::ops::{Deref, DerefMut};
 use futures::future::LocalBoxFuture;
 use glib::prelude::*;
 use glib::subclass::prelude::*;
+use libfieldmonitor::adapter::port_knock::PortKnock;
+use libfieldmonitor::adapter::proxy_chain::ProxyHop;
+use libfieldmonitor::adapter::ssh_tunnel::SshJumpHost;
+use libfieldmonitor::connection::{ConfigAccess, DualScopedConnectionConfiguration};
 use secure_string::SecureString;
 
-use crate::preferences::{GenericGroupConfiguration, ServerType};
+use crate::health::HealthCheck;
+use crate::preferences::{GenericGroupConfiguration, ServerAccount, ServerType};
 
 mod imp {
     use super::*;
@@ -73,6 +78,26 @@ pub struct FinalizedServerConfig {
     pub password: Option<SecureString>,
     pub user_remember: bool,
     pub password_remember: bool,
+    pub jump_hosts: Vec<SshJumpHost>,
+    /// Proxies the server is reached through, after the jump hosts.
+    pub proxies: Vec<ProxyHop>,
+    /// Knock sequence sent before connecting.
+    pub port_knocks: Vec<PortKnock>,
+    /// Accounts in addition to `user` and `password`, including their passwords.
+    pub accounts: Vec<ServerAccount>,
+    /// Local folder shared with SPICE guests.
+    pub shared_folder: Option<String>,
+    /// Whether local smartcard readers are shared with SPICE guests.
+    pub smartcard: bool,
+    /// Whether the local microphone is redirected to RDP servers.
+    pub microphone: bool,
+    /// Whether RDP servers are logged in to with the Kerberos tickets of the user.
+    pub kerberos: bool,
+    pub health_check: HealthCheck,
+    /// MAC address to send Wake-on-LAN packets to.
+    pub mac_address: Option<String>,
+    /// URL of the web management interface, may contain `${host}`.
+    pub web_url: Option<String>,
 }
 
 impl Default for FinalizedServerConfig {
@@ -87,6 +112,17 @@ impl Default for FinalizedServerConfig {
             password: None,
             user_remember: bool::default(),
             password_remember: bool::default(),
+            jump_hosts: Vec::new(),
+            proxies: Vec::new(),
+            port_knocks: Vec::new(),
+            accounts: Vec::new(),
+            shared_folder: None,
+            smartcard: false,
+            microphone: false,
+            kerberos: false,
+            health_check: HealthCheck::default(),
+            mac_address: None,
+            web_url: None,
         }
     }
 }
@@ -107,6 +143,57 @@ impl FinalizedServerConfig {
             None
         }
     }
+
+    /// Reads the server `server` of a connection into a config with the new key `key`.
+    /// Credentials are only marked as remembered if they are stored persistently in the source.
+    pub async fn copy_of(
+        key: String,
+        server: &str,
+        source: &DualScopedConnectionConfiguration,
+    ) -> anyhow::Result<Self> {
+        let session = source.session();
+        let persistent = source.persistent();
+
+        let remembered_user = persistent.user(server);
+        let remembered_password = persistent
+            .with_section_async(server, |section| {
+                Box::pin(async move { section.get_secret("password").await })
+            })
+            .await?;
+
+        let mut accounts = session.accounts(server).unwrap_or_default();
+        for account in &mut accounts {
+            account.password = session.account_password(server, &account.id).await?;
+        }
+
+        Ok(Self {
+            key,
+            server_type: session.server_type(server),
+            title: session.title(server).unwrap_or_default(),
+            host: session.host(server).unwrap_or_default(),
+            port: session
+                .port(server)
+                .unwrap_or_else(|| NonZeroU32::new(1).unwrap()),
+            user_remember: remembered_user.is_some(),
+            user: remembered_user.or_else(|| session.user(server)),
+            password_remember: remembered_password.is_some(),
+            password: match remembered_password {
+                Some(password) => Some(password),
+                None => session.password(server).await?,
+            },
+            jump_hosts: session.jump_hosts(server).unwrap_or_default(),
+            proxies: session.proxies(server).unwrap_or_default(),
+            port_knocks: session.port_knocks(server).unwrap_or_default(),
+            accounts,
+            shared_folder: session.shared_folder(server),
+            smartcard: session.smartcard(server).unwrap_or_default(),
+            microphone: session.microphone(server).unwrap_or_default(),
+            kerberos: session.kerberos(server).unwrap_or_default(),
+            health_check: session.health_check(server).unwrap_or_default(),
+            mac_address: session.mac_address(server),
+            web_url: session.web_url(server),
+        })
+    }
 }
 
 #[derive(Debug, Default)]
@@ -138,6 +225,10 @@ impl GenericGroupConfiguration for ServerUpdateMap {
         None
     }
 
+    fn password_command(&self) -> Option<&str> {
+        None
+    }
+
     fn server_type(&self, server: &str) -> Option<ServerType> {
         self.0.get(server).and_then(|s| s.server_type)
     }
@@ -177,10 +268,75 @@ impl GenericGroupConfiguration for ServerUpdateMap {
         })
     }
 
+    fn accounts(&self, server: &str) -> Option<Vec<ServerAccount>> {
+        self.0.get(server).map(|s| s.accounts.clone())
+    }
+
+    fn account_password(
+        &self,
+        server: &str,
+        account: &str,
+    ) -> LocalBoxFuture<anyhow::Result<Option<SecureString>>> {
+        let server = server.to_string();
+        let account = account.to_string();
+        Box::pin(async move {
+            Ok(self.0.get(&server).and_then(|s| {
+                s.accounts
+                    .iter()
+                    .find(|a| a.id == account)
+                    .and_then(|a| a.password.clone())
+            }))
+        })
+    }
+
+    fn jump_hosts(&self, server: &str) -> Option<Vec<SshJumpHost>> {
+        self.0.get(server).map(|s| s.jump_hosts.clone())
+    }
+
+    fn proxies(&self, server: &str) -> Option<Vec<ProxyHop>> {
+        self.0.get(server).map(|s| s.proxies.clone())
+    }
+
+    fn port_knocks(&self, server: &str) -> Option<Vec<PortKnock>> {
+        self.0.get(server).map(|s| s.port_knocks.clone())
+    }
+
+    fn shared_folder(&self, server: &str) -> Option<String> {
+        self.0.get(server).and_then(|s| s.shared_folder.clone())
+    }
+
+    fn smartcard(&self, server: &str) -> Option<bool> {
+        self.0.get(server).map(|s| s.smartcard)
+    }
+
+    fn microphone(&self, server: &str) -> Option<bool> {
+        self.0.get(server).map(|s| s.microphone)
+    }
+
+    fn kerberos(&self, server: &str) -> Option<bool> {
+        self.0.get(server).map(|s| s.kerberos)
+    }
+
+    fn health_check(&self, server: &str) -> Option<HealthCheck> {
+        self.0.get(server).map(|s| s.health_check.clone())
+    }
+
+    fn mac_address(&self, server: &str) -> Option<String> {
+        self.0.get(server).and_then(|s| s.mac_address.clone())
+    }
+
+    fn web_url(&self, server: &str) -> Option<String> {
+        self.0.get(server).and_then(|s| s.web_url.clone())
+    }
+
     fn set_connection_title(&mut self, _value: &str) {
         unimplemented!()
     }
 
+    fn set_password_command(&mut self, _value: &str) {
+        unimplemented!()
+    }
+
     fn set_server_type(&mut self, _server: &str, _value: Option<ServerType>) {
         unimplemented!()
     }
@@ -209,6 +365,68 @@ impl GenericGroupConfiguration for ServerUpdateMap {
         unimplemented!()
     }
 
+    fn set_accounts(&mut self, _server: &str, _value: &[ServerAccount]) {
+        unimplemented!()
+    }
+
+    fn set_account_password(
+        &mut self,
+        _server: &str,
+        _account: &str,
+        _value: Option<SecureString>,
+    ) {
+        unimplemented!()
+    }
+
+    fn set_account_password_session(
+        &mut self,
+        _server: &str,
+        _account: &str,
+        _value: Option<&SecureString>,
+    ) {
+        unimplemented!()
+    }
+
+    fn set_jump_hosts(&mut self, _server: &str, _value: &[SshJumpHost]) {
+        unimplemented!()
+    }
+
+    fn set_proxies(&mut self, _server: &str, _value: &[ProxyHop]) {
+        unimplemented!()
+    }
+
+    fn set_port_knocks(&mut self, _server: &str, _value: &[PortKnock]) {
+        unimplemented!()
+    }
+
+    fn set_shared_folder(&mut self, _server: &str, _value: Option<&str>) {
+        unimplemented!()
+    }
+
+    fn set_smartcard(&mut self, _server: &str, _value: bool) {
+        unimplemented!()
+    }
+
+    fn set_microphone(&mut self, _server: &str, _value: bool) {
+        unimplemented!()
+    }
+
+    fn set_kerberos(&mut self, _server: &str, _value: bool) {
+        unimplemented!()
+    }
+
+    fn set_health_check(&mut self, _server: &str, _value: &HealthCheck) {
+        unimplemented!()
+    }
+
+    fn set_mac_address(&mut self, _server: &str, _value: Option<&str>) {
+        unimplemented!()
+    }
+
+    fn set_web_url(&mut self, _server: &str, _value: Option<&str>) {
+        unimplemented!()
+    }
+
     fn remove_server(&mut self, _server: &str) {
         unimplemented!()
     }
@@ -235,6 +453,10 @@ where
         self.0.connection_title().or(self.1.connection_title())
     }
 
+    fn password_command(&self) -> Option<&str> {
+        self.0.password_command().or(self.1.password_command())
+    }
+
     fn server_type(&self, server: &str) -> Option<ServerType> {
         self.0.server_type(server).or(self.1.server_type(server))
     }
@@ -266,10 +488,76 @@ where
         })
     }
 
+    fn accounts(&self, server: &str) -> Option<Vec<ServerAccount>> {
+        self.0.accounts(server).or(self.1.accounts(server))
+    }
+
+    fn account_password(
+        &self,
+        server: &str,
+        account: &str,
+    ) -> LocalBoxFuture<anyhow::Result<Option<SecureString>>> {
+        let server = server.to_string();
+        let account = account.to_string();
+        Box::pin(async move {
+            let a_opt = self.0.account_password(&server, &account).await?;
+            match a_opt {
+                Some(a) => Ok(Some(a)),
+                None => self.1.account_password(&server, &account).await,
+            }
+        })
+    }
+
+    fn jump_hosts(&self, server: &str) -> Option<Vec<SshJumpHost>> {
+        self.0.jump_hosts(server).or(self.1.jump_hosts(server))
+    }
+
+    fn proxies(&self, server: &str) -> Option<Vec<ProxyHop>> {
+        self.0.proxies(server).or(self.1.proxies(server))
+    }
+
+    fn port_knocks(&self, server: &str) -> Option<Vec<PortKnock>> {
+        self.0.port_knocks(server).or(self.1.port_knocks(server))
+    }
+
+    fn shared_folder(&self, server: &str) -> Option<String> {
+        self.0
+            .shared_folder(server)
+            .or(self.1.shared_folder(server))
+    }
+
+    fn smartcard(&self, server: &str) -> Option<bool> {
+        self.0.smartcard(server).or(self.1.smartcard(server))
+    }
+
+    fn microphone(&self, server: &str) -> Option<bool> {
+        self.0.microphone(server).or(self.1.microphone(server))
+    }
+
+    fn kerberos(&self, server: &str) -> Option<bool> {
+        self.0.kerberos(server).or(self.1.kerberos(server))
+    }
+
+    fn health_check(&self, server: &str) -> Option<HealthCheck> {
+        self.0.health_check(server).or(self.1.health_check(server))
+    }
+
+    fn mac_address(&self, server: &str) -> Option<String> {
+        self.0.mac_address(server).or(self.1.mac_address(server))
+    }
+
+    fn web_url(&self, server: &str) -> Option<String> {
+        self.0.web_url(server).or(self.1.web_url(server))
+    }
+
     fn set_connection_title(&mut self, _value: &str) {
         unimplemented!()
     }
 
+    fn set_password_command(&mut self, _value: &str) {
+        unimplemented!()
+    }
+
     fn set_server_type(&mut self, _server: &str, _value: Option<ServerType>) {
         unimplemented!()
     }
@@ -298,6 +586,68 @@ where
         unimplemented!()
     }
 
+    fn set_accounts(&mut self, _server: &str, _value: &[ServerAccount]) {
+        unimplemented!()
+    }
+
+    fn set_account_password(
+        &mut self,
+        _server: &str,
+        _account: &str,
+        _value: Option<SecureString>,
+    ) {
+        unimplemented!()
+    }
+
+    fn set_account_password_session(
+        &mut self,
+        _server: &str,
+        _account: &str,
+        _value: Option<&SecureString>,
+    ) {
+        unimplemented!()
+    }
+
+    fn set_jump_hosts(&mut self, _server: &str, _value: &[SshJumpHost]) {
+        unimplemented!()
+    }
+
+    fn set_proxies(&mut self, _server: &str, _value: &[ProxyHop]) {
+        unimplemented!()
+    }
+
+    fn set_port_knocks(&mut self, _server: &str, _value: &[PortKnock]) {
+        unimplemented!()
+    }
+
+    fn set_shared_folder(&mut self, _server: &str, _value: Option<&str>) {
+        unimplemented!()
+    }
+
+    fn set_smartcard(&mut self, _server: &str, _value: bool) {
+        unimplemented!()
+    }
+
+    fn set_microphone(&mut self, _server: &str, _value: bool) {
+        unimplemented!()
+    }
+
+    fn set_kerberos(&mut self, _server: &str, _value: bool) {
+        unimplemented!()
+    }
+
+    fn set_health_check(&mut self, _server: &str, _value: &HealthCheck) {
+        unimplemented!()
+    }
+
+    fn set_mac_address(&mut self, _server: &str, _value: Option<&str>) {
+        unimplemented!()
+    }
+
+    fn set_web_url(&mut self, _server: &str, _value: Option<&str>) {
+        unimplemented!()
+    }
+
     fn remove_server(&mut self, _server: &str) {
         unimplemented!()
     }
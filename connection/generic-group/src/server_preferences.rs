@@ -1,11 +1,18 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::num::NonZeroU32;
+use std::time::Duration;
 
 use crate::credential_preferences::GenericGroupCredentialPreferences;
+use crate::health::{parse_mac_address, HealthCheck};
 use crate::preferences::{GenericGroupConfiguration, ServerType};
 use crate::server_config::FinalizedServerConfig;
 use adw::prelude::*;
 use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use libfieldmonitor::adapter::port_knock::{KnockProtocol, PortKnock, DEFAULT_KNOCK_DELAY};
+use libfieldmonitor::adapter::proxy_chain::{ProxyHop, ProxyProtocol};
+use libfieldmonitor::adapter::ssh_tunnel::SshJumpHost;
+use libfieldmonitor::host;
 
 mod imp {
     use super::*;
@@ -25,7 +32,26 @@ mod imp {
         #[template_child]
         pub(crate) port_entry: TemplateChild<adw::EntryRow>,
         #[template_child]
+        pub(crate) jump_hosts_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub(crate) proxies_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub(crate) port_knocks_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub(crate) spice_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub(crate) rdp_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub(crate) kerberos_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub(crate) health_port_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub(crate) mac_address_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
         pub(crate) credentials: TemplateChild<GenericGroupCredentialPreferences>,
+        pub(crate) jump_host_rows: RefCell<Vec<JumpHostRow>>,
+        pub(crate) proxy_rows: RefCell<Vec<ProxyRow>>,
+        pub(crate) port_knock_rows: RefCell<Vec<PortKnockRow>>,
 
         #[property(get, construct_only)]
         pub key: RefCell<String>,
@@ -37,6 +63,26 @@ mod imp {
         pub host: RefCell<String>,
         #[property(get, set)]
         pub port: RefCell<String>,
+        #[property(get, set)]
+        pub shared_folder: RefCell<String>,
+        #[property(get, set)]
+        pub smartcard: Cell<bool>,
+        #[property(get, set)]
+        pub microphone: Cell<bool>,
+        #[property(get, set)]
+        pub kerberos: Cell<bool>,
+        #[property(get, set)]
+        pub health_ping: Cell<bool>,
+        #[property(get, set)]
+        pub health_port_check: Cell<bool>,
+        #[property(get, set)]
+        pub health_host: RefCell<String>,
+        #[property(get, set)]
+        pub health_port: RefCell<String>,
+        #[property(get, set)]
+        pub mac_address: RefCell<String>,
+        #[property(get, set)]
+        pub web_url: RefCell<String>,
     }
 
     #[glib::object_subclass]
@@ -91,6 +137,45 @@ impl GenericGroupServerPreferences {
                     if let Some(v) = existing_configuration.port(&server) {
                         slf.set_port(v.to_string());
                     }
+                    for hop in existing_configuration
+                        .jump_hosts(&server)
+                        .unwrap_or_default()
+                    {
+                        slf.add_jump_host(Some(&hop));
+                    }
+                    for proxy in existing_configuration.proxies(&server).unwrap_or_default() {
+                        slf.add_proxy(Some(&proxy));
+                    }
+                    for port_knock in existing_configuration
+                        .port_knocks(&server)
+                        .unwrap_or_default()
+                    {
+                        slf.add_port_knock(Some(&port_knock));
+                    }
+                    if let Some(v) = existing_configuration.shared_folder(&server) {
+                        slf.set_shared_folder(v);
+                    }
+                    if let Some(v) = existing_configuration.smartcard(&server) {
+                        slf.set_smartcard(v);
+                    }
+                    if let Some(v) = existing_configuration.microphone(&server) {
+                        slf.set_microphone(v);
+                    }
+                    if let Some(v) = existing_configuration.kerberos(&server) {
+                        slf.set_kerberos(v);
+                    }
+                    if let Some(v) = existing_configuration.health_check(&server) {
+                        slf.set_health_ping(v.ping);
+                        slf.set_health_port_check(v.port_check);
+                        slf.set_health_host(v.host.unwrap_or_default());
+                        slf.set_health_port(v.port.map(|p| p.to_string()).unwrap_or_default());
+                    }
+                    if let Some(v) = existing_configuration.mac_address(&server) {
+                        slf.set_mac_address(v);
+                    }
+                    if let Some(v) = existing_configuration.web_url(&server) {
+                        slf.set_web_url(v);
+                    }
 
                     slf.imp()
                         .credentials
@@ -120,16 +205,211 @@ impl GenericGroupServerPreferences {
         };
         self.port_entry_error(false);
 
+        // Hosts with template variables can only be checked once they are resolved.
+        let host = if self.host().contains("${") {
+            self.host()
+        } else {
+            match host::parse_host(&self.host()) {
+                Ok(host) => host,
+                Err(err) => {
+                    self.imp().host_entry.add_css_class("error");
+                    self.imp()
+                        .host_entry
+                        .set_tooltip_text(Some(&err.to_string()));
+                    return None;
+                }
+            }
+        };
+        self.imp().host_entry.remove_css_class("error");
+        self.imp().host_entry.set_tooltip_text(None);
+
         config.title = self.title();
         debug_assert!(ServerType::try_from(self.server_type()).is_ok());
         config.server_type = self.server_type().try_into().ok();
-        config.host = self.host();
+        config.host = host;
         config.port = port;
         config.key = self.key();
+        config.jump_hosts = self.jump_hosts()?;
+        config.proxies = self.proxies()?;
+        config.port_knocks = self.port_knocks()?;
+        // Only SPICE supports sharing folders and smartcards.
+        if config.server_type == Some(ServerType::Spice) {
+            config.smartcard = self.smartcard();
+            let shared_folder = self.shared_folder();
+            let shared_folder = shared_folder.trim();
+            config.shared_folder = (!shared_folder.is_empty()).then(|| shared_folder.to_string());
+        }
+        if config.server_type == Some(ServerType::Rdp) {
+            config.microphone = self.microphone();
+            config.kerberos = self.kerberos();
+        }
+        config.health_check = self.health_check()?;
+        let mac_address = self.mac_address();
+        let mac_address = mac_address.trim();
+        if !mac_address.is_empty() && parse_mac_address(mac_address).is_none() {
+            self.imp().mac_address_entry.add_css_class("error");
+            return None;
+        }
+        self.imp().mac_address_entry.remove_css_class("error");
+        config.mac_address = (!mac_address.is_empty()).then(|| mac_address.to_string());
+        let web_url = self.web_url();
+        let web_url = web_url.trim();
+        config.web_url = (!web_url.is_empty()).then(|| web_url.to_string());
         self.imp().credentials.update_server_config(&mut config);
         Some(config)
     }
 
+    /// Collects the configured health check. Marks the port and returns None if it is invalid.
+    fn health_check(&self) -> Option<HealthCheck> {
+        let port = self.health_port();
+        let port = port.trim();
+        let port = if port.is_empty() {
+            None
+        } else {
+            let Some(port) = port
+                .parse::<u32>()
+                .ok()
+                .filter(|port| *port <= u32::from(u16::MAX))
+                .and_then(NonZeroU32::new)
+            else {
+                self.imp().health_port_entry.add_css_class("error");
+                return None;
+            };
+            Some(port)
+        };
+        self.imp().health_port_entry.remove_css_class("error");
+
+        let host = self.health_host();
+        let host = host.trim();
+        Some(HealthCheck {
+            ping: self.health_ping(),
+            port_check: self.health_port_check(),
+            host: (!host.is_empty()).then(|| host.to_string()),
+            port,
+        })
+    }
+
+    /// Collects the configured jump hosts. Marks invalid rows and returns None if any is invalid.
+    fn jump_hosts(&self) -> Option<Vec<SshJumpHost>> {
+        let mut valid = true;
+        let mut hops = Vec::new();
+        for row in self.imp().jump_host_rows.borrow().iter() {
+            match row.to_jump_host() {
+                Some(hop) => {
+                    row.expander.remove_css_class("error");
+                    hops.push(hop);
+                }
+                None => {
+                    row.expander.add_css_class("error");
+                    row.expander.set_expanded(true);
+                    valid = false;
+                }
+            }
+        }
+        valid.then_some(hops)
+    }
+
+    fn add_jump_host(&self, hop: Option<&SshJumpHost>) {
+        let row = JumpHostRow::new(hop);
+        let expander = row.expander.clone();
+        row.remove_button.connect_clicked(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            #[weak]
+            expander,
+            move |_| {
+                slf.imp().jump_hosts_group.remove(&expander);
+                slf.imp()
+                    .jump_host_rows
+                    .borrow_mut()
+                    .retain(|row| row.expander != expander);
+            }
+        ));
+        self.imp().jump_hosts_group.add(&row.expander);
+        self.imp().jump_host_rows.borrow_mut().push(row);
+    }
+
+    /// Collects the configured proxies. Marks invalid rows and returns None if any is invalid.
+    fn proxies(&self) -> Option<Vec<ProxyHop>> {
+        let mut valid = true;
+        let mut proxies = Vec::new();
+        for row in self.imp().proxy_rows.borrow().iter() {
+            match row.to_proxy() {
+                Some(proxy) => {
+                    row.expander.remove_css_class("error");
+                    proxies.push(proxy);
+                }
+                None => {
+                    row.expander.add_css_class("error");
+                    row.expander.set_expanded(true);
+                    valid = false;
+                }
+            }
+        }
+        valid.then_some(proxies)
+    }
+
+    fn add_proxy(&self, proxy: Option<&ProxyHop>) {
+        let row = ProxyRow::new(proxy);
+        let expander = row.expander.clone();
+        row.remove_button.connect_clicked(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            #[weak]
+            expander,
+            move |_| {
+                slf.imp().proxies_group.remove(&expander);
+                slf.imp()
+                    .proxy_rows
+                    .borrow_mut()
+                    .retain(|row| row.expander != expander);
+            }
+        ));
+        self.imp().proxies_group.add(&row.expander);
+        self.imp().proxy_rows.borrow_mut().push(row);
+    }
+
+    /// Collects the configured knock sequence. Marks invalid rows and returns None if any is
+    /// invalid.
+    fn port_knocks(&self) -> Option<Vec<PortKnock>> {
+        let mut valid = true;
+        let mut knocks = Vec::new();
+        for row in self.imp().port_knock_rows.borrow().iter() {
+            match row.to_port_knock() {
+                Some(port_knock) => {
+                    row.expander.remove_css_class("error");
+                    knocks.push(port_knock);
+                }
+                None => {
+                    row.expander.add_css_class("error");
+                    row.expander.set_expanded(true);
+                    valid = false;
+                }
+            }
+        }
+        valid.then_some(knocks)
+    }
+
+    fn add_port_knock(&self, port_knock: Option<&PortKnock>) {
+        let row = PortKnockRow::new(port_knock);
+        let expander = row.expander.clone();
+        row.remove_button.connect_clicked(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            #[weak]
+            expander,
+            move |_| {
+                slf.imp().port_knocks_group.remove(&expander);
+                slf.imp()
+                    .port_knock_rows
+                    .borrow_mut()
+                    .retain(|row| row.expander != expander);
+            }
+        ));
+        self.imp().port_knocks_group.add(&row.expander);
+        self.imp().port_knock_rows.borrow_mut().push(row);
+    }
+
     pub fn port_entry_error(&self, error: bool) {
         if error {
             self.imp().port_entry.add_css_class("error");
@@ -139,6 +419,298 @@ impl GenericGroupServerPreferences {
     }
 }
 
+/// Editor rows for a single jump host.
+pub(crate) struct JumpHostRow {
+    expander: adw::ExpanderRow,
+    host_entry: adw::EntryRow,
+    port_entry: adw::EntryRow,
+    user_entry: adw::EntryRow,
+    identity_file_entry: adw::EntryRow,
+    remove_button: gtk::Button,
+}
+
+impl JumpHostRow {
+    fn new(hop: Option<&SshJumpHost>) -> Self {
+        let default_hop = SshJumpHost::new(String::new());
+        let hop = hop.unwrap_or(&default_hop);
+
+        let expander = adw::ExpanderRow::builder()
+            .title(gettext("Jump Host"))
+            .expanded(hop.host.is_empty())
+            .build();
+        let host_entry = adw::EntryRow::builder()
+            .title(gettext("Hostname"))
+            .text(hop.host.as_str())
+            .build();
+        let port_entry = adw::EntryRow::builder()
+            .title(gettext("Port"))
+            .text(hop.port.to_string())
+            .input_purpose(gtk::InputPurpose::Number)
+            .build();
+        let user_entry = adw::EntryRow::builder()
+            .title(gettext("Username"))
+            .text(hop.user.as_deref().unwrap_or_default())
+            .build();
+        let identity_file_entry = adw::EntryRow::builder()
+            .title(gettext("Identity File"))
+            .text(hop.identity_file.as_deref().unwrap_or_default())
+            .build();
+        let remove_button = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text(gettext("Remove Jump Host"))
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+
+        host_entry
+            .bind_property("text", &expander, "subtitle")
+            .sync_create()
+            .build();
+
+        expander.add_suffix(&remove_button);
+        expander.add_row(&host_entry);
+        expander.add_row(&port_entry);
+        expander.add_row(&user_entry);
+        expander.add_row(&identity_file_entry);
+
+        Self {
+            expander,
+            host_entry,
+            port_entry,
+            user_entry,
+            identity_file_entry,
+            remove_button,
+        }
+    }
+
+    fn to_jump_host(&self) -> Option<SshJumpHost> {
+        let host = self.host_entry.text().trim().to_string();
+        if host.is_empty() {
+            return None;
+        }
+        let port = self
+            .port_entry
+            .text()
+            .parse::<u32>()
+            .ok()
+            .filter(|port| (1..=u32::from(u16::MAX)).contains(port))?;
+        let non_empty =
+            |text: glib::GString| Some(text.trim().to_string()).filter(|t| !t.is_empty());
+
+        let mut hop = SshJumpHost::new(host);
+        hop.port = port;
+        hop.user = non_empty(self.user_entry.text());
+        hop.identity_file = non_empty(self.identity_file_entry.text());
+        Some(hop)
+    }
+}
+
+/// Editor rows for a single proxy.
+pub(crate) struct ProxyRow {
+    expander: adw::ExpanderRow,
+    protocol_row: adw::ComboRow,
+    host_entry: adw::EntryRow,
+    port_entry: adw::EntryRow,
+    remove_button: gtk::Button,
+}
+
+impl ProxyRow {
+    const SELECTED_IDX_SOCKS5: u32 = 0;
+    const SELECTED_IDX_HTTP: u32 = 1;
+
+    fn new(proxy: Option<&ProxyHop>) -> Self {
+        let default_proxy = ProxyHop::new(ProxyProtocol::default(), String::new());
+        let proxy = proxy.unwrap_or(&default_proxy);
+
+        let expander = adw::ExpanderRow::builder()
+            .title(gettext("Proxy"))
+            .expanded(proxy.host.is_empty())
+            .build();
+        let protocol_row = adw::ComboRow::builder()
+            .title(gettext("Protocol"))
+            .model(&gtk::StringList::new(&["SOCKS5", "HTTP"]))
+            .selected(match proxy.protocol {
+                ProxyProtocol::Socks5 => Self::SELECTED_IDX_SOCKS5,
+                ProxyProtocol::Http => Self::SELECTED_IDX_HTTP,
+            })
+            .build();
+        let host_entry = adw::EntryRow::builder()
+            .title(gettext("Hostname"))
+            .text(proxy.host.as_str())
+            .build();
+        let port_entry = adw::EntryRow::builder()
+            .title(gettext("Port"))
+            .text(proxy.port.to_string())
+            .input_purpose(gtk::InputPurpose::Number)
+            .build();
+        let remove_button = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text(gettext("Remove Proxy"))
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+
+        host_entry
+            .bind_property("text", &expander, "subtitle")
+            .sync_create()
+            .build();
+        // Switching the protocol also switches to its default port, unless a port was entered.
+        protocol_row.connect_selected_notify(glib::clone!(
+            #[weak]
+            port_entry,
+            move |protocol_row| {
+                let is_default_port = [ProxyProtocol::Socks5, ProxyProtocol::Http]
+                    .iter()
+                    .any(|protocol| port_entry.text() == protocol.default_port().to_string());
+                if is_default_port {
+                    port_entry.set_text(&Self::protocol(protocol_row).default_port().to_string());
+                }
+            }
+        ));
+
+        expander.add_suffix(&remove_button);
+        expander.add_row(&protocol_row);
+        expander.add_row(&host_entry);
+        expander.add_row(&port_entry);
+
+        Self {
+            expander,
+            protocol_row,
+            host_entry,
+            port_entry,
+            remove_button,
+        }
+    }
+
+    fn protocol(protocol_row: &adw::ComboRow) -> ProxyProtocol {
+        match protocol_row.selected() {
+            Self::SELECTED_IDX_HTTP => ProxyProtocol::Http,
+            _ => ProxyProtocol::Socks5,
+        }
+    }
+
+    fn to_proxy(&self) -> Option<ProxyHop> {
+        let host = self.host_entry.text().trim().to_string();
+        if host.is_empty() {
+            return None;
+        }
+        let port = self
+            .port_entry
+            .text()
+            .trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|port| *port > 0)?;
+
+        let mut proxy = ProxyHop::new(Self::protocol(&self.protocol_row), host);
+        proxy.port = port;
+        Some(proxy)
+    }
+}
+
+/// Editor rows for a single knock of the knock sequence.
+pub(crate) struct PortKnockRow {
+    expander: adw::ExpanderRow,
+    host_entry: adw::EntryRow,
+    port_entry: adw::EntryRow,
+    protocol_row: adw::ComboRow,
+    delay_row: adw::SpinRow,
+    remove_button: gtk::Button,
+}
+
+impl PortKnockRow {
+    const SELECTED_IDX_TCP: u32 = 0;
+    const SELECTED_IDX_UDP: u32 = 1;
+
+    fn new(port_knock: Option<&PortKnock>) -> Self {
+        let expander = adw::ExpanderRow::builder()
+            .title(gettext("Knock"))
+            .expanded(port_knock.is_none())
+            .build();
+        let host_entry = adw::EntryRow::builder()
+            .title(gettext("Hostname (optional)"))
+            .text(
+                port_knock
+                    .and_then(|k| k.host.as_deref())
+                    .unwrap_or_default(),
+            )
+            .build();
+        let port_entry = adw::EntryRow::builder()
+            .title(gettext("Port"))
+            .text(port_knock.map(|k| k.port.to_string()).unwrap_or_default())
+            .input_purpose(gtk::InputPurpose::Number)
+            .build();
+        let protocol_row = adw::ComboRow::builder()
+            .title(gettext("Protocol"))
+            .model(&gtk::StringList::new(&["TCP", "UDP"]))
+            .selected(match port_knock.map(|k| k.protocol).unwrap_or_default() {
+                KnockProtocol::Tcp => Self::SELECTED_IDX_TCP,
+                KnockProtocol::Udp => Self::SELECTED_IDX_UDP,
+            })
+            .build();
+        let delay_row = adw::SpinRow::builder()
+            .title(gettext("Delay"))
+            .subtitle(gettext("Milliseconds to wait after the knock"))
+            .adjustment(&gtk::Adjustment::new(
+                port_knock
+                    .map_or(DEFAULT_KNOCK_DELAY, |k| k.delay)
+                    .as_millis() as f64,
+                0.0,
+                60_000.0,
+                50.0,
+                500.0,
+                0.0,
+            ))
+            .build();
+        let remove_button = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text(gettext("Remove Knock"))
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+
+        port_entry
+            .bind_property("text", &expander, "subtitle")
+            .sync_create()
+            .build();
+
+        expander.add_suffix(&remove_button);
+        expander.add_row(&host_entry);
+        expander.add_row(&port_entry);
+        expander.add_row(&protocol_row);
+        expander.add_row(&delay_row);
+
+        Self {
+            expander,
+            host_entry,
+            port_entry,
+            protocol_row,
+            delay_row,
+            remove_button,
+        }
+    }
+
+    fn to_port_knock(&self) -> Option<PortKnock> {
+        let port = self
+            .port_entry
+            .text()
+            .trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|port| *port > 0)?;
+        let host = self.host_entry.text().trim().to_string();
+
+        let mut port_knock = PortKnock::new(port);
+        port_knock.host = (!host.is_empty()).then_some(host);
+        port_knock.protocol = match self.protocol_row.selected() {
+            Self::SELECTED_IDX_UDP => KnockProtocol::Udp,
+            _ => KnockProtocol::Tcp,
+        };
+        port_knock.delay = Duration::from_millis(self.delay_row.value() as u64);
+        Some(port_knock)
+    }
+}
+
 #[gtk::template_callbacks]
 impl GenericGroupServerPreferences {
     const SELECTED_IDX_RDP: u32 = 0;
@@ -148,6 +720,15 @@ impl GenericGroupServerPreferences {
     #[template_callback]
     fn on_self_server_type_changed(&self) {
         let server_type: Option<ServerType> = self.server_type().try_into().ok();
+        self.imp()
+            .spice_group
+            .set_visible(server_type == Some(ServerType::Spice));
+        self.imp()
+            .rdp_group
+            .set_visible(server_type == Some(ServerType::Rdp));
+        self.imp()
+            .kerberos_group
+            .set_visible(server_type == Some(ServerType::Rdp));
         self.imp().server_type_row.set_selected(match server_type {
             Some(ServerType::Rdp) => Self::SELECTED_IDX_RDP,
             Some(ServerType::Spice) => Self::SELECTED_IDX_SPICE,
@@ -166,4 +747,40 @@ impl GenericGroupServerPreferences {
         };
         self.set_server_type(server_type.to_string());
     }
+
+    #[template_callback]
+    fn on_add_jump_host_clicked(&self) {
+        self.add_jump_host(None);
+    }
+
+    #[template_callback]
+    fn on_add_proxy_clicked(&self) {
+        self.add_proxy(None);
+    }
+
+    #[template_callback]
+    fn on_add_port_knock_clicked(&self) {
+        self.add_port_knock(None);
+    }
+
+    #[template_callback]
+    fn on_choose_shared_folder_clicked(&self) {
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move {
+                let file_dialog = gtk::FileDialog::builder()
+                    .title(gettext("Choose Shared Folder"))
+                    .modal(true)
+                    .build();
+                let window = slf.root().and_downcast::<gtk::Window>();
+                let Ok(folder) = file_dialog.select_folder_future(window.as_ref()).await else {
+                    return;
+                };
+                if let Some(path) = folder.path() {
+                    slf.set_shared_folder(path.to_string_lossy().into_owned());
+                }
+            }
+        ));
+    }
 }
@@ -16,11 +16,12 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::num::NonZeroU32;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::anyhow;
 use async_std::task::sleep;
@@ -35,12 +36,18 @@ use secure_string::SecureString;
 use serde::Deserialize;
 use virt::connect::Connect;
 use virt::domain::Domain;
+use virt::network::Network;
 use virt::sys::{
     VIR_CONNECT_LIST_DOMAINS_ACTIVE, VIR_CONNECT_LIST_DOMAINS_INACTIVE,
-    VIR_DOMAIN_DESTROY_GRACEFUL, VIR_DOMAIN_PAUSED, VIR_DOMAIN_REBOOT_ACPI_POWER_BTN,
-    VIR_DOMAIN_SHUTDOWN_ACPI_POWER_BTN, VIR_DOMAIN_START_PAUSED, VIR_DOMAIN_XML_SECURE,
+    VIR_CONNECT_LIST_NETWORKS_ACTIVE, VIR_CONNECT_LIST_NETWORKS_INACTIVE,
+    VIR_DOMAIN_AFFECT_CURRENT, VIR_DOMAIN_DESTROY_GRACEFUL,
+    VIR_DOMAIN_INTERFACE_ADDRESSES_SRC_LEASE, VIR_DOMAIN_MEMORY_STAT_AVAILABLE,
+    VIR_DOMAIN_MEMORY_STAT_RSS, VIR_DOMAIN_MEMORY_STAT_UNUSED, VIR_DOMAIN_PAUSED,
+    VIR_DOMAIN_REBOOT_ACPI_POWER_BTN, VIR_DOMAIN_SHUTDOWN_ACPI_POWER_BTN, VIR_DOMAIN_START_PAUSED,
+    VIR_DOMAIN_XML_SECURE, VIR_IP_ADDR_TYPE_IPV4,
 };
 
+use libfieldmonitor::action_failure::report_action_failure;
 use libfieldmonitor::adapter::rdp::RdpAdapter;
 use libfieldmonitor::adapter::spice::SpiceAdapter;
 use libfieldmonitor::adapter::types::Adapter;
@@ -49,6 +56,10 @@ use libfieldmonitor::adapter::vte_pty::VtePtyAdapter;
 use libfieldmonitor::connection::*;
 use libfieldmonitor::i18n::gettext_f;
 use libfieldmonitor::libexec_path;
+use libfieldmonitor::sftp::browse_files_action;
+
+use crate::media::{choose_media, CdromDrive};
+use crate::network::{LibvirtNetworks, NETWORKS_KEY};
 
 pub const PTY_DRIVER_BIN: &str = "de.capypara.FieldMonitor.PtyDrv.Libvirt";
 
@@ -68,6 +79,17 @@ impl VirtArc<Connect> {
             .map(|domain| VirtArc(self.0.clone(), domain, self.2.clone()))
             .collect::<Vec<_>>())
     }
+
+    pub fn list_all_networks(&self) -> Result<Vec<VirtArc<Network>>, virt::error::Error> {
+        Ok(self
+            .1
+            .list_all_networks(
+                VIR_CONNECT_LIST_NETWORKS_ACTIVE | VIR_CONNECT_LIST_NETWORKS_INACTIVE,
+            )?
+            .into_iter()
+            .map(|network| VirtArc(self.0.clone(), network, self.2.clone()))
+            .collect::<Vec<_>>())
+    }
 }
 
 impl Deref for VirtArc<Domain> {
@@ -78,6 +100,14 @@ impl Deref for VirtArc<Domain> {
     }
 }
 
+impl Deref for VirtArc<Network> {
+    type Target = Network;
+
+    fn deref(&self) -> &Self::Target {
+        &self.1
+    }
+}
+
 impl<T> Drop for VirtArc<T> {
     fn drop(&mut self) {
         if Arc::strong_count(&self.0) == 1 {
@@ -91,12 +121,19 @@ impl<T> Drop for VirtArc<T> {
     }
 }
 
+/// CPU time of a domain in nanoseconds at the time it was sampled.
+type CpuSample = (Instant, u64);
+
 pub struct LibvirtConnection {
     id: String,
     title: String,
     hostname: String,
     connection: VirtArc<Connect>,
     icon: Cow<'static, str>,
+    /// `sftp://` URI of the host, if the connection is tunneled through SSH.
+    sftp_uri: Option<String>,
+    /// The last CPU time sample of each running domain, by UUID.
+    cpu_samples: Arc<Mutex<HashMap<String, CpuSample>>>,
 }
 
 impl LibvirtConnection {
@@ -106,6 +143,7 @@ impl LibvirtConnection {
         uri: &str,
         title: &str,
         icon: Cow<'static, str>,
+        sftp_uri: Option<String>,
     ) -> ConnectionResult<Self> {
         let uri = uri.to_string();
         debug!(
@@ -119,11 +157,28 @@ impl LibvirtConnection {
             hostname: hostname.to_string(),
             connection: VirtArc::new(connection),
             icon,
+            sftp_uri,
+            cpu_samples: Default::default(),
         })
     }
 }
 
-impl Actionable for LibvirtConnection {}
+impl Actionable for LibvirtConnection {
+    fn actions(&self) -> Vec<(Cow<'static, str>, Cow<'static, str>)> {
+        if self.sftp_uri.is_some() {
+            vec![("sftp".into(), gettext("Browse Files (SFTP)").into())]
+        } else {
+            vec![]
+        }
+    }
+
+    fn action<'a>(&self, action_id: &str) -> Option<ServerAction<'a>> {
+        match action_id {
+            "sftp" => Some(browse_files_action(self.sftp_uri.clone()?)),
+            _ => None,
+        }
+    }
+}
 
 impl Connection for LibvirtConnection {
     fn metadata(&self) -> ConnectionMetadata {
@@ -145,28 +200,49 @@ impl Connection for LibvirtConnection {
             let mut servers: ServerMap = stream::iter(domains.into_iter())
                 .then(|domain| {
                     let hostname_cln = hostname.clone();
+                    let cpu_samples = self.cpu_samples.clone();
                     async move {
                         let domain_cln = domain.clone();
-                        let (domain_id, name, is_active) = run_in_thread(move || {
-                            let domain_id = domain_cln.get_uuid()?;
-                            let name = domain_cln
-                                .get_name()
-                                .unwrap_or_else(|_| gettext("(Unable to load server name)"));
-                            let is_active = domain_cln.is_active().ok();
-                            let is_paused = domain_cln
-                                .get_state()
-                                .map(|(s, _)| s == VIR_DOMAIN_PAUSED)
-                                .unwrap_or_default();
-                            Ok((domain_id, name, is_active.map(|ia| ia && !is_paused)))
-                        })
-                        .await?
-                        .map_err(virt_err)?;
-                        let bx: Box<dyn ServerConnection> = Box::new(LibvirtServer::new(
+                        let (domain_id, name, is_active, usage, ip_address) =
+                            run_in_thread(move || {
+                                let domain_id = domain_cln.get_uuid()?;
+                                let name = domain_cln
+                                    .get_name()
+                                    .unwrap_or_else(|_| gettext("(Unable to load server name)"));
+                                let is_active = domain_cln.is_active().ok();
+                                let is_paused = domain_cln
+                                    .get_state()
+                                    .map(|(s, _)| s == VIR_DOMAIN_PAUSED)
+                                    .unwrap_or_default();
+                                let usage = if is_active.unwrap_or_default() && !is_paused {
+                                    domain_usage(&domain_cln, &domain_id.to_string(), &cpu_samples)
+                                } else {
+                                    cpu_samples.lock().unwrap().remove(&domain_id.to_string());
+                                    vec![]
+                                };
+                                let ip_address = if is_active.unwrap_or_default() {
+                                    lease_address(&domain_cln)
+                                } else {
+                                    None
+                                };
+                                Ok((
+                                    domain_id,
+                                    name,
+                                    is_active.map(|ia| ia && !is_paused),
+                                    usage,
+                                    ip_address,
+                                ))
+                            })
+                            .await?
+                            .map_err(virt_err)?;
+                        let bx: Box<dyn ServerConnection + Send> = Box::new(LibvirtServer::new(
                             &hostname_cln,
                             domain,
                             self.id.clone(),
                             name,
                             is_active,
+                            usage,
+                            ip_address,
                         ));
                         Ok((Cow::Owned(domain_id.to_string()), bx))
                     }
@@ -176,6 +252,10 @@ impl Connection for LibvirtConnection {
 
             servers.sort_by_cached_key(|_, srv| srv.metadata().title);
 
+            if let Some(networks) = LibvirtNetworks::load(self.connection.clone()).await {
+                servers.insert(NETWORKS_KEY.into(), Box::new(networks));
+            }
+
             Ok(servers)
         })
     }
@@ -235,9 +315,35 @@ impl_deserialize_for_internally_tagged_enum! {
     (_ => Other),
 }
 
+#[derive(Debug, Deserialize)]
+struct LibvirtXmlDiskSource {
+    #[serde(rename = "@file", default)]
+    file: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LibvirtXmlDiskTarget {
+    #[serde(rename = "@dev")]
+    dev: String,
+    #[serde(rename = "@bus", default)]
+    bus: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LibvirtXmlDisk {
+    #[serde(rename = "@device", default)]
+    device: Option<String>,
+    #[serde(default)]
+    source: Option<LibvirtXmlDiskSource>,
+    target: LibvirtXmlDiskTarget,
+}
+
 #[derive(Debug, Deserialize)]
 struct LibvirtXmlDevices {
+    #[serde(default)]
     graphics: Vec<LibvirtXmlGraphics>,
+    #[serde(default)]
+    disk: Vec<LibvirtXmlDisk>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -251,6 +357,8 @@ pub struct LibvirtServer {
     connection_name: String,
     name: String,
     graphics: LibvirtGraphics,
+    usage: Vec<UsageIndicator>,
+    ip_address: Option<String>,
 }
 
 impl LibvirtServer {
@@ -260,6 +368,8 @@ impl LibvirtServer {
         connection_name: String,
         name: String,
         is_active: Option<bool>,
+        usage: Vec<UsageIndicator>,
+        ip_address: Option<String>,
     ) -> Self {
         Self {
             graphics: if is_active.unwrap_or(true) {
@@ -271,6 +381,8 @@ impl LibvirtServer {
             connection_name,
             name,
             is_active,
+            usage,
+            ip_address,
         }
     }
 
@@ -339,16 +451,19 @@ impl LibvirtServer {
 
 impl Actionable for LibvirtServer {
     fn actions(&self) -> Vec<(Cow<'static, str>, Cow<'static, str>)> {
-        if self.is_active.unwrap_or_default() {
-            vec![
-                ("pmreboot".into(), gettext("Reboot").into()),
-                ("pmshutdown".into(), gettext("Shutdown").into()),
-                ("reset".into(), gettext("Force Reset").into()),
-                ("poweroff".into(), gettext("Force Poweroff").into()),
-            ]
-        } else {
-            vec![("start".into(), gettext("Start / Resume").into())]
-        }
+        let mut actions: Vec<(Cow<'static, str>, Cow<'static, str>)> =
+            if self.is_active.unwrap_or_default() {
+                vec![
+                    ("pmreboot".into(), gettext("Reboot").into()),
+                    ("pmshutdown".into(), gettext("Shutdown").into()),
+                    ("reset".into(), gettext("Force Reset").into()),
+                    ("poweroff".into(), gettext("Force Poweroff").into()),
+                ]
+            } else {
+                vec![("start".into(), gettext("Start / Resume").into())]
+            };
+        actions.push(("media".into(), gettext("Change CD/DVD Media…").into()));
+        actions
     }
 
     fn action<'a>(&self, action_id: &str) -> Option<ServerAction<'a>> {
@@ -358,6 +473,7 @@ impl Actionable for LibvirtServer {
             "reset" => Some(self.act_reset()),
             "poweroff" => Some(self.act_poweroff()),
             "start" => Some(self.act_start()),
+            "media" => Some(self.act_media()),
             _ => None,
         }
     }
@@ -508,6 +624,77 @@ impl LibvirtServer {
         )
     }
 
+    fn act_media<'a>(&self) -> ServerAction<'a> {
+        let name = self.name.clone();
+        ServerAction::new(
+            Box::new(self.domain.clone()),
+            Box::new(move |params, window, toov| {
+                let name = name.clone();
+                Box::pin(async move {
+                    let domain = params.downcast::<VirtArc<Domain>>().unwrap();
+                    let show_toast = |text: String| {
+                        if let Some(toov) = &toov {
+                            toov.add_toast(adw::Toast::builder().title(text).timeout(5).build());
+                        }
+                    };
+                    let show_failure_toast = |text: String| {
+                        report_action_failure(&text);
+                        show_toast(text);
+                    };
+
+                    let domain_cln = (*domain).clone();
+                    let drives = match run_in_thread(move || cdrom_drives(&domain_cln)).await {
+                        Ok(Ok(drives)) => drives,
+                        Ok(Err(err)) => {
+                            show_failure_toast(gettext_f(
+                                "Failed to load drives of domain: {err}",
+                                &[("err", &err)],
+                            ));
+                            return false;
+                        }
+                        Err(err) => {
+                            error!("failed to load drives of domain: {err:?}");
+                            show_failure_toast(gettext("Failed to load drives of domain."));
+                            return false;
+                        }
+                    };
+                    if drives.is_empty() {
+                        show_toast(gettext("This domain has no CD/DVD drives."));
+                        return false;
+                    }
+
+                    let Some((index, change)) = choose_media(&name, &drives, window.as_ref()).await
+                    else {
+                        return false;
+                    };
+                    let xml = drives[index].device_xml(change.source());
+                    debug!("updating device of {name}: {xml}");
+
+                    let domain_cln = (*domain).clone();
+                    match run_in_thread(move || {
+                        domain_cln.update_device_flags(&xml, VIR_DOMAIN_AFFECT_CURRENT)
+                    })
+                    .await
+                    {
+                        Ok(Ok(_)) => show_toast(match change.source() {
+                            Some(_) => gettext("Media successfully inserted."),
+                            None => gettext("Media successfully ejected."),
+                        }),
+                        Ok(Err(err)) => show_failure_toast(gettext_f(
+                            "Failed to change media: {err}",
+                            &[("err", err.message())],
+                        )),
+                        Err(err) => {
+                            error!("failed to change media: {err:?}");
+                            show_failure_toast(gettext("Failed to change media."));
+                        }
+                    }
+                    false
+                })
+            }),
+        )
+    }
+
     async fn exec_cmd<F, S>(
         should_be_running: bool,
         domain: &VirtArc<Domain>,
@@ -551,6 +738,9 @@ impl LibvirtServer {
             )
         });
 
+        if !success {
+            report_action_failure(&text);
+        }
         if let Some(toov) = toov {
             toov.add_toast(adw::Toast::builder().title(&text).timeout(5).build());
         }
@@ -563,6 +753,9 @@ impl ServerConnection for LibvirtServer {
         ServerMetadataBuilder::default()
             .title(self.name.clone())
             .is_online(self.is_active)
+            .usage(self.usage.clone())
+            .host(self.ip_address.clone())
+            .ip_address(self.ip_address.clone())
             .build()
             .unwrap()
     }
@@ -666,11 +859,95 @@ impl ServerConnection for LibvirtServer {
     }
 }
 
-fn virt_err(error: virt::error::Error) -> ConnectionError {
+/// The address the DHCP server of the host network leased to the domain, if any. IPv4 addresses
+/// are preferred. Domains on networks not managed by libvirt have no leases.
+fn lease_address(domain: &Domain) -> Option<String> {
+    let interfaces = match domain.interface_addresses(VIR_DOMAIN_INTERFACE_ADDRESSES_SRC_LEASE, 0) {
+        Ok(interfaces) => interfaces,
+        Err(err) => {
+            debug!("failed to load DHCP leases of domain: {err}");
+            return None;
+        }
+    };
+    let addresses = interfaces
+        .into_iter()
+        .flat_map(|interface| interface.addrs)
+        .collect::<Vec<_>>();
+    addresses
+        .iter()
+        .find(|address| address.typed == VIR_IP_ADDR_TYPE_IPV4 as i64)
+        .or_else(|| addresses.first())
+        .map(|address| address.addr.clone())
+}
+
+/// Samples the resource usage of a running domain. The CPU usage is the share of the virtual
+/// CPUs of the domain used since the previous sample, so it is only known from the second
+/// sample on.
+fn domain_usage(
+    domain: &Domain,
+    domain_id: &str,
+    cpu_samples: &Mutex<HashMap<String, CpuSample>>,
+) -> Vec<UsageIndicator> {
+    let info = match domain.get_info() {
+        Ok(info) => info,
+        Err(err) => {
+            debug!("failed to get info of domain {domain_id}: {err}");
+            return vec![];
+        }
+    };
+    let mut usage = Vec::with_capacity(2);
+
+    let now = Instant::now();
+    let previous = cpu_samples
+        .lock()
+        .unwrap()
+        .insert(domain_id.to_string(), (now, info.cpu_time));
+    if let Some((sampled_at, cpu_time)) = previous {
+        let elapsed = now.duration_since(sampled_at).as_nanos() as f64;
+        let vcpus = f64::from(info.nr_virt_cpu.max(1));
+        if elapsed > 0.0 && info.cpu_time >= cpu_time {
+            usage.push(UsageIndicator {
+                label: gettext("CPU"),
+                fraction: (info.cpu_time - cpu_time) as f64 / (elapsed * vcpus),
+            });
+        }
+    }
+
+    // Prefer what the guest reports through its balloon driver, the memory the process
+    // occupies on the host is only an upper bound.
+    let stats = domain.memory_stats(0).unwrap_or_default();
+    let stat = |tag| {
+        stats
+            .iter()
+            .find(|stat| stat.tag == tag)
+            .map(|stat| stat.val as f64)
+    };
+    let memory = match (
+        stat(VIR_DOMAIN_MEMORY_STAT_AVAILABLE),
+        stat(VIR_DOMAIN_MEMORY_STAT_UNUSED),
+    ) {
+        (Some(available), Some(unused)) if available > 0.0 => {
+            Some((available - unused) / available)
+        }
+        _ => stat(VIR_DOMAIN_MEMORY_STAT_RSS)
+            .filter(|_| info.max_mem > 0)
+            .map(|rss| rss / info.max_mem as f64),
+    };
+    if let Some(fraction) = memory {
+        usage.push(UsageIndicator {
+            label: gettext("Memory"),
+            fraction,
+        });
+    }
+
+    usage
+}
+
+pub(crate) fn virt_err(error: virt::error::Error) -> ConnectionError {
     ConnectionError::General(Some(error.message().to_string()), error.into())
 }
 
-async fn run_in_thread<F, T>(task: F) -> ConnectionResult<T>
+pub(crate) async fn run_in_thread<F, T>(task: F) -> ConnectionResult<T>
 where
     F: (FnOnce() -> T) + Send + 'static,
     T: Send + 'static,
@@ -684,6 +961,24 @@ where
         .map_err(|e| ConnectionError::General(None, e.into()))
 }
 
+fn cdrom_drives(domain: &Domain) -> Result<Vec<CdromDrive>, String> {
+    let xml_str = domain
+        .get_xml_desc(0)
+        .map_err(|err| err.message().to_string())?;
+    let xml: LibvirtXmlDomain = from_str(&xml_str).map_err(|err| err.to_string())?;
+    Ok(xml
+        .devices
+        .disk
+        .into_iter()
+        .filter(|disk| disk.device.as_deref() == Some("cdrom"))
+        .map(|disk| CdromDrive {
+            target: disk.target.dev,
+            bus: disk.target.bus,
+            source: disk.source.and_then(|source| source.file),
+        })
+        .collect())
+}
+
 fn get_port(graphics: &LibvirtXmlGraphics) -> Result<NonZeroU32, String> {
     let port_opt = match graphics {
         LibvirtXmlGraphics::Vnc { port, .. } => port,
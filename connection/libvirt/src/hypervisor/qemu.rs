@@ -20,6 +20,8 @@ use adw::prelude::*;
 use futures::future::LocalBoxFuture;
 use gettextrs::gettext;
 use libfieldmonitor::connection::*;
+use libfieldmonitor::host::{parse_host_port, HostPort};
+use libfieldmonitor::sftp::sftp_uri;
 use std::borrow::Cow;
 use std::convert::Infallible;
 
@@ -125,12 +127,22 @@ impl ConnectionProvider for LibvirtQemuConnectionProvider {
                     &Self::build_uri(&configuration),
                     configuration.title().unwrap_or_default(),
                     "connection-libvirt-qemu-symbolic".into(),
+                    Self::build_sftp_uri(&configuration),
                 )
                 .await?,
             );
             Ok(conn)
         })
     }
+
+    fn supports_multiple_sessions(&self) -> bool {
+        // QEMU's SPICE server disconnects the previous client when a new one connects.
+        false
+    }
+
+    fn supports_status_polling(&self) -> bool {
+        true
+    }
 }
 
 const SSH_OPTS: &str = "?no_tty=1";
@@ -167,4 +179,16 @@ impl LibvirtQemuConnectionProvider {
 
         format!("qemu{suffix}://{ssh_part}{session_type}{params}")
     }
+
+    /// The `sftp://` URI of the host libvirt is reached at over SSH, if SSH is used.
+    fn build_sftp_uri(configuration: &ConnectionConfiguration) -> Option<String> {
+        if !configuration.use_ssh() {
+            return None;
+        }
+        let user = Some(configuration.ssh_username());
+        Some(match parse_host_port(configuration.ssh_hostname()) {
+            Ok(HostPort { host, port }) => sftp_uri(user, &host, port),
+            Err(_) => sftp_uri(user, configuration.ssh_hostname(), None),
+        })
+    }
 }
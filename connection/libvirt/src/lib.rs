@@ -19,4 +19,6 @@ pub use hypervisor::*;
 
 mod connection;
 mod hypervisor;
+mod media;
+mod network;
 mod qemu_preferences;
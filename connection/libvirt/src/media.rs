@@ -0,0 +1,189 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Changing the media of the CD/DVD drives of a domain.
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk::{gio, glib};
+use libfieldmonitor::i18n::gettext_f;
+use quick_xml::escape::escape;
+
+#[derive(Debug, Clone)]
+pub(crate) struct CdromDrive {
+    /// Name of the drive in the guest, eg. `sda`.
+    pub target: String,
+    pub bus: Option<String>,
+    /// Path of the inserted image on the host, if any.
+    pub source: Option<String>,
+}
+
+impl CdromDrive {
+    fn label(&self) -> String {
+        match &self.source {
+            Some(source) => gettext_f(
+                "{drive} ({image})",
+                &[
+                    ("drive", &self.target),
+                    ("image", source.rsplit('/').next().unwrap_or(source)),
+                ],
+            ),
+            None => gettext_f("{drive} (empty)", &[("drive", &self.target)]),
+        }
+    }
+
+    /// The device XML for this drive with the image at `source` inserted, or no image if `None`.
+    pub fn device_xml(&self, source: Option<&str>) -> String {
+        let source = source
+            .map(|source| format!("<source file=\"{}\"/>", escape(source)))
+            .unwrap_or_default();
+        let bus = self
+            .bus
+            .as_deref()
+            .map(|bus| format!(" bus=\"{}\"", escape(bus)))
+            .unwrap_or_default();
+        format!(
+            "<disk type=\"file\" device=\"cdrom\">{source}<target dev=\"{}\"{bus}/><readonly/></disk>",
+            escape(&self.target)
+        )
+    }
+}
+
+pub(crate) enum MediaChange {
+    Insert(String),
+    Eject,
+}
+
+impl MediaChange {
+    pub fn source(&self) -> Option<&str> {
+        match self {
+            MediaChange::Insert(path) => Some(path),
+            MediaChange::Eject => None,
+        }
+    }
+}
+
+/// Asks the user which image to insert into which of `drives`, or which drive to eject.
+/// Returns the index of the drive and the change to make.
+pub(crate) async fn choose_media(
+    domain_name: &str,
+    drives: &[CdromDrive],
+    window: Option<&gtk::Window>,
+) -> Option<(usize, MediaChange)> {
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+
+    let drives = drives.to_vec();
+    let labels = drives.iter().map(CdromDrive::label).collect::<Vec<_>>();
+    let drive_row = adw::ComboRow::builder()
+        .title(gettext("Drive"))
+        .model(&gtk::StringList::new(
+            &labels.iter().map(String::as_str).collect::<Vec<_>>(),
+        ))
+        .build();
+    list.append(&drive_row);
+
+    let path_row = adw::EntryRow::builder()
+        .title(gettext("Image path on the host"))
+        .build();
+    let browse_button = gtk::Button::builder()
+        .icon_name("document-open-symbolic")
+        .tooltip_text(gettext("Choose Image…"))
+        .valign(gtk::Align::Center)
+        .css_classes(["flat"])
+        .build();
+    path_row.add_suffix(&browse_button);
+    list.append(&path_row);
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext_f("CD/DVD Media of {domain}", &[("domain", domain_name)]))
+        .body(gettext(
+            "The image must be accessible by the hypervisor. For remote connections, enter its path on the remote host.",
+        ))
+        .extra_child(&list)
+        .close_response("cancel")
+        .default_response("insert")
+        .build();
+    dialog.add_responses(&[
+        ("cancel", &gettext("Cancel")),
+        ("eject", &gettext("Eject")),
+        ("insert", &gettext("Insert")),
+    ]);
+    dialog.set_response_appearance("insert", adw::ResponseAppearance::Suggested);
+    dialog.set_response_enabled("insert", false);
+
+    let update_responses = glib::clone!(
+        #[weak]
+        dialog,
+        #[weak]
+        path_row,
+        #[weak]
+        drive_row,
+        #[strong]
+        drives,
+        move || {
+            let has_media = drives
+                .get(drive_row.selected() as usize)
+                .is_some_and(|drive| drive.source.is_some());
+            dialog.set_response_enabled("eject", has_media);
+            dialog.set_response_enabled("insert", !path_row.text().trim().is_empty());
+        }
+    );
+    update_responses();
+    drive_row.connect_selected_notify({
+        let update_responses = update_responses.clone();
+        move |_| update_responses()
+    });
+    path_row.connect_changed(move |_| update_responses());
+
+    browse_button.connect_clicked(glib::clone!(
+        #[weak]
+        path_row,
+        move |button| {
+            let filter = gtk::FileFilter::new();
+            filter.set_name(Some(&gettext("Disk Images")));
+            filter.add_suffix("iso");
+            let filters = gio::ListStore::new::<gtk::FileFilter>();
+            filters.append(&filter);
+            let file_dialog = gtk::FileDialog::builder()
+                .title(gettext("Choose Image"))
+                .filters(&filters)
+                .modal(true)
+                .build();
+            let window = button.root().and_downcast::<gtk::Window>();
+            glib::spawn_future_local(async move {
+                if let Ok(file) = file_dialog.open_future(window.as_ref()).await {
+                    if let Some(path) = file.path() {
+                        path_row.set_text(&path.to_string_lossy());
+                    }
+                }
+            });
+        }
+    ));
+
+    let response = dialog.choose_future(window).await;
+    let index = drive_row.selected() as usize;
+    match response.as_str() {
+        "insert" => Some((
+            index,
+            MediaChange::Insert(path_row.text().trim().to_string()),
+        )),
+        "eject" => Some((index, MediaChange::Eject)),
+        _ => None,
+    }
+}
@@ -0,0 +1,494 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Virtual networks of the host, listed as a group of their own below the domains.
+//!
+//! The lease view shows the addresses the DHCP server of a network leased to the domains
+//! attached to it, which is how the address of a domain is usually found.
+use std::borrow::Cow;
+
+use adw::prelude::*;
+use anyhow::anyhow;
+use futures::future::LocalBoxFuture;
+use gettextrs::gettext;
+use log::{debug, error};
+use quick_xml::de::from_str;
+use serde::Deserialize;
+use virt::connect::Connect;
+use virt::network::Network;
+use virt::sys::VIR_DOMAIN_INTERFACE_ADDRESSES_SRC_LEASE;
+
+use libfieldmonitor::action_failure::report_action_failure;
+use libfieldmonitor::adapter::types::Adapter;
+use libfieldmonitor::connection::*;
+use libfieldmonitor::i18n::gettext_f;
+
+use crate::connection::{run_in_thread, virt_err, VirtArc};
+
+/// Key of the group of networks in the server list of a connection. Domains are listed by UUID,
+/// so it can not collide with them.
+pub(crate) const NETWORKS_KEY: &str = "networks";
+
+#[derive(Debug, Deserialize)]
+struct DomainXmlInterfaceMac {
+    #[serde(rename = "@address")]
+    address: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DomainXmlInterfaceSource {
+    #[serde(rename = "@network", default)]
+    network: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DomainXmlInterface {
+    #[serde(default)]
+    mac: Option<DomainXmlInterfaceMac>,
+    #[serde(default)]
+    source: Option<DomainXmlInterfaceSource>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DomainXmlDevices {
+    #[serde(default)]
+    interface: Vec<DomainXmlInterface>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DomainXml {
+    devices: DomainXmlDevices,
+}
+
+/// An address leased to an interface of a domain.
+#[derive(Debug, Clone)]
+struct Lease {
+    domain: String,
+    mac: String,
+    addresses: Vec<String>,
+}
+
+/// Group of the virtual networks of the host.
+pub(crate) struct LibvirtNetworks {
+    networks: Vec<LibvirtNetwork>,
+}
+
+impl LibvirtNetworks {
+    /// Lists the networks of the host. Returns `None` if it has none, or if they can not be
+    /// listed, eg. because the network driver is not available for the connection.
+    pub async fn load(connection: VirtArc<Connect>) -> Option<Self> {
+        let networks = match run_in_thread(move || {
+            connection
+                .list_all_networks()
+                .map_err(virt_err)?
+                .into_iter()
+                .map(|network| {
+                    let name = network.get_name().map_err(virt_err)?;
+                    Ok(LibvirtNetwork {
+                        is_active: network.is_active().ok(),
+                        autostart: network.get_autostart().unwrap_or_default(),
+                        bridge: network.get_bridge_name().ok(),
+                        connection: connection.clone(),
+                        network,
+                        name,
+                    })
+                })
+                .collect::<ConnectionResult<Vec<_>>>()
+        })
+        .await
+        {
+            Ok(Ok(networks)) => networks,
+            Ok(Err(err)) | Err(err) => {
+                debug!("failed to list networks: {err:?}");
+                return None;
+            }
+        };
+        (!networks.is_empty()).then_some(Self { networks })
+    }
+}
+
+impl Actionable for LibvirtNetworks {}
+
+impl ServerConnection for LibvirtNetworks {
+    fn metadata(&self) -> ServerMetadata {
+        ServerMetadataBuilder::default()
+            .title(gettext("Networks"))
+            .icon(IconSpec::Named("network-workgroup-symbolic".into()))
+            .build()
+            .unwrap()
+    }
+
+    fn supported_adapters(&self) -> Vec<(Cow<str>, Cow<str>)> {
+        vec![]
+    }
+
+    fn create_adapter(&self, _tag: &str) -> LocalBoxFuture<ConnectionResult<Box<dyn Adapter>>> {
+        Box::pin(async move {
+            Err(ConnectionError::General(
+                None,
+                anyhow!("networks can not be connected to"),
+            ))
+        })
+    }
+
+    fn servers(&self) -> LocalBoxFuture<ConnectionResult<ServerMap>> {
+        Box::pin(async move {
+            Ok(self
+                .networks
+                .iter()
+                .map(|network| {
+                    let bx: Box<dyn ServerConnection + Send> = Box::new(network.clone());
+                    (Cow::Owned(network.name.clone()), bx)
+                })
+                .collect())
+        })
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct LibvirtNetwork {
+    connection: VirtArc<Connect>,
+    network: VirtArc<Network>,
+    name: String,
+    is_active: Option<bool>,
+    autostart: bool,
+    bridge: Option<String>,
+}
+
+impl Actionable for LibvirtNetwork {
+    fn actions(&self) -> Vec<(Cow<'static, str>, Cow<'static, str>)> {
+        let mut actions: Vec<(Cow<'static, str>, Cow<'static, str>)> =
+            if self.is_active.unwrap_or_default() {
+                vec![
+                    ("netleases".into(), gettext("DHCP Leases…").into()),
+                    ("netstop".into(), gettext("Stop").into()),
+                ]
+            } else {
+                vec![("netstart".into(), gettext("Start").into())]
+            };
+        actions.push(if self.autostart {
+            ("netautostart".into(), gettext("Disable Autostart").into())
+        } else {
+            ("netautostart".into(), gettext("Enable Autostart").into())
+        });
+        actions
+    }
+
+    fn action<'a>(&self, action_id: &str) -> Option<ServerAction<'a>> {
+        match action_id {
+            "netleases" => Some(self.act_leases()),
+            "netstart" => Some(self.act_set_active(true)),
+            "netstop" => Some(self.act_set_active(false)),
+            "netautostart" => Some(self.act_set_autostart(!self.autostart)),
+            _ => None,
+        }
+    }
+}
+
+impl LibvirtNetwork {
+    fn act_set_active<'a>(&self, active: bool) -> ServerAction<'a> {
+        ServerAction::new(
+            Box::new(self.network.clone()),
+            Box::new(move |params, _window, toov| {
+                Box::pin(async move {
+                    let network = params.downcast::<VirtArc<Network>>().unwrap();
+                    Self::exec_cmd(
+                        &network,
+                        move |network| {
+                            if active {
+                                network.create().map(|_| ())
+                            } else {
+                                network.destroy()
+                            }
+                        },
+                        move || {
+                            if active {
+                                gettext("Network started.")
+                            } else {
+                                gettext("Network stopped.")
+                            }
+                        },
+                        move |err| {
+                            if active {
+                                gettext_f(
+                                    "Failed to start the network: {err}",
+                                    &[("err", err.message())],
+                                )
+                            } else {
+                                gettext_f(
+                                    "Failed to stop the network: {err}",
+                                    &[("err", err.message())],
+                                )
+                            }
+                        },
+                        toov.as_ref(),
+                    )
+                    .await
+                })
+            }),
+        )
+    }
+
+    fn act_set_autostart<'a>(&self, autostart: bool) -> ServerAction<'a> {
+        ServerAction::new(
+            Box::new(self.network.clone()),
+            Box::new(move |params, _window, toov| {
+                Box::pin(async move {
+                    let network = params.downcast::<VirtArc<Network>>().unwrap();
+                    Self::exec_cmd(
+                        &network,
+                        move |network| network.set_autostart(autostart).map(|_| ()),
+                        move || {
+                            if autostart {
+                                gettext("The network now starts with the host.")
+                            } else {
+                                gettext("The network no longer starts with the host.")
+                            }
+                        },
+                        |err| {
+                            gettext_f(
+                                "Failed to change autostart of the network: {err}",
+                                &[("err", err.message())],
+                            )
+                        },
+                        toov.as_ref(),
+                    )
+                    .await
+                })
+            }),
+        )
+    }
+
+    /// Shows the addresses leased to the domains attached to the network.
+    fn act_leases<'a>(&self) -> ServerAction<'a> {
+        ServerAction::new(
+            Box::new((self.connection.clone(), self.name.clone())),
+            Box::new(|params, window, toov| {
+                Box::pin(async move {
+                    let (connection, name) =
+                        *params.downcast::<(VirtArc<Connect>, String)>().unwrap();
+                    let name_cln = name.clone();
+                    match run_in_thread(move || leases(&connection, &name_cln)).await {
+                        Ok(Ok(leases)) => show_leases(&name, &leases, window.as_ref()).await,
+                        Ok(Err(err)) => show_failure_toast(
+                            toov.as_ref(),
+                            gettext_f(
+                                "Failed to load the DHCP leases: {err}",
+                                &[("err", err.message())],
+                            ),
+                        ),
+                        Err(err) => {
+                            error!("failed to load DHCP leases: {err:?}");
+                            show_failure_toast(
+                                toov.as_ref(),
+                                gettext("Failed to load the DHCP leases."),
+                            );
+                        }
+                    }
+                    false
+                })
+            }),
+        )
+    }
+
+    /// Runs `cmd` and shows the resulting message. Returns whether the connection should be
+    /// reloaded.
+    async fn exec_cmd<F>(
+        network: &VirtArc<Network>,
+        cmd: F,
+        success_msg: impl (Fn() -> String) + Send + 'static,
+        err_msg: impl (Fn(virt::error::Error) -> String) + Send + 'static,
+        toov: Option<&adw::ToastOverlay>,
+    ) -> bool
+    where
+        F: (FnOnce(&VirtArc<Network>) -> Result<(), virt::error::Error>) + Send + 'static,
+    {
+        let network = network.clone();
+        let (success, text) = run_in_thread(move || match cmd(&network) {
+            Ok(()) => (true, success_msg()),
+            Err(err) => (false, err_msg(err)),
+        })
+        .await
+        .unwrap_or_else(|e| {
+            error!("Internal error running action: {e}");
+            (
+                false,
+                gettext("Internal error while trying to execute command."),
+            )
+        });
+        if success {
+            show_toast(toov, text);
+        } else {
+            show_failure_toast(toov, text);
+        }
+        success
+    }
+}
+
+impl ServerConnection for LibvirtNetwork {
+    fn metadata(&self) -> ServerMetadata {
+        let mut info_lines = Vec::with_capacity(2);
+        if let Some(bridge) = &self.bridge {
+            info_lines.push(gettext_f("Bridge: {bridge}", &[("bridge", bridge)]));
+        }
+        if self.autostart {
+            info_lines.push(gettext("Starts with the host"));
+        }
+        ServerMetadataBuilder::default()
+            .title(self.name.clone())
+            .info_lines(info_lines)
+            .is_online(self.is_active)
+            .icon(IconSpec::Named("network-wired-symbolic".into()))
+            .build()
+            .unwrap()
+    }
+
+    fn supported_adapters(&self) -> Vec<(Cow<str>, Cow<str>)> {
+        vec![]
+    }
+
+    fn create_adapter(&self, _tag: &str) -> LocalBoxFuture<ConnectionResult<Box<dyn Adapter>>> {
+        Box::pin(async move {
+            Err(ConnectionError::General(
+                None,
+                anyhow!("networks can not be connected to"),
+            ))
+        })
+    }
+}
+
+fn show_toast(toov: Option<&adw::ToastOverlay>, text: String) {
+    if let Some(toov) = toov {
+        toov.add_toast(adw::Toast::builder().title(text).timeout(5).build());
+    }
+}
+
+/// Like [`show_toast`], for messages that tell that the action failed.
+fn show_failure_toast(toov: Option<&adw::ToastOverlay>, text: String) {
+    report_action_failure(&text);
+    show_toast(toov, text);
+}
+
+/// The leases of the running domains with interfaces on the network `network_name`.
+fn leases(
+    connection: &VirtArc<Connect>,
+    network_name: &str,
+) -> Result<Vec<Lease>, virt::error::Error> {
+    let mut leases = Vec::new();
+    for domain in connection.list_all_domains()? {
+        if !domain.is_active().unwrap_or_default() {
+            continue;
+        }
+        let xml = match from_str::<DomainXml>(&domain.get_xml_desc(0)?) {
+            Ok(xml) => xml,
+            Err(err) => {
+                debug!("failed to parse domain XML: {err}");
+                continue;
+            }
+        };
+        let macs = xml
+            .devices
+            .interface
+            .into_iter()
+            .filter(|interface| {
+                interface
+                    .source
+                    .as_ref()
+                    .and_then(|source| source.network.as_deref())
+                    == Some(network_name)
+            })
+            .filter_map(|interface| Some(interface.mac?.address.to_lowercase()))
+            .collect::<Vec<_>>();
+        if macs.is_empty() {
+            continue;
+        }
+
+        let domain_name = domain.get_name()?;
+        let interfaces = domain
+            .interface_addresses(VIR_DOMAIN_INTERFACE_ADDRESSES_SRC_LEASE, 0)
+            .unwrap_or_default();
+        for mac in macs {
+            let addresses = interfaces
+                .iter()
+                .filter(|interface| interface.hwaddr.to_lowercase() == mac)
+                .flat_map(|interface| interface.addrs.iter())
+                .map(|address| address.addr.clone())
+                .collect();
+            leases.push(Lease {
+                domain: domain_name.clone(),
+                mac,
+                addresses,
+            });
+        }
+    }
+    leases.sort_by(|a, b| a.domain.cmp(&b.domain));
+    Ok(leases)
+}
+
+async fn show_leases(network_name: &str, leases: &[Lease], window: Option<&gtk::Window>) {
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+    for lease in leases {
+        let row = adw::ActionRow::builder()
+            .title(&lease.domain)
+            .use_markup(false)
+            .build();
+        if lease.addresses.is_empty() {
+            row.set_subtitle(&gettext_f("{mac} · No lease", &[("mac", &lease.mac)]));
+        } else {
+            let addresses = lease.addresses.join(", ");
+            row.set_subtitle(&gettext_f(
+                "{mac} · {addresses}",
+                &[("mac", &lease.mac), ("addresses", &addresses)],
+            ));
+            let copy_button = gtk::Button::builder()
+                .icon_name("edit-copy-symbolic")
+                .tooltip_text(gettext("Copy Address"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            let address = lease.addresses[0].clone();
+            copy_button.connect_clicked(move |button| button.clipboard().set_text(&address));
+            row.add_suffix(&copy_button);
+        }
+        list.append(&row);
+    }
+    let scrolled_window = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .max_content_height(400)
+        .propagate_natural_height(true)
+        .child(&list)
+        .build();
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext_f(
+            "DHCP Leases of {network}",
+            &[("network", network_name)],
+        ))
+        .close_response("close")
+        .default_response("close")
+        .build();
+    if leases.is_empty() {
+        dialog.set_body(&gettext("No running domain is attached to this network."));
+    } else {
+        dialog.set_extra_child(Some(&scrolled_window));
+    }
+    dialog.add_response("close", &gettext("Close"));
+    dialog.choose_future(window).await;
+}
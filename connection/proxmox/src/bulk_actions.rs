@@ -0,0 +1,240 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Starting and shutting down all guests of a node at once, using the bulk actions of Proxmox.
+//! Those respect the start order and delays configured for the guests.
+use std::sync::Arc;
+use std::time::Duration;
+
+use adw::prelude::*;
+use async_std::task::sleep;
+use gettextrs::gettext;
+use libfieldmonitor::connection::ConnectionResult;
+use libfieldmonitor::i18n::gettext_f;
+use proxmox_api::{
+    NodeId, NodeStartallInput, NodeStopallInput, ProxmoxApiClient, TaskRunState, TaskStatus,
+    VmListEntry, VmStatus, VmType,
+};
+
+use crate::map_proxmox_error;
+use crate::tokiort::run_on_tokio;
+
+/// Interval in which the status of a running task is checked.
+const TASK_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Bulk actions wait for each guest in turn, so they may take a long time. The task is no
+/// longer followed after this many checks.
+const TASK_POLL_LIMIT: u32 = 900;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BulkAction {
+    StartAll,
+    ShutdownAll,
+}
+
+impl BulkAction {
+    /// Whether guests with `status` are affected by the action.
+    fn affects(self, status: VmStatus) -> bool {
+        match self {
+            BulkAction::StartAll => status == VmStatus::Stopped,
+            BulkAction::ShutdownAll => status == VmStatus::Running,
+        }
+    }
+}
+
+/// The guests of `node` that the action would affect, ordered by ID.
+pub async fn affected_guests(
+    client: Arc<ProxmoxApiClient>,
+    node: NodeId,
+    action: BulkAction,
+) -> ConnectionResult<Vec<VmListEntry>> {
+    run_on_tokio(async move {
+        let mut guests = Vec::new();
+        for vm_type in [VmType::Lxc, VmType::Qemu] {
+            guests.extend(
+                client
+                    .node_vms_brief(&node, vm_type)
+                    .await
+                    .map_err(map_proxmox_error)?
+                    .into_iter()
+                    .filter(|vm| action.affects(vm.status)),
+            );
+        }
+        guests.sort_unstable_by_key(|vm| vm.vmid.clone());
+        Ok(guests)
+    })
+    .await
+}
+
+/// Asks the user to confirm the action for the listed `guests`. Returns whether guests without
+/// the `onboot` option should be started as well, or `None` if the user cancelled.
+pub async fn confirm(
+    node: &NodeId,
+    action: BulkAction,
+    guests: &[VmListEntry],
+    window: Option<&gtk::Window>,
+) -> Option<bool> {
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+    for guest in guests {
+        list.append(
+            &adw::ActionRow::builder()
+                .title(guest.vmid.to_string())
+                .subtitle(guest.name.clone().unwrap_or_default())
+                .use_markup(false)
+                .build(),
+        );
+    }
+    let scrolled_window = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .max_content_height(300)
+        .propagate_natural_height(true)
+        .child(&list)
+        .build();
+    let content = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(12)
+        .build();
+    content.append(&scrolled_window);
+
+    let node = node.to_string();
+    let (heading, body, response) = match action {
+        BulkAction::StartAll => (
+            gettext_f("Start Guests of {node}?", &[("node", &node)]),
+            gettext(
+                "The guests are started in their configured start order. Only guests with “Start at boot” enabled are started, unless all guests are included below.",
+            ),
+            gettext("Start"),
+        ),
+        BulkAction::ShutdownAll => (
+            gettext_f("Shut Down Guests of {node}?", &[("node", &node)]),
+            gettext(
+                "The guests are shut down in the reverse of their configured start order. Guests that do not shut down in time are stopped.",
+            ),
+            gettext("Shut Down"),
+        ),
+    };
+    let force_row = adw::SwitchRow::builder()
+        .title(gettext("Include All Guests"))
+        .subtitle(gettext(
+            "Also start guests that do not have “Start at boot” enabled",
+        ))
+        .build();
+    if action == BulkAction::StartAll {
+        let force_list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .build();
+        force_list.append(&force_row);
+        content.append(&force_list);
+    }
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(heading)
+        .body(body)
+        .extra_child(&content)
+        .close_response("cancel")
+        .default_response("cancel")
+        .build();
+    dialog.add_responses(&[("cancel", &gettext("Cancel")), ("confirm", &response)]);
+    dialog.set_response_appearance(
+        "confirm",
+        match action {
+            BulkAction::StartAll => adw::ResponseAppearance::Suggested,
+            BulkAction::ShutdownAll => adw::ResponseAppearance::Destructive,
+        },
+    );
+
+    if dialog.choose_future(window).await.as_str() != "confirm" {
+        return None;
+    }
+    Some(action == BulkAction::StartAll && force_row.is_active())
+}
+
+/// Runs the action for `guests` and returns the UPID of the task.
+pub async fn run(
+    client: Arc<ProxmoxApiClient>,
+    node: NodeId,
+    action: BulkAction,
+    guests: &[VmListEntry],
+    force: bool,
+) -> ConnectionResult<String> {
+    let vms = Some(
+        guests
+            .iter()
+            .map(|vm| vm.vmid.to_string())
+            .collect::<Vec<_>>()
+            .join(","),
+    );
+    run_on_tokio(async move {
+        match action {
+            BulkAction::StartAll => {
+                client
+                    .node_startall(
+                        &node,
+                        NodeStartallInput {
+                            force: force.then_some(1),
+                            vms,
+                        },
+                    )
+                    .await
+            }
+            BulkAction::ShutdownAll => {
+                client
+                    .node_stopall(
+                        &node,
+                        NodeStopallInput {
+                            vms,
+                            ..Default::default()
+                        },
+                    )
+                    .await
+            }
+        }
+        .map_err(map_proxmox_error)
+    })
+    .await
+}
+
+/// Waits until the task with `upid` stopped. Returns `None` if it is still running after
+/// the poll limit.
+pub async fn wait_for_task(
+    client: Arc<ProxmoxApiClient>,
+    node: NodeId,
+    upid: String,
+) -> ConnectionResult<Option<TaskStatus>> {
+    for _ in 0..TASK_POLL_LIMIT {
+        let status = run_on_tokio({
+            let client = client.clone();
+            let node = node.clone();
+            let upid = upid.clone();
+            async move {
+                client
+                    .node_task_status(&node, &upid)
+                    .await
+                    .map_err(map_proxmox_error)
+            }
+        })
+        .await?;
+        if status.status == TaskRunState::Stopped {
+            return Ok(Some(status));
+        }
+        sleep(TASK_POLL_INTERVAL).await;
+    }
+    Ok(None)
+}
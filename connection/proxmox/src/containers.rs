@@ -0,0 +1,117 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Dialogs and helpers for LXC containers and their templates.
+use adw::prelude::*;
+use gettextrs::gettext;
+use libfieldmonitor::i18n::gettext_f;
+
+/// Name of a distribution as configured in the `ostype` of a container.
+pub fn os_type_title(os_type: &str) -> String {
+    match os_type {
+        "alpine" => "Alpine Linux".into(),
+        "archlinux" => "Arch Linux".into(),
+        "centos" => "CentOS".into(),
+        "debian" => "Debian".into(),
+        "devuan" => "Devuan".into(),
+        "fedora" => "Fedora".into(),
+        "gentoo" => "Gentoo".into(),
+        "nixos" => "NixOS".into(),
+        "opensuse" => "openSUSE".into(),
+        "ubuntu" => "Ubuntu".into(),
+        "unmanaged" => gettext("Unmanaged"),
+        other => other.to_string(),
+    }
+}
+
+/// How a container is cloned from a template.
+pub struct CloneOptions {
+    /// Hostname of the new container. If `None`, Proxmox derives it from the template.
+    pub hostname: Option<String>,
+    /// Whether all disks are copied. Otherwise a linked clone is created, which depends on the
+    /// template.
+    pub full: bool,
+}
+
+/// Asks the user how to clone a container from the template `template_title`. Returns `None`
+/// if the dialog was cancelled.
+pub async fn ask_clone_options(
+    template_title: &str,
+    window: Option<&gtk::Window>,
+) -> Option<CloneOptions> {
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+    let hostname_row = adw::EntryRow::builder()
+        .title(gettext("Hostname (Optional)"))
+        .build();
+    list.append(&hostname_row);
+    let full_row = adw::SwitchRow::builder()
+        .title(gettext("Full Clone"))
+        .subtitle(gettext(
+            "Copies all disks. Linked clones are faster to create, but depend on the template.",
+        ))
+        .build();
+    list.append(&full_row);
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext_f(
+            "Clone {template}",
+            &[("template", template_title)],
+        ))
+        .body(gettext(
+            "A new container is created from the template, using the next free ID of the cluster.",
+        ))
+        .extra_child(&list)
+        .close_response("cancel")
+        .default_response("clone")
+        .build();
+    dialog.add_responses(&[("cancel", &gettext("Cancel")), ("clone", &gettext("Clone"))]);
+    dialog.set_response_appearance("clone", adw::ResponseAppearance::Suggested);
+
+    if dialog.choose_future(window).await.as_str() != "clone" {
+        return None;
+    }
+    let hostname = hostname_row.text().trim().to_string();
+    Some(CloneOptions {
+        hostname: (!hostname.is_empty()).then_some(hostname),
+        full: full_row.is_active(),
+    })
+}
+
+/// Asks the user to confirm converting the container `vm_title` into a template.
+pub async fn confirm_convert_to_template(vm_title: &str, window: Option<&gtk::Window>) -> bool {
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext_f(
+            "Convert {vm} to a Template?",
+            &[("vm", vm_title)],
+        ))
+        .body(gettext(
+            "Templates can not be started anymore, only cloned. This can not be undone.",
+        ))
+        .close_response("cancel")
+        .default_response("cancel")
+        .build();
+    dialog.add_responses(&[
+        ("cancel", &gettext("Cancel")),
+        ("convert", &gettext("Convert")),
+    ]);
+    dialog.set_response_appearance("convert", adw::ResponseAppearance::Destructive);
+
+    dialog.choose_future(window).await.as_str() == "convert"
+}
@@ -0,0 +1,187 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Dialogs for changing the CD/DVD media and the boot device of QEMU VMs.
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+use libfieldmonitor::i18n::gettext_f;
+use proxmox_api::VmDeviceKind;
+
+/// Name of a volume without its storage and content type, eg. `debian.iso` for
+/// `local:iso/debian.iso`.
+fn volume_title(volume: &str) -> &str {
+    volume
+        .rsplit_once('/')
+        .or_else(|| volume.rsplit_once(':'))
+        .map(|(_, name)| name)
+        .unwrap_or(volume)
+}
+
+fn boxed_list() -> gtk::ListBox {
+    gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build()
+}
+
+fn combo_row(title: &str, items: &[String]) -> adw::ComboRow {
+    adw::ComboRow::builder()
+        .title(title)
+        .model(&gtk::StringList::new(
+            &items.iter().map(String::as_str).collect::<Vec<_>>(),
+        ))
+        .build()
+}
+
+/// Asks the user which ISO image of `images` to insert into which of `drives`, or which drive
+/// to empty. Returns the drive and the volume to insert, which is `None` for ejecting.
+pub async fn choose_media(
+    vm_title: &str,
+    drives: &[(String, Option<String>)],
+    images: &[String],
+    window: Option<&gtk::Window>,
+) -> Option<(String, Option<String>)> {
+    let list = boxed_list();
+    let drive_row = combo_row(
+        &gettext("Drive"),
+        &drives
+            .iter()
+            .map(|(drive, volume)| match volume {
+                Some(volume) => gettext_f(
+                    "{drive} ({image})",
+                    &[("drive", drive), ("image", volume_title(volume))],
+                ),
+                None => gettext_f("{drive} (empty)", &[("drive", drive)]),
+            })
+            .collect::<Vec<_>>(),
+    );
+    list.append(&drive_row);
+    let image_row = combo_row(
+        &gettext("Image"),
+        &images
+            .iter()
+            .map(|volume| volume_title(volume).to_string())
+            .collect::<Vec<_>>(),
+    );
+    image_row.set_subtitle(&gettext("ISO images on the storages of the node"));
+    image_row.set_sensitive(!images.is_empty());
+    list.append(&image_row);
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext_f("CD/DVD Media of {vm}", &[("vm", vm_title)]))
+        .extra_child(&list)
+        .close_response("cancel")
+        .default_response("insert")
+        .build();
+    if images.is_empty() {
+        dialog.set_body(&gettext(
+            "No ISO images were found on the storages of the node.",
+        ));
+    }
+    dialog.add_responses(&[
+        ("cancel", &gettext("Cancel")),
+        ("eject", &gettext("Eject")),
+        ("insert", &gettext("Insert")),
+    ]);
+    dialog.set_response_appearance("insert", adw::ResponseAppearance::Suggested);
+    dialog.set_response_enabled("insert", !images.is_empty());
+
+    let has_media = drives
+        .iter()
+        .map(|(_, volume)| volume.is_some())
+        .collect::<Vec<_>>();
+    let update_eject = glib::clone!(
+        #[weak]
+        dialog,
+        move |drive_row: &adw::ComboRow| {
+            dialog.set_response_enabled(
+                "eject",
+                has_media
+                    .get(drive_row.selected() as usize)
+                    .copied()
+                    .unwrap_or_default(),
+            );
+        }
+    );
+    update_eject(&drive_row);
+    drive_row.connect_selected_notify(update_eject);
+
+    let response = dialog.choose_future(window).await;
+    let (drive, _) = drives.get(drive_row.selected() as usize)?;
+    match response.as_str() {
+        "insert" => Some((
+            drive.clone(),
+            Some(images.get(image_row.selected() as usize)?.clone()),
+        )),
+        "eject" => Some((drive.clone(), None)),
+        _ => None,
+    }
+}
+
+/// Asks the user which kind of device of `kinds` the VM should boot from first. If `running` is
+/// set, the user may also choose to reset the VM to apply the change immediately.
+/// Returns the chosen kind and whether to reset the VM.
+pub async fn choose_boot_device(
+    vm_title: &str,
+    kinds: &[VmDeviceKind],
+    current: Option<VmDeviceKind>,
+    running: bool,
+    window: Option<&gtk::Window>,
+) -> Option<(VmDeviceKind, bool)> {
+    let list = boxed_list();
+    let kind_row = combo_row(
+        &gettext("Boot From"),
+        &kinds
+            .iter()
+            .map(|kind| match kind {
+                VmDeviceKind::Disk => gettext("Hard Disk"),
+                VmDeviceKind::Cdrom => gettext("CD/DVD Drive"),
+                VmDeviceKind::Network => gettext("Network (PXE)"),
+            })
+            .collect::<Vec<_>>(),
+    );
+    if let Some(position) = current.and_then(|current| kinds.iter().position(|k| *k == current)) {
+        kind_row.set_selected(position as u32);
+    }
+    list.append(&kind_row);
+    let reset_row = adw::SwitchRow::builder()
+        .title(gettext("Reset VM"))
+        .subtitle(gettext(
+            "The boot order only takes effect on the next start. Reset the VM to apply it now.",
+        ))
+        .visible(running)
+        .build();
+    list.append(&reset_row);
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext_f("Boot Device of {vm}", &[("vm", vm_title)]))
+        .extra_child(&list)
+        .close_response("cancel")
+        .default_response("save")
+        .build();
+    dialog.add_responses(&[("cancel", &gettext("Cancel")), ("save", &gettext("Save"))]);
+    dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+
+    if dialog.choose_future(window).await.as_str() != "save" {
+        return None;
+    }
+    Some((
+        *kinds.get(kind_row.selected() as usize)?,
+        running && reset_row.is_active(),
+    ))
+}
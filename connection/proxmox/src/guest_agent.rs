@@ -0,0 +1,278 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Running commands in QEMU guests and reading their IP addresses using the QEMU guest agent.
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use adw::prelude::*;
+use futures::{stream, StreamExt};
+use gettextrs::gettext;
+use gtk::glib;
+use libfieldmonitor::i18n::gettext_f;
+use log::debug;
+use proxmox_api::{AgentExecStatus, AgentNetworkInterface, NodeId, ProxmoxApiClient, VmId};
+use tokio::time::{sleep, timeout};
+
+use crate::tokiort::tkruntime;
+use crate::CONSOLE_PROXY_BATCH_SIZE;
+
+/// Time to wait for the agent of a guest to report its network interfaces. Agents that are
+/// enabled but not running only fail after a long timeout.
+const INTERFACES_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Time after which commands are no longer waited for.
+const EXEC_TIMEOUT: Duration = Duration::from_secs(60);
+
+const EXEC_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// IP addresses reported by the guest agents of the QEMU guests of a connection. Guests without
+/// a responding agent have no entry.
+///
+/// Querying agents can take long, so the cache is refreshed in the background when the guests
+/// of a node are listed, and the addresses are shown the next time the guests are listed.
+#[derive(Default)]
+pub struct GuestAgentCache {
+    addresses: BTreeMap<VmId, Vec<String>>,
+    /// Nodes whose guests are currently being refreshed.
+    refreshing: HashSet<String>,
+}
+
+impl GuestAgentCache {
+    /// IP addresses of the guest, or `None` if its agent did not respond.
+    pub fn addresses(&self, vm_id: &VmId) -> Option<&[String]> {
+        self.addresses.get(vm_id).map(Vec::as_slice)
+    }
+
+    /// Queries the agents of the running QEMU guests `running` of a node in the background.
+    /// Entries of the other guests of the node, `stopped`, are removed.
+    pub fn refresh(
+        cache: &Arc<Mutex<Self>>,
+        client: Arc<ProxmoxApiClient>,
+        node_id: NodeId,
+        running: Vec<VmId>,
+        stopped: Vec<VmId>,
+    ) {
+        {
+            let mut cache = cache.lock().unwrap();
+            for vm_id in &stopped {
+                cache.addresses.remove(vm_id);
+            }
+            if !cache.refreshing.insert(node_id.to_string()) {
+                return;
+            }
+        }
+        let cache = cache.clone();
+        tkruntime().spawn(async move {
+            let results = stream::iter(running)
+                .map(|vm_id| {
+                    let client = &client;
+                    let node_id = &node_id;
+                    async move {
+                        let result = timeout(
+                            INTERFACES_TIMEOUT,
+                            client.qemu_vm_agent_network_interfaces(node_id, &vm_id),
+                        )
+                        .await;
+                        (vm_id, result)
+                    }
+                })
+                .buffer_unordered(CONSOLE_PROXY_BATCH_SIZE)
+                .collect::<Vec<_>>()
+                .await;
+
+            let mut cache = cache.lock().unwrap();
+            for (vm_id, result) in results {
+                match result {
+                    Ok(Ok(interfaces)) => {
+                        cache.addresses.insert(vm_id, addresses(&interfaces));
+                    }
+                    Ok(Err(err)) => {
+                        debug!("guest agent of {vm_id} did not report interfaces: {err}");
+                        cache.addresses.remove(&vm_id);
+                    }
+                    Err(_) => {
+                        debug!("guest agent of {vm_id} timed out");
+                        cache.addresses.remove(&vm_id);
+                    }
+                }
+            }
+            cache.refreshing.remove(&node_id.to_string());
+        });
+    }
+}
+
+/// Addresses of all interfaces except loopback and link-local ones.
+fn addresses(interfaces: &[AgentNetworkInterface]) -> Vec<String> {
+    interfaces
+        .iter()
+        .flat_map(|interface| &interface.ip_addresses)
+        .map(|address| address.ip_address.as_str())
+        .filter(|address| {
+            !address.starts_with("127.") && *address != "::1" && !address.starts_with("fe80:")
+        })
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Runs `command` in the guest and waits for it to exit.
+pub async fn run_command(
+    client: Arc<ProxmoxApiClient>,
+    node_id: NodeId,
+    vm_id: VmId,
+    command: Vec<String>,
+) -> proxmox_api::Result<Option<AgentExecStatus>> {
+    let pid = client
+        .qemu_vm_agent_exec(&node_id, &vm_id, &command)
+        .await?;
+    let started = Instant::now();
+    while started.elapsed() < EXEC_TIMEOUT {
+        let status = client
+            .qemu_vm_agent_exec_status(&node_id, &vm_id, pid)
+            .await?;
+        if status.exited.unwrap_or_default() {
+            return Ok(Some(status));
+        }
+        sleep(EXEC_POLL_INTERVAL).await;
+    }
+    Ok(None)
+}
+
+/// Asks the user for a command to run in the guest.
+pub async fn ask_command(vm_title: &str, window: Option<&gtk::Window>) -> Option<String> {
+    let command_row = adw::EntryRow::builder()
+        .title(gettext("Command"))
+        .activates_default(true)
+        .build();
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+    list.append(&command_row);
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext_f("Run Command in {vm}", &[("vm", vm_title)]))
+        .body(gettext(
+            "The command is run by the QEMU guest agent, usually with administrator privileges. It is not run in a shell.",
+        ))
+        .extra_child(&list)
+        .close_response("cancel")
+        .default_response("run")
+        .build();
+    dialog.add_responses(&[("cancel", &gettext("Cancel")), ("run", &gettext("Run"))]);
+    dialog.set_response_appearance("run", adw::ResponseAppearance::Suggested);
+    dialog.set_response_enabled("run", false);
+    command_row.connect_changed(glib::clone!(
+        #[weak]
+        dialog,
+        move |row| dialog.set_response_enabled("run", !row.text().trim().is_empty())
+    ));
+
+    if dialog.choose_future(window).await.as_str() != "run" {
+        return None;
+    }
+    Some(command_row.text().trim().to_string())
+}
+
+/// Splits a command into the program and its arguments, using shell quoting rules.
+pub fn parse_command(command: &str) -> Result<Vec<String>, glib::Error> {
+    Ok(glib::shell_parse_argv(command)?
+        .into_iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Shows the exit code and output of a command.
+pub async fn show_output(vm_title: &str, status: &AgentExecStatus, window: Option<&gtk::Window>) {
+    let body = match (status.exitcode, status.signal) {
+        (_, Some(signal)) => gettext_f(
+            "The command was terminated by signal {signal}.",
+            &[("signal", &signal.to_string())],
+        ),
+        (Some(code), None) => gettext_f(
+            "The command exited with code {code}.",
+            &[("code", &code.to_string())],
+        ),
+        (None, None) => gettext("The command exited."),
+    };
+
+    let content = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(6)
+        .build();
+    for (title, data, truncated) in [
+        (
+            gettext("Output"),
+            &status.out_data,
+            status.out_truncated.unwrap_or_default(),
+        ),
+        (
+            gettext("Errors"),
+            &status.err_data,
+            status.err_truncated.unwrap_or_default(),
+        ),
+    ] {
+        let Some(data) = data.as_deref().filter(|data| !data.is_empty()) else {
+            continue;
+        };
+        let title = if truncated {
+            gettext_f("{title} (truncated)", &[("title", &title)])
+        } else {
+            title
+        };
+        content.append(
+            &gtk::Label::builder()
+                .label(title)
+                .xalign(0.0)
+                .css_classes(["heading"])
+                .build(),
+        );
+        content.append(&output_view(data));
+    }
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext_f("Command Output of {vm}", &[("vm", vm_title)]))
+        .body(body)
+        .extra_child(&content)
+        .close_response("close")
+        .default_response("close")
+        .build();
+    dialog.add_response("close", &gettext("Close"));
+    dialog.choose_future(window).await;
+}
+
+fn output_view(data: &str) -> gtk::ScrolledWindow {
+    let text_view = gtk::TextView::builder()
+        .editable(false)
+        .monospace(true)
+        .wrap_mode(gtk::WrapMode::WordChar)
+        .top_margin(12)
+        .bottom_margin(12)
+        .left_margin(12)
+        .right_margin(12)
+        .build();
+    text_view.buffer().set_text(data);
+    gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .min_content_height(100)
+        .max_content_height(300)
+        .propagate_natural_height(true)
+        .child(&text_view)
+        .css_classes(["card"])
+        .build()
+}
@@ -16,42 +16,85 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::future::Future;
-use std::mem;
 use std::num::NonZeroU32;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use crate::bulk_actions::{self, BulkAction};
+use crate::containers::{ask_clone_options, confirm_convert_to_template, os_type_title};
 use crate::credential_preferences::ProxmoxCredentialPreferences;
-use crate::preferences::{ProxmoxConfiguration, ProxmoxPreferences};
+use crate::devices::{choose_boot_device, choose_media};
+use crate::guest_agent::{ask_command, parse_command, run_command, show_output, GuestAgentCache};
+use crate::node_summary::show_node_summary;
+use crate::preferences::{ProxmoxConfiguration, ProxmoxPreferences, SpiceRoute};
 use crate::tokiort::{run_on_tokio, tkruntime};
-use adw::prelude::Cast;
+use adw::prelude::*;
 use anyhow::anyhow;
 use async_std::task::sleep;
 use futures::future::LocalBoxFuture;
+use futures::{stream, StreamExt};
 use gettextrs::gettext;
 use gtk::Widget;
 use http::Uri;
+use libfieldmonitor::action_failure::report_action_failure;
 use libfieldmonitor::adapter::spice::{SpiceAdapter, SpiceSessionConfigBuilder};
 use libfieldmonitor::adapter::types::Adapter;
 use libfieldmonitor::adapter::vnc::VncAdapter;
 use libfieldmonitor::adapter::vte_pty::VtePtyAdapter;
+use libfieldmonitor::adapter::web::WebAdapter;
 use libfieldmonitor::connection::*;
+use libfieldmonitor::connection_file::{save_connection_file, VvFile};
+use libfieldmonitor::i18n::{format_duration, gettext_f};
 use libfieldmonitor::libexec_path;
-use log::{error, warn};
+use libfieldmonitor::net::{http_client, NetConfig, TlsVerification};
+use libfieldmonitor::password_command::run_password_command;
+use libfieldmonitor::resolve::check_reachable;
+use log::{debug, error, warn};
 use proxmox_api::{
-    NodeId, NodeStatus, ProxmoxApiClient, Spiceproxy, Termproxy, VmConsoleProxyType, VmId,
-    VmStatus, VmType, Vncproxy,
+    ClientConfig, Credentials, HaRequestState, HaServiceState, NodeId, NodeStatus,
+    ProxmoxApiClient, QmpStatus, Spiceproxy, Termproxy, VmConsoleProxyType, VmDeviceKind, VmId,
+    VmListEntry, VmLock, VmStatus, VmSuspendInput, VmType, VmVncproxyInput, Vncproxy,
 };
 use secure_string::SecureString;
+use tokio::time::timeout;
 
+mod bulk_actions;
+mod containers;
 mod credential_preferences;
+mod devices;
+mod guest_agent;
+mod node_summary;
 mod preferences;
 mod tokiort;
 
 pub const PTY_DRIVER_BIN: &str = "de.capypara.FieldMonitor.PtyDrv.Proxmox";
 
+/// Actions changing the status of a guest directly. Guests managed by the HA manager are only
+/// started and stopped through it.
+const VM_STATUS_ACTIONS: &[&str] = &[
+    "vmreboot",
+    "vmshutdown",
+    "vmreset",
+    "vmstop",
+    "vmstart",
+    "vmsuspend",
+    "vmhibernate",
+];
+
+/// Number of guests for which the supported console proxies are fetched at once.
+const CONSOLE_PROXY_BATCH_SIZE: usize = 8;
+
+/// Number of container configurations that are fetched at once to find out their distribution.
+const CONTAINER_CONFIG_CONCURRENCY: usize = 8;
+
+/// Time to wait for the guests of a node. Nodes that are down are often still listed as online
+/// by the cluster for a while, and requests proxied to them only fail after a long timeout.
+const NODE_GUESTS_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct ProxmoxConnectionProviderConstructor;
 
 impl ConnectionProviderConstructor for ProxmoxConnectionProviderConstructor {
@@ -161,67 +204,119 @@ struct ProxmoxConnection {
     connection_id: String,
     title: String,
     client: Arc<ProxmoxApiClient>,
+    generate_vnc_password: bool,
+    spice_route: SpiceRoute,
+    container_console_user: Option<String>,
+    version: Option<String>,
+    cluster_name: Option<String>,
+    guest_agents: Arc<Mutex<GuestAgentCache>>,
+    container_os_types: ContainerOsTypes,
+    /// Set if the credentials only allow viewing. Power actions and consoles are then hidden.
+    read_only: bool,
+}
+
+/// The root URL of the API, which is also passed on to the PTY driver for terminals.
+fn api_root(config: &ConnectionConfiguration) -> Result<Uri, http::Error> {
+    let authority = format!(
+        "{}:{}",
+        config.hostname().unwrap_or_default(),
+        config.port().map(NonZeroU32::get).unwrap_or(8006)
+    );
+    let path = config.api_path().unwrap_or("/api2/json").trim();
+
+    Uri::builder()
+        .scheme(if config.use_https() { "https" } else { "http" })
+        .authority(authority)
+        .path_and_query(if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("/{path}")
+        })
+        .build()
 }
 
 impl ProxmoxConnection {
     async fn connect(config: ConnectionConfiguration) -> ConnectionResult<Self> {
-        let authority = format!(
-            "{}:{}",
-            config.hostname().unwrap_or_default(),
-            config.port().map(NonZeroU32::get).unwrap_or(8006)
-        );
-
-        let api_root = Uri::builder()
-            .scheme("https")
-            .authority(authority)
-            .path_and_query("/api2/json")
-            .build()
-            .map_err(|err| {
-                ConnectionError::General(
-                    Some(gettext(
-                        "Was unable to build a valid URL to connect to. Check your settings.",
-                    )),
-                    anyhow!(err),
-                )
-            })?;
+        let api_root = api_root(&config).map_err(|err| {
+            ConnectionError::General(
+                Some(gettext(
+                    "Was unable to build a valid URL to connect to. Check your settings.",
+                )),
+                anyhow!(err),
+            )
+        })?;
 
-        let pass = config
-            .password_or_apikey()
-            .await
-            .map_err(|err| {
-                ConnectionError::General(
-                    Some(gettext(
-                        "Failed to retrieve API Key or Password from secrets service.",
-                    )),
-                    anyhow!(err),
+        let pass = match config.password_command() {
+            Some(command) => {
+                let user = if config.use_apikey() {
+                    config.tokenid()
+                } else {
+                    config.username()
+                };
+                run_password_command(
+                    command,
+                    &[
+                        ("user", user.unwrap_or_default()),
+                        ("host", config.hostname().unwrap_or_default()),
+                    ],
                 )
-            })?
-            .unwrap_or_else(|| SecureString::from_str("").unwrap());
-
-        let client = if config.use_apikey() {
-            ProxmoxApiClient::connect_with_apikey(
-                &api_root,
-                config.tokenid().unwrap_or_default(),
-                pass,
-                config.ignore_ssl_cert_error(),
-            )
-            .await
-            .map_err(map_proxmox_error)
+                .await?
+            }
+            None => config
+                .password_or_apikey()
+                .await
+                .map_err(|err| {
+                    ConnectionError::General(
+                        Some(gettext(
+                            "Failed to retrieve API Key or Password from secrets service.",
+                        )),
+                        anyhow!(err),
+                    )
+                })?
+                .unwrap_or_else(|| SecureString::from_str("").unwrap()),
+        };
+
+        let credentials = if config.use_apikey() {
+            Credentials::Apikey {
+                tokenid: config.tokenid().unwrap_or_default().to_string(),
+                apikey: pass,
+            }
         } else {
-            ProxmoxApiClient::connect_with_ticket(
-                &api_root,
-                config.username().unwrap_or_default(),
-                pass,
-                config.ignore_ssl_cert_error(),
-            )
-            .await
-            .map_err(map_proxmox_error)
-        }?;
+            Credentials::Ticket {
+                user: config.username().unwrap_or_default().to_string(),
+                password: pass,
+            }
+        };
+        let http_client = http_client(&NetConfig::default().with_tls(
+            TlsVerification::from_ignore_errors(config.ignore_ssl_cert_error()),
+        ))
+        .map_err(|err| ConnectionError::General(None, anyhow!(err)))?;
+        let client = ClientConfig::new(api_root, credentials)
+            .ignore_ssl_errors(config.ignore_ssl_cert_error())
+            .http_client(http_client)
+            .connect()
+            .map_err(map_proxmox_error)?;
+
+        let version = ignore_non_auth_error(client.version().await, "version")?
+            .map(|version| version.version);
+        let cluster_name =
+            ignore_non_auth_error(client.cluster_name().await, "cluster name")?.flatten();
+        // Any action or console would fail with a permission error for auditors.
+        let read_only = ignore_non_auth_error(client.access_permissions().await, "permissions")?
+            .is_some_and(|permissions| permissions.is_audit_only());
 
         Ok(Self {
             connection_id: config.id().to_string(),
             title: config.title().unwrap_or_default().to_string(),
             client: Arc::new(client),
+            generate_vnc_password: config.generate_vnc_password(),
+            spice_route: config.spice_route(),
+            container_console_user: config.container_console_user().map(ToString::to_string),
+            version,
+            cluster_name,
+            guest_agents: Default::default(),
+            container_os_types: Default::default(),
+            read_only,
         })
     }
 }
@@ -230,8 +325,20 @@ impl Actionable for ProxmoxConnection {}
 
 impl Connection for ProxmoxConnection {
     fn metadata(&self) -> ConnectionMetadata {
+        let mut info_lines = Vec::with_capacity(3);
+        if self.read_only {
+            info_lines.push(gettext("Read-only, the credentials only allow viewing"));
+        }
+        if let Some(cluster_name) = &self.cluster_name {
+            info_lines.push(gettext_f("Cluster: {name}", &[("name", cluster_name)]));
+        }
+        if let Some(version) = &self.version {
+            info_lines.push(gettext_f("Proxmox VE {version}", &[("version", version)]));
+        }
+
         ConnectionMetadataBuilder::default()
             .title(self.title.clone())
+            .info_lines(info_lines)
             .icon(IconSpec::Named("connection-proxmox-symbolic".into()))
             .build()
             .unwrap()
@@ -241,8 +348,32 @@ impl Connection for ProxmoxConnection {
         Box::pin(async move {
             let connection_id = self.connection_id.clone();
             let client = self.client.clone();
-            let map = run_on_tokio(async move {
-                let mut server_map = ServerMapSend::default();
+            let generate_vnc_password = self.generate_vnc_password;
+            let spice_route = self.spice_route.clone();
+            let container_console_user = self.container_console_user.clone();
+            let guest_agents = self.guest_agents.clone();
+            let container_os_types = self.container_os_types.clone();
+            let read_only = self.read_only;
+            run_on_tokio(async move {
+                let mut server_map = ServerMap::default();
+
+                let ha_guests = Arc::new(
+                    ignore_non_auth_error(client.cluster_ha_status().await, "HA status")?
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|entry| {
+                            let vm_id = entry.vm_id()?;
+                            Some((
+                                vm_id,
+                                HaGuest {
+                                    sid: entry.sid?,
+                                    state: entry.state.unwrap_or(HaServiceState::Unknown),
+                                    node: entry.node,
+                                },
+                            ))
+                        })
+                        .collect::<BTreeMap<_, _>>(),
+                );
 
                 for node in client.nodes().await.map_err(map_proxmox_error)? {
                     server_map.insert(
@@ -250,20 +381,23 @@ impl Connection for ProxmoxConnection {
                         Box::new(ProxmoxNode {
                             client: client.clone(),
                             connection_id: connection_id.clone(),
+                            generate_vnc_password,
+                            spice_route: spice_route.clone(),
+                            container_console_user: container_console_user.clone(),
+                            guest_agents: guest_agents.clone(),
+                            container_os_types: container_os_types.clone(),
                             id: node.node,
-                            status: NodeStatus::Online,
+                            status: node.status,
+                            unreachable: Arc::default(),
+                            ha_guests: ha_guests.clone(),
+                            read_only,
                         }),
                     );
                 }
 
                 Ok(server_map)
             })
-            .await?;
-
-            // TODO: Is this actually safe?
-            let map_cast: ServerMap = unsafe { mem::transmute(map) };
-
-            Ok(map_cast)
+            .await
         })
     }
 }
@@ -271,14 +405,82 @@ impl Connection for ProxmoxConnection {
 struct ProxmoxNode {
     client: Arc<ProxmoxApiClient>,
     connection_id: String,
+    generate_vnc_password: bool,
+    spice_route: SpiceRoute,
+    container_console_user: Option<String>,
+    guest_agents: Arc<Mutex<GuestAgentCache>>,
+    container_os_types: ContainerOsTypes,
     id: NodeId,
     status: NodeStatus,
+    /// Set if listing the guests of the node failed, eg. because it is in maintenance. The node
+    /// is then shown as offline.
+    unreachable: Arc<AtomicBool>,
+    ha_guests: Arc<BTreeMap<VmId, HaGuest>>,
+    read_only: bool,
+}
+
+/// Distributions of the containers of a connection, `None` if a container does not have one
+/// configured. They rarely change, so they are only fetched when a container is first listed.
+type ContainerOsTypes = Arc<Mutex<BTreeMap<VmId, Option<String>>>>;
+
+/// Fetches the distributions of the containers among `guests` that are not known yet. Failures
+/// are only logged, the distribution is then tried again on the next refresh.
+async fn fetch_container_os_types(
+    client: &ProxmoxApiClient,
+    node_id: &NodeId,
+    guests: &[(VmType, VmListEntry)],
+    os_types: &ContainerOsTypes,
+) {
+    let missing = {
+        let known = os_types.lock().unwrap();
+        guests
+            .iter()
+            .filter(|(vm_type, vm)| matches!(vm_type, VmType::Lxc) && !known.contains_key(&vm.vmid))
+            .map(|(_, vm)| vm.vmid.clone())
+            .collect::<Vec<_>>()
+    };
+    let results = stream::iter(missing)
+        .map(|vm_id| async move {
+            let config = client.vm_config(node_id, &vm_id, Some(VmType::Lxc)).await;
+            (vm_id, config)
+        })
+        .buffer_unordered(CONTAINER_CONFIG_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut known = os_types.lock().unwrap();
+    for (vm_id, config) in results {
+        match config {
+            Ok(config) => {
+                known.insert(vm_id, config.ostype);
+            }
+            Err(err) => debug!("failed to load the configuration of container {vm_id}: {err}"),
+        }
+    }
+}
+
+/// A guest managed by the HA manager of the cluster.
+#[derive(Clone, Debug)]
+struct HaGuest {
+    /// ID of the HA resource, eg. `vm:100`.
+    sid: String,
+    state: HaServiceState,
+    /// The node the HA manager placed the guest on.
+    node: Option<NodeId>,
 }
 
 impl Actionable for ProxmoxNode {
     fn actions(&self) -> Vec<(Cow<'static, str>, Cow<'static, str>)> {
-        if self.status != NodeStatus::Offline {
+        if self.is_available() && self.read_only {
+            vec![("nodesummary".into(), gettext("Summary").into())]
+        } else if self.is_available() {
             vec![
+                ("nodesummary".into(), gettext("Summary").into()),
+                ("nodestartall".into(), gettext("Start All Guests…").into()),
+                (
+                    "nodeshutdownall".into(),
+                    gettext("Shut Down All Guests…").into(),
+                ),
                 ("nodereboot".into(), gettext("Reboot").into()),
                 ("nodeshutdown".into(), gettext("Shutdown").into()),
             ]
@@ -289,6 +491,9 @@ impl Actionable for ProxmoxNode {
 
     fn action<'a>(&self, action_id: &str) -> Option<ServerAction<'a>> {
         match action_id {
+            "nodesummary" => Some(self.act_summary()),
+            "nodestartall" => Some(self.act_bulk(BulkAction::StartAll)),
+            "nodeshutdownall" => Some(self.act_bulk(BulkAction::ShutdownAll)),
             "nodereboot" => Some(self.act_reboot()),
             "nodeshutdown" => Some(self.act_shutdown()),
             _ => None,
@@ -297,6 +502,10 @@ impl Actionable for ProxmoxNode {
 }
 
 impl ProxmoxNode {
+    fn is_available(&self) -> bool {
+        self.status != NodeStatus::Offline && !self.unreachable.load(Ordering::Relaxed)
+    }
+
     fn params(&self) -> ExecParams {
         ExecParams {
             client: self.client.clone(),
@@ -306,6 +515,139 @@ impl ProxmoxNode {
         }
     }
 
+    /// Shows the status, services and available updates of the node.
+    fn act_summary<'a>(&self) -> ServerAction<'a> {
+        ServerAction::new(
+            Box::new(self.params()),
+            Box::new(|params, window, _toov| {
+                Box::pin(async move {
+                    let params = params.downcast::<ExecParams>().unwrap();
+                    show_node_summary(params.client, params.node_id.unwrap(), window.as_ref());
+                    false
+                })
+            }),
+        )
+    }
+
+    /// Starts or shuts down the guests of the node once the user confirmed which guests are
+    /// affected, then follows the task until it is done.
+    fn act_bulk<'a>(&self, action: BulkAction) -> ServerAction<'a> {
+        ServerAction::new(
+            Box::new(self.params()),
+            Box::new(move |params, window, toov| {
+                Box::pin(async move {
+                    let params = params.downcast::<ExecParams>().unwrap();
+                    let client = params.client.clone();
+                    let node_id = params.node_id.unwrap();
+                    let node = node_id.to_string();
+
+                    let guests = match bulk_actions::affected_guests(
+                        client.clone(),
+                        node_id.clone(),
+                        action,
+                    )
+                    .await
+                    {
+                        Ok(guests) => guests,
+                        Err(err) => {
+                            warn!("failed to list guests for bulk action: {err:?}");
+                            show_failure_toast(
+                                toov.as_ref(),
+                                gettext("Failed to load the guests of the node."),
+                            );
+                            return false;
+                        }
+                    };
+                    if guests.is_empty() {
+                        show_toast(
+                            toov.as_ref(),
+                            match action {
+                                BulkAction::StartAll => {
+                                    gettext("All guests of the node are already running.")
+                                }
+                                BulkAction::ShutdownAll => {
+                                    gettext("No guests of the node are running.")
+                                }
+                            },
+                        );
+                        return false;
+                    }
+                    let Some(force) =
+                        bulk_actions::confirm(&node_id, action, &guests, window.as_ref()).await
+                    else {
+                        return false;
+                    };
+
+                    let upid = match bulk_actions::run(
+                        client.clone(),
+                        node_id.clone(),
+                        action,
+                        &guests,
+                        force,
+                    )
+                    .await
+                    {
+                        Ok(upid) => upid,
+                        Err(err) => {
+                            warn!("failed to run bulk action: {err:?}");
+                            show_failure_toast(
+                                toov.as_ref(),
+                                gettext("Failed to send the command."),
+                            );
+                            return false;
+                        }
+                    };
+                    show_toast(
+                        toov.as_ref(),
+                        match action {
+                            BulkAction::StartAll => {
+                                gettext_f("Starting guests of {node}…", &[("node", &node)])
+                            }
+                            BulkAction::ShutdownAll => {
+                                gettext_f("Shutting down guests of {node}…", &[("node", &node)])
+                            }
+                        },
+                    );
+
+                    let text = match bulk_actions::wait_for_task(client, node_id, upid).await {
+                        Ok(Some(status)) if status.is_ok() => match action {
+                            BulkAction::StartAll => {
+                                gettext_f("Guests of {node} were started.", &[("node", &node)])
+                            }
+                            BulkAction::ShutdownAll => gettext_f(
+                                "Guests of {node} were shut down.",
+                                &[("node", &node)],
+                            ),
+                        },
+                        Ok(Some(status)) => {
+                            let text = gettext_f(
+                                "The task on {node} finished with errors: {error}",
+                                &[
+                                    ("node", &node),
+                                    ("error", status.exitstatus.as_deref().unwrap_or_default()),
+                                ],
+                            );
+                            report_action_failure(&text);
+                            text
+                        }
+                        Ok(None) => gettext_f(
+                            "The task on {node} is still running. Check its progress in the web interface.",
+                            &[("node", &node)],
+                        ),
+                        Err(err) => {
+                            warn!("failed to follow bulk action task: {err:?}");
+                            let text = gettext("Failed to check whether the task finished.");
+                            report_action_failure(&text);
+                            text
+                        }
+                    };
+                    show_toast(toov.as_ref(), text);
+                    true
+                })
+            }),
+        )
+    }
+
     fn act_reboot<'a>(&self) -> ServerAction<'a> {
         ServerAction::new(
             Box::new(self.params()),
@@ -362,6 +704,7 @@ impl ProxmoxNode {
 impl ServerConnection for ProxmoxNode {
     fn metadata(&self) -> ServerMetadata {
         let is_online = match self.status {
+            _ if self.unreachable.load(Ordering::Relaxed) => Some(false),
             NodeStatus::Online => Some(true),
             NodeStatus::Offline => Some(false),
             NodeStatus::Unknown => None,
@@ -375,78 +718,208 @@ impl ServerConnection for ProxmoxNode {
     }
 
     fn supported_adapters(&self) -> Vec<(Cow<str>, Cow<str>)> {
-        if self.status == NodeStatus::Offline {
+        if !self.is_available() {
             vec![]
+        } else if self.read_only {
+            vec![(WebAdapter::TAG.into(), WebAdapter::label().into())]
         } else {
             vec![
                 (SpiceAdapter::TAG.into(), gettext("SPICE").into()),
                 (VncAdapter::TAG.into(), gettext("VNC").into()),
                 (VtePtyAdapter::TAG.into(), gettext("Console").into()),
+                (WebAdapter::TAG.into(), WebAdapter::label().into()),
             ]
         }
     }
 
     fn create_adapter(&self, tag: &str) -> LocalBoxFuture<ConnectionResult<Box<dyn Adapter>>> {
+        if tag == WebAdapter::TAG {
+            let url = web_interface_url(&self.client);
+            return Box::pin(async move {
+                let adapter: Box<dyn Adapter> = Box::new(WebAdapter::new(url?));
+                Ok(adapter)
+            });
+        }
         create_proxmox_adapter(
             tag,
             &self.connection_id,
             self.id.as_ref(),
             self.client.clone(),
             ProxmoxEntity::Node(self.id.clone()),
+            false,
+            self.spice_route.clone(),
+            None,
         )
     }
 
     fn servers(&self) -> LocalBoxFuture<ConnectionResult<ServerMap>> {
         Box::pin(async move {
+            // Requests for nodes that are known to be down would only run into a timeout.
+            if self.status == NodeStatus::Offline {
+                let mut server_map = ServerMap::default();
+                server_map.insert(
+                    "error".into(),
+                    Box::new(ProxmoxNodeError {
+                        message: gettext("The node is offline."),
+                    }),
+                );
+                return Ok(server_map);
+            }
+
             let client = self.client.clone();
             let connection_id = self.connection_id.clone();
             let node_id = self.id.clone();
+            let unreachable = self.unreachable.clone();
+            let generate_vnc_password = self.generate_vnc_password;
+            let spice_route = self.spice_route.clone();
+            let container_console_user = self.container_console_user.clone();
+            let guest_agents = self.guest_agents.clone();
+            let container_os_types = self.container_os_types.clone();
+            let ha_guests = self.ha_guests.clone();
+            let read_only = self.read_only;
+
+            run_on_tokio(async move {
+                let mut server_map = ServerMap::default();
+
+                // Only the fields needed for listing are kept, everything else about a guest
+                // is fetched on demand.
+                let fetch_guests = async {
+                    let lxc = client.node_vms_brief(&node_id, VmType::Lxc).await?;
+                    let qemu = client.node_vms_brief(&node_id, VmType::Qemu).await?;
+                    Ok::<_, proxmox_api::Error>(
+                        lxc.into_iter()
+                            .map(|vm| (VmType::Lxc, vm))
+                            .chain(qemu.into_iter().map(|vm| (VmType::Qemu, vm)))
+                            .collect::<Vec<_>>(),
+                    )
+                };
 
-            let map = run_on_tokio(async move {
-                let mut server_map = ServerMapSend::default();
-
-                for vm in client.node_lxc(&node_id).await.map_err(map_proxmox_error)? {
-                    server_map.insert(
-                        vm.vmid.to_string().into(),
-                        Box::new(ProxmoxVm {
-                            client: client.clone(),
-                            connection_id: connection_id.clone(),
-                            node_id: node_id.clone(),
-                            vm_id: vm.vmid,
-                            vm_type: VmType::Lxc,
-                            name: vm.name,
-                            status: vm.status,
-                        }),
-                    );
-                }
+                // A single node that is down must not prevent the rest of the cluster from
+                // being listed. Its error is shown in place of its guests instead. Failed
+                // authentication still fails the listing, so that the user is asked to log in
+                // again.
+                let result = match timeout(NODE_GUESTS_TIMEOUT, fetch_guests).await {
+                    Ok(Ok(guests)) => Ok(guests),
+                    Ok(Err(err @ proxmox_api::Error::AuthFailed)) => {
+                        return Err(map_proxmox_error(err))
+                    }
+                    Ok(Err(err)) => Err(err.to_string()),
+                    Err(_) => Err(gettext("The node did not respond in time.")),
+                };
+                let guests = match result {
+                    Ok(guests) => guests,
+                    Err(message) => {
+                        warn!("failed to list guests of node {node_id}: {message}");
+                        unreachable.store(true, Ordering::Relaxed);
+                        server_map.insert("error".into(), Box::new(ProxmoxNodeError { message }));
+                        return Ok(server_map);
+                    }
+                };
 
-                for vm in client
-                    .node_qemu(&node_id, false)
-                    .await
-                    .map_err(map_proxmox_error)?
-                {
+                let console_proxies = Arc::new(Mutex::new(ConsoleProxyCache::new(
+                    guests
+                        .iter()
+                        .filter(|(_, vm)| vm.status == VmStatus::Running)
+                        .map(|(vm_type, vm)| (vm.vmid.clone(), *vm_type))
+                        .collect(),
+                )));
+
+                let (running_qemu, others) =
+                    guests.iter().partition::<Vec<_>, _>(|(vm_type, vm)| {
+                        matches!(vm_type, VmType::Qemu) && vm.status == VmStatus::Running
+                    });
+                let running_qemu = running_qemu
+                    .into_iter()
+                    .map(|(_, vm)| vm.vmid.clone())
+                    .collect::<Vec<_>>();
+                let others = others
+                    .into_iter()
+                    .map(|(_, vm)| vm.vmid.clone())
+                    .collect::<Vec<_>>();
+
+                // Addresses found by the previous refresh are shown, the refresh runs in the
+                // background.
+                let agent_addresses = {
+                    let cache = guest_agents.lock().unwrap();
+                    running_qemu
+                        .iter()
+                        .filter_map(|vm_id| Some((vm_id.clone(), cache.addresses(vm_id)?.to_vec())))
+                        .collect::<BTreeMap<_, _>>()
+                };
+                GuestAgentCache::refresh(
+                    &guest_agents,
+                    client.clone(),
+                    node_id.clone(),
+                    running_qemu,
+                    others,
+                );
+
+                fetch_container_os_types(&client, &node_id, &guests, &container_os_types).await;
+                let os_types = container_os_types.lock().unwrap().clone();
+
+                for (vm_type, vm) in guests {
+                    let addresses = agent_addresses.get(&vm.vmid).cloned();
+                    let ha = ha_guests.get(&vm.vmid).cloned();
+                    let os_type = os_types.get(&vm.vmid).cloned().flatten();
                     server_map.insert(
                         vm.vmid.to_string().into(),
                         Box::new(ProxmoxVm {
                             client: client.clone(),
                             connection_id: connection_id.clone(),
+                            generate_vnc_password,
+                            spice_route: spice_route.clone(),
+                            container_console_user: container_console_user.clone(),
                             node_id: node_id.clone(),
                             vm_id: vm.vmid,
-                            vm_type: VmType::Qemu,
+                            vm_type,
                             name: vm.name,
                             status: vm.status,
+                            uptime: vm.uptime,
+                            agent_addresses: addresses,
+                            ha,
+                            lock: vm.lock,
+                            template: vm.template.unwrap_or_default(),
+                            os_type,
+                            console_proxies: console_proxies.clone(),
+                            read_only,
                         }),
                     );
                 }
 
                 Ok(server_map)
             })
-            .await?;
+            .await
+        })
+    }
+}
+
+/// Shown in place of the guests of a node that could not be listed.
+struct ProxmoxNodeError {
+    message: String,
+}
 
-            // TODO: Is this actually safe?
-            let map_cast: ServerMap = unsafe { mem::transmute(map) };
+impl Actionable for ProxmoxNodeError {}
 
-            Ok(map_cast)
+impl ServerConnection for ProxmoxNodeError {
+    fn metadata(&self) -> ServerMetadata {
+        ServerMetadataBuilder::default()
+            .title(gettext("Guests could not be loaded"))
+            .subtitle(Some(self.message.clone()))
+            .icon(IconSpec::Named("dialog-error-symbolic".into()))
+            .build()
+            .unwrap()
+    }
+
+    fn supported_adapters(&self) -> Vec<(Cow<str>, Cow<str>)> {
+        vec![]
+    }
+
+    fn create_adapter(&self, _tag: &str) -> LocalBoxFuture<ConnectionResult<Box<dyn Adapter>>> {
+        Box::pin(async move {
+            Err(ConnectionError::General(
+                None,
+                anyhow!(gettext("The node is not reachable.")),
+            ))
         })
     }
 }
@@ -454,47 +927,162 @@ impl ServerConnection for ProxmoxNode {
 struct ProxmoxVm {
     client: Arc<ProxmoxApiClient>,
     connection_id: String,
+    /// Whether VNC consoles use a generated password instead of the API ticket.
+    generate_vnc_password: bool,
+    spice_route: SpiceRoute,
+    /// User that is logged in automatically on the console of a container.
+    container_console_user: Option<String>,
     node_id: NodeId,
     vm_id: VmId,
     vm_type: VmType,
     name: Option<String>,
     status: VmStatus,
+    uptime: Option<i64>,
+    lock: Option<VmLock>,
+    /// Templates can not be started, containers can only be cloned from them.
+    template: bool,
+    /// LXC only: The distribution of the container, if its configuration could be loaded.
+    os_type: Option<String>,
+    /// Set if the guest is managed by the HA manager. It must then be started and stopped by
+    /// requesting a state from the HA manager, not through its status.
+    ha: Option<HaGuest>,
+    /// IP addresses reported by the QEMU guest agent, if it responded.
+    agent_addresses: Option<Vec<String>>,
+    console_proxies: Arc<Mutex<ConsoleProxyCache>>,
+    read_only: bool,
 }
 
 impl Actionable for ProxmoxVm {
     fn actions(&self) -> Vec<(Cow<'static, str>, Cow<'static, str>)> {
-        if self.status == VmStatus::Running {
-            match self.vm_type {
-                VmType::Lxc => vec![
-                    ("vmreboot".into(), gettext("Reboot").into()),
-                    ("vmshutdown".into(), gettext("Shutdown").into()),
-                    ("vmstop".into(), gettext("Force Poweroff").into()),
-                ],
-                VmType::Qemu => vec![
-                    ("vmreboot".into(), gettext("Reboot").into()),
-                    ("vmshutdown".into(), gettext("Shutdown").into()),
-                    ("vmreset".into(), gettext("Force Reset").into()),
-                    ("vmstop".into(), gettext("Force Poweroff").into()),
-                ],
+        if self.read_only {
+            return vec![];
+        }
+        let mut actions: Vec<(Cow<'static, str>, Cow<'static, str>)> =
+            if self.status == VmStatus::Running {
+                match self.vm_type {
+                    VmType::Lxc => vec![
+                        ("vmreboot".into(), gettext("Reboot").into()),
+                        ("vmshutdown".into(), gettext("Shutdown").into()),
+                        ("vmstop".into(), gettext("Force Poweroff").into()),
+                    ],
+                    // A VM suspended to RAM is still running, so it can always be resumed.
+                    VmType::Qemu => vec![
+                        ("vmreboot".into(), gettext("Reboot").into()),
+                        ("vmshutdown".into(), gettext("Shutdown").into()),
+                        ("vmsuspend".into(), gettext("Suspend (RAM)").into()),
+                        ("vmhibernate".into(), gettext("Hibernate (to Disk)").into()),
+                        ("vmstart".into(), gettext("Resume").into()),
+                        ("vmreset".into(), gettext("Force Reset").into()),
+                        ("vmstop".into(), gettext("Force Poweroff").into()),
+                    ],
+                }
+            } else if self.is_hibernated() {
+                vec![("vmstart".into(), gettext("Resume").into())]
+            } else {
+                vec![("vmstart".into(), gettext("Start / Resume").into())]
+            };
+        if let Some(ha) = &self.ha {
+            actions.clear();
+            if ha.state != HaServiceState::Started {
+                actions.push(("vmhastart".into(), gettext("Request HA Start").into()));
             }
-        } else {
-            vec![("vmstart".into(), gettext("Start / Resume").into())]
+            if ha.state != HaServiceState::Stopped {
+                actions.push(("vmhastop".into(), gettext("Request HA Stop").into()));
+            }
+        }
+        if self.template {
+            actions.clear();
+            if matches!(self.vm_type, VmType::Lxc) {
+                actions.push(("lxcclone".into(), gettext("Clone…").into()));
+            }
+        } else if matches!(self.vm_type, VmType::Lxc)
+            && self.status == VmStatus::Stopped
+            && self.ha.is_none()
+        {
+            actions.push(("lxctemplate".into(), gettext("Convert to Template…").into()));
+        }
+        if matches!(self.vm_type, VmType::Qemu) {
+            actions.push(("vmmedia".into(), gettext("Change CD/DVD Media…").into()));
+            actions.push(("vmboot".into(), gettext("Boot Device…").into()));
+            if self.status == VmStatus::Running && self.agent_addresses.is_some() {
+                actions.push((
+                    "vmagentexec".into(),
+                    gettext("Run Command in Guest…").into(),
+                ));
+            }
+        }
+        if self.status == VmStatus::Running && self.may_support_spice() {
+            actions.push(("vmexportvv".into(), gettext("Export as .vv File…").into()));
         }
+        actions.push(("vmnotes".into(), gettext("Notes…").into()));
+        actions
     }
 
     fn action<'a>(&self, action_id: &str) -> Option<ServerAction<'a>> {
+        if (self.ha.is_some() || self.template) && VM_STATUS_ACTIONS.contains(&action_id) {
+            return None;
+        }
         match action_id {
+            "vmhastart" => Some(self.act_ha_request(HaRequestState::Started)),
+            "vmhastop" => Some(self.act_ha_request(HaRequestState::Stopped)),
             "vmreboot" => Some(self.act_reboot()),
             "vmshutdown" => Some(self.act_shutdown()),
             "vmreset" => Some(self.act_reset()),
             "vmstop" => Some(self.act_stop()),
             "vmstart" => Some(self.act_start()),
+            "vmsuspend" => Some(self.act_suspend(false)),
+            "vmhibernate" => Some(self.act_suspend(true)),
+            "vmnotes" => Some(self.act_notes()),
+            "vmmedia" => Some(self.act_media()),
+            "vmboot" => Some(self.act_boot_device()),
+            "vmagentexec" => Some(self.act_agent_exec()),
+            "lxcclone" if self.template => Some(self.act_lxc_clone()),
+            "lxctemplate" if !self.template => Some(self.act_lxc_template()),
+            "vmexportvv" => Some(self.act_export_vv()),
             _ => None,
         }
     }
 }
 
 impl ProxmoxVm {
+    /// Returns the console proxies supported by this guest. If they are not known yet, they are
+    /// fetched together with those of the next few running guests of the node, since those are
+    /// likely to be requested next.
+    async fn available_console_proxies(&self) -> proxmox_api::Result<Vec<VmConsoleProxyType>> {
+        let batch = {
+            let cache = self.console_proxies.lock().unwrap();
+            if let Some(proxies) = cache.known.get(&self.vm_id) {
+                return Ok(proxies.clone());
+            }
+            cache.next_batch(&self.vm_id, self.vm_type)
+        };
+
+        let results = self
+            .client
+            .vms_available_console_proxies(&self.node_id, &batch, CONSOLE_PROXY_BATCH_SIZE)
+            .await;
+
+        let mut own_result = None;
+        let mut cache = self.console_proxies.lock().unwrap();
+        for ((vm_id, _), result) in batch.into_iter().zip(results) {
+            if vm_id == self.vm_id {
+                if let Ok(proxies) = &result {
+                    cache.known.insert(vm_id, proxies.clone());
+                }
+                own_result = Some(result);
+            } else {
+                match result {
+                    Ok(proxies) => {
+                        cache.known.insert(vm_id, proxies);
+                    }
+                    Err(err) => debug!("failed to prefetch console proxies for {vm_id}: {err}"),
+                }
+            }
+        }
+
+        own_result.unwrap_or(Err(proxmox_api::Error::MissingData))
+    }
+
     fn params(&self) -> ExecParams {
         ExecParams {
             client: self.client.clone(),
@@ -504,6 +1092,82 @@ impl ProxmoxVm {
         }
     }
 
+    fn title(&self) -> String {
+        match &self.name {
+            None => self.vm_id.to_string(),
+            Some(name) => format!("{} ({})", self.vm_id, name),
+        }
+    }
+
+    /// Whether the guest may have a SPICE console. Unless its console proxies were already
+    /// fetched, this is assumed.
+    fn may_support_spice(&self) -> bool {
+        let cache = self.console_proxies.lock().unwrap();
+        cache
+            .known
+            .get(&self.vm_id)
+            .map_or(true, |proxies| proxies.contains(&VmConsoleProxyType::Spice))
+    }
+
+    /// Whether the guest was suspended to disk. Starting it resumes it.
+    fn is_hibernated(&self) -> bool {
+        self.lock == Some(VmLock::Suspended)
+    }
+
+    /// Requests `state` from the HA manager. Guests in the error state must be disabled before
+    /// they can be started again, this is done first.
+    fn act_ha_request<'a>(&self, state: HaRequestState) -> ServerAction<'a> {
+        let (sid, recover) = self
+            .ha
+            .as_ref()
+            .map(|ha| (ha.sid.clone(), ha.state == HaServiceState::Error))
+            .unwrap_or_default();
+        ServerAction::new(
+            Box::new(self.params()),
+            Box::new(move |params, _window, toov| {
+                let sid = sid.clone();
+                Box::pin(async move {
+                    let params = params.downcast::<ExecParams>().unwrap();
+
+                    let (success, force_reload) = exec_cmd(
+                        params,
+                        move |params| {
+                            let sid = sid.clone();
+                            async move {
+                                if recover && state == HaRequestState::Started {
+                                    params
+                                        .client
+                                        .cluster_ha_resource_set_state(
+                                            &sid,
+                                            HaRequestState::Disabled,
+                                        )
+                                        .await?;
+                                }
+                                params
+                                    .client
+                                    .cluster_ha_resource_set_state(&sid, state)
+                                    .await
+                            }
+                        },
+                        move || match state {
+                            HaRequestState::Started => {
+                                gettext("Requested the HA manager to start the guest.")
+                            }
+                            _ => gettext("Requested the HA manager to stop the guest."),
+                        },
+                        |err| {
+                            warn!("failed HA state request: {err:?}");
+                            gettext("Failed to send the request to the HA manager.")
+                        },
+                        toov.as_ref(),
+                    )
+                    .await;
+                    success || force_reload
+                })
+            }),
+        )
+    }
+
     fn act_reboot<'a>(&self) -> ServerAction<'a> {
         ServerAction::new(
             Box::new(self.params()),
@@ -649,20 +1313,483 @@ impl ProxmoxVm {
                     let (success, force_reload) = exec_cmd(
                         params,
                         |params| async move {
+                            let node_id = params.node_id.unwrap();
+                            let vm_id = params.vm_id.unwrap();
+                            // VMs suspended to RAM are still running and need to be resumed
+                            // instead. VMs suspended to disk are resumed by starting them.
+                            if matches!(params.vm_type, Some(VmType::Qemu)) {
+                                let status = params
+                                    .client
+                                    .vm_qemu_status_current(&node_id, &vm_id)
+                                    .await?;
+                                let paused = matches!(
+                                    status.qmpstatus,
+                                    Some(QmpStatus::Paused | QmpStatus::Suspended)
+                                );
+                                if status.status == VmStatus::Running && paused {
+                                    return params
+                                        .client
+                                        .vm_resume(
+                                            &node_id,
+                                            &vm_id,
+                                            params.vm_type,
+                                            Default::default(),
+                                        )
+                                        .await;
+                                }
+                            }
                             params
                                 .client
-                                .vm_start(
+                                .vm_start(&node_id, &vm_id, params.vm_type, Default::default())
+                                .await
+                        },
+                        || gettext("VM is now starting."),
+                        |err| {
+                            warn!("failed stop: {err:?}");
+                            gettext("Failed to send start command.")
+                        },
+                        toov.as_ref(),
+                    )
+                    .await;
+                    success || force_reload
+                })
+            }),
+        )
+    }
+
+    /// Suspends the VM to RAM, or to disk if `to_disk` is set.
+    fn act_suspend<'a>(&self, to_disk: bool) -> ServerAction<'a> {
+        ServerAction::new(
+            Box::new(self.params()),
+            Box::new(move |params, _window, toov| {
+                Box::pin(async move {
+                    let params = params.downcast::<ExecParams>().unwrap();
+
+                    let (success, force_reload) = exec_cmd(
+                        params,
+                        move |params| async move {
+                            params
+                                .client
+                                .vm_suspend(
                                     &params.node_id.unwrap(),
                                     &params.vm_id.unwrap(),
                                     params.vm_type,
-                                    Default::default(),
+                                    VmSuspendInput {
+                                        todisk: Some(to_disk),
+                                        ..Default::default()
+                                    },
                                 )
                                 .await
                         },
-                        || gettext("VM is now starting."),
+                        move || {
+                            if to_disk {
+                                gettext("VM is now hibernating.")
+                            } else {
+                                gettext("VM is now suspending.")
+                            }
+                        },
                         |err| {
-                            warn!("failed stop: {err:?}");
-                            gettext("Failed to send start command.")
+                            warn!("failed suspend: {err:?}");
+                            gettext("Failed to send suspend command.")
+                        },
+                        toov.as_ref(),
+                    )
+                    .await;
+                    success || force_reload
+                })
+            }),
+        )
+    }
+
+    /// Shows the description of the guest and lets the user edit it.
+    fn act_notes<'a>(&self) -> ServerAction<'a> {
+        ServerAction::new(
+            Box::new(self.params()),
+            Box::new(|params, window, toov| {
+                Box::pin(async move {
+                    let params = params.downcast::<ExecParams>().unwrap();
+                    let client = params.client.clone();
+                    let node_id = params.node_id.clone().unwrap();
+                    let vm_id = params.vm_id.clone().unwrap();
+                    let vm_type = params.vm_type;
+
+                    let config = run_on_tokio({
+                        let client = client.clone();
+                        let node_id = node_id.clone();
+                        let vm_id = vm_id.clone();
+                        async move {
+                            client
+                                .vm_config(&node_id, &vm_id, vm_type)
+                                .await
+                                .map_err(map_proxmox_error)
+                        }
+                    })
+                    .await;
+                    let config = match config {
+                        Ok(config) => config,
+                        Err(err) => {
+                            warn!("failed to load VM config: {err:?}");
+                            show_failure_toast(
+                                toov.as_ref(),
+                                gettext("Failed to load the notes of the VM."),
+                            );
+                            return false;
+                        }
+                    };
+                    let old_description = config.description.unwrap_or_default();
+
+                    let Some(description) =
+                        edit_notes(&vm_id.to_string(), &old_description, window.as_ref()).await
+                    else {
+                        return false;
+                    };
+                    if description == old_description {
+                        return false;
+                    }
+
+                    exec_cmd(
+                        params,
+                        move |params| {
+                            let description = description.clone();
+                            async move {
+                                params
+                                    .client
+                                    .vm_set_description(
+                                        &params.node_id.unwrap(),
+                                        &params.vm_id.unwrap(),
+                                        params.vm_type,
+                                        &description,
+                                    )
+                                    .await
+                            }
+                        },
+                        || gettext("Notes of the VM saved."),
+                        |err| {
+                            warn!("failed to save VM notes: {err:?}");
+                            gettext("Failed to save the notes of the VM.")
+                        },
+                        toov.as_ref(),
+                    )
+                    .await;
+                    false
+                })
+            }),
+        )
+    }
+}
+
+impl ProxmoxVm {
+    /// Lets the user insert an ISO image from the storages of the node into a CD/DVD drive of
+    /// the VM, or empty a drive.
+    fn act_media<'a>(&self) -> ServerAction<'a> {
+        ServerAction::new(
+            Box::new(self.params()),
+            Box::new(|params, window, toov| {
+                Box::pin(async move {
+                    let params = params.downcast::<ExecParams>().unwrap();
+                    let client = params.client.clone();
+                    let node_id = params.node_id.clone().unwrap();
+                    let vm_id = params.vm_id.clone().unwrap();
+
+                    let loaded = run_on_tokio({
+                        let node_id = node_id.clone();
+                        let vm_id = vm_id.clone();
+                        async move {
+                            let config = client
+                                .vm_config(&node_id, &vm_id, Some(VmType::Qemu))
+                                .await
+                                .map_err(map_proxmox_error)?;
+                            let mut images = Vec::new();
+                            let storages = client
+                                .node_storages(&node_id, "iso")
+                                .await
+                                .map_err(map_proxmox_error)?;
+                            for storage in storages {
+                                if storage.active == Some(false) {
+                                    continue;
+                                }
+                                match client
+                                    .storage_content(&node_id, &storage.storage, "iso")
+                                    .await
+                                {
+                                    Ok(content) => {
+                                        images.extend(content.into_iter().map(|c| c.volid))
+                                    }
+                                    Err(err) => warn!(
+                                        "failed to list ISO images of storage {}: {err}",
+                                        storage.storage
+                                    ),
+                                }
+                            }
+                            images.sort_unstable();
+                            ConnectionResult::Ok((config.cdrom_drives(), images))
+                        }
+                    })
+                    .await;
+                    let (drives, images) = match loaded {
+                        Ok(loaded) => loaded,
+                        Err(err) => {
+                            warn!("failed to load VM drives: {err:?}");
+                            show_failure_toast(
+                                toov.as_ref(),
+                                gettext("Failed to load the drives of the VM."),
+                            );
+                            return false;
+                        }
+                    };
+                    if drives.is_empty() {
+                        show_toast(toov.as_ref(), gettext("This VM has no CD/DVD drives."));
+                        return false;
+                    }
+
+                    let Some((drive, volume)) =
+                        choose_media(&vm_id.to_string(), &drives, &images, window.as_ref()).await
+                    else {
+                        return false;
+                    };
+
+                    let ejecting = volume.is_none();
+                    exec_cmd(
+                        params,
+                        move |params| {
+                            let drive = drive.clone();
+                            let volume = volume.clone();
+                            async move {
+                                params
+                                    .client
+                                    .qemu_vm_set_cdrom(
+                                        &params.node_id.unwrap(),
+                                        &params.vm_id.unwrap(),
+                                        &drive,
+                                        volume.as_deref(),
+                                    )
+                                    .await
+                            }
+                        },
+                        move || {
+                            if ejecting {
+                                gettext("Media successfully ejected.")
+                            } else {
+                                gettext("Media successfully inserted.")
+                            }
+                        },
+                        |err| {
+                            warn!("failed to change media: {err:?}");
+                            gettext("Failed to change media.")
+                        },
+                        toov.as_ref(),
+                    )
+                    .await;
+                    false
+                })
+            }),
+        )
+    }
+
+    /// Lets the user choose whether the VM boots from disk, CD/DVD or network first, and
+    /// optionally resets a running VM to apply it.
+    fn act_boot_device<'a>(&self) -> ServerAction<'a> {
+        let running = self.status == VmStatus::Running;
+        ServerAction::new(
+            Box::new(self.params()),
+            Box::new(move |params, window, toov| {
+                Box::pin(async move {
+                    let params = params.downcast::<ExecParams>().unwrap();
+                    let client = params.client.clone();
+                    let node_id = params.node_id.clone().unwrap();
+                    let vm_id = params.vm_id.clone().unwrap();
+
+                    let config = run_on_tokio({
+                        let node_id = node_id.clone();
+                        let vm_id = vm_id.clone();
+                        async move {
+                            client
+                                .vm_config(&node_id, &vm_id, Some(VmType::Qemu))
+                                .await
+                                .map_err(map_proxmox_error)
+                        }
+                    })
+                    .await;
+                    let config = match config {
+                        Ok(config) => config,
+                        Err(err) => {
+                            warn!("failed to load VM config: {err:?}");
+                            show_failure_toast(
+                                toov.as_ref(),
+                                gettext("Failed to load the boot order of the VM."),
+                            );
+                            return false;
+                        }
+                    };
+
+                    let kinds = [
+                        VmDeviceKind::Disk,
+                        VmDeviceKind::Cdrom,
+                        VmDeviceKind::Network,
+                    ]
+                    .into_iter()
+                    .filter(|kind| {
+                        config
+                            .devices
+                            .keys()
+                            .any(|device| config.device_kind(device) == Some(*kind))
+                    })
+                    .collect::<Vec<_>>();
+                    let current = config
+                        .boot_order()
+                        .first()
+                        .and_then(|device| config.device_kind(device));
+
+                    let Some((kind, reset)) = choose_boot_device(
+                        &vm_id.to_string(),
+                        &kinds,
+                        current,
+                        running,
+                        window.as_ref(),
+                    )
+                    .await
+                    else {
+                        return false;
+                    };
+                    let order = config.boot_order_preferring(kind);
+
+                    let (success, force_reload) = exec_cmd(
+                        params,
+                        move |params| {
+                            let order = order.clone();
+                            async move {
+                                let node_id = params.node_id.unwrap();
+                                let vm_id = params.vm_id.unwrap();
+                                params
+                                    .client
+                                    .qemu_vm_set_boot_order(&node_id, &vm_id, &order)
+                                    .await?;
+                                if reset {
+                                    params
+                                        .client
+                                        .qemu_vm_reset(&node_id, &vm_id, Default::default())
+                                        .await?;
+                                }
+                                proxmox_api::Result::Ok(())
+                            }
+                        },
+                        move || {
+                            if reset {
+                                gettext("Boot order changed and VM was reset.")
+                            } else {
+                                gettext("Boot order changed.")
+                            }
+                        },
+                        |err| {
+                            warn!("failed to change boot order: {err:?}");
+                            gettext("Failed to change the boot order.")
+                        },
+                        toov.as_ref(),
+                    )
+                    .await;
+                    reset && (success || force_reload)
+                })
+            }),
+        )
+    }
+}
+
+impl ProxmoxVm {
+    /// Lets the user run a command in the guest using the QEMU guest agent and shows its output.
+    fn act_agent_exec<'a>(&self) -> ServerAction<'a> {
+        ServerAction::new(
+            Box::new(self.params()),
+            Box::new(|params, window, toov| {
+                Box::pin(async move {
+                    let params = params.downcast::<ExecParams>().unwrap();
+                    let vm_id = params.vm_id.clone().unwrap();
+                    let vm_title = vm_id.to_string();
+
+                    let Some(command) = ask_command(&vm_title, window.as_ref()).await else {
+                        return false;
+                    };
+                    let command = match parse_command(&command) {
+                        Ok(command) if !command.is_empty() => command,
+                        result => {
+                            if let Err(err) = result {
+                                debug!("invalid command: {err}");
+                            }
+                            show_toast(toov.as_ref(), gettext("The command is invalid."));
+                            return false;
+                        }
+                    };
+
+                    let result = run_on_tokio(async move {
+                        run_command(
+                            params.client,
+                            params.node_id.unwrap(),
+                            params.vm_id.unwrap(),
+                            command,
+                        )
+                        .await
+                        .map_err(map_proxmox_error)
+                    })
+                    .await;
+                    match result {
+                        Ok(Some(status)) => show_output(&vm_title, &status, window.as_ref()).await,
+                        Ok(None) => show_toast(
+                            toov.as_ref(),
+                            gettext("The command is still running, its output is not shown."),
+                        ),
+                        Err(err) => {
+                            warn!("failed to run command in guest: {err:?}");
+                            show_failure_toast(
+                                toov.as_ref(),
+                                gettext(
+                                    "Failed to run the command. Is the QEMU guest agent running?",
+                                ),
+                            );
+                        }
+                    }
+                    false
+                })
+            }),
+        )
+    }
+}
+
+impl ProxmoxVm {
+    /// Creates a new container from this template, with the next free ID of the cluster.
+    fn act_lxc_clone<'a>(&self) -> ServerAction<'a> {
+        let title = self.title();
+        ServerAction::new(
+            Box::new(self.params()),
+            Box::new(move |params, window, toov| {
+                let title = title.clone();
+                Box::pin(async move {
+                    let params = params.downcast::<ExecParams>().unwrap();
+                    let Some(options) = ask_clone_options(&title, window.as_ref()).await else {
+                        return false;
+                    };
+                    let options = Arc::new(options);
+
+                    let (success, force_reload) = exec_cmd(
+                        params,
+                        move |params| {
+                            let options = options.clone();
+                            async move {
+                                let newid = params.client.cluster_nextid().await?;
+                                params
+                                    .client
+                                    .lxc_clone(
+                                        &params.node_id.unwrap(),
+                                        &params.vm_id.unwrap(),
+                                        &newid,
+                                        options.hostname.as_deref(),
+                                        options.full,
+                                    )
+                                    .await
+                            }
+                        },
+                        || gettext("Cloning of the container started."),
+                        |err| {
+                            warn!("failed to clone container: {err:?}");
+                            gettext("Failed to clone the container.")
                         },
                         toov.as_ref(),
                     )
@@ -672,6 +1799,178 @@ impl ProxmoxVm {
             }),
         )
     }
+
+    /// Converts the stopped container into a template, after asking for confirmation.
+    fn act_lxc_template<'a>(&self) -> ServerAction<'a> {
+        let title = self.title();
+        ServerAction::new(
+            Box::new(self.params()),
+            Box::new(move |params, window, toov| {
+                let title = title.clone();
+                Box::pin(async move {
+                    let params = params.downcast::<ExecParams>().unwrap();
+                    if !confirm_convert_to_template(&title, window.as_ref()).await {
+                        return false;
+                    }
+
+                    let (success, force_reload) = exec_cmd(
+                        params,
+                        |params| async move {
+                            params
+                                .client
+                                .vm_template(
+                                    &params.node_id.unwrap(),
+                                    &params.vm_id.unwrap(),
+                                    params.vm_type,
+                                )
+                                .await
+                        },
+                        || gettext("Container converted to a template."),
+                        |err| {
+                            warn!("failed to convert container to template: {err:?}");
+                            gettext("Failed to convert the container to a template.")
+                        },
+                        toov.as_ref(),
+                    )
+                    .await;
+                    success || force_reload
+                })
+            }),
+        )
+    }
+}
+
+impl ProxmoxVm {
+    /// Requests a new SPICE ticket and writes it to a `.vv` file, which can be opened with
+    /// remote-viewer. The ticket expires shortly after, so the file is only usable once.
+    fn act_export_vv<'a>(&self) -> ServerAction<'a> {
+        let title = self.title();
+        let spice_route = self.spice_route.clone();
+        ServerAction::new(
+            Box::new(self.params()),
+            Box::new(move |params, window, toov| {
+                let title = title.clone();
+                let spice_route = spice_route.clone();
+                Box::pin(async move {
+                    let params = params.downcast::<ExecParams>().unwrap();
+                    let client = params.client.clone();
+                    let result = run_on_tokio(async move {
+                        params
+                            .client
+                            .vm_spiceproxy(
+                                &params.node_id.unwrap(),
+                                &params.vm_id.unwrap(),
+                                params.vm_type,
+                                Default::default(),
+                            )
+                            .await
+                            .map_err(map_proxmox_error)
+                    })
+                    .await;
+                    let spiceproxy = match result {
+                        Ok(spiceproxy) => spiceproxy,
+                        Err(err) => {
+                            warn!("failed to request SPICE ticket: {err:?}");
+                            let text = gettext("Failed to request a SPICE ticket.");
+                            show_failure_toast(toov.as_ref(), text);
+                            return false;
+                        }
+                    };
+
+                    let (host, proxy) =
+                        spice_host_and_proxy(&client, &spiceproxy, &spice_route).await;
+                    let file = VvFile {
+                        title: title.clone(),
+                        host,
+                        tls_port: Some(spiceproxy.tls_port.get()),
+                        password: Some(spiceproxy.password.into()),
+                        proxy,
+                        ca: spiceproxy.ca.map(|s| s.replace(r"\n", "\n")),
+                        host_subject: spiceproxy.host_subject,
+                        ..Default::default()
+                    };
+                    let result = save_connection_file(
+                        window.as_ref(),
+                        &format!("{title}.vv"),
+                        &gettext("Virt-Viewer Files"),
+                        file.to_file_contents(),
+                    )
+                    .await;
+                    match result {
+                        Ok(false) => {}
+                        Ok(true) => show_toast(
+                            toov.as_ref(),
+                            gettext("Connection file exported. Its ticket is only valid for a short time."),
+                        ),
+                        Err(err) => {
+                            warn!("failed to export connection file: {err}");
+                            let text = gettext_f(
+                                "Failed to export connection file: {err}",
+                                &[("err", &err.to_string())],
+                            );
+                            show_failure_toast(toov.as_ref(), text);
+                        }
+                    }
+                    false
+                })
+            }),
+        )
+    }
+}
+
+fn show_toast(toov: Option<&adw::ToastOverlay>, text: String) {
+    if let Some(toov) = toov {
+        toov.add_toast(adw::Toast::builder().title(text).timeout(5).build());
+    }
+}
+
+/// Like [`show_toast`], for messages that tell that the action failed.
+fn show_failure_toast(toov: Option<&adw::ToastOverlay>, text: String) {
+    report_action_failure(&text);
+    show_toast(toov, text);
+}
+
+/// Presents a dialog for viewing and editing the notes of a guest. Returns the new notes, or
+/// `None` if the dialog was cancelled.
+async fn edit_notes(vm_title: &str, notes: &str, window: Option<&gtk::Window>) -> Option<String> {
+    let text_view = gtk::TextView::builder()
+        .wrap_mode(gtk::WrapMode::WordChar)
+        .top_margin(12)
+        .bottom_margin(12)
+        .left_margin(12)
+        .right_margin(12)
+        .build();
+    text_view.buffer().set_text(notes);
+    let scrolled_window = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .min_content_height(200)
+        .max_content_height(400)
+        .propagate_natural_height(true)
+        .child(&text_view)
+        .css_classes(["card"])
+        .build();
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext_f("Notes of {vm}", &[("vm", vm_title)]))
+        .body(gettext(
+            "The notes are stored in the description of the VM and are also shown in the Proxmox web interface.",
+        ))
+        .extra_child(&scrolled_window)
+        .close_response("cancel")
+        .default_response("save")
+        .build();
+    dialog.add_responses(&[("cancel", &gettext("Cancel")), ("save", &gettext("Save"))]);
+    dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+
+    if dialog.choose_future(window).await.as_str() != "save" {
+        return None;
+    }
+    let buffer = text_view.buffer();
+    Some(
+        buffer
+            .text(&buffer.start_iter(), &buffer.end_iter(), false)
+            .to_string(),
+    )
 }
 
 impl ServerConnection for ProxmoxVm {
@@ -682,20 +1981,47 @@ impl ServerConnection for ProxmoxVm {
             VmStatus::Unknown => None,
         };
 
-        let title = match &self.name {
-            None => self.vm_id.to_string(),
-            Some(name) => format!("{} ({})", self.vm_id, name),
-        };
+        let title = self.title();
 
         let icon = match self.vm_type {
             VmType::Lxc => IconSpec::Named("container-symbolic".into()),
             VmType::Qemu => IconSpec::Default,
         };
 
+        let mut info_lines = match (self.status, self.uptime) {
+            (VmStatus::Running, Some(uptime)) if uptime > 0 => vec![format_uptime(uptime)],
+            (VmStatus::Stopped, _) if self.is_hibernated() => vec![gettext("Hibernated")],
+            _ => vec![],
+        };
+        if let Some(os_type) = &self.os_type {
+            info_lines.push(os_type_title(os_type));
+        }
+        // Prefer IPv4 addresses, those are usually the ones users connect to.
+        let ip_address = self.agent_addresses.as_ref().and_then(|addresses| {
+            addresses
+                .iter()
+                .find(|address| !address.contains(':'))
+                .or_else(|| addresses.first())
+                .cloned()
+        });
+
+        let mut badges = self.ha.iter().map(ha_badge).collect::<Vec<_>>();
+        if self.template {
+            badges.push(ServerBadge {
+                label: gettext("Template"),
+                tooltip: None,
+                style: BadgeStyle::Neutral,
+            });
+        }
+
         ServerMetadataBuilder::default()
             .title(title)
+            .info_lines(info_lines)
+            .host(ip_address.clone())
+            .ip_address(ip_address)
             .icon(icon)
             .is_online(is_online)
+            .badges(badges)
             .build()
             .unwrap()
     }
@@ -717,24 +2043,21 @@ impl ServerConnection for ProxmoxVm {
             };
         }
 
-        if self.status != VmStatus::Running {
+        if self.status != VmStatus::Running || self.read_only {
             vec![]
         } else {
             // TODO: Async?
             let res: proxmox_api::Result<Vec<_>> = tkruntime().block_on(async move {
                 let mut adapters: Vec<(Cow<str>, Cow<str>)> = Vec::with_capacity(3);
-                let supported = self
-                    .client
-                    .vm_available_console_proxies(&self.node_id, &self.vm_id, Some(self.vm_type))
-                    .await?;
+                let supported = self.available_console_proxies().await?;
 
-                if supported.as_ref().contains(&VmConsoleProxyType::Spice) {
+                if supported.contains(&VmConsoleProxyType::Spice) {
                     adapters.push(SPICE!());
                 }
-                if supported.as_ref().contains(&VmConsoleProxyType::Vnc) {
+                if supported.contains(&VmConsoleProxyType::Vnc) {
                     adapters.push(VNC!());
                 }
-                if supported.as_ref().contains(&VmConsoleProxyType::Term) {
+                if supported.contains(&VmConsoleProxyType::Term) {
                     adapters.push(TERM!());
                 }
 
@@ -755,10 +2078,107 @@ impl ServerConnection for ProxmoxVm {
             &format!("{}/{}", self.node_id, self.vm_id),
             self.client.clone(),
             ProxmoxEntity::Vm(self.vm_type, self.node_id.clone(), self.vm_id.clone()),
+            self.generate_vnc_password,
+            self.spice_route.clone(),
+            match self.vm_type {
+                VmType::Lxc => self.container_console_user.clone(),
+                VmType::Qemu => None,
+            },
         )
     }
 }
 
+/// Console proxies supported by the running guests of a single node. Shared between all
+/// [`ProxmoxVm`]s of a node, so that they can be fetched in batches.
+struct ConsoleProxyCache {
+    /// Running guests of the node, in the order they are listed.
+    guests: Vec<(VmId, VmType)>,
+    known: BTreeMap<VmId, Vec<VmConsoleProxyType>>,
+}
+
+impl ConsoleProxyCache {
+    fn new(guests: Vec<(VmId, VmType)>) -> Self {
+        Self {
+            guests,
+            known: BTreeMap::new(),
+        }
+    }
+
+    /// The given guest and up to `CONSOLE_PROXY_BATCH_SIZE - 1` of the guests listed after it,
+    /// for which the supported proxies are not yet known.
+    fn next_batch(&self, vm_id: &VmId, vm_type: VmType) -> Vec<(VmId, VmType)> {
+        let mut batch = vec![(vm_id.clone(), vm_type)];
+        if let Some(pos) = self.guests.iter().position(|(id, _)| id == vm_id) {
+            batch.extend(
+                self.guests[pos + 1..]
+                    .iter()
+                    .filter(|(id, _)| !self.known.contains_key(id))
+                    .take(CONSOLE_PROXY_BATCH_SIZE - 1)
+                    .cloned(),
+            );
+        }
+        batch
+    }
+}
+
+/// The badge marking a guest as managed by the HA manager, colored by its HA state.
+fn ha_badge(ha: &HaGuest) -> ServerBadge {
+    let (state, style) = match ha.state {
+        HaServiceState::Started => (gettext("started"), BadgeStyle::Success),
+        HaServiceState::Stopped => (gettext("stopped"), BadgeStyle::Neutral),
+        HaServiceState::Disabled => (gettext("disabled"), BadgeStyle::Neutral),
+        HaServiceState::RequestStart => (gettext("starting"), BadgeStyle::Warning),
+        HaServiceState::RequestStop => (gettext("stopping"), BadgeStyle::Warning),
+        HaServiceState::Migrate | HaServiceState::Relocate => {
+            (gettext("migrating"), BadgeStyle::Warning)
+        }
+        HaServiceState::Freeze => (gettext("frozen"), BadgeStyle::Warning),
+        HaServiceState::Fence | HaServiceState::Recovery => {
+            (gettext("recovering"), BadgeStyle::Error)
+        }
+        HaServiceState::Error => (gettext("error"), BadgeStyle::Error),
+        HaServiceState::Unknown => (gettext("unknown"), BadgeStyle::Neutral),
+    };
+    let tooltip = match &ha.node {
+        Some(node) => gettext_f(
+            "Managed by HA: {state} on {node}",
+            &[("state", &state), ("node", node.as_ref())],
+        ),
+        None => gettext_f("Managed by HA: {state}", &[("state", &state)]),
+    };
+    ServerBadge {
+        label: gettext("HA"),
+        tooltip: Some(tooltip),
+        style,
+    }
+}
+
+/// Returns a human-readable description of an uptime given in seconds.
+fn format_uptime(uptime: i64) -> String {
+    gettext_f(
+        "Up for {duration}",
+        &[("duration", &format_duration(uptime))],
+    )
+}
+
+/// Errors of requests for optional information are only logged, except for authentication
+/// errors, which must be handled by the caller.
+fn ignore_non_auth_error<T>(
+    result: proxmox_api::Result<T>,
+    what: &str,
+) -> ConnectionResult<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(proxmox_api::Error::AuthFailed) => {
+            Err(map_proxmox_error(proxmox_api::Error::AuthFailed))
+        }
+        Err(err) => {
+            warn!("failed to load {what}: {err}");
+            Ok(None)
+        }
+    }
+}
+
 fn map_proxmox_error(error: proxmox_api::Error) -> ConnectionError {
     match error {
         proxmox_api::Error::AuthFailed => ConnectionError::AuthFailed(None, error.into()),
@@ -807,6 +2227,8 @@ where
         // short sleep to maybe possibly give the chance to already have processed the task.
         // TODO: We could actually wait for the task to finish.
         sleep(Duration::from_millis(750)).await;
+    } else {
+        report_action_failure(&text);
     }
 
     if let Some(toov) = toov {
@@ -815,11 +2237,24 @@ where
     (success, should_reload)
 }
 
+#[derive(Clone)]
 enum ProxmoxEntity {
     Node(NodeId),
     Vm(VmType, NodeId, VmId),
 }
 
+/// The URL of the web interface of the cluster, which is served at the root of the API host.
+fn web_interface_url(client: &ProxmoxApiClient) -> ConnectionResult<String> {
+    let root = &client.config().root;
+    let (Some(scheme), Some(authority)) = (root.scheme_str(), root.authority()) else {
+        return Err(ConnectionError::General(
+            None,
+            anyhow!("API root has no scheme or host"),
+        ));
+    };
+    Ok(format!("{scheme}://{authority}/"))
+}
+
 enum AdapterCreds {
     Vnc(Vncproxy),
     Spice(Spiceproxy),
@@ -832,6 +2267,9 @@ fn create_proxmox_adapter<'a>(
     server_id: &str,
     client: Arc<ProxmoxApiClient>,
     entity: ProxmoxEntity,
+    generate_vnc_password: bool,
+    spice_route: SpiceRoute,
+    console_user: Option<String>,
 ) -> LocalBoxFuture<'a, ConnectionResult<Box<dyn Adapter>>> {
     let connection_id = connection_id.to_string();
     let server_id = server_id.to_string();
@@ -850,111 +2288,162 @@ fn create_proxmox_adapter<'a>(
         }
     };
 
-    Box::pin(run_on_tokio(async move {
-        let adapter_creds = match &entity {
-            ProxmoxEntity::Node(node_id) => match adapter_type {
-                VmConsoleProxyType::Vnc => AdapterCreds::Vnc(
-                    client
-                        .node_vncshell(node_id, Default::default())
-                        .await
-                        .map_err(map_proxmox_error)?,
-                ),
-                VmConsoleProxyType::Spice => AdapterCreds::Spice(
-                    client
-                        .node_spiceshell(node_id, Default::default())
-                        .await
-                        .map_err(map_proxmox_error)?,
-                ),
-                VmConsoleProxyType::Term => AdapterCreds::Term(
-                    client
-                        .node_termproxy(node_id, Default::default())
-                        .await
-                        .map_err(map_proxmox_error)?,
-                ),
-            },
-            ProxmoxEntity::Vm(vm_type, node_id, vm_id) => match adapter_type {
-                VmConsoleProxyType::Vnc => AdapterCreds::Vnc(
-                    client
-                        .vm_vncproxy(node_id, vm_id, Some(*vm_type), Default::default())
-                        .await
-                        .map_err(map_proxmox_error)?,
-                ),
-                VmConsoleProxyType::Spice => AdapterCreds::Spice(
-                    client
-                        .vm_spiceproxy(node_id, vm_id, Some(*vm_type), Default::default())
-                        .await
-                        .map_err(map_proxmox_error)?,
-                ),
-                VmConsoleProxyType::Term => AdapterCreds::Term(
-                    client
-                        .vm_termproxy(node_id, vm_id, Some(*vm_type), Default::default())
-                        .await
-                        .map_err(map_proxmox_error)?
-                        .1,
-                ),
-            },
-        };
+    Box::pin(async move {
+        let adapter_creds = run_on_tokio({
+            let client = client.clone();
+            let entity = entity.clone();
+            async move {
+                fetch_adapter_creds(&client, &entity, adapter_type, generate_vnc_password).await
+            }
+        })
+        .await?;
 
         let adapter: Box<dyn Adapter> = match adapter_creds {
             AdapterCreds::Vnc(vncproxy) => Box::new(VncAdapter::new_with_ca(
-                client.clientconfig_hostname().to_string(),
+                client.config().hostname().to_string(),
                 vncproxy.port.into(),
                 vncproxy.user,
-                vncproxy.ticket.into(),
+                vncproxy.password.unwrap_or(vncproxy.ticket).into(),
                 vncproxy.cert,
             )),
-            AdapterCreds::Spice(spiceproxy) => Box::new(SpiceAdapter::new_with_custom_config(
-                SpiceSessionConfigBuilder::default()
-                    .host(Some(spiceproxy.host))
-                    .password(Some(spiceproxy.password.into()))
-                    .proxy(Some(spiceproxy.proxy))
-                    .tls_port(Some(spiceproxy.tls_port))
-                    .ca(spiceproxy
-                        .ca
-                        .map(|s| s.replace(r"\n", "\n"))
-                        .map(String::into_bytes))
-                    .cert_subject(spiceproxy.host_subject)
-                    .build()
-                    .unwrap(),
-            )),
+            AdapterCreds::Spice(spiceproxy) => {
+                let (host, proxy) = spice_host_and_proxy(&client, &spiceproxy, &spice_route).await;
+                Box::new(SpiceAdapter::new_with_custom_config(
+                    SpiceSessionConfigBuilder::default()
+                        .host(Some(host))
+                        .password(Some(spiceproxy.password.into()))
+                        .proxy(proxy)
+                        .tls_port(Some(spiceproxy.tls_port))
+                        .ca(spiceproxy
+                            .ca
+                            .map(|s| s.replace(r"\n", "\n"))
+                            .map(String::into_bytes))
+                        .cert_subject(spiceproxy.host_subject)
+                        .build()
+                        .unwrap(),
+                ))
+            }
             AdapterCreds::Term(termproxy) => {
                 let (node_id, vm_type, vm_id) = match entity {
                     ProxmoxEntity::Node(node_id) => {
                         (node_id.to_string(), String::new(), String::new())
                     }
                     ProxmoxEntity::Vm(vm_type, node_id, vm_id) => {
-                        (vm_type.to_string(), node_id.to_string(), vm_id.to_string())
+                        (node_id.to_string(), vm_type.to_string(), vm_id.to_string())
                     }
                 };
-                let ignore_ssl_errors = if client.clientconfig_ignore_ssl_errors() {
-                    "1"
-                } else {
-                    "0"
-                };
 
                 Box::new(VtePtyAdapter::new(
                     connection_id,
                     server_id,
                     adapter_tag,
                     libexec_path(PTY_DRIVER_BIN).expect("failed to find libvirt vte driver in path. Is Field Monitor correctly installed?"),
-                    vec![
-                        client.clientconfig_connection_type().to_string(),
-                        client.clientconfig_root().to_string(),
-                        client.clientconfig_user_or_tokenid().to_string(),
-                        client.clientconfig_password_or_apikey().unsecure().to_string(),
-                        ignore_ssl_errors.to_string(),
+                    client.config().to_args().into_iter().chain([
                         node_id,
                         vm_type,
                         vm_id,
                         serde_json::to_string(&termproxy)
                             .map_err(|e| ConnectionError::General(
                                 None, anyhow!("failed serialization: {e}").context(e)
-                            ))?
-                    ],
+                            ))?,
+                        console_user.unwrap_or_default(),
+                    ]).collect(),
                 ))
             }
         };
 
         Ok(adapter)
-    }))
+    })
+}
+
+async fn fetch_adapter_creds(
+    client: &ProxmoxApiClient,
+    entity: &ProxmoxEntity,
+    adapter_type: VmConsoleProxyType,
+    generate_vnc_password: bool,
+) -> ConnectionResult<AdapterCreds> {
+    Ok(match entity {
+        ProxmoxEntity::Node(node_id) => match adapter_type {
+            VmConsoleProxyType::Vnc => AdapterCreds::Vnc(
+                client
+                    .node_vncshell(node_id, Default::default())
+                    .await
+                    .map_err(map_proxmox_error)?,
+            ),
+            VmConsoleProxyType::Spice => AdapterCreds::Spice(
+                client
+                    .node_spiceshell(node_id, Default::default())
+                    .await
+                    .map_err(map_proxmox_error)?,
+            ),
+            VmConsoleProxyType::Term => AdapterCreds::Term(
+                client
+                    .node_termproxy(node_id, Default::default())
+                    .await
+                    .map_err(map_proxmox_error)?,
+            ),
+        },
+        ProxmoxEntity::Vm(vm_type, node_id, vm_id) => match adapter_type {
+            VmConsoleProxyType::Vnc => AdapterCreds::Vnc(
+                client
+                    .vm_vncproxy(
+                        node_id,
+                        vm_id,
+                        Some(*vm_type),
+                        VmVncproxyInput {
+                            generate_password: generate_vnc_password.then_some(1),
+                            ..Default::default()
+                        },
+                    )
+                    .await
+                    .map_err(map_proxmox_error)?,
+            ),
+            VmConsoleProxyType::Spice => AdapterCreds::Spice(
+                client
+                    .vm_spiceproxy(node_id, vm_id, Some(*vm_type), Default::default())
+                    .await
+                    .map_err(map_proxmox_error)?,
+            ),
+            VmConsoleProxyType::Term => AdapterCreds::Term(
+                client
+                    .vm_termproxy(node_id, vm_id, Some(*vm_type), Default::default())
+                    .await
+                    .map_err(map_proxmox_error)?
+                    .1,
+            ),
+        },
+    })
+}
+
+/// The host and proxy to connect to a SPICE console with, according to `route`.
+async fn spice_host_and_proxy(
+    client: &ProxmoxApiClient,
+    spiceproxy: &Spiceproxy,
+    route: &SpiceRoute,
+) -> (String, Option<String>) {
+    match route {
+        SpiceRoute::Proxmox => (spiceproxy.host.clone(), Some(spiceproxy.proxy.clone())),
+        SpiceRoute::Proxy(proxy) => (spiceproxy.host.clone(), Some(proxy.clone())),
+        SpiceRoute::Direct => {
+            let host = client.config().hostname();
+            let reachable = match u16::try_from(spiceproxy.tls_port.get()) {
+                Ok(port) => check_reachable(host, port)
+                    .await
+                    .map_err(|err| err.to_string()),
+                Err(_) => Err("port out of range".to_string()),
+            };
+            match reachable {
+                // The certificate is checked against the host subject given by Proxmox, not the
+                // host, so the address can be connected to directly.
+                Ok(reachable) => (reachable.required_address().unwrap_or(reachable.host), None),
+                Err(err) => {
+                    warn!(
+                        "SPICE port {} on {host} not reachable, using the Proxmox proxy: {err}",
+                        spiceproxy.tls_port
+                    );
+                    (spiceproxy.host.clone(), Some(spiceproxy.proxy.clone()))
+                }
+            }
+        }
+    }
 }
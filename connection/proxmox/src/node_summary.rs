@@ -0,0 +1,274 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Dialog summarizing the status, services and available updates of a node.
+use std::sync::Arc;
+
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+use libfieldmonitor::connection::ConnectionResult;
+use libfieldmonitor::i18n::{format_bytes, format_percent, gettext_f};
+use log::warn;
+use proxmox_api::{
+    NodeAptUpdate, NodeId, NodeService, NodeServiceState, NodeServiceUnitState, NodeStatusInfo,
+    NodeUsage, ProxmoxApiClient,
+};
+
+use crate::tokiort::run_on_tokio;
+use crate::{format_uptime, map_proxmox_error};
+
+struct NodeSummary {
+    status: NodeStatusInfo,
+    services: Option<Vec<NodeService>>,
+    /// `None` if the updates could not be listed, eg. because the user lacks the permission.
+    updates: Option<Vec<NodeAptUpdate>>,
+}
+
+async fn load_summary(
+    client: Arc<ProxmoxApiClient>,
+    node_id: NodeId,
+) -> ConnectionResult<NodeSummary> {
+    run_on_tokio(async move {
+        let status = client
+            .node_status(&node_id)
+            .await
+            .map_err(map_proxmox_error)?;
+        let services = client
+            .node_services(&node_id)
+            .await
+            .inspect_err(|err| warn!("failed to list services of node {node_id}: {err}"))
+            .ok();
+        let updates = client
+            .node_apt_updates(&node_id)
+            .await
+            .inspect_err(|err| warn!("failed to list updates of node {node_id}: {err}"))
+            .ok();
+        Ok(NodeSummary {
+            status,
+            services,
+            updates,
+        })
+    })
+    .await
+}
+
+/// Shows the summary of the node `node_id`. The summary can be refreshed from the dialog.
+pub fn show_node_summary(
+    client: Arc<ProxmoxApiClient>,
+    node_id: NodeId,
+    window: Option<&gtk::Window>,
+) {
+    let refresh_button = gtk::Button::builder()
+        .icon_name("view-refresh-symbolic")
+        .tooltip_text(gettext("Refresh"))
+        .build();
+    let header_bar = adw::HeaderBar::new();
+    header_bar.pack_start(&refresh_button);
+    let toolbar_view = adw::ToolbarView::new();
+    toolbar_view.add_top_bar(&header_bar);
+
+    let dialog = adw::Dialog::builder()
+        .title(gettext_f(
+            "Summary of {node}",
+            &[("node", node_id.as_ref())],
+        ))
+        .content_width(480)
+        .content_height(640)
+        .child(&toolbar_view)
+        .build();
+
+    let refresh = move |toolbar_view: adw::ToolbarView, refresh_button: gtk::Button| {
+        let client = client.clone();
+        let node_id = node_id.clone();
+        glib::spawn_future_local(async move {
+            refresh_button.set_sensitive(false);
+            toolbar_view.set_content(Some(
+                &adw::Spinner::builder()
+                    .width_request(32)
+                    .height_request(32)
+                    .halign(gtk::Align::Center)
+                    .valign(gtk::Align::Center)
+                    .build(),
+            ));
+            let content: gtk::Widget = match load_summary(client, node_id).await {
+                Ok(summary) => summary_page(&summary).upcast(),
+                Err(err) => {
+                    warn!("failed to load node summary: {err:?}");
+                    adw::StatusPage::builder()
+                        .icon_name("dialog-error-symbolic")
+                        .title(gettext("Failed to Load Summary"))
+                        .description(err.to_string())
+                        .build()
+                        .upcast()
+                }
+            };
+            toolbar_view.set_content(Some(&content));
+            refresh_button.set_sensitive(true);
+        });
+    };
+    refresh(toolbar_view.clone(), refresh_button.clone());
+    refresh_button.connect_clicked(glib::clone!(
+        #[weak]
+        toolbar_view,
+        move |refresh_button| refresh(toolbar_view, refresh_button.clone())
+    ));
+
+    dialog.present(window);
+}
+
+fn summary_page(summary: &NodeSummary) -> adw::PreferencesPage {
+    let page = adw::PreferencesPage::new();
+    page.add(&status_group(&summary.status));
+    page.add(&updates_group(summary.updates.as_deref()));
+    if let Some(services) = &summary.services {
+        page.add(&services_group(services));
+    }
+    page
+}
+
+fn property_row(title: &str, value: &str) -> adw::ActionRow {
+    adw::ActionRow::builder()
+        .title(title)
+        .subtitle(value)
+        .subtitle_selectable(true)
+        .css_classes(["property"])
+        .build()
+}
+
+fn format_usage(usage: &NodeUsage) -> String {
+    gettext_f(
+        "{used} of {total}",
+        &[
+            ("used", &format_bytes(usage.used.max(0) as u64)),
+            ("total", &format_bytes(usage.total.max(0) as u64)),
+        ],
+    )
+}
+
+fn status_group(status: &NodeStatusInfo) -> adw::PreferencesGroup {
+    let group = adw::PreferencesGroup::builder()
+        .title(gettext("Status"))
+        .build();
+    if let Some(uptime) = status.uptime {
+        group.set_description(Some(&format_uptime(uptime)));
+    }
+    if let Some(cpu) = status.cpu {
+        let usage = format_percent(cpu, 1);
+        let value = match status.cpuinfo.as_ref().and_then(|info| info.cpus) {
+            Some(cpus) => gettext_f(
+                "{usage} of {cpus} CPUs",
+                &[("usage", &usage), ("cpus", &cpus.to_string())],
+            ),
+            None => usage,
+        };
+        group.add(&property_row(&gettext("CPU Usage"), &value));
+    }
+    if !status.loadavg.is_empty() {
+        group.add(&property_row(
+            &gettext("Load Average"),
+            &status.loadavg.join(", "),
+        ));
+    }
+    if let Some(memory) = &status.memory {
+        group.add(&property_row(&gettext("Memory"), &format_usage(memory)));
+    }
+    if let Some(rootfs) = &status.rootfs {
+        group.add(&property_row(&gettext("Root Disk"), &format_usage(rootfs)));
+    }
+    if let Some(pveversion) = &status.pveversion {
+        group.add(&property_row(&gettext("Manager Version"), pveversion));
+    }
+    if let Some(kversion) = &status.kversion {
+        group.add(&property_row(&gettext("Kernel"), kversion));
+    }
+    group
+}
+
+fn updates_group(updates: Option<&[NodeAptUpdate]>) -> adw::PreferencesGroup {
+    let group = adw::PreferencesGroup::builder()
+        .title(gettext("Updates"))
+        .description(gettext(
+            "As of the last time the package lists were refreshed on the node.",
+        ))
+        .build();
+    match updates {
+        None => group.add(
+            &adw::ActionRow::builder()
+                .title(gettext("Updates could not be listed."))
+                .sensitive(false)
+                .build(),
+        ),
+        Some([]) => group.add(
+            &adw::ActionRow::builder()
+                .title(gettext("The node is up to date."))
+                .build(),
+        ),
+        Some(updates) => {
+            let row = adw::ExpanderRow::builder()
+                .title(gettext_f(
+                    "Updates available: {count}",
+                    &[("count", &updates.len().to_string())],
+                ))
+                .build();
+            for update in updates {
+                let version = match (&update.old_version, &update.version) {
+                    (Some(old), Some(new)) => {
+                        gettext_f("{old} → {new}", &[("old", old), ("new", new)])
+                    }
+                    (None, Some(version)) | (Some(version), None) => version.clone(),
+                    (None, None) => String::new(),
+                };
+                row.add_row(&property_row(&update.package, &version));
+            }
+            group.add(&row);
+        }
+    }
+    group
+}
+
+fn services_group(services: &[NodeService]) -> adw::PreferencesGroup {
+    let group = adw::PreferencesGroup::builder()
+        .title(gettext("Services"))
+        .build();
+    for service in services {
+        if service.unit_state == Some(NodeServiceUnitState::NotFound) {
+            continue;
+        }
+        let state_label = gtk::Label::builder()
+            .label(match service.state {
+                Some(NodeServiceState::Running) => gettext("Running"),
+                Some(NodeServiceState::Stopped) => gettext("Stopped"),
+                Some(NodeServiceState::Unknown) | None => gettext("Unknown"),
+            })
+            .css_classes(["dim-label"])
+            .build();
+        // Enabled services are expected to run.
+        if service.state != Some(NodeServiceState::Running)
+            && service.unit_state == Some(NodeServiceUnitState::Enabled)
+        {
+            state_label.set_css_classes(&["error"]);
+        }
+        let row = adw::ActionRow::builder()
+            .title(service.desc.as_deref().unwrap_or(&service.service))
+            .subtitle(&service.service)
+            .build();
+        row.add_suffix(&state_label);
+        group.add(&row);
+    }
+    group
+}
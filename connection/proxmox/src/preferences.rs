@@ -31,15 +31,61 @@ use libfieldmonitor::connection::{ConfigAccess, ConfigAccessMut, ConnectionConfi
 
 use crate::credential_preferences::ProxmoxCredentialPreferences;
 
+/// How SPICE consoles are connected to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SpiceRoute {
+    /// Through the SPICE proxy of the node, as returned by the API.
+    #[default]
+    Proxmox,
+    /// Through the given proxy instead, eg. when the node is only reachable via port
+    /// forwarding.
+    Proxy(String),
+    /// Directly to the TLS port on the host of the connection, if it can be reached.
+    Direct,
+}
+
+impl SpiceRoute {
+    /// Index of the route in the combo row of the preferences.
+    fn index(&self) -> u32 {
+        match self {
+            SpiceRoute::Proxmox => 0,
+            SpiceRoute::Proxy(_) => 1,
+            SpiceRoute::Direct => 2,
+        }
+    }
+
+    /// The route with the index `index` in the combo row of the preferences. `proxy` is used
+    /// for custom proxies.
+    fn from_index(index: u32, proxy: String) -> Self {
+        match index {
+            1 => SpiceRoute::Proxy(proxy),
+            2 => SpiceRoute::Direct,
+            _ => SpiceRoute::Proxmox,
+        }
+    }
+}
+
 pub(super) trait ProxmoxConfiguration {
     fn title(&self) -> Option<&str>;
     fn set_title(&mut self, value: &str);
     fn ignore_ssl_cert_error(&self) -> bool;
     fn set_ignore_ssl_cert_error(&mut self, value: bool);
+    fn generate_vnc_password(&self) -> bool;
+    fn set_generate_vnc_password(&mut self, value: bool);
+    fn spice_route(&self) -> SpiceRoute;
+    fn set_spice_route(&mut self, value: &SpiceRoute);
+    /// User that is logged in automatically on the consoles of containers, if any.
+    fn container_console_user(&self) -> Option<&str>;
+    fn set_container_console_user(&mut self, value: &str);
     fn hostname(&self) -> Option<&str>;
     fn set_hostname(&mut self, value: &str);
     fn port(&self) -> Option<NonZeroU32>;
     fn set_port(&mut self, value: NonZeroU32);
+    fn use_https(&self) -> bool;
+    fn set_use_https(&mut self, value: bool);
+    /// Path of the API root on the server, e.g. `/api2/json`.
+    fn api_path(&self) -> Option<&str>;
+    fn set_api_path(&mut self, value: &str);
     fn use_apikey(&self) -> bool;
     fn set_use_apikey(&mut self, value: bool);
     fn username(&self) -> Option<&str>;
@@ -49,6 +95,9 @@ pub(super) trait ProxmoxConfiguration {
     fn password_or_apikey(&self) -> BoxFuture<anyhow::Result<Option<SecureString>>>;
     fn set_password_or_apikey(&mut self, value: Option<SecureString>);
     fn set_password_or_apikey_session(&mut self, value: Option<SecureString>);
+    /// Command printing the password or API key, used instead of the stored one.
+    fn password_command(&self) -> Option<&str>;
+    fn set_password_command(&mut self, value: &str);
 }
 
 impl ProxmoxConfiguration for ConnectionConfiguration {
@@ -69,6 +118,47 @@ impl ProxmoxConfiguration for ConnectionConfiguration {
         self.set_value("ignore-ssl-cert-error", value);
     }
 
+    fn generate_vnc_password(&self) -> bool {
+        self.get_try_as_bool("generate-vnc-password")
+            .unwrap_or_default()
+    }
+
+    fn set_generate_vnc_password(&mut self, value: bool) {
+        self.set_value("generate-vnc-password", value);
+    }
+
+    fn spice_route(&self) -> SpiceRoute {
+        match self.get_try_as_str("spice-route") {
+            Some("proxy") => match self.get_try_as_str("spice-proxy") {
+                Some(proxy) if !proxy.trim().is_empty() => SpiceRoute::Proxy(proxy.to_string()),
+                _ => SpiceRoute::Proxmox,
+            },
+            Some("direct") => SpiceRoute::Direct,
+            _ => SpiceRoute::Proxmox,
+        }
+    }
+
+    fn set_spice_route(&mut self, value: &SpiceRoute) {
+        let route = match value {
+            SpiceRoute::Proxmox => "proxmox",
+            SpiceRoute::Proxy(proxy) => {
+                self.set_value("spice-proxy", proxy.as_str());
+                "proxy"
+            }
+            SpiceRoute::Direct => "direct",
+        };
+        self.set_value("spice-route", route);
+    }
+
+    fn container_console_user(&self) -> Option<&str> {
+        self.get_try_as_str("container-console-user")
+            .filter(|user| !user.is_empty())
+    }
+
+    fn set_container_console_user(&mut self, value: &str) {
+        self.set_value("container-console-user", value);
+    }
+
     fn hostname(&self) -> Option<&str> {
         self.get_try_as_str("hostname")
     }
@@ -91,6 +181,32 @@ impl ProxmoxConfiguration for ConnectionConfiguration {
         self.set_value("port", value.get());
     }
 
+    fn use_https(&self) -> bool {
+        self.get_try_as_bool("use-https").unwrap_or(true)
+    }
+
+    fn set_use_https(&mut self, value: bool) {
+        self.set_value("use-https", value);
+    }
+
+    fn api_path(&self) -> Option<&str> {
+        self.get_try_as_str("api-path")
+            .filter(|path| !path.trim().is_empty())
+    }
+
+    fn set_api_path(&mut self, value: &str) {
+        self.set_value("api-path", value);
+    }
+
+    fn password_command(&self) -> Option<&str> {
+        self.get_try_as_str("password-command")
+            .filter(|command| !command.trim().is_empty())
+    }
+
+    fn set_password_command(&mut self, value: &str) {
+        self.set_value("password-command", value);
+    }
+
     fn use_apikey(&self) -> bool {
         self.get_try_as_bool("use-apikey").unwrap_or_default()
     }
@@ -154,6 +270,8 @@ mod imp {
         pub credentials: TemplateChild<ProxmoxCredentialPreferences>,
         #[template_child]
         pub port_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub spice_proxy_entry: TemplateChild<adw::EntryRow>,
         #[property(get, set)]
         title: RefCell<String>,
         #[property(get, set)]
@@ -161,7 +279,22 @@ mod imp {
         #[property(get, set)]
         port: RefCell<String>,
         #[property(get, set)]
+        use_https: Cell<bool>,
+        #[property(get, set)]
+        api_path: RefCell<String>,
+        #[property(get, set)]
         ignore_ssl_cert_error: Cell<bool>,
+        #[property(get, set)]
+        generate_vnc_password: Cell<bool>,
+        /// Index of the [`SpiceRoute`].
+        #[property(get, set)]
+        spice_route: Cell<u32>,
+        #[property(get, set)]
+        spice_proxy: RefCell<String>,
+        #[property(get, set)]
+        container_console_user: RefCell<String>,
+        #[property(get, set)]
+        password_command: RefCell<String>,
     }
 
     #[glib::object_subclass]
@@ -209,7 +342,25 @@ impl ProxmoxPreferences {
                             .map(ToString::to_string)
                             .unwrap_or_default(),
                     );
+                    slf.set_use_https(existing_configuration.use_https());
+                    slf.set_api_path(existing_configuration.api_path().unwrap_or_default());
                     slf.set_ignore_ssl_cert_error(existing_configuration.ignore_ssl_cert_error());
+                    slf.set_generate_vnc_password(existing_configuration.generate_vnc_password());
+                    let spice_route = existing_configuration.spice_route();
+                    if let SpiceRoute::Proxy(proxy) = &spice_route {
+                        slf.set_spice_proxy(proxy.as_str());
+                    }
+                    slf.set_spice_route(spice_route.index());
+                    slf.set_container_console_user(
+                        existing_configuration
+                            .container_console_user()
+                            .unwrap_or_default(),
+                    );
+                    slf.set_password_command(
+                        existing_configuration
+                            .password_command()
+                            .unwrap_or_default(),
+                    );
 
                     slf.imp()
                         .credentials
@@ -237,10 +388,24 @@ impl ProxmoxPreferences {
         };
         self.port_entry_error(false);
 
+        let spice_route =
+            SpiceRoute::from_index(self.spice_route(), self.spice_proxy().trim().to_string());
+        if spice_route == SpiceRoute::Proxy(String::new()) {
+            self.imp().spice_proxy_entry.add_css_class("error");
+            return Err(anyhow!("no SPICE proxy set"));
+        }
+        self.imp().spice_proxy_entry.remove_css_class("error");
+
         config.set_title(&self.title());
         config.set_hostname(&self.hostname());
         config.set_port(port);
+        config.set_use_https(self.use_https());
+        config.set_api_path(self.api_path().trim());
         config.set_ignore_ssl_cert_error(self.ignore_ssl_cert_error());
+        config.set_generate_vnc_password(self.generate_vnc_password());
+        config.set_spice_route(&spice_route);
+        config.set_container_console_user(self.container_console_user().trim());
+        config.set_password_command(self.password_command().trim());
 
         Ok(())
     }
@@ -259,4 +424,12 @@ impl ProxmoxPreferences {
 }
 
 #[gtk::template_callbacks]
-impl ProxmoxPreferences {}
+impl ProxmoxPreferences {
+    #[template_callback]
+    fn on_self_spice_route_changed(&self) {
+        let route = SpiceRoute::from_index(self.spice_route(), String::new());
+        self.imp()
+            .spice_proxy_entry
+            .set_visible(matches!(route, SpiceRoute::Proxy(_)));
+    }
+}
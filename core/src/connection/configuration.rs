@@ -24,7 +24,10 @@ use std::sync::Arc;
 
 use futures::future::{try_join_all, BoxFuture, LocalBoxFuture};
 use secure_string::SecureString;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use serde_yaml::{Mapping, Value};
+use thiserror::Error;
 
 use crate::connection::config_value::{ConfigValue, ConfigValueRef};
 use crate::ManagesSecrets;
@@ -74,6 +77,42 @@ pub trait ConfigAccessMut {
     fn set_secret(&mut self, key: impl ToString, value: SecureString);
 }
 
+/// Error when reading or writing a configuration as a typed value.
+#[derive(Debug, Error)]
+pub enum TypedConfigError {
+    #[error("invalid configuration in {section}: {source}")]
+    Invalid {
+        /// The section key, or "<root>" for the top level of the configuration.
+        section: String,
+        source: serde_yaml::Error,
+    },
+    #[error("configuration for {0} must serialize to a mapping")]
+    NotAMapping(String),
+}
+
+const ROOT_SECTION_NAME: &str = "<root>";
+
+fn deserialize_typed<T: DeserializeOwned>(
+    section: &str,
+    map: Mapping,
+) -> Result<T, TypedConfigError> {
+    serde_yaml::from_value(Value::Mapping(map)).map_err(|source| TypedConfigError::Invalid {
+        section: section.to_string(),
+        source,
+    })
+}
+
+fn serialize_typed<T: Serialize>(section: &str, value: &T) -> Result<Mapping, TypedConfigError> {
+    match serde_yaml::to_value(value) {
+        Ok(Value::Mapping(map)) => Ok(map),
+        Ok(_) => Err(TypedConfigError::NotAMapping(section.to_string())),
+        Err(source) => Err(TypedConfigError::Invalid {
+            section: section.to_string(),
+            source,
+        }),
+    }
+}
+
 #[derive(Clone)]
 pub struct ConnectionConfiguration {
     config: HashMap<String, Value>,
@@ -116,6 +155,19 @@ impl ConnectionConfiguration {
         }
     }
 
+    /// A copy of the persisted configuration under a new connection ID. Stored secrets are not
+    /// copied, see [`ManagesSecrets::rekey`].
+    pub fn duplicate(&self, connection_id: String) -> Self {
+        Self {
+            config: self.config.clone(),
+            config_not_persisted: Default::default(),
+            provider_tag: self.provider_tag.clone(),
+            connection_id,
+            secret_manager: self.secret_manager.clone(),
+            pending_secret_changes: self.pending_secret_changes.clone(),
+        }
+    }
+
     pub fn tag(&self) -> &str {
         &self.provider_tag
     }
@@ -124,6 +176,23 @@ impl ConnectionConfiguration {
         &self.connection_id
     }
 
+    /// All strings in the persisted configuration, including nested ones, eg. to redact them
+    /// from diagnostics.
+    pub fn string_values(&self) -> Vec<&str> {
+        fn collect<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+            match value {
+                Value::String(s) => out.push(s),
+                Value::Sequence(values) => values.iter().for_each(|v| collect(v, out)),
+                Value::Mapping(mapping) => mapping.values().for_each(|v| collect(v, out)),
+                Value::Tagged(tagged) => collect(&tagged.value, out),
+                _ => {}
+            }
+        }
+        let mut out = Vec::new();
+        self.config.values().for_each(|v| collect(v, &mut out));
+        out
+    }
+
     /// Saves pending secret changes to the keychain, returns persistent configuration.
     pub async fn save(&mut self) -> anyhow::Result<HashMap<String, Value>> {
         let pending_secret_changes = take(&mut self.pending_secret_changes);
@@ -298,6 +367,54 @@ impl ConnectionConfiguration {
         }
     }
 
+    /// Reads the top level of the configuration as `T`. Keys not known to `T` are ignored,
+    /// missing keys must be handled by `T` (e.g. with `#[serde(default)]`).
+    pub fn typed<T: DeserializeOwned>(&self) -> Result<T, TypedConfigError> {
+        let map = self
+            .config
+            .iter()
+            .map(|(k, v)| (Value::String(k.clone()), v.clone()))
+            .collect();
+        deserialize_typed(ROOT_SECTION_NAME, map)
+    }
+
+    /// Writes all fields of `value` to the top level of the configuration. Other keys are kept.
+    pub fn set_typed<T: Serialize>(&mut self, value: &T) -> Result<(), TypedConfigError> {
+        for (k, v) in serialize_typed(ROOT_SECTION_NAME, value)? {
+            let Value::String(k) = k else {
+                return Err(TypedConfigError::NotAMapping(ROOT_SECTION_NAME.to_string()));
+            };
+            self.set_value(k, v);
+        }
+        Ok(())
+    }
+
+    /// Reads a section of the configuration as `T`. A missing section is read as an empty one.
+    pub fn typed_section<T: DeserializeOwned>(
+        &self,
+        section_key: &str,
+    ) -> Result<T, TypedConfigError> {
+        let map = match self.config.get(section_key) {
+            Some(Value::Mapping(section_map)) => section_map.clone(),
+            _ => Mapping::new(),
+        };
+        deserialize_typed(section_key, map)
+    }
+
+    /// Writes all fields of `value` to a section of the configuration. Other keys of the section,
+    /// such as secrets, are kept.
+    pub fn set_typed_section<T: Serialize>(
+        &mut self,
+        section_key: &str,
+        value: &T,
+    ) -> Result<(), TypedConfigError> {
+        let map = serialize_typed(section_key, value)?;
+        self.with_section_mut(section_key, |section| {
+            section.section_map.extend(map);
+        });
+        Ok(())
+    }
+
     pub fn section_keys(&self) -> impl Iterator<Item = &str> + '_ {
         self.config.iter().filter_map(|(k, v)| {
             if let Value::Mapping(_) = v {
@@ -638,3 +755,368 @@ impl DualScopedConnectionConfiguration {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use futures::executor::block_on;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemorySecrets(Mutex<HashMap<(String, String), SecureString>>);
+
+    impl ManagesSecrets for MemorySecrets {
+        fn lookup(
+            &self,
+            connection_id: &str,
+            field: &str,
+        ) -> BoxFuture<anyhow::Result<Option<SecureString>>> {
+            let value = self
+                .0
+                .lock()
+                .unwrap()
+                .get(&(connection_id.to_string(), field.to_string()))
+                .cloned();
+            Box::pin(async move { Ok(value) })
+        }
+
+        fn store(
+            &self,
+            connection_id: &str,
+            field: &str,
+            password: SecureString,
+        ) -> BoxFuture<anyhow::Result<()>> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert((connection_id.to_string(), field.to_string()), password);
+            Box::pin(async { Ok(()) })
+        }
+
+        fn clear(&self, connection_id: &str, field: &str) -> BoxFuture<anyhow::Result<()>> {
+            self.0
+                .lock()
+                .unwrap()
+                .remove(&(connection_id.to_string(), field.to_string()));
+            Box::pin(async { Ok(()) })
+        }
+
+        fn connection_ids(&self) -> BoxFuture<anyhow::Result<Vec<String>>> {
+            let ids: Vec<String> = self
+                .0
+                .lock()
+                .unwrap()
+                .keys()
+                .map(|(c, _)| c.clone())
+                .collect();
+            Box::pin(async move { Ok(ids) })
+        }
+
+        fn fields(&self, connection_id: &str) -> BoxFuture<anyhow::Result<Vec<String>>> {
+            let fields: Vec<String> = self
+                .0
+                .lock()
+                .unwrap()
+                .keys()
+                .filter(|(c, _)| c == connection_id)
+                .map(|(_, f)| f.clone())
+                .collect();
+            Box::pin(async move { Ok(fields) })
+        }
+    }
+
+    fn secrets() -> Arc<Box<dyn ManagesSecrets>> {
+        Arc::new(Box::new(MemorySecrets::default()))
+    }
+
+    fn config(secret_manager: &Arc<Box<dyn ManagesSecrets>>) -> ConnectionConfiguration {
+        ConnectionConfiguration::new("conn".into(), "generic".into(), secret_manager.clone())
+    }
+
+    #[test]
+    fn value_round_trip() {
+        let mut c = config(&secrets());
+        c.set_value("host", "example.org");
+        c.set_value("port", 5900u64);
+        c.set_value("negative", -3i64);
+        c.set_value("tls", true);
+
+        assert_eq!(c.get_try_as_str("host"), Some("example.org"));
+        assert_eq!(c.get_try_as_u32("port"), Some(5900));
+        assert_eq!(c.get_try_as_u64("port"), Some(5900));
+        assert_eq!(c.get_try_as_i64("negative"), Some(-3));
+        assert_eq!(c.get_try_as_bool("tls"), Some(true));
+        assert_eq!(c.get_try_as_u32("negative"), None);
+        assert_eq!(c.get_try_as_str("port"), None);
+
+        c.clear("host");
+        assert_eq!(c.get("host"), None);
+    }
+
+    #[test]
+    fn u32_out_of_range() {
+        let mut c = config(&secrets());
+        c.set_value("big", u64::from(u32::MAX) + 1);
+        assert_eq!(c.get_try_as_u32("big"), None);
+        assert_eq!(c.get_try_as_u64("big"), Some(u64::from(u32::MAX) + 1));
+    }
+
+    #[test]
+    fn not_persisted_values() {
+        let mut c = config(&secrets());
+        c.set_value("__session_only", "temp");
+        c.set_secure_string("__password", SecureString::from("hunter2"));
+        c.set_value("kept", "yes");
+
+        assert_eq!(c.get_try_as_str("__session_only"), Some("temp"));
+        assert_eq!(
+            c.get_try_as_sec_string("__password"),
+            Some(SecureString::from("hunter2"))
+        );
+        let saved = block_on(c.save()).unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved.get("kept"), Some(&Value::from("yes")));
+    }
+
+    #[test]
+    fn section_round_trip() {
+        let mut c = config(&secrets());
+        c.with_section_mut("a", |mut s| {
+            s.set_value("key", "in a");
+            s.set_value("__temp", 1u64);
+        });
+        c.with_section_mut("b", |mut s| s.set_value("key", "in b"));
+        c.set_value("key", "at root");
+
+        c.with_section("a", |s| {
+            assert_eq!(s.get_try_as_str("key"), Some("in a"));
+            assert_eq!(s.get_try_as_u64("__temp"), Some(1));
+        });
+        c.with_section("b", |s| {
+            assert_eq!(s.get_try_as_str("key"), Some("in b"));
+            assert_eq!(s.get_try_as_u64("__temp"), None);
+        });
+        c.with_section("missing", |s| assert_eq!(s.get("key"), None));
+        assert_eq!(c.get_try_as_str("key"), Some("at root"));
+
+        let mut keys: Vec<_> = c.section_keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["a", "b"]);
+    }
+
+    #[test]
+    fn section_replaces_non_mapping() {
+        let mut c = config(&secrets());
+        c.set_value("a", "not a section");
+        c.with_section_mut("a", |mut s| s.set_value("key", "value"));
+        c.with_section("a", |s| assert_eq!(s.get_try_as_str("key"), Some("value")));
+    }
+
+    #[test]
+    fn secrets_round_trip() {
+        let secret_manager = secrets();
+        let mut c = config(&secret_manager);
+        c.set_secret("password", SecureString::from("root secret"));
+        c.with_section_mut("a", |mut s| {
+            s.set_secret("password", SecureString::from("section secret"))
+        });
+
+        // Pending secrets are visible before saving...
+        assert_eq!(
+            block_on(c.get_secret("password")).unwrap(),
+            Some(SecureString::from("root secret"))
+        );
+        let saved = block_on(c.save()).unwrap();
+        assert!(!saved.contains_key("password"));
+
+        // ...and are read from the secret manager afterwards.
+        let mut c = ConnectionConfiguration::new_existing(
+            "conn".into(),
+            "generic".into(),
+            saved,
+            secret_manager.clone(),
+        );
+        assert_eq!(
+            block_on(c.get_secret("password")).unwrap(),
+            Some(SecureString::from("root secret"))
+        );
+        let section_secret = block_on(c.with_section_async("a", |s| {
+            Box::pin(async move { s.get_secret("password").await.unwrap() })
+        }));
+        assert_eq!(section_secret, Some(SecureString::from("section secret")));
+
+        c.clear_secret("password");
+        assert_eq!(block_on(c.get_secret("password")).unwrap(), None);
+        block_on(c.save()).unwrap();
+        assert_eq!(
+            block_on(secret_manager.lookup("conn", "password")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn rekey_secrets() {
+        let secret_manager = secrets();
+        let mut c = config(&secret_manager);
+        c.set_secret("password", SecureString::from("secret"));
+        block_on(c.save()).unwrap();
+
+        block_on(secret_manager.rekey("conn", "copy", true)).unwrap();
+        block_on(secret_manager.rekey("conn", "moved", false)).unwrap();
+        for (connection_id, expected) in [
+            ("conn", None),
+            ("copy", Some(SecureString::from("secret"))),
+            ("moved", Some(SecureString::from("secret"))),
+        ] {
+            assert_eq!(
+                block_on(secret_manager.lookup(connection_id, "password")).unwrap(),
+                expected,
+                "{connection_id}"
+            );
+        }
+
+        block_on(secret_manager.clear_all("copy")).unwrap();
+        assert!(block_on(secret_manager.fields("copy")).unwrap().is_empty());
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Typed {
+        host: String,
+        #[serde(default)]
+        port: Option<u16>,
+    }
+
+    #[test]
+    fn typed_round_trip() {
+        let mut c = config(&secrets());
+        let value = Typed {
+            host: "example.org".into(),
+            port: Some(22),
+        };
+        c.set_value("unrelated", "kept");
+        c.set_typed(&value).unwrap();
+        assert_eq!(c.typed::<Typed>().unwrap(), value);
+        assert_eq!(c.get_try_as_str("unrelated"), Some("kept"));
+
+        c.with_section_mut("s", |mut s| s.set_value("extra", "kept"));
+        c.set_typed_section("s", &value).unwrap();
+        assert_eq!(c.typed_section::<Typed>("s").unwrap(), value);
+        c.with_section("s", |s| assert_eq!(s.get_try_as_str("extra"), Some("kept")));
+    }
+
+    #[test]
+    fn typed_errors() {
+        let mut c = config(&secrets());
+        assert!(matches!(
+            c.typed_section::<Typed>("missing"),
+            Err(TypedConfigError::Invalid { section, .. }) if section == "missing"
+        ));
+
+        assert!(matches!(
+            c.set_typed(&42u32),
+            Err(TypedConfigError::NotAMapping(section)) if section == ROOT_SECTION_NAME
+        ));
+    }
+
+    #[test]
+    fn dual_scoped_unified() {
+        let mut c = config(&secrets());
+        c.set_value("host", "example.org");
+        let mut dual = DualScopedConnectionConfiguration::new_unified(c);
+        assert!(Arc::ptr_eq(&dual.session, &dual.persistent));
+
+        // Changing the persistent configuration must not leak into the session.
+        dual.persistent_mut()
+            .set_value("host", "changed.example.org");
+        assert_eq!(
+            dual.persistent().get_try_as_str("host"),
+            Some("changed.example.org")
+        );
+        assert_eq!(dual.session().get_try_as_str("host"), Some("example.org"));
+        assert!(!Arc::ptr_eq(&dual.session, &dual.persistent));
+    }
+
+    #[test]
+    fn dual_scoped_collapses_equal_configs() {
+        let secret_manager = secrets();
+        let mut session = config(&secret_manager);
+        let mut persistent = config(&secret_manager);
+        session.set_value("host", "example.org");
+        persistent.set_value("host", "example.org");
+        let dual = DualScopedConnectionConfiguration::new_separate(session, persistent);
+        assert!(Arc::ptr_eq(&dual.session, &dual.persistent));
+
+        // Different secret managers are never collapsed.
+        let dual = DualScopedConnectionConfiguration::new_separate(
+            config(&secret_manager),
+            config(&secrets()),
+        );
+        assert!(!Arc::ptr_eq(&dual.session, &dual.persistent));
+    }
+
+    #[test]
+    fn dual_scoped_transform() {
+        let dual = DualScopedConnectionConfiguration::new_unified(config(&secrets()));
+
+        let dual = dual
+            .transform_update_unified(|c| {
+                c.set_value("host", "example.org");
+                Ok::<_, ()>(())
+            })
+            .unwrap();
+        assert!(Arc::ptr_eq(&dual.session, &dual.persistent));
+        assert_eq!(dual.session().get_try_as_str("host"), Some("example.org"));
+
+        let dual = dual
+            .transform_update_separate(
+                |c| {
+                    c.set_value("user", "session-user");
+                    Ok::<_, ()>(())
+                },
+                |_| Ok(()),
+            )
+            .unwrap();
+        assert!(!Arc::ptr_eq(&dual.session, &dual.persistent));
+        assert_eq!(dual.session().get_try_as_str("user"), Some("session-user"));
+        assert_eq!(dual.persistent().get_try_as_str("user"), None);
+        assert_eq!(
+            dual.persistent().get_try_as_str("host"),
+            Some("example.org")
+        );
+
+        // Updating both afterwards keeps them separate, errors are passed through.
+        let dual = dual
+            .transform_update_unified(|c| {
+                c.set_value("port", 22u64);
+                Ok::<_, ()>(())
+            })
+            .unwrap();
+        assert_eq!(dual.session().get_try_as_u32("port"), Some(22));
+        assert_eq!(dual.persistent().get_try_as_u32("port"), Some(22));
+        assert!(dual
+            .transform_update_unified(|_| Err::<(), _>("failed"))
+            .is_err());
+    }
+
+    #[test]
+    fn dual_scoped_explicit_clone() {
+        let mut c = config(&secrets());
+        c.set_value("host", "example.org");
+        let dual = DualScopedConnectionConfiguration::new_unified(c);
+        let mut cloned = dual.explicit_clone();
+        cloned
+            .persistent_mut()
+            .set_value("host", "other.example.org");
+        assert_eq!(
+            dual.persistent().get_try_as_str("host"),
+            Some("example.org")
+        );
+        assert_eq!(
+            cloned.persistent().get_try_as_str("host"),
+            Some("other.example.org")
+        );
+    }
+}
@@ -15,20 +15,9 @@
  *
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
-use futures::future::BoxFuture;
-use secure_string::SecureString;
 
-pub trait ManagesSecrets: Send + Sync {
-    fn lookup(
-        &self,
-        connection_id: &str,
-        field: &str,
-    ) -> BoxFuture<anyhow::Result<Option<SecureString>>>;
-    fn store(
-        &self,
-        connection_id: &str,
-        field: &str,
-        password: SecureString,
-    ) -> BoxFuture<anyhow::Result<()>>;
-    fn clear(&self, connection_id: &str, field: &str) -> BoxFuture<anyhow::Result<()>>;
-}
+pub use config_value::*;
+pub use configuration::*;
+
+mod config_value;
+mod configuration;
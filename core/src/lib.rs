@@ -0,0 +1,28 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! UI-independent parts of Field Monitor: the connection configuration model, the secrets
+//! abstraction, template variables and the setup of network connections.
+//!
+//! Nothing in here depends on GTK, so it can be used without initializing it. `libfieldmonitor`
+//! re-exports everything under its previous paths.
+pub use secrets::ManagesSecrets;
+
+pub mod connection;
+pub mod net;
+mod secrets;
+pub mod template;
@@ -0,0 +1,201 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Shared setup of network connections, so that all connection providers and PTY drivers
+//! handle proxies, certificate checks and timeouts the same way.
+//!
+//! HTTP clients require the `http` feature, TLS connections for websockets the `websocket`
+//! feature.
+use std::time::Duration;
+
+use log::debug;
+use thiserror::Error;
+
+/// Time after which establishing a connection is given up, if not configured otherwise.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How the certificate of the server is checked.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TlsVerification {
+    /// The certificate must be issued for the host by a CA trusted by the system.
+    #[default]
+    System,
+    /// This certificate (PEM encoded) is the only trusted root, eg. the self-signed certificate
+    /// of a server or the certificate of a private CA. The server certificate must be this
+    /// certificate or be issued by it, and it must be issued for the host.
+    Pinned(String),
+    /// Any certificate is accepted.
+    Disabled,
+}
+
+impl TlsVerification {
+    /// [`Self::Disabled`] if `ignore_errors` is set, [`Self::System`] otherwise.
+    pub fn from_ignore_errors(ignore_errors: bool) -> Self {
+        if ignore_errors {
+            Self::Disabled
+        } else {
+            Self::System
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NetConfig {
+    /// Proxy URL for all requests, eg. `http://proxy:3128` or `socks5://proxy:1080`. If not
+    /// set, the proxy is taken from the environment (`https_proxy` and friends).
+    /// Only applies to HTTP clients.
+    pub proxy: Option<String>,
+    pub tls: TlsVerification,
+    pub connect_timeout: Duration,
+    /// Time after which whole requests are given up. Not set by default, as some requests are
+    /// expected to take long, eg. waiting for a task to finish.
+    pub request_timeout: Option<Duration>,
+}
+
+impl Default for NetConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            tls: TlsVerification::default(),
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            request_timeout: None,
+        }
+    }
+}
+
+impl NetConfig {
+    pub fn with_tls(mut self, tls: TlsVerification) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy: Option<String>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    pub fn with_request_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum NetError {
+    #[cfg(feature = "http")]
+    #[error("http: {0}")]
+    Http(#[from] reqwest::Error),
+    #[cfg(feature = "websocket")]
+    #[error("tls: {0}")]
+    Tls(#[from] openssl::error::ErrorStack),
+}
+
+/// Creates an HTTP client for `config`. Connections are logged at trace level.
+#[cfg(feature = "http")]
+pub fn http_client(config: &NetConfig) -> Result<reqwest::Client, NetError> {
+    debug!(
+        "creating http client (proxy: {:?}, tls: {})",
+        config.proxy,
+        tls_description(&config.tls)
+    );
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(config.connect_timeout)
+        .connection_verbose(log::log_enabled!(log::Level::Trace));
+    if let Some(timeout) = config.request_timeout {
+        builder = builder.timeout(timeout);
+    }
+    if let Some(proxy) = &config.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    builder = match &config.tls {
+        TlsVerification::System => builder,
+        TlsVerification::Pinned(pem) => builder
+            .tls_built_in_root_certs(false)
+            .add_root_certificate(reqwest::Certificate::from_pem(pem.as_bytes())?),
+        TlsVerification::Disabled => builder.danger_accept_invalid_certs(true),
+    };
+    Ok(builder.build()?)
+}
+
+/// Creates the TLS configuration for a single connection (eg. a websocket) according to
+/// `config`.
+#[cfg(feature = "websocket")]
+pub fn tls_connect_configuration(
+    config: &NetConfig,
+) -> Result<openssl::ssl::ConnectConfiguration, NetError> {
+    use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+    use openssl::x509::store::X509StoreBuilder;
+    use openssl::x509::X509;
+
+    debug!(
+        "creating tls connection configuration (tls: {})",
+        tls_description(&config.tls)
+    );
+    let mut builder = SslConnector::builder(SslMethod::tls())?;
+    match &config.tls {
+        TlsVerification::System => {}
+        TlsVerification::Pinned(pem) => {
+            let mut store = X509StoreBuilder::new()?;
+            store.add_cert(X509::from_pem(pem.as_bytes())?)?;
+            builder.set_cert_store(store.build());
+        }
+        TlsVerification::Disabled => builder.set_verify(SslVerifyMode::NONE),
+    }
+    Ok(builder.build().configure()?)
+}
+
+fn tls_description(tls: &TlsVerification) -> &'static str {
+    match tls {
+        TlsVerification::System => "system",
+        TlsVerification::Pinned(_) => "pinned",
+        TlsVerification::Disabled => "disabled",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tls_from_ignore_errors() {
+        assert_eq!(
+            TlsVerification::from_ignore_errors(true),
+            TlsVerification::Disabled
+        );
+        assert_eq!(
+            TlsVerification::from_ignore_errors(false),
+            TlsVerification::System
+        );
+    }
+
+    #[test]
+    fn config_builders() {
+        let config = NetConfig::default();
+        assert_eq!(config.connect_timeout, DEFAULT_CONNECT_TIMEOUT);
+        assert_eq!(config.tls, TlsVerification::System);
+        assert_eq!(config.proxy, None);
+        assert_eq!(config.request_timeout, None);
+
+        let config = config
+            .with_tls(TlsVerification::Pinned("pem".into()))
+            .with_proxy(Some("socks5://proxy:1080".into()))
+            .with_request_timeout(Some(Duration::from_secs(1)));
+        assert_eq!(config.tls, TlsVerification::Pinned("pem".into()));
+        assert_eq!(config.proxy.as_deref(), Some("socks5://proxy:1080"));
+        assert_eq!(config.request_timeout, Some(Duration::from_secs(1)));
+    }
+}
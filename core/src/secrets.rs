@@ -0,0 +1,72 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+use futures::future::BoxFuture;
+use secure_string::SecureString;
+
+pub trait ManagesSecrets: Send + Sync {
+    fn lookup(
+        &self,
+        connection_id: &str,
+        field: &str,
+    ) -> BoxFuture<anyhow::Result<Option<SecureString>>>;
+    fn store(
+        &self,
+        connection_id: &str,
+        field: &str,
+        password: SecureString,
+    ) -> BoxFuture<anyhow::Result<()>>;
+    fn clear(&self, connection_id: &str, field: &str) -> BoxFuture<anyhow::Result<()>>;
+    /// Returns the IDs of all connections that have at least one secret stored.
+    fn connection_ids(&self) -> BoxFuture<anyhow::Result<Vec<String>>>;
+    /// Returns the names of all fields that have a secret stored for the connection.
+    fn fields(&self, connection_id: &str) -> BoxFuture<anyhow::Result<Vec<String>>>;
+
+    /// Moves all secrets of a connection to a new connection ID. If `keep_old` is set, the
+    /// secrets are copied instead and stay available under the old ID.
+    fn rekey(
+        &self,
+        old_connection_id: &str,
+        new_connection_id: &str,
+        keep_old: bool,
+    ) -> BoxFuture<anyhow::Result<()>> {
+        let old_connection_id = old_connection_id.to_string();
+        let new_connection_id = new_connection_id.to_string();
+        Box::pin(async move {
+            for field in self.fields(&old_connection_id).await? {
+                if let Some(secret) = self.lookup(&old_connection_id, &field).await? {
+                    self.store(&new_connection_id, &field, secret).await?;
+                    if !keep_old {
+                        self.clear(&old_connection_id, &field).await?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Removes all secrets of a connection.
+    fn clear_all(&self, connection_id: &str) -> BoxFuture<anyhow::Result<()>> {
+        let connection_id = connection_id.to_string();
+        Box::pin(async move {
+            for field in self.fields(&connection_id).await? {
+                self.clear(&connection_id, &field).await?;
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,172 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Template variables (`${NAME}`) in connection configuration values.
+//!
+//! Variables are resolved from the user-defined variables (see [`set_template_variables`])
+//! first and the process environment second.
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::RwLock;
+
+static TEMPLATE_VARIABLES: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+
+/// Replaces the user-defined template variables.
+pub fn set_template_variables(variables: BTreeMap<String, String>) {
+    *TEMPLATE_VARIABLES.write().unwrap() = variables;
+}
+
+/// Whether `name` can be used as the name of a template variable.
+pub fn is_valid_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Expands all `${NAME}` placeholders in `input`. `$$` is replaced by a single `$`.
+/// Placeholders of unknown variables are left untouched.
+pub fn expand_template(input: &str) -> String {
+    if !input.contains('$') {
+        return input.to_string();
+    }
+    expand_template_with(input, lookup_variable)
+}
+
+/// The value of the user-defined or environment variable `name`.
+pub fn lookup_variable(name: &str) -> Option<String> {
+    TEMPLATE_VARIABLES
+        .read()
+        .unwrap()
+        .get(name)
+        .cloned()
+        .or_else(|| env::var(name).ok())
+}
+
+/// Like [`expand_template`], but resolves variables using `lookup` only.
+pub fn expand_template_with(input: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        if let Some(after) = rest.strip_prefix("$$") {
+            out.push('$');
+            rest = after;
+            continue;
+        }
+
+        let replacement = rest
+            .strip_prefix("${")
+            .and_then(|after| after.find('}').map(|end| &after[..end]))
+            .filter(|name| is_valid_variable_name(name))
+            .and_then(|name| lookup(name).map(|value| (name.len() + 3, value)));
+
+        match replacement {
+            Some((placeholder_len, value)) => {
+                out.push_str(&value);
+                rest = &rest[placeholder_len..];
+            }
+            None => {
+                out.push('$');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOST" => Some("example.org".into()),
+            "PORT_1" => Some("5900".into()),
+            "EMPTY" => Some(String::new()),
+            "DOLLAR" => Some("${HOST}".into()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expand() {
+        for (input, expected) in [
+            ("", ""),
+            ("no variables", "no variables"),
+            ("${HOST}", "example.org"),
+            ("${HOST}:${PORT_1}", "example.org:5900"),
+            ("a${EMPTY}b", "ab"),
+            ("ünïcödé ${HOST} ✓", "ünïcödé example.org ✓"),
+            // Values are not expanded recursively.
+            ("${DOLLAR}", "${HOST}"),
+        ] {
+            assert_eq!(expand_template_with(input, lookup), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn expand_escapes() {
+        for (input, expected) in [
+            ("$$", "$"),
+            ("$${HOST}", "${HOST}"),
+            ("$$$${HOST}", "$${HOST}"),
+            ("$$${HOST}", "$example.org"),
+            ("price: 5$", "price: 5$"),
+            ("$HOST", "$HOST"),
+        ] {
+            assert_eq!(expand_template_with(input, lookup), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn expand_missing_or_invalid() {
+        for (input, expected) in [
+            ("${MISSING}", "${MISSING}"),
+            ("${MISSING}-${HOST}", "${MISSING}-example.org"),
+            ("${}", "${}"),
+            ("${HOST", "${HOST"),
+            ("${NOT VALID}", "${NOT VALID}"),
+            ("${HO-ST}", "${HO-ST}"),
+            ("${${HOST}}", "${example.org}"),
+        ] {
+            assert_eq!(expand_template_with(input, lookup), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn variable_names() {
+        assert!(is_valid_variable_name("HOST"));
+        assert!(is_valid_variable_name("host_2"));
+        assert!(!is_valid_variable_name(""));
+        assert!(!is_valid_variable_name("HO ST"));
+        assert!(!is_valid_variable_name("HÖST"));
+    }
+
+    #[test]
+    fn user_variables_before_environment() {
+        let name = "FIELD_MONITOR_TEMPLATE_TEST";
+        std::env::set_var(name, "from env");
+        assert_eq!(lookup_variable(name).as_deref(), Some("from env"));
+        set_template_variables(BTreeMap::from([(name.to_string(), "user".to_string())]));
+        assert_eq!(expand_template(&format!("${{{name}}}")), "user");
+        set_template_variables(BTreeMap::new());
+        assert_eq!(expand_template(&format!("${{{name}}}")), "from env");
+        std::env::remove_var(name);
+    }
+}
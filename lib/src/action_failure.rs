@@ -0,0 +1,56 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Reporting of failed server actions to whoever executes them.
+//!
+//! Actions only return whether the connection should be reloaded and show their result as a
+//! toast. Actions that failed additionally call [`report_action_failure`], which is forwarded to
+//! the observer of the future executing the action, see [`observe_action_failures`].
+use std::cell::RefCell;
+use std::future::Future;
+use std::rc::Rc;
+
+type Observer = Rc<dyn Fn(&str)>;
+
+thread_local! {
+    static OBSERVER: RefCell<Option<Observer>> = const { RefCell::new(None) };
+}
+
+/// Reports that the action currently being executed failed with the user-facing `message`.
+/// Does nothing if the action is not observed.
+pub fn report_action_failure(message: &str) {
+    let observer = OBSERVER.with_borrow(Clone::clone);
+    if let Some(observer) = observer {
+        observer(message);
+    }
+}
+
+/// Wraps the future `action` so that failures it reports with [`report_action_failure`] are
+/// passed to `observer`.
+pub fn observe_action_failures<F: Future>(
+    action: F,
+    observer: impl Fn(&str) + 'static,
+) -> impl Future<Output = F::Output> {
+    let mut action = Box::pin(action);
+    let observer: Observer = Rc::new(observer);
+    futures::future::poll_fn(move |cx| {
+        let previous = OBSERVER.replace(Some(observer.clone()));
+        let result = action.as_mut().poll(cx);
+        OBSERVER.set(previous);
+        result
+    })
+}
@@ -0,0 +1,116 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Adapters whose connection depends on something else staying open, eg. a tunnel.
+use std::rc::Rc;
+
+use crate::adapter::types::{Adapter, AdapterDisplay, AdapterDisplayWidget};
+use crate::connection::ConnectionError;
+
+/// Something the connection of a wrapped adapter depends on, eg. a tunnel to the server.
+pub trait AdapterGuard: Send + Sync + 'static {
+    /// Whether the guard still works, so that the display may reconnect on its own.
+    fn is_open(&self) -> bool;
+
+    /// Closes whatever the guard keeps open. Does nothing if it is already closed.
+    fn close(&self);
+}
+
+/// Wraps another adapter and keeps a guard for as long as the display exists. Everything else is
+/// forwarded to the inner adapter.
+pub struct GuardedAdapter<G: AdapterGuard> {
+    guard: G,
+    inner: Box<dyn Adapter>,
+}
+
+impl<G: AdapterGuard> GuardedAdapter<G> {
+    pub fn new(guard: G, inner: Box<dyn Adapter>) -> Self {
+        Self { guard, inner }
+    }
+}
+
+impl<G: AdapterGuard> Adapter for GuardedAdapter<G> {
+    fn create_and_connect_display(
+        self: Box<Self>,
+        on_connected: Rc<dyn Fn()>,
+        on_disconnected: Rc<dyn Fn(Result<(), ConnectionError>)>,
+    ) -> Box<dyn AdapterDisplay> {
+        let inner = self
+            .inner
+            .create_and_connect_display(on_connected, on_disconnected);
+        Box::new(GuardedAdapterDisplay {
+            inner,
+            guard: self.guard,
+        })
+    }
+
+    fn microphone(&self) -> Option<bool> {
+        self.inner.microphone()
+    }
+
+    fn set_microphone(&mut self, redirect: bool) {
+        self.inner.set_microphone(redirect)
+    }
+
+    fn set_printers(&mut self, printers: &[String]) {
+        self.inner.set_printers(printers)
+    }
+}
+
+/// Display of a [`GuardedAdapter`]. Closing it also closes the guard, it only reconnects on its
+/// own while the guard is open.
+pub struct GuardedAdapterDisplay<G: AdapterGuard> {
+    inner: Box<dyn AdapterDisplay>,
+    guard: G,
+}
+
+impl<G: AdapterGuard> AdapterDisplay for GuardedAdapterDisplay<G> {
+    fn widget(&self) -> AdapterDisplayWidget {
+        self.inner.widget()
+    }
+
+    fn close(&self) {
+        self.inner.close();
+        self.guard.close();
+    }
+
+    fn reconnect(&self) -> bool {
+        self.guard.is_open() && self.inner.reconnect()
+    }
+
+    fn supports_folder_sharing(&self) -> bool {
+        self.inner.supports_folder_sharing()
+    }
+
+    fn is_sharing_folder(&self) -> bool {
+        self.inner.is_sharing_folder()
+    }
+
+    fn set_sharing_folder(&self, share: bool) {
+        self.inner.set_sharing_folder(share)
+    }
+
+    fn connect_remote_title_changed(&self, callback: Rc<dyn Fn(Option<String>)>) {
+        self.inner.connect_remote_title_changed(callback)
+    }
+}
+
+impl<G: AdapterGuard> Drop for GuardedAdapterDisplay<G> {
+    fn drop(&mut self) {
+        self.close()
+    }
+}
@@ -16,8 +16,13 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 
+pub mod guarded;
+pub mod port_knock;
+pub mod proxy_chain;
 pub mod rdp;
 pub mod spice;
+pub mod ssh_tunnel;
 pub mod types;
 pub mod vnc;
 pub mod vte_pty;
+pub mod web;
@@ -0,0 +1,171 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Port knocking: contacting a sequence of closed ports, so that a firewall on the host opens the
+//! port of the actual service.
+use std::fmt;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use gtk::gio;
+use gtk::glib;
+use gtk::prelude::*;
+use log::debug;
+
+use crate::adapter::guarded::{AdapterGuard, GuardedAdapter};
+use crate::host::format_host_port;
+
+/// Default time to wait after a knock.
+pub const DEFAULT_KNOCK_DELAY: Duration = Duration::from_millis(200);
+/// How long a TCP knock waits for its connection attempt. Knock daemons only look at the first
+/// packet and knocked ports usually drop it, so there is no point in waiting for an answer.
+const TCP_KNOCK_TIMEOUT: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KnockProtocol {
+    #[default]
+    Tcp,
+    Udp,
+}
+
+impl KnockProtocol {
+    pub fn tag(self) -> &'static str {
+        match self {
+            KnockProtocol::Tcp => "tcp",
+            KnockProtocol::Udp => "udp",
+        }
+    }
+}
+
+impl TryFrom<&str> for KnockProtocol {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "tcp" => Ok(KnockProtocol::Tcp),
+            "udp" => Ok(KnockProtocol::Udp),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A single knock of a knock sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortKnock {
+    /// Host to knock at. If not set, the host of the server is used.
+    pub host: Option<String>,
+    pub port: u16,
+    pub protocol: KnockProtocol,
+    /// Time to wait after this knock, before the next knock or connecting.
+    pub delay: Duration,
+}
+
+impl PortKnock {
+    pub fn new(port: u16) -> Self {
+        Self {
+            host: None,
+            port,
+            protocol: KnockProtocol::default(),
+            delay: DEFAULT_KNOCK_DELAY,
+        }
+    }
+}
+
+/// Formats the knock as `host:port/protocol`, leaving out the host if it is not set.
+impl fmt::Display for PortKnock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.host.as_deref().filter(|host| !host.is_empty()) {
+            Some(host) => write!(f, "{}", format_host_port(host, self.port))?,
+            None => write!(f, "{}", self.port)?,
+        }
+        write!(f, "/{}", self.protocol.tag())
+    }
+}
+
+/// Sends the knocks of `sequence` in order, waiting for the delay of each knock after sending it.
+/// Knocks without a host are sent to `server_host`.
+///
+/// Whether the knocked ports answer does not matter, only failing to resolve a host or to send a
+/// packet is an error.
+pub async fn knock(sequence: &[PortKnock], server_host: &str) -> anyhow::Result<()> {
+    for knock in sequence {
+        let host = knock
+            .host
+            .as_deref()
+            .filter(|host| !host.is_empty())
+            .unwrap_or(server_host);
+        let addresses = gio::Resolver::default()
+            .lookup_by_name_future(host)
+            .await
+            .map_err(|err| anyhow!("failed to resolve {host}: {err}"))?;
+        let address = addresses
+            .first()
+            .ok_or_else(|| anyhow!("no address found for {host}"))?;
+        let socket_address = gio::InetSocketAddress::new(address, knock.port);
+
+        debug!(
+            "knocking at {}/{}",
+            format_host_port(&address.to_str(), knock.port),
+            knock.protocol.tag()
+        );
+        match knock.protocol {
+            KnockProtocol::Tcp => {
+                let client = gio::SocketClient::new();
+                // The knock must reach the host itself, not a proxy.
+                client.set_enable_proxy(false);
+                // Dropping the connection attempt on timeout cancels it.
+                if let Ok(Ok(connection)) = glib::future_with_timeout(
+                    TCP_KNOCK_TIMEOUT,
+                    client.connect_future(&socket_address),
+                )
+                .await
+                {
+                    connection.close_future(glib::Priority::DEFAULT).await.ok();
+                }
+            }
+            KnockProtocol::Udp => {
+                let socket = gio::Socket::new(
+                    address.family(),
+                    gio::SocketType::Datagram,
+                    gio::SocketProtocol::Udp,
+                )?;
+                socket.send_to(Some(&socket_address), [0u8], gio::Cancellable::NONE)?;
+                socket.close()?;
+            }
+        }
+        glib::timeout_future(knock.delay).await;
+    }
+    Ok(())
+}
+
+/// Guard of adapters that connect to a server after knocking at it with [`knock`].
+///
+/// It never counts as open, so that displays never reconnect on their own: a new adapter is
+/// created and the sequence is sent again before every reconnect.
+pub struct KnockSent;
+
+impl AdapterGuard for KnockSent {
+    fn is_open(&self) -> bool {
+        false
+    }
+
+    fn close(&self) {}
+}
+
+/// Wraps another adapter that connects to a server after knocking at it with [`knock`]. The knock
+/// sequence must already have been sent.
+pub type PortKnockAdapter = GuardedAdapter<KnockSent>;
@@ -0,0 +1,300 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Connections through a chain of SOCKS5 and HTTP proxies.
+//!
+//! Display adapters connect to servers on their own, so the chain is offered to them as a local
+//! port: every connection to it is forwarded through all proxies to the target endpoint. Each
+//! proxy is reached through the previous one, the handshakes are done by the proxy
+//! implementations of GIO.
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::rc::Rc;
+
+use anyhow::anyhow;
+use gtk::gio;
+use gtk::glib;
+use gtk::prelude::*;
+use log::{debug, warn};
+
+use crate::adapter::guarded::{AdapterGuard, GuardedAdapter};
+use crate::host::format_host_port;
+
+const CONNECT_TIMEOUT_SECS: u32 = 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProxyProtocol {
+    #[default]
+    Socks5,
+    /// HTTP proxies supporting the `CONNECT` method.
+    Http,
+}
+
+impl ProxyProtocol {
+    /// Name of the protocol, as used by GIO.
+    pub fn tag(self) -> &'static str {
+        match self {
+            ProxyProtocol::Socks5 => "socks5",
+            ProxyProtocol::Http => "http",
+        }
+    }
+
+    pub fn default_port(self) -> u16 {
+        match self {
+            ProxyProtocol::Socks5 => 1080,
+            ProxyProtocol::Http => 8080,
+        }
+    }
+}
+
+impl TryFrom<&str> for ProxyProtocol {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "socks5" => Ok(ProxyProtocol::Socks5),
+            "http" => Ok(ProxyProtocol::Http),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A single proxy of a chain. Authentication is not supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyHop {
+    pub protocol: ProxyProtocol,
+    pub host: String,
+    pub port: u16,
+}
+
+impl ProxyHop {
+    pub fn new(protocol: ProxyProtocol, host: String) -> Self {
+        Self {
+            protocol,
+            host,
+            port: protocol.default_port(),
+        }
+    }
+}
+
+/// Formats the proxy as a URI, eg. `socks5://example.com:1080`.
+impl fmt::Display for ProxyHop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}://{}",
+            self.protocol.tag(),
+            format_host_port(&self.host, self.port)
+        )
+    }
+}
+
+/// A local port forwarded through a chain of proxies to a target endpoint.
+///
+/// Forwarding stops when the chain is closed or dropped.
+pub struct ProxyChain {
+    local_port: u16,
+    /// Cancelled when the chain is closed, stops the listener and all forwarded connections.
+    cancellable: gio::Cancellable,
+}
+
+impl ProxyChain {
+    /// Connects through the chain once to check that it works, then starts listening on a local
+    /// port. `hops` must not be empty.
+    pub async fn open(
+        hops: &[ProxyHop],
+        target_host: &str,
+        target_port: u16,
+    ) -> anyhow::Result<Self> {
+        if hops.is_empty() {
+            return Err(anyhow!("no proxies given"));
+        }
+        let hops: Rc<[ProxyHop]> = hops.into();
+        let target_host: Rc<str> = target_host.into();
+
+        connect_through(&hops, &target_host, target_port)
+            .await?
+            .close_future(glib::Priority::DEFAULT)
+            .await
+            .ok();
+
+        let service = gio::SocketService::new();
+        let local_address = service
+            .add_address(
+                &gio::InetSocketAddress::new(
+                    &gio::InetAddress::new_loopback(gio::SocketFamily::Ipv4),
+                    0,
+                ),
+                gio::SocketType::Stream,
+                gio::SocketProtocol::Tcp,
+                None::<&glib::Object>,
+            )?
+            .downcast::<gio::InetSocketAddress>()
+            .map_err(|_| anyhow!("listening on an unexpected address"))?;
+
+        let cancellable = gio::Cancellable::new();
+        service.connect_incoming(glib::clone!(
+            #[strong]
+            cancellable,
+            move |_, connection, _| {
+                glib::spawn_future_local(gio::CancellableFuture::new(
+                    forward(
+                        connection.clone(),
+                        hops.clone(),
+                        target_host.clone(),
+                        target_port,
+                    ),
+                    cancellable.clone(),
+                ));
+                true
+            }
+        ));
+        glib::spawn_future_local(glib::clone!(
+            #[strong]
+            cancellable,
+            async move {
+                cancellable.future().await;
+                service.stop();
+                service.close();
+            }
+        ));
+
+        Ok(Self {
+            local_port: local_address.port(),
+            cancellable,
+        })
+    }
+
+    /// The local port connections to the target endpoint should be made to.
+    pub fn local_port(&self) -> u16 {
+        self.local_port
+    }
+
+    /// The local host connections to the target endpoint should be made to.
+    pub fn local_host(&self) -> String {
+        Ipv4Addr::LOCALHOST.to_string()
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.cancellable.is_cancelled()
+    }
+
+    /// Stops listening and closes all forwarded connections. Does nothing if the chain is already
+    /// closed.
+    pub fn close(&self) {
+        self.cancellable.cancel();
+    }
+}
+
+impl Drop for ProxyChain {
+    fn drop(&mut self) {
+        self.close()
+    }
+}
+
+/// Connects to the first proxy and asks every proxy to connect to the next one, the last one to
+/// the target endpoint.
+async fn connect_through(
+    hops: &[ProxyHop],
+    target_host: &str,
+    target_port: u16,
+) -> anyhow::Result<gio::IOStream> {
+    let first = &hops[0];
+    let client = gio::SocketClient::new();
+    client.set_timeout(CONNECT_TIMEOUT_SECS);
+    // The chain replaces the proxy configured in the system.
+    client.set_enable_proxy(false);
+    let mut stream = client
+        .connect_to_host_future(&format_host_port(&first.host, first.port), first.port)
+        .await
+        .map_err(|err| anyhow!("failed to connect to proxy {first}: {err}"))?
+        .upcast::<gio::IOStream>();
+
+    for (i, hop) in hops.iter().enumerate() {
+        let (dest_host, dest_port) = match hops.get(i + 1) {
+            Some(next_hop) => (next_hop.host.as_str(), next_hop.port),
+            None => (target_host, target_port),
+        };
+        let proxy = gio::Proxy::for_protocol(hop.protocol.tag())
+            .ok_or_else(|| anyhow!("{} proxies are not supported", hop.protocol.tag()))?;
+        // The handshake only needs the destination. Proxies after the first one may not even be
+        // resolvable from here, so a placeholder is given as the address of the proxy.
+        let address = gio::ProxyAddress::new(
+            &gio::InetAddress::new_loopback(gio::SocketFamily::Ipv4),
+            hop.port,
+            hop.protocol.tag(),
+            dest_host,
+            dest_port,
+            None,
+            None,
+        );
+        stream = proxy
+            .connect_future(&stream, &address)
+            .await
+            .map_err(|err| {
+                anyhow!(
+                    "proxy {hop} failed to connect to {}: {err}",
+                    format_host_port(dest_host, dest_port)
+                )
+            })?;
+    }
+    Ok(stream)
+}
+
+/// Forwards a local connection through the chain until either side closes it.
+async fn forward(
+    local: gio::SocketConnection,
+    hops: Rc<[ProxyHop]>,
+    target_host: Rc<str>,
+    target_port: u16,
+) {
+    let remote = match connect_through(&hops, &target_host, target_port).await {
+        Ok(remote) => remote,
+        Err(err) => {
+            warn!("failed to connect through proxies: {err}");
+            local.close_future(glib::Priority::DEFAULT).await.ok();
+            return;
+        }
+    };
+    if let Err(err) = remote
+        .splice_future(
+            &local,
+            gio::IOStreamSpliceFlags::CLOSE_STREAM1 | gio::IOStreamSpliceFlags::CLOSE_STREAM2,
+            glib::Priority::DEFAULT,
+        )
+        .await
+    {
+        debug!("forwarded connection through proxies closed: {err}");
+    }
+}
+
+impl AdapterGuard for ProxyChain {
+    fn is_open(&self) -> bool {
+        ProxyChain::is_open(self)
+    }
+
+    fn close(&self) {
+        ProxyChain::close(self)
+    }
+}
+
+/// Wraps another adapter that connects through a [`ProxyChain`]. The chain is kept open for as
+/// long as the display exists.
+///
+/// The inner adapter must be configured to connect to [`ProxyChain::local_host`] and
+/// [`ProxyChain::local_port`].
+pub type ProxyChainAdapter = GuardedAdapter<ProxyChain>;
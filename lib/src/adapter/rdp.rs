@@ -31,9 +31,19 @@ use crate::connection::ConnectionError;
 
 pub struct RdpAdapter {
     host: String,
+    /// Address to connect to instead of resolving `host`. The host name is still used for
+    /// validating the certificate and Kerberos.
+    address: Option<String>,
     port: u32,
     user: String,
     password: SecureString,
+    /// Redirect the local microphone to the remote server.
+    microphone: bool,
+    /// Names of the local printers shared with the remote server.
+    printers: Vec<String>,
+    /// Log in with the Kerberos tickets in the credential cache of the user instead of the
+    /// password.
+    kerberos: bool,
 }
 
 impl RdpAdapter {
@@ -42,12 +52,35 @@ impl RdpAdapter {
     pub fn new(host: String, port: u32, user: String, password: SecureString) -> Self {
         Self {
             host,
+            address: None,
             port,
             user,
             password,
+            microphone: false,
+            printers: Vec::new(),
+            kerberos: false,
         }
     }
 
+    /// Redirects the local microphone to the remote server, if `microphone` is true.
+    pub fn with_microphone(mut self, microphone: bool) -> Self {
+        self.microphone = microphone;
+        self
+    }
+
+    /// Connects to `address` instead of resolving the host, if set.
+    pub fn with_address(mut self, address: Option<String>) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// Authenticates with Kerberos (through CredSSP) only, if `kerberos` is true. The password is
+    /// then not used.
+    pub fn with_kerberos(mut self, kerberos: bool) -> Self {
+        self.kerberos = kerberos;
+        self
+    }
+
     pub fn label() -> Cow<'static, str> {
         gettext("RDP").into()
     }
@@ -63,11 +96,39 @@ impl Adapter for RdpAdapter {
 
         let settings_result = rdp.with_settings(|s| {
             s.set_server_port(self.port);
-            s.set_server_hostname(Some(self.host.as_str()))?;
-            s.set_username(Some(self.user.as_str()))?;
-            s.set_password(Some(self.password.unsecure()))?;
+            s.set_server_hostname(Some(self.address.as_deref().unwrap_or(&self.host)))?;
+            if !self.user.is_empty() || !self.kerberos {
+                s.set_username(Some(self.user.as_str()))?;
+            }
+            if !self.kerberos {
+                s.set_password(Some(self.password.unsecure()))?;
+            }
             s.set_remote_fx_codec(true);
-            s.parse_command_line(&["field-monitor", "/rfx", "/dynamic-resolution"], true)?;
+            let printer_args = self
+                .printers
+                .iter()
+                .map(|printer| format!("/printer:{printer}"))
+                .collect::<Vec<_>>();
+            let mut args = vec![
+                "field-monitor",
+                "/rfx",
+                "/dynamic-resolution",
+                "+auto-reconnect",
+            ];
+            if self.microphone {
+                args.push("/microphone");
+            }
+            if self.kerberos {
+                // Network level authentication is required for CredSSP, NTLM would need a
+                // password.
+                args.extend(["/sec:nla", "/auth-pkg-list:kerberos,!ntlm"]);
+            }
+            let server_name = format!("/server-name:{}", self.host);
+            if self.address.is_some() {
+                args.push(&server_name);
+            }
+            args.extend(printer_args.iter().map(String::as_str));
+            s.parse_command_line(&args, true)?;
             Ok(())
         });
 
@@ -89,20 +150,39 @@ impl Adapter for RdpAdapter {
             }
         });
 
-        glib::spawn_future_local(clone!(
-            #[weak]
+        connect(&rdp, on_disconnected.clone());
+
+        Box::new(RdpAdapterDisplay {
             rdp,
-            async move {
-                if rdp.rdp_connect().await.is_err() {
-                    handle_rdp_error(&rdp, &on_disconnected);
-                }
-            }
-        ));
+            on_disconnected,
+        })
+    }
+
+    fn microphone(&self) -> Option<bool> {
+        Some(self.microphone)
+    }
 
-        Box::new(RdpAdapterDisplay(rdp))
+    fn set_microphone(&mut self, redirect: bool) {
+        self.microphone = redirect;
+    }
+
+    fn set_printers(&mut self, printers: &[String]) {
+        self.printers = printers.to_vec();
     }
 }
 
+fn connect(rdp: &rdw_rdp::Display, on_disconnected: Rc<dyn Fn(Result<(), ConnectionError>)>) {
+    glib::spawn_future_local(clone!(
+        #[weak]
+        rdp,
+        async move {
+            if rdp.rdp_connect().await.is_err() {
+                handle_rdp_error(&rdp, &on_disconnected);
+            }
+        }
+    ));
+}
+
 fn handle_rdp_error(
     rdp: &rdw_rdp::Display,
     on_disconnected: &Rc<dyn Fn(Result<(), ConnectionError>)>,
@@ -137,19 +217,33 @@ fn handle_rdp_error(
     }
 }
 
-pub struct RdpAdapterDisplay(rdw_rdp::Display);
+pub struct RdpAdapterDisplay {
+    rdp: rdw_rdp::Display,
+    on_disconnected: Rc<dyn Fn(Result<(), ConnectionError>)>,
+}
 
 impl AdapterDisplay for RdpAdapterDisplay {
     fn widget(&self) -> AdapterDisplayWidget {
-        AdapterDisplayWidget::Rdw(self.0.clone().upcast())
+        AdapterDisplayWidget::Rdw(self.rdp.clone().upcast())
     }
 
     fn close(&self) {
-        let rdp = self.0.clone();
+        let rdp = self.rdp.clone();
         glib::spawn_future_local(async move {
             rdp.rdp_disconnect().await.ok();
         });
     }
+
+    fn reconnect(&self) -> bool {
+        if self.rdp.rdp_connected() {
+            return false;
+        }
+        // The settings still contain the credentials and the auto-reconnect cookie of the
+        // previous session.
+        debug!("RDP connection reconnecting");
+        connect(&self.rdp, self.on_disconnected.clone());
+        true
+    }
 }
 
 impl Drop for RdpAdapterDisplay {
@@ -32,6 +32,7 @@ use secure_string::SecureString;
 
 use crate::adapter::types::{Adapter, AdapterDisplay, AdapterDisplayWidget};
 use crate::connection::ConnectionError;
+use crate::host::format_host_port;
 
 #[derive(Builder, Debug, Clone, Default)]
 #[builder(pattern = "owned")]
@@ -53,6 +54,12 @@ pub struct SpiceSessionConfig {
     tls_port: Option<NonZeroU32>,
     #[builder(default = "None")]
     proxy: Option<String>,
+    /// Local folder to share with the guest via the WebDAV channel.
+    #[builder(default = "None")]
+    shared_dir: Option<String>,
+    /// Share local smartcard readers with the guest.
+    #[builder(default = "false")]
+    smartcard: bool,
 }
 
 impl SpiceSessionConfig {
@@ -83,6 +90,10 @@ impl SpiceSessionConfig {
         if self.proxy.is_some() {
             session.set_proxy(self.proxy.as_deref());
         }
+        if self.shared_dir.is_some() {
+            session.set_property("shared-dir", self.shared_dir.as_deref());
+        }
+        session.set_property("enable-smartcard", self.smartcard);
     }
 }
 
@@ -93,7 +104,7 @@ impl SpiceAdapter {
 
     pub fn new(host: String, port: u32, user: String, password: SecureString) -> Self {
         Self(SpiceSessionConfig {
-            uri: Some(format!("spice://{}:{}", host, port)),
+            uri: Some(format!("spice://{}", format_host_port(&host, port))),
             username: Some(user),
             password: Some(password),
             ca: None,
@@ -101,6 +112,8 @@ impl SpiceAdapter {
             cert_subject: None,
             tls_port: None,
             proxy: None,
+            shared_dir: None,
+            smartcard: false,
         })
     }
 
@@ -108,6 +121,18 @@ impl SpiceAdapter {
         Self(config)
     }
 
+    /// Shares the local folder `shared_dir` with the guest, if set.
+    pub fn with_shared_dir(mut self, shared_dir: Option<String>) -> Self {
+        self.0.shared_dir = shared_dir;
+        self
+    }
+
+    /// Shares local smartcard readers with the guest, if `smartcard` is true.
+    pub fn with_smartcard(mut self, smartcard: bool) -> Self {
+        self.0.smartcard = smartcard;
+        self
+    }
+
     pub fn label() -> Cow<'static, str> {
         gettext("SPICE").into()
     }
@@ -121,6 +146,7 @@ impl Adapter for SpiceAdapter {
     ) -> Box<dyn AdapterDisplay> {
         let spice = rdw_spice::Display::new();
 
+        let shared_dir = self.0.shared_dir.clone();
         let mut session = spice.session();
         self.0.apply(&mut session);
 
@@ -200,19 +226,71 @@ impl Adapter for SpiceAdapter {
             on_connected();
         });
 
-        Box::new(SpiceAdapterDisplay(spice))
+        Box::new(SpiceAdapterDisplay {
+            display: spice,
+            shared_dir,
+        })
     }
 }
 
-pub struct SpiceAdapterDisplay(rdw_spice::Display);
+pub struct SpiceAdapterDisplay {
+    display: rdw_spice::Display,
+    shared_dir: Option<String>,
+}
 
 impl AdapterDisplay for SpiceAdapterDisplay {
     fn widget(&self) -> AdapterDisplayWidget {
-        AdapterDisplayWidget::Rdw(self.0.clone().upcast())
+        AdapterDisplayWidget::Rdw(self.display.clone().upcast())
     }
 
     fn close(&self) {
-        self.0.session().disconnect();
+        self.display.session().disconnect();
+    }
+
+    fn connect_remote_title_changed(&self, callback: Rc<dyn Fn(Option<String>)>) {
+        // The name of the guest, as configured on the hypervisor.
+        self.display
+            .session()
+            .connect_notify_local(Some("name"), move |session, _| {
+                callback(session.property::<Option<String>>("name"))
+            });
+    }
+
+    fn supports_folder_sharing(&self) -> bool {
+        self.shared_dir.is_some()
+    }
+
+    fn is_sharing_folder(&self) -> bool {
+        self.display
+            .session()
+            .property::<Option<String>>("shared-dir")
+            .is_some()
+    }
+
+    fn set_sharing_folder(&self, share: bool) {
+        if self.shared_dir.is_none() {
+            return;
+        }
+        let session = self.display.session();
+        let shared_dir = if share {
+            self.shared_dir.as_deref()
+        } else {
+            None
+        };
+        session.set_property("shared-dir", shared_dir);
+
+        // The WebDAV channel only picks up the folder when it (re)connects.
+        for channel in session.channels() {
+            if !channel.is::<spice::WebdavChannel>() {
+                continue;
+            }
+            if share {
+                ChannelExt::connect(&channel);
+            } else {
+                ChannelExt::disconnect(&channel, ChannelEvent::None);
+            }
+        }
+        debug!("spice folder sharing enabled: {share}");
     }
 }
 
@@ -0,0 +1,298 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+use std::net::{Ipv4Addr, TcpListener};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use gtk::prelude::*;
+use gtk::{gio, glib};
+use log::{debug, warn};
+use parking_lot::Mutex;
+
+use crate::adapter::guarded::{AdapterGuard, GuardedAdapter};
+use crate::host::format_host_port;
+
+const SSH_DEFAULT_PORT: u32 = 22;
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(20);
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How often a hop is started again with another local port, if the port was taken by another
+/// process before ssh could listen on it.
+const MAX_PORT_ATTEMPTS: u32 = 3;
+
+/// A single SSH jump host ("hop") that is traversed to reach a server.
+///
+/// Authentication is done via the SSH agent or the given identity file; SSH is run in batch mode,
+/// so password prompts are not supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshJumpHost {
+    pub host: String,
+    pub port: u32,
+    pub user: Option<String>,
+    pub identity_file: Option<String>,
+}
+
+impl SshJumpHost {
+    pub fn new(host: String) -> Self {
+        Self {
+            host,
+            port: SSH_DEFAULT_PORT,
+            user: None,
+            identity_file: None,
+        }
+    }
+
+    /// Name under which the host key of this hop is stored in the known hosts file.
+    fn host_key_alias(&self) -> String {
+        if self.port == SSH_DEFAULT_PORT {
+            self.host.clone()
+        } else {
+            format!("[{}]:{}", self.host, self.port)
+        }
+    }
+}
+
+/// A chain of SSH port forwards through one or more jump hosts to a target endpoint.
+///
+/// Every hop runs its own `ssh` process, which forwards a local port to the next hop (or the
+/// target for the last hop). The next hop is then reached through that local port. This way every
+/// hop can use its own credentials.
+///
+/// The processes are killed when the tunnel is closed or dropped.
+pub struct SshTunnel {
+    local_port: u16,
+    processes: Mutex<Vec<Child>>,
+}
+
+impl SshTunnel {
+    /// Opens the tunnel and waits until it accepts connections. `hops` must not be empty.
+    pub async fn open(
+        hops: &[SshJumpHost],
+        target_host: &str,
+        target_port: u32,
+    ) -> anyhow::Result<Self> {
+        if hops.is_empty() {
+            return Err(anyhow!("no jump hosts given"));
+        }
+
+        // If any of the steps below fail, dropping this closes all already started processes.
+        let mut tunnel = Self {
+            local_port: 0,
+            processes: Mutex::new(Vec::with_capacity(hops.len())),
+        };
+
+        for (i, hop) in hops.iter().enumerate() {
+            let (forward_host, forward_port) = match hops.get(i + 1) {
+                Some(next_hop) => (next_hop.host.as_str(), next_hop.port),
+                None => (target_host, target_port),
+            };
+            let mut attempt = 1;
+            let local_port = loop {
+                let local_port = free_local_port()?;
+                let mut command =
+                    tunnel.forward_command(i, hop, local_port, forward_host, forward_port);
+                debug!("starting ssh for jump host {}: {command:?}", hop.host);
+                let child = command
+                    .spawn()
+                    .map_err(|err| anyhow!("failed to start ssh: {err}"))?;
+                tunnel.processes.lock().push(child);
+
+                match tunnel.wait_until_listening(local_port, &hop.host).await {
+                    Ok(()) => break local_port,
+                    // Another process may have taken the port after it was chosen, ssh then
+                    // exits because of ExitOnForwardFailure.
+                    Err(err)
+                        if attempt < MAX_PORT_ATTEMPTS
+                            && tunnel.last_process_exited()
+                            && TcpListener::bind((Ipv4Addr::LOCALHOST, local_port)).is_err() =>
+                    {
+                        debug!("local port {local_port} was taken, retrying: {err}");
+                        tunnel.processes.lock().pop();
+                        attempt += 1;
+                    }
+                    Err(err) => return Err(err),
+                }
+            };
+            tunnel.local_port = local_port;
+        }
+
+        Ok(tunnel)
+    }
+
+    /// The local port connections to the target endpoint should be made to.
+    pub fn local_port(&self) -> u16 {
+        self.local_port
+    }
+
+    /// The local host connections to the target endpoint should be made to.
+    pub fn local_host(&self) -> String {
+        Ipv4Addr::LOCALHOST.to_string()
+    }
+
+    /// Whether all ssh processes of the tunnel are still running.
+    pub fn is_open(&self) -> bool {
+        let mut processes = self.processes.lock();
+        !processes.is_empty()
+            && processes
+                .iter_mut()
+                .all(|child| matches!(child.try_wait(), Ok(None)))
+    }
+
+    /// Kills all ssh processes of the tunnel. Does nothing if the tunnel is already closed.
+    pub fn close(&self) {
+        for mut child in self.processes.lock().drain(..).rev() {
+            if let Err(err) = child.kill() {
+                warn!("failed to kill ssh process: {err}");
+            }
+            child.wait().ok();
+        }
+    }
+
+    /// The ssh command for the hop at `index`, forwarding `local_port` to the forward endpoint.
+    fn forward_command(
+        &self,
+        index: usize,
+        hop: &SshJumpHost,
+        local_port: u16,
+        forward_host: &str,
+        forward_port: u32,
+    ) -> Command {
+        let mut command = Command::new("ssh");
+        command
+            .args([
+                "-N",
+                "-o",
+                "ExitOnForwardFailure=yes",
+                "-o",
+                "BatchMode=yes",
+            ])
+            .arg("-L")
+            .arg(format!(
+                "{}:{local_port}:{}",
+                Ipv4Addr::LOCALHOST,
+                format_host_port(forward_host, forward_port)
+            ))
+            .stdin(Stdio::null());
+        if let Some(user) = hop.user.as_deref().filter(|u| !u.is_empty()) {
+            command.arg("-l").arg(user);
+        }
+        if let Some(identity_file) = hop.identity_file.as_deref().filter(|f| !f.is_empty()) {
+            command
+                .arg("-i")
+                .arg(identity_file)
+                .args(["-o", "IdentitiesOnly=yes"]);
+        }
+        if index == 0 {
+            // The host comes from the configuration, make sure ssh never reads it as an option.
+            command
+                .arg("-p")
+                .arg(hop.port.to_string())
+                .arg("--")
+                .arg(&hop.host);
+        } else {
+            // Reach this hop through the port forwarded by the previous hop.
+            command
+                .arg("-p")
+                .arg(self.local_port.to_string())
+                .arg("-o")
+                .arg(format!("HostKeyAlias={}", hop.host_key_alias()))
+                .arg("--")
+                .arg(Ipv4Addr::LOCALHOST.to_string());
+        }
+        command
+    }
+
+    /// Whether the ssh process started last has exited.
+    fn last_process_exited(&self) -> bool {
+        self.processes
+            .lock()
+            .last_mut()
+            .is_some_and(|child| matches!(child.try_wait(), Ok(Some(_))))
+    }
+
+    async fn wait_until_listening(&self, port: u16, hop_host: &str) -> anyhow::Result<()> {
+        let started = Instant::now();
+        loop {
+            if let Some(status) = self
+                .processes
+                .lock()
+                .last_mut()
+                .and_then(|child| child.try_wait().ok().flatten())
+            {
+                return Err(anyhow!(
+                    "ssh for jump host {hop_host} exited unexpectedly ({status})"
+                ));
+            }
+            if is_listening(port).await {
+                return Ok(());
+            }
+            if started.elapsed() > STARTUP_TIMEOUT {
+                return Err(anyhow!("timed out connecting to jump host {hop_host}"));
+            }
+            glib::timeout_future(STARTUP_POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        self.close()
+    }
+}
+
+/// Whether a connection to the local `port` can be made. Gives up after the poll interval, so
+/// that a hanging attempt does not delay noticing that ssh exited.
+async fn is_listening(port: u16) -> bool {
+    let address = gio::InetSocketAddress::new(
+        &gio::InetAddress::new_loopback(gio::SocketFamily::Ipv4),
+        port,
+    );
+    let client = gio::SocketClient::new();
+    client.set_enable_proxy(false);
+    // Dropping the connection attempt on timeout cancels it.
+    match glib::future_with_timeout(STARTUP_POLL_INTERVAL, client.connect_future(&address)).await {
+        Ok(Ok(connection)) => {
+            connection.close_future(glib::Priority::DEFAULT).await.ok();
+            true
+        }
+        _ => false,
+    }
+}
+
+fn free_local_port() -> anyhow::Result<u16> {
+    Ok(TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?
+        .local_addr()?
+        .port())
+}
+
+impl AdapterGuard for SshTunnel {
+    fn is_open(&self) -> bool {
+        SshTunnel::is_open(self)
+    }
+
+    fn close(&self) {
+        SshTunnel::close(self)
+    }
+}
+
+/// Wraps another adapter that connects through an [`SshTunnel`]. The tunnel is kept open for as
+/// long as the display exists.
+///
+/// The inner adapter must be configured to connect to [`SshTunnel::local_host`] and
+/// [`SshTunnel::local_port`].
+pub type SshTunnelAdapter = GuardedAdapter<SshTunnel>;
@@ -37,6 +37,33 @@ pub trait AdapterDisplay {
     ///
     /// Implementations should also call this in Drop.
     fn close(&self);
+
+    /// Reconnects a closed connection, reusing the existing session parameters.
+    ///
+    /// Returns false if the adapter can not reconnect on its own. The adapter then needs
+    /// to be created again.
+    fn reconnect(&self) -> bool {
+        false
+    }
+
+    /// Whether a local folder can be shared with the remote server while connected.
+    fn supports_folder_sharing(&self) -> bool {
+        false
+    }
+
+    /// Whether the local folder is currently shared with the remote server.
+    fn is_sharing_folder(&self) -> bool {
+        false
+    }
+
+    /// Starts or stops sharing the local folder. Does nothing if folder sharing is not
+    /// supported.
+    fn set_sharing_folder(&self, _share: bool) {}
+
+    /// Calls `callback` whenever the remote session reports a new title for itself, such as the
+    /// desktop name of a VNC server. `None` if the session no longer has a title.
+    /// Does nothing if the adapter can not report titles.
+    fn connect_remote_title_changed(&self, _callback: Rc<dyn Fn(Option<String>)>) {}
 }
 
 /// An adapter to connect to a remote server and provide widgets
@@ -47,4 +74,18 @@ pub trait Adapter: Send + Sync {
         on_connected: Rc<dyn Fn()>,
         on_disconnected: Rc<dyn Fn(Result<(), ConnectionError>)>,
     ) -> Box<dyn AdapterDisplay>;
+
+    /// Whether the local microphone will be redirected to the remote server.
+    /// `None` if the adapter does not support redirecting microphones.
+    fn microphone(&self) -> Option<bool> {
+        None
+    }
+
+    /// Sets whether the local microphone will be redirected to the remote server. Does nothing
+    /// if redirecting microphones is not supported.
+    fn set_microphone(&mut self, _redirect: bool) {}
+
+    /// Shares the local printers with the given names with the remote server. Does nothing if
+    /// the adapter does not support redirecting printers.
+    fn set_printers(&mut self, _printers: &[String]) {}
 }
@@ -23,6 +23,7 @@ use anyhow::anyhow;
 use gettextrs::gettext;
 use glib::prelude::*;
 use glib::translate::IntoGlib;
+use gtk::gio;
 use log::{debug, warn};
 use rdw_vnc::gvnc;
 use secure_string::SecureString;
@@ -32,6 +33,9 @@ use crate::connection::ConnectionError;
 
 pub struct VncAdapter {
     host: String,
+    /// Address to connect to instead of resolving `host`. The host name is still used for
+    /// validating the certificate.
+    address: Option<String>,
     port: u32,
     user: String,
     password: SecureString,
@@ -44,6 +48,7 @@ impl VncAdapter {
     pub fn new(host: String, port: u32, user: String, password: SecureString) -> Self {
         Self {
             host,
+            address: None,
             port,
             user,
             password,
@@ -60,6 +65,7 @@ impl VncAdapter {
     ) -> Self {
         Self {
             host,
+            address: None,
             port,
             user,
             password,
@@ -67,6 +73,12 @@ impl VncAdapter {
         }
     }
 
+    /// Connects to `address` instead of resolving the host, if set.
+    pub fn with_address(mut self, address: Option<String>) -> Self {
+        self.address = address;
+        self
+    }
+
     pub fn label() -> Cow<'static, str> {
         gettext("VNC").into()
     }
@@ -80,6 +92,7 @@ impl Adapter for VncAdapter {
     ) -> Box<dyn AdapterDisplay> {
         let error_container: Rc<RefCell<Option<ConnectionError>>> = Rc::new(RefCell::new(None));
         let host = self.host.clone();
+        let address = self.address.clone();
         let user = self.user.clone();
         let port = self.port;
 
@@ -169,9 +182,22 @@ impl Adapter for VncAdapter {
             }
         ));
 
-        vnc.connection()
-            .open_host(&host, &format!("{}", port))
-            .unwrap();
+        let socket_address = address
+            .as_deref()
+            .and_then(gio::InetAddress::from_string)
+            .zip(u16::try_from(port).ok())
+            .map(|(address, port)| gio::InetSocketAddress::new(&address, port));
+        match socket_address {
+            // The host name is still given for validating the certificate.
+            Some(socket_address) => vnc
+                .connection()
+                .open_addr(&socket_address, Some(&host))
+                .unwrap(),
+            None => vnc
+                .connection()
+                .open_host(&host, &format!("{}", port))
+                .unwrap(),
+        }
 
         Box::new(VncAdapterDisplay(vnc))
     }
@@ -187,6 +213,17 @@ impl AdapterDisplay for VncAdapterDisplay {
     fn close(&self) {
         self.0.connection().shutdown()
     }
+
+    fn connect_remote_title_changed(&self, callback: Rc<dyn Fn(Option<String>)>) {
+        let connection = self.0.connection();
+        // The desktop name is sent by the server during initialization.
+        connection.connect_vnc_initialized(glib::clone!(
+            #[strong]
+            callback,
+            move |conn| callback(conn.name().map(Into::into))
+        ));
+        connection.connect_vnc_desktop_rename(move |_, name| callback(Some(name.to_string())));
+    }
 }
 
 impl Drop for VncAdapterDisplay {
@@ -15,8 +15,10 @@
  *
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
-use std::iter;
+use std::fs::File;
+use std::io::Write;
 use std::ops::Deref;
+use std::os::fd::OwnedFd;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
@@ -25,14 +27,15 @@ use anyhow::anyhow;
 use gettextrs::gettext;
 use gtk::glib;
 use log::{debug, warn};
+use nix::fcntl::OFlag;
 use nix::sys::signal::{kill, Signal};
-use nix::unistd::Pid;
+use nix::unistd::{pipe2, Pid};
 use parking_lot::Mutex;
 use uuid::Uuid;
 use vte::prelude::*;
 
 use field_monitor_vte_driver_lib::dbus_server::VtePtyProcMon;
-use field_monitor_vte_driver_lib::DBUS_KEY_ENV_VAR;
+use field_monitor_vte_driver_lib::KEY_FD;
 
 use crate::adapter::types::{Adapter, AdapterDisplay, AdapterDisplayWidget};
 use crate::config::APP_ID;
@@ -52,8 +55,9 @@ impl VtePtyAdapter {
     /// Create a new Pty Vte adapter. The command must use field-monitor-vte-driver-lib. See that
     /// crate for more info.
     /// argv is as such: `<command> <dbus-path back to this process service>`
-    /// via env variable FM_KEY the process gets a key that can be used with the `extra_arguments`
-    /// D-Bus interface method to get the additional argument values.
+    /// via the inherited file descriptor `KEY_FD` the process gets a key that can be used once with
+    /// the `extra_arguments` D-Bus interface method to get the additional argument values. The
+    /// extra arguments may therefore contain secrets.
     pub fn new(
         connection_id: String,
         server_id: String,
@@ -89,10 +93,17 @@ impl Adapter for VtePtyAdapter {
             #[strong]
             vte,
             async move {
-                // This is not cryptographically safe, but it's not going to be anyway: we pass
-                // it to the environment, which isn't secret. This is good enough to at least
-                // somewhat obscure the extra arguments in a reasonable way.
-                let fm_key = Uuid::now_v7().to_string();
+                let fm_key = Uuid::new_v4().to_string();
+                let key_fd = match write_key(&fm_key) {
+                    Ok(fd) => fd,
+                    Err(err) => {
+                        on_disconnected(Err(ConnectionError::General(
+                            Some(gettext("Internal error while trying to build terminal.")),
+                            err,
+                        )));
+                        return;
+                    }
+                };
 
                 let dbus_server = match VtePtyProcMon::server(
                     APP_ID,
@@ -121,16 +132,17 @@ impl Adapter for VtePtyAdapter {
                 let env_owned = glib::environ()
                     .into_iter()
                     .filter_map(|s| s.into_string().ok())
-                    .chain(iter::once(format!("{}={}", DBUS_KEY_ENV_VAR, fm_key)))
                     .collect::<Vec<_>>();
                 let envv = env_owned.iter().map(Deref::deref).collect::<Vec<_>>();
 
                 match vte
-                    .spawn_future(
+                    .spawn_with_fds_future(
                         vte::PtyFlags::DEFAULT,
                         None,
                         &argv,
                         &envv,
+                        vec![key_fd],
+                        &[KEY_FD],
                         glib::SpawnFlags::DEFAULT,
                         || {},
                         -1,
@@ -192,6 +204,16 @@ impl Adapter for VtePtyAdapter {
     }
 }
 
+/// Creates a pipe containing `key`. Returns the read end, which is passed to the driver process.
+/// Both ends are close-on-exec, so that other processes spawned meanwhile do not inherit them.
+/// The spawn maps the read end to [`KEY_FD`] explicitly.
+fn write_key(key: &str) -> anyhow::Result<OwnedFd> {
+    let (read, write) = pipe2(OFlag::O_CLOEXEC)?;
+    // The key is much smaller than the pipe buffer, so this does not block.
+    File::from(write).write_all(key.as_bytes())?;
+    Ok(read)
+}
+
 pub struct VtePtyAdapterDisplay(vte::Terminal, Arc<Mutex<Option<Pid>>>);
 
 impl AdapterDisplay for VtePtyAdapterDisplay {
@@ -199,6 +221,14 @@ impl AdapterDisplay for VtePtyAdapterDisplay {
         AdapterDisplayWidget::Vte(self.0.clone())
     }
 
+    fn connect_remote_title_changed(&self, callback: Rc<dyn Fn(Option<String>)>) {
+        // Set by programs in the console with an escape sequence, eg. by shells.
+        self.0.connect_window_title_changed(move |vte| {
+            let title = vte.window_title().filter(|title| !title.is_empty());
+            callback(title.map(Into::into))
+        });
+    }
+
     fn close(&self) {
         let pty_pid_ctr_guard = self.1.lock();
         if let Some(pty_pid) = &*pty_pid_ctr_guard {
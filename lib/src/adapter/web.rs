@@ -0,0 +1,116 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Opening the web management interface of servers in the browser.
+use std::rc::Rc;
+
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+use log::warn;
+
+use crate::adapter::types::{Adapter, AdapterDisplay, AdapterDisplayWidget};
+use crate::connection::ConnectionError;
+use crate::i18n::gettext_f;
+
+/// Opens a web management interface, such as the one of a hypervisor, in the default browser
+/// instead of embedding a display.
+pub struct WebAdapter {
+    url: String,
+}
+
+impl WebAdapter {
+    pub const TAG: &'static str = "web";
+
+    pub fn new(url: String) -> Self {
+        Self { url }
+    }
+
+    pub fn label() -> String {
+        gettext("Web Interface")
+    }
+}
+
+impl Adapter for WebAdapter {
+    fn create_and_connect_display(
+        self: Box<Self>,
+        on_connected: Rc<dyn Fn()>,
+        on_disconnected: Rc<dyn Fn(Result<(), ConnectionError>)>,
+    ) -> Box<dyn AdapterDisplay> {
+        let url = self.url;
+        let button = gtk::Button::builder()
+            .label(gettext("Open Again"))
+            .halign(gtk::Align::Center)
+            .css_classes(["pill"])
+            .build();
+        let status_page = adw::StatusPage::builder()
+            .icon_name("web-browser-symbolic")
+            .title(gettext("Opened in Web Browser"))
+            .description(glib::markup_escape_text(&url))
+            .child(&button)
+            .build();
+
+        button.connect_clicked(glib::clone!(
+            #[strong]
+            url,
+            move |button| {
+                let url = url.clone();
+                let window = button.root().and_downcast::<gtk::Window>();
+                glib::spawn_future_local(async move {
+                    if let Err(err) = open(&url, window.as_ref()).await {
+                        warn!("failed to open {url}: {err}");
+                    }
+                });
+            }
+        ));
+
+        glib::spawn_future_local(glib::clone!(
+            #[strong]
+            status_page,
+            async move {
+                let window = status_page.root().and_downcast::<gtk::Window>();
+                match open(&url, window.as_ref()).await {
+                    Ok(()) => on_connected(),
+                    Err(err) => on_disconnected(Err(ConnectionError::General(
+                        Some(gettext_f("Failed to open {url}.", &[("url", &url)])),
+                        err.into(),
+                    ))),
+                }
+            }
+        ));
+
+        Box::new(WebAdapterDisplay(status_page.upcast()))
+    }
+}
+
+async fn open(url: &str, window: Option<&gtk::Window>) -> Result<(), glib::Error> {
+    gtk::UriLauncher::new(url).launch_future(window).await
+}
+
+/// Shows where the interface was opened. There is nothing to close, the browser is independent
+/// of the session.
+pub struct WebAdapterDisplay(gtk::Widget);
+
+impl AdapterDisplay for WebAdapterDisplay {
+    fn widget(&self) -> AdapterDisplayWidget {
+        AdapterDisplayWidget::Arbitrary {
+            widget: self.0.clone(),
+        }
+    }
+
+    fn close(&self) {}
+}
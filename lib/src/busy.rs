@@ -17,6 +17,7 @@
  */
 
 use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::rc::Rc;
 
 pub struct BusyStack(Rc<RefCell<usize>>, Rc<Cell<bool>>, Rc<Box<dyn Fn()>>);
@@ -52,3 +53,59 @@ impl Drop for BusyGuard {
         }
     }
 }
+
+type TargetNotify = Rc<Box<dyn Fn(&str, bool)>>;
+
+/// Tracks which targets (e.g. servers or connections, identified by their path) are busy.
+/// Unlike [`BusyStack`], a target being busy does not affect any other target.
+///
+/// `notify` is called with the target whenever it becomes busy or is no longer busy.
+pub struct BusyTargets(Rc<RefCell<HashMap<String, usize>>>, TargetNotify);
+
+impl BusyTargets {
+    pub fn new(notify: Box<dyn Fn(&str, bool)>) -> BusyTargets {
+        Self(Default::default(), Rc::new(notify))
+    }
+
+    pub fn is_busy(&self, target: &str) -> bool {
+        self.0.borrow().contains_key(target)
+    }
+
+    pub fn busy(&self, target: &str) -> TargetBusyGuard {
+        let became_busy = {
+            let mut targets = self.0.borrow_mut();
+            let count = targets.entry(target.to_string()).or_default();
+            *count += 1;
+            *count == 1
+        };
+        if became_busy {
+            self.1(target, true);
+        }
+        TargetBusyGuard(target.to_string(), self.0.clone(), self.1.clone())
+    }
+}
+
+pub struct TargetBusyGuard(String, Rc<RefCell<HashMap<String, usize>>>, TargetNotify);
+
+impl Drop for TargetBusyGuard {
+    fn drop(&mut self) {
+        let became_idle = {
+            let mut targets = self.1.borrow_mut();
+            match targets.get_mut(&self.0) {
+                Some(count) if *count > 1 => {
+                    *count -= 1;
+                    false
+                }
+                Some(_) => {
+                    targets.remove(&self.0);
+                    true
+                }
+                None => false,
+            }
+        };
+        // The borrow is released first, so `notify` can query the targets.
+        if became_idle {
+            self.2(&self.0, false);
+        }
+    }
+}
@@ -16,12 +16,14 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 use std::borrow::Cow;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::sync::Arc;
 
 use anyhow::anyhow;
 use futures::future::LocalBoxFuture;
+use futures::lock::Mutex;
+use futures::FutureExt;
 use gettextrs::gettext;
 use glib;
 use glib::prelude::*;
@@ -45,6 +47,12 @@ mod imp {
         pub provider: RefCell<Option<Rc<Box<dyn ConnectionProvider>>>>,
         pub implementation: RefCell<Option<Box<dyn Connection>>>,
         pub load_error: RefCell<Option<Arc<ConnectionError>>>,
+        /// Whether the provider was asked to load the connection yet. This is false for
+        /// connections created with `new_unloaded` until `load` is called.
+        #[property(get)]
+        pub loaded: Cell<bool>,
+        /// Held while loading, so concurrent calls to `load` only load once.
+        pub load_lock: Mutex<()>,
     }
 
     #[glib::object_subclass]
@@ -68,6 +76,17 @@ impl ConnectionInstance {
     pub async fn new(
         configuration: DualScopedConnectionConfiguration,
         provider: Rc<Box<dyn ConnectionProvider>>,
+    ) -> Self {
+        let slf = Self::new_unloaded(configuration, provider);
+        slf.load().await;
+        slf
+    }
+
+    /// Creates the instance without loading the connection via the provider yet. It is loaded
+    /// with [`Self::load`] or when its servers are first requested.
+    pub fn new_unloaded(
+        configuration: DualScopedConnectionConfiguration,
+        provider: Rc<Box<dyn ConnectionProvider>>,
     ) -> Self {
         let slf_id = Arc::new(configuration.session().id().to_string());
         let slf: Self = glib::Object::builder()
@@ -97,20 +116,39 @@ impl ConnectionInstance {
 
         let imp = slf.imp();
         imp.provider.replace(Some(provider));
-        slf.set_configuration(configuration).await;
+        imp.configuration.replace(Some(configuration));
         slf
     }
 
-    /// Changes the configuration and recreates the implementation.
+    /// Loads the connection via the provider, if this was not done yet.
+    pub async fn load(&self) {
+        let _guard = self.imp().load_lock.lock().await;
+        if self.loaded() {
+            return;
+        }
+        let session = self.with_configuration(|c| c.session().clone());
+        self.load_implementation(session).await;
+    }
+
+    /// Changes the configuration and recreates the implementation, if the connection was
+    /// already loaded.
     pub async fn set_configuration(&self, value: DualScopedConnectionConfiguration) {
         assert_eq!(value.session().id(), self.connection_id().as_str());
 
+        if self.loaded() {
+            self.load_implementation(value.session().clone()).await;
+        }
+        self.imp().configuration.replace(Some(value));
+    }
+
+    async fn load_implementation(&self, session: ConnectionConfiguration) {
         let slf_imp = self.imp();
         let provider = slf_imp.provider.borrow().as_ref().expect(NOT_INIT).clone();
-        match provider.load_connection(value.session().clone()).await {
+        match provider.load_connection(session).await {
             Ok(implementation) => {
                 self.set_title(implementation.metadata().title.as_str());
                 slf_imp.implementation.replace(Some(implementation));
+                slf_imp.load_error.replace(None);
             }
             Err(err) => {
                 error!(
@@ -121,7 +159,9 @@ impl ConnectionInstance {
                 slf_imp.load_error.replace(Some(Arc::new(err)));
             }
         }
-        slf_imp.configuration.replace(Some(value));
+        if !slf_imp.loaded.replace(true) {
+            self.notify_loaded();
+        }
     }
 
     pub fn with_configuration<T>(&self, cb: impl Fn(&DualScopedConnectionConfiguration) -> T) -> T {
@@ -139,6 +179,14 @@ impl ConnectionInstance {
             .as_ref()
             .map(|c| c.session().tag().to_string())
     }
+
+    /// The error the implementation failed to load with.
+    fn load_error(&self) -> ConnectionError {
+        match self.imp().load_error.borrow().as_ref() {
+            Some(err) => err.clone_outside(),
+            None => ConnectionError::General(None, anyhow!(gettext("Unknown error"))),
+        }
+    }
 }
 
 impl Actionable for ConnectionInstance {
@@ -163,7 +211,11 @@ impl Connection for ConnectionInstance {
             Some(implementation) => implementation.metadata(),
             None => ConnectionMetadataBuilder::default()
                 .title(self.title())
-                .icon(IconSpec::Named("dialog-error-symbolic".into()))
+                .icon(if self.loaded() {
+                    IconSpec::Named("dialog-error-symbolic".into())
+                } else {
+                    IconSpec::Default
+                })
                 .build()
                 .unwrap(),
         }
@@ -173,13 +225,26 @@ impl Connection for ConnectionInstance {
     #[allow(clippy::await_holding_refcell_ref)]
     fn servers(&self) -> LocalBoxFuture<ConnectionResult<ServerMap>> {
         Box::pin(async move {
+            self.load().await;
             let brw = self.imp().implementation.borrow();
             match brw.as_ref() {
                 Some(implementation) => implementation.servers().await,
-                None => Err(match self.imp().load_error.borrow().as_ref() {
-                    Some(err) => err.clone_outside(),
-                    None => ConnectionError::General(None, anyhow!(gettext("Unknown error"))),
-                }),
+                None => Err(self.load_error()),
+            }
+        })
+    }
+
+    #[allow(clippy::await_holding_refcell_ref)]
+    fn servers_with_progress<'a>(
+        &'a self,
+        progress: &'a LoadProgress,
+    ) -> LocalBoxFuture<'a, ConnectionResult<ServerMap>> {
+        Box::pin(async move {
+            progress.unless_cancelled(self.load().map(Ok)).await?;
+            let brw = self.imp().implementation.borrow();
+            match brw.as_ref() {
+                Some(implementation) => implementation.servers_with_progress(progress).await,
+                None => Err(self.load_error()),
             }
         })
     }
@@ -16,12 +16,11 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 
-pub use config_value::*;
-pub use configuration::*;
+pub use field_monitor_core::connection::*;
 pub use instance::*;
+pub use progress::*;
 pub use types::*;
 
-mod config_value;
-mod configuration;
 mod instance;
+mod progress;
 mod types;
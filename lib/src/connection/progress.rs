@@ -0,0 +1,118 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Progress of loading the servers of a connection, so that it can be shown to the user
+//! instead of an indefinite spinner, and loading can be cancelled.
+use std::fmt;
+use std::future::Future;
+use std::rc::Rc;
+
+use anyhow::anyhow;
+use futures::future::{select, Either};
+use gettextrs::gettext;
+use gtk::gio;
+use gtk::prelude::*;
+
+use crate::connection::{ConnectionError, ConnectionResult};
+
+/// A step of loading servers, eg. loading the guests of one node.
+#[derive(Debug, Clone)]
+pub struct LoadStep {
+    /// What is being loaded, shown to the user.
+    pub description: String,
+    /// Number of the step, starting at 1.
+    pub current: usize,
+    /// Number of steps, 0 if not known.
+    pub total: usize,
+}
+
+/// Receives the progress of loading servers and tells whether loading was cancelled.
+///
+/// Clones report to the same receiver and are cancelled together.
+#[derive(Clone)]
+pub struct LoadProgress {
+    report: Option<Rc<dyn Fn(&LoadStep)>>,
+    cancellable: gio::Cancellable,
+}
+
+impl fmt::Debug for LoadProgress {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("LoadProgress")
+            .field("cancelled", &self.is_cancelled())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for LoadProgress {
+    /// Progress that is not shown anywhere and can not be cancelled by the user.
+    fn default() -> Self {
+        Self {
+            report: None,
+            cancellable: gio::Cancellable::new(),
+        }
+    }
+}
+
+impl LoadProgress {
+    pub fn new(report: impl Fn(&LoadStep) + 'static) -> Self {
+        Self {
+            report: Some(Rc::new(report)),
+            cancellable: gio::Cancellable::new(),
+        }
+    }
+
+    /// Reports that step `current` of `total` started. `total` is 0 if it is not known.
+    pub fn report(&self, description: impl Into<String>, current: usize, total: usize) {
+        if let Some(report) = &self.report {
+            report(&LoadStep {
+                description: description.into(),
+                current,
+                total,
+            });
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancellable.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellable.is_cancelled()
+    }
+
+    /// Runs `future`, unless loading is cancelled before it finishes. It then fails with an
+    /// error telling that loading was cancelled.
+    pub async fn unless_cancelled<T>(
+        &self,
+        future: impl Future<Output = ConnectionResult<T>>,
+    ) -> ConnectionResult<T> {
+        if self.is_cancelled() {
+            return Err(cancelled_error());
+        }
+        match select(Box::pin(future), Box::pin(self.cancellable.future())).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(cancelled_error()),
+        }
+    }
+}
+
+fn cancelled_error() -> ConnectionError {
+    ConnectionError::General(
+        Some(gettext("Loading the servers was cancelled.")),
+        anyhow!("cancelled"),
+    )
+}
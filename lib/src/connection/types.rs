@@ -17,17 +17,21 @@
  */
 use std::any::Any;
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
 
+use anyhow::anyhow;
 use derive_builder::Builder;
 use futures::future::LocalBoxFuture;
 use indexmap::IndexMap;
+use secure_string::SecureString;
 use thiserror::Error;
 
 use crate::adapter::types::Adapter;
-use crate::connection::configuration::ConnectionConfiguration;
+use crate::connection::ConnectionConfiguration;
 use crate::connection::DualScopedConnectionConfiguration;
+use crate::connection::LoadProgress;
 
 pub type ConnectionResult<T> = Result<T, ConnectionError>;
 
@@ -73,7 +77,7 @@ impl ConnectionError {
     }
 }
 
-pub type IconFactory<M> = Box<dyn Fn(&M) -> gtk::Widget>;
+pub type IconFactory<M> = Box<dyn Fn(&M) -> gtk::Widget + Send + Sync>;
 
 /// Specifies how this entity should be represented with an icon, if at all.
 /// Any named or custom icon should have a width of 16px.
@@ -120,6 +124,9 @@ pub struct ConnectionMetadata {
     pub title: String,
     #[builder(default = "None")]
     pub subtitle: Option<String>,
+    /// Additional lines of secondary information (eg. versions), shown after the subtitle.
+    #[builder(default)]
+    pub info_lines: Vec<String>,
     #[builder(default = "IconSpec::Default")]
     pub icon: IconSpec<ConnectionMetadata>,
 }
@@ -134,10 +141,67 @@ pub struct ServerMetadata {
     pub title: String,
     #[builder(default = "None")]
     pub subtitle: Option<String>,
+    /// Additional lines of secondary information (eg. uptime), shown after the subtitle.
+    #[builder(default)]
+    pub info_lines: Vec<String>,
     #[builder(default = "None")]
     pub is_online: Option<bool>,
     #[builder(default = "IconSpec::Default")]
     pub icon: IconSpec<ServerMetadata>,
+    /// Current resource usage of the server, shown as small indicators next to its icon.
+    #[builder(default)]
+    pub usage: Vec<UsageIndicator>,
+    /// Short labels shown next to the icon of the server, eg. to mark it as managed by a
+    /// cluster.
+    #[builder(default)]
+    pub badges: Vec<ServerBadge>,
+    /// Host name or address the server can be reached at, if known. Available to external
+    /// tools as `${host}`.
+    #[builder(default = "None")]
+    pub host: Option<String>,
+    /// Primary IP address of the server as reported by the hypervisor or guest, if known. Shown
+    /// in the row of the server, where it can be copied.
+    #[builder(default = "None")]
+    pub ip_address: Option<String>,
+}
+
+/// Usage of a single resource of a server, eg. its CPU.
+#[derive(Debug, Clone)]
+pub struct UsageIndicator {
+    /// Short name of the resource.
+    pub label: String,
+    /// Fraction of the resource in use, between 0 and 1.
+    pub fraction: f64,
+}
+
+/// A short label shown next to the icon of a server.
+#[derive(Debug, Clone)]
+pub struct ServerBadge {
+    pub label: String,
+    /// Longer explanation, shown as the tooltip of the badge.
+    pub tooltip: Option<String>,
+    pub style: BadgeStyle,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BadgeStyle {
+    #[default]
+    Neutral,
+    Success,
+    Warning,
+    Error,
+}
+
+impl BadgeStyle {
+    /// CSS class for the style, if it has one.
+    pub fn css_class(&self) -> Option<&'static str> {
+        match self {
+            BadgeStyle::Neutral => None,
+            BadgeStyle::Success => Some("success"),
+            BadgeStyle::Warning => Some("warning"),
+            BadgeStyle::Error => Some("error"),
+        }
+    }
 }
 
 pub trait FieldMonitorApplication {}
@@ -152,6 +216,9 @@ pub trait ConnectionProviderConstructor: Send + Sync {
 
 /// A provider for creating new connections. Each provider can create new connections
 /// of a defined type.
+///
+/// Its futures are polled on the GTK main loop and may hold widgets and other GTK objects,
+/// so they are not `Send`.
 pub trait ConnectionProvider {
     /// Tag for configuration connection. Will be serialized with configuration
     /// and used to match connection providers.
@@ -233,6 +300,76 @@ pub trait ConnectionProvider {
         &self,
         configuration: ConnectionConfiguration,
     ) -> LocalBoxFuture<ConnectionResult<Box<dyn Connection>>>;
+
+    /// Whether servers of connections of this provider can be duplicated, or copied and moved
+    /// between connections. If so, [`Self::copy_server`] and [`Self::remove_server`] must be
+    /// implemented.
+    fn supports_copying_servers(&self) -> bool {
+        false
+    }
+
+    /// Whether more than one session can be opened to the same server at the same time.
+    /// Providers whose servers only accept a single client should return `false`; trying to open
+    /// another session then focuses the existing one instead.
+    fn supports_multiple_sessions(&self) -> bool {
+        true
+    }
+
+    /// Whether the servers of connections of this provider report status that changes on its
+    /// own, such as [`ServerMetadata::usage`]. Shown servers of such connections are reloaded
+    /// periodically.
+    fn supports_status_polling(&self) -> bool {
+        false
+    }
+
+    /// Other names than [`Self::tag`] that select this provider when adding a connection
+    /// non-interactively, eg. the protocols of the servers it connects to.
+    fn tag_aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Configures a new connection from `options`, without any user interaction. This is used
+    /// to add connections from the command line.
+    ///
+    /// `options` contains the values given by the user, such as `host`, `port`, `user` and
+    /// `title`. `provider` is the tag or alias the provider was selected with. Returns an error
+    /// describing the problem if required options are missing or invalid.
+    fn configure_from_options(
+        &self,
+        _options: &HashMap<String, String>,
+        _password: Option<SecureString>,
+        _configuration: DualScopedConnectionConfiguration,
+    ) -> LocalBoxFuture<anyhow::Result<DualScopedConnectionConfiguration>> {
+        Box::pin(async {
+            Err(anyhow!(
+                "this connection type can not be added non-interactively"
+            ))
+        })
+    }
+
+    /// Copies the server at `server_path` of the connection `source` into the connection
+    /// `target`, including its credentials. The copy gets a new key. `source` and `target` may
+    /// be configurations of the same connection, in which case the server is duplicated.
+    ///
+    /// Returns the updated `target` configuration.
+    fn copy_server(
+        &self,
+        _server_path: &[String],
+        _source: &DualScopedConnectionConfiguration,
+        _target: DualScopedConnectionConfiguration,
+    ) -> LocalBoxFuture<anyhow::Result<DualScopedConnectionConfiguration>> {
+        Box::pin(async { Err(anyhow!("copying servers is not supported")) })
+    }
+
+    /// Removes the server at `server_path` from a connection configuration, including its
+    /// credentials.
+    fn remove_server(
+        &self,
+        _server_path: &[String],
+        _configuration: DualScopedConnectionConfiguration,
+    ) -> LocalBoxFuture<anyhow::Result<DualScopedConnectionConfiguration>> {
+        Box::pin(async { Err(anyhow!("removing servers is not supported")) })
+    }
 }
 
 /// Parameters for an action. Can be downcast to expected type.
@@ -244,8 +381,9 @@ pub type Parameters = Box<dyn Any>;
 /// Return value: True if the connection should be reloaded, false otherwise.
 pub type ActionExecuteFut<'a> =
     dyn Fn(Parameters, Option<gtk::Window>, Option<adw::ToastOverlay>) -> LocalBoxFuture<'a, bool>;
-pub type ServerMap = IndexMap<Cow<'static, str>, Box<dyn ServerConnection>>;
-pub type ServerMapSend = IndexMap<Cow<'static, str>, Box<dyn ServerConnection + Send>>;
+/// Servers by their key. Servers must be `Send`, so that providers can list them on worker
+/// threads and hand the map back to the main thread as it is.
+pub type ServerMap = IndexMap<Cow<'static, str>, Box<dyn ServerConnection + Send>>;
 
 pub struct ServerAction<'a> {
     static_parameters: Parameters,
@@ -292,16 +430,33 @@ pub trait Actionable {
 /// grouped together.
 ///
 /// It manages zero, one or multiple servers.
+///
+/// The futures returned are `LocalBoxFuture`s polled on the GTK main loop. Connections that
+/// do slow work should move it to a worker thread themselves and only await the result, which
+/// is why `ServerMap` is `Send`.
 pub trait Connection: Actionable {
     /// Metadata about the connection.
     fn metadata(&self) -> ConnectionMetadata;
 
     /// Returns the servers managed by this connection.
     fn servers(&self) -> LocalBoxFuture<ConnectionResult<ServerMap>>;
+
+    /// Like `servers`, but reports the progress of loading to `progress` and stops early once it
+    /// is cancelled. Connections that load their servers in several slow steps should implement
+    /// this.
+    fn servers_with_progress<'a>(
+        &'a self,
+        progress: &'a LoadProgress,
+    ) -> LocalBoxFuture<'a, ConnectionResult<ServerMap>> {
+        Box::pin(progress.unless_cancelled(self.servers()))
+    }
 }
 
 /// A single instance of a server to connect to.
 /// It may contain sub-servers.
+///
+/// Like for `Connection`, its futures run on the GTK main loop. Adapters are created there,
+/// since they build widgets.
 pub trait ServerConnection: Actionable {
     /// Metadata about the server.
     fn metadata(&self) -> ServerMetadata;
@@ -314,6 +469,21 @@ pub trait ServerConnection: Actionable {
     /// that adapter).
     fn create_adapter(&self, tag: &str) -> LocalBoxFuture<ConnectionResult<Box<dyn Adapter>>>;
 
+    /// Accounts that can be used to log in to the server as tuples (id, human-readable name).
+    /// If there is more than one, the user chooses an account before connecting.
+    fn accounts(&self) -> Vec<(Cow<str>, Cow<str>)> {
+        vec![]
+    }
+
+    /// Like `create_adapter`, but logs in with the account `account` (see `accounts`).
+    fn create_adapter_for_account(
+        &self,
+        tag: &str,
+        _account: &str,
+    ) -> LocalBoxFuture<ConnectionResult<Box<dyn Adapter>>> {
+        self.create_adapter(tag)
+    }
+
     /// Returns the sub-servers grouped under this server (if any).
     fn servers(&self) -> LocalBoxFuture<ConnectionResult<ServerMap>> {
         Box::pin(async move { Ok(IndexMap::new()) })
@@ -0,0 +1,171 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Export of servers as connection files, so they can be opened with other clients.
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk::gio;
+use secure_string::SecureString;
+
+/// A standard `.rdp` file, as read by mstsc, FreeRDP and most other RDP clients.
+#[derive(Debug, Clone, Default)]
+pub struct RdpFile {
+    pub host: String,
+    pub port: u32,
+    pub user: Option<String>,
+    /// Stored in plain text. Clients that do not support this ignore it and ask for the
+    /// password instead.
+    pub password: Option<SecureString>,
+    pub smartcard: bool,
+    pub microphone: bool,
+}
+
+impl RdpFile {
+    pub fn to_file_contents(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        let mut lines = vec![
+            format!("full address:s:{host}:{}", self.port),
+            "screen mode id:i:2".to_string(),
+            format!("redirectsmartcards:i:{}", i32::from(self.smartcard)),
+            format!("audiocapturemode:i:{}", i32::from(self.microphone)),
+        ];
+        if let Some(user) = self.user.as_deref().filter(|user| !user.is_empty()) {
+            lines.push(format!("username:s:{user}"));
+        }
+        match &self.password {
+            Some(password) => {
+                lines.push(format!("ClearTextPassword:s:{}", password.unsecure()));
+                lines.push("prompt for credentials:i:0".to_string());
+            }
+            None => lines.push("prompt for credentials:i:1".to_string()),
+        }
+        // Windows clients expect CRLF line endings.
+        lines.iter().map(|line| format!("{line}\r\n")).collect()
+    }
+}
+
+/// A virt-viewer connection file (`.vv`) for SPICE servers, as opened by remote-viewer.
+#[derive(Debug, Clone, Default)]
+pub struct VvFile {
+    /// Shown as the window title by remote-viewer.
+    pub title: String,
+    pub host: String,
+    pub port: Option<u32>,
+    pub tls_port: Option<u32>,
+    pub user: Option<String>,
+    /// Stored in plain text.
+    pub password: Option<SecureString>,
+    /// HTTP proxy to connect through, as `http://host:port`.
+    pub proxy: Option<String>,
+    /// PEM encoded CA certificate to verify the TLS connection with.
+    pub ca: Option<String>,
+    /// Subject of the certificate of the server, if it does not match `host`.
+    pub host_subject: Option<String>,
+}
+
+impl VvFile {
+    pub fn to_file_contents(&self) -> String {
+        let mut entries = vec![
+            ("type", "spice".to_string()),
+            ("title", self.title.clone()),
+            ("host", self.host.clone()),
+        ];
+        if let Some(port) = self.port {
+            entries.push(("port", port.to_string()));
+        }
+        if let Some(tls_port) = self.tls_port {
+            entries.push(("tls-port", tls_port.to_string()));
+        }
+        if let Some(user) = self.user.as_deref().filter(|user| !user.is_empty()) {
+            entries.push(("username", user.to_string()));
+        }
+        if let Some(password) = &self.password {
+            entries.push(("password", password.unsecure().to_string()));
+        }
+        if let Some(proxy) = &self.proxy {
+            entries.push(("proxy", proxy.clone()));
+        }
+        if let Some(ca) = &self.ca {
+            entries.push(("ca", ca.clone()));
+        }
+        if let Some(host_subject) = &self.host_subject {
+            entries.push(("host-subject", host_subject.clone()));
+        }
+
+        let mut contents = "[virt-viewer]\n".to_string();
+        for (key, value) in entries {
+            contents.push_str(&format!("{key}={}\n", escape_key_file_value(&value)));
+        }
+        contents
+    }
+}
+
+/// Escapes `value` as a value of a GLib key file, which the file is read as.
+fn escape_key_file_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Asks where to save the file `initial_name` and writes `contents` to it. `filter_name`
+/// describes the files with the suffix of `initial_name`. Returns `false` if the user
+/// cancelled the dialog.
+pub async fn save_connection_file(
+    window: Option<&gtk::Window>,
+    initial_name: &str,
+    filter_name: &str,
+    contents: String,
+) -> anyhow::Result<bool> {
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some(filter_name));
+    if let Some((_, suffix)) = initial_name.rsplit_once('.') {
+        filter.add_suffix(suffix);
+    }
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&filter);
+    let file_dialog = gtk::FileDialog::builder()
+        .title(gettext("Export Connection File"))
+        .initial_name(initial_name.replace('/', "-"))
+        .filters(&filters)
+        .modal(true)
+        .build();
+    let Ok(file) = file_dialog.save_future(window).await else {
+        return Ok(false);
+    };
+
+    file.replace_contents_future(
+        contents.into_bytes(),
+        None,
+        false,
+        gio::FileCreateFlags::PRIVATE | gio::FileCreateFlags::REPLACE_DESTINATION,
+    )
+    .await
+    .map_err(|(_, err)| err)?;
+    Ok(true)
+}
@@ -0,0 +1,416 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Parsing and formatting of hosts and `host:port` pairs, including IPv6 literals.
+//!
+//! Accepted forms are host names (`example.com`), IPv4 addresses (`192.0.2.1`), IPv6 addresses
+//! with or without brackets and optionally with a zone index (`::1`, `[fe80::1%eth0]`) and all of
+//! these followed by a port (`example.com:5900`, `[::1]:5900`). IPv6 addresses followed by a port
+//! must be written in brackets.
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use gettextrs::gettext;
+
+use crate::i18n::gettext_f;
+
+const MAX_HOSTNAME_LEN: usize = 253;
+const MAX_LABEL_LEN: usize = 63;
+
+/// A host, optionally with a port. IPv6 addresses are stored without brackets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostPort {
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl HostPort {
+    /// Whether the host is an IPv6 address.
+    pub fn is_ipv6(&self) -> bool {
+        is_ipv6_host(&self.host)
+    }
+}
+
+impl fmt::Display for HostPort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.port {
+            Some(port) => f.write_str(&format_host_port(&self.host, port)),
+            None => f.write_str(&self.host),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostParseError {
+    Empty,
+    UnclosedBracket,
+    InvalidIpv6(String),
+    InvalidHostname(String),
+    InvalidPort(String),
+    MissingPort,
+    UnexpectedPort,
+}
+
+impl fmt::Display for HostParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            HostParseError::Empty => gettext("The host is empty."),
+            HostParseError::UnclosedBracket => {
+                gettext("The IPv6 address is missing a closing bracket.")
+            }
+            HostParseError::InvalidIpv6(addr) => {
+                gettext_f("“{addr}” is not a valid IPv6 address.", &[("addr", addr)])
+            }
+            HostParseError::InvalidHostname(host) => {
+                gettext_f("“{host}” is not a valid host name.", &[("host", host)])
+            }
+            HostParseError::InvalidPort(port) => {
+                gettext_f("“{port}” is not a valid port.", &[("port", port)])
+            }
+            HostParseError::MissingPort => gettext("The port is missing."),
+            HostParseError::UnexpectedPort => {
+                gettext("The host must not contain a port. Enter the port separately.")
+            }
+        };
+        f.write_str(&msg)
+    }
+}
+
+impl std::error::Error for HostParseError {}
+
+/// Parses a host with an optional port.
+pub fn parse_host_port(input: &str) -> Result<HostPort, HostParseError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(HostParseError::Empty);
+    }
+
+    if let Some(rest) = input.strip_prefix('[') {
+        let (addr, after) = rest
+            .split_once(']')
+            .ok_or(HostParseError::UnclosedBracket)?;
+        validate_ipv6(addr)?;
+        let port = match after {
+            "" => None,
+            _ => {
+                Some(parse_port(after.strip_prefix(':').ok_or_else(|| {
+                    HostParseError::InvalidPort(after.to_string())
+                })?)?)
+            }
+        };
+        return Ok(HostPort {
+            host: addr.to_string(),
+            port,
+        });
+    }
+
+    match input.matches(':').count() {
+        0 => {
+            validate_hostname(input)?;
+            Ok(HostPort {
+                host: input.to_string(),
+                port: None,
+            })
+        }
+        1 => {
+            let (host, port) = input.split_once(':').unwrap();
+            validate_hostname(host)?;
+            Ok(HostPort {
+                host: host.to_string(),
+                port: Some(parse_port(port)?),
+            })
+        }
+        // More than one colon: a bare IPv6 address, which can not have a port.
+        _ => {
+            validate_ipv6(input)?;
+            Ok(HostPort {
+                host: input.to_string(),
+                port: None,
+            })
+        }
+    }
+}
+
+/// Parses a host that must not contain a port. Brackets around IPv6 addresses are removed.
+pub fn parse_host(input: &str) -> Result<String, HostParseError> {
+    let host_port = parse_host_port(input)?;
+    match host_port.port {
+        None => Ok(host_port.host),
+        Some(_) => Err(HostParseError::UnexpectedPort),
+    }
+}
+
+/// Parses a host that must be followed by a port.
+pub fn parse_host_with_port(input: &str) -> Result<(String, u16), HostParseError> {
+    let host_port = parse_host_port(input)?;
+    match host_port.port {
+        Some(port) => Ok((host_port.host, port)),
+        None => Err(HostParseError::MissingPort),
+    }
+}
+
+/// Formats a host and port as `host:port`, with brackets around IPv6 addresses.
+pub fn format_host_port(host: &str, port: impl fmt::Display) -> String {
+    if is_ipv6_host(host) {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    }
+}
+
+fn is_ipv6_host(host: &str) -> bool {
+    host.contains(':')
+}
+
+fn parse_port(port: &str) -> Result<u16, HostParseError> {
+    port.parse::<u16>()
+        .ok()
+        .filter(|port| *port != 0)
+        .ok_or_else(|| HostParseError::InvalidPort(port.to_string()))
+}
+
+fn validate_ipv6(addr: &str) -> Result<(), HostParseError> {
+    let (ip, zone) = match addr.split_once('%') {
+        Some((ip, zone)) => (ip, Some(zone)),
+        None => (addr, None),
+    };
+    let zone_valid = zone.map_or(true, |zone| {
+        !zone.is_empty()
+            && zone
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    });
+    if ip.parse::<Ipv6Addr>().is_ok() && zone_valid {
+        Ok(())
+    } else {
+        Err(HostParseError::InvalidIpv6(addr.to_string()))
+    }
+}
+
+fn validate_hostname(host: &str) -> Result<(), HostParseError> {
+    if host.is_empty() {
+        return Err(HostParseError::Empty);
+    }
+    if host.parse::<Ipv4Addr>().is_ok() {
+        return Ok(());
+    }
+    let invalid = || HostParseError::InvalidHostname(host.to_string());
+
+    let name = host.strip_suffix('.').unwrap_or(host);
+    if name.is_empty() || name.len() > MAX_HOSTNAME_LEN {
+        return Err(invalid());
+    }
+    for label in name.split('.') {
+        let valid = !label.is_empty()
+            && label.len() <= MAX_LABEL_LEN
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_'));
+        if !valid {
+            return Err(invalid());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host_port(host: &str, port: Option<u16>) -> HostPort {
+        HostPort {
+            host: host.to_string(),
+            port,
+        }
+    }
+
+    #[test]
+    fn parse_valid() {
+        for (input, expected) in [
+            ("example.com", host_port("example.com", None)),
+            ("example.com.", host_port("example.com.", None)),
+            ("example.com:5900", host_port("example.com", Some(5900))),
+            ("  example.com:5900  ", host_port("example.com", Some(5900))),
+            ("pve", host_port("pve", None)),
+            ("pve:8006", host_port("pve", Some(8006))),
+            ("my_host", host_port("my_host", None)),
+            ("192.0.2.1", host_port("192.0.2.1", None)),
+            ("192.0.2.1:3389", host_port("192.0.2.1", Some(3389))),
+            ("::1", host_port("::1", None)),
+            ("2001:db8::1", host_port("2001:db8::1", None)),
+            // Without brackets, a trailing group is part of the address, not a port.
+            ("::1:5900", host_port("::1:5900", None)),
+            ("fe80::1%eth0", host_port("fe80::1%eth0", None)),
+            ("fe80::1%3", host_port("fe80::1%3", None)),
+            ("[::1]", host_port("::1", None)),
+            ("[::1]:5900", host_port("::1", Some(5900))),
+            ("[::1]:65535", host_port("::1", Some(65535))),
+            ("[2001:db8::1]:22", host_port("2001:db8::1", Some(22))),
+            ("[fe80::1%eth0]", host_port("fe80::1%eth0", None)),
+            ("[fe80::1%eth0]:22", host_port("fe80::1%eth0", Some(22))),
+            (
+                "[fe80::1%br-lan.10]:22",
+                host_port("fe80::1%br-lan.10", Some(22)),
+            ),
+            (
+                "[::ffff:192.0.2.1]:80",
+                host_port("::ffff:192.0.2.1", Some(80)),
+            ),
+        ] {
+            assert_eq!(parse_host_port(input), Ok(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let long_label = format!("{}.example.com", "a".repeat(MAX_LABEL_LEN + 1));
+        let long_name = vec!["a"; MAX_HOSTNAME_LEN / 2 + 2].join(".");
+        for (input, expected) in [
+            ("", HostParseError::Empty),
+            ("   ", HostParseError::Empty),
+            (":5900", HostParseError::Empty),
+            ("[::1", HostParseError::UnclosedBracket),
+            ("[::1]5900", HostParseError::InvalidPort("5900".into())),
+            ("[::1]:", HostParseError::InvalidPort("".into())),
+            ("[::1]:0", HostParseError::InvalidPort("0".into())),
+            ("[::1]:65536", HostParseError::InvalidPort("65536".into())),
+            ("[::1]:-1", HostParseError::InvalidPort("-1".into())),
+            ("[]", HostParseError::InvalidIpv6("".into())),
+            ("[::1::2]", HostParseError::InvalidIpv6("::1::2".into())),
+            (
+                "[example.com]:80",
+                HostParseError::InvalidIpv6("example.com".into()),
+            ),
+            (
+                "[192.0.2.1]:80",
+                HostParseError::InvalidIpv6("192.0.2.1".into()),
+            ),
+            ("[fe80::1%]", HostParseError::InvalidIpv6("fe80::1%".into())),
+            (
+                "[fe80::1%eth 0]",
+                HostParseError::InvalidIpv6("fe80::1%eth 0".into()),
+            ),
+            ("fe80::1%", HostParseError::InvalidIpv6("fe80::1%".into())),
+            ("1:2:3", HostParseError::InvalidIpv6("1:2:3".into())),
+            (
+                "example.com:http",
+                HostParseError::InvalidPort("http".into()),
+            ),
+            ("example.com:0", HostParseError::InvalidPort("0".into())),
+            (
+                "-example.com",
+                HostParseError::InvalidHostname("-example.com".into()),
+            ),
+            (
+                "example-.com",
+                HostParseError::InvalidHostname("example-.com".into()),
+            ),
+            (
+                "example..com",
+                HostParseError::InvalidHostname("example..com".into()),
+            ),
+            (".", HostParseError::InvalidHostname(".".into())),
+            (
+                "exa mple.com",
+                HostParseError::InvalidHostname("exa mple.com".into()),
+            ),
+            (
+                long_label.as_str(),
+                HostParseError::InvalidHostname(long_label.clone()),
+            ),
+            (
+                long_name.as_str(),
+                HostParseError::InvalidHostname(long_name.clone()),
+            ),
+        ] {
+            assert_eq!(parse_host_port(input), Err(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_with_and_without_port() {
+        assert_eq!(parse_host("[::1]"), Ok("::1".into()));
+        assert_eq!(parse_host("fe80::1%eth0"), Ok("fe80::1%eth0".into()));
+        assert_eq!(parse_host("pve"), Ok("pve".into()));
+        assert_eq!(parse_host("pve:8006"), Err(HostParseError::UnexpectedPort));
+        assert_eq!(
+            parse_host("[::1]:5900"),
+            Err(HostParseError::UnexpectedPort)
+        );
+
+        assert_eq!(parse_host_with_port("[::1]:5900"), Ok(("::1".into(), 5900)));
+        assert_eq!(
+            parse_host_with_port("[fe80::1%eth0]:22"),
+            Ok(("fe80::1%eth0".into(), 22))
+        );
+        assert_eq!(parse_host_with_port("pve:8006"), Ok(("pve".into(), 8006)));
+        assert_eq!(
+            parse_host_with_port("pve"),
+            Err(HostParseError::MissingPort)
+        );
+        assert_eq!(
+            parse_host_with_port("::1"),
+            Err(HostParseError::MissingPort)
+        );
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(format_host_port("example.com", 5900), "example.com:5900");
+        assert_eq!(format_host_port("192.0.2.1", 22), "192.0.2.1:22");
+        assert_eq!(format_host_port("::1", 5900), "[::1]:5900");
+        assert_eq!(format_host_port("fe80::1%eth0", 22), "[fe80::1%eth0]:22");
+        assert_eq!(host_port("::1", None).to_string(), "::1");
+        assert_eq!(host_port("::1", Some(1)).to_string(), "[::1]:1");
+    }
+
+    #[test]
+    fn format_parse_round_trip() {
+        for host in [
+            "example.com",
+            "pve",
+            "192.0.2.1",
+            "::1",
+            "::",
+            "2001:db8::1",
+            "::ffff:192.0.2.1",
+            "fe80::1%eth0",
+        ] {
+            assert_eq!(host_port(host, None).is_ipv6(), host.contains(':'));
+            for port in [1, 22, 5900, u16::MAX] {
+                let formatted = format_host_port(host, port);
+                assert_eq!(
+                    parse_host_with_port(&formatted),
+                    Ok((host.to_string(), port)),
+                    "{formatted}"
+                );
+                let formatted = host_port(host, Some(port)).to_string();
+                assert_eq!(
+                    parse_host_port(&formatted),
+                    Ok(host_port(host, Some(port))),
+                    "{formatted}"
+                );
+            }
+            assert_eq!(
+                parse_host(&host_port(host, None).to_string()),
+                Ok(host.into())
+            );
+        }
+    }
+}
@@ -15,7 +15,10 @@
  *
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
-use gettextrs::gettext;
+//! Translation helpers and locale-aware formatting of values shown in the UI.
+use std::ffi::CStr;
+
+use gettextrs::{gettext, ngettext};
 
 // Taken from Fractal. Thanks! : https://gitlab.gnome.org/World/fractal/-/blob/main/src/i18n.rs
 /// Like `gettext`, but replaces named variables with the given dictionary.
@@ -41,3 +44,110 @@ pub fn freplace(s: String, args: &[(&str, &str)]) -> String {
 
     s
 }
+
+/// Like `ngettext`, but replaces `{n}` with `n`.
+pub fn ngettext_n(msgid: &str, msgid_plural: &str, n: u32) -> String {
+    freplace(ngettext(msgid, msgid_plural, n), &[("n", &n.to_string())])
+}
+
+/// Decimal separator of the current locale, eg. `,` for German.
+fn decimal_separator() -> String {
+    // SAFETY: `localeconv` returns a pointer to static data, which is only invalidated by
+    //         changing the locale. GTK sets the locale once during initialization.
+    unsafe {
+        let lconv = nix::libc::localeconv();
+        if lconv.is_null() || (*lconv).decimal_point.is_null() {
+            return ".".to_string();
+        }
+        CStr::from_ptr((*lconv).decimal_point)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Formats `value` with `decimals` decimal places and the decimal separator of the locale.
+pub fn format_decimal(value: f64, decimals: usize) -> String {
+    let formatted = format!("{value:.decimals$}");
+    if decimals == 0 {
+        formatted
+    } else {
+        formatted.replacen('.', &decimal_separator(), 1)
+    }
+}
+
+/// Formats a fraction between 0 and 1 as percentage, eg. `42 %` for `0.42`.
+pub fn format_percent(fraction: f64, decimals: usize) -> String {
+    gettext_f(
+        "{percent} %",
+        &[(
+            "percent",
+            &format_decimal(fraction.clamp(0.0, 1.0) * 100.0, decimals),
+        )],
+    )
+}
+
+/// Formats a size in bytes, eg. `1.2 GB`.
+pub fn format_bytes(bytes: u64) -> String {
+    glib::format_size(bytes).into()
+}
+
+/// Formats a duration given in seconds with its two most significant units, eg. `3d 4h`.
+pub fn format_duration(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    let days = seconds / 86400;
+    let hours = (seconds % 86400) / 3600;
+    let minutes = (seconds % 3600) / 60;
+
+    if days > 0 {
+        gettext_f(
+            "{days}d {hours}h",
+            &[("days", &days.to_string()), ("hours", &hours.to_string())],
+        )
+    } else if hours > 0 {
+        gettext_f(
+            "{hours}h {minutes}m",
+            &[
+                ("hours", &hours.to_string()),
+                ("minutes", &minutes.to_string()),
+            ],
+        )
+    } else {
+        gettext_f("{minutes}m", &[("minutes", &minutes.to_string())])
+    }
+}
+
+/// Describes `time` relative to now, eg. "3 minutes ago". Times older than a week are shown
+/// as date.
+pub fn format_relative_time(time: &glib::DateTime) -> String {
+    let Ok(now) = glib::DateTime::now(&time.timezone()) else {
+        return format_date(time);
+    };
+    let seconds = now.difference(time).as_seconds();
+    // Small differences into the future are clock skew between hosts.
+    if seconds < 60 {
+        return gettext("Just now");
+    }
+    let minutes = seconds / 60;
+    if minutes < 60 {
+        return ngettext_n("{n} minute ago", "{n} minutes ago", minutes as u32);
+    }
+    let hours = minutes / 60;
+    if hours < 24 {
+        return ngettext_n("{n} hour ago", "{n} hours ago", hours as u32);
+    }
+    let days = hours / 24;
+    if days < 7 {
+        return ngettext_n("{n} day ago", "{n} days ago", days as u32);
+    }
+    format_date(time)
+}
+
+/// Formats the date of `time` in the representation of the locale.
+pub fn format_date(time: &glib::DateTime) -> String {
+    time.format("%x").map(Into::into).unwrap_or_default()
+}
+
+/// Formats `time` with date and time of day in the representation of the locale.
+pub fn format_date_time(time: &glib::DateTime) -> String {
+    time.format("%x %X").map(Into::into).unwrap_or_default()
+}
@@ -22,17 +22,26 @@ use std::path::PathBuf;
 
 use crate::config::LIBEXECDIR;
 use crate::connection::ConnectionError;
-pub use secrets::ManagesSecrets;
+pub use field_monitor_core::net;
+pub use field_monitor_core::template;
+pub use field_monitor_core::ManagesSecrets;
 
 #[macro_use]
 mod macros;
+pub mod action_failure;
 pub mod adapter;
 pub mod busy;
 pub mod config;
 pub mod connection;
+pub mod connection_file;
 pub mod gtk;
+pub mod host;
 pub mod i18n;
-mod secrets;
+pub mod password_command;
+pub mod resolve;
+pub mod sftp;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 pub fn config_error(connection_title: Option<String>) -> ConnectionError {
     ConnectionError::General(
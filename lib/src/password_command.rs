@@ -0,0 +1,98 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Looking up passwords with an external command, such as a password manager, instead of
+//! storing them.
+//!
+//! The command is split like a shell command line, but not run by a shell. Its first line of
+//! output is the password, so `pass show <entry>` and `secret-tool lookup <attr> <value>` can be
+//! used as they are.
+use std::ffi::OsStr;
+
+use anyhow::anyhow;
+use gettextrs::gettext;
+use gtk::gio;
+use log::debug;
+use secure_string::SecureString;
+
+use crate::connection::ConnectionError;
+use crate::i18n::gettext_f;
+use crate::template::{expand_template_with, lookup_variable};
+
+/// Runs the password command `command` and returns the password it printed.
+/// `${NAME}` placeholders in `command` are expanded using `variables` first, then the template
+/// variables. They are expanded after splitting the command into arguments, so values containing
+/// spaces or quotes always stay a single argument.
+pub async fn run_password_command(
+    command: &str,
+    variables: &[(&str, &str)],
+) -> Result<SecureString, ConnectionError> {
+    let lookup = |name: &str| {
+        variables
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+            .or_else(|| lookup_variable(name))
+    };
+    let argv = glib::shell_parse_argv(command)
+        .map_err(|err| {
+            ConnectionError::General(
+                Some(gettext("The password command is invalid.")),
+                err.into(),
+            )
+        })?
+        .iter()
+        .map(|arg| expand_template_with(&arg.to_string_lossy(), lookup))
+        .collect::<Vec<_>>();
+    let program = argv.first().cloned().unwrap_or_default();
+    debug!("running password command {program}");
+
+    // Not an authentication failure: asking for a password would not help.
+    let failed = |err: anyhow::Error| {
+        ConnectionError::General(
+            Some(gettext_f(
+                "Failed to get the password from “{command}”.",
+                &[("command", &program)],
+            )),
+            err,
+        )
+    };
+    let argv = argv.iter().map(OsStr::new).collect::<Vec<_>>();
+    let process = gio::Subprocess::newv(
+        &argv,
+        gio::SubprocessFlags::STDOUT_PIPE | gio::SubprocessFlags::STDERR_PIPE,
+    )
+    .map_err(|err| failed(err.into()))?;
+    let (stdout, stderr) = process
+        .communicate_utf8_future(None)
+        .await
+        .map_err(|err| failed(err.into()))?;
+    if !process.is_successful() {
+        return Err(failed(anyhow!(
+            "exited with status {}: {}",
+            process.exit_status(),
+            stderr.as_deref().unwrap_or_default().trim()
+        )));
+    }
+
+    let stdout = stdout.unwrap_or_default();
+    let password = stdout.lines().next().unwrap_or_default();
+    if password.is_empty() {
+        return Err(failed(anyhow!("no password was printed")));
+    }
+    Ok(SecureString::from(password))
+}
@@ -0,0 +1,276 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Resolving hosts and checking that they accept connections before an adapter is created.
+//!
+//! Adapters usually only report that the connection failed. Checking the host beforehand
+//! allows telling the user whether the name could not be resolved, the host could not be reached
+//! or the connection was refused.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::RwLock;
+
+use gettextrs::gettext;
+use gtk::gio;
+use gtk::prelude::*;
+use log::debug;
+
+use crate::i18n::gettext_f;
+
+const PROBE_TIMEOUT_SECS: u32 = 10;
+
+static PREFERRED_FAMILIES: RwLock<BTreeMap<String, AddressFamily>> = RwLock::new(BTreeMap::new());
+
+/// The address family used to connect to a host.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AddressFamily {
+    #[default]
+    Any,
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    fn of(addr: &gio::InetAddress) -> Self {
+        match addr.family() {
+            gio::SocketFamily::Ipv6 => Self::Ipv6,
+            _ => Self::Ipv4,
+        }
+    }
+
+    fn includes(self, addr: &gio::InetAddress) -> bool {
+        self == Self::Any || self == Self::of(addr)
+    }
+}
+
+impl fmt::Display for AddressFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressFamily::Any => f.write_str(&gettext("any address family")),
+            AddressFamily::Ipv4 => f.write_str("IPv4"),
+            AddressFamily::Ipv6 => f.write_str("IPv6"),
+        }
+    }
+}
+
+/// Sets the address family to use for connections to `host` for the rest of the session.
+pub fn set_preferred_family(host: &str, family: AddressFamily) {
+    let mut families = PREFERRED_FAMILIES.write().unwrap();
+    match family {
+        AddressFamily::Any => families.remove(host),
+        family => families.insert(host.to_string(), family),
+    };
+}
+
+/// The address family to use for connections to `host`.
+pub fn preferred_family(host: &str) -> AddressFamily {
+    PREFERRED_FAMILIES
+        .read()
+        .unwrap()
+        .get(host)
+        .copied()
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReachabilityErrorKind {
+    NameNotFound,
+    NoAddressOfFamily(AddressFamily),
+    NoRoute,
+    ConnectionRefused,
+    TimedOut,
+    Other(String),
+}
+
+/// The host of a server could not be resolved or did not accept a connection.
+#[derive(Debug, Clone)]
+pub struct ReachabilityError {
+    pub host: String,
+    pub port: u16,
+    pub kind: ReachabilityErrorKind,
+    /// Another address family the host has addresses of, that may be worth retrying with.
+    pub retry_family: Option<AddressFamily>,
+}
+
+impl ReachabilityError {
+    /// A short title for the error.
+    pub fn title(&self) -> String {
+        match self.kind {
+            ReachabilityErrorKind::NameNotFound | ReachabilityErrorKind::NoAddressOfFamily(_) => {
+                gettext("Server Not Found")
+            }
+            ReachabilityErrorKind::NoRoute => gettext("Server Unreachable"),
+            ReachabilityErrorKind::ConnectionRefused => gettext("Connection Refused"),
+            ReachabilityErrorKind::TimedOut => gettext("Server Not Responding"),
+            ReachabilityErrorKind::Other(_) => gettext("Connection Failed"),
+        }
+    }
+}
+
+impl fmt::Display for ReachabilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let port = self.port.to_string();
+        let msg = match &self.kind {
+            ReachabilityErrorKind::NameNotFound => gettext_f(
+                "The host name “{host}” could not be found. Check the host name and your DNS settings.",
+                &[("host", &self.host)],
+            ),
+            ReachabilityErrorKind::NoAddressOfFamily(family) => gettext_f(
+                "The host “{host}” has no {family} address.",
+                &[("host", &self.host), ("family", &family.to_string())],
+            ),
+            ReachabilityErrorKind::NoRoute => gettext_f(
+                "There is no network route to “{host}”. Check your network connection, VPN and firewall settings.",
+                &[("host", &self.host)],
+            ),
+            ReachabilityErrorKind::ConnectionRefused => gettext_f(
+                "“{host}” refused the connection on port {port}. Make sure the server is running and listening on this port.",
+                &[("host", &self.host), ("port", &port)],
+            ),
+            ReachabilityErrorKind::TimedOut => gettext_f(
+                "“{host}” did not respond on port {port}.",
+                &[("host", &self.host), ("port", &port)],
+            ),
+            ReachabilityErrorKind::Other(msg) => gettext_f(
+                "Could not connect to “{host}”: {msg}",
+                &[("host", &self.host), ("msg", msg)],
+            ),
+        };
+        f.write_str(&msg)
+    }
+}
+
+impl std::error::Error for ReachabilityError {}
+
+/// A host that accepted a connection, see [`check_reachable`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reachable {
+    /// The host as given. Adapters must keep using it for name checks, eg. of TLS certificates
+    /// or Kerberos tickets, even if they connect to [`Self::address`].
+    pub host: String,
+    /// The address that accepted the connection.
+    pub address: SocketAddr,
+    /// The address family set for the host with [`set_preferred_family`].
+    pub family: AddressFamily,
+}
+
+impl Reachable {
+    /// The IP address adapters must connect to, if an address family was set for the host.
+    /// `None` if they should resolve the host on their own.
+    pub fn required_address(&self) -> Option<String> {
+        match self.family {
+            AddressFamily::Any => None,
+            _ => Some(self.address.ip().to_string()),
+        }
+    }
+}
+
+/// Resolves `host` and checks that it accepts TCP connections on `port`, using the address
+/// family set with [`set_preferred_family`].
+///
+/// Returns the address that was reached. Adapters should connect to the host name, unless an
+/// address family was set for it, see [`Reachable::required_address`].
+pub async fn check_reachable(host: &str, port: u16) -> Result<Reachable, ReachabilityError> {
+    let family = preferred_family(host);
+    let error = |kind, retry_family| ReachabilityError {
+        host: host.to_string(),
+        port,
+        kind,
+        retry_family,
+    };
+
+    let addresses = gio::Resolver::default()
+        .lookup_by_name_future(host)
+        .await
+        .map_err(|err| {
+            debug!("failed to resolve {host}: {err}");
+            match err.kind::<gio::ResolverError>() {
+                Some(gio::ResolverError::NotFound) => {
+                    error(ReachabilityErrorKind::NameNotFound, None)
+                }
+                _ => error(
+                    ReachabilityErrorKind::Other(err.message().to_string()),
+                    None,
+                ),
+            }
+        })?;
+
+    let has_ipv4 = addresses
+        .iter()
+        .any(|a| AddressFamily::of(a) == AddressFamily::Ipv4);
+    let has_ipv6 = addresses
+        .iter()
+        .any(|a| AddressFamily::of(a) == AddressFamily::Ipv6);
+    let candidates: Vec<_> = addresses.iter().filter(|a| family.includes(a)).collect();
+
+    let Some(first) = candidates.first() else {
+        let retry_family = match family {
+            AddressFamily::Ipv4 if has_ipv6 => Some(AddressFamily::Ipv6),
+            AddressFamily::Ipv6 if has_ipv4 => Some(AddressFamily::Ipv4),
+            _ => None,
+        };
+        return Err(error(
+            ReachabilityErrorKind::NoAddressOfFamily(family),
+            retry_family,
+        ));
+    };
+    // Offer the family that was not (primarily) tried, if the host has addresses of both.
+    let retry_family = (has_ipv4 && has_ipv6).then(|| match AddressFamily::of(first) {
+        AddressFamily::Ipv6 => AddressFamily::Ipv4,
+        _ => AddressFamily::Ipv6,
+    });
+
+    let client = gio::SocketClient::new();
+    client.set_timeout(PROBE_TIMEOUT_SECS);
+
+    let mut last_error = None;
+    for address in candidates {
+        let socket_address = gio::InetSocketAddress::new(address, port);
+        match client.connect_future(&socket_address).await {
+            Ok(connection) => {
+                connection.close_future(glib::Priority::DEFAULT).await.ok();
+                let ip = address
+                    .to_str()
+                    .parse::<IpAddr>()
+                    .map_err(|err| error(ReachabilityErrorKind::Other(err.to_string()), None))?;
+                return Ok(Reachable {
+                    host: host.to_string(),
+                    address: SocketAddr::new(ip, port),
+                    family,
+                });
+            }
+            Err(err) => {
+                debug!("failed to connect to {host} ({}): {err}", address.to_str());
+                last_error = Some(err);
+            }
+        }
+    }
+
+    let kind = match last_error {
+        Some(err) => match err.kind::<gio::IOErrorEnum>() {
+            Some(gio::IOErrorEnum::ConnectionRefused) => ReachabilityErrorKind::ConnectionRefused,
+            Some(gio::IOErrorEnum::HostUnreachable | gio::IOErrorEnum::NetworkUnreachable) => {
+                ReachabilityErrorKind::NoRoute
+            }
+            Some(gio::IOErrorEnum::TimedOut) => ReachabilityErrorKind::TimedOut,
+            _ => ReachabilityErrorKind::Other(err.message().to_string()),
+        },
+        None => ReachabilityErrorKind::NameNotFound,
+    };
+    Err(error(kind, retry_family))
+}
@@ -0,0 +1,67 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Browsing the files of SSH hosts with the file manager.
+use glib::UriFlags;
+use log::warn;
+
+use crate::connection::ServerAction;
+use crate::i18n::gettext_f;
+
+/// The `sftp://` URI of `host`, with the user and port if given. IPv6 addresses are put in
+/// brackets and the user is escaped.
+pub fn sftp_uri(user: Option<&str>, host: &str, port: Option<u16>) -> String {
+    glib::Uri::join(
+        UriFlags::NONE,
+        Some("sftp"),
+        user.filter(|user| !user.is_empty()),
+        Some(host),
+        port.map(i32::from).unwrap_or(-1),
+        "/",
+        None,
+        None,
+    )
+    .to_string()
+}
+
+/// An action opening `uri` with the default file manager. Inside of Flatpak this goes through
+/// the OpenURI portal.
+pub fn browse_files_action<'a>(uri: String) -> ServerAction<'a> {
+    ServerAction::new(
+        Box::new(uri),
+        Box::new(|params, window, toov| {
+            Box::pin(async move {
+                let uri = params.downcast::<String>().unwrap();
+                if let Err(err) = gtk::UriLauncher::new(&uri)
+                    .launch_future(window.as_ref())
+                    .await
+                {
+                    warn!("failed to open {uri}: {err}");
+                    if let Some(toov) = toov {
+                        toov.add_toast(
+                            adw::Toast::builder()
+                                .title(gettext_f("Failed to open {url}.", &[("url", &uri)]))
+                                .timeout(5)
+                                .build(),
+                        );
+                    }
+                }
+                false
+            })
+        }),
+    )
+}
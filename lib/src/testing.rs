@@ -0,0 +1,209 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Helpers for testing connection providers without a running Field Monitor.
+//!
+//! This is only available with the `testing` feature. It provides configurations backed by an
+//! in-memory secret store and [`run_provider_lifecycle`], which loads a connection the way the
+//! application does and collects everything it shows about it. Adapters are only created, not
+//! connected, so no display server is needed.
+//!
+//! ```ignore
+//! let secrets = MemorySecretManager::shared();
+//! let mut configuration = mock_configuration("example", secrets.clone());
+//! configuration.set_value("host", "localhost");
+//! let report = block_on(run_provider_lifecycle(&*provider, configuration))?;
+//! assert_eq!(report.servers.len(), 1);
+//! ```
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::future::{BoxFuture, LocalBoxFuture};
+use parking_lot::Mutex;
+use secure_string::SecureString;
+use uuid::Uuid;
+
+use crate::connection::{
+    ConnectionConfiguration, ConnectionMetadata, ConnectionProvider, ConnectionResult,
+    ServerConnection, ServerMetadata,
+};
+use crate::ManagesSecrets;
+
+/// Secret store keeping secrets in memory, in place of the system keyring.
+#[derive(Debug, Default)]
+pub struct MemorySecretManager {
+    secrets: Mutex<HashMap<(String, String), SecureString>>,
+}
+
+impl MemorySecretManager {
+    /// A new, empty store in the form configurations expect it.
+    pub fn shared() -> Arc<Box<dyn ManagesSecrets>> {
+        Arc::new(Box::new(Self::default()))
+    }
+}
+
+impl ManagesSecrets for MemorySecretManager {
+    fn lookup(
+        &self,
+        connection_id: &str,
+        field: &str,
+    ) -> BoxFuture<anyhow::Result<Option<SecureString>>> {
+        let secret = self
+            .secrets
+            .lock()
+            .get(&(connection_id.to_string(), field.to_string()))
+            .cloned();
+        Box::pin(async move { Ok(secret) })
+    }
+
+    fn store(
+        &self,
+        connection_id: &str,
+        field: &str,
+        password: SecureString,
+    ) -> BoxFuture<anyhow::Result<()>> {
+        self.secrets
+            .lock()
+            .insert((connection_id.to_string(), field.to_string()), password);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn clear(&self, connection_id: &str, field: &str) -> BoxFuture<anyhow::Result<()>> {
+        self.secrets
+            .lock()
+            .remove(&(connection_id.to_string(), field.to_string()));
+        Box::pin(async { Ok(()) })
+    }
+
+    fn connection_ids(&self) -> BoxFuture<anyhow::Result<Vec<String>>> {
+        let mut ids = self
+            .secrets
+            .lock()
+            .keys()
+            .map(|(connection_id, _)| connection_id.clone())
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+        Box::pin(async move { Ok(ids) })
+    }
+
+    fn fields(&self, connection_id: &str) -> BoxFuture<anyhow::Result<Vec<String>>> {
+        let fields = self
+            .secrets
+            .lock()
+            .keys()
+            .filter(|(id, _)| id == connection_id)
+            .map(|(_, field)| field.clone())
+            .collect();
+        Box::pin(async move { Ok(fields) })
+    }
+}
+
+/// An empty configuration for a new connection of the provider with the tag `provider_tag`.
+pub fn mock_configuration(
+    provider_tag: &str,
+    secret_manager: Arc<Box<dyn ManagesSecrets>>,
+) -> ConnectionConfiguration {
+    ConnectionConfiguration::new(
+        Uuid::now_v7().to_string(),
+        provider_tag.to_string(),
+        secret_manager,
+    )
+}
+
+/// Runs `future` to completion on the default main context, like the application does.
+/// Providers may spawn work on it, so this must be used instead of other executors.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    glib::MainContext::default().block_on(future)
+}
+
+/// What a connection shows after it was loaded, see [`run_provider_lifecycle`].
+#[derive(Debug)]
+pub struct LifecycleReport {
+    pub title: Option<String>,
+    pub metadata: ConnectionMetadata,
+    pub actions: Vec<String>,
+    /// All servers of the connection, including sub-servers, in the order they are shown.
+    pub servers: Vec<ServerReport>,
+}
+
+#[derive(Debug)]
+pub struct ServerReport {
+    pub path: Vec<String>,
+    pub metadata: ServerMetadata,
+    pub actions: Vec<String>,
+    /// Tags of the adapters that could be created.
+    pub adapters: Vec<String>,
+}
+
+/// Loads `configuration` with `provider` and walks all servers of the connection. For every
+/// server, each supported adapter is created, but not connected.
+///
+/// Fails with the first error returned by the provider.
+pub async fn run_provider_lifecycle(
+    provider: &dyn ConnectionProvider,
+    configuration: ConnectionConfiguration,
+) -> ConnectionResult<LifecycleReport> {
+    let title = provider.title_for(&configuration).map(ToString::to_string);
+    let connection = provider.load_connection(configuration).await?;
+
+    let mut servers = Vec::new();
+    for (key, server) in connection.servers().await? {
+        collect_servers(vec![key.into_owned()], server.as_ref(), &mut servers).await?;
+    }
+
+    Ok(LifecycleReport {
+        title,
+        metadata: connection.metadata(),
+        actions: action_ids(connection.actions()),
+        servers,
+    })
+}
+
+fn collect_servers<'a>(
+    path: Vec<String>,
+    server: &'a dyn ServerConnection,
+    reports: &'a mut Vec<ServerReport>,
+) -> LocalBoxFuture<'a, ConnectionResult<()>> {
+    Box::pin(async move {
+        let mut adapters = Vec::new();
+        for (tag, _) in server.supported_adapters() {
+            server.create_adapter(&tag).await?;
+            adapters.push(tag.into_owned());
+        }
+        let sub_servers = server.servers().await?;
+        reports.push(ServerReport {
+            path: path.clone(),
+            metadata: server.metadata(),
+            actions: action_ids(server.actions()),
+            adapters,
+        });
+
+        for (key, sub_server) in sub_servers {
+            let mut sub_path = path.clone();
+            sub_path.push(key.into_owned());
+            collect_servers(sub_path, sub_server.as_ref(), reports).await?;
+        }
+        Ok(())
+    })
+}
+
+fn action_ids(actions: Vec<(Cow<'static, str>, Cow<'static, str>)>) -> Vec<String> {
+    actions.into_iter().map(|(id, _)| id.into_owned()).collect()
+}
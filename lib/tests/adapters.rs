@@ -0,0 +1,257 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Connects the VNC, RDP and SPICE adapters to real servers running in containers and checks
+//! that they authenticate and receive the first frame.
+//!
+//! This needs Docker (or Podman with `docker compose` support) and a display to open windows
+//! on, eg. `xvfb-run` or `GDK_BACKEND=broadway`. Run it with:
+//!
+//! ```sh
+//! cargo test -p libfieldmonitor --features integration-tests --test adapters
+//! ```
+//!
+//! The servers from `build-aux/dev-connection-servers` are started and stopped again
+//! afterwards. If they are already running, set `FM_TEST_SERVERS_RUNNING=1` to use them as they
+//! are. Arguments select which protocols to test, eg. `-- vnc rdp`.
+use std::cell::RefCell;
+use std::env;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+use std::rc::Rc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use gtk::glib;
+use gtk::prelude::*;
+use rdw::DisplayExt;
+use secure_string::SecureString;
+
+use libfieldmonitor::adapter::rdp::RdpAdapter;
+use libfieldmonitor::adapter::spice::SpiceAdapter;
+use libfieldmonitor::adapter::types::{Adapter, AdapterDisplayWidget};
+use libfieldmonitor::adapter::vnc::VncAdapter;
+
+/// Time a server may take to accept connections after its container was started.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(120);
+/// Time an adapter may take from connecting to receiving the first frame.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A server of the compose project, with the credentials set up in its image.
+struct TestServer {
+    name: &'static str,
+    port: u16,
+    adapter: fn() -> Box<dyn Adapter>,
+}
+
+const SERVERS: &[TestServer] = &[
+    TestServer {
+        name: "vnc",
+        port: 15901,
+        adapter: || {
+            Box::new(VncAdapter::new(
+                "localhost".into(),
+                15901,
+                String::new(),
+                SecureString::from("password123"),
+            ))
+        },
+    },
+    TestServer {
+        name: "rdp",
+        port: 15902,
+        adapter: || {
+            Box::new(RdpAdapter::new(
+                "localhost".into(),
+                15902,
+                "ubuntu".into(),
+                SecureString::from("ubuntu"),
+            ))
+        },
+    },
+    TestServer {
+        name: "spice",
+        port: 15900,
+        adapter: || {
+            Box::new(SpiceAdapter::new(
+                "localhost".into(),
+                15900,
+                String::new(),
+                SecureString::from("123456"),
+            ))
+        },
+    },
+];
+
+#[derive(Debug)]
+enum Outcome {
+    /// The display received its first frame.
+    FirstFrame,
+    Disconnected(String),
+    TimedOut {
+        connected: bool,
+    },
+}
+
+fn main() -> ExitCode {
+    let filters = env::args().skip(1).collect::<Vec<_>>();
+    let servers = SERVERS
+        .iter()
+        .filter(|server| filters.is_empty() || filters.iter().any(|f| f == server.name))
+        .collect::<Vec<_>>();
+    if servers.is_empty() {
+        eprintln!("no servers match {filters:?}");
+        return ExitCode::FAILURE;
+    }
+
+    let compose = ComposeProject::start(&servers);
+    gtk::init().expect("failed to initialize GTK, is a display available?");
+
+    let mut failed = 0;
+    for server in &servers {
+        let outcome = match wait_for_port(server.port) {
+            true => connect((server.adapter)()),
+            false => Outcome::Disconnected(format!("port {} is not reachable", server.port)),
+        };
+        match outcome {
+            Outcome::FirstFrame => println!("test {} ... ok", server.name),
+            outcome => {
+                println!("test {} ... FAILED: {outcome:?}", server.name);
+                failed += 1;
+            }
+        }
+    }
+    drop(compose);
+
+    println!("\n{} passed; {failed} failed", servers.len() - failed);
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Connects `adapter` in a window and runs the main loop until the display received its first
+/// frame, the connection was closed, or [`CONNECT_TIMEOUT`] passed.
+fn connect(adapter: Box<dyn Adapter>) -> Outcome {
+    let context = glib::MainContext::default();
+    let outcome: Rc<RefCell<Option<Outcome>>> = Rc::default();
+    let connected = Rc::new(RefCell::new(false));
+
+    let display = adapter.create_and_connect_display(
+        Rc::new(glib::clone!(
+            #[strong]
+            connected,
+            move || {
+                connected.replace(true);
+            }
+        )),
+        Rc::new(glib::clone!(
+            #[strong]
+            outcome,
+            move |result| {
+                let message = match result {
+                    Ok(()) => "closed by the server".to_string(),
+                    Err(err) => err.to_string(),
+                };
+                outcome
+                    .borrow_mut()
+                    .get_or_insert(Outcome::Disconnected(message));
+            }
+        )),
+    );
+    let AdapterDisplayWidget::Rdw(rdw_display) = display.widget() else {
+        panic!("adapter did not create an rdw display");
+    };
+    let window = gtk::Window::builder()
+        .default_width(800)
+        .default_height(600)
+        .child(&rdw_display)
+        .build();
+    window.present();
+
+    let started = Instant::now();
+    while outcome.borrow().is_none() {
+        // The size of the display is only known once the server sent the framebuffer.
+        if *connected.borrow() && rdw_display.display_size().is_some() {
+            outcome.replace(Some(Outcome::FirstFrame));
+        } else if started.elapsed() > CONNECT_TIMEOUT {
+            outcome.replace(Some(Outcome::TimedOut {
+                connected: *connected.borrow(),
+            }));
+        } else {
+            context.iteration(false);
+            sleep(Duration::from_millis(10));
+        }
+    }
+
+    display.close();
+    window.destroy();
+    while context.pending() {
+        context.iteration(false);
+    }
+    outcome.take().unwrap()
+}
+
+fn wait_for_port(port: u16) -> bool {
+    let started = Instant::now();
+    while started.elapsed() < STARTUP_TIMEOUT {
+        if TcpStream::connect(("localhost", port)).is_ok() {
+            return true;
+        }
+        sleep(Duration::from_secs(1));
+    }
+    false
+}
+
+/// The compose project of the development servers. Stopped again when dropped, unless it was
+/// already running.
+struct ComposeProject {
+    file: Option<PathBuf>,
+}
+
+impl ComposeProject {
+    fn start(servers: &[&TestServer]) -> Self {
+        if env::var_os("FM_TEST_SERVERS_RUNNING").is_some() {
+            return Self { file: None };
+        }
+        let file = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../build-aux/dev-connection-servers/docker-compose.yml");
+        let status = Command::new("docker")
+            .args(["compose", "-f"])
+            .arg(&file)
+            .args(["up", "--detach", "--build"])
+            .args(servers.iter().map(|server| server.name))
+            .status()
+            .expect("failed to run docker compose");
+        assert!(status.success(), "failed to start the test servers");
+        Self { file: Some(file) }
+    }
+}
+
+impl Drop for ComposeProject {
+    fn drop(&mut self) {
+        if let Some(file) = &self.file {
+            let _ = Command::new("docker")
+                .args(["compose", "-f"])
+                .arg(file)
+                .arg("down")
+                .status();
+        }
+    }
+}
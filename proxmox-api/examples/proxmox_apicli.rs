@@ -25,7 +25,7 @@ use secure_string::SecureString;
 use simple_logger::SimpleLogger;
 use tokio::time::sleep;
 
-use proxmox_api::{NodeId, ProxmoxApiClient, VmId};
+use proxmox_api::{ClientConfig, Credentials, NodeId, ProxmoxApiClient, VmId};
 
 /// Minimal API CLI client for Proxmox
 #[derive(Parser, Debug)]
@@ -198,26 +198,22 @@ async fn main() {
         exit(1);
     }
 
-    let client = match auth {
-        AuthArgs::UsernamePassword { username, password } => {
-            ProxmoxApiClient::connect_with_ticket(
-                &http::Uri::from_str(&args.url).expect("failed to parse URL"),
-                &username,
-                SecureString::from(password),
-                args.ignore_ssl_errors,
-            )
-            .await
-        }
-        AuthArgs::Apikey { apikey, tokenid } => {
-            ProxmoxApiClient::connect_with_apikey(
-                &http::Uri::from_str(&args.url).expect("failed to parse URL"),
-                &tokenid,
-                SecureString::from(apikey),
-                args.ignore_ssl_errors,
-            )
-            .await
-        }
-    }
+    let credentials = match auth {
+        AuthArgs::UsernamePassword { username, password } => Credentials::Ticket {
+            user: username,
+            password: SecureString::from(password),
+        },
+        AuthArgs::Apikey { apikey, tokenid } => Credentials::Apikey {
+            tokenid,
+            apikey: SecureString::from(apikey),
+        },
+    };
+    let client = ClientConfig::new(
+        http::Uri::from_str(&args.url).expect("failed to parse URL"),
+        credentials,
+    )
+    .ignore_ssl_errors(args.ignore_ssl_errors)
+    .connect()
     .expect("failed to create API client");
 
     match process_cmd(&client, &args.command).await {
@@ -0,0 +1,110 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! A client that blocks the calling thread until requests are done, for use outside of async
+//! code. Requires the `blocking` feature.
+use std::future::Future;
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::endpoints::Endpoint;
+use crate::*;
+
+/// Generates methods running the method of the same name of the async client.
+macro_rules! blocking_endpoints {
+    ($($(#[$attr:meta])* fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name(&self $(, $arg: $ty)*) -> Result<$ret> {
+                self.runtime.block_on(self.inner.$name($($arg),*))
+            }
+        )*
+    };
+}
+
+/// Blocking variant of [`crate::ProxmoxApiClient`]. It runs the requests on its own
+/// single-threaded runtime, so it must not be used from within an async runtime.
+pub struct ProxmoxApiClient {
+    inner: crate::ProxmoxApiClient,
+    runtime: Runtime,
+}
+
+impl ProxmoxApiClient {
+    pub fn connect(config: ClientConfig) -> Result<Self> {
+        Ok(Self {
+            inner: crate::ProxmoxApiClient::connect(config)?,
+            runtime: Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(Error::Runtime)?,
+        })
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        self.inner.config()
+    }
+
+    /// Sends a request to a single endpoint, see [`crate::endpoints`].
+    pub fn execute<E: Endpoint>(&self, request: &E) -> Result<E::Output> {
+        self.runtime.block_on(self.inner.execute(request))
+    }
+
+    /// Runs a request of the async client, for endpoints that have no blocking method.
+    pub fn block_on<'a, F: Future>(
+        &'a self,
+        request: impl FnOnce(&'a crate::ProxmoxApiClient) -> F,
+    ) -> F::Output {
+        self.runtime.block_on(request(&self.inner))
+    }
+
+    blocking_endpoints! {
+        fn version(&self) -> Version;
+        fn access_permissions(&self) -> AccessPermissions;
+        fn cluster_status(&self) -> Vec<ClusterStatusEntry>;
+        fn cluster_name(&self) -> Option<String>;
+        fn nodes(&self) -> Vec<Node>;
+        fn node_lxc(&self, node: &NodeId) -> Vec<LxcVm>;
+        fn node_qemu(&self, node: &NodeId, full: bool) -> Vec<QemuVm>;
+        fn node_vms_brief(&self, node: &NodeId, vm_type: VmType) -> Vec<VmListEntry>;
+        fn node_status(&self, node: &NodeId) -> NodeStatusInfo;
+        fn node_services(&self, node: &NodeId) -> Vec<NodeService>;
+        fn node_apt_updates(&self, node: &NodeId) -> Vec<NodeAptUpdate>;
+        fn node_reboot(&self, node: &NodeId) -> ();
+        fn node_shutdown(&self, node: &NodeId) -> ();
+        fn node_startall(&self, node: &NodeId, input: NodeStartallInput) -> String;
+        fn node_stopall(&self, node: &NodeId, input: NodeStopallInput) -> String;
+        fn node_task_status(&self, node: &NodeId, upid: &str) -> TaskStatus;
+        fn vm_qemu_status_current(&self, node: &NodeId, vm: &VmId) -> QemuVmStatus;
+        fn vm_config(&self, node: &NodeId, vm: &VmId, vm_type: Option<VmType>) -> VmConfig;
+        fn vm_start(
+            &self, node: &NodeId, vm: &VmId, vm_type: Option<VmType>, input: VmStartInput
+        ) -> String;
+        fn vm_stop(
+            &self, node: &NodeId, vm: &VmId, vm_type: Option<VmType>, input: VmStopInput
+        ) -> String;
+        fn vm_shutdown(
+            &self, node: &NodeId, vm: &VmId, vm_type: Option<VmType>, input: VmShutdownInput
+        ) -> String;
+        fn vm_reboot(
+            &self, node: &NodeId, vm: &VmId, vm_type: Option<VmType>, input: VmRebootInput
+        ) -> String;
+        fn node_storages(&self, node: &NodeId, content: &str) -> Vec<Storage>;
+        fn storage_content(
+            &self, node: &NodeId, storage: &str, content: &str
+        ) -> Vec<StorageContent>;
+    }
+}
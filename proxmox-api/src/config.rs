@@ -0,0 +1,161 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Configuration of how to connect to the API.
+use std::str::FromStr;
+
+use http::Uri;
+use secure_string::SecureString;
+
+use crate::{Error, Result};
+
+const APIKEY_TAG: &str = "apikey";
+const TICKET_TAG: &str = "ticket";
+
+/// How the client authenticates.
+#[derive(Clone, Debug)]
+pub enum Credentials {
+    /// An API token, eg. `root@pam!monitoring`, and its secret. Sent with every request.
+    Apikey {
+        tokenid: String,
+        apikey: SecureString,
+    },
+    /// A user, eg. `root@pam`, and its password. These are exchanged for tickets, which are
+    /// re-issued when they expire.
+    Ticket {
+        user: String,
+        password: SecureString,
+    },
+}
+
+impl Credentials {
+    fn tag(&self) -> &'static str {
+        match self {
+            Credentials::Apikey { .. } => APIKEY_TAG,
+            Credentials::Ticket { .. } => TICKET_TAG,
+        }
+    }
+
+    pub fn user_or_tokenid(&self) -> &str {
+        match self {
+            Credentials::Apikey { tokenid, .. } => tokenid,
+            Credentials::Ticket { user, .. } => user,
+        }
+    }
+
+    pub fn password_or_apikey(&self) -> &SecureString {
+        match self {
+            Credentials::Apikey { apikey, .. } => apikey,
+            Credentials::Ticket { password, .. } => password,
+        }
+    }
+}
+
+/// Everything needed to create a [`ProxmoxApiClient`](crate::ProxmoxApiClient).
+///
+/// ```no_run
+/// # use proxmox_api::{ClientConfig, Credentials};
+/// # fn example() -> proxmox_api::Result<()> {
+/// let client = ClientConfig::new(
+///     "https://pve.example.com:8006/api2/json".parse().unwrap(),
+///     Credentials::Ticket {
+///         user: "root@pam".into(),
+///         password: "secret".into(),
+///     },
+/// )
+/// .ignore_ssl_errors(true)
+/// .connect()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClientConfig {
+    pub root: Uri,
+    pub credentials: Credentials,
+    pub ignore_ssl_errors: bool,
+    /// HTTP client to send the requests with. If set, it is used as-is, and
+    /// `ignore_ssl_errors` is not applied to it.
+    pub http_client: Option<reqwest::Client>,
+}
+
+impl ClientConfig {
+    pub fn new(root: Uri, credentials: Credentials) -> Self {
+        Self {
+            root,
+            credentials,
+            ignore_ssl_errors: false,
+            http_client: None,
+        }
+    }
+
+    /// Whether invalid or self-signed certificates are accepted. This is the case for the
+    /// default installation of Proxmox VE.
+    pub fn ignore_ssl_errors(mut self, ignore_ssl_errors: bool) -> Self {
+        self.ignore_ssl_errors = ignore_ssl_errors;
+        self
+    }
+
+    /// Uses `http_client` instead of a client created from this configuration, eg. to share
+    /// proxy settings with other parts of an application.
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    pub fn connect(self) -> Result<crate::ProxmoxApiClient> {
+        crate::ProxmoxApiClient::connect(self)
+    }
+
+    /// Hostname of the API root.
+    pub fn hostname(&self) -> &str {
+        self.root.host().unwrap_or_default()
+    }
+
+    /// Encodes the configuration as strings, to pass it on to another process, which restores
+    /// it with [`Self::from_args`]. The HTTP client is not included.
+    pub fn to_args(&self) -> [String; 5] {
+        [
+            self.credentials.tag().to_string(),
+            self.root.to_string(),
+            self.credentials.user_or_tokenid().to_string(),
+            self.credentials.password_or_apikey().unsecure().to_string(),
+            if self.ignore_ssl_errors { "1" } else { "0" }.to_string(),
+        ]
+    }
+
+    /// Decodes a configuration encoded with [`Self::to_args`].
+    pub fn from_args(args: &[impl AsRef<str>]) -> Result<Self> {
+        let [tag, root, user_or_tokenid, password_or_apikey, ignore_ssl_errors] = args else {
+            return Err(Error::InvalidConfig);
+        };
+        let user_or_tokenid = user_or_tokenid.as_ref().to_string();
+        let password_or_apikey = SecureString::from(password_or_apikey.as_ref());
+        let credentials = match tag.as_ref() {
+            APIKEY_TAG => Credentials::Apikey {
+                tokenid: user_or_tokenid,
+                apikey: password_or_apikey,
+            },
+            TICKET_TAG => Credentials::Ticket {
+                user: user_or_tokenid,
+                password: password_or_apikey,
+            },
+            _ => return Err(Error::InvalidConfig),
+        };
+        Ok(Self::new(Uri::from_str(root.as_ref())?, credentials)
+            .ignore_ssl_errors(ignore_ssl_errors.as_ref() == "1"))
+    }
+}
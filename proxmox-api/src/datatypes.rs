@@ -0,0 +1,1255 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::num::NonZeroU32;
+
+use serde::de::{Error, Unexpected};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+pub use crate::datatypes::ids::*;
+pub use crate::datatypes::params::*;
+
+mod ids;
+mod params;
+
+/// Single element of response of GET /nodes
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct Node {
+    /// The cluster node name.
+    pub node: NodeId,
+    /// The cluster node name.
+    pub status: NodeStatus,
+    /// CPU utilization.
+    #[serde(default)]
+    pub cpu: Option<f64>,
+    /// Support level.
+    #[serde(default)]
+    pub level: Option<String>,
+    /// Number of available CPUs.
+    #[serde(default)]
+    pub maxcpu: Option<i64>,
+    /// Number of available memory in bytes.
+    #[serde(default)]
+    pub maxmem: Option<i64>,
+    /// Used memory in bytes.
+    #[serde(default)]
+    pub mem: Option<i64>,
+    /// The SSL fingerprint for the node certificate.
+    #[serde(default)]
+    pub ssl_fingerprint: Option<String>,
+    /// Node uptime in seconds.
+    #[serde(default)]
+    pub uptime: Option<i64>,
+}
+
+/// Response of GET /version
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/version
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct Version {
+    /// The current Proxmox VE point release in `x.y` format.
+    pub release: String,
+    /// The short git revision from which this version was build.
+    pub repoid: String,
+    /// The full pve-manager package version of this node.
+    pub version: String,
+}
+
+/// Response of GET /access/permissions: The privileges of the authenticated user or API token,
+/// by ACL path. The values are the propagate flags of the privileges.
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/access/permissions
+#[derive(PartialEq, Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct AccessPermissions(
+    #[serde(deserialize_with = "deserialize_permissions")]
+    pub  BTreeMap<String, BTreeMap<String, bool>>,
+);
+
+impl AccessPermissions {
+    /// Whether the privileges only allow viewing, eg. for tokens with the `PVEAuditor` role.
+    /// This is the case if all privileges on all paths are `*.Audit` privileges.
+    pub fn is_audit_only(&self) -> bool {
+        self.0
+            .values()
+            .flat_map(BTreeMap::keys)
+            .all(|privilege| privilege.ends_with(".Audit"))
+    }
+}
+
+/// Single element of response of GET /cluster/status
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/cluster/status
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct ClusterStatusEntry {
+    pub id: String,
+    pub name: String,
+    /// Indicates the type, either cluster or node. The type defines the object properties e.g.
+    /// quorate available for type cluster.
+    pub r#type: ClusterStatusEntryType,
+    /// [node] IP of the resolved nodename.
+    #[serde(default)]
+    pub ip: Option<String>,
+    /// [node] Indicates if this is the responding node.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub local: Option<bool>,
+    /// [node] ID of the node from the corosync configuration.
+    #[serde(default)]
+    pub nodeid: Option<i64>,
+    /// [cluster] Nodes count, including offline nodes.
+    #[serde(default)]
+    pub nodes: Option<i64>,
+    /// [node] Indicates if the node is online or offline.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub online: Option<bool>,
+    /// [cluster] Indicates if there is a majority of nodes online to make decisions.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub quorate: Option<bool>,
+    /// [cluster] Current version of the corosync configuration file.
+    #[serde(default)]
+    pub version: Option<i64>,
+}
+
+/// Type of a [`ClusterStatusEntry`].
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone, Copy)]
+pub enum ClusterStatusEntryType {
+    #[serde(rename = "cluster")]
+    Cluster,
+    #[serde(rename = "node")]
+    Node,
+    #[serde(rename = "unknown", other)]
+    Unknown,
+}
+
+/// Status of a node
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone, Copy)]
+pub enum NodeStatus {
+    #[serde(rename = "online")]
+    Online,
+    #[serde(rename = "offline")]
+    Offline,
+    #[serde(rename = "unknown", other)]
+    Unknown,
+}
+
+/// Single element of response of GET /cluster/ha/status/current
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/cluster/ha/status/current
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct HaStatusEntry {
+    pub id: String,
+    pub r#type: HaStatusEntryType,
+    /// Human readable status of the entry.
+    #[serde(default)]
+    pub status: Option<String>,
+    /// [lrm, master, service] The node the entry is about. For services, the node the service
+    /// is currently placed on.
+    #[serde(default)]
+    pub node: Option<NodeId>,
+    /// [service] ID of the HA resource, eg. `vm:100` or `ct:101`.
+    #[serde(default)]
+    pub sid: Option<String>,
+    /// [service] Current state of the service as tracked by the HA manager.
+    #[serde(default)]
+    pub state: Option<HaServiceState>,
+    /// [service] State requested for the service.
+    #[serde(default)]
+    pub request_state: Option<HaRequestState>,
+    /// [service] Internal state of the service in the cluster resource manager.
+    #[serde(default)]
+    pub crm_state: Option<String>,
+    /// [service] Maximal number of tries to restart the service on a node after its start
+    /// failed.
+    #[serde(default)]
+    pub max_restart: Option<i64>,
+    /// [service] Maximal number of service relocate tries when a service failed to start.
+    #[serde(default)]
+    pub max_relocate: Option<i64>,
+    /// [quorum] Whether the cluster is quorate.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub quorate: Option<bool>,
+    /// [lrm, master] Time of the last status update, as a UNIX timestamp.
+    #[serde(default)]
+    pub timestamp: Option<i64>,
+}
+
+impl HaStatusEntry {
+    /// The guest this entry is about, if it is about an HA managed guest.
+    pub fn vm_id(&self) -> Option<VmId> {
+        let (kind, id) = self.sid.as_deref()?.split_once(':')?;
+        if kind != "vm" && kind != "ct" {
+            return None;
+        }
+        Some(VmId::from(id.parse::<u64>().ok()?))
+    }
+}
+
+/// Type of a [`HaStatusEntry`].
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone, Copy)]
+pub enum HaStatusEntryType {
+    #[serde(rename = "quorum")]
+    Quorum,
+    #[serde(rename = "master")]
+    Master,
+    #[serde(rename = "lrm")]
+    Lrm,
+    #[serde(rename = "service")]
+    Service,
+    #[serde(rename = "fencing")]
+    Fencing,
+    #[serde(rename = "unknown", other)]
+    Unknown,
+}
+
+/// State of an HA managed service.
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone, Copy)]
+pub enum HaServiceState {
+    #[serde(rename = "started")]
+    Started,
+    #[serde(rename = "stopped")]
+    Stopped,
+    #[serde(rename = "request_start")]
+    RequestStart,
+    #[serde(rename = "request_stop")]
+    RequestStop,
+    #[serde(rename = "migrate")]
+    Migrate,
+    #[serde(rename = "relocate")]
+    Relocate,
+    #[serde(rename = "fence")]
+    Fence,
+    #[serde(rename = "recovery")]
+    Recovery,
+    #[serde(rename = "freeze")]
+    Freeze,
+    #[serde(rename = "disabled")]
+    Disabled,
+    #[serde(rename = "error")]
+    Error,
+    #[serde(rename = "unknown", other)]
+    Unknown,
+}
+
+/// State requested for an HA resource.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum HaRequestState {
+    #[serde(rename = "started")]
+    Started,
+    #[serde(rename = "stopped")]
+    Stopped,
+    #[serde(rename = "disabled")]
+    Disabled,
+    #[serde(rename = "ignored")]
+    Ignored,
+}
+
+impl HaRequestState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HaRequestState::Started => "started",
+            HaRequestState::Stopped => "stopped",
+            HaRequestState::Disabled => "disabled",
+            HaRequestState::Ignored => "ignored",
+        }
+    }
+}
+
+/// Single element of response of GET /node/{node}/lxc
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/lxc
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct LxcVm {
+    /// LXC Container status.
+    pub status: VmStatus,
+    /// The (unique) ID of the VM.
+    pub vmid: VmId,
+    /// Current CPU usage.
+    #[serde(default)]
+    pub cpu: Option<f64>,
+    /// Maximum usable CPUs.
+    #[serde(default)]
+    pub cpus: Option<f64>,
+    /// Root disk usage in bytes.
+    #[serde(default)]
+    pub disk: Option<i64>,
+    /// The amount of bytes the guest read from its block devices since it was started.
+    #[serde(default)]
+    pub diskread: Option<i64>,
+    /// The amount of bytes the guest wrote to its block devices since it was started.
+    #[serde(default)]
+    pub diskwrite: Option<i64>,
+    /// The current config lock, if any.
+    #[serde(default)]
+    pub lock: Option<VmLock>,
+    /// Root disk size in bytes.
+    #[serde(default)]
+    pub maxdisk: Option<i64>,
+    /// Maximum memory in bytes.
+    #[serde(default)]
+    pub maxmem: Option<i64>,
+    /// Maximum SWAP memory in bytes.
+    #[serde(default)]
+    pub maxswap: Option<i64>,
+    /// Currently used memory in bytes.
+    #[serde(default)]
+    pub mem: Option<i64>,
+    /// Container name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The amount of traffic in bytes that was received by the guest over the network since
+    /// it was started.
+    #[serde(default)]
+    pub netin: Option<i64>,
+    /// The amount of traffic in bytes that was sent by the guest over the network since it
+    /// was started.
+    #[serde(default)]
+    pub netout: Option<i64>,
+    /// Currently used SWAP memory in bytes.
+    #[serde(default)]
+    pub swap: Option<i64>,
+    /// The current configured tags.
+    #[serde(default, deserialize_with = "deserialize_tags")]
+    pub tags: Vec<String>,
+    /// Whether the container is a template.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub template: Option<bool>,
+    /// Uptime.
+    #[serde(default)]
+    pub uptime: Option<i64>,
+}
+
+/// Single element of response of GET /node/{node}/qemu
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/qemu
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct QemuVm {
+    /// QEMU process status.
+    pub status: VmStatus,
+    /// The (unique) ID of the VM.
+    pub vmid: VmId,
+    /// Current CPU usage.
+    #[serde(default)]
+    pub cpu: Option<f64>,
+    /// Maximum usable CPUs.
+    #[serde(default)]
+    pub cpus: Option<f64>,
+    /// The amount of bytes the guest read from its block devices since it was started.
+    #[serde(default)]
+    pub diskread: Option<i64>,
+    /// The amount of bytes the guest wrote to its block devices since it was started.
+    #[serde(default)]
+    pub diskwrite: Option<i64>,
+    /// The current config lock, if any.
+    #[serde(default)]
+    pub lock: Option<VmLock>,
+    /// Root disk size in bytes.
+    #[serde(default)]
+    pub maxdisk: Option<i64>,
+    /// Maximum memory in bytes.
+    #[serde(default)]
+    pub maxmem: Option<i64>,
+    /// Currently used memory in bytes.
+    #[serde(default)]
+    pub mem: Option<i64>,
+    /// VM name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The amount of traffic in bytes that was received by the guest over the network since
+    /// it was started.
+    #[serde(default)]
+    pub netin: Option<i64>,
+    /// The amount of traffic in bytes that was sent by the guest over the network since it
+    /// was started.
+    #[serde(default)]
+    pub netout: Option<i64>,
+    /// PID of running qemu process.
+    #[serde(default)]
+    pub pid: Option<i64>,
+    /// VM run state from the 'query-status' QMP monitor command. Only set if the full status
+    /// was requested.
+    #[serde(default)]
+    pub qmpstatus: Option<QmpStatus>,
+    /// The currently running machine type (if running).
+    #[serde(default, rename = "running-machine")]
+    pub running_machine: Option<String>,
+    /// The currently running QEMU version (if running).
+    #[serde(default, rename = "running-qemu")]
+    pub running_qemu: Option<String>,
+    /// The current configured tags.
+    #[serde(default, deserialize_with = "deserialize_tags")]
+    pub tags: Vec<String>,
+    /// Whether the VM is a template.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub template: Option<bool>,
+    /// Uptime.
+    #[serde(default)]
+    pub uptime: Option<i64>,
+}
+
+/// Reduced single element of the responses of GET /node/{node}/lxc and GET /node/{node}/qemu.
+///
+/// Only contains the fields needed to list guests, all other fields of the response are
+/// discarded during deserialization. Use this for nodes with a very large number of guests.
+///
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/lxc
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/qemu
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct VmListEntry {
+    /// Guest status.
+    pub status: VmStatus,
+    /// The (unique) ID of the VM.
+    pub vmid: VmId,
+    /// Guest name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Uptime in seconds.
+    #[serde(default)]
+    pub uptime: Option<i64>,
+    /// The current config lock, if any. [`VmLock::Suspended`] if the guest is suspended to disk.
+    #[serde(default)]
+    pub lock: Option<VmLock>,
+    /// Whether the guest is a template. Templates can not be started, only cloned.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub template: Option<bool>,
+}
+
+/// Response of GET /node/{node}/qemu/{vmid}/config and GET /node/{node}/lxc/{vmid}/config.
+///
+/// Only contains the fields needed by Field Monitor, all other fields of the configuration are
+/// discarded during deserialization.
+///
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/qemu/{vmid}/config
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/lxc/{vmid}/config
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct VmConfig {
+    /// Description of the guest, shown as notes in the web interface.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// QEMU only: Name of the VM.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// LXC only: Host name of the container.
+    #[serde(default)]
+    pub hostname: Option<String>,
+    /// Number of CPU cores.
+    #[serde(default)]
+    pub cores: Option<i64>,
+    /// Whether the guest is started when the node boots.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub onboot: Option<bool>,
+    /// Startup and shutdown behavior, eg. `order=1,up=30`.
+    #[serde(default)]
+    pub startup: Option<String>,
+    /// The current config lock, if any.
+    #[serde(default)]
+    pub lock: Option<VmLock>,
+    /// Whether the guest is a template.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub template: Option<bool>,
+    /// The configured tags.
+    #[serde(default, deserialize_with = "deserialize_tags")]
+    pub tags: Vec<String>,
+    /// LXC only: Distribution of the container, eg. `debian` or `alpine`. QEMU: The guest
+    /// operating system, eg. `l26` or `win11`.
+    #[serde(default)]
+    pub ostype: Option<String>,
+    /// QEMU only: Boot order, eg. `order=scsi0;ide2;net0`. Older VMs may still use the legacy
+    /// format (eg. `cdn`).
+    #[serde(default)]
+    pub boot: Option<String>,
+    /// QEMU only: Drives and network devices, eg. `ide2` or `net0`.
+    #[serde(flatten, deserialize_with = "deserialize_devices")]
+    pub devices: BTreeMap<String, String>,
+}
+
+impl VmConfig {
+    /// QEMU only: The CD/DVD drives and the volume inserted into each, if any.
+    pub fn cdrom_drives(&self) -> Vec<(String, Option<String>)> {
+        self.devices
+            .iter()
+            .filter(|(name, value)| is_drive(name) && is_cdrom(value))
+            .map(|(name, value)| {
+                let volume = value.split(',').next().unwrap_or_default();
+                (
+                    name.clone(),
+                    (!volume.is_empty() && volume != "none").then(|| volume.to_string()),
+                )
+            })
+            .collect()
+    }
+
+    /// QEMU only: The devices the VM boots from, in order. If the VM uses the legacy boot
+    /// format, all drives and network devices are returned, ordered like Proxmox does.
+    pub fn boot_order(&self) -> Vec<String> {
+        if let Some(order) = self
+            .boot
+            .as_deref()
+            .and_then(|boot| boot.split(',').find_map(|opt| opt.strip_prefix("order=")))
+        {
+            return order
+                .split(';')
+                .filter(|device| !device.is_empty())
+                .map(ToString::to_string)
+                .collect();
+        }
+        let mut order = Vec::new();
+        for kind in self.boot.as_deref().unwrap_or("cdn").chars() {
+            order.extend(
+                self.devices
+                    .iter()
+                    .filter(|(name, value)| match kind {
+                        'c' => is_drive(name) && !is_cdrom(value),
+                        'd' => is_drive(name) && is_cdrom(value),
+                        'n' => is_net(name),
+                        _ => false,
+                    })
+                    .map(|(name, _)| name.clone()),
+            );
+        }
+        order
+    }
+
+    /// QEMU only: The boot order, changed so that the VM boots from devices of `kind` first.
+    /// Devices of `kind` that are not in the boot order yet are added.
+    pub fn boot_order_preferring(&self, kind: VmDeviceKind) -> Vec<String> {
+        let current = self.boot_order();
+        let (mut order, rest): (Vec<_>, Vec<_>) = current
+            .iter()
+            .cloned()
+            .partition(|device| self.device_kind(device) == Some(kind));
+        order.extend(
+            self.devices
+                .keys()
+                .filter(|device| {
+                    self.device_kind(device) == Some(kind) && !current.contains(device)
+                })
+                .cloned(),
+        );
+        order.extend(rest);
+        order
+    }
+
+    /// QEMU only: The kind of a drive or network device of the VM.
+    pub fn device_kind(&self, device: &str) -> Option<VmDeviceKind> {
+        let value = self.devices.get(device)?;
+        if is_net(device) {
+            Some(VmDeviceKind::Network)
+        } else if is_drive(device) && is_cdrom(value) {
+            Some(VmDeviceKind::Cdrom)
+        } else if is_drive(device) {
+            Some(VmDeviceKind::Disk)
+        } else {
+            None
+        }
+    }
+}
+
+/// Kind of a bootable device of a QEMU VM.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum VmDeviceKind {
+    Disk,
+    Cdrom,
+    Network,
+}
+
+/// Single element of response of GET /nodes/{node}/storage
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/storage
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct Storage {
+    /// The storage identifier.
+    pub storage: String,
+    /// Storage type, eg. `dir`, `lvmthin` or `zfspool`.
+    pub r#type: String,
+    /// Allowed storage content types, eg. `images` or `iso`.
+    #[serde(default, deserialize_with = "deserialize_list")]
+    pub content: Vec<String>,
+    /// Set when storage is accessible.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub active: Option<bool>,
+    /// Set when storage is enabled (not disabled).
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub enabled: Option<bool>,
+    /// Shared flag from storage configuration.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub shared: Option<bool>,
+    /// Total storage space in bytes.
+    #[serde(default)]
+    pub total: Option<i64>,
+    /// Used storage space in bytes.
+    #[serde(default)]
+    pub used: Option<i64>,
+    /// Available storage space in bytes.
+    #[serde(default)]
+    pub avail: Option<i64>,
+    /// Used fraction (used/total).
+    #[serde(default)]
+    pub used_fraction: Option<f64>,
+}
+
+/// Single element of response of GET /nodes/{node}/storage/{storage}/content
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/storage/{storage}/content
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct StorageContent {
+    /// Volume identifier, eg. `local:iso/debian.iso`.
+    pub volid: String,
+    /// Content type of the volume, eg. `iso` or `images`.
+    pub content: String,
+    /// Format identifier, eg. `raw`, `qcow2` or `iso`.
+    pub format: String,
+    /// Volume size in bytes.
+    #[serde(default)]
+    pub size: Option<i64>,
+    /// Used space in bytes. Only set for some formats.
+    #[serde(default)]
+    pub used: Option<i64>,
+    /// Creation time as UNIX timestamp.
+    #[serde(default)]
+    pub ctime: Option<i64>,
+    /// The guest the volume belongs to, if any.
+    #[serde(default)]
+    pub vmid: Option<VmId>,
+    /// Optional notes. Only set for backups.
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Whether the volume is protected. Only set for backups.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub protected: Option<bool>,
+}
+
+/// Response of GET /nodes/{node}/status
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/status
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct NodeStatusInfo {
+    /// CPU utilization.
+    #[serde(default)]
+    pub cpu: Option<f64>,
+    #[serde(default)]
+    pub cpuinfo: Option<NodeCpuInfo>,
+    /// Load averages of the last 1, 5 and 15 minutes.
+    #[serde(default)]
+    pub loadavg: Vec<String>,
+    #[serde(default)]
+    pub memory: Option<NodeUsage>,
+    #[serde(default)]
+    pub swap: Option<NodeUsage>,
+    /// Usage of the root file system.
+    #[serde(default)]
+    pub rootfs: Option<NodeUsage>,
+    /// Node uptime in seconds.
+    #[serde(default)]
+    pub uptime: Option<i64>,
+    /// Version of the running kernel.
+    #[serde(default)]
+    pub kversion: Option<String>,
+    /// Version of the pve-manager package.
+    #[serde(default)]
+    pub pveversion: Option<String>,
+}
+
+/// CPU details of [`NodeStatusInfo`].
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct NodeCpuInfo {
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Number of logical CPUs.
+    #[serde(default)]
+    pub cpus: Option<i64>,
+    /// Number of physical cores per socket.
+    #[serde(default)]
+    pub cores: Option<i64>,
+    #[serde(default)]
+    pub sockets: Option<i64>,
+    /// Clock speed in MHz.
+    #[serde(default)]
+    pub mhz: Option<String>,
+}
+
+/// Memory or disk usage of [`NodeStatusInfo`], in bytes.
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct NodeUsage {
+    pub total: i64,
+    pub used: i64,
+    #[serde(default)]
+    pub free: Option<i64>,
+}
+
+/// Single element of response of GET /nodes/{node}/services
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/services
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct NodeService {
+    /// Service ID.
+    pub service: String,
+    /// Short description of the service.
+    #[serde(default)]
+    pub desc: Option<String>,
+    #[serde(default)]
+    pub state: Option<NodeServiceState>,
+    /// Whether the service is enabled, disabled or not installed.
+    #[serde(default, rename = "unit-state")]
+    pub unit_state: Option<NodeServiceUnitState>,
+}
+
+/// Current state of a [`NodeService`].
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone, Copy)]
+pub enum NodeServiceState {
+    #[serde(rename = "running")]
+    Running,
+    #[serde(rename = "stopped")]
+    Stopped,
+    #[serde(rename = "unknown", other)]
+    Unknown,
+}
+
+/// Unit file state of a [`NodeService`].
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone, Copy)]
+pub enum NodeServiceUnitState {
+    #[serde(rename = "enabled")]
+    Enabled,
+    #[serde(rename = "disabled")]
+    Disabled,
+    #[serde(rename = "static")]
+    Static,
+    #[serde(rename = "masked")]
+    Masked,
+    /// The service is not installed.
+    #[serde(rename = "not-found")]
+    NotFound,
+    #[serde(rename = "unknown", other)]
+    Unknown,
+}
+
+/// Single element of response of GET /nodes/{node}/apt/update
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/apt/update
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct NodeAptUpdate {
+    #[serde(rename = "Package")]
+    pub package: String,
+    /// The version that is available.
+    #[serde(default, rename = "Version")]
+    pub version: Option<String>,
+    /// The version that is installed.
+    #[serde(default, rename = "OldVersion")]
+    pub old_version: Option<String>,
+}
+
+/// Response of GET /nodes/{node}/tasks/{upid}/status
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/tasks/{upid}/status
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct TaskStatus {
+    pub status: TaskRunState,
+    /// `OK` if the task succeeded, otherwise the error or a warning summary. Only set once the
+    /// task stopped.
+    #[serde(default)]
+    pub exitstatus: Option<String>,
+}
+
+impl TaskStatus {
+    /// Whether the task stopped and succeeded. Tasks that finished with warnings, eg. because
+    /// a single guest of a bulk action failed, did not succeed.
+    pub fn is_ok(&self) -> bool {
+        self.status == TaskRunState::Stopped && self.exitstatus.as_deref() == Some("OK")
+    }
+}
+
+/// State of a task.
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone, Copy)]
+pub enum TaskRunState {
+    #[serde(rename = "running")]
+    Running,
+    #[serde(rename = "stopped")]
+    Stopped,
+}
+
+/// Single element of response of GET /node/{node}/qemu/{vmid}/status/current
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/qemu/{vmid}/status/current
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct QemuVmStatus {
+    /// HA manager service status.
+    #[serde(default)]
+    pub ha: VmHaStatus,
+    /// QEMU process status.
+    pub status: VmStatus,
+    /// The (unique) ID of the VM.
+    pub vmid: VmId,
+    /// QEMU Guest Agent is enabled in config.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub agent: Option<bool>,
+    /// Enable a specific clipboard. If not set, depending on the display type the SPICE one will be added.
+    #[serde(default)]
+    pub clipboard: Option<VmClipboard>,
+    /// Current CPU usage.
+    #[serde(default)]
+    pub cpu: Option<f64>,
+    /// Maximum usable CPUs.
+    #[serde(default)]
+    pub cpus: Option<f64>,
+    /// The amount of bytes the guest read from its block devices since it was started.
+    #[serde(default)]
+    pub diskread: Option<i64>,
+    /// The amount of bytes the guest wrote to its block devices since it was started.
+    #[serde(default)]
+    pub diskwrite: Option<i64>,
+    /// The current config lock, if any.
+    #[serde(default)]
+    pub lock: Option<VmLock>,
+    /// Root disk size in bytes.
+    #[serde(default)]
+    pub maxdisk: Option<i64>,
+    /// Maximum memory in bytes.
+    #[serde(default)]
+    pub maxmem: Option<i64>,
+    /// Currently used memory in bytes.
+    #[serde(default)]
+    pub mem: Option<i64>,
+    /// VM name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The amount of traffic in bytes that was received by the guest over the network since
+    /// it was started.
+    #[serde(default)]
+    pub netin: Option<i64>,
+    /// The amount of traffic in bytes that was sent by the guest over the network since it
+    /// was started.
+    #[serde(default)]
+    pub netout: Option<i64>,
+    /// PID of running qemu process.
+    #[serde(default)]
+    pub pid: Option<i64>,
+    /// VM run state from the 'query-status' QMP monitor command.
+    #[serde(default)]
+    pub qmpstatus: Option<QmpStatus>,
+    /// The currently running machine type (if running).
+    #[serde(default, rename = "running-machine")]
+    pub running_machine: Option<String>,
+    /// The currently running QEMU version (if running).
+    #[serde(default, rename = "running-qemu")]
+    pub running_qemu: Option<String>,
+    /// QEMU VGA configuration supports spice.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub spice: Option<bool>,
+    /// The current configured tags.
+    #[serde(default, deserialize_with = "deserialize_tags")]
+    pub tags: Vec<String>,
+    /// Whether the VM is a template.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub template: Option<bool>,
+    /// Uptime.
+    #[serde(default)]
+    pub uptime: Option<i64>,
+}
+
+/// HA manager status of a guest, part of [`QemuVmStatus`].
+#[derive(PartialEq, Deserialize, Debug, Clone, Default)]
+pub struct VmHaStatus {
+    /// Whether the guest is managed by the HA manager.
+    #[serde(default, deserialize_with = "deserialize_int_bool")]
+    pub managed: bool,
+    /// Current state of the HA resource, if the guest is managed.
+    #[serde(default)]
+    pub state: Option<HaServiceState>,
+    /// HA group of the resource, if any.
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+/// Clipboard of a QEMU VM.
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone, Copy)]
+pub enum VmClipboard {
+    #[serde(rename = "vnc")]
+    Vnc,
+    #[serde(rename = "unknown", other)]
+    Unknown,
+}
+
+/// Run state of a QEMU VM, as reported by the QMP `query-status` command.
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone, Copy)]
+pub enum QmpStatus {
+    #[serde(rename = "running")]
+    Running,
+    #[serde(rename = "paused")]
+    Paused,
+    /// Suspended to RAM.
+    #[serde(rename = "suspended")]
+    Suspended,
+    #[serde(rename = "prelaunch")]
+    Prelaunch,
+    #[serde(rename = "inmigrate")]
+    Inmigrate,
+    #[serde(rename = "postmigrate")]
+    Postmigrate,
+    #[serde(rename = "shutdown")]
+    Shutdown,
+    #[serde(rename = "io-error")]
+    IoError,
+    #[serde(rename = "internal-error")]
+    InternalError,
+    #[serde(rename = "guest-panicked")]
+    GuestPanicked,
+    #[serde(rename = "unknown", other)]
+    Unknown,
+}
+
+/// Lock of the configuration of a guest. Most guest actions fail while the guest is locked.
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone, Copy)]
+pub enum VmLock {
+    #[serde(rename = "backup")]
+    Backup,
+    #[serde(rename = "clone")]
+    Clone,
+    #[serde(rename = "create")]
+    Create,
+    #[serde(rename = "migrate")]
+    Migrate,
+    #[serde(rename = "rollback")]
+    Rollback,
+    #[serde(rename = "snapshot")]
+    Snapshot,
+    #[serde(rename = "snapshot-delete")]
+    SnapshotDelete,
+    /// Currently being suspended to disk.
+    #[serde(rename = "suspending")]
+    Suspending,
+    /// Suspended to disk. Starting the guest resumes it.
+    #[serde(rename = "suspended")]
+    Suspended,
+    /// LXC only.
+    #[serde(rename = "copy")]
+    Copy,
+    /// LXC only.
+    #[serde(rename = "destroyed")]
+    Destroyed,
+    /// LXC only.
+    #[serde(rename = "disk")]
+    Disk,
+    /// LXC only.
+    #[serde(rename = "fstrim")]
+    Fstrim,
+    /// LXC only.
+    #[serde(rename = "mounted")]
+    Mounted,
+    #[serde(rename = "unknown", other)]
+    Unknown,
+}
+
+/// Status of a VM
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone, Copy)]
+pub enum VmStatus {
+    #[serde(rename = "stopped")]
+    Stopped,
+    #[serde(rename = "running")]
+    Running,
+    #[serde(rename = "unknown", other)]
+    Unknown,
+}
+
+/// Return value of termproxy API endpoints:
+///
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/termproxy
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/lxc/{vmid}/termproxy
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/qemu/{vmid}/termproxy
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone)]
+pub struct Termproxy {
+    #[serde(deserialize_with = "try_deserialize_port_from_str")]
+    pub port: NonZeroU32,
+    pub ticket: String,
+    pub upid: String,
+    pub user: String,
+}
+
+/// Return value of spiceproxy/spiceshell API endpoints:
+///
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/spiceshell
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/lxc/{vmid}/spiceproxy
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/qemu/{vmid}/spiceproxy
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct Spiceproxy {
+    pub host: String,
+    pub password: String,
+    pub proxy: String,
+    #[serde(
+        rename = "tls-port",
+        deserialize_with = "try_deserialize_port_from_str"
+    )]
+    pub tls_port: NonZeroU32,
+    pub r#type: String,
+    #[serde(default)]
+    pub ca: Option<String>,
+    #[serde(rename = "host-subject", default)]
+    pub host_subject: Option<String>,
+}
+
+/// Return value of vncproxy/vncshell API endpoints:
+///
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/vncshell
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/lxc/{vmid}/vncproxy
+/// - https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/qemu/{vmid}/vncproxy
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct Vncproxy {
+    pub cert: String,
+    /// The generated password, if one was requested. It must be used instead of the ticket
+    /// to authenticate with the VNC server.
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(deserialize_with = "try_deserialize_port_from_str")]
+    pub port: NonZeroU32,
+    pub ticket: String,
+    pub upid: String,
+    pub user: String,
+}
+
+/// Results of QEMU guest agent commands are wrapped in an object.
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone)]
+pub struct AgentResult<T> {
+    pub result: T,
+}
+
+/// Single element of response of GET /nodes/{node}/qemu/{vmid}/agent/network-get-interfaces
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/qemu/{vmid}/agent/network-get-interfaces
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct AgentNetworkInterface {
+    pub name: String,
+    #[serde(rename = "hardware-address", default)]
+    pub hardware_address: Option<String>,
+    #[serde(rename = "ip-addresses", default)]
+    pub ip_addresses: Vec<AgentIpAddress>,
+}
+
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct AgentIpAddress {
+    #[serde(rename = "ip-address")]
+    pub ip_address: String,
+    #[serde(rename = "ip-address-type")]
+    pub ip_address_type: AgentIpAddressType,
+    #[serde(default)]
+    pub prefix: Option<u32>,
+}
+
+/// Family of an [`AgentIpAddress`].
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone, Copy)]
+pub enum AgentIpAddressType {
+    #[serde(rename = "ipv4")]
+    Ipv4,
+    #[serde(rename = "ipv6")]
+    Ipv6,
+}
+
+/// Return value of POST /nodes/{node}/qemu/{vmid}/agent/exec
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone)]
+pub struct AgentExec {
+    pub pid: i64,
+}
+
+/// Return value of GET /nodes/{node}/qemu/{vmid}/agent/exec-status
+///
+/// https://pve.proxmox.com/pve-docs/api-viewer/index.html#/nodes/{node}/qemu/{vmid}/agent/exec-status
+#[derive(PartialEq, Deserialize, Debug, Clone)]
+pub struct AgentExecStatus {
+    /// Whether the process has exited. The other fields are only set once it has.
+    #[serde(default, deserialize_with = "deserialize_opt_int_bool")]
+    pub exited: Option<bool>,
+    #[serde(default)]
+    pub exitcode: Option<i64>,
+    /// Signal the process was killed with, if any.
+    #[serde(default)]
+    pub signal: Option<i64>,
+    #[serde(rename = "out-data", default)]
+    pub out_data: Option<String>,
+    #[serde(rename = "err-data", default)]
+    pub err_data: Option<String>,
+    #[serde(
+        rename = "out-truncated",
+        default,
+        deserialize_with = "deserialize_opt_int_bool"
+    )]
+    pub out_truncated: Option<bool>,
+    #[serde(
+        rename = "err-truncated",
+        default,
+        deserialize_with = "deserialize_opt_int_bool"
+    )]
+    pub err_truncated: Option<bool>,
+}
+
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone)]
+pub(crate) struct Ticket {
+    pub ticket: String,
+    #[serde(rename = "CSRFPreventionToken")]
+    pub csrf_prevention_token: String,
+}
+
+#[derive(Eq, PartialEq, Deserialize, Debug, Clone)]
+pub(crate) struct Wrapper<T> {
+    pub data: Option<T>,
+    pub reason: Option<String>,
+}
+
+/// Type of a VM.
+#[derive(Clone, Copy, Debug)]
+pub enum VmType {
+    Lxc,
+    Qemu,
+}
+
+impl Display for VmType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VmType::Lxc => "lxc".fmt(f),
+            VmType::Qemu => "qemu".fmt(f),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VmConsoleProxyType {
+    Term,
+    Spice,
+    Vnc,
+}
+
+fn is_drive(name: &str) -> bool {
+    ["ide", "sata", "scsi", "virtio"].iter().any(|bus| {
+        name.strip_prefix(bus)
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+    })
+}
+
+fn is_net(name: &str) -> bool {
+    name.strip_prefix("net")
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn is_cdrom(value: &str) -> bool {
+    value.split(',').any(|opt| opt == "media=cdrom")
+}
+
+/// Keeps only the string entries of the config that are drives or network devices.
+fn deserialize_devices<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let entries: BTreeMap<String, Value> = de::Deserialize::deserialize(deserializer)?;
+    Ok(entries
+        .into_iter()
+        .filter(|(name, _)| is_drive(name) || is_net(name))
+        .filter_map(|(name, value)| match value {
+            Value::String(value) => Some((name, value)),
+            _ => None,
+        })
+        .collect())
+}
+
+fn deserialize_permissions<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, BTreeMap<String, bool>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let paths: BTreeMap<String, BTreeMap<String, u8>> = de::Deserialize::deserialize(deserializer)?;
+    Ok(paths
+        .into_iter()
+        .map(|(path, privileges)| {
+            let privileges = privileges
+                .into_iter()
+                .map(|(privilege, propagate)| (privilege, propagate > 0))
+                .collect();
+            (path, privileges)
+        })
+        .collect())
+}
+
+/// Tags are separated by semicolons, older versions also allow commas and spaces.
+fn deserialize_tags<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: Option<String> = de::Deserialize::deserialize(deserializer)?;
+    Ok(s.iter()
+        .flat_map(|s| s.split([';', ',', ' ']))
+        .filter(|tag| !tag.is_empty())
+        .map(ToString::to_string)
+        .collect())
+}
+
+fn deserialize_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: Option<String> = de::Deserialize::deserialize(deserializer)?;
+    Ok(s.iter()
+        .flat_map(|s| s.split(','))
+        .filter(|item| !item.is_empty())
+        .map(ToString::to_string)
+        .collect())
+}
+
+fn deserialize_int_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let s: u8 = de::Deserialize::deserialize(deserializer)?;
+
+    Ok(s > 0)
+}
+
+fn deserialize_opt_int_bool<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let s: Option<u8> = de::Deserialize::deserialize(deserializer)?;
+
+    Ok(s.map(|v| v > 0))
+}
+
+fn try_deserialize_port_from_str<'de, D>(deserializer: D) -> Result<NonZeroU32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum MaybeNonZeroU32<'a> {
+        String(&'a str),
+        NonZeroU32(NonZeroU32),
+    }
+
+    match MaybeNonZeroU32::deserialize(deserializer)? {
+        MaybeNonZeroU32::String(string) => {
+            if let Ok(num) = string.parse::<u32>() {
+                if let Some(non_zero) = NonZeroU32::new(num) {
+                    return Ok(non_zero);
+                }
+            }
+            Err(Error::invalid_type(
+                Unexpected::Str(string),
+                &"a non-zero integer",
+            ))
+        }
+        MaybeNonZeroU32::NonZeroU32(v) => Ok(v),
+    }
+}
@@ -311,6 +311,43 @@ impl VmStatusInput for VmSuspendInput {
     }
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VmResumeInput {
+    /// QEMU only: Ignore locks - only root is allowed to use this option.
+    pub skiplock: Option<bool>,
+    /// QEMU only: Do not check if the VM is locked for migration.
+    pub nocheck: Option<bool>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VmResumeInputLxc {}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VmResumeInputQemu {
+    /// Ignore locks - only root is allowed to use this option.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skiplock: Option<u8>,
+    /// Do not check if the VM is locked for migration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nocheck: Option<u8>,
+}
+
+impl VmStatusInput for VmResumeInput {
+    type LxcInput = VmResumeInputLxc;
+    type QemuInput = VmResumeInputQemu;
+
+    fn into_lxc(self) -> Self::LxcInput {
+        VmResumeInputLxc {}
+    }
+
+    fn into_qemu(self) -> Self::QemuInput {
+        VmResumeInputQemu {
+            skiplock: self.skiplock.map(|v| if v { 1 } else { 0 }),
+            nocheck: self.nocheck.map(|v| if v { 1 } else { 0 }),
+        }
+    }
+}
+
 #[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
 pub struct NodeTermproxyInput {
     /// Run specific command or default to login (requires 'root@pam')
@@ -374,6 +411,29 @@ impl VmStatusInput for VmTermproxyInput {
     }
 }
 
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct NodeStartallInput {
+    /// Also start guests that do not have the `onboot` option set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force: Option<u8>,
+    /// Only consider the guests with these IDs, separated by commas.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vms: Option<String>,
+}
+
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct NodeStopallInput {
+    /// Force a hard stop of guests that did not shut down after the timeout.
+    #[serde(rename = "force-stop", skip_serializing_if = "Option::is_none")]
+    pub force_stop: Option<u8>,
+    /// Seconds to wait for each guest to shut down.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Only consider the guests with these IDs, separated by commas.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vms: Option<String>,
+}
+
 #[derive(Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct VncwebsocketInput {
     /// Port number returned by previous vncproxy call.
@@ -431,6 +491,8 @@ pub struct NodeVncshellInput {
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct VmVncproxyInput {
+    /// QEMU only: generates a random password to be used as ticket instead of the API ticket.
+    pub generate_password: Option<u8>,
     /// LXC only: sets the height of the console in pixels.
     pub height: Option<u64>,
     /// use websocket instead of standard vnc.
@@ -454,6 +516,9 @@ pub(crate) struct VmVncproxyInputLxc {
 
 #[derive(Serialize, Debug, Clone, PartialEq, Eq)]
 pub(crate) struct VmVncproxyInputQemu {
+    /// Generates a random password to be used as ticket instead of the API ticket.
+    #[serde(rename = "generate-password", skip_serializing_if = "Option::is_none")]
+    pub generate_password: Option<u8>,
     /// use websocket instead of standard vnc.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub websocket: Option<u8>,
@@ -473,6 +538,7 @@ impl VmStatusInput for VmVncproxyInput {
 
     fn into_qemu(self) -> Self::QemuInput {
         VmVncproxyInputQemu {
+            generate_password: self.generate_password,
             websocket: self.websocket,
         }
     }
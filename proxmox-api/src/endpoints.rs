@@ -0,0 +1,1061 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Request types for the API endpoints. Each holds the path and the parameters of one endpoint
+//! and is sent with [`ProxmoxApiClient::execute`]:
+//!
+//! ```ignore
+//! let vms = client
+//!     .execute(&GetNodeQemu::new(node).full(true))
+//!     .await?;
+//! ```
+//!
+//! The methods of [`ProxmoxApiClient`] are shortcuts for the most common requests.
+use http::Method;
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Serializer};
+
+use crate::*;
+
+/// A request to a single endpoint of the API.
+pub trait Endpoint: Serialize {
+    /// The `data` of the response.
+    type Output: DeserializeOwned;
+    /// Parameters of GET requests are sent in the query, all others as a form.
+    const METHOD: Method;
+
+    /// Path of the endpoint, relative to the API root.
+    fn path(&self) -> String;
+}
+
+fn int_bool(value: bool) -> u8 {
+    if value {
+        1
+    } else {
+        0
+    }
+}
+
+/// GET /version
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct GetVersion;
+
+impl Endpoint for GetVersion {
+    type Output = Version;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        "version".into()
+    }
+}
+
+/// GET /access/permissions
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct GetAccessPermissions;
+
+impl Endpoint for GetAccessPermissions {
+    type Output = AccessPermissions;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        "access/permissions".into()
+    }
+}
+
+/// GET /cluster/status
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct GetClusterStatus;
+
+impl Endpoint for GetClusterStatus {
+    type Output = Vec<ClusterStatusEntry>;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        "cluster/status".into()
+    }
+}
+
+/// GET /cluster/nextid
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct GetClusterNextid;
+
+impl Endpoint for GetClusterNextid {
+    type Output = VmId;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        "cluster/nextid".into()
+    }
+}
+
+/// GET /cluster/ha/status/current
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct GetClusterHaStatus;
+
+impl Endpoint for GetClusterHaStatus {
+    type Output = Vec<HaStatusEntry>;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        "cluster/ha/status/current".into()
+    }
+}
+
+/// PUT /cluster/ha/resources/{sid}
+#[derive(Serialize, Debug, Clone)]
+pub struct PutClusterHaResource {
+    #[serde(skip)]
+    sid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<HaRequestState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+impl PutClusterHaResource {
+    /// `sid` is the ID of the HA resource, eg. `vm:100`.
+    pub fn new(sid: impl Into<String>) -> Self {
+        Self {
+            sid: sid.into(),
+            state: None,
+            comment: None,
+        }
+    }
+
+    /// Requests a state. The HA manager then starts or stops the guest on its own.
+    pub fn state(mut self, state: HaRequestState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+}
+
+impl Endpoint for PutClusterHaResource {
+    type Output = ();
+    const METHOD: Method = Method::PUT;
+
+    fn path(&self) -> String {
+        format!("cluster/ha/resources/{}", self.sid)
+    }
+}
+
+/// GET /nodes
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct GetNodes;
+
+impl Endpoint for GetNodes {
+    type Output = Vec<Node>;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        "nodes".into()
+    }
+}
+
+/// GET /nodes/{node}/status
+#[derive(Serialize, Debug, Clone)]
+pub struct GetNodeStatus {
+    #[serde(skip)]
+    node: NodeId,
+}
+
+impl GetNodeStatus {
+    pub fn new(node: NodeId) -> Self {
+        Self { node }
+    }
+}
+
+impl Endpoint for GetNodeStatus {
+    type Output = NodeStatusInfo;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/status", self.node)
+    }
+}
+
+/// Command of [`PostNodeStatus`].
+#[derive(Eq, PartialEq, Serialize, Debug, Clone, Copy)]
+pub enum NodeCommand {
+    #[serde(rename = "reboot")]
+    Reboot,
+    #[serde(rename = "shutdown")]
+    Shutdown,
+}
+
+/// POST /nodes/{node}/status
+#[derive(Serialize, Debug, Clone)]
+pub struct PostNodeStatus {
+    #[serde(skip)]
+    node: NodeId,
+    command: NodeCommand,
+}
+
+impl PostNodeStatus {
+    pub fn new(node: NodeId, command: NodeCommand) -> Self {
+        Self { node, command }
+    }
+}
+
+impl Endpoint for PostNodeStatus {
+    type Output = ();
+    const METHOD: Method = Method::POST;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/status", self.node)
+    }
+}
+
+/// GET /nodes/{node}/services
+#[derive(Serialize, Debug, Clone)]
+pub struct GetNodeServices {
+    #[serde(skip)]
+    node: NodeId,
+}
+
+impl GetNodeServices {
+    pub fn new(node: NodeId) -> Self {
+        Self { node }
+    }
+}
+
+impl Endpoint for GetNodeServices {
+    type Output = Vec<NodeService>;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/services", self.node)
+    }
+}
+
+/// GET /nodes/{node}/apt/update
+#[derive(Serialize, Debug, Clone)]
+pub struct GetNodeAptUpdates {
+    #[serde(skip)]
+    node: NodeId,
+}
+
+impl GetNodeAptUpdates {
+    pub fn new(node: NodeId) -> Self {
+        Self { node }
+    }
+}
+
+impl Endpoint for GetNodeAptUpdates {
+    type Output = Vec<NodeAptUpdate>;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/apt/update", self.node)
+    }
+}
+
+/// POST /nodes/{node}/startall. Returns the UPID of the task.
+#[derive(Debug, Clone)]
+pub struct PostNodeStartall {
+    node: NodeId,
+    input: NodeStartallInput,
+}
+
+impl PostNodeStartall {
+    pub fn new(node: NodeId) -> Self {
+        Self {
+            node,
+            input: NodeStartallInput::default(),
+        }
+    }
+
+    pub fn input(mut self, input: NodeStartallInput) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Also start guests that do not have the `onboot` option set.
+    pub fn force(mut self, force: bool) -> Self {
+        self.input.force = Some(int_bool(force));
+        self
+    }
+
+    /// Only consider these guests.
+    pub fn vms(mut self, vms: &[VmId]) -> Self {
+        self.input.vms = Some(join_vm_ids(vms));
+        self
+    }
+}
+
+impl Serialize for PostNodeStartall {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.input.serialize(serializer)
+    }
+}
+
+impl Endpoint for PostNodeStartall {
+    type Output = String;
+    const METHOD: Method = Method::POST;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/startall", self.node)
+    }
+}
+
+/// POST /nodes/{node}/stopall. Returns the UPID of the task.
+#[derive(Debug, Clone)]
+pub struct PostNodeStopall {
+    node: NodeId,
+    input: NodeStopallInput,
+}
+
+impl PostNodeStopall {
+    pub fn new(node: NodeId) -> Self {
+        Self {
+            node,
+            input: NodeStopallInput::default(),
+        }
+    }
+
+    pub fn input(mut self, input: NodeStopallInput) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Force a hard stop of guests that did not shut down after the timeout.
+    pub fn force_stop(mut self, force_stop: bool) -> Self {
+        self.input.force_stop = Some(int_bool(force_stop));
+        self
+    }
+
+    /// Seconds to wait for each guest to shut down.
+    pub fn timeout(mut self, timeout: u64) -> Self {
+        self.input.timeout = Some(timeout);
+        self
+    }
+
+    /// Only consider these guests.
+    pub fn vms(mut self, vms: &[VmId]) -> Self {
+        self.input.vms = Some(join_vm_ids(vms));
+        self
+    }
+}
+
+impl Serialize for PostNodeStopall {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.input.serialize(serializer)
+    }
+}
+
+impl Endpoint for PostNodeStopall {
+    type Output = String;
+    const METHOD: Method = Method::POST;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/stopall", self.node)
+    }
+}
+
+fn join_vm_ids(vms: &[VmId]) -> String {
+    vms.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// GET /nodes/{node}/tasks/{upid}/status
+#[derive(Serialize, Debug, Clone)]
+pub struct GetNodeTaskStatus {
+    #[serde(skip)]
+    node: NodeId,
+    #[serde(skip)]
+    upid: String,
+}
+
+impl GetNodeTaskStatus {
+    pub fn new(node: NodeId, upid: impl Into<String>) -> Self {
+        Self {
+            node,
+            upid: upid.into(),
+        }
+    }
+}
+
+impl Endpoint for GetNodeTaskStatus {
+    type Output = TaskStatus;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/tasks/{}/status", self.node, self.upid)
+    }
+}
+
+/// GET /nodes/{node}/lxc
+#[derive(Serialize, Debug, Clone)]
+pub struct GetNodeLxc {
+    #[serde(skip)]
+    node: NodeId,
+}
+
+impl GetNodeLxc {
+    pub fn new(node: NodeId) -> Self {
+        Self { node }
+    }
+}
+
+impl Endpoint for GetNodeLxc {
+    type Output = Vec<LxcVm>;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/lxc", self.node)
+    }
+}
+
+/// GET /nodes/{node}/qemu
+#[derive(Serialize, Debug, Clone)]
+pub struct GetNodeQemu {
+    #[serde(skip)]
+    node: NodeId,
+    full: u8,
+}
+
+impl GetNodeQemu {
+    pub fn new(node: NodeId) -> Self {
+        Self { node, full: 0 }
+    }
+
+    /// Determine the full status of active VMs.
+    pub fn full(mut self, full: bool) -> Self {
+        self.full = int_bool(full);
+        self
+    }
+}
+
+impl Endpoint for GetNodeQemu {
+    type Output = Vec<QemuVm>;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/qemu", self.node)
+    }
+}
+
+/// GET /nodes/{node}/lxc or GET /nodes/{node}/qemu, keeping only the fields of
+/// [`VmListEntry`].
+#[derive(Debug, Clone)]
+pub struct GetNodeVmsBrief {
+    node: NodeId,
+    vm_type: VmType,
+}
+
+impl GetNodeVmsBrief {
+    pub fn new(node: NodeId, vm_type: VmType) -> Self {
+        Self { node, vm_type }
+    }
+}
+
+impl Serialize for GetNodeVmsBrief {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self.vm_type {
+            VmType::Lxc => serializer.collect_seq([] as [(&str, &str); 0]),
+            VmType::Qemu => serializer.collect_seq([("full", "0")]),
+        }
+    }
+}
+
+impl Endpoint for GetNodeVmsBrief {
+    type Output = Vec<VmListEntry>;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/{}", self.node, self.vm_type)
+    }
+}
+
+/// GET /nodes/{node}/storage
+#[derive(Serialize, Debug, Clone)]
+pub struct GetNodeStorages {
+    #[serde(skip)]
+    node: NodeId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enabled: Option<u8>,
+}
+
+impl GetNodeStorages {
+    pub fn new(node: NodeId) -> Self {
+        Self {
+            node,
+            content: None,
+            enabled: None,
+        }
+    }
+
+    /// Only list storages that can hold content of this type, eg. `iso`.
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    /// Only list enabled storages.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = Some(int_bool(enabled));
+        self
+    }
+}
+
+impl Endpoint for GetNodeStorages {
+    type Output = Vec<Storage>;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/storage", self.node)
+    }
+}
+
+/// GET /nodes/{node}/storage/{storage}/content
+#[derive(Serialize, Debug, Clone)]
+pub struct GetStorageContent {
+    #[serde(skip)]
+    node: NodeId,
+    #[serde(skip)]
+    storage: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vmid: Option<u64>,
+}
+
+impl GetStorageContent {
+    pub fn new(node: NodeId, storage: impl Into<String>) -> Self {
+        Self {
+            node,
+            storage: storage.into(),
+            content: None,
+            vmid: None,
+        }
+    }
+
+    /// Only list content of this type, eg. `iso`.
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    /// Only list images of this guest.
+    pub fn vmid(mut self, vm: VmId) -> Self {
+        self.vmid = Some(vm.into());
+        self
+    }
+}
+
+impl Endpoint for GetStorageContent {
+    type Output = Vec<StorageContent>;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/storage/{}/content", self.node, self.storage)
+    }
+}
+
+/// GET /nodes/{node}/qemu/{vmid}/status/current
+#[derive(Serialize, Debug, Clone)]
+pub struct GetQemuStatusCurrent {
+    #[serde(skip)]
+    node: NodeId,
+    #[serde(skip)]
+    vm: VmId,
+}
+
+impl GetQemuStatusCurrent {
+    pub fn new(node: NodeId, vm: VmId) -> Self {
+        Self { node, vm }
+    }
+}
+
+impl Endpoint for GetQemuStatusCurrent {
+    type Output = QemuVmStatus;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/qemu/{}/status/current", self.node, self.vm)
+    }
+}
+
+/// GET /nodes/{node}/{lxc,qemu}/{vmid}/config
+#[derive(Serialize, Debug, Clone)]
+pub struct GetVmConfig {
+    #[serde(skip)]
+    node: NodeId,
+    #[serde(skip)]
+    vm: VmId,
+    #[serde(skip)]
+    vm_type: VmType,
+}
+
+impl GetVmConfig {
+    pub fn new(node: NodeId, vm: VmId, vm_type: VmType) -> Self {
+        Self { node, vm, vm_type }
+    }
+}
+
+impl Endpoint for GetVmConfig {
+    type Output = VmConfig;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/{}/{}/config", self.node, self.vm_type, self.vm)
+    }
+}
+
+/// PUT /nodes/{node}/{lxc,qemu}/{vmid}/config
+#[derive(Debug, Clone)]
+pub struct PutVmConfig {
+    node: NodeId,
+    vm: VmId,
+    vm_type: VmType,
+    values: Vec<(String, String)>,
+    delete: Vec<String>,
+}
+
+impl PutVmConfig {
+    pub fn new(node: NodeId, vm: VmId, vm_type: VmType) -> Self {
+        Self {
+            node,
+            vm,
+            vm_type,
+            values: Vec::new(),
+            delete: Vec::new(),
+        }
+    }
+
+    /// Sets the option `key`, eg. `boot` or `ide2`.
+    pub fn set(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.values.push((key.into(), value.into()));
+        self
+    }
+
+    /// Removes the option `key` from the configuration.
+    pub fn delete(mut self, key: impl Into<String>) -> Self {
+        self.delete.push(key.into());
+        self
+    }
+}
+
+impl Serialize for PutVmConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let delete = (!self.delete.is_empty()).then(|| ("delete", self.delete.join(",")));
+        serializer.collect_seq(
+            self.values
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.clone()))
+                .chain(delete),
+        )
+    }
+}
+
+impl Endpoint for PutVmConfig {
+    type Output = ();
+    const METHOD: Method = Method::PUT;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/{}/{}/config", self.node, self.vm_type, self.vm)
+    }
+}
+
+/// POST /nodes/{node}/{lxc,qemu}/{vmid}/template
+#[derive(Serialize, Debug, Clone)]
+pub struct PostVmTemplate {
+    #[serde(skip)]
+    node: NodeId,
+    #[serde(skip)]
+    vm: VmId,
+    #[serde(skip)]
+    vm_type: VmType,
+}
+
+impl PostVmTemplate {
+    pub fn new(node: NodeId, vm: VmId, vm_type: VmType) -> Self {
+        Self { node, vm, vm_type }
+    }
+}
+
+impl Endpoint for PostVmTemplate {
+    type Output = ();
+    const METHOD: Method = Method::POST;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/{}/{}/template", self.node, self.vm_type, self.vm)
+    }
+}
+
+/// POST /nodes/{node}/lxc/{vmid}/clone. Returns the UPID of the task.
+#[derive(Serialize, Debug, Clone)]
+pub struct PostLxcClone {
+    #[serde(skip)]
+    node: NodeId,
+    #[serde(skip)]
+    vm: VmId,
+    newid: u64,
+    full: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hostname: Option<String>,
+}
+
+impl PostLxcClone {
+    pub fn new(node: NodeId, vm: VmId, newid: VmId) -> Self {
+        Self {
+            node,
+            vm,
+            newid: newid.into(),
+            full: 0,
+            hostname: None,
+        }
+    }
+
+    /// Create a full copy instead of a linked clone. Only templates can be cloned as linked
+    /// clones.
+    pub fn full(mut self, full: bool) -> Self {
+        self.full = int_bool(full);
+        self
+    }
+
+    pub fn hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = Some(hostname.into());
+        self
+    }
+}
+
+impl Endpoint for PostLxcClone {
+    type Output = String;
+    const METHOD: Method = Method::POST;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/lxc/{}/clone", self.node, self.vm)
+    }
+}
+
+/// Defines an endpoint of a guest whose parameters differ between LXC and QEMU.
+macro_rules! vm_endpoint {
+    ($(#[$attr:meta])* $name:ident($input:ty, $route:literal) -> $output:ty) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone)]
+        pub struct $name {
+            node: NodeId,
+            vm: VmId,
+            vm_type: VmType,
+            input: $input,
+        }
+
+        impl $name {
+            pub fn new(node: NodeId, vm: VmId, vm_type: VmType) -> Self {
+                Self {
+                    node,
+                    vm,
+                    vm_type,
+                    input: Default::default(),
+                }
+            }
+
+            /// Sets the parameters. Parameters that the guest type does not support are
+            /// not sent.
+            pub fn input(mut self, input: $input) -> Self {
+                self.input = input;
+                self
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                match self.vm_type {
+                    VmType::Lxc => self.input.clone().into_lxc().serialize(serializer),
+                    VmType::Qemu => self.input.clone().into_qemu().serialize(serializer),
+                }
+            }
+        }
+
+        impl Endpoint for $name {
+            type Output = $output;
+            const METHOD: Method = Method::POST;
+
+            fn path(&self) -> String {
+                format!(
+                    concat!("nodes/{}/{}/{}/", $route),
+                    self.node, self.vm_type, self.vm
+                )
+            }
+        }
+    };
+}
+
+vm_endpoint! {
+    /// POST /nodes/{node}/{lxc,qemu}/{vmid}/status/start. Returns the UPID of the task.
+    PostVmStart(VmStartInput, "status/start") -> String
+}
+vm_endpoint! {
+    /// POST /nodes/{node}/{lxc,qemu}/{vmid}/status/stop. Returns the UPID of the task.
+    PostVmStop(VmStopInput, "status/stop") -> String
+}
+vm_endpoint! {
+    /// POST /nodes/{node}/{lxc,qemu}/{vmid}/status/shutdown. Returns the UPID of the task.
+    PostVmShutdown(VmShutdownInput, "status/shutdown") -> String
+}
+vm_endpoint! {
+    /// POST /nodes/{node}/{lxc,qemu}/{vmid}/status/reboot. Returns the UPID of the task.
+    PostVmReboot(VmRebootInput, "status/reboot") -> String
+}
+vm_endpoint! {
+    /// POST /nodes/{node}/{lxc,qemu}/{vmid}/status/suspend. Returns the UPID of the task.
+    PostVmSuspend(VmSuspendInput, "status/suspend") -> String
+}
+vm_endpoint! {
+    /// POST /nodes/{node}/{lxc,qemu}/{vmid}/status/resume. Returns the UPID of the task.
+    PostVmResume(VmResumeInput, "status/resume") -> String
+}
+vm_endpoint! {
+    /// POST /nodes/{node}/{lxc,qemu}/{vmid}/termproxy
+    PostVmTermproxy(VmTermproxyInput, "termproxy") -> Termproxy
+}
+vm_endpoint! {
+    /// POST /nodes/{node}/{lxc,qemu}/{vmid}/vncproxy
+    PostVmVncproxy(VmVncproxyInput, "vncproxy") -> Vncproxy
+}
+
+/// POST /nodes/{node}/qemu/{vmid}/status/reset. Returns the UPID of the task.
+#[derive(Serialize, Debug, Clone)]
+pub struct PostQemuReset {
+    #[serde(skip)]
+    node: NodeId,
+    #[serde(skip)]
+    vm: VmId,
+    #[serde(flatten)]
+    input: VmResetInputQemu,
+}
+
+impl PostQemuReset {
+    pub fn new(node: NodeId, vm: VmId) -> Self {
+        Self {
+            node,
+            vm,
+            input: VmResetInputQemu::default(),
+        }
+    }
+
+    pub fn input(mut self, input: VmResetInputQemu) -> Self {
+        self.input = input;
+        self
+    }
+}
+
+impl Endpoint for PostQemuReset {
+    type Output = String;
+    const METHOD: Method = Method::POST;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/qemu/{}/status/reset", self.node, self.vm)
+    }
+}
+
+/// POST /nodes/{node}/{lxc,qemu}/{vmid}/spiceproxy
+#[derive(Serialize, Debug, Clone)]
+pub struct PostVmSpiceproxy {
+    #[serde(skip)]
+    node: NodeId,
+    #[serde(skip)]
+    vm: VmId,
+    #[serde(skip)]
+    vm_type: VmType,
+    #[serde(flatten)]
+    input: VmSpiceproxyInput,
+}
+
+impl PostVmSpiceproxy {
+    pub fn new(node: NodeId, vm: VmId, vm_type: VmType) -> Self {
+        Self {
+            node,
+            vm,
+            vm_type,
+            input: VmSpiceproxyInput::default(),
+        }
+    }
+
+    pub fn input(mut self, input: VmSpiceproxyInput) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// SPICE proxy the client connects through.
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.input.proxy = Some(proxy.into());
+        self
+    }
+}
+
+impl Endpoint for PostVmSpiceproxy {
+    type Output = Spiceproxy;
+    const METHOD: Method = Method::POST;
+
+    fn path(&self) -> String {
+        format!(
+            "nodes/{}/{}/{}/spiceproxy",
+            self.node, self.vm_type, self.vm
+        )
+    }
+}
+
+/// Defines a console endpoint of a node.
+macro_rules! node_console_endpoint {
+    ($(#[$attr:meta])* $name:ident($input:ty, $route:literal) -> $output:ty) => {
+        $(#[$attr])*
+        #[derive(Serialize, Debug, Clone)]
+        pub struct $name {
+            #[serde(skip)]
+            node: NodeId,
+            #[serde(flatten)]
+            input: $input,
+        }
+
+        impl $name {
+            pub fn new(node: NodeId) -> Self {
+                Self {
+                    node,
+                    input: Default::default(),
+                }
+            }
+
+            pub fn input(mut self, input: $input) -> Self {
+                self.input = input;
+                self
+            }
+
+            /// Runs this command instead of a login shell. Requires `root@pam`.
+            pub fn cmd(mut self, cmd: NodeTermproxyCmd) -> Self {
+                self.input.cmd = Some(cmd);
+                self
+            }
+        }
+
+        impl Endpoint for $name {
+            type Output = $output;
+            const METHOD: Method = Method::POST;
+
+            fn path(&self) -> String {
+                format!(concat!("nodes/{}/", $route), self.node)
+            }
+        }
+    };
+}
+
+node_console_endpoint! {
+    /// POST /nodes/{node}/termproxy
+    PostNodeTermproxy(NodeTermproxyInput, "termproxy") -> Termproxy
+}
+node_console_endpoint! {
+    /// POST /nodes/{node}/spiceshell
+    PostNodeSpiceshell(NodeSpiceshellInput, "spiceshell") -> Spiceproxy
+}
+node_console_endpoint! {
+    /// POST /nodes/{node}/vncshell
+    PostNodeVncshell(NodeVncshellInput, "vncshell") -> Vncproxy
+}
+
+/// GET /nodes/{node}/qemu/{vmid}/agent/network-get-interfaces
+#[derive(Serialize, Debug, Clone)]
+pub struct GetQemuAgentNetworkInterfaces {
+    #[serde(skip)]
+    node: NodeId,
+    #[serde(skip)]
+    vm: VmId,
+}
+
+impl GetQemuAgentNetworkInterfaces {
+    pub fn new(node: NodeId, vm: VmId) -> Self {
+        Self { node, vm }
+    }
+}
+
+impl Endpoint for GetQemuAgentNetworkInterfaces {
+    type Output = AgentResult<Vec<AgentNetworkInterface>>;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!(
+            "nodes/{}/qemu/{}/agent/network-get-interfaces",
+            self.node, self.vm
+        )
+    }
+}
+
+/// POST /nodes/{node}/qemu/{vmid}/agent/exec
+#[derive(Debug, Clone)]
+pub struct PostQemuAgentExec {
+    node: NodeId,
+    vm: VmId,
+    command: Vec<String>,
+}
+
+impl PostQemuAgentExec {
+    /// `command` is the program followed by its arguments.
+    pub fn new(node: NodeId, vm: VmId, command: Vec<String>) -> Self {
+        Self { node, vm, command }
+    }
+}
+
+impl Serialize for PostQemuAgentExec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.command.iter().map(|arg| ("command", arg)))
+    }
+}
+
+impl Endpoint for PostQemuAgentExec {
+    type Output = AgentExec;
+    const METHOD: Method = Method::POST;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/qemu/{}/agent/exec", self.node, self.vm)
+    }
+}
+
+/// GET /nodes/{node}/qemu/{vmid}/agent/exec-status
+#[derive(Serialize, Debug, Clone)]
+pub struct GetQemuAgentExecStatus {
+    #[serde(skip)]
+    node: NodeId,
+    #[serde(skip)]
+    vm: VmId,
+    pid: i64,
+}
+
+impl GetQemuAgentExecStatus {
+    pub fn new(node: NodeId, vm: VmId, pid: i64) -> Self {
+        Self { node, vm, pid }
+    }
+}
+
+impl Endpoint for GetQemuAgentExecStatus {
+    type Output = AgentExecStatus;
+    const METHOD: Method = Method::GET;
+
+    fn path(&self) -> String {
+        format!("nodes/{}/qemu/{}/agent/exec-status", self.node, self.vm)
+    }
+}
@@ -0,0 +1,942 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub use crate::config::*;
+pub use crate::datatypes::*;
+use crate::endpoints::*;
+use futures::future::BoxFuture;
+use futures::lock::Mutex;
+use futures::{stream, StreamExt};
+use http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use log::{debug, warn};
+use reqwest::{ClientBuilder, RequestBuilder, Response};
+use secure_string::SecureString;
+use serde::Deserialize;
+use thiserror::Error;
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod config;
+mod datatypes;
+pub mod endpoints;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("http: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("http: {0}")]
+    Http(#[from] http::Error),
+    #[error("invalid header value: {0}")]
+    InvalidHeaderValue(#[from] http::header::InvalidHeaderValue),
+    #[error("the value provided for an identifier is invalid")]
+    InvalidIdValue,
+    #[error("invalid URI: {0}")]
+    InvalidUri(#[from] http::uri::InvalidUri),
+    #[error("the client configuration is invalid")]
+    InvalidConfig,
+    #[cfg(feature = "blocking")]
+    #[error("failed to start runtime: {0}")]
+    Runtime(std::io::Error),
+    #[error("authentication failed")]
+    AuthFailed,
+    #[error("API returned no data")]
+    MissingData,
+    #[error("API failed with status {0}")]
+    ApiUnknown(StatusCode),
+    #[error("API failed with status {0}: {1}")]
+    Api(StatusCode, String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Debug)]
+struct Client {
+    client: reqwest::Client,
+    hostname: String,
+    root: String, // ends with /
+}
+
+impl Client {
+    fn new(config: &ClientConfig) -> Result<Self> {
+        let mut root_str = config.root.to_string();
+        if !root_str.ends_with('/') {
+            root_str.push('/');
+        }
+        let client = match &config.http_client {
+            Some(client) => client.clone(),
+            None => ClientBuilder::new()
+                .danger_accept_invalid_certs(config.ignore_ssl_errors)
+                .build()?,
+        };
+        Ok(Self {
+            client,
+            hostname: config.hostname().to_string(),
+            root: root_str,
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.root, path))
+    }
+}
+
+struct AuthHeaders<'a> {
+    auth_header: Cow<'a, str>,
+    extra_headers: HeaderMap,
+}
+
+/// Age after which tickets are re-issued before they are used. Proxmox tickets are valid for two
+/// hours, this leaves enough headroom for slow requests and clock drift.
+const TICKET_REFRESH_AFTER: Duration = Duration::from_secs(90 * 60);
+
+struct TicketProvider {
+    client: Client,
+    user: String,
+    password: SecureString,
+    just_reauth: AtomicBool,
+    current_ticket: Arc<Mutex<Option<IssuedTicket>>>,
+}
+
+struct IssuedTicket {
+    ticket: Ticket,
+    issued_at: Instant,
+}
+
+impl IssuedTicket {
+    fn needs_refresh(&self) -> bool {
+        self.issued_at.elapsed() >= TICKET_REFRESH_AFTER
+    }
+}
+
+impl TicketProvider {
+    async fn reauth(&self) -> Result<()> {
+        debug!("re-issuing ticket");
+        self.just_reauth.store(true, Ordering::Release);
+        let ticket = self
+            .client
+            .request(Method::POST, "access/ticket")
+            .form(&[
+                ("username", &*self.user),
+                ("password", self.password.unsecure()),
+            ])
+            .send()
+            .await?
+            .json::<Wrapper<Ticket>>()
+            .await?
+            .data
+            .ok_or(Error::MissingData)?;
+        self.current_ticket.lock().await.replace(IssuedTicket {
+            ticket,
+            issued_at: Instant::now(),
+        });
+        Ok(())
+    }
+}
+
+struct ApikeyProvider {
+    tokenid: String,
+    apikey: SecureString,
+}
+
+enum DoAfterAuthRetry {
+    Retry,
+    Fail,
+}
+
+trait ApiAccessProvider {
+    fn provide_auth_headers(&self) -> BoxFuture<Result<AuthHeaders>>;
+    fn auth_success(&self) {}
+    fn failed_auth(&self) -> BoxFuture<DoAfterAuthRetry>;
+}
+
+impl ApiAccessProvider for ApikeyProvider {
+    fn provide_auth_headers(&self) -> BoxFuture<Result<AuthHeaders>> {
+        Box::pin(async move {
+            Ok(AuthHeaders {
+                auth_header: format!("PVEAPIToken={}={}", self.tokenid, self.apikey.unsecure())
+                    .into(),
+                extra_headers: HeaderMap::new(),
+            })
+        })
+    }
+
+    fn failed_auth(&self) -> BoxFuture<DoAfterAuthRetry> {
+        Box::pin(async move { DoAfterAuthRetry::Fail })
+    }
+}
+
+impl ApiAccessProvider for TicketProvider {
+    fn provide_auth_headers(&self) -> BoxFuture<Result<AuthHeaders>> {
+        let current_ticket = self.current_ticket.clone();
+        Box::pin(async move {
+            let lock = current_ticket.lock().await;
+            // Tickets close to expiring are re-issued right away, instead of letting the request
+            // fail first.
+            if let Some(ticket) = lock
+                .as_ref()
+                .filter(|issued| !issued.needs_refresh())
+                .map(|issued| &issued.ticket)
+            {
+                let mut extra_headers = HeaderMap::with_capacity(1);
+                extra_headers.insert(
+                    "CSRFPreventionToken",
+                    HeaderValue::from_str(&ticket.csrf_prevention_token)?,
+                );
+                Ok(AuthHeaders {
+                    auth_header: format!("PVEAuthCookie={}", ticket.ticket).into(),
+                    extra_headers,
+                })
+            } else {
+                drop(lock);
+                self.reauth().await?;
+                self.provide_auth_headers().await
+            }
+        })
+    }
+
+    fn auth_success(&self) {
+        self.just_reauth.store(false, Ordering::Release)
+    }
+
+    fn failed_auth(&self) -> BoxFuture<DoAfterAuthRetry> {
+        Box::pin(async move {
+            if self.just_reauth.load(Ordering::Acquire) {
+                DoAfterAuthRetry::Fail
+            } else {
+                match self.reauth().await {
+                    Ok(_) => DoAfterAuthRetry::Retry,
+                    Err(err) => {
+                        warn!("ticket reissuing failed: {err}");
+                        DoAfterAuthRetry::Fail
+                    }
+                }
+            }
+        })
+    }
+}
+
+pub struct ProxmoxApiClient {
+    client: Client,
+    config: ClientConfig,
+    api_access_provider: Box<dyn ApiAccessProvider + Send + Sync>,
+}
+
+/// Public API
+impl ProxmoxApiClient {
+    /// Creates a client. No request is made yet, so this succeeds even if the API can not be
+    /// reached or the credentials are wrong.
+    pub fn connect(config: ClientConfig) -> Result<Self> {
+        let client = Client::new(&config)?;
+        let api_access_provider: Box<dyn ApiAccessProvider + Send + Sync> =
+            match config.credentials.clone() {
+                Credentials::Apikey { tokenid, apikey } => {
+                    debug!("creating proxmox client with api key");
+                    Box::new(ApikeyProvider { tokenid, apikey })
+                }
+                Credentials::Ticket { user, password } => {
+                    debug!("creating proxmox client with username and password");
+                    Box::new(TicketProvider {
+                        client: client.clone(),
+                        user,
+                        password,
+                        just_reauth: Default::default(),
+                        current_ticket: Arc::new(Default::default()),
+                    })
+                }
+            };
+        Ok(Self {
+            client,
+            config,
+            api_access_provider,
+        })
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// Sends a request to a single endpoint, see [`endpoints`]. The other methods of the client
+    /// are shortcuts for the most common requests.
+    pub async fn execute<E: Endpoint>(&self, request: &E) -> Result<E::Output> {
+        let route = request.path();
+        debug!("{} @ {route}", E::METHOD);
+        let resp = self
+            .do_request(E::METHOD, &route, |req| {
+                if E::METHOD == Method::GET {
+                    req.query(request)
+                } else {
+                    req.form(request)
+                }
+            })
+            .await?;
+        let status = resp.status();
+        let wrapper = match resp.json::<Wrapper<E::Output>>().await {
+            Ok(wrapper) => wrapper,
+            Err(_) if !status.is_success() => return Err(Error::ApiUnknown(status)),
+            Err(err) => return Err(err.into()),
+        };
+        match self.handle_wrapper(status, wrapper) {
+            // Endpoints without a return value respond with `null`.
+            Err(Error::MissingData) => {
+                E::Output::deserialize(serde_json::Value::Null).map_err(|_| Error::MissingData)
+            }
+            result => result,
+        }
+    }
+
+    pub async fn version(&self) -> Result<Version> {
+        self.execute(&GetVersion).await
+    }
+
+    pub async fn access_permissions(&self) -> Result<AccessPermissions> {
+        self.execute(&GetAccessPermissions).await
+    }
+
+    pub async fn cluster_status(&self) -> Result<Vec<ClusterStatusEntry>> {
+        self.execute(&GetClusterStatus).await
+    }
+
+    /// Name of the cluster, if the node the client is connected to is part of one.
+    pub async fn cluster_name(&self) -> Result<Option<String>> {
+        Ok(self
+            .cluster_status()
+            .await?
+            .into_iter()
+            .find(|entry| entry.r#type == ClusterStatusEntryType::Cluster)
+            .map(|entry| entry.name))
+    }
+
+    /// Status of the HA manager and of the guests it manages. Empty if HA is not configured.
+    pub async fn cluster_ha_status(&self) -> Result<Vec<HaStatusEntry>> {
+        self.execute(&GetClusterHaStatus).await
+    }
+
+    /// Requests `state` for the HA resource `sid` (eg. `vm:100`). The HA manager then starts or
+    /// stops the guest on its own.
+    pub async fn cluster_ha_resource_set_state(
+        &self,
+        sid: &str,
+        state: HaRequestState,
+    ) -> Result<()> {
+        self.execute(&PutClusterHaResource::new(sid).state(state))
+            .await
+    }
+
+    pub async fn nodes(&self) -> Result<Vec<Node>> {
+        self.execute(&GetNodes).await
+    }
+
+    pub async fn node_lxc(&self, node: &NodeId) -> Result<Vec<LxcVm>> {
+        let mut vms = self.execute(&GetNodeLxc::new(node.clone())).await?;
+
+        vms.sort_unstable_by_key(|vm| vm.vmid.clone());
+
+        Ok(vms)
+    }
+
+    pub async fn node_qemu(&self, node: &NodeId, full: bool) -> Result<Vec<QemuVm>> {
+        let mut vms = self
+            .execute(&GetNodeQemu::new(node.clone()).full(full))
+            .await?;
+
+        vms.sort_unstable_by_key(|vm| vm.vmid.clone());
+
+        Ok(vms)
+    }
+
+    /// Lists the guests of the given type on a node, but only keeps the fields of
+    /// [`VmListEntry`]. This is much lighter on memory than [`Self::node_lxc`] and
+    /// [`Self::node_qemu`] for nodes with a very large number of guests.
+    pub async fn node_vms_brief(&self, node: &NodeId, vm_type: VmType) -> Result<Vec<VmListEntry>> {
+        let mut vms = self
+            .execute(&GetNodeVmsBrief::new(node.clone(), vm_type))
+            .await?;
+
+        vms.sort_unstable_by_key(|vm| vm.vmid.clone());
+
+        Ok(vms)
+    }
+
+    pub async fn node_status(&self, node: &NodeId) -> Result<NodeStatusInfo> {
+        self.execute(&GetNodeStatus::new(node.clone())).await
+    }
+
+    pub async fn node_services(&self, node: &NodeId) -> Result<Vec<NodeService>> {
+        self.execute(&GetNodeServices::new(node.clone())).await
+    }
+
+    /// Package updates available on a node, as of the last time the package lists were
+    /// refreshed.
+    pub async fn node_apt_updates(&self, node: &NodeId) -> Result<Vec<NodeAptUpdate>> {
+        self.execute(&GetNodeAptUpdates::new(node.clone())).await
+    }
+
+    pub async fn node_reboot(&self, node: &NodeId) -> Result<()> {
+        self.execute(&PostNodeStatus::new(node.clone(), NodeCommand::Reboot))
+            .await
+    }
+
+    pub async fn node_shutdown(&self, node: &NodeId) -> Result<()> {
+        self.execute(&PostNodeStatus::new(node.clone(), NodeCommand::Shutdown))
+            .await
+    }
+
+    /// Starts the guests of a node in the order of their `startup` option. Unless forced,
+    /// only guests with `onboot` set are started. Returns the UPID of the task.
+    pub async fn node_startall(&self, node: &NodeId, input: NodeStartallInput) -> Result<String> {
+        self.execute(&PostNodeStartall::new(node.clone()).input(input))
+            .await
+    }
+
+    /// Shuts down the guests of a node in the reverse order of their `startup` option.
+    /// Returns the UPID of the task.
+    pub async fn node_stopall(&self, node: &NodeId, input: NodeStopallInput) -> Result<String> {
+        self.execute(&PostNodeStopall::new(node.clone()).input(input))
+            .await
+    }
+
+    pub async fn node_task_status(&self, node: &NodeId, upid: &str) -> Result<TaskStatus> {
+        self.execute(&GetNodeTaskStatus::new(node.clone(), upid))
+            .await
+    }
+
+    pub async fn vm_qemu_status_current(&self, node: &NodeId, vm: &VmId) -> Result<QemuVmStatus> {
+        self.execute(&GetQemuStatusCurrent::new(node.clone(), vm.clone()))
+            .await
+    }
+
+    pub async fn vm_config(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+    ) -> Result<VmConfig> {
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        self.execute(&GetVmConfig::new(node.clone(), vm.clone(), vm_type))
+            .await
+    }
+
+    /// The next free guest ID of the cluster.
+    pub async fn cluster_nextid(&self) -> Result<VmId> {
+        self.execute(&GetClusterNextid).await
+    }
+
+    /// Converts a stopped guest into a template. This can not be undone.
+    pub async fn vm_template(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+    ) -> Result<()> {
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        self.execute(&PostVmTemplate::new(node.clone(), vm.clone(), vm_type))
+            .await?;
+        Ok(())
+    }
+
+    /// LXC only: Clones the container `vm` as `newid`. Templates can be cloned as linked
+    /// clones, all other containers only as full clones. Returns the UPID of the clone task.
+    pub async fn lxc_clone(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        newid: &VmId,
+        hostname: Option<&str>,
+        full: bool,
+    ) -> Result<String> {
+        let mut request = PostLxcClone::new(node.clone(), vm.clone(), newid.clone()).full(full);
+        if let Some(hostname) = hostname {
+            request = request.hostname(hostname);
+        }
+        self.execute(&request).await
+    }
+
+    /// Sets the description (notes) of a guest. An empty description removes it.
+    pub async fn vm_set_description(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+        description: &str,
+    ) -> Result<()> {
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        let request = PutVmConfig::new(node.clone(), vm.clone(), vm_type);
+        let request = if description.is_empty() {
+            request.delete("description")
+        } else {
+            request.set("description", description)
+        };
+        self.execute(&request).await
+    }
+
+    /// QEMU only: Inserts the volume `volume` (eg. `local:iso/debian.iso`) into the CD/DVD
+    /// drive `drive`. If `volume` is `None`, the drive is emptied.
+    pub async fn qemu_vm_set_cdrom(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        drive: &str,
+        volume: Option<&str>,
+    ) -> Result<()> {
+        let value = format!("{},media=cdrom", volume.unwrap_or("none"));
+        self.execute(&PutVmConfig::new(node.clone(), vm.clone(), VmType::Qemu).set(drive, value))
+            .await
+    }
+
+    /// QEMU only: Sets the devices the VM boots from, in order. Takes effect on the next
+    /// start or reset of the VM.
+    pub async fn qemu_vm_set_boot_order(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        devices: &[String],
+    ) -> Result<()> {
+        let value = format!("order={}", devices.join(";"));
+        self.execute(&PutVmConfig::new(node.clone(), vm.clone(), VmType::Qemu).set("boot", value))
+            .await
+    }
+
+    /// Storages of a node that can hold content of the type `content` (eg. `iso`).
+    pub async fn node_storages(&self, node: &NodeId, content: &str) -> Result<Vec<Storage>> {
+        self.execute(
+            &GetNodeStorages::new(node.clone())
+                .content(content)
+                .enabled(true),
+        )
+        .await
+    }
+
+    pub async fn storage_content(
+        &self,
+        node: &NodeId,
+        storage: &str,
+        content: &str,
+    ) -> Result<Vec<StorageContent>> {
+        self.execute(&GetStorageContent::new(node.clone(), storage).content(content))
+            .await
+    }
+
+    pub async fn vm_available_console_proxies(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+    ) -> Result<impl AsRef<[VmConsoleProxyType]>> {
+        const ALL: [VmConsoleProxyType; 3] = [
+            VmConsoleProxyType::Term,
+            VmConsoleProxyType::Vnc,
+            VmConsoleProxyType::Spice,
+        ];
+
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        Ok(match vm_type {
+            VmType::Lxc => &ALL[..],
+            VmType::Qemu => {
+                if self
+                    .vm_qemu_status_current(node, vm)
+                    .await?
+                    .spice
+                    .unwrap_or_default()
+                {
+                    &ALL[..]
+                } else {
+                    &[VmConsoleProxyType::Term, VmConsoleProxyType::Vnc][..]
+                }
+            }
+        })
+    }
+
+    /// Batched version of [`Self::vm_available_console_proxies`]. Runs at most `batch_size`
+    /// requests at once. The results are returned in the same order as `vms`.
+    pub async fn vms_available_console_proxies(
+        &self,
+        node: &NodeId,
+        vms: &[(VmId, VmType)],
+        batch_size: usize,
+    ) -> Vec<Result<Vec<VmConsoleProxyType>>> {
+        stream::iter(vms)
+            .map(|(vm, vm_type)| async move {
+                self.vm_available_console_proxies(node, vm, Some(*vm_type))
+                    .await
+                    .map(|proxies| proxies.as_ref().to_vec())
+            })
+            .buffered(batch_size.max(1))
+            .collect()
+            .await
+    }
+
+    pub async fn vm_start(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+        input: VmStartInput,
+    ) -> Result<String> {
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        self.execute(&PostVmStart::new(node.clone(), vm.clone(), vm_type).input(input))
+            .await
+    }
+
+    pub async fn vm_stop(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+        input: VmStopInput,
+    ) -> Result<String> {
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        self.execute(&PostVmStop::new(node.clone(), vm.clone(), vm_type).input(input))
+            .await
+    }
+
+    pub async fn qemu_vm_reset(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        input: VmResetInputQemu,
+    ) -> Result<String> {
+        self.execute(&PostQemuReset::new(node.clone(), vm.clone()).input(input))
+            .await
+    }
+
+    pub async fn vm_shutdown(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+        input: VmShutdownInput,
+    ) -> Result<String> {
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        self.execute(&PostVmShutdown::new(node.clone(), vm.clone(), vm_type).input(input))
+            .await
+    }
+
+    pub async fn vm_reboot(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+        input: VmRebootInput,
+    ) -> Result<String> {
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        self.execute(&PostVmReboot::new(node.clone(), vm.clone(), vm_type).input(input))
+            .await
+    }
+
+    pub async fn vm_suspend(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+        input: VmSuspendInput,
+    ) -> Result<String> {
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        self.execute(&PostVmSuspend::new(node.clone(), vm.clone(), vm_type).input(input))
+            .await
+    }
+
+    /// Resumes a guest that was suspended to RAM. Guests suspended to disk are resumed by
+    /// [`Self::vm_start`] instead.
+    pub async fn vm_resume(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+        input: VmResumeInput,
+    ) -> Result<String> {
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        self.execute(&PostVmResume::new(node.clone(), vm.clone(), vm_type).input(input))
+            .await
+    }
+
+    /// QEMU only: Network interfaces of the guest as reported by the QEMU guest agent. Fails if
+    /// the agent is not enabled or not running.
+    pub async fn qemu_vm_agent_network_interfaces(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+    ) -> Result<Vec<AgentNetworkInterface>> {
+        let result = self
+            .execute(&GetQemuAgentNetworkInterfaces::new(
+                node.clone(),
+                vm.clone(),
+            ))
+            .await?;
+        Ok(result.result)
+    }
+
+    /// QEMU only: Starts `command` (program and arguments) in the guest using the QEMU guest
+    /// agent. Returns the PID of the process, see [`Self::qemu_vm_agent_exec_status`].
+    pub async fn qemu_vm_agent_exec(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        command: &[String],
+    ) -> Result<i64> {
+        let exec = self
+            .execute(&PostQemuAgentExec::new(
+                node.clone(),
+                vm.clone(),
+                command.to_vec(),
+            ))
+            .await?;
+        Ok(exec.pid)
+    }
+
+    /// QEMU only: Status and output of a process started with [`Self::qemu_vm_agent_exec`].
+    pub async fn qemu_vm_agent_exec_status(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        pid: i64,
+    ) -> Result<AgentExecStatus> {
+        self.execute(&GetQemuAgentExecStatus::new(node.clone(), vm.clone(), pid))
+            .await
+    }
+
+    pub async fn node_termproxy(
+        &self,
+        node: &NodeId,
+        input: NodeTermproxyInput,
+    ) -> Result<Termproxy> {
+        self.execute(&PostNodeTermproxy::new(node.clone()).input(input))
+            .await
+    }
+
+    pub async fn vm_termproxy(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+        input: VmTermproxyInput,
+    ) -> Result<(VmType, Termproxy)> {
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        let resp = self
+            .execute(&PostVmTermproxy::new(node.clone(), vm.clone(), vm_type).input(input))
+            .await?;
+        Ok((vm_type, resp))
+    }
+
+    pub async fn node_spiceshell(
+        &self,
+        node: &NodeId,
+        input: NodeSpiceshellInput,
+    ) -> Result<Spiceproxy> {
+        self.execute(&PostNodeSpiceshell::new(node.clone()).input(input))
+            .await
+    }
+
+    pub async fn vm_spiceproxy(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+        input: VmSpiceproxyInput,
+    ) -> Result<Spiceproxy> {
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        self.execute(&PostVmSpiceproxy::new(node.clone(), vm.clone(), vm_type).input(input))
+            .await
+    }
+
+    pub async fn node_vncshell(&self, node: &NodeId, input: NodeVncshellInput) -> Result<Vncproxy> {
+        self.execute(&PostNodeVncshell::new(node.clone()).input(input))
+            .await
+    }
+
+    pub async fn vm_vncproxy(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: Option<VmType>,
+        input: VmVncproxyInput,
+    ) -> Result<Vncproxy> {
+        let vm_type = self.vm_type(node, vm, vm_type).await?;
+        self.execute(&PostVmVncproxy::new(node.clone(), vm.clone(), vm_type).input(input))
+            .await
+    }
+
+    pub async fn node_vncwebsocket(
+        &self,
+        node: &NodeId,
+        input: &VncwebsocketInput,
+    ) -> Result<http::Request<()>> {
+        self.make_vncwebsocket(node, None, input).await
+    }
+
+    pub async fn vm_vncwebsocket(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        vm_type: VmType,
+        input: &VncwebsocketInput,
+    ) -> Result<http::Request<()>> {
+        self.make_vncwebsocket(node, Some((vm, vm_type)), input)
+            .await
+    }
+
+    async fn make_vncwebsocket(
+        &self,
+        node: &NodeId,
+        vm: Option<(&VmId, VmType)>,
+        input: &VncwebsocketInput,
+    ) -> Result<http::Request<()>> {
+        let auth_header = self.api_access_provider.provide_auth_headers().await?;
+
+        let url = match vm {
+            None => format!(
+                "{}nodes/{}/vncwebsocket?port={}&vncticket={}",
+                self.client.root.replacen("http", "ws", 1),
+                node,
+                input.port,
+                urlencoding::encode(&input.vncticket)
+            ),
+            Some((vm_id, VmType::Qemu)) => format!(
+                "{}nodes/{}/qemu/{}/vncwebsocket?port={}&vncticket={}",
+                self.client.root.replacen("http", "ws", 1),
+                node,
+                vm_id,
+                input.port,
+                urlencoding::encode(&input.vncticket)
+            ),
+            Some((vm_id, VmType::Lxc)) => format!(
+                "{}nodes/{}/lxc/{}/vncwebsocket?port={}&vncticket={}",
+                self.client.root.replacen("http", "ws", 1),
+                node,
+                vm_id,
+                input.port,
+                urlencoding::encode(&input.vncticket)
+            ),
+        };
+
+        let mut builder = http::Request::builder()
+            .method(Method::GET)
+            .uri(&url)
+            .header("Host", &self.client.hostname)
+            .header("Connection", "Upgrade")
+            .header("Upgrade", "websocket")
+            .header("Sec-WebSocket-Protocol", "binary")
+            .header("Authorization", &*auth_header.auth_header);
+        if let Some(headers) = builder.headers_mut() {
+            for (k, v) in auth_header.extra_headers {
+                if let Some(k) = k {
+                    headers.insert(k, v);
+                }
+            }
+        }
+        Ok(builder.body(())?)
+    }
+}
+
+/// Private API
+impl ProxmoxApiClient {
+    async fn vm_type(
+        &self,
+        node: &NodeId,
+        vm: &VmId,
+        maybe_vm_type: Option<VmType>,
+    ) -> Result<VmType> {
+        match maybe_vm_type {
+            Some(vm_type) => Ok(vm_type),
+            None => {
+                for lxcvm in self.node_lxc(node).await? {
+                    if &lxcvm.vmid == vm {
+                        return Ok(VmType::Lxc);
+                    }
+                }
+                Ok(VmType::Qemu)
+            }
+        }
+    }
+
+    async fn base_request(&self, method: Method, route: &str) -> Result<RequestBuilder> {
+        let auth_header = self.api_access_provider.provide_auth_headers().await?;
+        Ok(self
+            .client
+            .request(method, route)
+            .headers(auth_header.extra_headers)
+            .header(header::AUTHORIZATION, auth_header.auth_header.as_ref()))
+    }
+
+    async fn do_request<F>(
+        &self,
+        method: Method,
+        route: &str,
+        modify_request: F,
+    ) -> Result<Response>
+    where
+        F: Fn(RequestBuilder) -> RequestBuilder,
+    {
+        let mut response = modify_request(self.base_request(method.clone(), route).await?)
+            .send()
+            .await;
+
+        let status = response
+            .as_ref()
+            .map(|resp| Some(resp.status()))
+            .unwrap_or_else(|err| err.status());
+
+        if status == Some(StatusCode::UNAUTHORIZED) {
+            debug!("request failed with 401");
+            // Retry with re-auth potentially
+            match self.api_access_provider.failed_auth().await {
+                DoAfterAuthRetry::Retry => {
+                    debug!("access backend indicated retry possible: retrying");
+                    response = modify_request(self.base_request(method, route).await?)
+                        .send()
+                        .await;
+
+                    if response.is_ok() {
+                        debug!("retry success");
+                    } else {
+                        debug!("retry failed");
+                        return Err(Error::AuthFailed);
+                    }
+                }
+                DoAfterAuthRetry::Fail => {
+                    debug!("retry not possible");
+                    return Err(Error::AuthFailed);
+                }
+            }
+        }
+
+        if response.is_ok() {
+            self.api_access_provider.auth_success();
+        }
+
+        response.map_err(Into::into)
+    }
+
+    fn handle_wrapper<T>(&self, status_code: StatusCode, wrapper: Wrapper<T>) -> Result<T> {
+        if status_code.is_success() || status_code.is_informational() {
+            match wrapper.data {
+                None => Err(Error::MissingData),
+                Some(v) => Ok(v),
+            }
+        } else {
+            match wrapper.reason {
+                None => Err(Error::ApiUnknown(status_code)),
+                Some(v) => Err(Error::Api(status_code, v)),
+            }
+        }
+    }
+}
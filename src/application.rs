@@ -17,9 +17,12 @@
  */
 use std::borrow::Cow;
 use std::cell::Cell;
+use std::cell::OnceCell;
 use std::cell::RefCell;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::iter;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::{Arc, OnceLock};
 
@@ -27,35 +30,84 @@ use adw::glib::user_config_dir;
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use anyhow::anyhow;
-use async_std::fs::{create_dir_all, read_dir, read_to_string, remove_file, OpenOptions};
-use async_std::io::WriteExt;
+use async_std::fs::{create_dir_all, read_dir, read_to_string, remove_file};
+use futures::future::join_all;
 use futures::StreamExt;
 use gettextrs::gettext;
 use glib::subclass::Signal;
-use gtk::{gio, glib};
+use gtk::{gdk, gio, glib};
 use log::{debug, error, info, warn};
+use secure_string::SecureString;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use libfieldmonitor::busy::{BusyGuard, BusyStack};
+use libfieldmonitor::action_failure::observe_action_failures;
+use libfieldmonitor::busy::{BusyGuard, BusyStack, BusyTargets, TargetBusyGuard};
 use libfieldmonitor::connection::ConnectionConfiguration;
 use libfieldmonitor::connection::ConnectionInstance;
 use libfieldmonitor::connection::ConnectionProvider;
+use libfieldmonitor::connection::{ConfigAccess, ConfigAccessMut};
 use libfieldmonitor::connection::{Connection, DualScopedConnectionConfiguration};
 use libfieldmonitor::i18n::gettext_f;
 use libfieldmonitor::ManagesSecrets;
 
+use crate::audit_log::{self, AuditEntry, AuditEvent};
+use crate::availability_history;
+use crate::config_crypt::{self, ConfigKey, KEY_SECRET_CONNECTION_ID};
+use crate::config_writer::ConfigWriter;
 use crate::connection::CONNECTION_PROVIDERS;
 use crate::connection_loader::ConnectionLoader;
+use crate::event_sinks::{self, SinkEvent, SinkEventKind};
+use crate::failed_actions::{FailedAction, FailedActions};
 use crate::secrets::SecretManager;
-use crate::settings::FieldMonitorSettings;
+use crate::settings::{ConfigurableShortcut, FieldMonitorSettings};
+use crate::status_poller::StatusPoller;
 use crate::widget::add_connection_dialog::FieldMonitorAddConnectionDialog;
+use crate::widget::appearance_dialog::edit_appearance;
 use crate::widget::authenticate_connection_dialog::FieldMonitorAuthenticateConnectionDialog;
+use crate::widget::close_warning_dialog::{CloseWarningKind, FieldMonitorCloseWarningDialog};
+use crate::widget::external_tools_dialog::edit_external_tools;
 use crate::widget::preferences::FieldMonitorPreferencesDialog;
+use crate::widget::server_tags_dialog::edit_server_tags;
 use crate::widget::update_connection_dialog::FieldMonitorUpdateConnectionDialog;
 use crate::widget::window::FieldMonitorWindow;
 use libfieldmonitor::config::{APP_ID, VERSION};
 
+/// Name of the `GOptionEntry` collecting the remaining (non-option) arguments.
+const OPTION_REMAINING: &str = "";
+/// Environment variable the password of connections added from the command line is read from.
+const PASSWORD_ENV_VAR: &str = "FIELD_MONITOR_PASSWORD";
+
+/// Application actions that are disabled in kiosk mode.
+const KIOSK_DISABLED_ACTIONS: &[&str] = &[
+    "add-connection",
+    "edit-connection",
+    "duplicate-connection",
+    "remove-connection",
+    "auth-connection",
+    "connect-to-server",
+    "connect-to-server-new-session",
+    "perform-connection-action",
+    "duplicate-server",
+    "copy-server",
+    "move-connection-to-workspace",
+    "set-connection-load-on-demand",
+    "edit-external-tools",
+    "open-external-tool",
+    "edit-appearance",
+    "edit-server-tags",
+    "reload-connections",
+    "new-window",
+    "preferences",
+];
+/// Accelerators of non-application actions that are removed in kiosk mode.
+const KIOSK_DISABLED_ACCELS: &[&str] = &[
+    "window.close",
+    "win.fullscreen",
+    "win.show-sidebar",
+    "view.close",
+];
+
 mod imp {
     use super::*;
 
@@ -67,17 +119,37 @@ mod imp {
         pub providers: RefCell<HashMap<String, Rc<Box<dyn ConnectionProvider>>>>,
         /// Manages a stack for `pending_server_action`. If stack size is zero, sets to false.
         pub busy_stack: RefCell<Option<BusyStack>>,
+        /// Servers and connections with a pending action or connection request.
+        pub busy_targets: RefCell<Option<BusyTargets>>,
+        /// Actions that failed and can be retried.
+        pub failed_actions: RefCell<FailedActions>,
+        /// Desktop notifications held back while a window is in focus mode, with their IDs.
+        pub queued_notifications: RefCell<Vec<(Option<String>, gio::Notification)>>,
         /// Whether Field Monitor is currently loading all connections for the first time.
         #[property(get, construct_only)]
         pub starting: Cell<bool>,
         /// Whether Field Monitor is currently (re-)loading all connections.
         #[property(get)]
         pub loading_connections: Cell<bool>,
-        /// Currently busy with processing an action or connection request to a server or connection.
+        /// Currently busy with a task affecting all connections, such as saving or reloading them.
+        /// Actions and connection requests only make their target busy, see `busy_targets`.
         #[property(get)]
         pub busy: Rc<Cell<bool>>,
         #[property(get, construct_only)]
         pub settings: RefCell<Option<FieldMonitorSettings>>,
+        /// Whether the application runs in kiosk mode, set once the kiosk sessions are opened.
+        pub kiosk: Cell<bool>,
+        pub status_poller: OnceCell<StatusPoller>,
+        pub config_writer: ConfigWriter,
+        pub connections_monitor: RefCell<Option<gio::FileMonitor>>,
+        /// Key for encrypting connection files, once loaded from the keyring.
+        pub config_key: RefCell<Option<ConfigKey>>,
+        /// Held while loading or creating the key, so that only one key is ever created.
+        pub config_key_lock: futures::lock::Mutex<()>,
+        /// Cookie of the inhibitor keeping the system awake while sessions are active.
+        pub session_inhibit_cookie: Cell<Option<u32>>,
+        /// Whether the inhibitor is updated on the next idle.
+        pub session_inhibit_update_queued: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -103,6 +175,14 @@ mod imp {
                     Signal::builder("connection-removed")
                         .param_types([String::static_type()])
                         .build(),
+                    // This signal is emitted when a server or connection (given by its path)
+                    // becomes busy with an action or connection request, or is no longer busy.
+                    Signal::builder("target-busy-changed")
+                        .param_types([String::static_type(), bool::static_type()])
+                        .build(),
+                    // This signal is emitted when an action failed, or a failed action was
+                    // retried successfully or dismissed.
+                    Signal::builder("failed-actions-changed").build(),
                 ]
             })
         }
@@ -111,6 +191,13 @@ mod imp {
             let obj = self.obj();
             obj.setup_gactions();
             obj.set_accels_for_action("app.quit", &["<primary>q"]);
+            if let Some(settings) = obj.settings() {
+                settings.connect_inhibit_suspend_notify(glib::clone!(
+                    #[weak]
+                    obj,
+                    move |_| obj.update_session_inhibit()
+                ));
+            }
             // Listen to own signals for debug purposes
             self.obj().connect_closure(
                 "connection-updated",
@@ -143,18 +230,7 @@ mod imp {
         // to do that, we'll just present any existing window.
         fn activate(&self) {
             let hold = self.obj().hold();
-            // Init providers if not done already.
-            if self.providers.borrow().is_empty() {
-                self.providers.replace(
-                    CONNECTION_PROVIDERS
-                        .iter()
-                        .map(|constructor| {
-                            let provider = constructor.new();
-                            (provider.tag().to_owned(), Rc::new(provider))
-                        })
-                        .collect(),
-                );
-            }
+            self.init_providers();
             // Init secret service if not done already.
             if self.secret_manager.borrow().is_none() {
                 let slf = self;
@@ -189,6 +265,38 @@ mod imp {
                 self.finish_activate();
             }
         }
+
+        fn handle_local_options(&self, options: &glib::VariantDict) -> ControlFlow<glib::ExitCode> {
+            let command = options
+                .lookup::<Vec<String>>(OPTION_REMAINING)
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            match command.first().map(String::as_str) {
+                None => ControlFlow::Continue(()),
+                Some("add-connection") => {
+                    self.init_providers();
+                    ControlFlow::Break(
+                        glib::MainContext::default()
+                            .block_on(self.obj().add_connection_from_options(options)),
+                    )
+                }
+                Some(command) => {
+                    eprintln!(
+                        "{}",
+                        gettext_f("Unknown command: {command}", &[("command", command)])
+                    );
+                    ControlFlow::Break(glib::ExitCode::FAILURE)
+                }
+            }
+        }
+
+        fn shutdown(&self) {
+            // The main loop no longer runs, so scheduled writes would be lost otherwise.
+            self.config_writer.write_pending_now();
+            availability_history::write_pending_now();
+            self.parent_shutdown();
+        }
     }
 
     impl GtkApplicationImpl for FieldMonitorApplication {}
@@ -205,6 +313,7 @@ mod imp {
                     slf,
                     async move {
                         slf.obj().reload_connections().await;
+                        slf.obj().watch_connections_dir().await;
                     }
                 ));
             }
@@ -232,6 +341,21 @@ mod imp {
             }
         }
 
+        /// Init providers if not done already.
+        fn init_providers(&self) {
+            if self.providers.borrow().is_empty() {
+                self.providers.replace(
+                    CONNECTION_PROVIDERS
+                        .iter()
+                        .map(|constructor| {
+                            let provider = constructor.new();
+                            (provider.tag().to_owned(), Rc::new(provider))
+                        })
+                        .collect(),
+                );
+            }
+        }
+
         pub fn get_provider(&self, tag: &str) -> Option<Rc<Box<dyn ConnectionProvider>>> {
             self.providers.borrow().get(tag).as_ref().cloned().cloned()
         }
@@ -252,6 +376,12 @@ impl FieldMonitorApplication {
             .property("settings", FieldMonitorSettings::new(APP_ID))
             .property("starting", true)
             .build();
+        if let Some(settings) = app.settings() {
+            app.imp()
+                .status_poller
+                .set(StatusPoller::new(&settings))
+                .ok();
+        }
         app.imp().busy_stack.borrow_mut().replace(BusyStack::new(
             app.imp().busy.clone(),
             Box::new(glib::clone!(
@@ -260,6 +390,16 @@ impl FieldMonitorApplication {
                 move || app.notify("busy")
             )),
         ));
+        app.imp()
+            .busy_targets
+            .borrow_mut()
+            .replace(BusyTargets::new(Box::new(glib::clone!(
+                #[weak]
+                app,
+                move |target, busy| {
+                    app.emit_by_name::<()>("target-busy-changed", &[&target, &busy]);
+                }
+            ))));
 
         // Accelerators. We remove ALL accelerators first and only use custom accelerators
         // since we remove and re-add them later. Plus some default accelerators are not useful
@@ -267,6 +407,63 @@ impl FieldMonitorApplication {
         // TODO: If somebody knows a more elegant way, let me know.
         app.remove_accels();
         app.add_accels();
+        app.add_command_line_options();
+
+        // Connections of other workspaces may have been skipped when loading.
+        if let Some(settings) = app.settings() {
+            let load_skipped = glib::clone!(
+                #[weak]
+                app,
+                move |_: &FieldMonitorSettings| {
+                    if app.imp().connections.borrow().is_none() {
+                        return;
+                    }
+                    glib::spawn_future_local(glib::clone!(
+                        #[weak]
+                        app,
+                        async move { app.load_workspace_connections().await }
+                    ));
+                }
+            );
+            settings.connect_active_workspace_notify(load_skipped.clone());
+            settings.connect_load_active_workspace_only_notify(load_skipped);
+            settings.connect_shortcuts_changed(glib::clone!(
+                #[weak]
+                app,
+                move |_| {
+                    app.remove_accels();
+                    app.add_accels();
+                }
+            ));
+            settings.connect_encrypt_connections_notify(glib::clone!(
+                #[weak]
+                app,
+                move |_| {
+                    glib::spawn_future_local(glib::clone!(
+                        #[weak]
+                        app,
+                        async move { app.rewrite_connection_files().await }
+                    ));
+                }
+            ));
+
+            if settings.audit_log_enabled() {
+                if let Err(err) = audit_log::prune(settings.audit_log_retention_days()) {
+                    warn!("failed to remove old audit log entries: {err}");
+                }
+            }
+        }
+
+        // The kiosk sessions can only be opened once all connections are known.
+        app.connect_starting_notify(|app| {
+            if !app.starting() && app.settings().is_some_and(|s| s.kiosk_mode()) {
+                glib::spawn_future_local(glib::clone!(
+                    #[weak]
+                    app,
+                    async move { app.start_kiosk().await }
+                ));
+            }
+        });
 
         // Prefer dark style by default
         app.style_manager()
@@ -282,20 +479,269 @@ impl FieldMonitorApplication {
     }
 
     pub fn add_accels(&self) {
-        self.set_accels_for_action("window.close", &["<Alt>F4"]);
-        self.set_accels_for_action("app.new-window", &["<Primary>N"]);
-        self.set_accels_for_action("app.preferences", &["<Primary>comma"]);
-        self.set_accels_for_action("app.reload-connections", &["<Primary>R"]);
-        self.set_accels_for_action("win.show-help-overlay", &["<Primary>question"]);
-        self.set_accels_for_action("win.fullscreen", &["F11"]);
-        self.set_accels_for_action("win.show-sidebar", &["<Primary>E"]);
-        self.set_accels_for_action("view.close", &["<Shift><Primary>W"]);
-        self.set_accels_for_action("view.term-copy", &["<Shift><Primary>C"]);
-        self.set_accels_for_action("view.term-paste", &["<Shift><Primary>V"]);
-        self.set_accels_for_action("view.term-select-all", &["<Shift><Primary>A"]);
-        self.set_accels_for_action("view.term-zoom-in", &["<Primary>plus"]);
-        self.set_accels_for_action("view.term-zoom-out", &["<Primary>minus"]);
-        self.set_accels_for_action("view.term-zoom-reset", &["<Primary>0"]);
+        let settings = self.settings();
+        for shortcut in ConfigurableShortcut::ALL {
+            let accel = match &settings {
+                Some(settings) => settings.shortcut(&shortcut),
+                None => shortcut.default.to_string(),
+            };
+            if accel.is_empty() {
+                self.set_accels_for_action(shortcut.action, &[]);
+            } else {
+                self.set_accels_for_action(shortcut.action, &[&accel]);
+            }
+        }
+        if self.imp().kiosk.get() {
+            for action in KIOSK_DISABLED_ACCELS {
+                self.set_accels_for_action(action, &[]);
+            }
+        }
+    }
+
+    /// Registers the options of the `add-connection` command. The command itself is the
+    /// remaining argument.
+    fn add_command_line_options(&self) {
+        self.add_main_option(
+            OPTION_REMAINING,
+            glib::Char::from(0),
+            glib::OptionFlags::NONE,
+            glib::OptionArg::StringArray,
+            "",
+            Some("[add-connection]"),
+        );
+        for (name, description, arg_description) in [
+            (
+                "provider",
+                gettext("Type of the connection to add: rdp, spice or vnc"),
+                "TYPE",
+            ),
+            (
+                "type",
+                gettext("Type of the server, if the connection type has multiple"),
+                "TYPE",
+            ),
+            ("host", gettext("Host of the server"), "HOST"),
+            ("user", gettext("Username for the server"), "USER"),
+            ("title", gettext("Name of the connection"), "TITLE"),
+        ] {
+            self.add_main_option(
+                name,
+                glib::Char::from(0),
+                glib::OptionFlags::NONE,
+                glib::OptionArg::String,
+                &description,
+                Some(arg_description),
+            );
+        }
+        self.add_main_option(
+            "port",
+            glib::Char::from(0),
+            glib::OptionFlags::NONE,
+            glib::OptionArg::Int,
+            &gettext("Port of the server"),
+            Some("PORT"),
+        );
+        self.add_main_option(
+            "password-stdin",
+            glib::Char::from(0),
+            glib::OptionFlags::NONE,
+            glib::OptionArg::None,
+            &gettext(
+                "Read the password from the standard input. Otherwise it is taken from FIELD_MONITOR_PASSWORD, if set.",
+            ),
+            None,
+        );
+    }
+
+    /// Adds a connection configured with the command line `options` and prints its ID.
+    async fn add_connection_from_options(&self, options: &glib::VariantDict) -> glib::ExitCode {
+        let mut values = HashMap::new();
+        for name in ["provider", "type", "host", "user", "title"] {
+            if let Ok(Some(value)) = options.lookup::<String>(name) {
+                values.insert(name.to_string(), value);
+            }
+        }
+        if let Ok(Some(port)) = options.lookup::<i32>("port") {
+            values.insert("port".to_string(), port.to_string());
+        }
+
+        let password = if options.contains("password-stdin") {
+            let mut line = String::new();
+            if let Err(err) = std::io::stdin().read_line(&mut line) {
+                eprintln!(
+                    "{}",
+                    gettext_f(
+                        "Failed to read password: {err}",
+                        &[("err", &err.to_string())]
+                    )
+                );
+                return glib::ExitCode::FAILURE;
+            }
+            Some(line.trim_end_matches(['\r', '\n']).to_string())
+        } else {
+            std::env::var(PASSWORD_ENV_VAR).ok()
+        }
+        .map(SecureString::from);
+
+        let Some(provider_name) = values.get("provider").cloned() else {
+            eprintln!("{}", gettext("The --provider option is required."));
+            return glib::ExitCode::FAILURE;
+        };
+        let Some(provider) = self.connection_providers().into_iter().find(|provider| {
+            provider.tag() == provider_name || provider.tag_aliases().contains(&&*provider_name)
+        }) else {
+            eprintln!(
+                "{}",
+                gettext_f(
+                    "Unknown connection type: {provider}",
+                    &[("provider", &provider_name)]
+                )
+            );
+            return glib::ExitCode::FAILURE;
+        };
+
+        match SecretManager::new().await {
+            Ok(secrets) => {
+                self.imp()
+                    .secret_manager
+                    .replace(Some(Arc::new(Box::new(secrets))));
+            }
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    gettext_f(
+                        "Failed to connect to the secret service: {err}",
+                        &[("err", &err.to_string())]
+                    )
+                );
+                return glib::ExitCode::FAILURE;
+            }
+        }
+
+        let configuration = DualScopedConnectionConfiguration::new_unified(
+            self.reserve_new_connection(&**provider),
+        );
+        let result = match provider
+            .configure_from_options(&values, password, configuration)
+            .await
+        {
+            Ok(mut configuration) => self
+                .write_connection(configuration.persistent_mut())
+                .await
+                .map(|()| configuration.session().id().to_string()),
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(connection_id) => {
+                println!("{connection_id}");
+                glib::ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    gettext_f(
+                        "Failed to add connection: {err}",
+                        &[("err", &err.to_string())]
+                    )
+                );
+                glib::ExitCode::FAILURE
+            }
+        }
+    }
+
+    /// The shared timer for refreshing the status of servers.
+    pub fn status_poller(&self) -> Option<&StatusPoller> {
+        self.imp().status_poller.get()
+    }
+
+    /// Records `entry` in the audit log, if the audit log is enabled.
+    pub fn audit(&self, entry: AuditEntry) {
+        if !self
+            .settings()
+            .is_some_and(|settings| settings.audit_log_enabled())
+        {
+            return;
+        }
+        if let Err(err) = audit_log::append(&entry) {
+            error!("failed to write audit log: {err}");
+        }
+    }
+
+    /// Sends `event` to the webhook and command configured for alerting.
+    pub fn notify_event_sinks(&self, event: SinkEvent) {
+        if let Some(settings) = self.settings() {
+            event_sinks::dispatch(&settings, event);
+        }
+    }
+
+    /// Whether any window is in focus mode, see [`FieldMonitorWindow::focus_mode`].
+    pub fn in_focus_mode(&self) -> bool {
+        self.windows()
+            .into_iter()
+            .filter_map(|window| window.downcast::<FieldMonitorWindow>().ok())
+            .any(|window| window.focus_mode())
+    }
+
+    /// Sends a desktop notification that is not urgent. While a window is in focus mode it is
+    /// held back until focus mode ends. A queued notification with the same `id` is replaced.
+    pub fn send_deferrable_notification(&self, id: Option<&str>, notification: &gio::Notification) {
+        if !self.in_focus_mode() {
+            self.send_notification(id, notification);
+            return;
+        }
+        let mut queue = self.imp().queued_notifications.borrow_mut();
+        if id.is_some() {
+            queue.retain(|(queued_id, _)| queued_id.as_deref() != id);
+        }
+        queue.push((id.map(ToString::to_string), notification.clone()));
+    }
+
+    /// Sends the notifications held back during focus mode, unless a window is still in it.
+    pub fn flush_queued_notifications(&self) {
+        if self.in_focus_mode() {
+            return;
+        }
+        let queue = self.imp().queued_notifications.take();
+        for (id, notification) in queue {
+            self.send_notification(id.as_deref(), &notification);
+        }
+    }
+
+    /// Actions that failed and were not retried successfully yet, newest first.
+    pub fn failed_actions(&self) -> Vec<FailedAction> {
+        self.imp().failed_actions.borrow().to_vec()
+    }
+
+    /// Forgets the failed action, for example once it was retried successfully.
+    pub fn dismiss_failed_action(&self, path: &str, action_id: &str) {
+        if self
+            .imp()
+            .failed_actions
+            .borrow_mut()
+            .remove(path, action_id)
+        {
+            self.emit_by_name::<()>("failed-actions-changed", &[]);
+        }
+    }
+
+    /// Remembers `action` for the dashboard and offers to retry it right away in a toast.
+    fn queue_failed_action(&self, action: FailedAction) {
+        if let Some(window) = self.active_window().and_downcast::<FieldMonitorWindow>() {
+            window.add_toast(
+                adw::Toast::builder()
+                    .title(gettext_f(
+                        "{action} failed for {title}",
+                        &[("action", &action.action_label), ("title", &action.title)],
+                    ))
+                    .button_label(gettext("Retry"))
+                    .action_name("app.perform-connection-action")
+                    .action_target(&action.action_target())
+                    .timeout(10)
+                    .build(),
+            );
+        }
+        self.imp().failed_actions.borrow_mut().push(action);
+        self.emit_by_name::<()>("failed-actions-changed", &[]);
     }
 
     pub fn open_new_window(&self) -> FieldMonitorWindow {
@@ -310,6 +756,91 @@ impl FieldMonitorApplication {
         dialog.present(window.as_ref());
     }
 
+    /// Quits the app, after asking the user to confirm if any window is still connected to a
+    /// server.
+    pub fn request_quit(&self) {
+        let open_connection_descs = self
+            .windows()
+            .iter()
+            .filter_map(|window| window.downcast_ref::<FieldMonitorWindow>())
+            .flat_map(|window| window.tab_view().describe_active())
+            .collect::<Vec<_>>();
+        if open_connection_descs.is_empty() || !FieldMonitorCloseWarningDialog::is_enabled() {
+            self.quit();
+            return;
+        }
+
+        let dialog =
+            FieldMonitorCloseWarningDialog::new(CloseWarningKind::App, open_connection_descs);
+        dialog.connect_response(
+            Some(FieldMonitorCloseWarningDialog::RESPONSE_CLOSE),
+            glib::clone!(
+                #[weak(rename_to = app)]
+                self,
+                move |_, _| app.quit()
+            ),
+        );
+        dialog.present(self.active_window().as_ref());
+    }
+
+    /// Re-evaluates whether the system must be kept awake, because a session is connected or
+    /// recorded. Sessions call this when their state changes; the check runs on the next idle,
+    /// so closed sessions are already gone from their windows.
+    pub fn update_session_inhibit(&self) {
+        let imp = self.imp();
+        if imp.session_inhibit_update_queued.replace(true) {
+            return;
+        }
+        glib::idle_add_local_once(glib::clone!(
+            #[weak(rename_to = app)]
+            self,
+            move || {
+                app.imp().session_inhibit_update_queued.set(false);
+                app.apply_session_inhibit();
+            }
+        ));
+    }
+
+    fn apply_session_inhibit(&self) {
+        let imp = self.imp();
+        let enabled = self
+            .settings()
+            .is_some_and(|settings| settings.inhibit_suspend());
+        let active_window = self
+            .windows()
+            .into_iter()
+            .filter_map(|window| window.downcast::<FieldMonitorWindow>().ok())
+            .find(|window| {
+                window
+                    .tab_view()
+                    .screens()
+                    .iter()
+                    .any(|screen| screen.is_connected() || screen.recording())
+            })
+            .filter(|_| enabled);
+
+        match (active_window, imp.session_inhibit_cookie.get()) {
+            (Some(window), None) => {
+                let cookie = self.inhibit(
+                    Some(&window),
+                    gtk::ApplicationInhibitFlags::IDLE | gtk::ApplicationInhibitFlags::SUSPEND,
+                    Some(&gettext("Connected to a server")),
+                );
+                // 0 means the request was not successful.
+                if cookie != 0 {
+                    debug!("inhibiting suspend while sessions are active");
+                    imp.session_inhibit_cookie.set(Some(cookie));
+                }
+            }
+            (None, Some(cookie)) => {
+                debug!("no longer inhibiting suspend");
+                self.uninhibit(cookie);
+                imp.session_inhibit_cookie.set(None);
+            }
+            _ => {}
+        }
+    }
+
     async fn connections_dir(&self) -> PathBuf {
         let dir = user_config_dir().join("field-monitor").join("connections");
         create_dir_all(&dir).await.ok();
@@ -318,7 +849,7 @@ impl FieldMonitorApplication {
 
     fn setup_gactions(&self) {
         let quit_action = gio::ActionEntry::builder("quit")
-            .activate(move |app: &Self, _, _| app.quit())
+            .activate(move |app: &Self, _, _| app.request_quit())
             .build();
         let about_action = gio::ActionEntry::builder("about")
             .activate(move |app: &Self, _, _| app.show_about())
@@ -343,6 +874,10 @@ impl FieldMonitorApplication {
                 app.edit_connection_via_dialog(connection_id)
             })
             .build();
+        let duplicate_connection_action = gio::ActionEntry::builder("duplicate-connection")
+            .parameter_type(Some(&String::static_variant_type()))
+            .activate(move |app: &Self, _, connection_id| app.duplicate_connection(connection_id))
+            .build();
         let remove_connection_action = gio::ActionEntry::builder("remove-connection")
             .parameter_type(Some(&String::static_variant_type()))
             .activate(move |app: &Self, _, connection_id| {
@@ -358,27 +893,20 @@ impl FieldMonitorApplication {
         let connect_to_server_action = gio::ActionEntry::builder("connect-to-server")
             .parameter_type(Some(&*<(String, String)>::static_variant_type()))
             .activate(move |app: &Self, _, connection_id| {
-                let Some((path, adapter_id)) =
-                    connection_id.and_then(<(String, String)>::from_variant)
-                else {
-                    warn!("Invalid parameters passed to app.connect-to-server. Ignoring.");
-                    return;
-                };
-                if app.busy() {
-                    warn!("Server action still pending. Action ignored.");
-                    return;
-                }
-                let pending_guard = app.be_busy();
-                glib::spawn_future_local(glib::clone!(
-                    #[weak]
-                    app,
-                    async move {
-                        app.connect_to_server(&path, &adapter_id).await;
-                        drop(pending_guard);
-                    }
-                ));
+                app.activate_connect_to_server("connect-to-server", connection_id, false)
             })
             .build();
+        let connect_to_server_new_session_action =
+            gio::ActionEntry::builder("connect-to-server-new-session")
+                .parameter_type(Some(&*<(String, String)>::static_variant_type()))
+                .activate(move |app: &Self, _, connection_id| {
+                    app.activate_connect_to_server(
+                        "connect-to-server-new-session",
+                        connection_id,
+                        true,
+                    )
+                })
+                .build();
         let perform_connection_action_action =
             gio::ActionEntry::builder("perform-connection-action")
                 .parameter_type(Some(&*<(bool, String, String)>::static_variant_type()))
@@ -391,11 +919,11 @@ impl FieldMonitorApplication {
                         );
                         return;
                     };
-                    if app.busy() {
-                        warn!("Connection action still pending. Action ignored.");
+                    if app.target_busy(&entity_path) {
+                        warn!("Action for {entity_path} still pending. Action ignored.");
                         return;
                     }
-                    let pending_guard = app.be_busy();
+                    let pending_guard = app.be_busy_with(&entity_path);
                     glib::spawn_future_local(glib::clone!(
                         #[weak]
                         app,
@@ -407,6 +935,44 @@ impl FieldMonitorApplication {
                     ));
                 })
                 .build();
+        let duplicate_server_action = gio::ActionEntry::builder("duplicate-server")
+            .parameter_type(Some(&String::static_variant_type()))
+            .activate(move |app: &Self, _, path| app.duplicate_server(path))
+            .build();
+        let copy_server_action = gio::ActionEntry::builder("copy-server")
+            .parameter_type(Some(&*<(String, bool)>::static_variant_type()))
+            .activate(move |app: &Self, _, target| app.copy_server_via_dialog(target))
+            .build();
+        let move_connection_to_workspace_action =
+            gio::ActionEntry::builder("move-connection-to-workspace")
+                .parameter_type(Some(&String::static_variant_type()))
+                .activate(move |app: &Self, _, connection_id| {
+                    app.move_connection_to_workspace_via_dialog(connection_id)
+                })
+                .build();
+        let set_connection_load_on_demand_action =
+            gio::ActionEntry::builder("set-connection-load-on-demand")
+                .parameter_type(Some(&*<(String, bool)>::static_variant_type()))
+                .activate(move |app: &Self, _, target| app.set_connection_load_on_demand(target))
+                .build();
+        let edit_external_tools_action = gio::ActionEntry::builder("edit-external-tools")
+            .parameter_type(Some(&String::static_variant_type()))
+            .activate(move |app: &Self, _, connection_id| {
+                app.edit_external_tools_via_dialog(connection_id)
+            })
+            .build();
+        let open_external_tool_action = gio::ActionEntry::builder("open-external-tool")
+            .parameter_type(Some(&String::static_variant_type()))
+            .activate(move |app: &Self, _, url| app.open_external_tool(url))
+            .build();
+        let edit_appearance_action = gio::ActionEntry::builder("edit-appearance")
+            .parameter_type(Some(&String::static_variant_type()))
+            .activate(move |app: &Self, _, path| app.edit_appearance_via_dialog(path))
+            .build();
+        let edit_server_tags_action = gio::ActionEntry::builder("edit-server-tags")
+            .parameter_type(Some(&<(String, String)>::static_variant_type()))
+            .activate(move |app: &Self, _, target| app.edit_server_tags_via_dialog(target))
+            .build();
         let new_window_action = gio::ActionEntry::builder("new-window")
             .activate(move |app: &Self, _, _| {
                 app.open_new_window();
@@ -424,10 +990,20 @@ impl FieldMonitorApplication {
             reload_connections_action,
             add_connection_action,
             edit_connection_action,
+            duplicate_connection_action,
             remove_connection_action,
             auth_connection_action,
             connect_to_server_action,
+            connect_to_server_new_session_action,
             perform_connection_action_action,
+            duplicate_server_action,
+            copy_server_action,
+            move_connection_to_workspace_action,
+            set_connection_load_on_demand_action,
+            edit_external_tools_action,
+            open_external_tool_action,
+            edit_appearance_action,
+            edit_server_tags_action,
             new_window_action,
             preferences_action,
         ]);
@@ -441,6 +1017,19 @@ impl FieldMonitorApplication {
         brw.as_ref().unwrap().busy()
     }
 
+    /// Whether the server or connection at `path` is busy with an action or connection request.
+    pub fn target_busy(&self, path: &str) -> bool {
+        let brw = self.imp().busy_targets.borrow();
+        brw.as_ref().is_some_and(|targets| targets.is_busy(path))
+    }
+
+    /// Mark the server or connection at `path` as busy. Other servers and connections stay
+    /// usable, but further actions for `path` are ignored until the returned guard is dropped.
+    pub fn be_busy_with(&self, path: &str) -> TargetBusyGuard {
+        let brw = self.imp().busy_targets.borrow();
+        brw.as_ref().unwrap().busy(path)
+    }
+
     fn show_about(&self) {
         let window = self.active_window();
 
@@ -550,6 +1139,77 @@ impl FieldMonitorApplication {
         dialog.present(window.as_ref());
     }
 
+    /// Saves a copy of a connection, including its stored secrets, and opens the copy for
+    /// editing.
+    fn duplicate_connection(&self, target: Option<&glib::Variant>) {
+        debug!("app.duplicate-connection: {:?}", target);
+
+        let Some(connection_id) = target.and_then(glib::Variant::str).map(ToString::to_string)
+        else {
+            warn!("Invalid connection ID target passed to app.duplicate-connection. Ignoring.");
+            return;
+        };
+        let Some(connection) = self.connection(&connection_id) else {
+            warn!("Connection passed to app.duplicate-connection not found. Ignoring.");
+            return;
+        };
+
+        let window = self.active_window();
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move {
+                match slf.save_connection_copy(&connection).await {
+                    Ok(copy) => {
+                        let dialog = FieldMonitorUpdateConnectionDialog::new(&slf, copy);
+                        slf.show_toast_or_parentless_dialog_on_signal(
+                            &dialog,
+                            "finished-updating",
+                            window.clone(),
+                            gettext("Connection successfully updated."),
+                        );
+                        dialog.present(window.as_ref());
+                    }
+                    Err(err) => {
+                        error!("Failed to duplicate connection {connection_id}: {err:?}");
+                        slf.toast_or_parentless_dialog(
+                            window.as_ref(),
+                            &gettext("Failed to duplicate the connection."),
+                        );
+                    }
+                }
+            }
+        ));
+    }
+
+    /// Saves a copy of `connection` under a new ID. Its secrets are copied in the keyring.
+    async fn save_connection_copy(
+        &self,
+        connection: &ConnectionInstance,
+    ) -> anyhow::Result<ConnectionInstance> {
+        let _busy = self.be_busy();
+        let source_id = connection.connection_id();
+        let new_id = Uuid::now_v7().to_string();
+        let mut config =
+            connection.with_configuration(|c| c.persistent().duplicate(new_id.clone()));
+        if config.get_try_as_str("title").is_some() {
+            config.set_value(
+                "title",
+                gettext_f("{title} (Copy)", &[("title", &connection.title())]),
+            );
+        }
+
+        let secret_manager = self.imp().secret_manager.borrow().as_ref().unwrap().clone();
+        secret_manager.rekey(&source_id, &new_id, true).await?;
+        if let Some(settings) = self.settings() {
+            let workspace = settings.connection_workspace(&source_id);
+            settings.set_connection_workspace(&new_id, workspace.as_deref());
+        }
+        self.save_connection(DualScopedConnectionConfiguration::new_unified(config), true)
+            .await?
+            .ok_or_else(|| anyhow!("copy of connection {source_id} was not loaded"))
+    }
+
     fn remove_connection_via_dialog(&self, target: Option<&glib::Variant>) {
         debug!("app.remove-connection: {:?}", target);
         let imp = self.imp();
@@ -621,40 +1281,376 @@ impl FieldMonitorApplication {
         dialog.present(window.as_ref());
     }
 
-    fn auth_connection_via_dialog(&self, target: Option<&glib::Variant>) {
-        debug!("app.auth-connection: {:?}", target);
-        let imp = self.imp();
+    fn duplicate_server(&self, target: Option<&glib::Variant>) {
+        debug!("app.duplicate-server: {:?}", target);
 
-        let Some(connection_id) = target.and_then(glib::Variant::str).map(ToString::to_string)
-        else {
-            warn!("Invalid connection ID target passed to app.auth-connection. Ignoring.");
+        let Some(path) = target.and_then(glib::Variant::str).map(ToString::to_string) else {
+            warn!("Invalid server path passed to app.duplicate-server. Ignoring.");
             return;
         };
-        let Some(connection) = imp
-            .connections
-            .borrow()
-            .as_ref()
-            .and_then(|m| m.get(&*connection_id).cloned())
-        else {
-            warn!("Connection passed to app.auth-connection not found. Ignoring.");
+        let Some((connection_id, _)) = path.split_once('/') else {
+            warn!("Server path passed to app.duplicate-server has no server. Ignoring.");
             return;
         };
+        let connection_id = connection_id.to_string();
 
         let window = self.active_window();
-        let dialog = FieldMonitorAuthenticateConnectionDialog::new(self, connection, &[]);
-        let msg = gettext("Authentication successfully updated");
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move {
+                let msg = match slf.copy_server(&path, &connection_id, false).await {
+                    Ok(()) => gettext("Server successfully duplicated."),
+                    Err(err) => {
+                        error!("Failed to duplicate server {path}: {err:?}");
+                        gettext("Failed to duplicate the server.")
+                    }
+                };
+                slf.toast_or_parentless_dialog(window.as_ref(), &msg);
+            }
+        ));
+    }
 
-        self.show_toast_or_parentless_dialog_on_signal(
-            &dialog,
-            "auth-finished",
-            window.clone(),
+    fn copy_server_via_dialog(&self, target: Option<&glib::Variant>) {
+        debug!("app.copy-server: {:?}", target);
+
+        let Some((path, remove_source)) = target.and_then(<(String, bool)>::from_variant) else {
+            warn!("Invalid parameters passed to app.copy-server. Ignoring.");
+            return;
+        };
+        let Some(connection) = path
+            .split_once('/')
+            .and_then(|(connection_id, _)| self.connection(connection_id))
+        else {
+            warn!("Connection of server passed to app.copy-server not found. Ignoring.");
+            return;
+        };
+
+        let mut targets = self
+            .connections()
+            .into_iter()
+            .filter(|c| {
+                c.connection_id() != connection.connection_id()
+                    && c.provider_tag() == connection.provider_tag()
+            })
+            .collect::<Vec<_>>();
+        targets.sort_by_key(|c| c.title().to_lowercase());
+
+        let window = self.active_window();
+        if targets.is_empty() {
+            self.toast_or_parentless_dialog(
+                window.as_ref(),
+                &gettext_f(
+                    "There are no other connections of the type {type}.",
+                    &[("type", &connection.provider().title())],
+                ),
+            );
+            return;
+        }
+
+        let titles = targets.iter().map(|c| c.title()).collect::<Vec<_>>();
+        let target_dropdown =
+            gtk::DropDown::from_strings(&titles.iter().map(String::as_str).collect::<Vec<_>>());
+
+        let (heading, confirm_label) = if remove_source {
+            (gettext("Move Server"), gettext("Move"))
+        } else {
+            (gettext("Copy Server"), gettext("Copy"))
+        };
+        let dialog = adw::AlertDialog::builder()
+            .heading(heading)
+            .body(gettext(
+                "Choose the connection the server and its credentials should be added to.",
+            ))
+            .extra_child(&target_dropdown)
+            .build();
+        dialog.add_response("cancel", &gettext("Cancel"));
+        dialog.add_response("confirm", &confirm_label);
+        dialog.set_response_appearance("confirm", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("confirm"));
+        dialog.set_close_response("cancel");
+
+        dialog.connect_response(
+            Some("confirm"),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                #[strong]
+                window,
+                move |_, _| {
+                    let Some(target) = targets.get(target_dropdown.selected() as usize) else {
+                        return;
+                    };
+                    let target_id = target.connection_id();
+                    let path = path.clone();
+                    let window = window.clone();
+                    glib::spawn_future_local(glib::clone!(
+                        #[weak]
+                        slf,
+                        async move {
+                            let msg = match slf.copy_server(&path, &target_id, remove_source).await
+                            {
+                                Ok(()) if remove_source => gettext("Server successfully moved."),
+                                Ok(()) => gettext("Server successfully copied."),
+                                Err(err) => {
+                                    error!("Failed to copy server {path}: {err:?}");
+                                    gettext("Failed to copy the server.")
+                                }
+                            };
+                            slf.toast_or_parentless_dialog(window.as_ref(), &msg);
+                        }
+                    ));
+                }
+            ),
+        );
+        dialog.present(window.as_ref());
+    }
+
+    fn move_connection_to_workspace_via_dialog(&self, target: Option<&glib::Variant>) {
+        debug!("app.move-connection-to-workspace: {:?}", target);
+
+        let Some(connection_id) = target.and_then(glib::Variant::str).map(ToString::to_string)
+        else {
+            warn!("Invalid connection ID passed to app.move-connection-to-workspace. Ignoring.");
+            return;
+        };
+        let Some(settings) = self.settings() else {
+            return;
+        };
+
+        let window = self.active_window();
+        let workspaces = settings.workspaces();
+        if workspaces.is_empty() {
+            self.toast_or_parentless_dialog(
+                window.as_ref(),
+                &gettext("There are no workspaces yet. Add them in the preferences."),
+            );
+            return;
+        }
+
+        let no_workspace = gettext("No Workspace");
+        let names = iter::once(no_workspace.as_str())
+            .chain(workspaces.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+        let workspace_dropdown = gtk::DropDown::from_strings(&names);
+        if let Some(current) = settings.connection_workspace(&connection_id) {
+            if let Some(i) = workspaces.iter().position(|w| *w == current) {
+                workspace_dropdown.set_selected(i as u32 + 1);
+            }
+        }
+
+        let dialog = adw::AlertDialog::builder()
+            .heading(gettext("Move to Workspace"))
+            .body(gettext(
+                "Choose the workspace the connection should be shown in.",
+            ))
+            .extra_child(&workspace_dropdown)
+            .build();
+        dialog.add_response("cancel", &gettext("Cancel"));
+        dialog.add_response("move", &gettext("Move"));
+        dialog.set_response_appearance("move", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("move"));
+        dialog.set_close_response("cancel");
+
+        dialog.connect_response(Some("move"), move |_, _| {
+            let workspace = match workspace_dropdown.selected() {
+                0 => None,
+                i => workspaces.get(i as usize - 1).map(String::as_str),
+            };
+            settings.set_connection_workspace(&connection_id, workspace);
+        });
+        dialog.present(window.as_ref());
+    }
+
+    fn set_connection_load_on_demand(&self, target: Option<&glib::Variant>) {
+        debug!("app.set-connection-load-on-demand: {:?}", target);
+
+        let Some((connection_id, on_demand)) = target.and_then(<(String, bool)>::from_variant)
+        else {
+            warn!("Invalid target passed to app.set-connection-load-on-demand. Ignoring.");
+            return;
+        };
+        let Some(settings) = self.settings() else {
+            return;
+        };
+        settings.set_loaded_on_demand(&connection_id, on_demand);
+
+        // Connections that are no longer loaded on demand are expected to be loaded right away.
+        if !on_demand {
+            if let Some(connection) = self.connection(&connection_id) {
+                glib::spawn_future_local(async move { connection.load().await });
+            }
+        }
+    }
+
+    fn edit_external_tools_via_dialog(&self, target: Option<&glib::Variant>) {
+        debug!("app.edit-external-tools: {:?}", target);
+
+        let Some(connection_id) = target.and_then(glib::Variant::str).map(ToString::to_string)
+        else {
+            warn!("Invalid connection ID passed to app.edit-external-tools. Ignoring.");
+            return;
+        };
+        let (Some(settings), Some(connection)) = (self.settings(), self.connection(&connection_id))
+        else {
+            return;
+        };
+
+        let window = self.active_window();
+        glib::spawn_future_local(async move {
+            let tools = settings.external_tools(&connection_id);
+            if let Some(tools) =
+                edit_external_tools(&connection.title(), &tools, window.as_ref()).await
+            {
+                settings.set_external_tools(&connection_id, &tools);
+            }
+        });
+    }
+
+    fn edit_appearance_via_dialog(&self, target: Option<&glib::Variant>) {
+        debug!("app.edit-appearance: {:?}", target);
+
+        let Some(path) = target.and_then(glib::Variant::str).map(ToString::to_string) else {
+            warn!("Invalid path passed to app.edit-appearance. Ignoring.");
+            return;
+        };
+        let Some(settings) = self.settings() else {
+            return;
+        };
+
+        let window = self.active_window();
+        glib::spawn_future_local(async move {
+            if let Some(appearance) =
+                edit_appearance(&settings.appearance(&path), window.as_ref()).await
+            {
+                settings.set_appearance(&path, &appearance);
+            }
+        });
+    }
+
+    fn edit_server_tags_via_dialog(&self, target: Option<&glib::Variant>) {
+        debug!("app.edit-server-tags: {:?}", target);
+
+        let Some((path, title)) = target.and_then(<(String, String)>::from_variant) else {
+            warn!("Invalid target passed to app.edit-server-tags. Ignoring.");
+            return;
+        };
+        let Some(settings) = self.settings() else {
+            return;
+        };
+
+        let window = self.active_window();
+        glib::spawn_future_local(async move {
+            let tags = settings.server_tags().remove(&path).unwrap_or_default();
+            if let Some(tags) = edit_server_tags(&title, &tags, window.as_ref()).await {
+                settings.set_server_tags(&path, &tags);
+            }
+        });
+    }
+
+    /// Opens the URL of an external tool with the default application for it. Inside of Flatpak
+    /// this goes through the OpenURI portal.
+    fn open_external_tool(&self, target: Option<&glib::Variant>) {
+        debug!("app.open-external-tool: {:?}", target);
+
+        let Some(url) = target.and_then(glib::Variant::str).map(ToString::to_string) else {
+            warn!("Invalid URL passed to app.open-external-tool. Ignoring.");
+            return;
+        };
+
+        let window = self.active_window();
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move {
+                if let Err(err) = gtk::UriLauncher::new(&url)
+                    .launch_future(window.as_ref())
+                    .await
+                {
+                    warn!("Failed to open {url}: {err}");
+                    slf.toast_or_parentless_dialog(
+                        window.as_ref(),
+                        &gettext_f("Failed to open {url}.", &[("url", &url)]),
+                    );
+                }
+            }
+        ));
+    }
+
+    fn toast_or_parentless_dialog(&self, window: Option<&gtk::Window>, msg: &str) {
+        match window.and_then(|w| w.downcast_ref::<FieldMonitorWindow>()) {
+            Some(window) => window.toast(msg),
+            None => self.show_parentless_ok_dialog(msg),
+        }
+    }
+
+    fn auth_connection_via_dialog(&self, target: Option<&glib::Variant>) {
+        debug!("app.auth-connection: {:?}", target);
+        let imp = self.imp();
+
+        let Some(connection_id) = target.and_then(glib::Variant::str).map(ToString::to_string)
+        else {
+            warn!("Invalid connection ID target passed to app.auth-connection. Ignoring.");
+            return;
+        };
+        let Some(connection) = imp
+            .connections
+            .borrow()
+            .as_ref()
+            .and_then(|m| m.get(&*connection_id).cloned())
+        else {
+            warn!("Connection passed to app.auth-connection not found. Ignoring.");
+            return;
+        };
+
+        let window = self.active_window();
+        let dialog = FieldMonitorAuthenticateConnectionDialog::new(self, connection, &[]);
+        let msg = gettext("Authentication successfully updated");
+
+        self.show_toast_or_parentless_dialog_on_signal(
+            &dialog,
+            "auth-finished",
+            window.clone(),
             msg,
         );
 
         dialog.present(window.as_ref());
     }
 
-    pub async fn connect_to_server(&self, path: &str, adapter_id: &str) -> Option<()> {
+    fn activate_connect_to_server(
+        &self,
+        action_name: &str,
+        parameter: Option<&glib::Variant>,
+        new_session: bool,
+    ) {
+        let Some((path, adapter_id)) = parameter.and_then(<(String, String)>::from_variant) else {
+            warn!("Invalid parameters passed to app.{action_name}. Ignoring.");
+            return;
+        };
+        if self.target_busy(&path) {
+            warn!("Connection to {path} still pending. Action ignored.");
+            return;
+        }
+        let pending_guard = self.be_busy_with(&path);
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = app)]
+            self,
+            async move {
+                app.connect_to_server(&path, &adapter_id, new_session).await;
+                drop(pending_guard);
+            }
+        ));
+    }
+
+    /// Connects to a server. If the server is already open in the current window, the existing
+    /// view is focused instead, unless `new_session` is set, multiple sessions are enabled in the
+    /// settings and the provider supports them.
+    pub async fn connect_to_server(
+        &self,
+        path: &str,
+        adapter_id: &str,
+        new_session: bool,
+    ) -> Option<()> {
         let imp = self.imp();
         let mut window = self
             .active_window()
@@ -662,11 +1658,14 @@ impl FieldMonitorApplication {
             .map(Result::unwrap)
             .unwrap_or_else(|| self.open_new_window());
 
+        let new_session =
+            new_session || self.settings().as_ref().unwrap().allow_multiple_sessions();
+
         // TODO: We could also check all windows for the connection, not just the open one, but
         //       this is probably better? That way the user CAN still connect twice to a server
         //       if they really want to.
         // If already open in current window: Focus and select instead.
-        if window.focus_connection_view(path, adapter_id) {
+        if !new_session && window.focus_connection_view(path, adapter_id) {
             return Some(());
         }
 
@@ -678,6 +1677,14 @@ impl FieldMonitorApplication {
         )
         .await?;
 
+        if new_session
+            && !loader.supports_multiple_sessions()
+            && window.focus_connection_view(path, adapter_id)
+        {
+            window.toast(&gettext("This server only supports one session at a time"));
+            return Some(());
+        }
+
         // If this setting is enabled open a new window to place the view into.
         if self.settings().as_ref().unwrap().open_in_new_window() {
             window = self.open_new_window();
@@ -694,6 +1701,72 @@ impl FieldMonitorApplication {
         Some(())
     }
 
+    /// Opens the sessions configured for kiosk mode fullscreen, one window per session, spread
+    /// across all monitors, and disables everything that is not needed to watch them.
+    async fn start_kiosk(&self) {
+        let imp = self.imp();
+        let Some(settings) = self.settings() else {
+            return;
+        };
+        let sessions = settings.kiosk_sessions();
+        if sessions.is_empty() {
+            warn!("Kiosk mode is enabled, but no sessions are configured.");
+            return;
+        }
+
+        imp.kiosk.set(true);
+        for action in KIOSK_DISABLED_ACTIONS {
+            if let Some(action) = self
+                .lookup_action(action)
+                .and_downcast::<gio::SimpleAction>()
+            {
+                action.set_enabled(false);
+            }
+        }
+        self.remove_accels();
+        self.add_accels();
+
+        let monitors = gdk::Display::default().map(|display| display.monitors());
+        let mut unused_window = self.active_window().and_downcast::<FieldMonitorWindow>();
+        for (index, (path, adapter_id)) in sessions.into_iter().enumerate() {
+            let window = unused_window
+                .take()
+                .unwrap_or_else(|| self.open_new_window());
+
+            let Some(loader) = ConnectionLoader::load_server(
+                imp.connections.borrow(),
+                Some(window.upcast_ref()),
+                &path,
+                Some(self.clone()),
+            )
+            .await
+            else {
+                warn!("Could not open kiosk session for {path}.");
+                unused_window = Some(window);
+                continue;
+            };
+
+            window.set_kiosk(true);
+            window.open_connection_view(
+                &path,
+                &adapter_id,
+                &loader.server_title(),
+                &loader.connection_title(),
+                loader,
+            );
+
+            let monitor = monitors
+                .as_ref()
+                .filter(|monitors| monitors.n_items() > 0)
+                .and_then(|monitors| monitors.item(index as u32 % monitors.n_items()))
+                .and_downcast::<gdk::Monitor>();
+            match monitor {
+                Some(monitor) => window.fullscreen_on_monitor(&monitor),
+                None => window.fullscreen(),
+            }
+        }
+    }
+
     pub async fn perform_connection_action(
         &self,
         is_server: bool,
@@ -714,17 +1787,61 @@ impl FieldMonitorApplication {
         .await?;
 
         let action = loader.action(action_id)?;
+        let action_label = loader
+            .actions()
+            .into_iter()
+            .find(|(id, _)| id == action_id)
+            .map_or_else(|| action_id.to_string(), |(_, label)| label.into_owned());
         debug!("executing action...");
-        let should_reload = action
-            .execute(
+        let failure = Rc::new(RefCell::new(None));
+        let should_reload = observe_action_failures(
+            action.execute(
                 window.clone().as_ref(),
                 window
                     .and_downcast::<FieldMonitorWindow>()
                     .map(|w| w.toast_overlay().clone())
                     .as_ref(),
-            )
-            .await;
+            ),
+            glib::clone!(
+                #[strong]
+                failure,
+                move |message: &str| *failure.borrow_mut() = Some(message.to_string())
+            ),
+        )
+        .await;
         debug!("action executed");
+        let title = if is_server {
+            loader.server_title()
+        } else {
+            loader.connection_title()
+        };
+        let failure = failure.take();
+        self.audit(AuditEntry::new(
+            AuditEvent::ServerAction,
+            path,
+            &title,
+            failure.is_none(),
+            Some(action_label.clone()),
+        ));
+        if let Some(message) = failure {
+            self.notify_event_sinks(SinkEvent::new(
+                SinkEventKind::ActionFailed,
+                path,
+                &title,
+                Some(format!("{action_label}: {message}")),
+            ));
+            self.queue_failed_action(FailedAction {
+                is_server,
+                path: path.to_string(),
+                action_id: action_id.to_string(),
+                action_label,
+                title,
+                message,
+                time: glib::DateTime::now_local().ok(),
+            });
+        } else {
+            self.dismiss_failed_action(path, action_id);
+        }
         if should_reload {
             debug!("action executed: asked to reload");
             self.reload_connection(&loader.connection_id()).await;
@@ -732,6 +1849,44 @@ impl FieldMonitorApplication {
         Some(())
     }
 
+    /// Copies the server at `path` into the connection `target_connection_id`, which must be of
+    /// the same provider. If the target is the connection of the server, the server is
+    /// duplicated. If `remove_source` is set, the server is then removed from its connection.
+    pub async fn copy_server(
+        &self,
+        path: &str,
+        target_connection_id: &str,
+        remove_source: bool,
+    ) -> anyhow::Result<()> {
+        let _busy = self.be_busy();
+        let mut server_path = path.split('/').map(ToString::to_string).collect::<Vec<_>>();
+        let source_id = server_path.remove(0);
+
+        let source = self
+            .connection(&source_id)
+            .ok_or_else(|| anyhow!("connection {source_id} not found"))?;
+        let target = self
+            .connection(target_connection_id)
+            .ok_or_else(|| anyhow!("connection {target_connection_id} not found"))?;
+        let provider = source.provider();
+        if !provider.supports_copying_servers() {
+            return Err(anyhow!("provider {} can not copy servers", provider.tag()));
+        }
+
+        let source_config = source.with_configuration(|c| c.explicit_clone());
+        let target_config = target.with_configuration(|c| c.explicit_clone());
+        let target_config = provider
+            .copy_server(&server_path, &source_config, target_config)
+            .await?;
+        self.save_connection(target_config, true).await?;
+
+        if remove_source && source_id != target_connection_id {
+            let source_config = provider.remove_server(&server_path, source_config).await?;
+            self.save_connection(source_config, true).await?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn connection_providers(
         &self,
     ) -> impl IntoIterator<Item = Rc<Box<dyn ConnectionProvider>>> {
@@ -754,6 +1909,40 @@ impl FieldMonitorApplication {
         )
     }
 
+    /// Returns the IDs of connections that still have secrets stored, but no longer exist on
+    /// disk. Connections of other workspaces are considered existing, even if not loaded.
+    pub async fn orphaned_secret_connection_ids(&self) -> anyhow::Result<Vec<String>> {
+        let secret_manager = self.imp().secret_manager.borrow().as_ref().unwrap().clone();
+        let mut existing = HashSet::new();
+        let mut dir = read_dir(self.connections_dir().await).await?;
+        while let Some(dir_entry) = dir.next().await {
+            let path = dir_entry?.path();
+            if path.extension().map(|s| s.to_string_lossy()) == Some(Cow::Borrowed("yaml")) {
+                if let Some(stem) = path.file_stem() {
+                    existing.insert(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+        Ok(secret_manager
+            .connection_ids()
+            .await?
+            .into_iter()
+            .filter(|connection_id| {
+                !existing.contains(connection_id) && connection_id != KEY_SECRET_CONNECTION_ID
+            })
+            .collect())
+    }
+
+    /// Removes all stored secrets of the given connections.
+    pub async fn remove_connection_secrets(&self, connection_ids: &[String]) -> anyhow::Result<()> {
+        let secret_manager = self.imp().secret_manager.borrow().as_ref().unwrap().clone();
+        for connection_id in connection_ids {
+            info!("Removing secrets of connection {connection_id}...");
+            secret_manager.clear_all(connection_id).await?;
+        }
+        Ok(())
+    }
+
     /// Returns an iterator of all currently known connections.
     pub fn connections(&self) -> impl IntoIterator<Item = ConnectionInstance> {
         let brw = self.imp().connections.borrow();
@@ -784,13 +1973,23 @@ impl FieldMonitorApplication {
     /// Updates or adds a new configuration.
     /// When done, the signal connection-updated is emitted.
     /// If the connection provider was not found, the connection is ignored.
+    pub async fn update_connection(&self, connection: DualScopedConnectionConfiguration) {
+        self.add_or_update_connection(connection, true).await;
+    }
+
+    /// Like [`Self::update_connection`], but only loads a newly added connection via its provider
+    /// if `load_new` is set. Returns the instance, if it was added or updated.
     #[allow(clippy::await_holding_refcell_ref)]
     /// TODO with Rust 1.81 replace with:
     //#[expect(
     //    clippy::await_holding_refcell_ref,
     //    reason = "OK because we explicitly drop. See known problems of lint."
     //)]
-    pub async fn update_connection(&self, connection: DualScopedConnectionConfiguration) {
+    async fn add_or_update_connection(
+        &self,
+        connection: DualScopedConnectionConfiguration,
+        load_new: bool,
+    ) -> Option<ConnectionInstance> {
         let _busy = self.be_busy();
         debug!("adding connection {}", connection.session().id());
 
@@ -805,7 +2004,7 @@ impl FieldMonitorApplication {
                 "unknown connection provider tag {}",
                 connection.session().tag()
             );
-            return;
+            return None;
         };
 
         let instance = match entry {
@@ -817,7 +2016,14 @@ impl FieldMonitorApplication {
             }
             None => {
                 drop(brw);
-                let instance = ConnectionInstance::new(connection, provider).await;
+                let load_on_demand = self
+                    .settings()
+                    .is_some_and(|settings| settings.is_loaded_on_demand(&connection_id));
+                let instance = if load_on_demand || !load_new {
+                    ConnectionInstance::new_unloaded(connection, provider)
+                } else {
+                    ConnectionInstance::new(connection, provider).await
+                };
                 let mut brw_mut = imp.connections.borrow_mut();
                 let connections = brw_mut.as_mut().unwrap();
                 connections.insert(connection_id.clone(), instance.clone());
@@ -827,27 +2033,39 @@ impl FieldMonitorApplication {
         assert_eq!(&connection_id, instance.connection_id().as_str());
 
         self.emit_by_name::<()>("connection-updated", &[&instance]);
+        Some(instance)
     }
 
-    async fn update_connection_by_file(&self, path: &PathBuf) -> anyhow::Result<()> {
+    async fn update_connection_by_file(
+        &self,
+        path: &PathBuf,
+        load_new: bool,
+    ) -> anyhow::Result<Option<ConnectionInstance>> {
         let _busy = self.be_busy();
         let connection_id = path
             .file_stem()
             .ok_or_else(|| anyhow!("Connection file had no filename."))?
             .to_string_lossy();
-        let content = read_to_string(path).await?;
+        // Remembered before reading, so a change while reading is detected as a conflict.
+        self.imp().config_writer.remember(path);
+        let content = self
+            .decode_connection_file(read_to_string(path).await?)
+            .await?;
         let saved_config: SavedConnectionConfiguration = serde_yaml::from_str(&content)?;
         let secret_manager = self.imp().secret_manager.borrow().as_ref().unwrap().clone();
-        self.update_connection(DualScopedConnectionConfiguration::new_unified(
-            ConnectionConfiguration::new_existing(
-                connection_id.into_owned(),
-                saved_config.tag,
-                saved_config.config,
-                secret_manager,
-            ),
-        ))
-        .await;
-        Ok(())
+        Ok(self
+            .add_or_update_connection(
+                DualScopedConnectionConfiguration::new_unified(
+                    ConnectionConfiguration::new_existing(
+                        connection_id.into_owned(),
+                        saved_config.tag,
+                        saved_config.config,
+                        secret_manager,
+                    ),
+                ),
+                load_new,
+            )
+            .await)
     }
 
     /// Removes a connection (or does nothing if the connection was not added before).
@@ -858,6 +2076,9 @@ impl FieldMonitorApplication {
         if let Some(map) = brw.as_mut() {
             if map.remove(connection_id).is_some() {
                 if from_disk {
+                    if let Some(settings) = self.settings() {
+                        settings.set_connection_workspace(connection_id, None);
+                    }
                     let connection_id = connection_id.to_string();
                     glib::spawn_future_local(glib::clone!(
                         #[strong(rename_to=slf)]
@@ -869,6 +2090,10 @@ impl FieldMonitorApplication {
                             info!("Removing connection {connection_id} from disk...");
                             let mut filename = slf.connections_dir().await;
                             filename.push(format!("{}.yaml", connection_id));
+                            // A pending save must not recreate the file after it was removed.
+                            let writer = &slf.imp().config_writer;
+                            writer.discard(&filename);
+                            writer.flush().await;
                             remove_file(filename).await.ok();
                         }
                     ));
@@ -879,11 +2104,25 @@ impl FieldMonitorApplication {
     }
 
     /// Reloads all connections. I/O errors and config deserialization errors are logged but ignored.
+    ///
+    /// All connections are listed as soon as their configuration is read. They are then loaded
+    /// via their providers concurrently, errors of individual connections are shown on their
+    /// info pages.
     pub async fn reload_connections(&self) {
-        let _busy = self.be_busy();
-        debug!("reloading connections");
-        self.imp().set_loading_connection(true);
+        let added = {
+            let _busy = self.be_busy();
+            debug!("reloading connections");
+            self.imp().set_loading_connection(true);
+            self.remove_all_connections();
+            let added = self.add_connections_from_disk().await;
+            self.imp().set_loading_connection(false);
+            added
+        };
+        self.load_added_connections(added).await;
+        debug!("reloading connections done");
+    }
 
+    fn remove_all_connections(&self) {
         // Remove already loaded connections
         let connections_to_remove = {
             let connections_brw = self.imp().connections.borrow();
@@ -899,7 +2138,57 @@ impl FieldMonitorApplication {
         for connection_id in connections_to_remove.into_iter() {
             self.remove_connection(&connection_id, false);
         }
+    }
+
+    /// Loads connections of the active workspace that were skipped on startup, because
+    /// only connections of the active workspace are loaded.
+    pub async fn load_workspace_connections(&self) {
+        let added = {
+            let _busy = self.be_busy();
+            debug!("loading connections of active workspace");
+            self.imp().set_loading_connection(true);
+            let added = self.add_connections_from_disk().await;
+            self.imp().set_loading_connection(false);
+            added
+        };
+        self.load_added_connections(added).await;
+    }
+
+    /// Loads the given connections via their providers, all at the same time. Connections
+    /// that are loaded on demand are skipped.
+    async fn load_added_connections(&self, connections: Vec<ConnectionInstance>) {
+        let settings = self.settings();
+        join_all(
+            connections
+                .iter()
+                .filter(|connection| {
+                    !settings.as_ref().is_some_and(|settings| {
+                        settings.is_loaded_on_demand(&connection.connection_id())
+                    })
+                })
+                .map(ConnectionInstance::load),
+        )
+        .await;
+    }
+
+    /// Whether a connection should be loaded from disk now.
+    fn should_load_connection(&self, connection_id: &str) -> bool {
+        if self.connection(connection_id).is_some() {
+            return false;
+        }
+        match self.settings() {
+            Some(settings) if settings.load_active_workspace_only() => {
+                settings.is_in_active_workspace(connection_id)
+            }
+            _ => true,
+        }
+    }
 
+    /// Adds all connections from disk that were not added yet, without loading them via their
+    /// providers. Connections of inactive workspaces are skipped, if configured.
+    async fn add_connections_from_disk(&self) -> Vec<ConnectionInstance> {
+        let added = RefCell::new(Vec::new());
+        self.imp().config_writer.flush().await;
         match read_dir(self.connections_dir().await).await {
             Ok(dir) => {
                 dir.for_each_concurrent(5, |dir_entry_res| async {
@@ -914,13 +2203,25 @@ impl FieldMonitorApplication {
                                 );
                                 return;
                             }
+                            let connection_id = path
+                                .file_stem()
+                                .map(|s| s.to_string_lossy().into_owned())
+                                .unwrap_or_default();
+                            if !self.should_load_connection(&connection_id) {
+                                debug!("skipped connection file {}", path.display());
+                                return;
+                            }
                             debug!("processing connection file {}", path.display());
-                            if let Err(err) = self.update_connection_by_file(&path.into()).await {
-                                error!(
-                                    "Failed to read connection {}: {}",
-                                    dir_entry.file_name().to_string_lossy(),
-                                    err
-                                );
+                            match self.update_connection_by_file(&path.into(), false).await {
+                                Ok(Some(instance)) => added.borrow_mut().push(instance),
+                                Ok(None) => {}
+                                Err(err) => {
+                                    error!(
+                                        "Failed to read connection {}: {}",
+                                        dir_entry.file_name().to_string_lossy(),
+                                        err
+                                    );
+                                }
                             }
                         }
                         Err(err) => {
@@ -934,8 +2235,81 @@ impl FieldMonitorApplication {
                 error!("Failed to read connections settings directory: {err}");
             }
         }
-        debug!("reloading connections done");
-        self.imp().set_loading_connection(false);
+        added.into_inner()
+    }
+
+    /// Watches the connections directory, so that connection files changed by other programs,
+    /// eg. file synchronization clients, are reloaded and removed ones are removed.
+    async fn watch_connections_dir(&self) {
+        let dir = gio::File::for_path(self.connections_dir().await);
+        let monitor = match dir
+            .monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)
+        {
+            Ok(monitor) => monitor,
+            Err(err) => {
+                warn!("failed to watch the connections directory: {err}");
+                return;
+            }
+        };
+        monitor.connect_changed(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            move |_, file, other_file, event| {
+                let (changed, removed) = match event {
+                    gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::MovedIn => {
+                        (Some(file), None)
+                    }
+                    gio::FileMonitorEvent::Renamed => (other_file, Some(file)),
+                    gio::FileMonitorEvent::Deleted | gio::FileMonitorEvent::MovedOut => {
+                        (None, Some(file))
+                    }
+                    _ => return,
+                };
+                if let Some(path) = removed.and_then(|file| file.path()) {
+                    slf.on_connection_file_removed(&path);
+                }
+                if let Some(path) = changed.and_then(|file| file.path()) {
+                    slf.on_connection_file_changed(&path);
+                }
+            }
+        ));
+        self.imp().connections_monitor.replace(Some(monitor));
+    }
+
+    fn on_connection_file_changed(&self, path: &Path) {
+        let Some(connection_id) = connection_id_of_file(path) else {
+            return;
+        };
+        // Our own writes are already known.
+        if self.imp().config_writer.is_known(path) {
+            return;
+        }
+        if self.connection(&connection_id).is_none() && !self.should_load_connection(&connection_id)
+        {
+            return;
+        }
+        info!("connection file of {connection_id} was changed by another program, reloading");
+        glib::spawn_future_local(glib::clone!(
+            #[strong(rename_to = slf)]
+            self,
+            async move {
+                slf.reload_connection(&connection_id).await;
+            }
+        ));
+    }
+
+    fn on_connection_file_removed(&self, path: &Path) {
+        let Some(connection_id) = connection_id_of_file(path) else {
+            return;
+        };
+        if path.exists() {
+            return;
+        }
+        self.imp().config_writer.forget(path);
+        if self.connection(&connection_id).is_some() {
+            info!("connection file of {connection_id} was removed by another program");
+            self.remove_connection(&connection_id, false);
+        }
     }
 
     /// Reloads a single connections.
@@ -943,6 +2317,7 @@ impl FieldMonitorApplication {
         let _busy = self.be_busy();
         debug!("reloading connection {id}");
 
+        self.imp().config_writer.flush().await;
         let file_path = self.connections_dir().await.join(format!("{id}.yaml"));
         if !file_path.exists() {
             warn!("refusing to reload {id}: connection file does no longer exist");
@@ -950,7 +2325,7 @@ impl FieldMonitorApplication {
         }
 
         debug!("processing connection file {}", file_path.display());
-        if let Err(err) = self.update_connection_by_file(&file_path).await {
+        if let Err(err) = self.update_connection_by_file(&file_path, true).await {
             error!(
                 "Failed to read connection {:?}: {}",
                 file_path.file_name(),
@@ -971,57 +2346,149 @@ impl FieldMonitorApplication {
         save_now: bool,
     ) -> anyhow::Result<Option<ConnectionInstance>> {
         let _busy = self.be_busy();
-        let mut filename = self.connections_dir().await;
-
-        let c_persistent = connection.persistent_mut();
+        self.write_connection(connection.persistent_mut()).await?;
+
+        if save_now {
+            let connection_id = connection.session().id().to_string();
+            self.update_connection(connection).await;
+            match self.connection(&connection_id) {
+                None => {
+                    warn!("connection was not updated properly after save.");
+                    Ok(None)
+                }
+                Some(connection_instance) => Ok(Some(connection_instance)),
+            }
+        } else {
+            self.update_connection_eventually(connection);
+            Ok(None)
+        }
+    }
 
+    /// Stores the secrets of a connection and writes its configuration file. The file is
+    /// written with the [`ConfigWriter`], so saves in quick succession are combined.
+    async fn write_connection(
+        &self,
+        c_persistent: &mut ConnectionConfiguration,
+    ) -> anyhow::Result<()> {
+        let mut filename = self.connections_dir().await;
         filename.push(format!("{}.yaml", c_persistent.id()));
         let config = c_persistent.save().await?;
 
-        let file_existed_before = filename.exists();
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&filename)
-            .await?;
-
-        match serde_yaml::to_string(&SavedConnectionConfiguration {
+        let content = serde_yaml::to_string(&SavedConnectionConfiguration {
             tag: c_persistent.tag().to_string(),
             config,
-        }) {
-            Ok(value) => match file.write_all(value.as_bytes()).await {
-                Ok(()) => {
-                    if save_now {
-                        let connection_id = connection.session().id().to_string();
-                        self.update_connection(connection).await;
-                        match self.connection(&connection_id) {
-                            None => {
-                                warn!("connection was not updated properly after save.");
-                                Ok(None)
-                            }
-                            Some(connection_instance) => Ok(Some(connection_instance)),
-                        }
-                    } else {
-                        self.update_connection_eventually(connection);
-                        Ok(None)
-                    }
-                }
-                Err(err) => {
-                    if !file_existed_before {
-                        remove_file(filename).await.ok();
-                    }
-                    Err(err.into())
-                }
-            },
+        })?;
+        let content = self.encode_connection_file(content).await?;
+        self.imp()
+            .config_writer
+            .write(filename, content)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Loads the key for encrypting connection files from the keyring. If there is none yet
+    /// and `create` is set, a new key is created.
+    async fn config_key(&self, create: bool) -> anyhow::Result<Option<ConfigKey>> {
+        if let Some(key) = self.imp().config_key.borrow().clone() {
+            return Ok(Some(key));
+        }
+        let _lock = self.imp().config_key_lock.lock().await;
+        // Another caller may have loaded or created the key while waiting for the lock.
+        if let Some(key) = self.imp().config_key.borrow().clone() {
+            return Ok(Some(key));
+        }
+        let secret_manager = self.imp().secret_manager.borrow().as_ref().unwrap().clone();
+        let key = match ConfigKey::load(secret_manager.as_ref().as_ref()).await? {
+            Some(key) => key,
+            None if create => {
+                info!("Creating key for encrypting connection files...");
+                ConfigKey::create(secret_manager.as_ref().as_ref()).await?
+            }
+            None => return Ok(None),
+        };
+        self.imp().config_key.replace(Some(key.clone()));
+        Ok(Some(key))
+    }
+
+    /// Encrypts the content of a connection file, if connection files are to be encrypted.
+    async fn encode_connection_file(&self, content: String) -> anyhow::Result<String> {
+        if !self.settings().is_some_and(|s| s.encrypt_connections()) {
+            return Ok(content);
+        }
+        let key = self.config_key(true).await?;
+        key.expect("the key is created if missing")
+            .encrypt(&content)
+    }
+
+    /// Decrypts the content of a connection file, if it is encrypted.
+    async fn decode_connection_file(&self, content: String) -> anyhow::Result<String> {
+        if !config_crypt::is_encrypted(&content) {
+            return Ok(content);
+        }
+        match self.config_key(false).await? {
+            Some(key) => key.decrypt(&content),
+            None => Err(anyhow!(
+                "the connection file is encrypted, but the key is missing from the keyring"
+            )),
+        }
+    }
+
+    /// Writes all connection files again, encrypted or not as configured. Files of connections
+    /// that are not loaded are included.
+    async fn rewrite_connection_files(&self) {
+        let _busy = self.be_busy();
+        // Created up front, the files are encoded concurrently.
+        if self.settings().is_some_and(|s| s.encrypt_connections()) {
+            if let Err(err) = self.config_key(true).await {
+                error!("Failed to create key for encrypting connection files: {err}");
+                return;
+            }
+        }
+        let writer = &self.imp().config_writer;
+        writer.flush().await;
+        let mut dir = match read_dir(self.connections_dir().await).await {
+            Ok(dir) => dir,
             Err(err) => {
-                if !file_existed_before {
-                    remove_file(filename).await.ok();
-                }
-                Err(err.into())
+                error!("Failed to read connections directory: {err}");
+                return;
+            }
+        };
+        let mut paths = Vec::new();
+        while let Some(dir_entry) = dir.next().await {
+            match dir_entry {
+                Ok(dir_entry) => paths.push(PathBuf::from(dir_entry.path())),
+                Err(err) => error!("Failed to read connections directory: {err}"),
             }
         }
+        paths.retain(|path| connection_id_of_file(path).is_some());
+
+        join_all(paths.into_iter().map(|path| async move {
+            writer.remember(&path);
+            let result = async {
+                let content = self
+                    .decode_connection_file(read_to_string(&path).await?)
+                    .await?;
+                let content = self.encode_connection_file(content).await?;
+                writer.write(path.clone(), content).await?;
+                anyhow::Ok(())
+            };
+            if let Err(err) = result.await {
+                error!(
+                    "Failed to rewrite connection file {}: {err}",
+                    path.display()
+                );
+            }
+        }))
+        .await;
+    }
+}
+
+/// The ID of the connection stored in the file at `path`, if it is a connection file.
+fn connection_id_of_file(path: &Path) -> Option<String> {
+    if path.extension()? != "yaml" {
+        return None;
     }
+    Some(path.file_stem()?.to_string_lossy().into_owned())
 }
 
 #[derive(Serialize, Deserialize)]
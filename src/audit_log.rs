@@ -0,0 +1,169 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Local audit log of connections and server actions.
+//!
+//! Entries are stored as JSON lines in the user data directory, one object per event.
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+
+use gettextrs::gettext;
+use glib::user_data_dir;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditEvent {
+    Connected,
+    ConnectionFailed,
+    Disconnected,
+    Closed,
+    ServerAction,
+}
+
+impl AuditEvent {
+    pub fn label(&self) -> String {
+        match self {
+            AuditEvent::Connected => gettext("Connected"),
+            AuditEvent::ConnectionFailed => gettext("Connection Failed"),
+            AuditEvent::Disconnected => gettext("Disconnected"),
+            AuditEvent::Closed => gettext("Connection Closed"),
+            AuditEvent::ServerAction => gettext("Server Action"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Time of the event as an ISO 8601 timestamp.
+    pub time: String,
+    /// Name of the local user.
+    pub user: String,
+    pub event: AuditEvent,
+    /// Path of the server or connection the event is about.
+    pub target: String,
+    /// Title of the server or connection at the time of the event.
+    pub title: String,
+    pub success: bool,
+    /// The action that was performed or the error that occurred.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(
+        event: AuditEvent,
+        target: &str,
+        title: &str,
+        success: bool,
+        detail: Option<String>,
+    ) -> Self {
+        Self {
+            time: glib::DateTime::now_local()
+                .and_then(|time| time.format_iso8601())
+                .map(Into::into)
+                .unwrap_or_default(),
+            user: glib::user_name().to_string_lossy().into_owned(),
+            event,
+            target: target.to_string(),
+            title: title.to_string(),
+            success,
+            detail,
+        }
+    }
+
+    pub fn date_time(&self) -> Option<glib::DateTime> {
+        glib::DateTime::from_iso8601(&self.time, None).ok()
+    }
+}
+
+fn log_path() -> PathBuf {
+    user_data_dir()
+        .join("field-monitor")
+        .join("audit-log.jsonl")
+}
+
+/// Appends an entry to the log.
+pub fn append(entry: &AuditEntry) -> anyhow::Result<()> {
+    let path = log_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Reads all entries of the log, oldest first. Lines that can not be parsed are skipped.
+pub fn read_entries() -> anyhow::Result<Vec<AuditEntry>> {
+    let content = match fs::read_to_string(log_path()) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                warn!("skipping invalid audit log entry: {err}");
+                None
+            }
+        })
+        .collect())
+}
+
+/// Removes entries older than `retention_days` days. Entries are kept forever if it is 0.
+pub fn prune(retention_days: u32) -> anyhow::Result<()> {
+    if retention_days == 0 {
+        return Ok(());
+    }
+    let cutoff = glib::DateTime::now_local()?.add_days(-(retention_days as i32))?;
+    let entries = read_entries()?;
+    let kept: Vec<_> = entries
+        .iter()
+        .filter(|entry| entry.date_time().map_or(true, |time| time >= cutoff))
+        .collect();
+    if kept.len() == entries.len() {
+        return Ok(());
+    }
+
+    let mut content = String::new();
+    for entry in kept {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    let path = log_path();
+    let tmp_path = path.with_extension("jsonl.tmp");
+    fs::write(&tmp_path, content)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+/// Removes all entries.
+pub fn clear() -> anyhow::Result<()> {
+    match fs::remove_file(log_path()) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,159 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Local history of whether servers were online, as reported when their connection was polled.
+//!
+//! The history is kept in memory and written to a JSON file in the user data directory shortly
+//! after it changed. Only a bounded number of samples is kept per server.
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use glib::user_data_dir;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// Samples kept per server. With the minimum interval this covers at least a day.
+const MAX_SAMPLES: usize = 288;
+
+/// An unchanged status is only recorded again after this many seconds.
+const MIN_SAMPLE_INTERVAL_SECS: i64 = 300;
+
+/// Seconds to wait for more changes before writing the history.
+const SAVE_DELAY_SECS: u32 = 10;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sample {
+    /// Time of the sample as a Unix timestamp.
+    pub time: i64,
+    pub online: bool,
+}
+
+impl Sample {
+    pub fn date_time(&self) -> Option<glib::DateTime> {
+        glib::DateTime::from_unix_local(self.time).ok()
+    }
+}
+
+thread_local! {
+    static HISTORY: RefCell<Option<HashMap<String, Vec<Sample>>>> = const { RefCell::new(None) };
+    static SAVE_PENDING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn history_path() -> PathBuf {
+    user_data_dir()
+        .join("field-monitor")
+        .join("availability-history.json")
+}
+
+fn load() -> HashMap<String, Vec<Sample>> {
+    let content = match fs::read_to_string(history_path()) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return HashMap::new(),
+        Err(err) => {
+            warn!("failed to read availability history: {err}");
+            return HashMap::new();
+        }
+    };
+    serde_json::from_str(&content).unwrap_or_else(|err| {
+        warn!("discarding invalid availability history: {err}");
+        HashMap::new()
+    })
+}
+
+fn with_history<R>(f: impl FnOnce(&mut HashMap<String, Vec<Sample>>) -> R) -> R {
+    HISTORY.with_borrow_mut(|history| f(history.get_or_insert_with(load)))
+}
+
+fn save() -> anyhow::Result<()> {
+    let content = with_history(|history| serde_json::to_string(history))?;
+    let path = history_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, content)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+fn schedule_save() {
+    if SAVE_PENDING.replace(true) {
+        return;
+    }
+    glib::timeout_add_seconds_local_once(SAVE_DELAY_SECS, write_pending_now);
+}
+
+/// Writes the history immediately if a write is scheduled.
+pub fn write_pending_now() {
+    if SAVE_PENDING.replace(false) {
+        if let Err(err) = save() {
+            warn!("failed to write availability history: {err}");
+        }
+    }
+}
+
+/// Records the status of the server at `path`. Nothing is recorded if the status is unknown,
+/// or if it did not change since a recent sample.
+///
+/// Returns whether the server went offline, that is it was last recorded as online.
+pub fn record(path: &str, is_online: Option<bool>) -> bool {
+    let Some(online) = is_online else {
+        return false;
+    };
+    let Ok(now) = glib::DateTime::now_local() else {
+        return false;
+    };
+    let now = now.to_unix();
+    let (changed, went_offline) = with_history(|history| {
+        let samples = history.entry(path.to_string()).or_default();
+        let was_online = samples.last().map(|last| last.online);
+        if let Some(last) = samples.last() {
+            if last.online == online && now - last.time < MIN_SAMPLE_INTERVAL_SECS {
+                return (false, false);
+            }
+        }
+        samples.push(Sample { time: now, online });
+        if samples.len() > MAX_SAMPLES {
+            samples.drain(..samples.len() - MAX_SAMPLES);
+        }
+        (true, was_online == Some(true) && !online)
+    });
+    if changed {
+        schedule_save();
+    }
+    went_offline
+}
+
+/// The recorded samples of the server at `path`, oldest first.
+pub fn samples(path: &str) -> Vec<Sample> {
+    with_history(|history| history.get(path).cloned().unwrap_or_default())
+}
+
+/// The time the server at `path` was last recorded as online, if ever.
+pub fn last_seen_online(path: &str) -> Option<glib::DateTime> {
+    with_history(|history| {
+        history
+            .get(path)?
+            .iter()
+            .rev()
+            .find(|sample| sample.online)
+            .and_then(Sample::date_time)
+    })
+}
@@ -0,0 +1,103 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Encryption of connection files at rest.
+//!
+//! Encrypted files start with [`HEADER`], followed by the nonce and the ciphertext, base64
+//! encoded. They are encrypted with ChaCha20-Poly1305 using a single key, which is kept in the
+//! keyring like the passwords of connections.
+use anyhow::anyhow;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use gtk::glib;
+use secure_string::SecureString;
+
+use libfieldmonitor::ManagesSecrets;
+
+const HEADER: &str = "# Field Monitor encrypted connection v1\n";
+/// Connection ID the key is stored under in the keyring. It is not a valid connection ID, so
+/// it never belongs to a connection.
+pub const KEY_SECRET_CONNECTION_ID: &str = "__field-monitor";
+const KEY_SECRET_FIELD: &str = "connection-file-key";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+pub fn is_encrypted(content: &str) -> bool {
+    content.starts_with(HEADER)
+}
+
+#[derive(Clone)]
+pub struct ConfigKey(Key);
+
+impl ConfigKey {
+    /// Loads the key from the keyring, if one was created before.
+    pub async fn load(secrets: &dyn ManagesSecrets) -> anyhow::Result<Option<Self>> {
+        let Some(encoded) = secrets
+            .lookup(KEY_SECRET_CONNECTION_ID, KEY_SECRET_FIELD)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let bytes = glib::base64_decode(encoded.unsecure());
+        if bytes.len() != KEY_LEN {
+            return Err(anyhow!("the connection file key in the keyring is invalid"));
+        }
+        Ok(Some(Self(*Key::from_slice(&bytes))))
+    }
+
+    /// Generates a new key and stores it in the keyring.
+    pub async fn create(secrets: &dyn ManagesSecrets) -> anyhow::Result<Self> {
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        secrets
+            .store(
+                KEY_SECRET_CONNECTION_ID,
+                KEY_SECRET_FIELD,
+                SecureString::from(glib::base64_encode(&key).as_str()),
+            )
+            .await?;
+        Ok(Self(key))
+    }
+
+    pub fn encrypt(&self, content: &str) -> anyhow::Result<String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let mut data = nonce.to_vec();
+        data.extend(
+            ChaCha20Poly1305::new(&self.0)
+                .encrypt(&nonce, content.as_bytes())
+                .map_err(|_| anyhow!("failed to encrypt the connection file"))?,
+        );
+        Ok(format!("{HEADER}{}\n", glib::base64_encode(&data)))
+    }
+
+    /// Decrypts the content of a file for which [`is_encrypted`] is true.
+    pub fn decrypt(&self, content: &str) -> anyhow::Result<String> {
+        let encoded = content
+            .strip_prefix(HEADER)
+            .ok_or_else(|| anyhow!("the connection file is not encrypted"))?;
+        let data = glib::base64_decode(encoded.trim());
+        if data.len() < NONCE_LEN {
+            return Err(anyhow!("the encrypted connection file is truncated"));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plain = ChaCha20Poly1305::new(&self.0)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                anyhow!("failed to decrypt the connection file, it is corrupted or the key changed")
+            })?;
+        Ok(String::from_utf8(plain)?)
+    }
+}
@@ -0,0 +1,272 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Writing of connection configuration files.
+//!
+//! Writes are debounced per file, so saving a connection repeatedly in quick succession only
+//! writes it once. Different files are written in parallel on worker threads. Files are
+//! replaced atomically: the content is written to a temporary file and synced to disk before it
+//! is renamed over the old file. A crash or kill during a save therefore leaves either the old
+//! or the new configuration, never a partially written one.
+//!
+//! The writer also remembers the modification time of every file it read or wrote. A file
+//! whose modification time differs was changed by another program: it is not overwritten, and
+//! changes reported by the file monitor can be told apart from our own writes.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::future::Future;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+use futures::channel::oneshot;
+use futures::future::join_all;
+use gettextrs::gettext;
+use gtk::{gio, glib};
+use log::{debug, warn};
+
+/// Time to wait for further changes to a file before it is written.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+type Waiter = oneshot::Sender<io::Result<()>>;
+
+#[derive(Default)]
+struct PendingWrite {
+    /// Content to write next, if the file changed since the last write started.
+    content: Option<String>,
+    /// Notified once the content they were waiting for has been written.
+    waiters: Vec<Waiter>,
+}
+
+#[derive(Default)]
+struct Inner {
+    pending: RefCell<HashMap<PathBuf, PendingWrite>>,
+    /// Modification times of the files as last read or written by us.
+    known_mtimes: RefCell<HashMap<PathBuf, SystemTime>>,
+}
+
+#[derive(Clone, Default)]
+pub struct ConfigWriter(Rc<Inner>);
+
+impl ConfigWriter {
+    /// Schedules writing `content` to the file at `path`. The returned future resolves once
+    /// `content`, or content scheduled later for the same file, was written.
+    ///
+    /// Fails if the file was changed by another program since it was last read.
+    pub fn write(
+        &self,
+        path: PathBuf,
+        content: String,
+    ) -> impl Future<Output = io::Result<()>> + 'static {
+        let (sender, receiver) = oneshot::channel();
+        let mut pending = self.0.pending.borrow_mut();
+        let is_scheduled = pending.contains_key(&path);
+        let entry = pending.entry(path.clone()).or_default();
+        entry.content = Some(content);
+        entry.waiters.push(sender);
+        drop(pending);
+
+        if !is_scheduled {
+            glib::spawn_future_local(self.clone().run(path));
+        }
+        async move {
+            receiver
+                .await
+                .unwrap_or_else(|_| Err(io::Error::other("the write was discarded")))
+        }
+    }
+
+    /// Drops a scheduled write to `path`, eg. because the file is about to be removed. A write
+    /// that already started is not interrupted, use [`Self::flush`] to wait for it.
+    pub fn discard(&self, path: &Path) {
+        if let Some(pending) = self.0.pending.borrow_mut().get_mut(path) {
+            pending.content = None;
+        }
+        self.0.known_mtimes.borrow_mut().remove(path);
+    }
+
+    /// Waits until all scheduled writes are done.
+    pub async fn flush(&self) {
+        let receivers = self
+            .0
+            .pending
+            .borrow_mut()
+            .values_mut()
+            .map(|pending| {
+                let (sender, receiver) = oneshot::channel();
+                pending.waiters.push(sender);
+                receiver
+            })
+            .collect::<Vec<_>>();
+        join_all(receivers).await;
+    }
+
+    /// Writes all scheduled content immediately, blocking the main thread. This is meant for
+    /// shutdown, where the main loop no longer runs the scheduled writes.
+    pub fn write_pending_now(&self) {
+        for (path, pending) in self.0.pending.borrow_mut().iter_mut() {
+            if let Some(content) = pending.content.take() {
+                let expected_mtime = self.known_mtime(path);
+                let result = write_atomically(path, &content, expected_mtime).map(|_| ());
+                if let Err(err) = &result {
+                    warn!("failed to write {}: {err}", path.display());
+                }
+                for waiter in pending.waiters.drain(..) {
+                    waiter.send(clone_result(&result)).ok();
+                }
+            }
+        }
+    }
+
+    /// Remembers the current modification time of the file at `path`, after it was read.
+    pub fn remember(&self, path: &Path) {
+        match modified(path) {
+            Ok(mtime) => {
+                self.0
+                    .known_mtimes
+                    .borrow_mut()
+                    .insert(path.to_path_buf(), mtime);
+            }
+            Err(err) => warn!(
+                "failed to get modification time of {}: {err}",
+                path.display()
+            ),
+        }
+    }
+
+    /// Forgets the file at `path`, after it was removed by another program.
+    pub fn forget(&self, path: &Path) {
+        self.0.known_mtimes.borrow_mut().remove(path);
+    }
+
+    /// Whether the file at `path` is as it was last read or written by us, or is currently being
+    /// written by us.
+    pub fn is_known(&self, path: &Path) -> bool {
+        if self.0.pending.borrow().contains_key(path) {
+            return true;
+        }
+        match (self.known_mtime(path), modified(path)) {
+            (Some(known), Ok(current)) => known == current,
+            _ => false,
+        }
+    }
+
+    fn known_mtime(&self, path: &Path) -> Option<SystemTime> {
+        self.0.known_mtimes.borrow().get(path).copied()
+    }
+
+    /// Writes the file at `path` until no more content is scheduled for it.
+    async fn run(self, path: PathBuf) {
+        glib::timeout_future(DEBOUNCE).await;
+        loop {
+            let (content, waiters) = {
+                let mut pending = self.0.pending.borrow_mut();
+                let Some(entry) = pending.get_mut(&path) else {
+                    return;
+                };
+                if entry.content.is_none() && entry.waiters.is_empty() {
+                    pending.remove(&path);
+                    return;
+                }
+                (entry.content.take(), std::mem::take(&mut entry.waiters))
+            };
+
+            let result = match content {
+                Some(content) => {
+                    debug!("writing {}", path.display());
+                    let expected_mtime = self.known_mtime(&path);
+                    let write_path = path.clone();
+                    let result = gio::spawn_blocking(move || {
+                        write_atomically(&write_path, &content, expected_mtime)
+                    })
+                    .await
+                    .unwrap_or_else(|_| Err(io::Error::other("writing the file panicked")));
+                    result.map(|mtime| {
+                        self.0.known_mtimes.borrow_mut().insert(path.clone(), mtime);
+                    })
+                }
+                None => Ok(()),
+            };
+            if let Err(err) = &result {
+                warn!("failed to write {}: {err}", path.display());
+            }
+            for waiter in waiters {
+                waiter.send(clone_result(&result)).ok();
+            }
+        }
+    }
+}
+
+fn clone_result(result: &io::Result<()>) -> io::Result<()> {
+    match result {
+        Ok(()) => Ok(()),
+        Err(err) => Err(io::Error::new(err.kind(), err.to_string())),
+    }
+}
+
+fn modified(path: &Path) -> io::Result<SystemTime> {
+    fs::metadata(path)?.modified()
+}
+
+/// Replaces the file at `path` with `content`, see the module documentation. If
+/// `expected_mtime` is set, the file must still have that modification time, otherwise it was
+/// changed by another program and is left alone.
+///
+/// Returns the modification time of the new file.
+fn write_atomically(
+    path: &Path,
+    content: &str,
+    expected_mtime: Option<SystemTime>,
+) -> io::Result<SystemTime> {
+    if let Some(expected_mtime) = expected_mtime {
+        match modified(path) {
+            Ok(mtime) if mtime != expected_mtime => {
+                return Err(io::Error::other(gettext(
+                    "The connection was changed by another program. It was reloaded, please try again.",
+                )));
+            }
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+    }
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::other("path has no file name"))?;
+    let mut tmp_name = file_name.to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = (|| {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        fs::remove_file(&tmp_path).ok();
+    }
+    result?;
+
+    // The rename itself is only durable once the directory is synced.
+    if let Some(dir) = path.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+    modified(path)
+}
@@ -25,6 +25,8 @@ use libfieldmonitor::connection::ConnectionProviderConstructor;
 pub static CONNECTION_PROVIDERS: &[&dyn ConnectionProviderConstructor] = &[
     #[cfg(feature = "devel")]
     &field_monitor_debug::DebugConnectionProviderConstructor,
+    #[cfg(feature = "devel")]
+    &field_monitor_example::ExampleConnectionProviderConstructor,
     &GenericConnectionProviderConstructor,
     &ProxmoxConnectionProviderConstructor,
     &LibvirtQemuConnectionProviderConstructor,
@@ -30,6 +30,7 @@ use log::{debug, warn};
 
 use libfieldmonitor::adapter::types::Adapter;
 use libfieldmonitor::connection::*;
+use libfieldmonitor::resolve::ReachabilityError;
 
 use crate::application::FieldMonitorApplication;
 use crate::widget::authenticate_connection_dialog::FieldMonitorAuthenticateConnectionDialog;
@@ -39,6 +40,15 @@ enum Entity {
     Server(Box<dyn ServerConnection>),
 }
 
+enum AdapterFailure {
+    /// Authentication failed, the connection was re-authenticated and may be tried again.
+    Reauthed(ConnectionInstance),
+    /// The server could not be reached, the error was not shown yet.
+    Unreachable(ReachabilityError),
+    /// An error was already shown.
+    Shown,
+}
+
 /// Loads connections and gets resources from them. Interactively shows error messages to the user
 /// and short-circuits any failures or not found resources by returning None.
 pub struct ConnectionLoader {
@@ -155,7 +165,7 @@ impl ConnectionLoader {
                     }
                 };
 
-                let mut server: Option<Box<dyn ServerConnection>> = None;
+                let mut server: Option<Box<dyn ServerConnection + Send>> = None;
 
                 for path_part in path_parts {
                     // get subservers
@@ -271,6 +281,24 @@ impl ConnectionLoader {
         self.connection.metadata().title
     }
 
+    /// Whether the provider allows opening more than one session to the same server.
+    pub fn supports_multiple_sessions(&self) -> bool {
+        self.connection.provider().supports_multiple_sessions()
+    }
+
+    /// The accounts of the server (see [`ServerConnection::accounts`]). Empty if this is not
+    /// for a server.
+    pub fn accounts(&self) -> Vec<(String, String)> {
+        match &self.entity {
+            Entity::Server(e) => e
+                .accounts()
+                .into_iter()
+                .map(|(id, name)| (id.into_owned(), name.into_owned()))
+                .collect(),
+            Entity::Connection(_) => vec![],
+        }
+    }
+
     pub fn actions(&self) -> Vec<(Cow<'static, str>, Cow<'static, str>)> {
         match &self.entity {
             Entity::Connection(e) => e.actions(),
@@ -285,20 +313,27 @@ impl ConnectionLoader {
         }
     }
 
+    /// Creates an adapter for the server.
+    /// Success: Ok(Adapter)
+    /// Server not reachable: Err(Some(ReachabilityError)) ( the caller should present this. )
+    /// Other error: Err(None) ( give up; an error is already shown. )
+    /// If `account` is set, the server logs in with that account.
     pub async fn create_adapter(
         &mut self,
         tag: &str,
+        account: Option<&str>,
         try_reauth: bool,
-    ) -> Option<Box<dyn Adapter>> {
+    ) -> Result<Box<dyn Adapter>, Option<ReachabilityError>> {
         debug!("creating adapter");
-        match self.create_adapter_internal(tag, try_reauth).await {
-            Ok(servers_new) => Some(servers_new),
-            Err(None) => None,
-            Err(Some(connection)) => {
+        match self.create_adapter_internal(tag, account, try_reauth).await {
+            Ok(servers_new) => Ok(servers_new),
+            Err(AdapterFailure::Shown) => Err(None),
+            Err(AdapterFailure::Unreachable(err)) => Err(Some(err)),
+            Err(AdapterFailure::Reauthed(connection)) => {
                 // Connection failed and we (potentially) re-authed.
                 // Try recreating and then try again.
                 debug!("recreating self and retrying");
-                *self = Self::do_load_connection(
+                let Some(reloaded) = Self::do_load_connection(
                     true,
                     connection,
                     self.window.as_ref(),
@@ -306,8 +341,16 @@ impl ConnectionLoader {
                     self.app.clone(),
                     false,
                 )
-                .await?;
-                self.create_adapter_internal(tag, false).await.ok()
+                .await
+                else {
+                    return Err(None);
+                };
+                *self = reloaded;
+                match self.create_adapter_internal(tag, account, false).await {
+                    Ok(adapter) => Ok(adapter),
+                    Err(AdapterFailure::Unreachable(err)) => Err(Some(err)),
+                    Err(_) => Err(None),
+                }
             }
         }
     }
@@ -340,36 +383,47 @@ impl ConnectionLoader {
     async fn create_adapter_internal(
         &self,
         tag: &str,
+        account: Option<&str>,
         try_reauth: bool,
-    ) -> Result<Box<dyn Adapter>, Option<ConnectionInstance>> {
-        match &self.entity {
+    ) -> Result<Box<dyn Adapter>, AdapterFailure> {
+        let server = match &self.entity {
             Entity::Connection(_) => panic!("an adapter can only be created for a server"),
-            Entity::Server(e) => match e.create_adapter(tag).await {
-                Ok(adapter) => Ok(adapter),
-                Err(ConnectionError::AuthFailed(_, _)) if try_reauth => {
-                    warn!("auth failed, asking to re-auth");
-                    let connection = Self::handle_auth_needed(
-                        self.connection.clone(),
-                        self.server_path.as_slice(),
-                        self.app.clone(),
-                        self.window.clone(),
-                    )
-                    .await
-                    .unwrap();
-                    debug!("reauth finished");
-                    Err(Some(connection))
-                }
-                Err(ConnectionError::General(msg, details))
-                | Err(ConnectionError::AuthFailed(msg, details)) => {
-                    warn!("failed to load servers: {msg:?} - {details}");
-                    Self::do_show_error(
-                        &gettext("Failed to load or connect to server"),
-                        msg.as_deref(),
-                        self.window.as_ref(),
-                    );
-                    Err(None)
+            Entity::Server(e) => e,
+        };
+        let result = match account {
+            Some(account) => server.create_adapter_for_account(tag, account).await,
+            None => server.create_adapter(tag).await,
+        };
+        match result {
+            Ok(adapter) => Ok(adapter),
+            Err(ConnectionError::AuthFailed(_, _)) if try_reauth => {
+                warn!("auth failed, asking to re-auth");
+                let connection = Self::handle_auth_needed(
+                    self.connection.clone(),
+                    self.server_path.as_slice(),
+                    self.app.clone(),
+                    self.window.clone(),
+                )
+                .await
+                .unwrap();
+                debug!("reauth finished");
+                Err(AdapterFailure::Reauthed(connection))
+            }
+            Err(ConnectionError::General(msg, details))
+            | Err(ConnectionError::AuthFailed(msg, details)) => {
+                warn!("failed to load servers: {msg:?} - {details}");
+                match details.downcast::<ReachabilityError>() {
+                    Ok(err) => Err(AdapterFailure::Unreachable(err)),
+                    Err(_) => {
+                        Self::do_show_error(
+                            &gettext("Failed to load or connect to server"),
+                            msg.as_deref(),
+                            self.window.as_ref(),
+                        );
+                        Err(AdapterFailure::Shown)
+                    }
                 }
-            },
+            }
         }
     }
 
@@ -0,0 +1,420 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Reporting problems: A log of recent messages and prefilled issue reports, from which
+//! anything that identifies the user's servers is removed.
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::env;
+use std::net::Ipv6Addr;
+use std::sync::Mutex;
+
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+use libfieldmonitor::config::VERSION;
+use libfieldmonitor::connection::ConnectionInstance;
+use log::{Level, Log, Metadata, Record};
+
+const ISSUE_URL: &str = "https://github.com/theCapypara/field-monitor/issues/new";
+/// Messages of this level and above are kept for reports, even if they are not printed.
+const RECORDED_LEVEL: Level = Level::Info;
+const MAX_LOG_LINES: usize = 200;
+/// Log lines in a report are limited to this many characters, to stay below the URL length
+/// GitHub accepts.
+const MAX_REPORT_LOG_CHARS: usize = 4000;
+/// Tokens ending in these are file names rather than host names.
+const FILE_EXTENSIONS: &[&str] = &[
+    "rs", "so", "ui", "blp", "xml", "json", "yaml", "yml", "toml", "txt", "log", "png", "svg",
+    "iso", "img", "sh", "py", "desktop", "service", "socket",
+];
+const REDACTED: &str = "[redacted]";
+
+static RECENT_LOG_LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Forwards to the regular logger and records recent messages.
+struct DiagnosticsLogger {
+    inner: Box<dyn Log>,
+}
+
+impl Log for DiagnosticsLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= RECORDED_LEVEL || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= RECORDED_LEVEL {
+            let mut lines = RECENT_LOG_LINES.lock().unwrap();
+            if lines.len() >= MAX_LOG_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(format!(
+                "{} {}: {}",
+                record.level(),
+                record.target(),
+                record.args()
+            ));
+        }
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+/// Sets up logging to stderr, configured with `RUST_LOG`, while recording recent messages
+/// for problem reports.
+pub fn init_logging() {
+    let mut builder = pretty_env_logger::formatted_timed_builder();
+    if let Ok(filters) = env::var("RUST_LOG") {
+        builder.parse_filters(&filters);
+    }
+    let inner = builder.build();
+    let max_level = inner.filter().max(RECORDED_LEVEL.to_level_filter());
+    if log::set_boxed_logger(Box::new(DiagnosticsLogger {
+        inner: Box::new(inner),
+    }))
+    .is_ok()
+    {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Replaces things that identify the user's servers in `text`: the `sensitive` values (eg.
+/// from the configuration), IP addresses, host names and the home directory.
+pub fn redact(text: &str, sensitive: &[&str]) -> String {
+    let mut text = text.to_string();
+    let mut sensitive = sensitive
+        .iter()
+        .map(|value| value.trim())
+        // Short values like ports or flags would redact unrelated parts of the text.
+        .filter(|value| value.len() >= 4)
+        .collect::<Vec<_>>();
+    // Longer values first, so that values containing others are replaced completely.
+    sensitive.sort_unstable_by_key(|value| std::cmp::Reverse(value.len()));
+    for value in sensitive {
+        text = text.replace(value, REDACTED);
+    }
+    if let Some(home) = glib::home_dir().to_str().filter(|home| home.len() > 1) {
+        text = text.replace(home, "~");
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut token = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '%') {
+            token.push(c);
+        } else {
+            result.push_str(&redact_token(&token));
+            token.clear();
+            result.push(c);
+        }
+    }
+    result.push_str(&redact_token(&token));
+    result
+}
+
+/// Redacts `token` if it is an IP address or a host name, optionally followed by a port.
+fn redact_token(token: &str) -> Cow<str> {
+    if is_ipv6(token) {
+        let addr = token.trim_end_matches('.');
+        return Cow::Owned(format!("[ip]{}", &token[addr.len()..]));
+    }
+    if !token.contains(':') {
+        return redact_name(token, false);
+    }
+
+    // Host names and IPv4 addresses followed by a port, eg. `pve:8006`.
+    let parts = token.split(':').collect::<Vec<_>>();
+    let mut redacted = false;
+    let parts = parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| {
+            let before_port = parts.get(i + 1).is_some_and(|next| is_port(next));
+            let part = redact_name(part, before_port);
+            redacted |= matches!(part, Cow::Owned(_));
+            part
+        })
+        .collect::<Vec<_>>();
+    if redacted {
+        Cow::Owned(parts.join(":"))
+    } else {
+        Cow::Borrowed(token)
+    }
+}
+
+/// Redacts `name` if it is an IPv4 address or a host name. Names without a dot are only
+/// considered host names if `single_label` is set, eg. because they are followed by a port.
+fn redact_name(name: &str, single_label: bool) -> Cow<str> {
+    // A trailing dot is usually the end of a sentence.
+    let trimmed = name.trim_end_matches('.');
+    let labels = trimmed.split('.').collect::<Vec<_>>();
+    if labels.iter().any(|label| label.is_empty()) {
+        return Cow::Borrowed(name);
+    }
+    let is_host = if labels.len() < 2 {
+        single_label
+            && trimmed
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && trimmed.chars().any(|c| c.is_ascii_alphabetic())
+            && !trimmed.starts_with('-')
+            && !trimmed.ends_with('-')
+    } else {
+        let tld = labels[labels.len() - 1];
+        tld.len() >= 2
+            && tld.chars().all(|c| c.is_ascii_alphabetic())
+            && !FILE_EXTENSIONS.contains(&tld.to_ascii_lowercase().as_str())
+    };
+    let is_ipv4 = labels.len() == 4 && labels.iter().all(|label| label.parse::<u8>().is_ok());
+    if is_ipv4 {
+        Cow::Owned(format!("[ip]{}", &name[trimmed.len()..]))
+    } else if is_host {
+        Cow::Owned(format!("[host]{}", &name[trimmed.len()..]))
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Whether `token` is an IPv6 address, optionally with a zone index.
+fn is_ipv6(token: &str) -> bool {
+    let addr = token.trim_end_matches('.');
+    let addr = addr.split_once('%').map_or(addr, |(addr, _)| addr);
+    // `::` alone is no address worth redacting, but the separator in paths like `a::b`.
+    addr.chars().any(|c| c.is_ascii_hexdigit()) && addr.parse::<Ipv6Addr>().is_ok()
+}
+
+fn is_port(value: &str) -> bool {
+    (1..=5).contains(&value.len()) && value.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Builds the body of an issue report for `error`, which happened with a connection of the
+/// type `provider`.
+fn report_body(provider: Option<&str>, error: &str, sensitive: &[&str]) -> String {
+    let os = glib::os_info("PRETTY_NAME").map_or_else(|| "unknown".to_string(), Into::into);
+    let mut log_lines = Vec::new();
+    let mut log_len = 0;
+    for line in RECENT_LOG_LINES.lock().unwrap().iter().rev() {
+        let line = redact(line, sensitive);
+        log_len += line.len() + 1;
+        if log_len > MAX_REPORT_LOG_CHARS {
+            break;
+        }
+        log_lines.push(line);
+    }
+    log_lines.reverse();
+
+    format!(
+        "<!-- Please describe what you were doing when the problem occurred. -->\n\n\
+         **Field Monitor version:** {VERSION}\n\
+         **GTK version:** {}.{}.{}\n\
+         **OS:** {os}\n\
+         **Connection type:** {}\n\n\
+         **Error:**\n```\n{}\n```\n\n\
+         <details><summary>Recent log messages</summary>\n\n```\n{}\n```\n</details>\n",
+        gtk::major_version(),
+        gtk::minor_version(),
+        gtk::micro_version(),
+        provider.unwrap_or("unknown"),
+        redact(error, sensitive),
+        log_lines.join("\n"),
+    )
+}
+
+fn issue_url(title: &str, body: &str) -> String {
+    format!(
+        "{ISSUE_URL}?title={}&body={}",
+        glib::Uri::escape_string(title, None, false),
+        glib::Uri::escape_string(body, None, false)
+    )
+}
+
+/// Shows the report of `error` to the user, who can then copy it or open a prefilled issue.
+/// Values in the configuration of `connection` are redacted from it.
+pub fn present_problem_report(
+    parent: &impl IsA<gtk::Widget>,
+    title: &str,
+    connection: Option<&ConnectionInstance>,
+    error: &str,
+) {
+    let provider = connection.and_then(ConnectionInstance::provider_tag);
+    let sensitive = connection
+        .map(|connection| {
+            connection.with_configuration(|config| {
+                let mut values = config.persistent().string_values();
+                values.extend(config.session().string_values());
+                values.push(config.persistent().id());
+                values
+                    .into_iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+            })
+        })
+        .unwrap_or_default();
+    let sensitive = sensitive.iter().map(String::as_str).collect::<Vec<_>>();
+    let title = redact(title, &sensitive);
+    let body = report_body(provider.as_deref(), error, &sensitive);
+
+    let text_view = gtk::TextView::builder()
+        .editable(false)
+        .monospace(true)
+        .wrap_mode(gtk::WrapMode::WordChar)
+        .top_margin(6)
+        .bottom_margin(6)
+        .left_margin(6)
+        .right_margin(6)
+        .build();
+    text_view.buffer().set_text(&body);
+    let scrolled_window = gtk::ScrolledWindow::builder()
+        .child(&text_view)
+        .min_content_height(240)
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .css_classes(["card"])
+        .build();
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("Report Problem"))
+        .body(gettext(
+            "The report below is added to a new issue on GitHub. Host names, IP addresses and the values of the connection settings were removed from it. Please check it for anything else you do not want to share.",
+        ))
+        .extra_child(&scrolled_window)
+        .close_response("cancel")
+        .default_response("open")
+        .build();
+    dialog.add_responses(&[
+        ("cancel", &gettext("Cancel")),
+        ("copy", &gettext("Copy")),
+        ("open", &gettext("Open Issue")),
+    ]);
+    dialog.set_response_appearance("open", adw::ResponseAppearance::Suggested);
+
+    let window = parent.root().and_downcast::<gtk::Window>();
+    let clipboard = parent.clipboard();
+    dialog.connect_response(None, move |_, response| match response {
+        "copy" => clipboard.set_text(&body),
+        "open" => {
+            let url = issue_url(&title, &body);
+            let window = window.clone();
+            glib::spawn_future_local(async move {
+                if let Err(err) = gtk::UriLauncher::new(&url)
+                    .launch_future(window.as_ref())
+                    .await
+                {
+                    log::warn!("Failed to open issue URL: {err}");
+                }
+            });
+        }
+        _ => {}
+    });
+    dialog.present(Some(parent));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_tokens() {
+        for (token, expected) in [
+            ("", ""),
+            ("word", "word"),
+            ("example.com", "[host]"),
+            ("example.com.", "[host]."),
+            ("pve.example.com:8006", "[host]:8006"),
+            ("pve:8006", "[host]:8006"),
+            ("my-pve:22", "[host]:22"),
+            ("192.0.2.1", "[ip]"),
+            ("192.0.2.1.", "[ip]."),
+            ("192.0.2.1:3389", "[ip]:3389"),
+            ("::1", "[ip]"),
+            ("2001:db8::1", "[ip]"),
+            ("2001:db8::1.", "[ip]."),
+            ("fe80::1%eth0", "[ip]"),
+            ("::ffff:192.0.2.1", "[ip]"),
+            // Not host names or addresses.
+            ("::", "::"),
+            ("1.2", "1.2"),
+            ("1.2.3", "1.2.3"),
+            ("main.rs", "main.rs"),
+            ("main.rs:120", "main.rs:120"),
+            ("config.yaml", "config.yaml"),
+            ("12:30:45", "12:30:45"),
+            ("Error:", "Error:"),
+            ("https:", "https:"),
+            ("pve:", "pve:"),
+            ("monitor::application", "monitor::application"),
+            ("100%", "100%"),
+            ("-pve:22", "-pve:22"),
+            ("pve:123456", "pve:123456"),
+        ] {
+            assert_eq!(redact_token(token), expected, "{token}");
+        }
+    }
+
+    #[test]
+    fn redact_text() {
+        for (text, expected) in [
+            (
+                "failed to connect to pve.example.com:8006: timed out",
+                "failed to connect to [host]:8006: timed out",
+            ),
+            (
+                "connecting to [::1]:5900 and [fe80::1%eth0]:22",
+                "connecting to [[ip]]:5900 and [[ip]]:22",
+            ),
+            ("no route to host 2001:db8::1.", "no route to host [ip]."),
+            (
+                "https://pve:8006/api2/json failed",
+                "https://[host]:8006/api2/json failed",
+            ),
+            (
+                "INFO field_monitor::application: loaded main.rs:120 at 12:30:45",
+                "INFO field_monitor::application: loaded main.rs:120 at 12:30:45",
+            ),
+            ("ping 192.0.2.1, then 10.0.0.1.", "ping [ip], then [ip]."),
+        ] {
+            assert_eq!(redact(text, &[]), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn redact_sensitive_values() {
+        assert_eq!(
+            redact(
+                "user admin@pam on office-server-1 with id abc",
+                &["admin@pam", " office-server-1 ", "abc", "admin"]
+            ),
+            "user [redacted] on [redacted] with id abc"
+        );
+    }
+
+    #[test]
+    fn redact_home_dir() {
+        let home = glib::home_dir();
+        let Some(home) = home.to_str().filter(|home| home.len() > 1) else {
+            return;
+        };
+        assert_eq!(
+            redact(&format!("could not open {home}/vm/disk"), &[]),
+            "could not open ~/vm/disk"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Forwarding of connection events to external alerting, in addition to the toasts shown in
+//! the app.
+//!
+//! Selected events are sent as a JSON object to a webhook with a POST request, or written to the
+//! standard input of a command. Failures to deliver an event are only logged.
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use gettextrs::gettext;
+use gtk::{gio, glib};
+use libfieldmonitor::net::{http_client, NetConfig};
+use log::{debug, warn};
+use serde::Serialize;
+use tokio::runtime::Runtime;
+
+use crate::settings::FieldMonitorSettings;
+
+/// Time after which delivering an event to a sink is given up.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SinkEventKind {
+    ServerOffline,
+    ActionFailed,
+    SessionDisconnected,
+}
+
+impl SinkEventKind {
+    pub const ALL: [SinkEventKind; 3] = [
+        SinkEventKind::ServerOffline,
+        SinkEventKind::ActionFailed,
+        SinkEventKind::SessionDisconnected,
+    ];
+
+    /// Name of the event in the settings and in the sent payload.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            SinkEventKind::ServerOffline => "server-offline",
+            SinkEventKind::ActionFailed => "action-failed",
+            SinkEventKind::SessionDisconnected => "session-disconnected",
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            SinkEventKind::ServerOffline => gettext("Server went offline"),
+            SinkEventKind::ActionFailed => gettext("Server action failed"),
+            SinkEventKind::SessionDisconnected => gettext("Session disconnected"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SinkEvent {
+    pub event: SinkEventKind,
+    /// Time of the event as an ISO 8601 timestamp.
+    pub time: String,
+    /// Path of the server or connection the event is about.
+    pub target: String,
+    /// Title of the server or connection at the time of the event.
+    pub title: String,
+    pub detail: Option<String>,
+}
+
+impl SinkEvent {
+    pub fn new(event: SinkEventKind, target: &str, title: &str, detail: Option<String>) -> Self {
+        Self {
+            event,
+            time: glib::DateTime::now_local()
+                .and_then(|time| time.format_iso8601())
+                .map(Into::into)
+                .unwrap_or_default(),
+            target: target.to_string(),
+            title: title.to_string(),
+            detail,
+        }
+    }
+}
+
+enum EventSink {
+    Webhook(String),
+    Command(String),
+}
+
+impl EventSink {
+    fn configured(settings: &FieldMonitorSettings) -> Vec<Self> {
+        let mut sinks = Vec::with_capacity(2);
+        let url = settings.event_webhook_url();
+        if !url.trim().is_empty() {
+            sinks.push(EventSink::Webhook(url.trim().to_string()));
+        }
+        let command = settings.event_command();
+        if !command.trim().is_empty() {
+            sinks.push(EventSink::Command(command.trim().to_string()));
+        }
+        sinks
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            EventSink::Webhook(url) => url,
+            EventSink::Command(command) => command,
+        }
+    }
+
+    async fn deliver(&self, payload: &str) -> anyhow::Result<()> {
+        match self {
+            EventSink::Webhook(url) => post_webhook(url, payload).await,
+            EventSink::Command(command) => run_command(command, payload).await,
+        }
+    }
+}
+
+/// Sends `event` to the configured sinks, if its kind is selected in the settings.
+pub fn dispatch(settings: &FieldMonitorSettings, event: SinkEvent) {
+    if !settings.event_sink_enabled(event.event) {
+        return;
+    }
+    let sinks = EventSink::configured(settings);
+    if sinks.is_empty() {
+        return;
+    }
+    let payload = match serde_json::to_string(&event) {
+        Ok(payload) => payload,
+        Err(err) => {
+            warn!("failed to serialize event for event sinks: {err}");
+            return;
+        }
+    };
+    debug!(
+        "sending {} event to {} sink(s)",
+        event.event.tag(),
+        sinks.len()
+    );
+    glib::spawn_future_local(async move {
+        for sink in sinks {
+            match glib::future_with_timeout(DELIVERY_TIMEOUT, sink.deliver(&payload)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => warn!("failed to send event to {}: {err}", sink.name()),
+                Err(_) => warn!("timed out sending event to {}", sink.name()),
+            }
+        }
+    });
+}
+
+/// Runtime the HTTP client for webhooks runs on, as it does not run on the GLib main loop.
+fn http_runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .expect("failed setting up tokio async runtime")
+    })
+}
+
+/// POSTs `payload` as JSON to the HTTP or HTTPS `url`.
+async fn post_webhook(url: &str, payload: &str) -> anyhow::Result<()> {
+    let client = http_client(&NetConfig::default().with_request_timeout(Some(DELIVERY_TIMEOUT)))?;
+    let request = client
+        .post(url)
+        .header("User-Agent", "Field Monitor")
+        .header("Content-Type", "application/json")
+        .body(payload.to_string());
+    let response = http_runtime().spawn(request.send()).await??;
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(anyhow!("unexpected response: {status}"))
+    }
+}
+
+/// Runs `command`, split like a shell command line, with `payload` on its standard input.
+async fn run_command(command: &str, payload: &str) -> anyhow::Result<()> {
+    let argv = glib::shell_parse_argv(command)?;
+    let argv = argv.iter().map(|arg| arg.as_os_str()).collect::<Vec<_>>();
+    let process = gio::Subprocess::newv(
+        &argv,
+        gio::SubprocessFlags::STDIN_PIPE | gio::SubprocessFlags::STDERR_PIPE,
+    )?;
+    let (_, stderr) = process
+        .communicate_utf8_future(Some(payload.to_string()))
+        .await?;
+    if !process.is_successful() {
+        return Err(anyhow!(
+            "exited with status {}: {}",
+            process.exit_status(),
+            stderr.as_deref().unwrap_or_default().trim()
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Queue of server and connection actions that failed, so they can be retried later.
+//!
+//! The queue only lives as long as the app runs. Failed actions are also recorded in the audit
+//! log, if enabled.
+use std::collections::VecDeque;
+
+use adw::prelude::*;
+use gtk::glib;
+
+/// Number of failed actions that are kept. The oldest ones are dropped first.
+const MAX_FAILED_ACTIONS: usize = 10;
+
+#[derive(Clone, Debug)]
+pub struct FailedAction {
+    pub is_server: bool,
+    /// Path of the server or connection the action was performed on.
+    pub path: String,
+    pub action_id: String,
+    pub action_label: String,
+    /// Title of the server or connection at the time of the failure.
+    pub title: String,
+    /// The error reported by the action.
+    pub message: String,
+    pub time: Option<glib::DateTime>,
+}
+
+impl FailedAction {
+    /// Target of `app.perform-connection-action` to perform the action again.
+    pub fn action_target(&self) -> glib::Variant {
+        (self.is_server, &*self.path, &*self.action_id).to_variant()
+    }
+
+    fn is_same_action(&self, path: &str, action_id: &str) -> bool {
+        self.path == path && self.action_id == action_id
+    }
+}
+
+/// Failed actions, newest first. Each action of a server or connection is only kept once.
+#[derive(Debug, Default)]
+pub struct FailedActions(VecDeque<FailedAction>);
+
+impl FailedActions {
+    pub fn push(&mut self, action: FailedAction) {
+        self.remove(&action.path, &action.action_id);
+        self.0.push_front(action);
+        self.0.truncate(MAX_FAILED_ACTIONS);
+    }
+
+    /// Removes the action, returns whether it was queued.
+    pub fn remove(&mut self, path: &str, action_id: &str) -> bool {
+        let len = self.0.len();
+        self.0
+            .retain(|action| !action.is_same_action(path, action_id));
+        self.0.len() != len
+    }
+
+    pub fn to_vec(&self) -> Vec<FailedAction> {
+        self.0.iter().cloned().collect()
+    }
+}
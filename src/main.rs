@@ -25,12 +25,21 @@ use std::fs::read_dir;
 use std::path::PathBuf;
 
 use self::application::FieldMonitorApplication;
+use self::settings::SettingDisplayRendering;
 
 mod application;
+mod audit_log;
+mod availability_history;
+mod config_crypt;
+mod config_writer;
 mod connection;
 mod connection_loader;
+mod diagnostics;
+mod event_sinks;
+mod failed_actions;
 mod secrets;
 mod settings;
+mod status_poller;
 mod util;
 mod widget;
 
@@ -45,7 +54,7 @@ fn main() -> glib::ExitCode {
         rdw_vnc::gvnc::ffi::vnc_util_set_debug(glib::ffi::GTRUE);
     }
     glib::log_set_default_handler(glib::rust_log_handler);
-    pretty_env_logger::init_timed();
+    diagnostics::init_logging();
 
     // Set up gettext translations
     bindtextdomain(GETTEXT_PACKAGE, LOCALEDIR).expect("Unable to bind the text domain");
@@ -68,6 +77,14 @@ fn main() -> glib::ExitCode {
         }
     }
 
+    // The rendering path can only be chosen before GTK is initialized.
+    SettingDisplayRendering::from(
+        gio::Settings::new(APP_ID)
+            .string("display-rendering")
+            .to_string(),
+    )
+    .apply_to_environment();
+
     // Create a new GtkApplication. The application manages our main loop,
     // application windows, integration with the window manager/compositor, and
     // desktop features such as file opening and single-instance applications.
@@ -37,6 +37,22 @@ impl SecretManager {
         let keyring = oo7::portal::Keyring::load_default().await?;
         Ok(Self { keyring })
     }
+
+    /// Returns the value of `attribute` of all items of this app matching the other attributes.
+    async fn item_attribute_values(
+        &self,
+        attributes: std::collections::HashMap<&str, &str>,
+        attribute: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        let items = self.keyring.search_items(&attributes).await?;
+        let mut values: Vec<String> = items
+            .iter()
+            .filter_map(|item| item.attributes().get(attribute).map(|v| v.to_string()))
+            .collect();
+        values.sort();
+        values.dedup();
+        Ok(values)
+    }
 }
 
 impl ManagesSecrets for SecretManager {
@@ -121,4 +137,28 @@ impl ManagesSecrets for SecretManager {
                 .map_err(Into::into)
         })
     }
+
+    fn connection_ids(&self) -> BoxFuture<anyhow::Result<Vec<String>>> {
+        Box::pin(async move {
+            let mut attributes = std::collections::HashMap::new();
+            attributes.insert("app", APP_ID);
+
+            self.item_attribute_values(attributes, "connection_id")
+                .await
+                .inspect_err(|err| warn!("failed to list connections with secrets: {err}"))
+        })
+    }
+
+    fn fields(&self, connection_id: &str) -> BoxFuture<anyhow::Result<Vec<String>>> {
+        let connection_id = connection_id.to_string();
+        Box::pin(async move {
+            let mut attributes = std::collections::HashMap::new();
+            attributes.insert("app", APP_ID);
+            attributes.insert("connection_id", &connection_id);
+
+            self.item_attribute_values(attributes, "field")
+                .await
+                .inspect_err(|err| warn!("failed to list secrets of {connection_id}: {err}"))
+        })
+    }
 }
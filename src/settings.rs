@@ -19,8 +19,30 @@ use adw::gio;
 use adw::prelude::*;
 use glib::subclass::prelude::*;
 use libfieldmonitor::impl_enum_param_spec;
+use libfieldmonitor::template;
+use log::error;
 use std::cell::Cell;
 use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use crate::event_sinks::SinkEventKind;
+
+const TEMPLATE_VARIABLES_KEY: &str = "template-variables";
+const WORKSPACES_KEY: &str = "workspaces";
+const CONNECTION_WORKSPACES_KEY: &str = "connection-workspaces";
+const LOAD_ON_DEMAND_CONNECTIONS_KEY: &str = "load-on-demand-connections";
+const SNIPPETS_KEY: &str = "snippets";
+const SMART_FOLDERS_KEY: &str = "smart-folders";
+const SERVER_TAGS_KEY: &str = "server-tags";
+const SERVER_VIEW_STATES_KEY: &str = "server-view-states";
+const KIOSK_SESSIONS_KEY: &str = "kiosk-sessions";
+const EXTERNAL_TOOLS_KEY: &str = "external-tools";
+const APPEARANCES_KEY: &str = "appearances";
+const WINDOW_STATES_KEY: &str = "window-states";
+const SESSION_TOOLBAR_KEY: &str = "session-toolbar";
+const SHARED_PRINTERS_KEY: &str = "shared-printers";
+const SHORTCUTS_KEY: &str = "shortcuts";
+const EVENT_SINK_EVENTS_KEY: &str = "event-sink-events";
 
 #[derive(Copy, Clone, Debug, Default)]
 pub enum SettingSharpWindowCorners {
@@ -84,6 +106,525 @@ impl<'a> From<&'a SettingHeaderBarBehavior> for String {
 
 impl_enum_param_spec!(SettingHeaderBarBehavior, String);
 
+#[derive(Copy, Clone, Debug, Default)]
+pub enum SettingTermCursorShape {
+    #[default]
+    Block,
+    Ibeam,
+    Underline,
+}
+
+impl From<String> for SettingTermCursorShape {
+    fn from(value: String) -> Self {
+        match &*value {
+            "ibeam" => SettingTermCursorShape::Ibeam,
+            "underline" => SettingTermCursorShape::Underline,
+            _ => SettingTermCursorShape::Block,
+        }
+    }
+}
+
+impl<'a> From<&'a SettingTermCursorShape> for String {
+    fn from(value: &'a SettingTermCursorShape) -> Self {
+        match value {
+            SettingTermCursorShape::Block => "block",
+            SettingTermCursorShape::Ibeam => "ibeam",
+            SettingTermCursorShape::Underline => "underline",
+        }
+        .to_string()
+    }
+}
+
+impl_enum_param_spec!(SettingTermCursorShape, String);
+
+#[derive(Copy, Clone, Debug, Default)]
+pub enum SettingTermColorScheme {
+    #[default]
+    Auto,
+    Light,
+    Dark,
+    SolarizedLight,
+    SolarizedDark,
+}
+
+impl From<String> for SettingTermColorScheme {
+    fn from(value: String) -> Self {
+        match &*value {
+            "light" => SettingTermColorScheme::Light,
+            "dark" => SettingTermColorScheme::Dark,
+            "solarized-light" => SettingTermColorScheme::SolarizedLight,
+            "solarized-dark" => SettingTermColorScheme::SolarizedDark,
+            _ => SettingTermColorScheme::Auto,
+        }
+    }
+}
+
+impl<'a> From<&'a SettingTermColorScheme> for String {
+    fn from(value: &'a SettingTermColorScheme) -> Self {
+        match value {
+            SettingTermColorScheme::Auto => "auto",
+            SettingTermColorScheme::Light => "light",
+            SettingTermColorScheme::Dark => "dark",
+            SettingTermColorScheme::SolarizedLight => "solarized-light",
+            SettingTermColorScheme::SolarizedDark => "solarized-dark",
+        }
+        .to_string()
+    }
+}
+
+impl_enum_param_spec!(SettingTermColorScheme, String);
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SettingDisplayRendering {
+    #[default]
+    Auto,
+    Gl,
+    Software,
+}
+
+impl SettingDisplayRendering {
+    /// Configures GTK for this rendering path. Must be called before GTK is initialized, since
+    /// GTK reads the environment only once. Renderers chosen by the user in the environment
+    /// take precedence.
+    pub fn apply_to_environment(&self) {
+        if std::env::var_os("GSK_RENDERER").is_some() {
+            return;
+        }
+        match self {
+            SettingDisplayRendering::Auto => {}
+            SettingDisplayRendering::Gl => std::env::set_var("GSK_RENDERER", "ngl"),
+            SettingDisplayRendering::Software => {
+                std::env::set_var("GSK_RENDERER", "cairo");
+                std::env::set_var("GDK_DISABLE", "gl,vulkan,dmabuf");
+            }
+        }
+    }
+}
+
+impl From<String> for SettingDisplayRendering {
+    fn from(value: String) -> Self {
+        match &*value {
+            "gl" => SettingDisplayRendering::Gl,
+            "software" => SettingDisplayRendering::Software,
+            _ => SettingDisplayRendering::Auto,
+        }
+    }
+}
+
+impl<'a> From<&'a SettingDisplayRendering> for String {
+    fn from(value: &'a SettingDisplayRendering) -> Self {
+        match value {
+            SettingDisplayRendering::Auto => "auto",
+            SettingDisplayRendering::Gl => "gl",
+            SettingDisplayRendering::Software => "software",
+        }
+        .to_string()
+    }
+}
+
+impl_enum_param_spec!(SettingDisplayRendering, String);
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SettingScalingFilter {
+    #[default]
+    Default,
+    Nearest,
+    Trilinear,
+}
+
+impl SettingScalingFilter {
+    /// The filter graphical sessions are scaled with, `None` to leave the choice to GTK.
+    pub fn gsk_filter(&self) -> Option<gtk::gsk::ScalingFilter> {
+        match self {
+            SettingScalingFilter::Default => None,
+            SettingScalingFilter::Nearest => Some(gtk::gsk::ScalingFilter::Nearest),
+            SettingScalingFilter::Trilinear => Some(gtk::gsk::ScalingFilter::Trilinear),
+        }
+    }
+}
+
+impl From<String> for SettingScalingFilter {
+    fn from(value: String) -> Self {
+        match &*value {
+            "nearest" => SettingScalingFilter::Nearest,
+            "trilinear" => SettingScalingFilter::Trilinear,
+            _ => SettingScalingFilter::Default,
+        }
+    }
+}
+
+impl<'a> From<&'a SettingScalingFilter> for String {
+    fn from(value: &'a SettingScalingFilter) -> Self {
+        match value {
+            SettingScalingFilter::Default => "default",
+            SettingScalingFilter::Nearest => "nearest",
+            SettingScalingFilter::Trilinear => "trilinear",
+        }
+        .to_string()
+    }
+}
+
+impl_enum_param_spec!(SettingScalingFilter, String);
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SnippetKind {
+    /// Text typed into consoles.
+    #[default]
+    Text,
+    /// Key combinations sent to graphical sessions.
+    Keys,
+}
+
+impl From<String> for SnippetKind {
+    fn from(value: String) -> Self {
+        match &*value {
+            "keys" => SnippetKind::Keys,
+            _ => SnippetKind::Text,
+        }
+    }
+}
+
+impl<'a> From<&'a SnippetKind> for String {
+    fn from(value: &'a SnippetKind) -> Self {
+        match value {
+            SnippetKind::Text => "text",
+            SnippetKind::Keys => "keys",
+        }
+        .to_string()
+    }
+}
+
+/// Online state of the servers collected by a smart folder.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SmartFolderOnlineState {
+    #[default]
+    Any,
+    Online,
+    Offline,
+}
+
+impl From<String> for SmartFolderOnlineState {
+    fn from(value: String) -> Self {
+        match &*value {
+            "online" => SmartFolderOnlineState::Online,
+            "offline" => SmartFolderOnlineState::Offline,
+            _ => SmartFolderOnlineState::Any,
+        }
+    }
+}
+
+impl<'a> From<&'a SmartFolderOnlineState> for String {
+    fn from(value: &'a SmartFolderOnlineState) -> Self {
+        match value {
+            SmartFolderOnlineState::Any => "any",
+            SmartFolderOnlineState::Online => "online",
+            SmartFolderOnlineState::Offline => "offline",
+        }
+        .to_string()
+    }
+}
+
+/// A button of the toolbar shown below the header bar of sessions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SessionToolbarItem {
+    CtrlAltDel,
+    /// Menu with the actions the provider offers for the server, eg. rebooting it.
+    ServerActions,
+    Screenshot,
+    ViewOnly,
+}
+
+impl SessionToolbarItem {
+    /// All items, in the order they are shown.
+    pub const ALL: [Self; 4] = [
+        Self::CtrlAltDel,
+        Self::ServerActions,
+        Self::Screenshot,
+        Self::ViewOnly,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::CtrlAltDel => "ctrl-alt-del",
+            Self::ServerActions => "server-actions",
+            Self::Screenshot => "screenshot",
+            Self::ViewOnly => "view-only",
+        }
+    }
+}
+
+/// An action whose keyboard shortcut can be changed in the preferences.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ConfigurableShortcut {
+    pub action: &'static str,
+    /// Accelerator used unless the user chose another one.
+    pub default: &'static str,
+}
+
+impl ConfigurableShortcut {
+    /// All configurable shortcuts, in the order they are shown.
+    pub const ALL: [Self; 15] = [
+        Self::new("app.new-window", "<Primary>N"),
+        Self::new("window.close", "<Alt>F4"),
+        Self::new("app.preferences", "<Primary>comma"),
+        Self::new("app.reload-connections", "<Primary>R"),
+        Self::new("win.show-help-overlay", "<Primary>question"),
+        Self::new("win.fullscreen", "F11"),
+        Self::new("win.show-sidebar", "<Primary>E"),
+        Self::new("view.close", "<Shift><Primary>W"),
+        Self::new("view.term-copy", "<Shift><Primary>C"),
+        Self::new("view.term-paste", "<Shift><Primary>V"),
+        Self::new("view.term-select-all", "<Shift><Primary>A"),
+        Self::new("view.term-zoom-in", "<Primary>plus"),
+        Self::new("view.term-zoom-out", "<Primary>minus"),
+        Self::new("view.term-zoom-reset", "<Primary>0"),
+        Self::new("view.term-find", "<Shift><Primary>F"),
+    ];
+
+    const fn new(action: &'static str, default: &'static str) -> Self {
+        Self { action, default }
+    }
+}
+
+/// Color of the icon of a connection or server, from the GNOME palette.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum AccentColor {
+    #[default]
+    None,
+    Blue,
+    Teal,
+    Green,
+    Yellow,
+    Orange,
+    Red,
+    Pink,
+    Purple,
+    Slate,
+}
+
+impl AccentColor {
+    pub const ALL: [AccentColor; 10] = [
+        AccentColor::None,
+        AccentColor::Blue,
+        AccentColor::Teal,
+        AccentColor::Green,
+        AccentColor::Yellow,
+        AccentColor::Orange,
+        AccentColor::Red,
+        AccentColor::Pink,
+        AccentColor::Purple,
+        AccentColor::Slate,
+    ];
+
+    /// The style class coloring icons, defined in `style.css`.
+    pub fn css_class(&self) -> Option<&'static str> {
+        match self {
+            AccentColor::None => None,
+            AccentColor::Blue => Some("fm-accent-blue"),
+            AccentColor::Teal => Some("fm-accent-teal"),
+            AccentColor::Green => Some("fm-accent-green"),
+            AccentColor::Yellow => Some("fm-accent-yellow"),
+            AccentColor::Orange => Some("fm-accent-orange"),
+            AccentColor::Red => Some("fm-accent-red"),
+            AccentColor::Pink => Some("fm-accent-pink"),
+            AccentColor::Purple => Some("fm-accent-purple"),
+            AccentColor::Slate => Some("fm-accent-slate"),
+        }
+    }
+}
+
+impl From<String> for AccentColor {
+    fn from(value: String) -> Self {
+        match &*value {
+            "blue" => AccentColor::Blue,
+            "teal" => AccentColor::Teal,
+            "green" => AccentColor::Green,
+            "yellow" => AccentColor::Yellow,
+            "orange" => AccentColor::Orange,
+            "red" => AccentColor::Red,
+            "pink" => AccentColor::Pink,
+            "purple" => AccentColor::Purple,
+            "slate" => AccentColor::Slate,
+            _ => AccentColor::None,
+        }
+    }
+}
+
+impl<'a> From<&'a AccentColor> for String {
+    fn from(value: &'a AccentColor) -> Self {
+        match value {
+            AccentColor::None => "",
+            AccentColor::Blue => "blue",
+            AccentColor::Teal => "teal",
+            AccentColor::Green => "green",
+            AccentColor::Yellow => "yellow",
+            AccentColor::Orange => "orange",
+            AccentColor::Red => "red",
+            AccentColor::Pink => "pink",
+            AccentColor::Purple => "purple",
+            AccentColor::Slate => "slate",
+        }
+        .to_string()
+    }
+}
+
+/// User-chosen icon and color of a connection or server.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Appearance {
+    /// Name of an icon of the icon theme, or the absolute path of an image file. Empty for the
+    /// icon chosen by the provider.
+    pub icon: String,
+    pub color: AccentColor,
+}
+
+impl Appearance {
+    pub fn is_default(&self) -> bool {
+        self.icon.is_empty() && self.color == AccentColor::None
+    }
+
+    /// Whether the icon is an image file instead of a themed icon.
+    pub fn icon_is_file(&self) -> bool {
+        self.icon.starts_with('/')
+    }
+}
+
+/// Navigation state of a window, restored for the window that is opened in its place.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WindowState {
+    /// The page last shown from the sidebar: a connection ID, [`WindowState::PAGE_DASHBOARD`],
+    /// [`WindowState::PAGE_QUICK_CONNECT`] or empty if none was shown yet.
+    pub page: String,
+    /// The connection that was last selected in each workspace ("" for all connections).
+    pub workspace_selections: BTreeMap<String, String>,
+}
+
+impl WindowState {
+    pub const PAGE_DASHBOARD: &'static str = "dashboard";
+    pub const PAGE_QUICK_CONNECT: &'static str = "quick-connect";
+}
+
+/// A user-defined text or keystroke macro that can be sent to sessions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Snippet {
+    pub name: String,
+    pub kind: SnippetKind,
+    /// The text to type, or a space separated list of key combinations such as
+    /// `<Control><Alt>Delete`.
+    pub content: String,
+    /// Keyboard shortcut that sends the snippet, empty if it has none.
+    pub shortcut: String,
+}
+
+/// A virtual group in the sidebar, collecting the servers of all connections that match its
+/// query. Empty criteria match all servers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SmartFolder {
+    pub name: String,
+    /// Tag of the provider of the connections, eg. `proxmox`.
+    pub provider: String,
+    /// Pattern the title of servers must match, case-insensitive. `*` matches any number of
+    /// characters, `?` a single one.
+    pub title_pattern: String,
+    pub online: SmartFolderOnlineState,
+    /// User tag the servers must have.
+    pub tag: String,
+}
+
+impl SmartFolder {
+    /// Whether a server of a connection of the provider `provider_tag` matches the query.
+    pub fn matches(
+        &self,
+        provider_tag: &str,
+        title: &str,
+        is_online: Option<bool>,
+        tags: &[String],
+    ) -> bool {
+        let online_matches = match self.online {
+            SmartFolderOnlineState::Any => true,
+            SmartFolderOnlineState::Online => is_online == Some(true),
+            SmartFolderOnlineState::Offline => is_online == Some(false),
+        };
+        online_matches
+            && (self.provider.is_empty() || self.provider == provider_tag)
+            && (self.tag.is_empty() || tags.iter().any(|tag| tag.eq_ignore_ascii_case(&self.tag)))
+            && (self.title_pattern.trim().is_empty()
+                || glob_matches(
+                    &self.title_pattern.trim().to_lowercase(),
+                    &title.to_lowercase(),
+                ))
+    }
+}
+
+/// Whether `text` matches `pattern`, in which `*` matches any number of characters and `?` a
+/// single one.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    // Position in the pattern after the last `*` and the position in the text it matched up to.
+    let mut backtrack = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((after_star, matched)) => {
+                    p = after_star;
+                    t = matched + 1;
+                    backtrack = Some((after_star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// An external application that servers of a connection can be opened with.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExternalTool {
+    pub name: String,
+    /// URL to open. `${host}` and `${title}` are replaced by the host and title of the server,
+    /// other variables are resolved like template variables.
+    pub url: String,
+}
+
+impl ExternalTool {
+    /// The URL for the server with the given metadata. `None` if it uses a variable that is
+    /// not known, eg. `${host}` for a server without a known host.
+    pub fn url_for(&self, host: Option<&str>, title: &str) -> Option<String> {
+        let complete = Cell::new(true);
+        let url = template::expand_template_with(&self.url, |name| {
+            let value = match name {
+                "host" => host.map(ToString::to_string),
+                "title" => Some(glib::Uri::escape_string(title, None, false).to_string()),
+                _ => template::lookup_variable(name),
+            };
+            complete.set(complete.get() && value.is_some());
+            value
+        });
+        complete.get().then_some(url)
+    }
+}
+
+impl Snippet {
+    /// The text to type into consoles, with `\n` replaced by line breaks.
+    pub fn text(&self) -> String {
+        self.content.replace("\\n", "\n")
+    }
+
+    /// The key combinations to send to graphical sessions.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.content.split_whitespace()
+    }
+}
+
 mod imp {
     use super::*;
 
@@ -99,6 +640,56 @@ mod imp {
         pub header_bar_behavior: RefCell<SettingHeaderBarBehavior>,
         #[property(get, set)]
         pub open_in_new_window: Cell<bool>,
+        #[property(get, set)]
+        pub allow_multiple_sessions: Cell<bool>,
+        #[property(get, set)]
+        pub confirm_close_sessions: Cell<bool>,
+        #[property(get, set)]
+        pub inhibit_suspend: Cell<bool>,
+        #[property(get, set)]
+        pub kiosk_mode: Cell<bool>,
+        #[property(get, set)]
+        pub status_poll_interval: Cell<u32>,
+        /// Name of the active workspace, empty if all connections are shown.
+        #[property(get, set)]
+        pub active_workspace: RefCell<String>,
+        #[property(get, set)]
+        pub load_active_workspace_only: Cell<bool>,
+        #[property(get, set)]
+        pub encrypt_connections: Cell<bool>,
+        #[property(get, set)]
+        pub audit_log_enabled: Cell<bool>,
+        #[property(get, set)]
+        pub audit_log_retention_days: Cell<u32>,
+        /// URL events are POSTed to, empty if no webhook is used.
+        #[property(get, set)]
+        pub event_webhook_url: RefCell<String>,
+        /// Command events are written to, empty if no command is used.
+        #[property(get, set)]
+        pub event_command: RefCell<String>,
+        /// Maximum size of session recordings in MiB, 0 for no limit.
+        #[property(get, set)]
+        pub recording_max_size: Cell<u32>,
+        #[property(get, set)]
+        pub term_scrollback_lines: Cell<u32>,
+        #[property(get, set)]
+        pub term_scrollback_unlimited: Cell<bool>,
+        /// Pango font description of the terminal font, empty for the system monospace font.
+        #[property(get, set)]
+        pub term_font: RefCell<String>,
+        #[property(get, set)]
+        pub term_cursor_shape: RefCell<SettingTermCursorShape>,
+        #[property(get, set)]
+        pub term_color_scheme: RefCell<SettingTermColorScheme>,
+        #[property(get, set)]
+        pub term_paste_protection: Cell<bool>,
+        #[property(get, set)]
+        pub display_rendering: RefCell<SettingDisplayRendering>,
+        /// Maximum number of frames per second graphical sessions are drawn with, 0 for no limit.
+        #[property(get, set)]
+        pub display_max_fps: Cell<u32>,
+        #[property(get, set)]
+        pub display_scaling_filter: RefCell<SettingScalingFilter>,
     }
 
     #[glib::object_subclass]
@@ -132,7 +723,551 @@ impl FieldMonitorSettings {
         settings
             .bind("open-in-new-window", &slf, "open-in-new-window")
             .build();
+        settings
+            .bind("allow-multiple-sessions", &slf, "allow-multiple-sessions")
+            .build();
+        settings
+            .bind("confirm-close-sessions", &slf, "confirm-close-sessions")
+            .build();
+        settings
+            .bind("inhibit-suspend", &slf, "inhibit-suspend")
+            .build();
+        settings.bind("kiosk-mode", &slf, "kiosk-mode").build();
+        settings
+            .bind("status-poll-interval", &slf, "status-poll-interval")
+            .build();
+        settings
+            .bind("active-workspace", &slf, "active-workspace")
+            .build();
+        settings
+            .bind(
+                "load-active-workspace-only",
+                &slf,
+                "load-active-workspace-only",
+            )
+            .build();
+        settings
+            .bind("encrypt-connections", &slf, "encrypt-connections")
+            .build();
+        settings
+            .bind("audit-log-enabled", &slf, "audit-log-enabled")
+            .build();
+        settings
+            .bind("audit-log-retention-days", &slf, "audit-log-retention-days")
+            .build();
+        settings
+            .bind("event-webhook-url", &slf, "event-webhook-url")
+            .build();
+        settings
+            .bind("event-command", &slf, "event-command")
+            .build();
+        settings
+            .bind("recording-max-size", &slf, "recording-max-size")
+            .build();
+        settings
+            .bind("term-scrollback-lines", &slf, "term-scrollback-lines")
+            .build();
+        settings
+            .bind(
+                "term-scrollback-unlimited",
+                &slf,
+                "term-scrollback-unlimited",
+            )
+            .build();
+        settings.bind("term-font", &slf, "term-font").build();
+        settings
+            .bind("term-cursor-shape", &slf, "term-cursor-shape")
+            .build();
+        settings
+            .bind("term-color-scheme", &slf, "term-color-scheme")
+            .build();
+        settings
+            .bind("term-paste-protection", &slf, "term-paste-protection")
+            .build();
+        settings
+            .bind("display-rendering", &slf, "display-rendering")
+            .build();
+        settings
+            .bind("display-max-fps", &slf, "display-max-fps")
+            .build();
+        settings
+            .bind("display-scaling-filter", &slf, "display-scaling-filter")
+            .build();
+
+        template::set_template_variables(settings.get(TEMPLATE_VARIABLES_KEY));
+        settings.connect_changed(Some(TEMPLATE_VARIABLES_KEY), |settings, key| {
+            template::set_template_variables(settings.get(key))
+        });
 
         slf
     }
+
+    /// The user-defined template variables, see [`template`].
+    pub fn template_variables(&self) -> BTreeMap<String, String> {
+        self.settings().unwrap().get(TEMPLATE_VARIABLES_KEY)
+    }
+
+    pub fn set_template_variables(&self, variables: &BTreeMap<String, String>) {
+        self.store(TEMPLATE_VARIABLES_KEY, variables.to_variant());
+    }
+
+    /// The names of all workspaces.
+    pub fn workspaces(&self) -> Vec<String> {
+        self.settings().unwrap().get(WORKSPACES_KEY)
+    }
+
+    /// Adds a workspace, if no workspace with that name exists yet.
+    pub fn add_workspace(&self, name: &str) {
+        let mut workspaces = self.workspaces();
+        if workspaces.iter().any(|w| w == name) {
+            return;
+        }
+        workspaces.push(name.to_string());
+        self.store(WORKSPACES_KEY, workspaces.to_variant());
+    }
+
+    /// Removes a workspace. Its connections no longer belong to any workspace afterwards.
+    pub fn remove_workspace(&self, name: &str) {
+        let mut workspaces = self.workspaces();
+        workspaces.retain(|w| w != name);
+        let mut connection_workspaces = self.connection_workspaces();
+        connection_workspaces.retain(|_, w| w != name);
+
+        if self.active_workspace() == name {
+            self.set_active_workspace(String::new());
+        }
+        self.store(
+            CONNECTION_WORKSPACES_KEY,
+            connection_workspaces.to_variant(),
+        );
+        self.store(WORKSPACES_KEY, workspaces.to_variant());
+    }
+
+    /// The workspace a connection belongs to, if any.
+    pub fn connection_workspace(&self, connection_id: &str) -> Option<String> {
+        self.connection_workspaces().remove(connection_id)
+    }
+
+    pub fn set_connection_workspace(&self, connection_id: &str, workspace: Option<&str>) {
+        let mut connection_workspaces = self.connection_workspaces();
+        match workspace {
+            Some(workspace) => {
+                connection_workspaces.insert(connection_id.to_string(), workspace.to_string())
+            }
+            None => connection_workspaces.remove(connection_id),
+        };
+        self.store(
+            CONNECTION_WORKSPACES_KEY,
+            connection_workspaces.to_variant(),
+        );
+    }
+
+    /// Whether the connection should be shown for the active workspace.
+    pub fn is_in_active_workspace(&self, connection_id: &str) -> bool {
+        let active = self.active_workspace();
+        active.is_empty() || self.connection_workspace(connection_id).as_ref() == Some(&active)
+    }
+
+    /// Calls `f` whenever workspaces are added or removed or connections are moved between them.
+    pub fn connect_workspaces_changed(&self, f: impl Fn(&Self) + 'static) {
+        self.settings().unwrap().connect_changed(
+            None,
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_, key| {
+                    if key == WORKSPACES_KEY || key == CONNECTION_WORKSPACES_KEY {
+                        f(&slf)
+                    }
+                }
+            ),
+        );
+    }
+
+    /// Whether the connection is only loaded once the user requests it.
+    pub fn is_loaded_on_demand(&self, connection_id: &str) -> bool {
+        self.load_on_demand_connections()
+            .iter()
+            .any(|id| id == connection_id)
+    }
+
+    pub fn set_loaded_on_demand(&self, connection_id: &str, on_demand: bool) {
+        let mut connections = self.load_on_demand_connections();
+        connections.retain(|id| id != connection_id);
+        if on_demand {
+            connections.push(connection_id.to_string());
+        }
+        self.store(LOAD_ON_DEMAND_CONNECTIONS_KEY, connections.to_variant());
+    }
+
+    /// Calls `f` whenever connections are set to be loaded on demand or no longer are.
+    pub fn connect_load_on_demand_changed(&self, f: impl Fn(&Self) + 'static) {
+        self.settings().unwrap().connect_changed(
+            Some(LOAD_ON_DEMAND_CONNECTIONS_KEY),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_, _| f(&slf)
+            ),
+        );
+    }
+
+    /// The view preferences last used for the server at `server_path`, by name.
+    pub fn server_view_state(&self, server_path: &str) -> BTreeMap<String, glib::Variant> {
+        self.server_view_states()
+            .remove(server_path)
+            .unwrap_or_default()
+    }
+
+    pub fn set_server_view_state_value(&self, server_path: &str, name: &str, value: glib::Variant) {
+        let mut states = self.server_view_states();
+        states
+            .entry(server_path.to_string())
+            .or_default()
+            .insert(name.to_string(), value);
+        self.store(SERVER_VIEW_STATES_KEY, states.to_variant());
+    }
+
+    /// The navigation state of the window in `slot`. Windows get the lowest slot not used by
+    /// another open window, so the first window always restores the state of the first window.
+    pub fn window_state(&self, slot: usize) -> WindowState {
+        let states: Vec<(String, BTreeMap<String, String>)> =
+            self.settings().unwrap().get(WINDOW_STATES_KEY);
+        states
+            .into_iter()
+            .nth(slot)
+            .map(|(page, workspace_selections)| WindowState {
+                page,
+                workspace_selections,
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn set_window_state(&self, slot: usize, state: &WindowState) {
+        let mut states: Vec<(String, BTreeMap<String, String>)> =
+            self.settings().unwrap().get(WINDOW_STATES_KEY);
+        if states.len() <= slot {
+            states.resize(slot + 1, Default::default());
+        }
+        states[slot] = (state.page.clone(), state.workspace_selections.clone());
+        self.store(WINDOW_STATES_KEY, states.to_variant());
+    }
+
+    /// The buttons of the session toolbar, in the order they are shown. The toolbar is hidden
+    /// if this is empty.
+    pub fn session_toolbar_items(&self) -> Vec<SessionToolbarItem> {
+        let ids: Vec<String> = self.settings().unwrap().get(SESSION_TOOLBAR_KEY);
+        SessionToolbarItem::ALL
+            .into_iter()
+            .filter(|item| ids.iter().any(|id| id == item.id()))
+            .collect()
+    }
+
+    pub fn set_session_toolbar_item(&self, item: SessionToolbarItem, shown: bool) {
+        let mut ids: Vec<String> = self.settings().unwrap().get(SESSION_TOOLBAR_KEY);
+        ids.retain(|id| id != item.id());
+        if shown {
+            ids.push(item.id().to_string());
+        }
+        self.store(SESSION_TOOLBAR_KEY, ids.to_variant());
+    }
+
+    pub fn connect_session_toolbar_changed(&self, f: impl Fn(&Self) + 'static) {
+        self.settings().unwrap().connect_changed(
+            Some(SESSION_TOOLBAR_KEY),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_, _| f(&slf)
+            ),
+        );
+    }
+
+    /// The accelerator of `shortcut`, empty if the user disabled it.
+    pub fn shortcut(&self, shortcut: &ConfigurableShortcut) -> String {
+        let custom: BTreeMap<String, String> = self.settings().unwrap().get(SHORTCUTS_KEY);
+        custom
+            .get(shortcut.action)
+            .cloned()
+            .unwrap_or_else(|| shortcut.default.to_string())
+    }
+
+    pub fn is_shortcut_customized(&self, shortcut: &ConfigurableShortcut) -> bool {
+        let custom: BTreeMap<String, String> = self.settings().unwrap().get(SHORTCUTS_KEY);
+        custom.contains_key(shortcut.action)
+    }
+
+    /// Sets the accelerator of `shortcut`. An empty accelerator disables the shortcut, `None`
+    /// restores the default.
+    pub fn set_shortcut(&self, shortcut: &ConfigurableShortcut, accel: Option<&str>) {
+        let mut custom: BTreeMap<String, String> = self.settings().unwrap().get(SHORTCUTS_KEY);
+        match accel {
+            Some(accel) => custom.insert(shortcut.action.to_string(), accel.to_string()),
+            None => custom.remove(shortcut.action),
+        };
+        self.store(SHORTCUTS_KEY, custom.to_variant());
+    }
+
+    pub fn reset_shortcuts(&self) {
+        self.store(
+            SHORTCUTS_KEY,
+            BTreeMap::<String, String>::new().to_variant(),
+        );
+    }
+
+    pub fn connect_shortcuts_changed(&self, f: impl Fn(&Self) + 'static) {
+        self.settings().unwrap().connect_changed(
+            Some(SHORTCUTS_KEY),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_, _| f(&slf)
+            ),
+        );
+    }
+
+    /// Names of the local printers shared with sessions that support redirecting printers.
+    pub fn shared_printers(&self) -> Vec<String> {
+        self.settings().unwrap().get(SHARED_PRINTERS_KEY)
+    }
+
+    pub fn set_printer_shared(&self, printer: &str, shared: bool) {
+        let mut printers = self.shared_printers();
+        printers.retain(|name| name != printer);
+        if shared {
+            printers.push(printer.to_string());
+        }
+        self.store(SHARED_PRINTERS_KEY, printers.to_variant());
+    }
+
+    /// Whether events of `kind` are sent to the event sinks.
+    pub fn event_sink_enabled(&self, kind: SinkEventKind) -> bool {
+        let events: Vec<String> = self.settings().unwrap().get(EVENT_SINK_EVENTS_KEY);
+        events.iter().any(|event| event == kind.tag())
+    }
+
+    pub fn set_event_sink_enabled(&self, kind: SinkEventKind, enabled: bool) {
+        let mut events: Vec<String> = self.settings().unwrap().get(EVENT_SINK_EVENTS_KEY);
+        events.retain(|event| event != kind.tag());
+        if enabled {
+            events.push(kind.tag().to_string());
+        }
+        self.store(EVENT_SINK_EVENTS_KEY, events.to_variant());
+    }
+
+    /// Server paths and adapter IDs of the sessions opened in kiosk mode.
+    pub fn kiosk_sessions(&self) -> Vec<(String, String)> {
+        self.settings().unwrap().get(KIOSK_SESSIONS_KEY)
+    }
+
+    pub fn set_kiosk_sessions(&self, sessions: &[(String, String)]) {
+        self.store(KIOSK_SESSIONS_KEY, sessions.to_variant());
+    }
+
+    /// The user-defined snippets, in the order they are shown.
+    pub fn snippets(&self) -> Vec<Snippet> {
+        let snippets: Vec<(String, String, String, String)> =
+            self.settings().unwrap().get(SNIPPETS_KEY);
+        snippets
+            .into_iter()
+            .map(|(name, kind, content, shortcut)| Snippet {
+                name,
+                kind: kind.into(),
+                content,
+                shortcut,
+            })
+            .collect()
+    }
+
+    pub fn set_snippets(&self, snippets: &[Snippet]) {
+        let snippets: Vec<_> = snippets
+            .iter()
+            .map(|snippet| {
+                (
+                    snippet.name.clone(),
+                    String::from(&snippet.kind),
+                    snippet.content.clone(),
+                    snippet.shortcut.clone(),
+                )
+            })
+            .collect();
+        self.store(SNIPPETS_KEY, snippets.to_variant());
+    }
+
+    /// Calls `f` whenever snippets are added, changed or removed.
+    pub fn connect_snippets_changed(&self, f: impl Fn(&Self) + 'static) {
+        self.settings().unwrap().connect_changed(
+            Some(SNIPPETS_KEY),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_, _| f(&slf)
+            ),
+        );
+    }
+
+    /// The smart folders, in the order they are configured.
+    pub fn smart_folders(&self) -> Vec<SmartFolder> {
+        let folders: Vec<(String, String, String, String, String)> =
+            self.settings().unwrap().get(SMART_FOLDERS_KEY);
+        folders
+            .into_iter()
+            .map(|(name, provider, title_pattern, online, tag)| SmartFolder {
+                name,
+                provider,
+                title_pattern,
+                online: online.into(),
+                tag,
+            })
+            .collect()
+    }
+
+    pub fn set_smart_folders(&self, folders: &[SmartFolder]) {
+        let folders: Vec<_> = folders
+            .iter()
+            .map(|folder| {
+                (
+                    folder.name.clone(),
+                    folder.provider.clone(),
+                    folder.title_pattern.clone(),
+                    String::from(&folder.online),
+                    folder.tag.clone(),
+                )
+            })
+            .collect();
+        self.store(SMART_FOLDERS_KEY, folders.to_variant());
+    }
+
+    /// Calls `f` whenever smart folders are added, changed or removed.
+    pub fn connect_smart_folders_changed(&self, f: impl Fn(&Self) + 'static) {
+        self.settings().unwrap().connect_changed(
+            Some(SMART_FOLDERS_KEY),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_, _| f(&slf)
+            ),
+        );
+    }
+
+    /// The user tags of all servers, by server path.
+    pub fn server_tags(&self) -> BTreeMap<String, Vec<String>> {
+        self.settings().unwrap().get(SERVER_TAGS_KEY)
+    }
+
+    /// Replaces the user tags of the server at `server_path`. Empty tags are dropped.
+    pub fn set_server_tags(&self, server_path: &str, tags: &[String]) {
+        let mut all_tags = self.server_tags();
+        let tags = tags
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if tags.is_empty() {
+            all_tags.remove(server_path);
+        } else {
+            all_tags.insert(server_path.to_string(), tags);
+        }
+        self.store(SERVER_TAGS_KEY, all_tags.to_variant());
+    }
+
+    /// Calls `f` whenever the tags of a server change.
+    pub fn connect_server_tags_changed(&self, f: impl Fn(&Self) + 'static) {
+        self.settings().unwrap().connect_changed(
+            Some(SERVER_TAGS_KEY),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_, _| f(&slf)
+            ),
+        );
+    }
+
+    /// The external tools configured for a connection, in the order they are shown.
+    pub fn external_tools(&self, connection_id: &str) -> Vec<ExternalTool> {
+        let mut tools: BTreeMap<String, Vec<(String, String)>> =
+            self.settings().unwrap().get(EXTERNAL_TOOLS_KEY);
+        tools
+            .remove(connection_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, url)| ExternalTool { name, url })
+            .collect()
+    }
+
+    pub fn set_external_tools(&self, connection_id: &str, tools: &[ExternalTool]) {
+        let mut all_tools: BTreeMap<String, Vec<(String, String)>> =
+            self.settings().unwrap().get(EXTERNAL_TOOLS_KEY);
+        if tools.is_empty() {
+            all_tools.remove(connection_id);
+        } else {
+            all_tools.insert(
+                connection_id.to_string(),
+                tools
+                    .iter()
+                    .map(|tool| (tool.name.clone(), tool.url.clone()))
+                    .collect(),
+            );
+        }
+        self.store(EXTERNAL_TOOLS_KEY, all_tools.to_variant());
+    }
+
+    /// The appearance of the connection or server at `path`.
+    pub fn appearance(&self, path: &str) -> Appearance {
+        self.appearances()
+            .remove(path)
+            .map(|(icon, color)| Appearance {
+                icon,
+                color: color.into(),
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn set_appearance(&self, path: &str, appearance: &Appearance) {
+        let mut appearances = self.appearances();
+        if appearance.is_default() {
+            appearances.remove(path);
+        } else {
+            appearances.insert(
+                path.to_string(),
+                (appearance.icon.clone(), String::from(&appearance.color)),
+            );
+        }
+        self.store(APPEARANCES_KEY, appearances.to_variant());
+    }
+
+    /// Calls `f` whenever the appearance of a connection or server is changed.
+    pub fn connect_appearances_changed(&self, f: impl Fn(&Self) + 'static) {
+        self.settings().unwrap().connect_changed(
+            Some(APPEARANCES_KEY),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_, _| f(&slf)
+            ),
+        );
+    }
+
+    fn appearances(&self) -> BTreeMap<String, (String, String)> {
+        self.settings().unwrap().get(APPEARANCES_KEY)
+    }
+
+    fn server_view_states(&self) -> BTreeMap<String, BTreeMap<String, glib::Variant>> {
+        self.settings().unwrap().get(SERVER_VIEW_STATES_KEY)
+    }
+
+    fn load_on_demand_connections(&self) -> Vec<String> {
+        self.settings().unwrap().get(LOAD_ON_DEMAND_CONNECTIONS_KEY)
+    }
+
+    fn connection_workspaces(&self) -> BTreeMap<String, String> {
+        self.settings().unwrap().get(CONNECTION_WORKSPACES_KEY)
+    }
+
+    fn store(&self, key: &str, value: glib::Variant) {
+        if let Err(err) = self.settings().unwrap().set(key, value) {
+            error!("failed to store setting {key}: {err}");
+        }
+    }
 }
@@ -0,0 +1,106 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Periodic refreshing of server status, such as resource usage.
+//!
+//! There is a single timer for the whole application. Widgets subscribe to it and are only
+//! asked to refresh while they are mapped, so hidden pages cause no requests.
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use adw::prelude::*;
+use gtk::glib;
+
+use crate::settings::FieldMonitorSettings;
+
+struct Subscriber {
+    widget: glib::WeakRef<gtk::Widget>,
+    callback: Rc<dyn Fn()>,
+}
+
+#[derive(Default)]
+struct Inner {
+    subscribers: RefCell<Vec<Subscriber>>,
+    timer: RefCell<Option<glib::SourceId>>,
+}
+
+#[derive(Clone, Default)]
+pub struct StatusPoller(Rc<Inner>);
+
+impl StatusPoller {
+    /// Creates the poller. It polls with the interval of the `status-poll-interval` setting and
+    /// follows changes to it.
+    pub fn new(settings: &FieldMonitorSettings) -> Self {
+        let slf = Self::default();
+        slf.set_interval(settings.status_poll_interval());
+        let weak = Rc::downgrade(&slf.0);
+        settings.connect_status_poll_interval_notify(move |settings| {
+            if let Some(inner) = weak.upgrade() {
+                StatusPoller(inner).set_interval(settings.status_poll_interval());
+            }
+        });
+        slf
+    }
+
+    /// Calls `callback` on every poll while `widget` is mapped, until `widget` is destroyed.
+    pub fn subscribe(&self, widget: &impl IsA<gtk::Widget>, callback: impl Fn() + 'static) {
+        self.0.subscribers.borrow_mut().push(Subscriber {
+            widget: widget.upcast_ref::<gtk::Widget>().downgrade(),
+            callback: Rc::new(callback),
+        });
+    }
+
+    /// Restarts the timer with an interval of `seconds`. If 0, polling is disabled.
+    fn set_interval(&self, seconds: u32) {
+        if let Some(timer) = self.0.timer.take() {
+            timer.remove();
+        }
+        if seconds == 0 {
+            return;
+        }
+        let weak = Rc::downgrade(&self.0);
+        let timer =
+            glib::timeout_add_local(Duration::from_secs(seconds.into()), move || {
+                match weak.upgrade() {
+                    Some(inner) => {
+                        StatusPoller(inner).poll();
+                        glib::ControlFlow::Continue
+                    }
+                    None => glib::ControlFlow::Break,
+                }
+            });
+        self.0.timer.replace(Some(timer));
+    }
+
+    fn poll(&self) {
+        // Callbacks may subscribe, so they must not run while the list is borrowed.
+        let mut callbacks = Vec::new();
+        self.0.subscribers.borrow_mut().retain(|subscriber| {
+            let Some(widget) = subscriber.widget.upgrade() else {
+                return false;
+            };
+            if widget.is_mapped() {
+                callbacks.push(subscriber.callback.clone());
+            }
+            true
+        });
+        for callback in callbacks {
+            callback();
+        }
+    }
+}
@@ -16,20 +16,85 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 use adw::gdk;
+use gtk::pango;
 use std::cmp::Ordering;
 use vte::prelude::*;
 
+use crate::settings::{FieldMonitorSettings, SettingTermColorScheme, SettingTermCursorShape};
+
 const BLACK: gdk::RGBA = gdk::RGBA::new(0.0, 0.0, 0.0, 1.0);
 const WHITE: gdk::RGBA = gdk::RGBA::new(1.0, 1.0, 1.0, 1.0);
 
-pub fn configure_vte_styling(terminal: &vte::Terminal, style_manager: &adw::StyleManager) {
-    if style_manager.is_dark() {
-        terminal.set_color_foreground(&WHITE);
-        terminal.set_color_background(&BLACK);
+// https://ethanschoonover.com/solarized/
+const SOLARIZED_BASE03: u32 = 0x002b36;
+const SOLARIZED_BASE00: u32 = 0x657b83;
+const SOLARIZED_BASE0: u32 = 0x839496;
+const SOLARIZED_BASE3: u32 = 0xfdf6e3;
+const SOLARIZED_PALETTE: [u32; 16] = [
+    0x073642, 0xdc322f, 0x859900, 0xb58900, 0x268bd2, 0xd33682, 0x2aa198, 0xeee8d5, 0x002b36,
+    0xcb4b16, 0x586e75, 0x657b83, 0x839496, 0x6c71c4, 0x93a1a1, 0xfdf6e3,
+];
+
+/// Applies the terminal preferences and the color scheme to `terminal`. Without `settings` the
+/// colors follow the style of `style_manager`.
+pub fn configure_vte_styling(
+    terminal: &vte::Terminal,
+    style_manager: &adw::StyleManager,
+    settings: Option<&FieldMonitorSettings>,
+) {
+    let color_scheme = settings
+        .map(FieldMonitorSettings::term_color_scheme)
+        .unwrap_or_default();
+    match color_scheme {
+        SettingTermColorScheme::Auto if style_manager.is_dark() => {
+            terminal.set_colors(Some(&WHITE), Some(&BLACK), &[])
+        }
+        SettingTermColorScheme::Auto | SettingTermColorScheme::Light => {
+            terminal.set_colors(Some(&BLACK), Some(&WHITE), &[])
+        }
+        SettingTermColorScheme::Dark => terminal.set_colors(Some(&WHITE), Some(&BLACK), &[]),
+        SettingTermColorScheme::SolarizedLight => {
+            let palette = SOLARIZED_PALETTE.map(rgb);
+            terminal.set_colors(
+                Some(&rgb(SOLARIZED_BASE00)),
+                Some(&rgb(SOLARIZED_BASE3)),
+                &palette.iter().collect::<Vec<_>>(),
+            )
+        }
+        SettingTermColorScheme::SolarizedDark => {
+            let palette = SOLARIZED_PALETTE.map(rgb);
+            terminal.set_colors(
+                Some(&rgb(SOLARIZED_BASE0)),
+                Some(&rgb(SOLARIZED_BASE03)),
+                &palette.iter().collect::<Vec<_>>(),
+            )
+        }
+    }
+
+    let Some(settings) = settings else {
+        return;
+    };
+    terminal.set_scrollback_lines(if settings.term_scrollback_unlimited() {
+        -1
     } else {
-        terminal.set_color_foreground(&BLACK);
-        terminal.set_color_background(&WHITE);
+        settings.term_scrollback_lines().into()
+    });
+    let font = settings.term_font();
+    if font.is_empty() {
+        terminal.set_font(None);
+    } else {
+        terminal.set_font(Some(&pango::FontDescription::from_string(&font)));
     }
+    terminal.set_cursor_shape(match settings.term_cursor_shape() {
+        SettingTermCursorShape::Block => vte::CursorShape::Block,
+        SettingTermCursorShape::Ibeam => vte::CursorShape::Ibeam,
+        SettingTermCursorShape::Underline => vte::CursorShape::Underline,
+    });
+}
+
+fn rgb(hex: u32) -> gdk::RGBA {
+    let channel = |shift: u32| ((hex >> shift) & 0xff) as f32 / 255.0;
+    gdk::RGBA::new(channel(16), channel(8), channel(0), 1.0)
 }
 
 #[derive(Debug)]
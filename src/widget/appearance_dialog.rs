@@ -0,0 +1,174 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Dialog for choosing the icon and color of a connection or server.
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk::{gio, glib};
+
+use crate::settings::{AccentColor, Appearance};
+
+fn color_title(color: AccentColor) -> String {
+    match color {
+        AccentColor::None => gettext("Default"),
+        AccentColor::Blue => gettext("Blue"),
+        AccentColor::Teal => gettext("Teal"),
+        AccentColor::Green => gettext("Green"),
+        AccentColor::Yellow => gettext("Yellow"),
+        AccentColor::Orange => gettext("Orange"),
+        AccentColor::Red => gettext("Red"),
+        AccentColor::Pink => gettext("Pink"),
+        AccentColor::Purple => gettext("Purple"),
+        AccentColor::Slate => gettext("Slate"),
+    }
+}
+
+fn update_preview(preview: &gtk::Image, icon: &str, color: AccentColor) {
+    if icon.starts_with('/') {
+        preview.set_from_file(Some(icon));
+    } else if icon.is_empty() {
+        preview.set_icon_name(Some("image-missing-symbolic"));
+    } else {
+        preview.set_icon_name(Some(icon));
+    }
+    for other in AccentColor::ALL.iter().filter_map(AccentColor::css_class) {
+        preview.remove_css_class(other);
+    }
+    if let Some(class) = color.css_class() {
+        preview.add_css_class(class);
+    }
+}
+
+/// Lets the user choose a new icon and color, starting with `appearance`. Returns the new
+/// appearance, which is the default appearance if the user reset it, or `None` if the dialog
+/// was cancelled.
+pub async fn edit_appearance(
+    appearance: &Appearance,
+    window: Option<&gtk::Window>,
+) -> Option<Appearance> {
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+
+    let preview = gtk::Image::builder().pixel_size(16).build();
+    let icon_row = adw::EntryRow::builder()
+        .title(gettext("Icon Name or Image File"))
+        .text(&appearance.icon)
+        .build();
+    icon_row.add_prefix(&preview);
+    let browse_button = gtk::Button::builder()
+        .icon_name("document-open-symbolic")
+        .tooltip_text(gettext("Choose Image…"))
+        .valign(gtk::Align::Center)
+        .css_classes(["flat"])
+        .build();
+    icon_row.add_suffix(&browse_button);
+    list.append(&icon_row);
+
+    let color_titles = AccentColor::ALL.map(color_title);
+    let color_row = adw::ComboRow::builder()
+        .title(gettext("Color"))
+        .model(&gtk::StringList::new(
+            &color_titles.iter().map(String::as_str).collect::<Vec<_>>(),
+        ))
+        .build();
+    if let Some(position) = AccentColor::ALL.iter().position(|c| *c == appearance.color) {
+        color_row.set_selected(position as u32);
+    }
+    list.append(&color_row);
+
+    let selected_color = |color_row: &adw::ComboRow| {
+        AccentColor::ALL
+            .get(color_row.selected() as usize)
+            .copied()
+            .unwrap_or_default()
+    };
+    update_preview(&preview, &appearance.icon, appearance.color);
+    icon_row.connect_changed(glib::clone!(
+        #[weak]
+        preview,
+        #[weak]
+        color_row,
+        move |icon_row| update_preview(
+            &preview,
+            icon_row.text().trim(),
+            selected_color(&color_row)
+        )
+    ));
+    color_row.connect_selected_notify(glib::clone!(
+        #[weak]
+        preview,
+        #[weak]
+        icon_row,
+        move |color_row| update_preview(
+            &preview,
+            icon_row.text().trim(),
+            selected_color(color_row)
+        )
+    ));
+
+    browse_button.connect_clicked(glib::clone!(
+        #[weak]
+        icon_row,
+        move |button| {
+            let filter = gtk::FileFilter::new();
+            filter.set_name(Some(&gettext("Images")));
+            filter.add_pixbuf_formats();
+            let filters = gio::ListStore::new::<gtk::FileFilter>();
+            filters.append(&filter);
+            let file_dialog = gtk::FileDialog::builder()
+                .title(gettext("Choose Image"))
+                .filters(&filters)
+                .modal(true)
+                .build();
+            let window = button.root().and_downcast::<gtk::Window>();
+            glib::spawn_future_local(async move {
+                if let Ok(file) = file_dialog.open_future(window.as_ref()).await {
+                    if let Some(path) = file.path() {
+                        icon_row.set_text(&path.to_string_lossy());
+                    }
+                }
+            });
+        }
+    ));
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("Icon and Color"))
+        .body(gettext(
+            "Enter the name of an icon of the icon theme, such as “starred-symbolic”, or choose an image file. Leave it empty to keep the default icon.",
+        ))
+        .extra_child(&list)
+        .close_response("cancel")
+        .default_response("save")
+        .build();
+    dialog.add_responses(&[
+        ("cancel", &gettext("Cancel")),
+        ("reset", &gettext("Reset")),
+        ("save", &gettext("Save")),
+    ]);
+    dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+
+    match dialog.choose_future(window).await.as_str() {
+        "save" => Some(Appearance {
+            icon: icon_row.text().trim().to_string(),
+            color: selected_color(&color_row),
+        }),
+        "reset" => Some(Appearance::default()),
+        _ => None,
+    }
+}
@@ -21,11 +21,24 @@ use adw::subclass::prelude::*;
 use gettextrs::gettext;
 use gtk::glib;
 
+use crate::application::FieldMonitorApplication;
+use crate::APP;
+
+/// What is about to be closed, determines the wording of the dialog.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CloseWarningKind {
+    Window,
+    Tab,
+    App,
+}
+
 mod imp {
     use super::*;
 
     #[derive(Default)]
-    pub struct FieldMonitorCloseWarningDialog {}
+    pub struct FieldMonitorCloseWarningDialog {
+        pub dont_ask_again: gtk::CheckButton,
+    }
 
     #[glib::object_subclass]
     impl ObjectSubclass for FieldMonitorCloseWarningDialog {
@@ -48,10 +61,37 @@ glib::wrapper! {
 impl FieldMonitorCloseWarningDialog {
     pub const RESPONSE_CLOSE: &'static str = "close";
 
-    pub fn new(connection_descriptions: impl IntoIterator<Item = (String, String)>) -> Self {
+    /// Whether closing connected sessions should be confirmed with this dialog.
+    pub fn is_enabled() -> bool {
+        APP.with_borrow(|app| app.as_ref().and_then(FieldMonitorApplication::settings))
+            .map(|settings| settings.confirm_close_sessions())
+            .unwrap_or(true)
+    }
+
+    pub fn new(
+        kind: CloseWarningKind,
+        connection_descriptions: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        let (heading, body, close_label) = match kind {
+            CloseWarningKind::Window => (
+                gettext("Close Window?"),
+                gettext("The app is still connected to one or more servers. Closing the window will disconnect from all servers."),
+                gettext("Close"),
+            ),
+            CloseWarningKind::Tab => (
+                gettext("Close Tab?"),
+                gettext("The app is still connected to this server. Closing the tab will disconnect from it."),
+                gettext("Close"),
+            ),
+            CloseWarningKind::App => (
+                gettext("Quit Field Monitor?"),
+                gettext("The app is still connected to one or more servers. Quitting will disconnect from all servers in all windows."),
+                gettext("Quit"),
+            ),
+        };
         let slf: Self = glib::Object::builder()
-            .property("heading", gettext("Close Window?"))
-            .property("body", gettext("The app is still connected to one or more servers. Closing the window will disconnect from all servers."))
+            .property("heading", heading)
+            .property("body", body)
             .build();
 
         let list_box = gtk::ListBox::builder().css_classes(["boxed-list"]).build();
@@ -72,14 +112,32 @@ impl FieldMonitorCloseWarningDialog {
             )
         }
 
-        slf.set_extra_child(Some(&list_box));
+        let dont_ask_again = &slf.imp().dont_ask_again;
+        dont_ask_again.set_label(Some(&gettext("Don't ask again")));
+        dont_ask_again.set_margin_top(12);
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        content.append(&list_box);
+        content.append(dont_ask_again);
+        slf.set_extra_child(Some(&content));
 
         slf.add_response("cancel", &gettext("Cancel"));
-        slf.add_response(Self::RESPONSE_CLOSE, &gettext("Close"));
+        slf.add_response(Self::RESPONSE_CLOSE, &close_label);
         slf.set_response_appearance(Self::RESPONSE_CLOSE, adw::ResponseAppearance::Destructive);
         slf.set_default_response(Some("cancel"));
         slf.set_close_response("cancel");
 
+        slf.connect_response(Some(Self::RESPONSE_CLOSE), |slf, _| {
+            if !slf.imp().dont_ask_again.is_active() {
+                return;
+            }
+            if let Some(settings) =
+                APP.with_borrow(|app| app.as_ref().and_then(FieldMonitorApplication::settings))
+            {
+                settings.set_confirm_close_sessions(false);
+            }
+        });
+
         slf
     }
 }
@@ -15,10 +15,12 @@
  *
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
+use crate::settings::Appearance;
 use crate::util::OrdKeyed;
+use crate::widget::connection_list::icon_widget;
 use crate::widget::connection_list::info_page::FieldMonitorConnectionInfoPage;
+use crate::widget::connection_list::smart_folder_page::FieldMonitorSmartFolderPage;
 use crate::widget::connection_list::FieldMonitorConnectionStack;
-use crate::widget::connection_list::DEFAULT_GENERIC_ICON;
 use crate::widget::navbar_row::FieldMonitorNavbarRow;
 use adw::gio;
 use adw::prelude::*;
@@ -151,6 +153,13 @@ mod imp {
 
                 for page in pages.iter() {
                     let page: gtk::StackPage = page.unwrap();
+                    let info_page = page
+                        .child()
+                        .downcast::<FieldMonitorConnectionInfoPage>()
+                        .ok();
+                    if !info_page.as_ref().map_or(true, is_in_active_workspace) {
+                        continue;
+                    }
                     let item = gtk::Label::builder()
                         .label("")
                         .halign(gtk::Align::Start)
@@ -172,9 +181,8 @@ mod imp {
                         item.upcast_ref()
                     ])]);
 
-                    let conn_meta = page
-                        .child()
-                        .downcast_ref::<FieldMonitorConnectionInfoPage>()
+                    let conn_meta = info_page
+                        .as_ref()
                         .and_then(FieldMonitorConnectionInfoPage::connection)
                         .as_ref()
                         .map(ConnectionInstance::metadata);
@@ -183,16 +191,15 @@ mod imp {
                         .map(|m| m.icon.clone())
                         .unwrap_or_else(|| IconSpec::Named("dialog-error-symbolic".into()));
 
-                    let icon: gtk::Widget = match icon_spec {
-                        IconSpec::Default => gtk::Image::builder()
-                            .icon_name(DEFAULT_GENERIC_ICON)
-                            .build()
-                            .upcast(),
-                        IconSpec::None => gtk::Box::builder().width_request(16).build().upcast(),
-                        IconSpec::Named(name) => {
-                            gtk::Image::builder().icon_name(&*name).build().upcast()
-                        }
-                        IconSpec::Custom(factory) => factory(&conn_meta.unwrap()),
+                    let appearance = info_page
+                        .as_ref()
+                        .and_then(connection_appearance)
+                        .unwrap_or_default();
+
+                    let icon = if page.child().is::<FieldMonitorSmartFolderPage>() {
+                        gtk::Image::from_icon_name("folder-saved-search-symbolic").upcast()
+                    } else {
+                        icon_widget(&icon_spec, &conn_meta.unwrap_or_default(), &appearance)
                     };
 
                     row.add_prefix(&icon);
@@ -201,8 +208,9 @@ mod imp {
 
                     let is_selected = page.name().as_deref() == active_connection.as_deref();
 
+                    // Smart folders are listed before the connections.
                     sorted_rows.push(OrdKeyed(
-                        page.title().map(|v| v.to_lowercase()),
+                        (info_page.is_some(), page.title().map(|v| v.to_lowercase())),
                         (page, row, is_selected),
                     ));
                 }
@@ -224,8 +232,8 @@ mod imp {
                         glib::clone!(
                             #[weak(rename_to=slf)]
                             self,
-                            move |page, _| {
-                                slf.obj().on_page_updated(page);
+                            move |page, pspec| {
+                                slf.obj().on_page_updated(page, pspec);
                             }
                         ),
                     );
@@ -256,6 +264,14 @@ glib::wrapper! {
         @implements gio::ActionGroup, gio::ActionMap;
 }
 
+impl FieldMonitorNavbarConnectionList {
+    /// Rebuilds the list, e.g. after the active workspace changed.
+    pub fn refresh(&self) {
+        self.imp().clear_sidebar();
+        self.imp().populate_sidebar();
+    }
+}
+
 #[gtk::template_callbacks]
 impl FieldMonitorNavbarConnectionList {
     #[template_callback]
@@ -280,8 +296,15 @@ impl FieldMonitorNavbarConnectionList {
         self.imp().populate_sidebar();
     }
 
-    fn on_page_updated(&self, _page: &gtk::StackPage) {
-        // the title could change :(
+    fn on_page_updated(&self, page: &gtk::StackPage, pspec: &glib::ParamSpec) {
+        // The rows are sorted by title, so only title changes need the list to be rebuilt.
+        if pspec.name() != "title" {
+            let rows_brw = self.imp().rows.borrow();
+            if let Some(entry) = page.name().and_then(|name| rows_brw.get(name.as_str())) {
+                self.imp().update_row(page, &entry.row);
+                return;
+            }
+        }
         self.imp().clear_sidebar();
         self.imp().populate_sidebar();
     }
@@ -309,6 +332,21 @@ struct RowEntry {
     page: gtk::StackPage,
 }
 
+fn is_in_active_workspace(page: &FieldMonitorConnectionInfoPage) -> bool {
+    let settings = page.application().and_then(|app| app.settings());
+    match (settings, page.connection()) {
+        (Some(settings), Some(connection)) => {
+            settings.is_in_active_workspace(&connection.connection_id())
+        }
+        _ => true,
+    }
+}
+
+fn connection_appearance(page: &FieldMonitorConnectionInfoPage) -> Option<Appearance> {
+    let settings = page.application().and_then(|app| app.settings())?;
+    Some(settings.appearance(&page.connection()?.connection_id()))
+}
+
 fn row_string(row: &FieldMonitorNavbarRow) -> String {
     row.child_ref()
         .unwrap()
@@ -16,8 +16,11 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 use crate::application::FieldMonitorApplication;
+use crate::settings::FieldMonitorSettings;
 use crate::util::ListChange;
 use crate::widget::connection_list::info_page::FieldMonitorConnectionInfoPage;
+use crate::widget::connection_list::smart_folder_page::FieldMonitorSmartFolderPage;
+use crate::widget::loading_indicator::FieldMonitorLoadingIndicator;
 use crate::APP;
 use adw::gio;
 use adw::prelude::*;
@@ -27,6 +30,10 @@ use log::debug;
 use std::cell::Cell;
 use std::cell::RefCell;
 
+/// Prefix of the names of the pages of smart folders in the stack, followed by the name of the
+/// folder. Connection IDs are UUIDs, so they never start with it.
+pub const SMART_FOLDER_PAGE_PREFIX: &str = "smart-folder:";
+
 mod imp {
     use super::*;
 
@@ -55,6 +62,7 @@ mod imp {
         type ParentType = adw::Bin;
 
         fn class_init(klass: &mut Self::Class) {
+            FieldMonitorLoadingIndicator::ensure_type();
             Self::bind_template(klass);
             Self::Type::bind_template_callbacks(klass);
         }
@@ -83,6 +91,14 @@ mod imp {
             for connection in connections {
                 obj.on_update_connection(connection);
             }
+            if let Some(settings) = app.settings() {
+                obj.update_smart_folders(&settings);
+                settings.connect_smart_folders_changed(glib::clone!(
+                    #[weak]
+                    obj,
+                    move |settings| obj.update_smart_folders(settings)
+                ));
+            }
             app.connect_closure(
                 "connection-updated",
                 false,
@@ -164,8 +180,15 @@ impl FieldMonitorConnectionStack {
         }
     }
 
+    /// Whether there are no connections. Smart folders are not counted.
     pub fn is_empty(&self) -> bool {
-        self.imp().stack.pages().n_items() < 1
+        !self
+            .imp()
+            .stack
+            .pages()
+            .iter::<gtk::StackPage>()
+            .flatten()
+            .any(|page| !is_smart_folder_page(&page))
     }
 
     pub fn select_connection(&self, connection: &str) {
@@ -176,6 +199,10 @@ impl FieldMonitorConnectionStack {
         self.notify_visible_connection_id();
     }
 
+    pub fn has_connection(&self, connection_id: &str) -> bool {
+        self.imp().stack.child_by_name(connection_id).is_some()
+    }
+
     pub fn pages(&self) -> gtk::SelectionModel {
         self.imp().stack.pages()
     }
@@ -216,6 +243,46 @@ impl FieldMonitorConnectionStack {
         }
     }
 
+    fn update_smart_folders(&self, settings: &FieldMonitorSettings) {
+        let imp = self.imp();
+        let folders = settings.smart_folders();
+
+        let stale = imp
+            .stack
+            .pages()
+            .iter::<gtk::StackPage>()
+            .flatten()
+            .filter(|page| {
+                page.name().is_some_and(|name| {
+                    name.strip_prefix(SMART_FOLDER_PAGE_PREFIX)
+                        .is_some_and(|name| !folders.iter().any(|folder| folder.name == name))
+                })
+            })
+            .collect::<Vec<_>>();
+        for page in stale {
+            self.on_connection_removed(&page.name().unwrap_or_default());
+        }
+
+        for folder in folders {
+            let name = format!("{SMART_FOLDER_PAGE_PREFIX}{}", folder.name);
+            match imp.stack.child_by_name(&name) {
+                Some(child) => {
+                    child
+                        .downcast::<FieldMonitorSmartFolderPage>()
+                        .unwrap()
+                        .set_folder(&folder);
+                }
+                None => {
+                    let page = imp.stack.add_named(
+                        &FieldMonitorSmartFolderPage::new(&self.application().unwrap(), &folder),
+                        Some(&name),
+                    );
+                    page.set_title(&folder.name);
+                }
+            }
+        }
+    }
+
     fn on_connection_removed(&self, id: &str) {
         let imp = self.imp();
         let currently_selected_name = imp.stack.visible_child_name();
@@ -286,3 +353,8 @@ impl FieldMonitorConnectionStack {
         }
     }
 }
+
+fn is_smart_folder_page(page: &gtk::StackPage) -> bool {
+    page.name()
+        .is_some_and(|name| name.starts_with(SMART_FOLDER_PAGE_PREFIX))
+}
@@ -16,18 +16,27 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 use crate::application::FieldMonitorApplication;
+use crate::availability_history;
+use crate::diagnostics::present_problem_report;
+use crate::event_sinks::{SinkEvent, SinkEventKind};
 use crate::widget::connection_list::server_group::FieldMonitorServerGroup;
 use crate::widget::connection_list::server_row::FieldMonitorServerRow;
 use crate::widget::connection_list::{maybe_add_actions_button, ServerOrConnection};
+use crate::widget::loading_indicator::FieldMonitorLoadingIndicator;
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use futures::lock::Mutex;
 use gettextrs::gettext;
 use gtk::glib;
 use libfieldmonitor::connection::*;
+use libfieldmonitor::i18n::gettext_f;
 use log::{debug, warn};
 use std::borrow::Cow;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+/// Key of the group of servers without children in `groups`. Server paths are never empty.
+const MAIN_GROUP_KEY: &str = "";
 
 mod imp {
     use super::*;
@@ -36,6 +45,8 @@ mod imp {
     #[properties(wrapper_type = super::FieldMonitorConnectionInfoPage)]
     #[template(resource = "/de/capypara/FieldMonitor/widget/connection_list/info_page.ui")]
     pub struct FieldMonitorConnectionInfoPage {
+        #[template_child]
+        pub title_widget: TemplateChild<adw::WindowTitle>,
         #[template_child]
         pub status_stack: TemplateChild<gtk::Stack>,
         #[template_child]
@@ -45,9 +56,15 @@ mod imp {
         #[template_child]
         pub status_page_error: TemplateChild<adw::StatusPage>,
         #[template_child]
+        pub scrolled_window: TemplateChild<gtk::ScrolledWindow>,
+        #[template_child]
         pub group_box: TemplateChild<gtk::Box>,
         #[template_child]
         pub box_for_connection_action: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub loading_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub loading_progress_bar: TemplateChild<gtk::ProgressBar>,
         #[property(get, set)]
         pub connection: RefCell<Option<ConnectionInstance>>,
         #[property(get, set)]
@@ -57,6 +74,16 @@ mod imp {
         #[property(get, construct_only)]
         pub application: RefCell<Option<FieldMonitorApplication>>,
         pub reload_connections_reentry_lock: Mutex<()>,
+        /// Server rows that were not yet scrolled into view and are not loaded yet.
+        pub pending_rows: RefCell<Vec<FieldMonitorServerRow>>,
+        /// The shown server groups, by the path of the server they show the children of.
+        pub groups: RefCell<HashMap<String, FieldMonitorServerGroup>>,
+        /// The shown server rows, by server path.
+        pub rows: RefCell<HashMap<String, FieldMonitorServerRow>>,
+        /// Set while a reload triggered by the status poller is running.
+        pub polling: Cell<bool>,
+        /// Progress of the running load, to cancel it.
+        pub load_progress: RefCell<Option<LoadProgress>>,
     }
 
     #[glib::object_subclass]
@@ -66,6 +93,7 @@ mod imp {
         type ParentType = adw::Bin;
 
         fn class_init(klass: &mut Self::Class) {
+            FieldMonitorLoadingIndicator::ensure_type();
             Self::bind_template(klass);
             Self::Type::bind_template_callbacks(klass);
         }
@@ -99,13 +127,99 @@ impl FieldMonitorConnectionInfoPage {
         imp.settings_button.set_action_target(Some(&connection_id));
         imp.auth_button.set_action_target(Some(&connection_id));
 
+        let vadjustment = imp.scrolled_window.vadjustment();
+        vadjustment.connect_value_changed(glib::clone!(
+            #[weak]
+            slf,
+            move |_| slf.load_visible_rows()
+        ));
+        vadjustment.connect_changed(glib::clone!(
+            #[weak]
+            slf,
+            move |_| slf.load_visible_rows()
+        ));
+
+        // Actions of the connection are only known once it is loaded.
+        connection.connect_loaded_notify(glib::clone!(
+            #[weak]
+            slf,
+            move |_| {
+                slf.update_actions_button();
+                glib::spawn_future_local(async move { slf.reload_connection().await });
+            }
+        ));
+        if let Some(settings) = app.settings() {
+            settings.connect_load_on_demand_changed(glib::clone!(
+                #[weak]
+                slf,
+                move |_| slf.update_actions_button()
+            ));
+            // Server rows pick up their new icons when they are updated.
+            settings.connect_appearances_changed(glib::clone!(
+                #[weak]
+                slf,
+                move |_| {
+                    glib::spawn_future_local(async move { slf.reload_connection().await });
+                }
+            ));
+        }
+        slf.update_actions_button();
+
+        if let Some(poller) = app.status_poller() {
+            poller.subscribe(
+                &slf,
+                glib::clone!(
+                    #[weak]
+                    slf,
+                    move || slf.on_status_poll()
+                ),
+            );
+        }
+
+        slf
+    }
+
+    fn on_status_poll(&self) {
+        let Some(connection) = self.connection() else {
+            return;
+        };
+        if !connection.loaded() || !connection.provider().supports_status_polling() {
+            return;
+        }
+        // Skip this poll if the previous one did not finish yet.
+        if self.imp().polling.replace(true) {
+            return;
+        }
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move {
+                slf.reload_connection().await;
+                slf.imp().polling.set(false);
+            }
+        ));
+    }
+
+    fn update_actions_button(&self) {
+        let imp = self.imp();
+        let Some(connection) = self.connection() else {
+            return;
+        };
+        let connection_id = connection.connection_id();
+        let load_on_demand = self.is_loaded_on_demand(&connection_id);
+
+        while let Some(child) = imp.box_for_connection_action.last_child() {
+            imp.box_for_connection_action.remove(&child);
+        }
         maybe_add_actions_button(
-            &slf.imp().box_for_connection_action,
-            ServerOrConnection::Connection(connection),
+            &imp.box_for_connection_action,
+            ServerOrConnection::Connection {
+                connection: &connection,
+                load_on_demand,
+            },
             &connection_id,
+            false,
         );
-
-        slf
     }
 
     async fn reload_connection(&self) {
@@ -117,24 +231,69 @@ impl FieldMonitorConnectionInfoPage {
     async fn try_reload_connection(&self) -> ConnectionResult<()> {
         let imp = self.imp();
         let _ = imp.reload_connections_reentry_lock.lock().await;
-        imp.status_stack.set_visible_child_name("loading");
+        // Only show the progress on the first load, updates are applied in place.
+        if imp.status_stack.visible_child_name().as_deref() != Some("servers") {
+            imp.loading_label.set_label(&gettext("Loading servers…"));
+            imp.loading_progress_bar.set_visible(false);
+            imp.status_stack.set_visible_child_name("loading");
+        }
+        let progress = LoadProgress::new(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            move |step| slf.show_load_step(step)
+        ));
+        imp.load_progress.replace(Some(progress.clone()));
+        let result = self.load_servers(&progress).await;
+        imp.load_progress.take();
+        result
+    }
+
+    async fn load_servers(&self, progress: &LoadProgress) -> ConnectionResult<()> {
+        let imp = self.imp();
         let connection = imp.connection.borrow().clone().unwrap();
         let connection_id = connection.connection_id();
 
-        debug!("reloading connection, removing old entries");
-        while let Some(child) = imp.group_box.last_child() {
-            imp.group_box.remove(&child);
+        if !connection.loaded() {
+            debug!("connection not loaded, removing old entries");
+            self.set_groups(&[]);
+            imp.groups.borrow_mut().clear();
+            imp.rows.borrow_mut().clear();
+            imp.pending_rows.borrow_mut().clear();
+            // Connections that are not loaded on demand are being loaded in the background, the
+            // page is reloaded once that is done.
+            if self.is_loaded_on_demand(&connection_id) {
+                imp.title_widget.set_subtitle("");
+                imp.group_box
+                    .append(&self.make_not_loaded_group(&connection));
+                imp.status_stack.set_visible_child_name("servers");
+            }
+            return Ok(());
         }
 
-        let servers = connection.servers().await?;
+        let servers = connection.servers_with_progress(progress).await?;
         let no_servers = servers.is_empty();
         debug!("loaded servers");
 
+        let metadata = connection.metadata();
+        let subtitle = metadata
+            .subtitle
+            .into_iter()
+            .chain(metadata.info_lines)
+            .collect::<Vec<_>>()
+            .join(" · ");
+        imp.title_widget.set_subtitle(&subtitle);
+
         let mut servers_with_no_children = Vec::with_capacity(servers.len());
         let mut servers_with_children = Vec::with_capacity(servers.len());
 
-        for (key, server) in servers {
-            let subservers = server.servers().await?;
+        let total = servers.len();
+        for (i, (key, server)) in servers.into_iter().enumerate() {
+            progress.report(
+                gettext_f("Loading {server}…", &[("server", &server.metadata().title)]),
+                i + 1,
+                total,
+            );
+            let subservers = progress.unless_cancelled(server.servers()).await?;
             if subservers.is_empty() {
                 servers_with_no_children.push(Server {
                     key,
@@ -150,73 +309,258 @@ impl FieldMonitorConnectionInfoPage {
             }
         }
         let has_servers_with_no_children = !servers_with_no_children.is_empty();
+        let copyable = connection.provider().supports_copying_servers();
         debug!("loaded subservers");
 
+        // Groups and rows of servers that are still there are reused, so that updates do not
+        // reset the scroll position.
+        let mut old_groups = imp.groups.take();
+        let mut old_rows = imp.rows.take();
+        let mut groups = Vec::with_capacity(servers_with_children.len() + 1);
+        let mut new_groups = HashMap::with_capacity(servers_with_children.len() + 1);
+        let mut new_rows = HashMap::new();
+        let mut pending_rows = Vec::new();
+        let mut make_row = |full_path: Vec<String>, server: Box<dyn ServerConnection>| {
+            let path = full_path.join("/");
+            self.record_availability(&path, server.as_ref());
+            let row = match old_rows.remove(&path) {
+                Some(row) => {
+                    row.update(server, copyable);
+                    row
+                }
+                None => FieldMonitorServerRow::new(&full_path, server, copyable),
+            };
+            pending_rows.push(row.clone());
+            new_rows.insert(path, row.clone());
+            row.upcast::<gtk::ListBoxRow>()
+        };
+
         // Main group (servers with no children)
-        let group = FieldMonitorServerGroup::new(&self.application().unwrap(), None).await?;
+        let group = match old_groups.remove(MAIN_GROUP_KEY) {
+            Some(group) => group,
+            None => FieldMonitorServerGroup::new(&self.application().unwrap(), None).await?,
+        };
+        let mut rows = Vec::with_capacity(servers_with_no_children.len());
         for server in servers_with_no_children {
-            group.add(
-                &FieldMonitorServerRow::new(
-                    &[connection_id.clone(), server.key.to_string()],
-                    server.server,
-                )
-                .await?,
-            );
+            rows.push(make_row(
+                vec![connection_id.clone(), server.key.to_string()],
+                server.server,
+            ));
         }
         // if servers is empty, we have no server at all, add a small note.
         if no_servers {
-            group.add(
-                &adw::ActionRow::builder()
+            rows.push(
+                adw::ActionRow::builder()
                     .sensitive(false)
                     .title(gettext("No servers available"))
-                    .build(),
+                    .build()
+                    .upcast(),
             );
-            imp.group_box.append(&group);
-        } else if has_servers_with_no_children {
-            imp.group_box.append(&group);
         }
+        group.set_rows(&rows);
+        if no_servers || has_servers_with_no_children {
+            groups.push(group.clone());
+        }
+        new_groups.insert(MAIN_GROUP_KEY.to_string(), group);
         debug!("created main group");
 
         // Additional groups: servers with subservers
         for server in servers_with_children {
-            let group = FieldMonitorServerGroup::new(
-                &self.application().unwrap(),
-                Some((
-                    server.server.as_ref(),
-                    &[connection_id.clone(), server.key.to_string()],
-                )),
-            )
-            .await?;
+            let full_path = [connection_id.clone(), server.key.to_string()];
+            let title_server = Some((server.server.as_ref(), &full_path[..]));
+            let path = full_path.join("/");
+            self.record_availability(&path, server.server.as_ref());
+            let group = match old_groups.remove(&path) {
+                Some(group) => {
+                    group.set_title_server(title_server).await?;
+                    group
+                }
+                None => {
+                    FieldMonitorServerGroup::new(&self.application().unwrap(), title_server).await?
+                }
+            };
+            let mut rows = Vec::with_capacity(server.subservers.len());
             for (key, subserver) in server.subservers {
-                group.add(
-                    &FieldMonitorServerRow::new(
-                        &[
-                            connection_id.clone(),
-                            server.key.to_string(),
-                            key.to_string(),
-                        ],
-                        subserver,
-                    )
-                    .await?,
-                );
+                rows.push(make_row(
+                    vec![
+                        connection_id.clone(),
+                        server.key.to_string(),
+                        key.to_string(),
+                    ],
+                    subserver,
+                ));
             }
-            imp.group_box.append(&group);
+            group.set_rows(&rows);
+            groups.push(group.clone());
+            new_groups.insert(path, group);
         }
 
+        self.set_groups(&groups);
+        imp.groups.replace(new_groups);
+        imp.rows.replace(new_rows);
+        imp.pending_rows.replace(pending_rows);
+
         debug!("finished loading");
         imp.status_stack.set_visible_child_name("servers");
+        self.set_needs_attention(false);
+
+        // Load the rows that are visible initially, once they have been allocated.
+        glib::idle_add_local_once(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            move || slf.load_visible_rows()
+        ));
 
         Ok(())
     }
 
+    fn show_load_step(&self, step: &LoadStep) {
+        let imp = self.imp();
+        imp.loading_label.set_label(&step.description);
+        imp.loading_progress_bar.set_visible(step.total > 0);
+        if step.total > 0 {
+            imp.loading_progress_bar
+                .set_fraction((step.current - 1) as f64 / step.total as f64);
+            imp.loading_progress_bar.set_text(Some(&gettext_f(
+                "{current} of {total}",
+                &[
+                    ("current", &step.current.to_string()),
+                    ("total", &step.total.to_string()),
+                ],
+            )));
+        }
+    }
+
+    /// Records the status of the server at `path` and reports it to the event sinks if it went
+    /// offline.
+    fn record_availability(&self, path: &str, server: &dyn ServerConnection) {
+        let metadata = server.metadata();
+        if !availability_history::record(path, metadata.is_online) {
+            return;
+        }
+        if let Some(app) = self.application() {
+            app.notify_event_sinks(SinkEvent::new(
+                SinkEventKind::ServerOffline,
+                path,
+                &metadata.title,
+                None,
+            ));
+        }
+    }
+
+    /// Makes `groups` the content of the page, in this order. Groups that are already shown
+    /// are moved instead of re-added.
+    fn set_groups(&self, groups: &[FieldMonitorServerGroup]) {
+        let group_box = &*self.imp().group_box;
+        let mut previous: Option<gtk::Widget> = None;
+        for group in groups {
+            if group.parent().as_ref() == Some(group_box.upcast_ref()) {
+                group_box.reorder_child_after(group, previous.as_ref());
+            } else {
+                group_box.insert_child_after(group, previous.as_ref());
+            }
+            previous = Some(group.clone().upcast());
+        }
+
+        let mut stale = match &previous {
+            Some(last) => last.next_sibling(),
+            None => group_box.first_child(),
+        };
+        while let Some(child) = stale {
+            stale = child.next_sibling();
+            group_box.remove(&child);
+        }
+    }
+
+    fn is_loaded_on_demand(&self, connection_id: &str) -> bool {
+        self.application()
+            .and_then(|app| app.settings())
+            .is_some_and(|settings| settings.is_loaded_on_demand(connection_id))
+    }
+
+    /// Marks the entry of the connection in the sidebar, if it failed to load.
+    fn set_needs_attention(&self, value: bool) {
+        if let Some(stack) = self.parent().and_downcast::<gtk::Stack>() {
+            stack.page(self).set_needs_attention(value);
+        }
+    }
+
+    /// Placeholder for connections that are loaded on demand and were not loaded yet.
+    fn make_not_loaded_group(&self, connection: &ConnectionInstance) -> adw::PreferencesGroup {
+        let load_button = gtk::Button::builder()
+            .label(gettext("Load"))
+            .valign(gtk::Align::Center)
+            .css_classes(["suggested-action"])
+            .build();
+        load_button.connect_clicked(glib::clone!(
+            #[weak]
+            connection,
+            move |button| {
+                button.set_sensitive(false);
+                glib::spawn_future_local(async move { connection.load().await });
+            }
+        ));
+
+        let row = adw::ActionRow::builder()
+            .title(gettext("Not loaded yet"))
+            .subtitle(gettext("This connection is only loaded when requested."))
+            .activatable_widget(&load_button)
+            .build();
+        row.add_suffix(&load_button);
+
+        let group = adw::PreferencesGroup::new();
+        group.add(&row);
+        group
+    }
+
+    /// Loads all pending server rows that are currently in (or close to) the visible area of
+    /// the server list.
+    fn load_visible_rows(&self) {
+        let imp = self.imp();
+        let scrolled_window = &*imp.scrolled_window;
+        let height = scrolled_window.height() as f32;
+        if height <= 0.0 {
+            return;
+        }
+        // Also load rows just outside the visible area, so they are ready when scrolling.
+        let margin = height / 2.0;
+
+        let mut rows_to_load = Vec::new();
+        imp.pending_rows.borrow_mut().retain(|row| {
+            let visible = row
+                .compute_bounds(scrolled_window)
+                .map(|bounds| {
+                    bounds.y() + bounds.height() >= -margin && bounds.y() <= height + margin
+                })
+                .unwrap_or_default();
+            if visible {
+                rows_to_load.push(row.clone());
+            }
+            !visible
+        });
+
+        for row in rows_to_load {
+            glib::spawn_future_local(async move {
+                if let Err(err) = row.load().await {
+                    warn!("failed to load server row: {:?}", err);
+                }
+            });
+        }
+    }
+
     fn error(&self, err: &ConnectionError) {
         let imp = self.imp();
+        self.set_needs_attention(true);
 
         match err {
             ConnectionError::General(expl, err) => {
                 imp.status_stack.set_visible_child_name("error");
 
                 imp.status_page_error.set_description(expl.as_deref());
+                self.set_error_text(match expl {
+                    Some(expl) => format!("{expl}\n\n{err:?}"),
+                    None => format!("{err:?}"),
+                });
 
                 warn!("failed to load connection in info page: {:?}", err);
             }
@@ -235,6 +579,33 @@ impl FieldMonitorConnectionInfoPage {
     async fn on_self_connection_changed(&self) {
         self.reload_connection().await;
     }
+
+    #[template_callback]
+    fn on_report_problem_clicked(&self) {
+        present_problem_report(
+            self,
+            "Failed to load connection",
+            self.connection().as_ref(),
+            &self.error_text(),
+        );
+    }
+
+    #[template_callback]
+    fn on_cancel_loading_clicked(&self) {
+        if let Some(progress) = self.imp().load_progress.borrow().as_ref() {
+            progress.cancel();
+        }
+    }
+
+    #[template_callback]
+    async fn on_retry_button_clicked(&self) {
+        let (Some(app), Some(connection)) = (self.application(), self.connection()) else {
+            return;
+        };
+        self.imp().status_stack.set_visible_child_name("loading");
+        // Reloading the connection updates this page again.
+        app.reload_connection(&connection.connection_id()).await;
+    }
 }
 
 struct Server {
@@ -22,7 +22,14 @@ mod connection_stack;
 mod info_page;
 mod server_group;
 mod server_row;
+mod smart_folder_page;
 
+use crate::application::FieldMonitorApplication;
+use crate::settings::{Appearance, FieldMonitorSettings};
+use crate::widget::loading_indicator::inline_loading_indicator;
+use crate::widget::status_indicator::FieldMonitorStatusIndicator;
+use crate::widget::window::FieldMonitorWindow;
+use crate::APP;
 use adw::prelude::*;
 pub use connection_list_navbar::*;
 pub use connection_stack::*;
@@ -30,7 +37,7 @@ use gettextrs::gettext;
 use glib::object::Cast;
 use gtk::gio;
 use libfieldmonitor::connection::*;
-use libfieldmonitor::i18n::gettext_f;
+use libfieldmonitor::i18n::{format_percent, gettext_f};
 use std::borrow::Cow;
 
 async fn make_server_prefix_suffix(
@@ -38,39 +45,115 @@ async fn make_server_prefix_suffix(
     path: &[String],
     row: Option<&impl IsA<adw::ActionRow>>,
 ) -> ConnectionResult<(gtk::Widget, gtk::Widget)> {
-    let path = path.join("/");
-    let metadata = server.metadata();
+    let prefix = make_icon(&server.metadata(), &path.join("/"));
+    // Servers with sub-servers are only shown as group headers, those can not be copied.
+    let suffix = make_server_suffix(server, path, row, false).await?;
+
+    Ok((prefix, suffix))
+}
 
-    let prefix = make_icon(&metadata);
+/// If `copyable` is set, actions for duplicating, copying and moving the server are offered.
+async fn make_server_suffix(
+    server: &dyn ServerConnection,
+    path: &[String],
+    row: Option<&impl IsA<adw::ActionRow>>,
+    copyable: bool,
+) -> ConnectionResult<gtk::Widget> {
+    let path = path.join("/");
 
     let suffix = gtk::Box::builder()
         .spacing(6)
         .orientation(gtk::Orientation::Horizontal)
         .build();
+    if let Some(ip_address) = server.metadata().ip_address {
+        suffix.append(&make_copy_address_button(ip_address));
+    }
     maybe_add_connect_button(row, &suffix, server, &path);
-    maybe_add_actions_button(&suffix, ServerOrConnection::Server(server), &path);
+    maybe_add_actions_button(&suffix, ServerOrConnection::Server(server), &path, copyable);
+
+    Ok(suffix.upcast())
+}
+
+/// Button copying `ip_address` to the clipboard.
+fn make_copy_address_button(ip_address: String) -> gtk::Button {
+    let button = gtk::Button::builder()
+        .icon_name("edit-copy-symbolic")
+        .tooltip_text(gettext("Copy IP Address"))
+        .valign(gtk::Align::Center)
+        .css_classes(["flat"])
+        .build();
+    button.connect_clicked(move |button| {
+        button.clipboard().set_text(&ip_address);
+        if let Some(window) = button.root().and_downcast::<FieldMonitorWindow>() {
+            window.toast(&gettext_f(
+                "Copied {address} to the clipboard",
+                &[("address", &ip_address)],
+            ));
+        }
+    });
+    button
+}
+
+/// The subtitle of a server, followed by its IP address and additional info lines.
+fn server_subtitle(metadata: &ServerMetadata) -> Option<String> {
+    let lines = metadata
+        .subtitle
+        .iter()
+        .chain(metadata.ip_address.iter())
+        .chain(metadata.info_lines.iter())
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
 
-    Ok((prefix, suffix.upcast()))
+/// The icon of the server at `path`, with the icon and color chosen by the user, if any.
+fn make_icon(metadata: &ServerMetadata, path: &str) -> gtk::Widget {
+    let appearance = app_settings()
+        .map(|settings| settings.appearance(path))
+        .unwrap_or_default();
+    let wdg = icon_widget(&metadata.icon, metadata, &appearance);
+
+    add_status(wdg, metadata, path)
 }
 
-fn make_icon(metadata: &ServerMetadata) -> gtk::Widget {
-    let wdg = match &metadata.icon {
-        IconSpec::Default => gtk::Image::builder()
-            .icon_name(DEFAULT_GENERIC_ICON)
+/// The widget for `spec`, unless `appearance` replaces the icon.
+pub fn icon_widget<M>(spec: &IconSpec<M>, metadata: &M, appearance: &Appearance) -> gtk::Widget {
+    let wdg: gtk::Widget = if appearance.icon_is_file() {
+        gtk::Image::builder()
+            .file(&appearance.icon)
+            .pixel_size(16)
             .build()
-            .upcast(),
-        IconSpec::None => gtk::Box::builder().width_request(16).build().upcast(),
-        IconSpec::Named(name) => gtk::Image::builder()
-            .icon_name(name.as_ref())
+            .upcast()
+    } else if !appearance.icon.is_empty() {
+        gtk::Image::builder()
+            .icon_name(&appearance.icon)
             .build()
-            .upcast(),
-        IconSpec::Custom(factory) => factory(metadata),
+            .upcast()
+    } else {
+        match spec {
+            IconSpec::Default => gtk::Image::builder()
+                .icon_name(DEFAULT_GENERIC_ICON)
+                .build()
+                .upcast(),
+            IconSpec::None => gtk::Box::builder().width_request(16).build().upcast(),
+            IconSpec::Named(name) => gtk::Image::builder()
+                .icon_name(name.as_ref())
+                .build()
+                .upcast(),
+            IconSpec::Custom(factory) => factory(metadata),
+        }
     };
-
-    add_status(wdg, metadata)
+    if let Some(class) = appearance.color.css_class() {
+        wdg.add_css_class(class);
+    }
+    wdg
 }
 
-fn add_status(child_wdgt: gtk::Widget, metadata: &ServerMetadata) -> gtk::Widget {
+fn add_status(child_wdgt: gtk::Widget, metadata: &ServerMetadata, path: &str) -> gtk::Widget {
     let parent = gtk::Box::builder()
         .orientation(gtk::Orientation::Horizontal)
         .valign(gtk::Align::Center)
@@ -79,34 +162,60 @@ fn add_status(child_wdgt: gtk::Widget, metadata: &ServerMetadata) -> gtk::Widget
 
     parent.append(&child_wdgt);
 
-    match metadata.is_online {
-        Some(status) => {
-            let (class, tooltip_text, icon_name) = if status {
-                ("success", "Online", "circle-filled-symbolic")
-            } else {
-                ("dim-label", "Offline", "circle-outline-thick-symbolic")
-            };
-
-            let status_icon = gtk::Image::builder()
-                .pixel_size(8)
-                .icon_name(icon_name)
-                .css_classes([class])
-                .tooltip_text(tooltip_text)
-                .valign(gtk::Align::Center)
-                .build();
-
-            parent.append(&status_icon);
-        }
-        None => {
-            let placeholder = gtk::Box::builder().width_request(8).build();
+    let status_indicator = FieldMonitorStatusIndicator::new(metadata.is_online);
+    status_indicator.set_history_path(path);
+    parent.append(&status_indicator);
 
-            parent.append(&placeholder);
-        }
+    if !metadata.usage.is_empty() {
+        parent.append(&make_usage_indicators(&metadata.usage));
+    }
+    for badge in &metadata.badges {
+        parent.append(&make_badge(badge));
     }
 
     parent.upcast()
 }
 
+fn make_usage_indicators(usage: &[UsageIndicator]) -> gtk::Widget {
+    let indicators = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .valign(gtk::Align::Center)
+        .spacing(2)
+        .css_classes(["usage-indicators"])
+        .build();
+
+    for indicator in usage {
+        let level_bar = gtk::LevelBar::builder()
+            .value(indicator.fraction.clamp(0.0, 1.0))
+            .width_request(32)
+            .tooltip_text(gettext_f(
+                "{label}: {percent}",
+                &[
+                    ("label", &indicator.label),
+                    ("percent", &format_percent(indicator.fraction, 0)),
+                ],
+            ))
+            .build();
+        level_bar.update_property(&[gtk::accessible::Property::Label(&indicator.label)]);
+        indicators.append(&level_bar);
+    }
+
+    indicators.upcast()
+}
+
+fn make_badge(badge: &ServerBadge) -> gtk::Label {
+    let label = gtk::Label::builder()
+        .label(&badge.label)
+        .valign(gtk::Align::Center)
+        .css_classes(["fm-server-badge", "caption-heading"])
+        .build();
+    if let Some(class) = badge.style.css_class() {
+        label.add_css_class(class);
+    }
+    label.set_tooltip_text(badge.tooltip.as_deref());
+    label
+}
+
 fn maybe_add_connect_button(
     row: Option<&impl IsA<adw::ActionRow>>,
     boxx: &gtk::Box,
@@ -132,15 +241,23 @@ fn maybe_add_connect_button(
     }
 }
 
-fn maybe_add_actions_button(boxx: &gtk::Box, server_or_connection: ServerOrConnection, path: &str) {
-    let (actions, is_server) = match server_or_connection {
-        ServerOrConnection::Server(server) => (server.actions(), true),
-        ServerOrConnection::Connection(connection) => (connection.actions(), false),
+fn maybe_add_actions_button(
+    boxx: &gtk::Box,
+    server_or_connection: ServerOrConnection,
+    path: &str,
+    copyable: bool,
+) {
+    let (actions, server_metadata, load_on_demand) = match server_or_connection {
+        ServerOrConnection::Server(server) => (server.actions(), Some(server.metadata()), false),
+        ServerOrConnection::Connection {
+            connection,
+            load_on_demand,
+        } => (connection.actions(), None, load_on_demand),
     };
+    let is_server = server_metadata.is_some();
+    let connection_id = path.split('/').next().unwrap_or_default().to_string();
+    boxx.append(&make_busy_indicator(path));
 
-    if actions.is_empty() {
-        return;
-    }
     let menu = gio::Menu::new();
     for (action_id, action_title) in actions {
         let action_target = (is_server, path, &*action_id).to_variant();
@@ -155,6 +272,86 @@ fn maybe_add_actions_button(boxx: &gtk::Box, server_or_connection: ServerOrConne
             ),
         );
     }
+    // The tools are looked up whenever the menu is opened, so changes apply right away.
+    let tools_menu = gio::Menu::new();
+    let server_host_title = server_metadata.map(|metadata| (metadata.host, metadata.title));
+    if let Some((host, title)) = &server_host_title {
+        update_external_tools_menu(&tools_menu, &connection_id, host.as_deref(), title);
+        menu.append_section(None, &tools_menu);
+    }
+    if copyable {
+        menu.append_section(None, &make_copy_menu(path));
+    }
+    let appearance_menu = gio::Menu::new();
+    appearance_menu.append(
+        Some(&gettext("Icon and Color…")),
+        Some(
+            gio::Action::print_detailed_name("app.edit-appearance", Some(&path.to_variant()))
+                .as_str(),
+        ),
+    );
+    if let Some((_, title)) = &server_host_title {
+        appearance_menu.append(
+            Some(&gettext("Tags…")),
+            Some(
+                gio::Action::print_detailed_name(
+                    "app.edit-server-tags",
+                    Some(&(path, title).to_variant()),
+                )
+                .as_str(),
+            ),
+        );
+    }
+    menu.append_section(None, &appearance_menu);
+    if !is_server {
+        let workspace_menu = gio::Menu::new();
+        workspace_menu.append(
+            Some(&gettext("Duplicate Connection")),
+            Some(
+                gio::Action::print_detailed_name(
+                    "app.duplicate-connection",
+                    Some(&path.to_variant()),
+                )
+                .as_str(),
+            ),
+        );
+        workspace_menu.append(
+            Some(&gettext("Move to Workspace…")),
+            Some(
+                gio::Action::print_detailed_name(
+                    "app.move-connection-to-workspace",
+                    Some(&path.to_variant()),
+                )
+                .as_str(),
+            ),
+        );
+        let load_label = if load_on_demand {
+            gettext("Load on Startup")
+        } else {
+            gettext("Load Only When Opened")
+        };
+        workspace_menu.append(
+            Some(&gettext("External Tools…")),
+            Some(
+                gio::Action::print_detailed_name(
+                    "app.edit-external-tools",
+                    Some(&path.to_variant()),
+                )
+                .as_str(),
+            ),
+        );
+        workspace_menu.append(
+            Some(&load_label),
+            Some(
+                gio::Action::print_detailed_name(
+                    "app.set-connection-load-on-demand",
+                    Some(&(path, !load_on_demand).to_variant()),
+                )
+                .as_str(),
+            ),
+        );
+        menu.append_section(None, &workspace_menu);
+    }
 
     let button = gtk::MenuButton::builder()
         .menu_model(&menu)
@@ -163,10 +360,98 @@ fn maybe_add_actions_button(boxx: &gtk::Box, server_or_connection: ServerOrConne
         .valign(gtk::Align::Center)
         .css_classes(["flat"])
         .build();
+    if let Some((host, title)) = server_host_title {
+        button.set_create_popup_func(move |_| {
+            update_external_tools_menu(&tools_menu, &connection_id, host.as_deref(), &title)
+        });
+    }
 
     boxx.append(&button);
 }
 
+/// Spinner that is only visible while an action or connection request for `path` is pending.
+fn make_busy_indicator(path: &str) -> gtk::Widget {
+    let indicator = inline_loading_indicator();
+    indicator.set_tooltip_text(Some(&gettext("Action in progress…")));
+    let Some(app) = APP.with_borrow(Clone::clone) else {
+        indicator.set_visible(false);
+        return indicator.upcast();
+    };
+    indicator.set_visible(app.target_busy(path));
+
+    let path = path.to_string();
+    app.connect_closure(
+        "target-busy-changed",
+        false,
+        glib::closure_local!(
+            #[watch]
+            indicator,
+            move |_: FieldMonitorApplication, target: String, busy: bool| {
+                if target == path {
+                    indicator.set_visible(busy);
+                }
+            }
+        ),
+    );
+    indicator.upcast()
+}
+
+fn app_settings() -> Option<FieldMonitorSettings> {
+    APP.with_borrow(|app| app.as_ref().and_then(FieldMonitorApplication::settings))
+}
+
+/// Fills `menu` with the external tools of the connection that can be used for the server.
+fn update_external_tools_menu(
+    menu: &gio::Menu,
+    connection_id: &str,
+    host: Option<&str>,
+    title: &str,
+) {
+    menu.remove_all();
+    let Some(settings) = app_settings() else {
+        return;
+    };
+    for tool in settings.external_tools(connection_id) {
+        let Some(url) = tool.url_for(host, title) else {
+            continue;
+        };
+        menu.append(
+            Some(&gettext_f("Open {tool}", &[("tool", &tool.name)])),
+            Some(
+                gio::Action::print_detailed_name("app.open-external-tool", Some(&url.to_variant()))
+                    .as_str(),
+            ),
+        );
+    }
+}
+
+fn make_copy_menu(path: &str) -> gio::Menu {
+    let menu = gio::Menu::new();
+    menu.append(
+        Some(&gettext("Duplicate")),
+        Some(
+            gio::Action::print_detailed_name("app.duplicate-server", Some(&path.to_variant()))
+                .as_str(),
+        ),
+    );
+    for (remove_source, label) in [
+        (false, gettext("Copy to Other Connection…")),
+        (true, gettext("Move to Other Connection…")),
+    ] {
+        menu.append(
+            Some(&label),
+            Some(
+                gio::Action::print_detailed_name(
+                    "app.copy-server",
+                    Some(&(path, remove_source).to_variant()),
+                )
+                .as_str(),
+            ),
+        );
+    }
+    menu
+}
+
 fn make_multi_connection_button(path: &str, adapters: Vec<(Cow<str>, Cow<str>)>) -> gtk::Widget {
     let menu = gio::Menu::new();
     for (adapter_id, adapter_label) in adapters {
@@ -210,5 +495,8 @@ fn make_single_connect_button(
 
 enum ServerOrConnection<'a> {
     Server(&'a dyn ServerConnection),
-    Connection(&'a dyn Connection),
+    Connection {
+        connection: &'a dyn Connection,
+        load_on_demand: bool,
+    },
 }
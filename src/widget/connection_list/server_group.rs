@@ -25,7 +25,7 @@ use gtk::glib;
 use libfieldmonitor::connection::*;
 
 use crate::application::FieldMonitorApplication;
-use crate::widget::connection_list::make_server_prefix_suffix;
+use crate::widget::connection_list::{make_server_prefix_suffix, server_subtitle};
 
 mod imp {
     use super::*;
@@ -83,26 +83,55 @@ impl FieldMonitorServerGroup {
     ) -> ConnectionResult<Self> {
         let slf: FieldMonitorServerGroup =
             glib::Object::builder().property("application", app).build();
+        slf.set_title_server(title_server).await?;
+        Ok(slf)
+    }
 
-        if let Some((title_server, full_path)) = title_server {
-            let metadata = title_server.metadata();
-            slf.set_server_title(metadata.title);
-            if let Some(subtitle) = metadata.subtitle {
-                slf.set_server_subtitle(subtitle);
-            }
+    /// Updates the header of the group, which shows the server that contains the servers
+    /// of the group. Groups without one have no header.
+    pub async fn set_title_server(
+        &self,
+        title_server: Option<(&dyn ServerConnection, &[String])>,
+    ) -> ConnectionResult<()> {
+        let imp = self.imp();
+        let Some((title_server, full_path)) = title_server else {
+            imp.server_title_box.set_visible(false);
+            return Ok(());
+        };
 
-            let (prefix, suffix) =
-                make_server_prefix_suffix(title_server, full_path, None::<&adw::ActionRow>).await?;
-            slf.imp().prefix_box.append(&prefix);
-            slf.imp().suffix_box.append(&suffix);
-        } else {
-            slf.imp().server_title_box.set_visible(false);
-        }
+        let metadata = title_server.metadata();
+        let (prefix, suffix) =
+            make_server_prefix_suffix(title_server, full_path, None::<&adw::ActionRow>).await?;
 
-        Ok(slf)
+        self.set_server_subtitle(server_subtitle(&metadata).unwrap_or_default());
+        self.set_server_title(metadata.title);
+        for boxx in [&*imp.prefix_box, &*imp.suffix_box] {
+            while let Some(child) = boxx.last_child() {
+                boxx.remove(&child);
+            }
+        }
+        imp.prefix_box.append(&prefix);
+        imp.suffix_box.append(&suffix);
+        imp.server_title_box.set_visible(true);
+        Ok(())
     }
 
-    pub fn add(&self, row: &impl IsA<gtk::Widget>) {
-        self.imp().servers.append(row);
+    /// Makes `rows` the rows of this group, in this order. Rows that are already in this or
+    /// another group are moved, so their state is kept.
+    pub fn set_rows(&self, rows: &[gtk::ListBoxRow]) {
+        let servers = &*self.imp().servers;
+        for (i, row) in rows.iter().enumerate() {
+            let parent = row.parent();
+            if parent.as_ref() == Some(servers.upcast_ref()) && row.index() == i as i32 {
+                continue;
+            }
+            if let Some(list) = parent.and_downcast::<gtk::ListBox>() {
+                list.remove(row);
+            }
+            servers.insert(row, i as i32);
+        }
+        while let Some(row) = servers.row_at_index(rows.len() as i32) {
+            servers.remove(&row);
+        }
     }
 }
@@ -15,17 +15,25 @@
  *
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
-use crate::widget::connection_list::make_server_prefix_suffix;
+use crate::widget::connection_list::{make_icon, make_server_suffix, server_subtitle};
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use gtk::glib;
 use libfieldmonitor::connection::*;
+use std::cell::{Cell, RefCell};
 
 mod imp {
     use super::*;
 
-    #[derive(Debug, Default)]
-    pub struct FieldMonitorServerRow {}
+    #[derive(Default)]
+    pub struct FieldMonitorServerRow {
+        pub full_path: RefCell<Vec<String>>,
+        /// Set until the suffix of the row has been created.
+        pub server: RefCell<Option<Box<dyn ServerConnection>>>,
+        pub copyable: Cell<bool>,
+        pub prefix: RefCell<Option<gtk::Widget>>,
+        pub suffix: RefCell<Option<gtk::Widget>>,
+    }
 
     #[glib::object_subclass]
     impl ObjectSubclass for FieldMonitorServerRow {
@@ -47,22 +55,58 @@ glib::wrapper! {
 }
 
 impl FieldMonitorServerRow {
-    pub async fn new(
-        full_path: &[String],
-        server: Box<dyn ServerConnection>,
-    ) -> ConnectionResult<Self> {
-        let metadata = server.metadata();
+    /// Creates the row. The connect and action buttons are only added once [`Self::load`] is
+    /// called, since finding out which adapters a server supports may require
+    /// additional requests.
+    ///
+    /// If `copyable` is set, the server can be duplicated, copied and moved.
+    pub fn new(full_path: &[String], server: Box<dyn ServerConnection>, copyable: bool) -> Self {
         let slf: Self = glib::Object::builder()
-            .property("title", &metadata.title)
-            .property("subtitle", &metadata.subtitle)
             .property("selectable", false)
             .build();
+        slf.imp().full_path.replace(full_path.to_vec());
+        slf.update(server, copyable);
+        slf
+    }
+
+    /// Updates the row with a newer state of its server. The buttons of the row are kept
+    /// until [`Self::load`] is called again.
+    pub fn update(&self, server: Box<dyn ServerConnection>, copyable: bool) {
+        let imp = self.imp();
+        let metadata = server.metadata();
+        self.set_title(&metadata.title);
+        self.set_subtitle(&server_subtitle(&metadata).unwrap_or_default());
+
+        let prefix = make_icon(&metadata, &imp.full_path.borrow().join("/"));
+        self.add_prefix(&prefix);
+        if let Some(old_prefix) = imp.prefix.replace(Some(prefix)) {
+            self.remove(&old_prefix);
+        }
+
+        imp.server.replace(Some(server));
+        imp.copyable.set(copyable);
+    }
+
+    pub async fn load(&self) -> ConnectionResult<()> {
+        let Some(server) = self.imp().server.take() else {
+            return Ok(());
+        };
+        let full_path = self.imp().full_path.borrow().clone();
 
-        let (prefix, suffix) =
-            make_server_prefix_suffix(server.as_ref(), full_path, Some(&slf)).await?;
-        slf.add_prefix(&prefix);
-        slf.add_suffix(&suffix);
+        // The new suffix sets it again, if the server has a connect button.
+        self.set_activatable_widget(None::<&gtk::Widget>);
+        let suffix = make_server_suffix(
+            server.as_ref(),
+            &full_path,
+            Some(self),
+            self.imp().copyable.get(),
+        )
+        .await?;
+        self.add_suffix(&suffix);
+        if let Some(old_suffix) = self.imp().suffix.replace(Some(suffix)) {
+            self.remove(&old_suffix);
+        }
 
-        Ok(slf)
+        Ok(())
     }
 }
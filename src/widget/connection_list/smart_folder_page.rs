@@ -0,0 +1,258 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Page of a smart folder, showing the servers of all connections that match its query.
+use crate::application::FieldMonitorApplication;
+use crate::settings::SmartFolder;
+use crate::widget::connection_list::server_row::FieldMonitorServerRow;
+use crate::widget::loading_indicator::FieldMonitorLoadingIndicator;
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::glib;
+use libfieldmonitor::connection::*;
+use log::{debug, warn};
+use std::cell::{Cell, RefCell};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
+    #[properties(wrapper_type = super::FieldMonitorSmartFolderPage)]
+    #[template(resource = "/de/capypara/FieldMonitor/widget/connection_list/smart_folder_page.ui")]
+    pub struct FieldMonitorSmartFolderPage {
+        #[template_child]
+        pub status_stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub group_box: TemplateChild<gtk::Box>,
+        #[property(get, construct_only)]
+        pub application: RefCell<Option<FieldMonitorApplication>>,
+        #[property(get, set)]
+        pub folder_name: RefCell<String>,
+        pub folder: RefCell<SmartFolder>,
+        /// Set while a refresh is running.
+        pub refreshing: Cell<bool>,
+        /// Set if another refresh was requested while one was running.
+        pub refresh_queued: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for FieldMonitorSmartFolderPage {
+        const NAME: &'static str = "FieldMonitorSmartFolderPage";
+        type Type = super::FieldMonitorSmartFolderPage;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            FieldMonitorLoadingIndicator::ensure_type();
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for FieldMonitorSmartFolderPage {}
+    impl WidgetImpl for FieldMonitorSmartFolderPage {}
+    impl BinImpl for FieldMonitorSmartFolderPage {}
+}
+
+glib::wrapper! {
+    pub struct FieldMonitorSmartFolderPage(ObjectSubclass<imp::FieldMonitorSmartFolderPage>)
+        @extends gtk::Widget, adw::Bin;
+}
+
+impl FieldMonitorSmartFolderPage {
+    pub fn new(app: &FieldMonitorApplication, folder: &SmartFolder) -> Self {
+        let slf: Self = glib::Object::builder()
+            .property("application", app)
+            .property("folder-name", &folder.name)
+            .build();
+        slf.imp().folder.replace(folder.clone());
+
+        // Like the dashboard, the page is only updated while it is shown.
+        slf.connect_map(|slf| slf.refresh());
+        for signal in ["connection-updated", "connection-removed"] {
+            app.connect_local(
+                signal,
+                false,
+                glib::clone!(
+                    #[weak]
+                    slf,
+                    #[upgrade_or_default]
+                    move |_| {
+                        slf.refresh_if_mapped();
+                        None
+                    }
+                ),
+            );
+        }
+        if let Some(settings) = app.settings() {
+            settings.connect_server_tags_changed(glib::clone!(
+                #[weak]
+                slf,
+                move |_| slf.refresh_if_mapped()
+            ));
+        }
+        if let Some(poller) = app.status_poller() {
+            poller.subscribe(
+                &slf,
+                glib::clone!(
+                    #[weak]
+                    slf,
+                    move || slf.refresh()
+                ),
+            );
+        }
+
+        slf
+    }
+
+    /// Replaces the query of the folder, eg. after it was edited in the preferences.
+    pub fn set_folder(&self, folder: &SmartFolder) {
+        if *self.imp().folder.borrow() == *folder {
+            return;
+        }
+        self.set_folder_name(folder.name.as_str());
+        self.imp().folder.replace(folder.clone());
+        self.refresh_if_mapped();
+    }
+
+    fn refresh_if_mapped(&self) {
+        if self.is_mapped() {
+            self.refresh();
+        }
+    }
+
+    /// Collects the matching servers again. If a refresh is already running, another one
+    /// follows once it is done.
+    pub fn refresh(&self) {
+        let imp = self.imp();
+        if imp.refreshing.replace(true) {
+            imp.refresh_queued.set(true);
+            return;
+        }
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move {
+                loop {
+                    slf.imp().refresh_queued.set(false);
+                    slf.update().await;
+                    if !slf.imp().refresh_queued.get() {
+                        break;
+                    }
+                }
+                slf.imp().refreshing.set(false);
+            }
+        ));
+    }
+
+    async fn update(&self) {
+        let Some(app) = self.application() else {
+            return;
+        };
+        let folder = self.imp().folder.borrow().clone();
+        let tags = app
+            .settings()
+            .map(|settings| settings.server_tags())
+            .unwrap_or_default();
+
+        // Only loaded connections are searched, so that opening the folder does not load
+        // connections that are loaded on demand.
+        let mut connections = app
+            .connections()
+            .into_iter()
+            .filter(ConnectionInstance::loaded)
+            .collect::<Vec<_>>();
+        connections.sort_by_key(|connection| connection.title().to_lowercase());
+
+        let mut groups = Vec::new();
+        for connection in connections {
+            let connection_id = connection.connection_id();
+            let provider_tag = connection.provider_tag().unwrap_or_default();
+            let servers = match connection.servers().await {
+                Ok(servers) => servers,
+                Err(err) => {
+                    debug!("skipping connection {connection_id} in smart folder: {err:?}");
+                    continue;
+                }
+            };
+
+            let mut rows = Vec::new();
+            let mut pending = vec![(vec![connection_id], servers)];
+            while let Some((parent_path, servers)) = pending.pop() {
+                for (key, server) in servers {
+                    let mut full_path = parent_path.clone();
+                    full_path.push(key.to_string());
+                    match server.servers().await {
+                        Ok(subservers) if !subservers.is_empty() => {
+                            pending.push((full_path.clone(), subservers))
+                        }
+                        Ok(_) => {}
+                        Err(err) => warn!("failed to list servers of {full_path:?}: {err:?}"),
+                    }
+
+                    let metadata = server.metadata();
+                    let server_tags = tags
+                        .get(&full_path.join("/"))
+                        .map(Vec::as_slice)
+                        .unwrap_or_default();
+                    if folder.matches(
+                        &provider_tag,
+                        &metadata.title,
+                        metadata.is_online,
+                        server_tags,
+                    ) {
+                        rows.push((
+                            metadata.title.to_lowercase(),
+                            FieldMonitorServerRow::new(&full_path, server, false),
+                        ));
+                    }
+                }
+            }
+            if rows.is_empty() {
+                continue;
+            }
+
+            rows.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let group = adw::PreferencesGroup::builder()
+                .title(glib::markup_escape_text(&connection.title()))
+                .build();
+            for (_, row) in rows {
+                group.add(&row);
+                glib::spawn_future_local(async move {
+                    if let Err(err) = row.load().await {
+                        warn!("failed to load server row: {:?}", err);
+                    }
+                });
+            }
+            groups.push(group);
+        }
+
+        let imp = self.imp();
+        while let Some(child) = imp.group_box.last_child() {
+            imp.group_box.remove(&child);
+        }
+        let is_empty = groups.is_empty();
+        for group in groups {
+            imp.group_box.append(&group);
+        }
+        imp.status_stack
+            .set_visible_child_name(if is_empty { "empty" } else { "servers" });
+    }
+}
@@ -0,0 +1,94 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Choosing which account to log in with when a server has more than one.
+use adw::prelude::*;
+use gettextrs::gettext;
+
+use libfieldmonitor::i18n::gettext_f;
+
+/// Asks the user which of `accounts` (id, name) to log in to `server_title` with.
+/// Returns the ID of the chosen account and whether the choice should be remembered, or `None`
+/// if the user cancelled.
+pub async fn choose_account(
+    server_title: &str,
+    accounts: &[(String, String)],
+    window: Option<&gtk::Window>,
+) -> Option<(String, bool)> {
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+
+    let mut checks: Vec<gtk::CheckButton> = Vec::with_capacity(accounts.len());
+    for (_, name) in accounts {
+        let check = gtk::CheckButton::builder()
+            .valign(gtk::Align::Center)
+            .build();
+        if let Some(first) = checks.first() {
+            check.set_group(Some(first));
+        } else {
+            check.set_active(true);
+        }
+        let row = adw::ActionRow::builder()
+            .title(name)
+            .activatable_widget(&check)
+            .build();
+        row.add_prefix(&check);
+        list.append(&row);
+        checks.push(check);
+    }
+
+    let remember_list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+    let remember_row = adw::SwitchRow::builder()
+        .title(gettext("Remember Choice"))
+        .subtitle(gettext("Always log in with this account without asking"))
+        .build();
+    remember_list.append(&remember_row);
+
+    let content = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(12)
+        .build();
+    content.append(&list);
+    content.append(&remember_list);
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("Choose Account"))
+        .body(gettext_f(
+            "Which account should be used to log in to {server}?",
+            &[("server", server_title)],
+        ))
+        .extra_child(&content)
+        .close_response("cancel")
+        .default_response("connect")
+        .build();
+    dialog.add_responses(&[
+        ("cancel", &gettext("Cancel")),
+        ("connect", &gettext("Connect")),
+    ]);
+    dialog.set_response_appearance("connect", adw::ResponseAppearance::Suggested);
+
+    if dialog.choose_future(window).await.as_str() != "connect" {
+        return None;
+    }
+    let index = checks.iter().position(|check| check.is_active())?;
+    Some((accounts[index].0.clone(), remember_row.is_active()))
+}
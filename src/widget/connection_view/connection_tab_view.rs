@@ -16,9 +16,11 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 use crate::connection_loader::ConnectionLoader;
+use crate::widget::close_warning_dialog::{CloseWarningKind, FieldMonitorCloseWarningDialog};
 use crate::widget::connection_view::server_screen::FieldMonitorServerScreen;
 use crate::widget::window::FieldMonitorWindow;
 use crate::APP;
+use adw::gdk;
 use adw::gio;
 use adw::prelude::*;
 use adw::subclass::prelude::*;
@@ -59,7 +61,29 @@ mod imp {
     }
 
     #[glib::derived_properties]
-    impl ObjectImpl for FieldMonitorConnectionTabView {}
+    impl ObjectImpl for FieldMonitorConnectionTabView {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+            // Accept connection tabs dragged out of the sidebar of any window.
+            let drop_target = gtk::DropTarget::new(
+                FieldMonitorServerScreen::static_type(),
+                gdk::DragAction::MOVE,
+            );
+            drop_target.connect_drop(glib::clone!(
+                #[weak]
+                obj,
+                #[upgrade_or]
+                false,
+                move |_, value, _, _| {
+                    value
+                        .get::<FieldMonitorServerScreen>()
+                        .is_ok_and(|screen| obj.receive_page(&screen, None))
+                }
+            ));
+            obj.add_controller(drop_target);
+        }
+    }
     impl WidgetImpl for FieldMonitorConnectionTabView {}
     impl BinImpl for FieldMonitorConnectionTabView {}
 }
@@ -108,17 +132,7 @@ impl FieldMonitorConnectionTabView {
         let view =
             FieldMonitorServerScreen::new(&app, Some(window), server_path, adapter_id, loader);
 
-        let tab_view = self.imp().tab_view.get();
-        let tab = self.add_new_page(&view, server_title, Some(connection_title));
-        view.set_close_cb(glib::clone!(
-            #[weak]
-            tab,
-            #[weak]
-            tab_view,
-            move || {
-                tab_view.close_page(&tab);
-            }
-        ));
+        self.add_new_page(&view, server_title, Some(connection_title));
     }
 
     pub fn n_pages(&self) -> u32 {
@@ -137,6 +151,31 @@ impl FieldMonitorConnectionTabView {
             .unwrap_or_default()
     }
 
+    /// Server paths and adapter IDs of all open connection views, in tab order.
+    pub fn sessions(&self) -> Vec<(String, String)> {
+        self.screens()
+            .into_iter()
+            .map(|view| (view.server_path(), view.adapter_id()))
+            .collect()
+    }
+
+    /// All open connection views, in tab order.
+    pub fn screens(&self) -> Vec<FieldMonitorServerScreen> {
+        self.imp()
+            .tab_view
+            .pages()
+            .iter::<adw::TabPage>()
+            .filter_map_ok(|tab| tab.child().downcast::<FieldMonitorServerScreen>().ok())
+            .collect::<Result<_, _>>()
+            .unwrap_or_default()
+    }
+
+    fn on_page_closing(&self, page: &adw::TabPage) {
+        if self.visible_page().as_ref() == Some(page) {
+            self.set_visible_page(None::<&adw::TabPage>);
+        }
+    }
+
     pub(super) fn inner(&self) -> adw::TabView {
         self.imp().tab_view.get()
     }
@@ -159,6 +198,7 @@ impl FieldMonitorConnectionTabView {
             if let Some(subtitle) = subtitle {
                 view.set_subtitle(subtitle);
             }
+            view.set_server_title(title);
         }
 
         tab_page.set_title(title);
@@ -182,21 +222,39 @@ impl FieldMonitorConnectionTabView {
         let tab_view = new_window.tab_view();
         imp.tab_view.transfer_page(page, &tab_view.inner(), 0);
 
-        if let Ok(view) = child.downcast::<FieldMonitorServerScreen>() {
-            view.set_close_cb(glib::clone!(
-                #[weak]
-                page,
-                #[weak]
-                tab_view,
-                move || {
-                    tab_view.imp().tab_view.get().close_page(&page);
-                }
-            ));
-        }
-
         new_window.present();
         new_window.select_connection_view();
     }
+
+    /// Moves the page of `screen` into this tab view at `position` (at the end if `None`),
+    /// keeping its session alive. The page may belong to the tab view of another window; if it
+    /// already belongs to this one, it is reordered instead.
+    pub fn receive_page(&self, screen: &FieldMonitorServerScreen, position: Option<i32>) -> bool {
+        let Some(source) = screen
+            .ancestor(FieldMonitorConnectionTabView::static_type())
+            .and_downcast::<FieldMonitorConnectionTabView>()
+        else {
+            return false;
+        };
+        let source_inner = source.inner();
+        let page = source_inner.page(screen);
+        let inner = self.inner();
+
+        if &source == self {
+            let last = inner.n_pages() - 1;
+            inner.reorder_page(&page, position.map_or(last, |position| position.min(last)));
+        } else {
+            let n_pages = inner.n_pages();
+            let position = position.map_or(n_pages, |position| position.min(n_pages));
+            source_inner.transfer_page(&page, &inner, position);
+        }
+        self.set_visible_page(Some(&page));
+        if let Some(window) = self.root().and_downcast::<FieldMonitorWindow>() {
+            window.select_connection_view();
+            window.present();
+        }
+        true
+    }
 }
 
 #[gtk::template_callbacks]
@@ -223,6 +281,7 @@ impl FieldMonitorConnectionTabView {
     fn on_tab_view_selected_page_changed(&self) {
         let new_page = self.imp().tab_view.selected_page();
         if let Some(new_page) = new_page {
+            new_page.set_needs_attention(false);
             if self.visible_page().as_ref() == Some(&new_page) {
                 self.set_visible_page(Some(&new_page))
             }
@@ -231,11 +290,62 @@ impl FieldMonitorConnectionTabView {
 
     #[template_callback]
     fn on_tab_view_close_page(&self, page: &adw::TabPage) -> glib::Propagation {
-        if self.visible_page().as_ref() == Some(page) {
-            self.set_visible_page(None::<&adw::TabPage>);
+        let connected_view = page
+            .child()
+            .downcast::<FieldMonitorServerScreen>()
+            .ok()
+            .filter(FieldMonitorServerScreen::is_connected);
+        let Some(view) = connected_view.filter(|_| FieldMonitorCloseWarningDialog::is_enabled())
+        else {
+            self.on_page_closing(page);
+            return glib::Propagation::Proceed;
+        };
+
+        // The tab view waits for close_page_finish to be called once the user decided.
+        let dialog = FieldMonitorCloseWarningDialog::new(
+            CloseWarningKind::Tab,
+            [(view.title(), view.subtitle())],
+        );
+        dialog.connect_closure(
+            "response",
+            false,
+            glib::closure_local!(
+                #[weak(rename_to = slf)]
+                self,
+                #[weak]
+                page,
+                move |_: &FieldMonitorCloseWarningDialog, response: &str| {
+                    let confirmed = response == FieldMonitorCloseWarningDialog::RESPONSE_CLOSE;
+                    if confirmed {
+                        slf.on_page_closing(&page);
+                    }
+                    slf.imp().tab_view.close_page_finish(&page, confirmed);
+                }
+            ),
+        );
+        dialog.present(Some(self));
+        glib::Propagation::Stop
+    }
+
+    #[template_callback]
+    fn on_tab_view_page_attached(&self, page: &adw::TabPage) {
+        // Pages can be attached when opened or when moved over from another window.
+        let Ok(view) = page.child().downcast::<FieldMonitorServerScreen>() else {
+            return;
+        };
+        let tab_view = self.imp().tab_view.get();
+        view.set_close_cb(glib::clone!(
+            #[weak]
+            page,
+            #[weak]
+            tab_view,
+            move || {
+                tab_view.close_page(&page);
+            }
+        ));
+        if let Some(window) = self.root().and_downcast::<FieldMonitorWindow>() {
+            view.set_window(Some(&window));
         }
-        // TODO: confirmation dialog
-        glib::Propagation::Proceed
     }
 
     #[template_callback]
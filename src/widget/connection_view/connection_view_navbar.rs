@@ -15,7 +15,11 @@
  *
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
+use crate::widget::connection_view::server_screen::{
+    adapter_icon_name, FieldMonitorServerScreen, SessionStatus,
+};
 use crate::widget::connection_view::FieldMonitorConnectionTabView;
+use adw::gdk;
 use adw::gio;
 use adw::prelude::*;
 use adw::subclass::prelude::*;
@@ -25,6 +29,9 @@ use log::{debug, warn};
 use std::cell::RefCell;
 use std::collections::HashMap;
 
+/// Size of the badge showing the state of a session on its adapter icon.
+const SESSION_BADGE_SIZE: i32 = 8;
+
 mod imp {
     use super::*;
     use adw::gdk::pango;
@@ -63,7 +70,24 @@ mod imp {
     }
 
     #[glib::derived_properties]
-    impl ObjectImpl for FieldMonitorNavbarConnectionView {}
+    impl ObjectImpl for FieldMonitorNavbarConnectionView {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+            let drop_target = gtk::DropTarget::new(
+                FieldMonitorServerScreen::static_type(),
+                gdk::DragAction::MOVE,
+            );
+            drop_target.connect_drop(glib::clone!(
+                #[weak]
+                obj,
+                #[upgrade_or]
+                false,
+                move |_, value, _, y| obj.on_list_drop(value, y)
+            ));
+            self.list.add_controller(drop_target);
+        }
+    }
     impl WidgetImpl for FieldMonitorNavbarConnectionView {}
     impl BinImpl for FieldMonitorNavbarConnectionView {}
 
@@ -199,6 +223,11 @@ mod imp {
                     row.add_suffix(&close_button);
                     row.add_suffix(&new_window_button);
 
+                    if let Ok(screen) = page.child().downcast::<FieldMonitorServerScreen>() {
+                        row.add_prefix(&Self::build_status_icon(&screen));
+                        row.add_controller(Self::build_drag_source(tab_view, &page, &screen));
+                    }
+
                     page.bind_property("title", &item, "label")
                         .sync_create()
                         .build();
@@ -221,6 +250,95 @@ mod imp {
 
             self.obj().set_visible(!self_empty);
         }
+
+        /// The icon of the adapter of `screen` with a badge showing the state of its session.
+        fn build_status_icon(screen: &FieldMonitorServerScreen) -> gtk::Widget {
+            let badge = gtk::Stack::builder()
+                .halign(gtk::Align::End)
+                .valign(gtk::Align::End)
+                .css_classes(["fm-session-badge"])
+                .build();
+            badge.add_named(
+                &adw::Spinner::builder()
+                    .width_request(SESSION_BADGE_SIZE)
+                    .height_request(SESSION_BADGE_SIZE)
+                    .build(),
+                Some(SessionStatus::Connecting.name()),
+            );
+            for (status, icon_name) in [
+                (SessionStatus::Connected, "circle-filled-symbolic"),
+                (SessionStatus::Disconnected, "circle-outline-thick-symbolic"),
+                (SessionStatus::Failed, "dialog-error-symbolic"),
+            ] {
+                badge.add_named(
+                    &gtk::Image::builder()
+                        .icon_name(icon_name)
+                        .pixel_size(SESSION_BADGE_SIZE)
+                        .css_classes([status.name()])
+                        .build(),
+                    Some(status.name()),
+                );
+            }
+            screen
+                .bind_property("session-status", &badge, "visible-child-name")
+                .transform_to(|_, status: SessionStatus| Some(status.name()))
+                .sync_create()
+                .build();
+
+            let overlay = gtk::Overlay::builder()
+                .valign(gtk::Align::Center)
+                .child(
+                    &gtk::Image::builder()
+                        .icon_name(adapter_icon_name(&screen.adapter_id()))
+                        .margin_end(SESSION_BADGE_SIZE / 2)
+                        .margin_bottom(SESSION_BADGE_SIZE / 2)
+                        .build(),
+                )
+                .build();
+            overlay.add_overlay(&badge);
+            screen
+                .bind_property("session-status", &overlay, "tooltip-text")
+                .transform_to(|_, status: SessionStatus| Some(status.label()))
+                .sync_create()
+                .build();
+            overlay.upcast()
+        }
+
+        /// Allows dragging the row of `page` into the sidebar or connection view of another
+        /// window. Dropping it anywhere else moves the page into a new window.
+        fn build_drag_source(
+            tab_view: &FieldMonitorConnectionTabView,
+            page: &adw::TabPage,
+            screen: &FieldMonitorServerScreen,
+        ) -> gtk::DragSource {
+            let drag_source = gtk::DragSource::builder()
+                .actions(gdk::DragAction::MOVE)
+                .content(&gdk::ContentProvider::for_value(&screen.to_value()))
+                .build();
+            drag_source.connect_drag_begin(|drag_source, _| {
+                if let Some(row) = drag_source.widget() {
+                    let icon = gtk::WidgetPaintable::new(Some(&row));
+                    drag_source.set_icon(Some(&icon), 0, 0);
+                }
+            });
+            drag_source.connect_drag_cancel(glib::clone!(
+                #[weak]
+                tab_view,
+                #[weak]
+                page,
+                #[upgrade_or]
+                false,
+                move |_, _, reason| {
+                    if reason == gdk::DragCancelReason::NoTarget {
+                        tab_view.move_page_to_new_window(&page);
+                        true
+                    } else {
+                        false
+                    }
+                }
+            ));
+            drag_source
+        }
     }
 }
 
@@ -243,6 +361,17 @@ impl FieldMonitorNavbarConnectionView {
         }
     }
 
+    fn on_list_drop(&self, value: &glib::Value, y: f64) -> bool {
+        let Ok(screen) = value.get::<FieldMonitorServerScreen>() else {
+            return false;
+        };
+        let Some(tab_view) = self.tab_view() else {
+            return false;
+        };
+        let position = self.imp().list.row_at_y(y as i32).map(|row| row.index());
+        tab_view.receive_page(&screen, position)
+    }
+
     fn on_tab_view_visible_page_changed(&self, page: Option<adw::TabPage>) {
         debug!("tab view nav bar: on_tab_view_visible_page_changed");
         for row in self.imp().rows.borrow().values() {
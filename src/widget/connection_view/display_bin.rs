@@ -0,0 +1,201 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Container of the display of a session, which can limit the frame rate graphical sessions are
+//! drawn with and the filter they are scaled with.
+
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::{glib, gsk};
+use log::debug;
+
+/// Interval in which the number of drawn and skipped redraws is logged, in microseconds.
+const STATS_INTERVAL: i64 = 10_000_000;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct FieldMonitorDisplayBin {
+        /// Maximum number of frames drawn per second, 0 for no limit.
+        pub max_fps: Cell<u32>,
+        pub scaling_filter: Cell<Option<gsk::ScalingFilter>>,
+        /// The last frame drawn, reused until the next frame is due.
+        pub last_frame: RefCell<Option<gsk::RenderNode>>,
+        pub last_frame_time: Cell<i64>,
+        /// Draws the next frame once it's due, if frames were skipped.
+        pub redraw_source: RefCell<Option<glib::SourceId>>,
+        pub drawn_frames: Cell<u32>,
+        pub skipped_frames: Cell<u32>,
+        pub stats_time: Cell<i64>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for FieldMonitorDisplayBin {
+        const NAME: &'static str = "FieldMonitorDisplayBin";
+        type Type = super::FieldMonitorDisplayBin;
+        type ParentType = adw::Bin;
+    }
+
+    impl ObjectImpl for FieldMonitorDisplayBin {
+        fn dispose(&self) {
+            if let Some(source) = self.redraw_source.take() {
+                source.remove();
+            }
+        }
+    }
+
+    impl WidgetImpl for FieldMonitorDisplayBin {
+        fn size_allocate(&self, width: i32, height: i32, baseline: i32) {
+            self.parent_size_allocate(width, height, baseline);
+            // The last frame has the old size.
+            self.last_frame.take();
+        }
+
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let obj = self.obj();
+            // Terminals and other widgets are drawn as usual.
+            let Some(child) = obj.child().filter(|child| child.is::<rdw::Display>()) else {
+                self.parent_snapshot(snapshot);
+                return;
+            };
+            let max_fps = self.max_fps.get();
+            let now = glib::monotonic_time();
+            self.log_stats(now);
+
+            if max_fps > 0 {
+                let interval = 1_000_000 / i64::from(max_fps);
+                let elapsed = now - self.last_frame_time.get();
+                if elapsed < interval {
+                    if let Some(frame) = &*self.last_frame.borrow() {
+                        snapshot.append_node(frame);
+                        self.skipped_frames.set(self.skipped_frames.get() + 1);
+                        obj.schedule_redraw(interval - elapsed);
+                        return;
+                    }
+                }
+            }
+
+            let child_snapshot = gtk::Snapshot::new();
+            obj.snapshot_child(&child, &child_snapshot);
+            let Some(mut frame) = child_snapshot.to_node() else {
+                self.last_frame.take();
+                return;
+            };
+            if let Some(filter) = self.scaling_filter.get() {
+                frame = with_scaling_filter(frame, filter);
+            }
+            snapshot.append_node(&frame);
+            self.drawn_frames.set(self.drawn_frames.get() + 1);
+            self.last_frame_time.set(now);
+            self.last_frame.replace((max_fps > 0).then_some(frame));
+        }
+    }
+
+    impl BinImpl for FieldMonitorDisplayBin {}
+
+    impl FieldMonitorDisplayBin {
+        fn log_stats(&self, now: i64) {
+            let since = now - self.stats_time.get();
+            if since < STATS_INTERVAL {
+                return;
+            }
+            if self.stats_time.get() != 0 {
+                let secs = since as f64 / 1_000_000.0;
+                debug!(
+                    "display: {:.1} redraws/s drawn, {:.1} redraws/s skipped",
+                    f64::from(self.drawn_frames.get()) / secs,
+                    f64::from(self.skipped_frames.get()) / secs,
+                );
+            }
+            self.stats_time.set(now);
+            self.drawn_frames.set(0);
+            self.skipped_frames.set(0);
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct FieldMonitorDisplayBin(ObjectSubclass<imp::FieldMonitorDisplayBin>)
+        @extends gtk::Widget, adw::Bin;
+}
+
+impl FieldMonitorDisplayBin {
+    /// Limits how many frames per second are drawn, 0 for no limit. Redraws in between are skipped
+    /// and show the last frame, the session itself still receives and decodes every update.
+    pub fn set_max_fps(&self, max_fps: u32) {
+        let imp = self.imp();
+        if imp.max_fps.replace(max_fps) != max_fps {
+            imp.last_frame.take();
+            self.queue_draw();
+        }
+    }
+
+    /// The filter used when the session is scaled, `None` to leave the choice to GTK.
+    pub fn set_scaling_filter(&self, filter: Option<gsk::ScalingFilter>) {
+        let imp = self.imp();
+        if imp.scaling_filter.replace(filter) != filter {
+            imp.last_frame.take();
+            self.queue_draw();
+        }
+    }
+
+    fn schedule_redraw(&self, delay_us: i64) {
+        let imp = self.imp();
+        if imp.redraw_source.borrow().is_some() {
+            return;
+        }
+        let source = glib::timeout_add_local_once(
+            Duration::from_micros(delay_us.max(0) as u64),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move || {
+                    slf.imp().redraw_source.take();
+                    slf.queue_draw();
+                }
+            ),
+        );
+        imp.redraw_source.replace(Some(source));
+    }
+}
+
+/// Draws the textures in `node` with `filter`. Only textures inside of containers, transforms
+/// and clips are found, which is how displays draw the remote screen.
+fn with_scaling_filter(node: gsk::RenderNode, filter: gsk::ScalingFilter) -> gsk::RenderNode {
+    if let Some(texture) = node.downcast_ref::<gsk::TextureNode>() {
+        gsk::TextureScaleNode::new(&texture.texture(), &texture.bounds(), filter).upcast()
+    } else if let Some(container) = node.downcast_ref::<gsk::ContainerNode>() {
+        let children = (0..container.n_children())
+            .map(|i| with_scaling_filter(container.child(i), filter))
+            .collect::<Vec<_>>();
+        gsk::ContainerNode::new(&children).upcast()
+    } else if let Some(transform) = node.downcast_ref::<gsk::TransformNode>() {
+        gsk::TransformNode::new(
+            with_scaling_filter(transform.child(), filter),
+            &transform.transform(),
+        )
+        .upcast()
+    } else if let Some(clip) = node.downcast_ref::<gsk::ClipNode>() {
+        gsk::ClipNode::new(with_scaling_filter(clip.child(), filter), &clip.clip()).upcast()
+    } else {
+        node
+    }
+}
@@ -16,9 +16,14 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 
+mod account_picker;
 mod connection_tab_view;
 mod connection_view_navbar;
+mod display_bin;
+mod observer_window;
+mod recorder;
 mod server_screen;
+mod term_watch;
 pub use connection_tab_view::*;
 pub use connection_view_navbar::*;
 pub use server_screen::*;
@@ -0,0 +1,99 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Read-only mirrors of a session in separate windows, eg. to present it on a projector.
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk::gdk::Key;
+use gtk::glib;
+use libfieldmonitor::i18n::gettext_f;
+
+/// Opens a window mirroring what `display_bin` currently shows. The mirror follows when the
+/// display is replaced, eg. on reconnects. It only duplicates the rendered frames, input is not
+/// accepted and no further connection to the server is made.
+pub fn open_observer_window(
+    display_bin: &adw::Bin,
+    title: &str,
+    application: Option<&gtk::Application>,
+) -> adw::Window {
+    let paintable = gtk::WidgetPaintable::new(gtk::Widget::NONE);
+    display_bin
+        .bind_property("child", &paintable, "widget")
+        .sync_create()
+        .build();
+
+    let picture = gtk::Picture::builder()
+        .paintable(&paintable)
+        .content_fit(gtk::ContentFit::Contain)
+        .can_shrink(true)
+        .can_target(false)
+        .hexpand(true)
+        .vexpand(true)
+        .build();
+
+    let fullscreen_button = gtk::Button::builder()
+        .icon_name("view-fullscreen-symbolic")
+        .tooltip_text(gettext("Fullscreen"))
+        .build();
+    let header_bar = adw::HeaderBar::new();
+    header_bar.pack_end(&fullscreen_button);
+
+    let toolbar_view = adw::ToolbarView::new();
+    toolbar_view.add_top_bar(&header_bar);
+    toolbar_view.set_content(Some(&picture));
+
+    let window = adw::Window::builder()
+        .title(gettext_f("{title} (Observer)", &[("title", title)]))
+        .default_width(960)
+        .default_height(600)
+        .content(&toolbar_view)
+        .build();
+    window.set_application(application);
+    window
+        .bind_property("fullscreened", &toolbar_view, "reveal-top-bars")
+        .invert_boolean()
+        .sync_create()
+        .build();
+
+    fullscreen_button.connect_clicked(glib::clone!(
+        #[weak]
+        window,
+        move |_| window.fullscreen()
+    ));
+    let key_controller = gtk::EventControllerKey::new();
+    key_controller.connect_key_pressed(glib::clone!(
+        #[weak]
+        window,
+        #[upgrade_or]
+        glib::Propagation::Proceed,
+        move |_, key, _, _| match key {
+            Key::Escape if window.is_fullscreen() => {
+                window.unfullscreen();
+                glib::Propagation::Stop
+            }
+            Key::F11 => {
+                window.set_fullscreened(!window.is_fullscreen());
+                glib::Propagation::Stop
+            }
+            _ => glib::Propagation::Proceed,
+        }
+    ));
+    window.add_controller(key_controller);
+
+    window.present();
+    window
+}
@@ -0,0 +1,161 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Recording graphical sessions to video files.
+use std::cell::Cell;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use futures::StreamExt;
+use gst::prelude::*;
+use gtk::gdk;
+use log::warn;
+
+/// Frames per second of recordings. Remote desktops rarely change faster than this, and it keeps
+/// the files small.
+pub const RECORDING_FRAME_RATE: u32 = 10;
+
+/// Records the frames of a session into a WebM or, if the path ends with `.mp4`, MP4 file.
+///
+/// The video has the size of the first frame. Frames of other sizes, eg. after the remote
+/// resolution changed, are scaled to fit.
+pub struct SessionRecorder {
+    pipeline: gst::Pipeline,
+    source: gst_app::AppSrc,
+    sink: gst::Element,
+    started: Instant,
+    frame_size: Cell<(u32, u32)>,
+    max_bytes: Option<u64>,
+}
+
+impl SessionRecorder {
+    /// Starts recording to `path`. The recording ends on its own once the file reaches
+    /// `max_size_mib` MiB, unless that is 0.
+    pub fn new(path: &Path, width: u32, height: u32, max_size_mib: u32) -> anyhow::Result<Self> {
+        gst::init()?;
+        // Most encoders require even dimensions.
+        let (width, height) = ((width & !1).max(2), (height & !1).max(2));
+        let encoder = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("mp4") => "x264enc tune=zerolatency ! mp4mux",
+            _ => "vp8enc deadline=1 ! webmmux",
+        };
+        let pipeline = gst::parse::launch(&format!(
+            "appsrc name=source is-live=true format=time ! videoconvert ! videoscale \
+             ! video/x-raw,width={width},height={height},pixel-aspect-ratio=1/1 \
+             ! {encoder} ! filesink name=sink"
+        ))?
+        .downcast::<gst::Pipeline>()
+        .map_err(|_| anyhow!("recording pipeline is not a pipeline"))?;
+
+        let source = pipeline
+            .by_name("source")
+            .and_then(|element| element.downcast::<gst_app::AppSrc>().ok())
+            .ok_or_else(|| anyhow!("recording pipeline has no source"))?;
+        let sink = pipeline
+            .by_name("sink")
+            .ok_or_else(|| anyhow!("recording pipeline has no sink"))?;
+        sink.set_property("location", path.to_string_lossy().as_ref());
+        source.set_caps(Some(&frame_caps(width, height)));
+
+        pipeline.set_state(gst::State::Playing)?;
+        Ok(Self {
+            pipeline,
+            source,
+            sink,
+            started: Instant::now(),
+            frame_size: Cell::new((width, height)),
+            max_bytes: (max_size_mib > 0).then(|| u64::from(max_size_mib) * 1024 * 1024),
+        })
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Adds `texture` as the current frame. Returns `false` once the maximum size is reached,
+    /// the recording should then be finished.
+    pub fn push_frame(&self, texture: &gdk::Texture) -> bool {
+        let (width, height) = (texture.width() as u32, texture.height() as u32);
+        if self.frame_size.replace((width, height)) != (width, height) {
+            self.source.set_caps(Some(&frame_caps(width, height)));
+        }
+
+        let mut downloader = gdk::TextureDownloader::new(texture);
+        downloader.set_format(gdk::MemoryFormat::B8g8r8a8);
+        let (bytes, stride) = downloader.download_bytes();
+        let row_len = width as usize * 4;
+        let data = if stride == row_len {
+            bytes.to_vec()
+        } else {
+            bytes
+                .chunks(stride)
+                .flat_map(|row| &row[..row_len])
+                .copied()
+                .collect()
+        };
+
+        let mut buffer = gst::Buffer::from_mut_slice(data);
+        buffer
+            .get_mut()
+            .unwrap()
+            .set_pts(gst::ClockTime::from_nseconds(
+                self.elapsed().as_nanos() as u64
+            ));
+        if let Err(err) = self.source.push_buffer(buffer) {
+            warn!("failed to push frame to recording: {err}");
+        }
+
+        match (
+            self.max_bytes,
+            self.sink.query_position::<gst::format::Bytes>(),
+        ) {
+            (Some(max_bytes), Some(written)) => *written < max_bytes,
+            _ => true,
+        }
+    }
+
+    /// Ends the recording and waits until the file is complete.
+    pub async fn finish(self) {
+        if let Err(err) = self.source.end_of_stream() {
+            warn!("failed to end recording: {err}");
+        } else if let Some(bus) = self.pipeline.bus() {
+            let mut messages =
+                bus.stream_filtered(&[gst::MessageType::Eos, gst::MessageType::Error]);
+            if let Some(gst::MessageView::Error(err)) =
+                messages.next().await.as_ref().map(gst::Message::view)
+            {
+                warn!("recording failed: {}", err.error());
+            }
+        }
+        if let Err(err) = self.pipeline.set_state(gst::State::Null) {
+            warn!("failed to stop recording: {err}");
+        }
+    }
+}
+
+fn frame_caps(width: u32, height: u32) -> gst::Caps {
+    gst::Caps::builder("video/x-raw")
+        .field("format", "BGRA")
+        .field("width", width as i32)
+        .field("height", height as i32)
+        .field(
+            "framerate",
+            gst::Fraction::new(RECORDING_FRAME_RATE as i32, 1),
+        )
+        .build()
+}
@@ -15,7 +15,7 @@
  *
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
-use adw::gdk::{Key, ModifierType};
+use adw::gdk::{Key, ModifierType, BUTTON_MIDDLE, BUTTON_PRIMARY};
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use anyhow::anyhow;
@@ -23,6 +23,7 @@ use async_std::task::sleep;
 use futures::lock::Mutex;
 use gettextrs::gettext;
 use glib::object::ObjectExt;
+use gtk::gdk;
 use gtk::gio;
 use gtk::glib;
 use log::{debug, info, warn};
@@ -30,23 +31,91 @@ use rdw::DisplayExt;
 use std::borrow::Cow;
 use std::cell::Cell;
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::iter;
 use std::rc::Rc;
 use std::time::Duration;
 use vte::TerminalExt;
 
+use libfieldmonitor::adapter::rdp::RdpAdapter;
+use libfieldmonitor::adapter::spice::SpiceAdapter;
 use libfieldmonitor::adapter::types::{AdapterDisplay, AdapterDisplayWidget};
+use libfieldmonitor::adapter::vnc::VncAdapter;
+use libfieldmonitor::adapter::vte_pty::VtePtyAdapter;
+use libfieldmonitor::adapter::web::WebAdapter;
 use libfieldmonitor::connection::{ConnectionError, ConnectionResult};
 use libfieldmonitor::i18n::gettext_f;
+use libfieldmonitor::resolve::{set_preferred_family, AddressFamily, ReachabilityError};
 
 use crate::application::FieldMonitorApplication;
+use crate::audit_log::{AuditEntry, AuditEvent};
 use crate::connection_loader::ConnectionLoader;
-use crate::settings::{FieldMonitorSettings, SettingHeaderBarBehavior};
+use crate::diagnostics::present_problem_report;
+use crate::event_sinks::{SinkEvent, SinkEventKind};
+use crate::settings::{
+    FieldMonitorSettings, SessionToolbarItem, SettingHeaderBarBehavior, SnippetKind,
+};
 use crate::util::configure_vte_styling;
+use crate::widget::connection_view::account_picker::choose_account;
+use crate::widget::connection_view::display_bin::FieldMonitorDisplayBin;
+use crate::widget::connection_view::observer_window::open_observer_window;
+use crate::widget::connection_view::recorder::{SessionRecorder, RECORDING_FRAME_RATE};
+use crate::widget::connection_view::term_watch::{present_term_watch_dialog, TermWatcher};
 use crate::widget::foucs_grabber::FieldMonitorFocusGrabber;
 use crate::widget::grab_note::FieldMonitorGrabNote;
+use crate::widget::loading_indicator::set_loading_paintable;
 use crate::widget::window::FieldMonitorWindow;
 
+const MIN_TERM_COLUMNS: f64 = 20.0;
+const MAX_TERM_COLUMNS: f64 = 500.0;
+const MIN_TERM_ROWS: f64 = 5.0;
+const MAX_TERM_ROWS: f64 = 200.0;
+/// Properties that are stored per server and restored when it is opened again.
+const VIEW_STATE_PROPERTIES: [&str; 4] = [
+    "dynamic-resize",
+    "scale-to-window",
+    "integer-scaling",
+    "view-only",
+];
+/// Properties that are stored per connection and apply to all of its servers.
+const CONNECTION_VIEW_STATE_PROPERTIES: [&str; 3] =
+    ["relative-pointer", "grab-on-focus", "middle-click-paste"];
+/// Actions whose shortcuts take precedence over the input of terminals.
+const TERM_SHORTCUT_ACTIONS: [&str; 8] = [
+    "view.term-copy",
+    "view.term-paste",
+    "view.term-select-all",
+    "view.term-zoom-reset",
+    "view.term-zoom-in",
+    "view.term-zoom-out",
+    "view.term-find",
+    "view.close",
+];
+/// Name of the stored font scale of terminals.
+const VIEW_STATE_ZOOM: &str = "zoom";
+/// Font scale of terminals is changed in steps of a tenth, within the given bounds.
+const TERM_ZOOM_STEPS_PER_UNIT: f64 = 10.0;
+const TERM_ZOOM_MIN: f64 = 0.5;
+const TERM_ZOOM_MAX: f64 = 4.0;
+/// Name of the stored account choice, empty if the user is asked.
+const VIEW_STATE_ACCOUNT: &str = "account";
+
+// PCRE2 compile flags, as used by vte::Regex.
+const PCRE2_CASELESS: u32 = 0x00000008;
+const PCRE2_MULTILINE: u32 = 0x00000400;
+/// Web links that are highlighted in terminals, in addition to hyperlinks sent by the remote
+/// side. Trailing punctuation is not considered part of the link.
+const TERM_LINK_PATTERN: &str = r#"https?://[^\s<>"'`]*[^\s<>"'`.,;:!?)\]}]"#;
+
+/// Whether a link found in a terminal may be opened. Hyperlinks sent by the remote side can use
+/// any scheme, only http and https links are passed on to the system.
+fn is_web_link(link: &str) -> bool {
+    glib::Uri::parse(link, glib::UriFlags::NONE).is_ok_and(|uri| {
+        let scheme = uri.scheme();
+        scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https")
+    })
+}
+
 mod imp {
     use super::*;
 
@@ -57,17 +126,21 @@ mod imp {
         #[template_child]
         pub toolbar_view: TemplateChild<adw::ToolbarView>,
         #[template_child]
+        pub session_toolbar: TemplateChild<gtk::Box>,
+        #[template_child]
         pub header_bar: TemplateChild<adw::HeaderBar>,
         #[template_child]
         pub outer_stack: TemplateChild<gtk::Stack>,
         #[template_child]
         pub status_stack: TemplateChild<gtk::Stack>,
         #[template_child]
+        pub loading_status_page: TemplateChild<adw::StatusPage>,
+        #[template_child]
         pub error_status_page: TemplateChild<adw::StatusPage>,
         #[template_child]
         pub button_fullscreen: TemplateChild<gtk::Button>,
         #[template_child]
-        pub display_bin: TemplateChild<adw::Bin>,
+        pub display_bin: TemplateChild<FieldMonitorDisplayBin>,
         #[template_child]
         pub focus_grabber: TemplateChild<FieldMonitorFocusGrabber>,
         #[template_child]
@@ -76,10 +149,25 @@ mod imp {
         pub menu_button: TemplateChild<gtk::MenuButton>,
         #[template_child]
         pub show_output_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub report_problem_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub retry_family_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub term_search_bar: TemplateChild<gtk::SearchBar>,
+        #[template_child]
+        pub term_search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub term_search_regex_button: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub recording_label: TemplateChild<gtk::Label>,
         #[property(get, construct_only)]
         pub application: RefCell<Option<FieldMonitorApplication>>,
-        #[property(get, construct_only, nullable)]
+        /// The window the screen is currently shown in. Changes when the tab is moved between
+        /// windows.
+        #[property(get, set = Self::set_window, nullable)]
         pub window: RefCell<Option<FieldMonitorWindow>>,
+        pub window_fullscreened_handler: RefCell<Option<glib::SignalHandlerId>>,
         #[property(get, construct_only)]
         pub server_path: RefCell<String>,
         #[property(get, construct_only)]
@@ -88,24 +176,87 @@ mod imp {
         pub title: RefCell<String>,
         #[property(get, set)]
         pub subtitle: RefCell<String>,
+        /// Name of the server as reported by its provider.
+        #[property(get, set)]
+        pub server_title: RefCell<String>,
+        /// Title the remote session reports for itself, empty if it reports none.
+        #[property(get, set)]
+        pub remote_title: RefCell<String>,
         #[property(get, set, default = true)]
         pub reveal_osd_controls: Cell<bool>,
         #[property(get, set)]
         pub dynamic_resize: Cell<bool>,
         #[property(get, set)]
         pub scale_to_window: Cell<bool>,
+        /// Whether displays scaled to the window are only scaled by whole multiples of the
+        /// remote resolution, which keeps text crisp.
+        #[property(get, set)]
+        pub integer_scaling: Cell<bool>,
         #[property(get, set)]
         pub allow_reauths: Cell<bool>,
+        #[property(get, set)]
+        pub view_only: Cell<bool>,
+        /// Whether the pointer is sent as relative motion instead of absolute positions, as
+        /// needed by some games and CAD programs.
+        #[property(get, set)]
+        pub relative_pointer: Cell<bool>,
+        /// Whether input is grabbed as soon as the window is focused instead of on click.
+        #[property(get, set)]
+        pub grab_on_focus: Cell<bool>,
+        /// Whether the middle mouse button pastes the primary selection into terminals.
+        #[property(get, set, default = true)]
+        pub middle_click_paste: Cell<bool>,
+        #[property(get, set)]
+        pub sharing_folder: Cell<bool>,
+        /// Whether the local microphone is redirected to the remote server.
+        #[property(get, set)]
+        pub microphone: Cell<bool>,
+        #[property(get, set, default = true)]
+        pub term_resize_with_window: Cell<bool>,
+        /// Whether the session is being recorded to a video file.
+        #[property(get, set)]
+        pub recording: Cell<bool>,
+        /// State of the session as shown in the sidebar.
+        #[property(get, builder(SessionStatus::default()))]
+        pub session_status: Cell<SessionStatus>,
         // None: Status not initialized yet
         // true: Connected
         // false: Disconnected
         pub connection_state: RefCell<Option<bool>>,
         pub connection_loader: Mutex<Option<ConnectionLoader>>,
+        pub supports_multiple_sessions: Cell<bool>,
         pub adapter: RefCell<Option<Box<dyn AdapterDisplay>>>,
+        // Whether the current adapter redirects the microphone, None if it can't.
+        pub adapter_microphone: Cell<Option<bool>>,
+        // Microphone redirection chosen in this view, overriding the one of the server.
+        pub microphone_override: Cell<Option<bool>>,
         // Generation of the connection. This is used to prevent "old" adapters from triggering
         // the connection / disconnection events.
         pub connection_generation: RefCell<u32>,
         pub close_cb: RefCell<Option<Box<dyn Fn()>>>,
+        // Host and address family offered by the retry button of the error page.
+        pub retry_family: RefCell<Option<(String, AddressFamily)>>,
+        /// Details of the last connection error, for problem reports.
+        pub error_report: RefCell<Option<String>>,
+        pub term_watcher: Rc<TermWatcher>,
+        /// Kind of the snippets that can be sent to the current session, if any.
+        pub snippet_kind: Cell<Option<SnippetKind>>,
+        pub snippets_menu: gio::Menu,
+        pub snippet_shortcut_controller: gtk::ShortcutController,
+        pub snippet_shortcuts: RefCell<Vec<gtk::Shortcut>>,
+        /// Actions the provider offers for the server, shown in the menu and the toolbar.
+        pub server_actions_menu: gio::Menu,
+        /// Link under the pointer when the terminal was last clicked.
+        pub term_link: RefCell<Option<String>>,
+        /// Account chosen to log in with, if the server has more than one. Kept for reconnects.
+        pub account: RefCell<Option<String>>,
+        /// Windows mirroring this session, closed together with it.
+        pub observer_windows: RefCell<Vec<glib::WeakRef<adw::Window>>>,
+        pub recorder: RefCell<Option<SessionRecorder>>,
+        pub recording_source: RefCell<Option<glib::SourceId>>,
+        pub integer_scaling_tick: RefCell<Option<gtk::TickCallbackId>>,
+        // Pointer mode chosen by the adapter, while the relative pointer is forced.
+        pub adapter_mouse_absolute: Cell<Option<bool>>,
     }
 
     #[glib::object_subclass]
@@ -122,6 +273,22 @@ mod imp {
 
             klass.install_property_action("view.scale-to-window", "scale-to-window");
 
+            klass.install_property_action("view.integer-scaling", "integer-scaling");
+
+            klass.install_property_action("view.view-only", "view-only");
+
+            klass.install_property_action("view.relative-pointer", "relative-pointer");
+
+            klass.install_property_action("view.grab-on-focus", "grab-on-focus");
+
+            klass.install_property_action("view.middle-click-paste", "middle-click-paste");
+
+            klass.install_property_action("view.share-folder", "sharing-folder");
+
+            klass.install_property_action("view.microphone", "microphone");
+
+            klass.install_property_action("view.record", "recording");
+
             klass.install_action(
                 "view.fit-to-screen",
                 None,
@@ -134,6 +301,20 @@ mod imp {
                 "view.reconnect",
                 None,
                 |slf: &super::FieldMonitorServerScreen, _, _| {
+                    glib::spawn_future_local(glib::clone!(
+                        #[strong]
+                        slf,
+                        async move { slf.reconnect().await }
+                    ));
+                },
+            );
+
+            klass.install_action(
+                "view.switch-account",
+                None,
+                |slf: &super::FieldMonitorServerScreen, _, _| {
+                    slf.imp().account.replace(None);
+                    slf.store_view_state(VIEW_STATE_ACCOUNT, "".to_variant());
                     glib::spawn_future_local(glib::clone!(
                         #[strong]
                         slf,
@@ -225,6 +406,123 @@ mod imp {
                     slf.send_term_command(TermCommand::ZoomOut);
                 },
             );
+
+            klass.install_action(
+                "view.term-find",
+                None,
+                |slf: &super::FieldMonitorServerScreen, _, _| {
+                    debug!("view.term-find");
+                    slf.send_term_command(TermCommand::Find);
+                },
+            );
+
+            klass.install_action(
+                "view.term-find-next",
+                None,
+                |slf: &super::FieldMonitorServerScreen, _, _| {
+                    debug!("view.term-find-next");
+                    slf.send_term_command(TermCommand::FindNext);
+                },
+            );
+
+            klass.install_action(
+                "view.term-find-previous",
+                None,
+                |slf: &super::FieldMonitorServerScreen, _, _| {
+                    debug!("view.term-find-previous");
+                    slf.send_term_command(TermCommand::FindPrevious);
+                },
+            );
+
+            klass.install_action(
+                "view.term-watch",
+                None,
+                |slf: &super::FieldMonitorServerScreen, _, _| {
+                    debug!("view.term-watch");
+                    present_term_watch_dialog(slf, slf.imp().term_watcher.clone());
+                },
+            );
+
+            klass.install_action(
+                "view.screenshot",
+                None,
+                |slf: &super::FieldMonitorServerScreen, _, _| {
+                    debug!("view.screenshot");
+                    slf.save_screenshot();
+                },
+            );
+
+            klass.install_action(
+                "view.open-observer",
+                None,
+                |slf: &super::FieldMonitorServerScreen, _, _| {
+                    debug!("view.open-observer");
+                    slf.open_observer();
+                },
+            );
+
+            klass.install_action(
+                "view.term-open-link",
+                None,
+                |slf: &super::FieldMonitorServerScreen, _, _| {
+                    debug!("view.term-open-link");
+                    if let Some(link) = slf.imp().term_link.borrow().clone() {
+                        slf.open_term_link(link);
+                    }
+                },
+            );
+
+            klass.install_action(
+                "view.term-copy-link",
+                None,
+                |slf: &super::FieldMonitorServerScreen, _, _| {
+                    debug!("view.term-copy-link");
+                    if let Some(link) = slf.imp().term_link.borrow().as_deref() {
+                        slf.clipboard().set_text(link);
+                    }
+                },
+            );
+
+            klass.install_action(
+                "view.term-export",
+                None,
+                |slf: &super::FieldMonitorServerScreen, _, _| {
+                    debug!("view.term-export");
+                    slf.send_term_command(TermCommand::Export);
+                },
+            );
+
+            klass
+                .install_property_action("view.term-resize-with-window", "term-resize-with-window");
+
+            klass.install_action(
+                "view.send-snippet",
+                Some(&u32::static_variant_type()),
+                |slf: &super::FieldMonitorServerScreen, _, params| {
+                    debug!("view.send-snippet: {params:?}");
+                    let Some(index) = params.and_then(u32::from_variant) else {
+                        return;
+                    };
+                    slf.send_snippet(index as usize);
+                },
+            );
+
+            klass.install_action(
+                "view.grab-input",
+                None,
+                |slf: &super::FieldMonitorServerScreen, _, _| {
+                    slf.imp().focus_grabber.grab();
+                },
+            );
+
+            klass.install_action(
+                "view.term-set-size",
+                None,
+                |slf: &super::FieldMonitorServerScreen, _, _| {
+                    debug!("view.term-set-size");
+                    slf.present_term_size_dialog();
+                },
+            );
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -233,10 +531,66 @@ mod imp {
     }
 
     #[glib::derived_properties]
-    impl ObjectImpl for FieldMonitorServerScreen {}
+    impl ObjectImpl for FieldMonitorServerScreen {
+        fn constructed(&self) {
+            self.parent_constructed();
+            set_loading_paintable(&self.loading_status_page);
+        }
+
+        fn dispose(&self) {
+            // Dispose may run more than once, only record the closed connection once.
+            if self.connection_state.replace(None) == Some(true) {
+                self.obj().audit(AuditEvent::Closed, true, None);
+            }
+            self.obj().stop_recording();
+            if let Some(app) = self.application.borrow().as_ref() {
+                app.update_session_inhibit();
+            }
+            for window in self.observer_windows.take() {
+                if let Some(window) = window.upgrade() {
+                    window.close();
+                }
+            }
+        }
+    }
     impl WidgetImpl for FieldMonitorServerScreen {}
     impl BinImpl for FieldMonitorServerScreen {}
 
+    impl FieldMonitorServerScreen {
+        fn set_window(&self, window: Option<FieldMonitorWindow>) {
+            if *self.window.borrow() == window {
+                return;
+            }
+            let old_window = self.window.replace(window.clone());
+            if let Some(old_window) = &old_window {
+                // The grab does not move along with the view.
+                if self.focus_grabber.grabbed() {
+                    old_window.set_input_grabbed(false);
+                }
+            }
+            if let (Some(old_window), Some(handler)) =
+                (old_window, self.window_fullscreened_handler.take())
+            {
+                old_window.disconnect(handler);
+            }
+            if let Some(window) = window {
+                let obj = self.obj();
+                let handler = window.connect_notify_local(
+                    Some("fullscreened"),
+                    glib::clone!(
+                        #[weak]
+                        obj,
+                        move |window, _| {
+                            obj.on_window_fullscreened_changed(window);
+                        }
+                    ),
+                );
+                self.window_fullscreened_handler.replace(Some(handler));
+                obj.on_window_fullscreened_changed(&window);
+            }
+        }
+    }
+
     impl Drop for FieldMonitorServerScreen {
         fn drop(&mut self) {
             debug!("drop FieldMonitorServerScreen");
@@ -247,7 +601,8 @@ mod imp {
 glib::wrapper! {
     pub struct FieldMonitorServerScreen(ObjectSubclass<imp::FieldMonitorServerScreen>)
         @extends gtk::Widget, adw::Bin,
-        @implements gio::ActionGroup, gio::ActionMap;
+        @implements gio::ActionGroup, gio::ActionMap, gtk::Accessible, gtk::Buildable,
+            gtk::ConstraintTarget;
 }
 
 impl FieldMonitorServerScreen {
@@ -270,22 +625,43 @@ impl FieldMonitorServerScreen {
             .build();
         let imp = slf.imp();
 
+        imp.snippet_shortcut_controller
+            .set_propagation_phase(gtk::PropagationPhase::Capture);
+        slf.add_controller(imp.snippet_shortcut_controller.clone());
         slf.add_menu(MenuKind::Other, vec![]);
 
-        if let Some(window) = window {
-            window.connect_notify_local(
-                Some("fullscreened"),
-                glib::clone!(
-                    #[weak]
-                    slf,
-                    move |window, _| {
-                        slf.on_window_fullscreened_changed(window);
-                    }
-                ),
-            );
-            slf.on_window_fullscreened_changed(window);
+        slf.restore_view_state();
+        for property in ["session-status", "recording"] {
+            slf.connect_notify_local(Some(property), |slf, _| {
+                if let Some(app) = slf.application() {
+                    app.update_session_inhibit();
+                }
+            });
+        }
+        for property in VIEW_STATE_PROPERTIES {
+            slf.connect_notify_local(Some(property), |slf, pspec| {
+                let value = slf.property::<bool>(pspec.name());
+                slf.store_view_state(pspec.name(), value.to_variant());
+            });
+        }
+        for property in CONNECTION_VIEW_STATE_PROPERTIES {
+            slf.connect_notify_local(Some(property), |slf, pspec| {
+                let value = slf.property::<bool>(pspec.name());
+                if let Some(settings) = slf.application().and_then(|app| app.settings()) {
+                    settings.set_server_view_state_value(
+                        &slf.connection_id(),
+                        pspec.name(),
+                        value.to_variant(),
+                    );
+                }
+            });
+        }
+        for property in ["server-title", "remote-title"] {
+            slf.connect_notify_local(Some(property), |slf, _| slf.update_title());
         }
 
+        imp.supports_multiple_sessions
+            .set(loader.supports_multiple_sessions());
         imp.connection_loader.try_lock().unwrap().replace(loader);
         glib::spawn_future_local(glib::clone!(
             #[strong]
@@ -300,13 +676,55 @@ impl FieldMonitorServerScreen {
                 slf,
                 move |_| slf.update_header_bar_state()
             ));
+            settings.connect_snippets_changed(glib::clone!(
+                #[weak]
+                slf,
+                move |_| slf.update_snippets()
+            ));
+            settings.connect_session_toolbar_changed(glib::clone!(
+                #[weak]
+                slf,
+                move |_| slf.update_session_toolbar()
+            ));
+            slf.on_settings_display_throttling_changed(&settings);
+            for property in ["display-max-fps", "display-scaling-filter"] {
+                settings.connect_notify_local(
+                    Some(property),
+                    glib::clone!(
+                        #[weak]
+                        slf,
+                        move |settings, _| slf.on_settings_display_throttling_changed(settings)
+                    ),
+                );
+            }
         }
+        slf.update_session_toolbar();
 
         info!("Created connection view for {server_path}");
 
         slf
     }
 
+    fn on_settings_display_throttling_changed(&self, settings: &FieldMonitorSettings) {
+        let display_bin = &self.imp().display_bin;
+        display_bin.set_max_fps(settings.display_max_fps());
+        display_bin.set_scaling_filter(settings.display_scaling_filter().gsk_filter());
+    }
+
+    /// Shows the title reported by the remote session next to the name of the server.
+    fn update_title(&self) {
+        let server_title = self.server_title();
+        let remote_title = self.remote_title();
+        if remote_title.is_empty() || remote_title == server_title {
+            self.set_title(server_title);
+        } else {
+            self.set_title(gettext_f(
+                "{server} — {remote}",
+                &[("server", &server_title), ("remote", &remote_title)],
+            ));
+        }
+    }
+
     pub fn set_close_cb(&self, close_cb: impl Fn() + 'static) {
         self.imp().close_cb.replace(Some(Box::new(close_cb)));
     }
@@ -322,33 +740,102 @@ impl FieldMonitorServerScreen {
         }
     }
 
+    /// Reconnects to the server. Lets the current adapter reconnect on its own if it can,
+    /// otherwise the adapter is recreated, see [`Self::reset`].
+    pub async fn reconnect(&self) {
+        let imp = self.imp();
+        let menu_kind = {
+            let adapter = imp.adapter.borrow();
+            match adapter.as_ref() {
+                Some(adapter) if adapter.reconnect() => match adapter.widget() {
+                    AdapterDisplayWidget::Rdw(_) => Some(MenuKind::Rdw),
+                    AdapterDisplayWidget::Vte(_) => Some(MenuKind::Vte),
+                    AdapterDisplayWidget::Arbitrary { .. } => Some(MenuKind::Other),
+                },
+                _ => None,
+            }
+        };
+        let Some(menu_kind) = menu_kind else {
+            return self.reset().await;
+        };
+
+        info!("Connection view reconnecting with existing adapter");
+        imp.status_stack.set_visible_child_name("loading");
+        imp.outer_stack.set_visible_child_name("status");
+        imp.connection_state.replace(None);
+        self.set_session_status(SessionStatus::Connecting);
+
+        let actions = imp
+            .connection_loader
+            .lock()
+            .await
+            .as_ref()
+            .unwrap()
+            .actions();
+        self.add_menu(menu_kind, actions);
+        self.configure_folder_sharing(imp.adapter.borrow().as_deref());
+    }
+
     pub async fn reset(&self) {
         info!("Connection view reset");
         let imp = self.imp();
         imp.status_stack.set_visible_child_name("loading");
         imp.outer_stack.set_visible_child_name("status");
+        self.set_session_status(SessionStatus::Connecting);
+        self.announce_status(
+            &gettext_f("Connecting to {title}…", &[("title", &self.title())]),
+            gtk::AccessibleAnnouncementPriority::Low,
+        );
 
         let mut loader_brw = imp.connection_loader.lock().await;
         let loader = loader_brw.as_mut().unwrap();
         imp.connection_state.replace(None);
 
-        let adapter_id = { imp.adapter_id.borrow().clone() };
-        let Some(adapter) = loader
-            .create_adapter(&adapter_id, self.allow_reauths())
-            .await
-        else {
-            // we disallow reauth because the adapter creator already tries that. it also already
-            // shows a detailed error message, so we don't need to.
+        let Some(account) = self.choose_account(loader).await else {
             self.handle_error(
                 Err(ConnectionError::General(
-                    None,
-                    anyhow!("Failed to create adapter"),
+                    Some(gettext("No account was chosen.")),
+                    anyhow!("account choice cancelled"),
                 )),
                 false,
             );
             return;
         };
 
+        let adapter_id = { imp.adapter_id.borrow().clone() };
+        let mut adapter = match loader
+            .create_adapter(&adapter_id, account.as_deref(), self.allow_reauths())
+            .await
+        {
+            Ok(adapter) => adapter,
+            Err(Some(err)) => {
+                self.show_unreachable(err);
+                return;
+            }
+            Err(None) => {
+                // we disallow reauth because the adapter creator already tries that. it also
+                // already shows a detailed error message, so we don't need to.
+                self.handle_error(
+                    Err(ConnectionError::General(
+                        None,
+                        anyhow!("Failed to create adapter"),
+                    )),
+                    false,
+                );
+                return;
+            }
+        };
+        // The server may have been renamed since the view was opened, the loader is reloaded
+        // when re-authenticating.
+        self.set_server_title(loader.server_title());
+        if let Some(redirect) = imp.microphone_override.get() {
+            adapter.set_microphone(redirect);
+        }
+        if let Some(settings) = self.application().and_then(|app| app.settings()) {
+            adapter.set_printers(&settings.shared_printers());
+        }
+        self.configure_microphone(adapter.microphone());
+
         // Make sure we only react to events from this adapter as long as we don't again reconnect
         // by having a counter that tracks the "generation" of connection attempt.
         // This is only briefly relevant during reconnection (unless we have a memory leak).
@@ -433,20 +920,458 @@ impl FieldMonitorServerScreen {
         }
     }
 
+    /// Sends the snippet at `index` of the snippet settings to the session. Text snippets are
+    /// typed into consoles, key snippets are sent to graphical sessions.
+    fn send_snippet(&self, index: usize) {
+        let Some(snippet) = self
+            .application()
+            .and_then(|app| app.settings())
+            .and_then(|settings| settings.snippets().into_iter().nth(index))
+        else {
+            return;
+        };
+        if self.imp().snippet_kind.get() != Some(snippet.kind) {
+            return;
+        }
+        info!("Sending snippet {}", snippet.name);
+        match snippet.kind {
+            SnippetKind::Text => {
+                if let Some(terminal) = self.terminal() {
+                    terminal.feed_child(snippet.text().as_bytes());
+                }
+            }
+            SnippetKind::Keys => {
+                for keys in snippet.keys() {
+                    self.send_keys(keys);
+                }
+            }
+        }
+    }
+
+    /// Fills the snippet menu and shortcuts with the snippets applicable to the current session.
+    fn update_snippets(&self) {
+        let imp = self.imp();
+        imp.snippets_menu.remove_all();
+        for shortcut in imp.snippet_shortcuts.take() {
+            imp.snippet_shortcut_controller.remove_shortcut(&shortcut);
+        }
+
+        let Some(kind) = imp.snippet_kind.get() else {
+            return;
+        };
+        let snippets = self
+            .application()
+            .and_then(|app| app.settings())
+            .map(|settings| settings.snippets())
+            .unwrap_or_default();
+
+        let items = gio::Menu::new();
+        let mut shortcuts = Vec::new();
+        for (index, snippet) in snippets.iter().enumerate() {
+            if snippet.kind != kind {
+                continue;
+            }
+            let target = (index as u32).to_variant();
+            items.append_item(&gio::MenuItem::new(
+                Some(&snippet.name.replace('_', "__")),
+                Some(&gio::Action::print_detailed_name(
+                    "view.send-snippet",
+                    Some(&target),
+                )),
+            ));
+            if snippet.shortcut.is_empty() {
+                continue;
+            }
+            match gtk::ShortcutTrigger::parse_string(&snippet.shortcut) {
+                Some(trigger) => {
+                    let shortcut = gtk::Shortcut::builder()
+                        .trigger(&trigger)
+                        .action(&gtk::NamedAction::new("view.send-snippet"))
+                        .arguments(&target)
+                        .build();
+                    imp.snippet_shortcut_controller
+                        .add_shortcut(shortcut.clone());
+                    shortcuts.push(shortcut);
+                }
+                None => warn!(
+                    "invalid shortcut {} for snippet {}",
+                    snippet.shortcut, snippet.name
+                ),
+            }
+        }
+        imp.snippet_shortcuts.replace(shortcuts);
+
+        imp.snippets_menu.append_section(None, &items);
+        imp.snippets_menu.append_section(
+            None,
+            &build_menu(&[Some(MenuObject::Item(gio::MenuItem::new(
+                Some(&gettext("_Manage Snippets…")),
+                Some("app.preferences"),
+            )))]),
+        );
+    }
+
     fn send_term_command(&self, cmd: TermCommand) {
         let brw = self.imp().adapter.borrow();
         if let Some(AdapterDisplayWidget::Vte(vte)) = brw.as_ref().map(|adapter| adapter.widget()) {
             match cmd {
                 TermCommand::Copy => vte.copy_clipboard_format(vte::Format::Text),
-                TermCommand::Paste => vte.paste_clipboard(),
+                TermCommand::Paste => self.paste_term_clipboard(vte),
                 TermCommand::SelectAll => vte.select_all(),
                 TermCommand::ZoomReset => vte.set_font_scale(1.0),
-                TermCommand::ZoomIn => vte.set_font_scale(vte.font_scale() + 0.1),
-                TermCommand::ZoomOut => vte.set_font_scale(vte.font_scale() - 0.1),
+                TermCommand::ZoomIn => vte.set_font_scale(normalize_term_zoom(
+                    vte.font_scale() + TERM_ZOOM_STEPS_PER_UNIT.recip(),
+                )),
+                TermCommand::ZoomOut => vte.set_font_scale(normalize_term_zoom(
+                    vte.font_scale() - TERM_ZOOM_STEPS_PER_UNIT.recip(),
+                )),
+                TermCommand::Find => {
+                    self.imp().term_search_bar.set_search_mode(true);
+                    self.imp().term_search_entry.grab_focus();
+                }
+                TermCommand::FindNext => {
+                    vte.search_find_next();
+                }
+                TermCommand::FindPrevious => {
+                    vte.search_find_previous();
+                }
+                TermCommand::Export => self.export_term_output(vte),
+            }
+        }
+    }
+
+    /// Pastes the clipboard into `terminal`. If paste protection is enabled, text containing line
+    /// breaks is shown for review first, since each line may run a command on the server.
+    fn paste_term_clipboard(&self, terminal: vte::Terminal) {
+        let protected = self
+            .application()
+            .and_then(|app| app.settings())
+            .map_or(true, |settings| settings.term_paste_protection());
+        if !protected {
+            terminal.paste_clipboard();
+            return;
+        }
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move {
+                let text = match terminal.clipboard().read_text_future().await {
+                    Ok(Some(text)) => text,
+                    Ok(None) => return,
+                    Err(err) => {
+                        warn!("failed to read clipboard: {err}");
+                        return;
+                    }
+                };
+                if text.contains(['\n', '\r']) {
+                    slf.present_term_paste_dialog(terminal, &text);
+                } else {
+                    terminal.paste_text(&text);
+                }
+            }
+        ));
+    }
+
+    fn present_term_paste_dialog(&self, terminal: vte::Terminal, text: &str) {
+        let line_count = text.lines().count();
+        let text_view = gtk::TextView::builder()
+            .monospace(true)
+            .wrap_mode(gtk::WrapMode::None)
+            .top_margin(12)
+            .bottom_margin(12)
+            .left_margin(12)
+            .right_margin(12)
+            .build();
+        text_view.buffer().set_text(text);
+        let scrolled_window = gtk::ScrolledWindow::builder()
+            .min_content_height(120)
+            .max_content_height(300)
+            .propagate_natural_height(true)
+            .child(&text_view)
+            .css_classes(["card"])
+            .build();
+
+        let dialog = adw::AlertDialog::builder()
+            .heading(gettext("Paste Multiple Lines?"))
+            .body(gettext_f(
+                "The text contains {count} lines. Each line may run a command on {title}. Review and edit the text before pasting it.",
+                &[
+                    ("count", &line_count.to_string()),
+                    ("title", &self.title()),
+                ],
+            ))
+            .extra_child(&scrolled_window)
+            .close_response("cancel")
+            .default_response("cancel")
+            .build();
+        dialog.add_responses(&[("cancel", &gettext("Cancel")), ("paste", &gettext("Paste"))]);
+        dialog.set_response_appearance("paste", adw::ResponseAppearance::Destructive);
+
+        dialog.connect_response(Some("paste"), move |_, _| {
+            let buffer = text_view.buffer();
+            let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+            info!("Pasting reviewed text into terminal");
+            terminal.paste_text(&text);
+        });
+        dialog.present(Some(self));
+    }
+
+    fn audit(&self, event: AuditEvent, success: bool, detail: Option<String>) {
+        if let Some(app) = self.application() {
+            app.audit(AuditEntry::new(
+                event,
+                &self.server_path(),
+                &self.title(),
+                success,
+                detail,
+            ));
+        }
+    }
+
+    /// Notifies the user that a line of the terminal output matched a watch rule.
+    fn on_term_watch_matched(&self, pattern: &str, line: &str) {
+        info!("Terminal output matched watch rule {pattern}");
+        let tab_page = self
+            .ancestor(adw::TabView::static_type())
+            .and_downcast::<adw::TabView>()
+            .map(|tab_view| tab_view.page(self));
+        let window_active = self
+            .root()
+            .and_downcast::<gtk::Window>()
+            .is_some_and(|window| window.is_active());
+        if let Some(tab_page) = tab_page {
+            if !(window_active && tab_page.is_selected()) {
+                tab_page.set_needs_attention(true);
+            }
+        }
+
+        if let Some(app) = self.application() {
+            let notification = gio::Notification::new(&gettext_f(
+                "Output of {title} matched “{pattern}”",
+                &[("title", &self.title()), ("pattern", pattern)],
+            ));
+            notification.set_body(Some(line));
+            app.send_deferrable_notification(
+                Some(&format!("term-watch-{}", self.server_path())),
+                &notification,
+            );
+        }
+    }
+
+    /// Saves the session as it is currently shown as a PNG image.
+    fn save_screenshot(&self) {
+        let Some(texture) = self
+            .imp()
+            .display_bin
+            .child()
+            .and_then(|widget| render_widget(&widget))
+        else {
+            warn!("failed to render the session for a screenshot");
+            if let Some(window) = self.window() {
+                window.toast(&gettext("Failed to save the screenshot."));
+            }
+            return;
+        };
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move {
+                let file_dialog = gtk::FileDialog::builder()
+                    .title(gettext("Save Screenshot"))
+                    .initial_name(format!("{}.png", slf.title().replace('/', "-")))
+                    .modal(true)
+                    .build();
+                let window = slf.root().and_downcast::<gtk::Window>();
+                let Ok(file) = file_dialog.save_future(window.as_ref()).await else {
+                    return;
+                };
+                if let Err((_, err)) = file
+                    .replace_contents_future(
+                        texture.save_to_png_bytes(),
+                        None,
+                        false,
+                        gio::FileCreateFlags::REPLACE_DESTINATION,
+                    )
+                    .await
+                {
+                    warn!("failed to save screenshot: {err}");
+                    if let Some(window) = slf.window() {
+                        window.toast(&gettext("Failed to save the screenshot."));
+                    }
+                }
+            }
+        ));
+    }
+
+    /// Opens a read-only mirror of the session in a new window.
+    fn open_observer(&self) {
+        let imp = self.imp();
+        let window = open_observer_window(
+            imp.display_bin.upcast_ref(),
+            &self.title(),
+            self.application()
+                .as_ref()
+                .map(|app| app.upcast_ref::<gtk::Application>()),
+        );
+        let mut windows = imp.observer_windows.borrow_mut();
+        windows.retain(|window| window.upgrade().is_some());
+        windows.push(window.downgrade());
+    }
+
+    /// Asks for a file and starts recording the session into it. Recording is turned off again
+    /// if the user cancels.
+    fn start_recording(&self) {
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move {
+                let filters = gio::ListStore::new::<gtk::FileFilter>();
+                for (name, suffix) in [
+                    (gettext("WebM Video"), "webm"),
+                    (gettext("MP4 Video"), "mp4"),
+                ] {
+                    let filter = gtk::FileFilter::new();
+                    filter.set_name(Some(&name));
+                    filter.add_suffix(suffix);
+                    filters.append(&filter);
+                }
+                let file_dialog = gtk::FileDialog::builder()
+                    .title(gettext("Record Session"))
+                    .initial_name(format!("{}.webm", slf.title().replace('/', "-")))
+                    .filters(&filters)
+                    .modal(true)
+                    .build();
+                let window = slf.root().and_downcast::<gtk::Window>();
+                let path = file_dialog
+                    .save_future(window.as_ref())
+                    .await
+                    .ok()
+                    .and_then(|file| file.path());
+                let display = slf.imp().display_bin.child();
+                // Recording may have been turned off while the dialog was open.
+                let (Some(path), Some(display), true) = (path, display, slf.recording()) else {
+                    slf.set_recording(false);
+                    return;
+                };
+
+                let max_size = slf
+                    .application()
+                    .and_then(|app| app.settings())
+                    .map(|settings| settings.recording_max_size())
+                    .unwrap_or_default();
+                let recorder = match SessionRecorder::new(
+                    &path,
+                    display.width() as u32,
+                    display.height() as u32,
+                    max_size,
+                ) {
+                    Ok(recorder) => recorder,
+                    Err(err) => {
+                        warn!("failed to start recording: {err}");
+                        if let Some(window) = slf.window() {
+                            window.toast(&gettext("Failed to start the recording."));
+                        }
+                        slf.set_recording(false);
+                        return;
+                    }
+                };
+
+                let imp = slf.imp();
+                imp.recorder.replace(Some(recorder));
+                imp.recording_label.set_label("0:00");
+                let source = glib::timeout_add_local(
+                    Duration::from_millis(1000 / u64::from(RECORDING_FRAME_RATE)),
+                    glib::clone!(
+                        #[weak]
+                        slf,
+                        #[upgrade_or]
+                        glib::ControlFlow::Break,
+                        move || {
+                            slf.record_frame();
+                            glib::ControlFlow::Continue
+                        }
+                    ),
+                );
+                imp.recording_source.replace(Some(source));
+            }
+        ));
+    }
+
+    fn record_frame(&self) {
+        let imp = self.imp();
+        let texture = imp
+            .display_bin
+            .child()
+            .and_then(|widget| render_widget(&widget));
+        let within_limit = match (imp.recorder.borrow().as_ref(), texture) {
+            (Some(recorder), Some(texture)) => {
+                let within_limit = recorder.push_frame(&texture);
+                let secs = recorder.elapsed().as_secs();
+                imp.recording_label
+                    .set_label(&format!("{}:{:02}", secs / 60, secs % 60));
+                within_limit
             }
+            _ => true,
+        };
+        if !within_limit {
+            if let Some(window) = self.window() {
+                window.toast(&gettext(
+                    "The recording was stopped, it reached the maximum size.",
+                ));
+            }
+            self.set_recording(false);
         }
     }
 
+    fn stop_recording(&self) {
+        let imp = self.imp();
+        if let Some(source) = imp.recording_source.take() {
+            source.remove();
+        }
+        if let Some(recorder) = imp.recorder.take() {
+            glib::spawn_future_local(recorder.finish());
+        }
+    }
+
+    fn export_term_output(&self, terminal: vte::Terminal) {
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move {
+                let file_dialog = gtk::FileDialog::builder()
+                    .title(gettext("Export Output"))
+                    .initial_name(format!("{}.txt", slf.title().replace('/', "-")))
+                    .modal(true)
+                    .build();
+                let window = slf.root().and_downcast::<gtk::Window>();
+                let Ok(file) = file_dialog.save_future(window.as_ref()).await else {
+                    return;
+                };
+                let result = file
+                    .replace(
+                        None,
+                        false,
+                        gio::FileCreateFlags::REPLACE_DESTINATION,
+                        gio::Cancellable::NONE,
+                    )
+                    .and_then(|stream| {
+                        terminal.write_contents_sync(
+                            &stream,
+                            vte::WriteFlags::Default,
+                            gio::Cancellable::NONE,
+                        )?;
+                        stream.close(gio::Cancellable::NONE)
+                    });
+                if let Err(err) = result {
+                    warn!("failed to export terminal output: {err}");
+                    if let Some(window) = slf.window() {
+                        window.toast(&gettext("Failed to export the terminal output."));
+                    }
+                }
+            }
+        ));
+    }
+
     pub fn add_display(
         &self,
         display: Box<dyn AdapterDisplay>,
@@ -461,6 +1386,17 @@ impl FieldMonitorServerScreen {
                 display.set_vexpand(true);
                 display.set_hexpand(true);
                 imp.focus_grabber.set_display(Some(display));
+                imp.adapter_mouse_absolute.set(None);
+                // The adapter may change the pointer mode, eg. once the server reported
+                // whether it supports absolute positions.
+                display.connect_notify_local(
+                    Some("mouse-absolute"),
+                    glib::clone!(
+                        #[weak(rename_to = slf)]
+                        self,
+                        move |_, _| slf.on_self_relative_pointer_changed()
+                    ),
+                );
                 self.add_menu(MenuKind::Rdw, server_actions);
                 self.remove_css_class("connection-view-vte");
                 display.add_css_class("rdw-display");
@@ -476,19 +1412,74 @@ impl FieldMonitorServerScreen {
                     .css_classes(["vte-box"])
                     .build();
 
-                // make vte react to theme
-                let style_manager = self.application().unwrap().style_manager();
+                // make vte react to theme and terminal preferences
+                let app = self.application().unwrap();
+                let style_manager = app.style_manager();
+                let settings = app.settings();
                 style_manager.connect_dark_notify(glib::clone!(
                     #[weak]
                     terminal,
-                    move |style_manager| configure_vte_styling(&terminal, style_manager)
+                    #[strong]
+                    settings,
+                    move |style_manager| {
+                        configure_vte_styling(&terminal, style_manager, settings.as_ref())
+                    }
+                ));
+                if let Some(settings) = &settings {
+                    settings.connect_notify_local(
+                        None,
+                        glib::clone!(
+                            #[weak]
+                            terminal,
+                            #[weak]
+                            style_manager,
+                            move |settings, pspec| {
+                                if pspec.name().starts_with("term-") {
+                                    configure_vte_styling(&terminal, &style_manager, Some(settings))
+                                }
+                            }
+                        ),
+                    );
+                }
+                configure_vte_styling(terminal, &style_manager, settings.as_ref());
+
+                if let Some(zoom) = self
+                    .stored_view_state()
+                    .get(VIEW_STATE_ZOOM)
+                    .and_then(f64::from_variant)
+                {
+                    terminal.set_font_scale(normalize_term_zoom(zoom));
+                }
+                terminal.connect_font_scale_notify(glib::clone!(
+                    #[weak(rename_to = slf)]
+                    self,
+                    move |terminal| {
+                        let zoom = terminal.font_scale();
+                        let stored = slf
+                            .stored_view_state()
+                            .get(VIEW_STATE_ZOOM)
+                            .and_then(f64::from_variant);
+                        if stored != Some(zoom) {
+                            slf.store_view_state(VIEW_STATE_ZOOM, zoom.to_variant())
+                        }
+                    }
                 ));
-                configure_vte_styling(terminal, &style_manager);
 
                 bx.append(terminal);
 
+                imp.term_watcher.watch(
+                    terminal,
+                    glib::clone!(
+                        #[weak(rename_to = slf)]
+                        self,
+                        move |pattern, line| slf.on_term_watch_matched(pattern, line)
+                    ),
+                );
+
                 self.setup_vte_event_controllers(terminal);
+                self.setup_vte_links(terminal);
                 self.setup_vte_menu_model(terminal);
+                self.set_term_resize_with_window(true);
                 imp.focus_grabber.set_display(None);
                 self.add_menu(MenuKind::Vte, server_actions);
                 self.add_css_class("connection-view-vte");
@@ -503,9 +1494,118 @@ impl FieldMonitorServerScreen {
         };
 
         self.configure_rdw_action_support(&display_widget);
+        self.configure_folder_sharing(Some(&*display));
+
+        let label = match &display_widget {
+            AdapterDisplayWidget::Vte(_) => {
+                gettext_f("Terminal of {title}", &[("title", &self.title())])
+            }
+            _ => gettext_f("Remote Screen of {title}", &[("title", &self.title())]),
+        };
+        widget.update_property(&[
+            gtk::accessible::Property::Label(&label),
+            gtk::accessible::Property::Description(&gettext_f(
+                "Connected using {adapter}",
+                &[("adapter", &adapter_title(&self.adapter_id()))],
+            )),
+        ]);
+
+        self.set_remote_title("");
+        display.connect_remote_title_changed(Rc::new(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            move |title: Option<String>| slf.set_remote_title(title.unwrap_or_default())
+        )));
 
         imp.adapter.borrow_mut().replace(display);
         imp.display_bin.set_child(Some(&widget));
+
+        // Apply the (possibly restored) view preferences to the new display.
+        if matches!(display_widget, AdapterDisplayWidget::Rdw(_)) {
+            self.on_self_dynamic_resize_changed();
+            self.on_self_scale_to_window_changed();
+            self.on_self_relative_pointer_changed();
+        }
+        self.on_self_view_only_changed();
+    }
+
+    /// The account to log in with if the server has more than one. Asks the user, unless an
+    /// account was already chosen in this view or the choice was remembered.
+    /// `None` if the user cancelled.
+    async fn choose_account(&self, loader: &ConnectionLoader) -> Option<Option<String>> {
+        let imp = self.imp();
+        let accounts = loader.accounts();
+        self.action_set_enabled("view.switch-account", accounts.len() > 1);
+        if accounts.len() < 2 {
+            return Some(None);
+        }
+        let is_known = |account: &String| accounts.iter().any(|(id, _)| id == account);
+
+        let chosen = imp.account.borrow().clone().or_else(|| {
+            self.stored_view_state()
+                .get(VIEW_STATE_ACCOUNT)
+                .and_then(String::from_variant)
+        });
+        if let Some(account) = chosen.filter(is_known) {
+            imp.account.replace(Some(account.clone()));
+            return Some(Some(account));
+        }
+
+        let window = self.root().and_downcast::<gtk::Window>();
+        let (account, remember) =
+            choose_account(&self.server_title(), &accounts, window.as_ref()).await?;
+        if remember {
+            self.store_view_state(VIEW_STATE_ACCOUNT, account.to_variant());
+        }
+        imp.account.replace(Some(account.clone()));
+        Some(Some(account))
+    }
+
+    fn stored_view_state(&self) -> BTreeMap<String, glib::Variant> {
+        self.application()
+            .and_then(|app| app.settings())
+            .map(|settings| settings.server_view_state(&self.server_path()))
+            .unwrap_or_default()
+    }
+
+    fn restore_view_state(&self) {
+        let state = self.stored_view_state();
+        for property in VIEW_STATE_PROPERTIES {
+            if let Some(value) = state.get(property).and_then(bool::from_variant) {
+                self.set_property(property, value);
+            }
+        }
+        let Some(settings) = self.application().and_then(|app| app.settings()) else {
+            return;
+        };
+        let state = settings.server_view_state(&self.connection_id());
+        for property in CONNECTION_VIEW_STATE_PROPERTIES {
+            if let Some(value) = state.get(property).and_then(bool::from_variant) {
+                self.set_property(property, value);
+            }
+        }
+    }
+
+    /// ID of the connection of the server. The view state of the connection is stored under
+    /// it, as it is never the path of a server.
+    fn connection_id(&self) -> String {
+        let server_path = self.server_path();
+        match server_path.split_once('/') {
+            Some((connection_id, _)) => connection_id.to_string(),
+            None => server_path,
+        }
+    }
+
+    fn store_view_state(&self, name: &str, value: glib::Variant) {
+        if let Some(settings) = self.application().and_then(|app| app.settings()) {
+            settings.set_server_view_state_value(&self.server_path(), name, value);
+        }
+    }
+
+    fn set_session_status(&self, status: SessionStatus) {
+        if self.imp().session_status.replace(status) != status {
+            self.notify_session_status();
+        }
     }
 
     pub fn on_connected(&self) {
@@ -525,7 +1625,25 @@ impl FieldMonitorServerScreen {
                 *state = Some(true);
             }
         }
+        self.audit(AuditEvent::Connected, true, None);
+        self.set_session_status(SessionStatus::Connected);
         imp.outer_stack.set_visible_child_name("connection");
+        self.announce_status(
+            &gettext_f(
+                "Connected to {title} using {adapter}",
+                &[
+                    ("title", &self.title()),
+                    ("adapter", &adapter_title(&self.adapter_id())),
+                ],
+            ),
+            gtk::AccessibleAnnouncementPriority::Medium,
+        );
+        // Keyboard users continue on the remote screen or terminal.
+        if imp.focus_grabber.is_visible() {
+            imp.focus_grabber.grab_focus();
+        } else if let Some(child) = imp.display_bin.child() {
+            child.child_focus(gtk::DirectionType::TabForward);
+        }
         glib::spawn_future_local(glib::clone!(
             #[weak(rename_to=slf)]
             self,
@@ -543,11 +1661,24 @@ impl FieldMonitorServerScreen {
         match state {
             None => {
                 info!("Connection failed to establish.");
-                *state = Some(false)
+                *state = Some(false);
+                let detail = result.as_ref().err().map(ToString::to_string);
+                self.audit(AuditEvent::ConnectionFailed, false, detail);
             }
             Some(true) => {
                 info!("Connection got disconnected.");
-                *state = Some(false)
+                *state = Some(false);
+                let detail = result.as_ref().err().map(ToString::to_string);
+                self.audit(AuditEvent::Disconnected, result.is_ok(), detail.clone());
+                if let Some(app) = self.application() {
+                    app.notify_event_sinks(SinkEvent::new(
+                        SinkEventKind::SessionDisconnected,
+                        &self.server_path(),
+                        &self.title(),
+                        detail,
+                    ));
+                }
+                self.set_recording(false);
             }
             Some(false) => {
                 warn!("Got multiple on_disconnected events. Ignoring.");
@@ -558,20 +1689,62 @@ impl FieldMonitorServerScreen {
         self.handle_error(result, true)
     }
 
+    fn show_unreachable(&self, err: ReachabilityError) {
+        let imp = self.imp();
+        warn!("Server not reachable: {err}");
+        self.audit(AuditEvent::ConnectionFailed, false, Some(err.to_string()));
+        self.set_session_status(SessionStatus::Failed);
+
+        self.add_menu(MenuKind::Other, vec![]);
+        self.configure_folder_sharing(None);
+        imp.status_stack.set_visible_child_name("disconnected");
+        imp.outer_stack.set_visible_child_name("status");
+        imp.focus_grabber.ungrab();
+
+        imp.error_status_page.set_title(&err.title());
+        imp.error_status_page
+            .set_description(Some(&err.to_string()));
+        self.announce_error_status();
+        imp.report_problem_button.set_visible(false);
+        imp.error_report.replace(None);
+
+        match err.retry_family {
+            Some(family) => {
+                imp.retry_family_button.set_label(&gettext_f(
+                    "Retry With {family}",
+                    &[("family", &family.to_string())],
+                ));
+                imp.retry_family_button.set_visible(true);
+                imp.retry_family.replace(Some((err.host, family)));
+            }
+            None => {
+                imp.retry_family_button.set_visible(false);
+                imp.retry_family.replace(None);
+            }
+        }
+    }
+
     fn handle_error(&self, result: ConnectionResult<()>, allow_reauth: bool) {
         let imp = self.imp();
 
         self.add_menu(MenuKind::Other, vec![]);
+        self.configure_folder_sharing(None);
+        imp.retry_family_button.set_visible(false);
+        imp.retry_family.replace(None);
+        imp.report_problem_button.set_visible(false);
+        imp.error_report.replace(None);
 
         match result {
             Ok(()) => {
                 imp.status_stack.set_visible_child_name("disconnected");
                 imp.outer_stack.set_visible_child_name("status");
                 imp.focus_grabber.ungrab();
+                self.set_session_status(SessionStatus::Disconnected);
 
                 imp.error_status_page.set_title(&gettext("Disconnected"));
                 imp.error_status_page
                     .set_description(Some(&gettext("The connection to the server was closed.")));
+                self.announce_error_status();
             }
             Err(ConnectionError::AuthFailed(_msg, err)) if allow_reauth && self.allow_reauths() => {
                 warn!("Connection failed with auth error: {err}");
@@ -602,6 +1775,7 @@ impl FieldMonitorServerScreen {
                 imp.status_stack.set_visible_child_name("disconnected");
                 imp.outer_stack.set_visible_child_name("status");
                 imp.focus_grabber.ungrab();
+                self.set_session_status(SessionStatus::Failed);
 
                 warn!("Connection failed: {err}");
                 imp.error_status_page
@@ -611,11 +1785,30 @@ impl FieldMonitorServerScreen {
                     None => base_desc,
                     Some(msg) => format!("{base_desc}\n{msg}"),
                 };
-                imp.error_status_page.set_description(Some(&desc))
+                imp.error_status_page.set_description(Some(&desc));
+                self.announce_error_status();
+
+                imp.error_report.replace(Some(format!("{desc}\n\n{err:?}")));
+                imp.report_problem_button.set_visible(true);
             }
         }
     }
 
+    /// Lets screen readers announce a change of the connection state, which is otherwise only
+    /// shown visually.
+    fn announce_status(&self, message: &str, priority: gtk::AccessibleAnnouncementPriority) {
+        self.announce(message, priority);
+    }
+
+    fn announce_error_status(&self) {
+        let page = &*self.imp().error_status_page;
+        let message = match page.description() {
+            Some(description) => format!("{}. {description}", page.title()),
+            None => page.title().to_string(),
+        };
+        self.announce_status(&message, gtk::AccessibleAnnouncementPriority::High);
+    }
+
     fn configure_rdw_action_support(&self, display: &AdapterDisplayWidget) {
         let mut is_rdw = false;
         let mut is_vte = false;
@@ -628,9 +1821,19 @@ impl FieldMonitorServerScreen {
 
         self.action_set_enabled("view.dynamic-resize", is_rdw);
         self.action_set_enabled("view.scale-to-window", is_rdw);
-        self.action_set_enabled("view.fit-to-screen", is_rdw);
+        self.action_set_enabled("view.integer-scaling", is_rdw);
+        self.action_set_enabled("view.fit-to-screen", is_rdw || is_vte);
+        self.action_set_enabled("view.view-only", is_rdw || is_vte);
+        self.action_set_enabled("view.relative-pointer", is_rdw);
+        self.action_set_enabled("view.grab-on-focus", is_rdw);
+        self.action_set_enabled("view.middle-click-paste", is_vte);
+        self.action_set_enabled("view.send-keys", is_rdw);
+        self.action_set_enabled("view.record", is_rdw);
+        self.action_set_enabled("view.screenshot", is_rdw || is_vte);
+        self.action_set_enabled("view.open-observer", is_rdw || is_vte);
         if !is_rdw {
             self.imp().dynamic_resize.set(false);
+            self.set_recording(false);
         }
 
         // Configure the "Show Output" button for disconnected connections.
@@ -642,12 +1845,45 @@ impl FieldMonitorServerScreen {
         self.action_set_enabled("view.term-zoom-reset", is_vte);
         self.action_set_enabled("view.term-zoom-in", is_vte);
         self.action_set_enabled("view.term-zoom-out", is_vte);
+        self.action_set_enabled("view.term-resize-with-window", is_vte);
+        self.action_set_enabled("view.term-set-size", is_vte);
+        self.action_set_enabled("view.term-find", is_vte);
+        self.action_set_enabled("view.term-find-next", is_vte);
+        self.action_set_enabled("view.term-find-previous", is_vte);
+        self.action_set_enabled("view.term-export", is_vte);
+        self.action_set_enabled("view.term-watch", is_vte);
+        if !is_vte {
+            self.imp().term_search_bar.set_search_mode(false);
+        }
+        self.set_term_link(None);
 
         self.notify_dynamic_resize();
         self.notify_scale_to_window();
     }
 
+    fn configure_folder_sharing(&self, display: Option<&dyn AdapterDisplay>) {
+        let supported = display.is_some_and(|display| display.supports_folder_sharing());
+        self.action_set_enabled("view.share-folder", supported);
+        // Set directly, the adapter already is in this state.
+        self.imp()
+            .sharing_folder
+            .set(supported && display.unwrap().is_sharing_folder());
+        self.notify_sharing_folder();
+    }
+
+    fn configure_microphone(&self, redirect: Option<bool>) {
+        let imp = self.imp();
+        imp.adapter_microphone.set(redirect);
+        self.action_set_enabled("view.microphone", redirect.is_some());
+        // Set directly, the adapter already is in this state.
+        imp.microphone.set(redirect.unwrap_or_default());
+        self.notify_microphone();
+    }
+
     fn fit_to_screen(&self) {
+        if let Some(terminal) = self.terminal() {
+            return self.fit_to_terminal(&terminal);
+        }
         let display = self
             .imp()
             .display_bin
@@ -666,58 +1902,148 @@ impl FieldMonitorServerScreen {
                 } else {
                     0
                 };
+                // The remote resolution is in device pixels, the window size in logical
+                // pixels. The scale may be fractional, eg. 1.25.
+                let scale = window
+                    .surface()
+                    .map(|surface| surface.scale())
+                    .unwrap_or(1.0);
                 if w != 0 && h != 0 {
-                    window.resize(w, h + header_bar_h);
+                    window.resize(
+                        (w as f64 / scale).ceil() as usize,
+                        (h as f64 / scale).ceil() as usize + header_bar_h,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Resizes the window to fit the terminal's current geometry.
+    fn fit_to_terminal(&self, terminal: &vte::Terminal) {
+        let Some(window) = self.root().and_downcast::<FieldMonitorWindow>() else {
+            return;
+        };
+        let (_, natural) = terminal.preferred_size();
+        let header_bar_h = if !self.imp().toolbar_view.is_extend_content_to_top_edge() {
+            self.imp().header_bar.height()
+        } else {
+            0
+        };
+        if natural.width() > 0 && natural.height() > 0 {
+            window.resize(
+                natural.width() as usize,
+                (natural.height() + header_bar_h) as usize,
+            );
+        }
+    }
+
+    fn terminal(&self) -> Option<vte::Terminal> {
+        match self.imp().adapter.borrow().as_ref()?.widget() {
+            AdapterDisplayWidget::Vte(terminal) => Some(terminal),
+            _ => None,
+        }
+    }
+
+    /// Sets the terminal to a fixed size of `columns`×`rows` and resizes the window to fit it.
+    fn set_term_size(&self, columns: i64, rows: i64) {
+        let Some(terminal) = self.terminal() else {
+            return;
+        };
+        info!("Setting terminal size to {columns}x{rows}");
+        self.set_term_resize_with_window(false);
+        terminal.set_size(columns, rows);
+        self.fit_to_terminal(&terminal);
+    }
+
+    fn present_term_size_dialog(&self) {
+        let Some(terminal) = self.terminal() else {
+            return;
+        };
+
+        let columns_row = adw::SpinRow::with_range(MIN_TERM_COLUMNS, MAX_TERM_COLUMNS, 1.0);
+        columns_row.set_title(&gettext("Columns"));
+        columns_row.set_value(terminal.column_count() as f64);
+        let rows_row = adw::SpinRow::with_range(MIN_TERM_ROWS, MAX_TERM_ROWS, 1.0);
+        rows_row.set_title(&gettext("Rows"));
+        rows_row.set_value(terminal.row_count() as f64);
+
+        let list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .build();
+        list.append(&columns_row);
+        list.append(&rows_row);
+
+        let dialog = adw::AlertDialog::builder()
+            .heading(gettext("Terminal Size"))
+            .body(gettext(
+                "The terminal keeps this size until resizing it with the window is enabled again.",
+            ))
+            .extra_child(&list)
+            .close_response("cancel")
+            .default_response("apply")
+            .build();
+        dialog.add_responses(&[("cancel", &gettext("Cancel")), ("apply", &gettext("Apply"))]);
+        dialog.set_response_appearance("apply", adw::ResponseAppearance::Suggested);
+
+        dialog.connect_response(
+            Some("apply"),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_, _| {
+                    slf.set_term_size(columns_row.value() as i64, rows_row.value() as i64);
                 }
-            }
-        }
+            ),
+        );
+        dialog.present(Some(self));
     }
 
     fn setup_vte_event_controllers(&self, terminal: &vte::Terminal) {
-        let shortcut_controller = gtk::ShortcutController::new();
+        // The terminal handles most key presses itself, so the shortcuts of actions that apply
+        // to it are checked before it gets them. They follow the shortcuts of the application.
+        let shortcut_controller = gtk::EventControllerKey::new();
         shortcut_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
-        shortcut_controller.add_shortcut(
-            gtk::Shortcut::builder()
-                .trigger(&gtk::ShortcutTrigger::parse_string("<Shift><Primary>C").unwrap())
-                .action(&gtk::ShortcutAction::parse_string("action(view.term-copy)").unwrap())
-                .build(),
-        );
-        shortcut_controller.add_shortcut(
-            gtk::Shortcut::builder()
-                .trigger(&gtk::ShortcutTrigger::parse_string("<Shift><Primary>V").unwrap())
-                .action(&gtk::ShortcutAction::parse_string("action(view.term-paste)").unwrap())
-                .build(),
-        );
-        shortcut_controller.add_shortcut(
-            gtk::Shortcut::builder()
-                .trigger(&gtk::ShortcutTrigger::parse_string("<Shift><Primary>A").unwrap())
-                .action(&gtk::ShortcutAction::parse_string("action(view.term-select-all)").unwrap())
-                .build(),
-        );
-        shortcut_controller.add_shortcut(
-            gtk::Shortcut::builder()
-                .trigger(&gtk::ShortcutTrigger::parse_string("<Primary>0").unwrap())
-                .action(&gtk::ShortcutAction::parse_string("action(view.term-zoom-reset)").unwrap())
-                .build(),
-        );
-        shortcut_controller.add_shortcut(
-            gtk::Shortcut::builder()
-                .trigger(&gtk::ShortcutTrigger::parse_string("<Primary>plus").unwrap())
-                .action(&gtk::ShortcutAction::parse_string("action(view.term-zoom-in)").unwrap())
-                .build(),
-        );
-        shortcut_controller.add_shortcut(
-            gtk::Shortcut::builder()
-                .trigger(&gtk::ShortcutTrigger::parse_string("<Primary>minus").unwrap())
-                .action(&gtk::ShortcutAction::parse_string("action(view.term-zoom-out)").unwrap())
-                .build(),
-        );
-        shortcut_controller.add_shortcut(
-            gtk::Shortcut::builder()
-                .trigger(&gtk::ShortcutTrigger::parse_string("<Shift><Primary>W").unwrap())
-                .action(&gtk::ShortcutAction::parse_string("action(view.close)").unwrap())
-                .build(),
-        );
+        shortcut_controller.connect_key_pressed(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            #[upgrade_or]
+            glib::Propagation::Proceed,
+            move |controller, _, _, _| {
+                let (Some(event), Some(app)) = (controller.current_event(), slf.application())
+                else {
+                    return glib::Propagation::Proceed;
+                };
+                for action in TERM_SHORTCUT_ACTIONS {
+                    let matches = app
+                        .accels_for_action(action)
+                        .iter()
+                        .filter_map(|accel| gtk::ShortcutTrigger::parse_string(accel))
+                        .any(|trigger| trigger.trigger(&event, false) == gdk::KeyMatch::Exact);
+                    if matches && slf.activate_action(action, None).is_ok() {
+                        return glib::Propagation::Stop;
+                    }
+                }
+                glib::Propagation::Proceed
+            }
+        ));
+
+        // Claiming the middle button before the terminal sees it prevents pasting the primary
+        // selection.
+        let middle_click_controller = gtk::GestureClick::builder()
+            .button(BUTTON_MIDDLE)
+            .propagation_phase(gtk::PropagationPhase::Capture)
+            .build();
+        middle_click_controller.connect_pressed(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            move |gesture, _, _, _| {
+                if !slf.middle_click_paste() {
+                    gesture.set_state(gtk::EventSequenceState::Claimed);
+                }
+            }
+        ));
+        terminal.add_controller(middle_click_controller);
 
         let scroll_controller = gtk::EventControllerScroll::new(
             gtk::EventControllerScrollFlags::VERTICAL | gtk::EventControllerScrollFlags::DISCRETE,
@@ -750,8 +2076,81 @@ impl FieldMonitorServerScreen {
         terminal.add_controller(scroll_controller);
     }
 
+    /// Makes links in the terminal clickable: Ctrl+click opens them, and the link under the
+    /// pointer is offered in the context menu.
+    fn setup_vte_links(&self, terminal: &vte::Terminal) {
+        terminal.set_allow_hyperlink(true);
+        match vte::Regex::for_match(TERM_LINK_PATTERN, PCRE2_MULTILINE) {
+            Ok(regex) => {
+                let tag = terminal.match_add_regex(&regex, 0);
+                terminal.match_set_cursor_name(tag, "pointer");
+            }
+            Err(err) => warn!("failed to compile terminal link pattern: {err}"),
+        }
+
+        let click_controller = gtk::GestureClick::builder()
+            .button(0)
+            .propagation_phase(gtk::PropagationPhase::Capture)
+            .build();
+        click_controller.connect_pressed(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            #[weak]
+            terminal,
+            move |gesture, _n_press, x, y| {
+                let link = terminal
+                    .check_hyperlink_at(x, y)
+                    .or_else(|| terminal.check_match_at(x, y).0)
+                    .map(String::from)
+                    .filter(|link| is_web_link(link));
+                slf.set_term_link(link.clone());
+
+                let ctrl_held = gesture
+                    .current_event_state()
+                    .contains(ModifierType::CONTROL_MASK);
+                if let Some(link) =
+                    link.filter(|_| ctrl_held && gesture.current_button() == BUTTON_PRIMARY)
+                {
+                    gesture.set_state(gtk::EventSequenceState::Claimed);
+                    slf.open_term_link(link);
+                }
+            }
+        ));
+        terminal.add_controller(click_controller);
+    }
+
+    fn set_term_link(&self, link: Option<String>) {
+        self.action_set_enabled("view.term-open-link", link.is_some());
+        self.action_set_enabled("view.term-copy-link", link.is_some());
+        self.imp().term_link.replace(link);
+    }
+
+    fn open_term_link(&self, link: String) {
+        if !is_web_link(&link) {
+            warn!("not opening terminal link with unsupported scheme: {link}");
+            return;
+        }
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move {
+                let window = slf.root().and_downcast::<gtk::Window>();
+                if let Err(err) = gtk::UriLauncher::new(&link)
+                    .launch_future(window.as_ref())
+                    .await
+                {
+                    warn!("failed to open {link}: {err}");
+                    if let Some(window) = slf.window() {
+                        window.toast(&gettext_f("Failed to open {url}.", &[("url", &link)]));
+                    }
+                }
+            }
+        ));
+    }
+
     fn setup_vte_menu_model(&self, terminal: &vte::Terminal) {
         let menu = Self::vte_menu_shortcuts();
+        menu.prepend_section(None, &Self::vte_menu_link());
         menu.append_section(None, &Self::vte_menu_zoom());
         terminal.set_context_menu_model(Some(&menu));
     }
@@ -761,6 +2160,12 @@ impl FieldMonitorServerScreen {
 
         match menu_kind {
             MenuKind::Rdw => {
+                let microphone_item = gio::MenuItem::new(
+                    Some(&gettext("Redirect _Microphone")),
+                    Some("view.microphone"),
+                );
+                microphone_item
+                    .set_attribute_value("hidden-when", Some(&"action-disabled".to_variant()));
                 menu.append_section(
                     None,
                     &build_menu(&[
@@ -772,16 +2177,53 @@ impl FieldMonitorServerScreen {
                             Some(&gettext("_Scale to Window")),
                             Some("view.scale-to-window"),
                         ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("_Integer Scaling")),
+                            Some("view.integer-scaling"),
+                        ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("_View Only")),
+                            Some("view.view-only"),
+                        ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("Relative _Pointer")),
+                            Some("view.relative-pointer"),
+                        ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("_Grab Input on Focus")),
+                            Some("view.grab-on-focus"),
+                        ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("S_hare Folder")),
+                            Some("view.share-folder"),
+                        ))),
+                        Some(MenuObject::Item(microphone_item)),
                     ]),
                 );
 
                 menu.append_section(
                     None,
                     &build_menu(&[
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("_Control Remote Screen")),
+                            Some("view.grab-input"),
+                        ))),
                         Some(MenuObject::Item(gio::MenuItem::new(
                             Some(&gettext("_Resize Window to Screen")),
                             Some("view.fit-to-screen"),
                         ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("Save Scree_nshot…")),
+                            Some("view.screenshot"),
+                        ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("R_ecord Session")),
+                            Some("view.record"),
+                        ))),
+                        Some(MenuObject::Submenu(
+                            gettext("S_nippets"),
+                            self.imp().snippets_menu.clone(),
+                        )),
                         Some(MenuObject::Submenu(
                             gettext("Send _Keys"),
                             build_menu(&[
@@ -864,28 +2306,103 @@ impl FieldMonitorServerScreen {
                 let menu_vte = Self::vte_menu_shortcuts();
                 menu_vte.append_submenu(Some(&gettext("_Zoom")), &Self::vte_menu_zoom());
                 menu.append_section(None, &menu_vte);
+
+                menu.append_section(
+                    None,
+                    &build_menu(&[
+                        Some(MenuObject::Submenu(
+                            gettext("S_nippets"),
+                            self.imp().snippets_menu.clone(),
+                        )),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("_Search Output…")),
+                            Some("view.term-find"),
+                        ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("_Watch Output…")),
+                            Some("view.term-watch"),
+                        ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("_Export Output…")),
+                            Some("view.term-export"),
+                        ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("Save Scree_nshot…")),
+                            Some("view.screenshot"),
+                        ))),
+                    ]),
+                );
+
+                menu.append_section(
+                    None,
+                    &build_menu(&[
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("_View Only")),
+                            Some("view.view-only"),
+                        ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("Paste with _Middle Click")),
+                            Some("view.middle-click-paste"),
+                        ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("Resize Terminal with _Window")),
+                            Some("view.term-resize-with-window"),
+                        ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("Set Terminal _Size…")),
+                            Some("view.term-set-size"),
+                        ))),
+                        Some(MenuObject::Item(gio::MenuItem::new(
+                            Some(&gettext("_Resize Window to Terminal")),
+                            Some("view.fit-to-screen"),
+                        ))),
+                    ]),
+                );
             }
             _ => {}
         }
 
-        let more_actions = if server_actions.is_empty() {
-            None
+        let server_actions_menu = &self.imp().server_actions_menu;
+        server_actions_menu.remove_all();
+        let server_path = self.server_path();
+        for (action_id, label) in server_actions {
+            let action_target = (true, &server_path, &*action_id).to_variant();
+            server_actions_menu.append_item(&gio::MenuItem::new(
+                Some(&*label),
+                Some(&gio::Action::print_detailed_name(
+                    "app.perform-connection-action",
+                    Some(&action_target),
+                )),
+            ))
+        }
+        let more_actions = (server_actions_menu.n_items() > 0)
+            .then(|| MenuObject::Submenu(gettext("Server _Actions"), server_actions_menu.clone()));
+
+        let another_session = if self.imp().supports_multiple_sessions.get() {
+            let action_target = (self.server_path(), self.adapter_id()).to_variant();
+            Some(MenuObject::Item(gio::MenuItem::new(
+                Some(&gettext("Open _Another Session")),
+                Some(&gio::Action::print_detailed_name(
+                    "app.connect-to-server-new-session",
+                    Some(&action_target),
+                )),
+            )))
         } else {
-            let server_path = self.server_path();
-            let submenu = gio::Menu::new();
-            for (action_id, label) in server_actions {
-                let action_target = (true, &server_path, &*action_id).to_variant();
-                submenu.append_item(&gio::MenuItem::new(
-                    Some(&*label),
-                    Some(&gio::Action::print_detailed_name(
-                        "app.perform-connection-action",
-                        Some(&action_target),
-                    )),
-                ))
-            }
-            Some(MenuObject::Submenu(gettext("Server _Actions"), submenu))
+            None
         };
 
+        let switch_account_item = gio::MenuItem::new(
+            Some(&gettext("Log In with Another Acc_ount…")),
+            Some("view.switch-account"),
+        );
+        switch_account_item
+            .set_attribute_value("hidden-when", Some(&"action-disabled".to_variant()));
+        let observer_item = gio::MenuItem::new(
+            Some(&gettext("Open _Observer Window")),
+            Some("view.open-observer"),
+        );
+        observer_item.set_attribute_value("hidden-when", Some(&"action-disabled".to_variant()));
+
         menu.append_section(
             None,
             &build_menu(&[
@@ -893,6 +2410,9 @@ impl FieldMonitorServerScreen {
                     Some(&gettext("_Move to New Window")),
                     Some("tab.move-to-new-window"),
                 ))),
+                Some(MenuObject::Item(observer_item)),
+                another_session,
+                Some(MenuObject::Item(switch_account_item)),
                 more_actions,
                 Some(MenuObject::Item(gio::MenuItem::new(
                     Some(&gettext("_Close Connection")),
@@ -921,6 +2441,64 @@ impl FieldMonitorServerScreen {
 
         menu.freeze();
         self.imp().menu_button.set_menu_model(Some(&menu));
+        self.imp().snippet_kind.set(match menu_kind {
+            MenuKind::Rdw => Some(SnippetKind::Keys),
+            MenuKind::Vte => Some(SnippetKind::Text),
+            _ => None,
+        });
+        self.update_snippets();
+        self.update_session_toolbar();
+    }
+
+    /// Fills the toolbar below the header bar with the buttons chosen in the preferences.
+    fn update_session_toolbar(&self) {
+        let toolbar = &self.imp().session_toolbar;
+        while let Some(child) = toolbar.first_child() {
+            toolbar.remove(&child);
+        }
+        let items = self
+            .application()
+            .and_then(|app| app.settings())
+            .map(|settings| settings.session_toolbar_items())
+            .unwrap_or_default();
+        toolbar.set_visible(!items.is_empty());
+
+        for item in items {
+            let button: gtk::Widget = match item {
+                SessionToolbarItem::CtrlAltDel => gtk::Button::builder()
+                    .label(gettext("Ctrl+Alt+Del"))
+                    .tooltip_text(gettext("Send Ctrl+Alt+Del"))
+                    .action_name("view.send-keys")
+                    .action_target(&"<Control><Alt>Delete".to_variant())
+                    .build()
+                    .upcast(),
+                SessionToolbarItem::ServerActions => {
+                    if self.imp().server_actions_menu.n_items() == 0 {
+                        continue;
+                    }
+                    gtk::MenuButton::builder()
+                        .icon_name("system-shutdown-symbolic")
+                        .tooltip_text(gettext("Server Actions"))
+                        .menu_model(&self.imp().server_actions_menu)
+                        .build()
+                        .upcast()
+                }
+                SessionToolbarItem::Screenshot => gtk::Button::builder()
+                    .icon_name("camera-photo-symbolic")
+                    .tooltip_text(gettext("Save Screenshot"))
+                    .action_name("view.screenshot")
+                    .build()
+                    .upcast(),
+                SessionToolbarItem::ViewOnly => gtk::ToggleButton::builder()
+                    .icon_name("view-reveal-symbolic")
+                    .tooltip_text(gettext("View Only"))
+                    .action_name("view.view-only")
+                    .build()
+                    .upcast(),
+            };
+            button.add_css_class("flat");
+            toolbar.append(&button);
+        }
     }
 
     fn vte_menu_shortcuts() -> gio::Menu {
@@ -940,6 +2518,20 @@ impl FieldMonitorServerScreen {
         ])
     }
 
+    /// Entries for the link under the pointer, hidden if there is none.
+    fn vte_menu_link() -> gio::Menu {
+        let items = [
+            (gettext("_Open Link"), "view.term-open-link"),
+            (gettext("Copy _Link"), "view.term-copy-link"),
+        ]
+        .map(|(label, action)| {
+            let item = gio::MenuItem::new(Some(&label), Some(action));
+            item.set_attribute_value("hidden-when", Some(&"action-disabled".to_variant()));
+            Some(MenuObject::Item(item))
+        });
+        build_menu(&items)
+    }
+
     fn vte_menu_zoom() -> gio::Menu {
         build_menu(&[Some(MenuObject::Section(build_menu(&[
             Some(MenuObject::Item(gio::MenuItem::new(
@@ -989,6 +2581,124 @@ impl FieldMonitorServerScreen {
         }
     }
 
+    #[template_callback]
+    fn on_term_search_changed(&self) {
+        let Some(terminal) = self.terminal() else {
+            return;
+        };
+        let imp = self.imp();
+        let text = imp.term_search_entry.text();
+        imp.term_search_entry.remove_css_class("error");
+        if text.is_empty() {
+            terminal.search_set_regex(None, 0);
+            return;
+        }
+
+        let pattern = if imp.term_search_regex_button.is_active() {
+            text.to_string()
+        } else {
+            escape_regex(&text)
+        };
+        // Searches are case-insensitive unless the search contains upper case characters.
+        let mut flags = PCRE2_MULTILINE;
+        if !text.chars().any(char::is_uppercase) {
+            flags |= PCRE2_CASELESS;
+        }
+        match vte::Regex::for_search(&pattern, flags) {
+            Ok(regex) => {
+                terminal.search_set_regex(Some(&regex), 0);
+                terminal.search_set_wrap_around(true);
+                // Start from the most recent output.
+                terminal.search_find_previous();
+            }
+            Err(err) => {
+                debug!("invalid terminal search regex: {err}");
+                imp.term_search_entry.add_css_class("error");
+                terminal.search_set_regex(None, 0);
+            }
+        }
+    }
+
+    #[template_callback]
+    fn on_term_search_next(&self) {
+        self.send_term_command(TermCommand::FindNext);
+    }
+
+    #[template_callback]
+    fn on_term_search_previous(&self) {
+        self.send_term_command(TermCommand::FindPrevious);
+    }
+
+    #[template_callback]
+    fn on_term_search_bar_mode_changed(&self) {
+        if self.imp().term_search_bar.is_search_mode() {
+            return;
+        }
+        if let Some(terminal) = self.terminal() {
+            terminal.search_set_regex(None, 0);
+            terminal.grab_focus();
+        }
+    }
+
+    #[template_callback]
+    fn on_self_term_resize_with_window_changed(&self) {
+        let Some(terminal) = self.terminal() else {
+            return;
+        };
+        // With a fixed size the terminal is centered instead of filling the view. The remote
+        // side is informed about the new size by the pty (SIGWINCH) whenever it changes.
+        if self.term_resize_with_window() {
+            terminal.set_hexpand(true);
+            terminal.set_vexpand(true);
+            terminal.set_halign(gtk::Align::Fill);
+            terminal.set_valign(gtk::Align::Fill);
+        } else {
+            terminal.set_size(terminal.column_count(), terminal.row_count());
+            terminal.set_hexpand(false);
+            terminal.set_vexpand(false);
+            terminal.set_halign(gtk::Align::Center);
+            terminal.set_valign(gtk::Align::Center);
+        }
+    }
+
+    #[template_callback]
+    fn on_self_sharing_folder_changed(&self) {
+        let sharing_folder = self.sharing_folder();
+        if let Some(adapter) = self.imp().adapter.borrow().as_ref() {
+            if adapter.supports_folder_sharing() && adapter.is_sharing_folder() != sharing_folder {
+                adapter.set_sharing_folder(sharing_folder);
+            }
+        }
+    }
+
+    #[template_callback]
+    fn on_self_recording_changed(&self) {
+        if !self.recording() {
+            self.stop_recording();
+        } else if self.imp().recorder.borrow().is_none() {
+            self.start_recording();
+        }
+    }
+
+    #[template_callback]
+    fn on_self_microphone_changed(&self) {
+        let imp = self.imp();
+        let microphone = self.microphone();
+        // The microphone can only be redirected when connecting.
+        if imp
+            .adapter_microphone
+            .get()
+            .is_some_and(|redirect| redirect != microphone)
+        {
+            imp.microphone_override.set(Some(microphone));
+            glib::spawn_future_local(glib::clone!(
+                #[strong(rename_to = slf)]
+                self,
+                async move { slf.reset().await }
+            ));
+        }
+    }
+
     #[template_callback]
     fn on_self_scale_to_window_changed(&self) {
         let display = self
@@ -1009,6 +2719,104 @@ impl FieldMonitorServerScreen {
                 display.set_halign(gtk::Align::Center);
                 display.set_valign(gtk::Align::Center);
             }
+            self.set_integer_scaling_active(
+                &display,
+                self.scale_to_window() && self.integer_scaling(),
+            );
+        }
+    }
+
+    /// While `active`, keeps `display` at the largest whole multiple of the remote resolution
+    /// that fits into the view. If not even the remote resolution fits, it is scaled down to fit.
+    fn set_integer_scaling_active(&self, display: &rdw::Display, active: bool) {
+        let imp = self.imp();
+        if let Some(tick) = imp.integer_scaling_tick.take() {
+            tick.remove();
+        }
+        display.set_size_request(-1, -1);
+        if !active {
+            return;
+        }
+
+        // There is no signal for size changes of the view, so the size is checked every frame.
+        let tick = imp.display_bin.add_tick_callback(|bin, _| {
+            let Some(display) = bin.child().and_downcast::<rdw::Display>() else {
+                return glib::ControlFlow::Continue;
+            };
+            let scale = bin
+                .native()
+                .and_then(|native| native.surface())
+                .map(|surface| surface.scale())
+                .unwrap_or(1.0);
+            let size = display
+                .display_size()
+                .and_then(|remote| integer_scaled_size(remote, (bin.width(), bin.height()), scale));
+            let align = if size.is_some() {
+                gtk::Align::Center
+            } else {
+                gtk::Align::Fill
+            };
+            let (width, height) = size.unwrap_or((-1, -1));
+            if display.size_request() != (width, height) {
+                display.set_size_request(width, height);
+            }
+            display.set_halign(align);
+            display.set_valign(align);
+            glib::ControlFlow::Continue
+        });
+        imp.integer_scaling_tick.replace(Some(tick));
+    }
+
+    #[template_callback]
+    fn on_report_problem_clicked(&self) {
+        let Some(error) = self.imp().error_report.borrow().clone() else {
+            return;
+        };
+        let connection = self
+            .application()
+            .and_then(|app| app.connection(&self.connection_id()));
+        present_problem_report(self, "Connection failed", connection.as_ref(), &error);
+    }
+
+    #[template_callback]
+    fn on_self_relative_pointer_changed(&self) {
+        let Some(display) = self
+            .imp()
+            .display_bin
+            .child()
+            .and_downcast::<rdw::Display>()
+        else {
+            return;
+        };
+        let imp = self.imp();
+        if self.relative_pointer() {
+            if display.mouse_absolute() {
+                imp.adapter_mouse_absolute.set(Some(true));
+                display.set_mouse_absolute(false);
+            }
+        } else if let Some(absolute) = imp.adapter_mouse_absolute.take() {
+            display.set_mouse_absolute(absolute);
+        }
+    }
+
+    #[template_callback]
+    fn on_self_view_only_changed(&self) {
+        let view_only = self.view_only();
+        if let Some(terminal) = self.terminal() {
+            terminal.set_input_enabled(!view_only);
+        }
+        let display = self
+            .imp()
+            .display_bin
+            .child()
+            .map(Cast::downcast::<rdw::Display>)
+            .and_then(Result::ok);
+        if let Some(display) = display {
+            if view_only {
+                self.imp().focus_grabber.ungrab();
+            }
+            display.set_can_target(!view_only);
+            display.set_focusable(!view_only);
         }
     }
 
@@ -1030,6 +2838,7 @@ impl FieldMonitorServerScreen {
             } else {
                 window.remove_css_class("connection-view-grabbed");
             }
+            window.set_input_grabbed(grabbed);
         }
 
         if let Some(display) = self
@@ -1045,16 +2854,42 @@ impl FieldMonitorServerScreen {
                 // TODO: The displayed string is not stable.
                 let shortcut = shortcut.split(',').next().unwrap();
 
-                self.imp().grab_note.show_note(&gettext_f(
+                let note = gettext_f(
                     "Press {keycombo} to ungrab the mouse and keyboard.",
                     &[("keycombo", shortcut)],
-                ));
+                );
+                self.imp().grab_note.show_note(&note);
+                self.announce_status(
+                    &format!(
+                        "{} {note}",
+                        gettext("Mouse and keyboard input is sent to the server.")
+                    ),
+                    gtk::AccessibleAnnouncementPriority::Medium,
+                );
             } else {
                 self.imp().grab_note.hide_note();
+                self.announce_status(
+                    &gettext("Mouse and keyboard input is no longer sent to the server."),
+                    gtk::AccessibleAnnouncementPriority::Medium,
+                );
             }
         }
     }
 
+    #[template_callback]
+    fn on_retry_family_clicked(&self) {
+        let Some((host, family)) = self.imp().retry_family.take() else {
+            return;
+        };
+        info!("Retrying connection to {host} with {family:?}");
+        set_preferred_family(&host, family);
+        glib::spawn_future_local(glib::clone!(
+            #[strong(rename_to = slf)]
+            self,
+            async move { slf.reset().await }
+        ));
+    }
+
     #[template_callback]
     fn on_show_navigation_clicked(&self) {
         // TODO: A bug somewhere? For some reason clicking this button only partially ungrabs in a
@@ -1098,6 +2933,13 @@ impl FieldMonitorServerScreen {
 
         let toolbar_view = &self.imp().toolbar_view;
 
+        // Kiosk windows never show any controls.
+        if self.window().is_some_and(|window| window.kiosk()) {
+            toolbar_view.set_extend_content_to_top_edge(true);
+            toolbar_view.set_reveal_top_bars(false);
+            return;
+        }
+
         match (header_bar_behavior, reveal_osd_controls, fullscreened) {
             // On top with no-overlay or not in fullscreen: no overlay
             (SettingHeaderBarBehavior::NoOverlay, _, _)
@@ -1121,6 +2963,39 @@ impl FieldMonitorServerScreen {
     }
 }
 
+/// State of the session of a server screen.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "FieldMonitorSessionStatus")]
+pub enum SessionStatus {
+    #[default]
+    Connecting,
+    Connected,
+    /// The connection was closed without an error.
+    Disconnected,
+    Failed,
+}
+
+impl SessionStatus {
+    pub fn label(self) -> String {
+        match self {
+            SessionStatus::Connecting => gettext("Connecting…"),
+            SessionStatus::Connected => gettext("Connected"),
+            SessionStatus::Disconnected => gettext("Disconnected"),
+            SessionStatus::Failed => gettext("Connection Failed"),
+        }
+    }
+
+    /// Name used for the state in stacks and CSS classes.
+    pub fn name(self) -> &'static str {
+        match self {
+            SessionStatus::Connecting => "connecting",
+            SessionStatus::Connected => "connected",
+            SessionStatus::Disconnected => "disconnected",
+            SessionStatus::Failed => "failed",
+        }
+    }
+}
+
 enum MenuKind {
     Rdw,
     Vte,
@@ -1133,6 +3008,71 @@ enum MenuObject {
     Submenu(String, gio::Menu),
 }
 
+/// Icon of the adapter with the ID `adapter_id`.
+pub fn adapter_icon_name(adapter_id: &str) -> &'static str {
+    match adapter_id {
+        RdpAdapter::TAG | SpiceAdapter::TAG | VncAdapter::TAG => "display-with-window-symbolic",
+        VtePtyAdapter::TAG => "utilities-terminal-symbolic",
+        WebAdapter::TAG => "web-browser-symbolic",
+        _ => "network-server-symbolic",
+    }
+}
+
+/// Name of the adapter with the ID `adapter_id` for announcements.
+fn adapter_title(adapter_id: &str) -> String {
+    match adapter_id {
+        RdpAdapter::TAG => gettext("RDP"),
+        SpiceAdapter::TAG => gettext("SPICE"),
+        VncAdapter::TAG => gettext("VNC"),
+        VtePtyAdapter::TAG => gettext("Console"),
+        WebAdapter::TAG => WebAdapter::label(),
+        other => other.to_string(),
+    }
+}
+
+/// Renders `widget` as it is currently shown into a texture.
+fn render_widget(widget: &gtk::Widget) -> Option<gdk::Texture> {
+    let snapshot = gtk::Snapshot::new();
+    gtk::WidgetPaintable::new(Some(widget)).snapshot(
+        &snapshot,
+        widget.width() as f64,
+        widget.height() as f64,
+    );
+    let node = snapshot.to_node()?;
+    let renderer = widget.native()?.renderer()?;
+    Some(renderer.render_texture(node, None))
+}
+
+/// Rounds a terminal font scale to whole zoom steps and clamps it to the supported range, so
+/// repeated zooming does not accumulate rounding errors in the stored value.
+fn normalize_term_zoom(zoom: f64) -> f64 {
+    ((zoom * TERM_ZOOM_STEPS_PER_UNIT).round() / TERM_ZOOM_STEPS_PER_UNIT)
+        .clamp(TERM_ZOOM_MIN, TERM_ZOOM_MAX)
+}
+
+/// Logical size of a display showing the `remote` resolution at the largest whole multiple that
+/// fits into `available` logical pixels, with `scale` device pixels per logical pixel. `None` if
+/// the remote resolution does not fit even once.
+fn integer_scaled_size(
+    remote: (usize, usize),
+    available: (i32, i32),
+    scale: f64,
+) -> Option<(i32, i32)> {
+    let (remote_w, remote_h) = (remote.0 as f64, remote.1 as f64);
+    if remote_w == 0.0 || remote_h == 0.0 {
+        return None;
+    }
+    let factor = (available.0 as f64 * scale / remote_w)
+        .min(available.1 as f64 * scale / remote_h)
+        .floor();
+    (factor >= 1.0).then(|| {
+        (
+            (remote_w * factor / scale).round() as i32,
+            (remote_h * factor / scale).round() as i32,
+        )
+    })
+}
+
 fn build_menu(items: &[Option<MenuObject>]) -> gio::Menu {
     let menu = gio::Menu::new();
 
@@ -1154,4 +3094,20 @@ enum TermCommand {
     ZoomReset,
     ZoomIn,
     ZoomOut,
+    Find,
+    FindNext,
+    FindPrevious,
+    Export,
+}
+
+/// Escapes all characters of `text` that have a special meaning in PCRE2 patterns.
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.^$|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
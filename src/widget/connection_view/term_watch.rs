@@ -0,0 +1,237 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Watch rules that check the output of terminal consoles for regular expressions.
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use adw::prelude::*;
+use anyhow::anyhow;
+use gettextrs::gettext;
+use gtk::glib;
+use log::{debug, warn};
+use vte::prelude::*;
+
+/// A regular expression the terminal output is checked against.
+pub struct TermWatchRule {
+    pub pattern: String,
+    regex: glib::Regex,
+}
+
+impl TermWatchRule {
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        let regex = glib::Regex::new(
+            pattern,
+            glib::RegexCompileFlags::OPTIMIZE,
+            glib::RegexMatchFlags::DEFAULT,
+        )?
+        .ok_or_else(|| anyhow!("invalid pattern: {pattern}"))?;
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex,
+        })
+    }
+
+    fn matches(&self, line: &str) -> bool {
+        self.regex
+            .match_(
+                glib::GString::from(line).as_gstr(),
+                glib::RegexMatchFlags::DEFAULT,
+            )
+            .is_some()
+    }
+}
+
+/// Checks each completed line of terminal output against the watch rules of a session.
+#[derive(Default)]
+pub struct TermWatcher {
+    rules: RefCell<Vec<TermWatchRule>>,
+    /// The first row that was not checked yet.
+    next_row: Cell<i64>,
+}
+
+impl TermWatcher {
+    pub fn patterns(&self) -> Vec<String> {
+        self.rules
+            .borrow()
+            .iter()
+            .map(|rule| rule.pattern.clone())
+            .collect()
+    }
+
+    pub fn add_rule(&self, rule: TermWatchRule) {
+        self.rules.borrow_mut().push(rule);
+    }
+
+    pub fn remove_rule(&self, pattern: &str) {
+        self.rules
+            .borrow_mut()
+            .retain(|rule| rule.pattern != pattern);
+    }
+
+    /// Starts checking the output of `terminal`. `on_match` is called with the pattern and the
+    /// line for every line matching a rule. Output that is already in the terminal is skipped.
+    pub fn watch(
+        self: &Rc<Self>,
+        terminal: &vte::Terminal,
+        on_match: impl Fn(&str, &str) + 'static,
+    ) {
+        self.next_row.set(terminal.cursor_position().1);
+        terminal.connect_contents_changed(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            move |terminal| {
+                for (pattern, line) in slf.check_new_lines(terminal) {
+                    on_match(&pattern, &line);
+                }
+            }
+        ));
+    }
+
+    fn check_new_lines(&self, terminal: &vte::Terminal) -> Vec<(String, String)> {
+        let cursor_row = terminal.cursor_position().1;
+        let next_row = self.next_row.get();
+        if cursor_row < next_row {
+            // The terminal was reset or cleared.
+            self.next_row.set(cursor_row);
+            return Vec::new();
+        }
+        // Only completed lines are checked, the line of the cursor may still change.
+        if cursor_row == next_row {
+            return Vec::new();
+        }
+        self.next_row.set(cursor_row);
+
+        let rules = self.rules.borrow();
+        if rules.is_empty() {
+            return Vec::new();
+        }
+        let (text, _) = terminal.text_range_format(
+            vte::Format::Text,
+            next_row,
+            0,
+            cursor_row - 1,
+            terminal.column_count(),
+        );
+        let Some(text) = text else {
+            return Vec::new();
+        };
+
+        let mut matches = Vec::new();
+        for line in text.lines() {
+            for rule in rules.iter().filter(|rule| rule.matches(line)) {
+                debug!("terminal output matched watch rule {}", rule.pattern);
+                matches.push((rule.pattern.clone(), line.trim().to_string()));
+            }
+        }
+        matches
+    }
+}
+
+/// Presents a dialog for editing the watch rules of `watcher`.
+pub fn present_term_watch_dialog(parent: &impl IsA<gtk::Widget>, watcher: Rc<TermWatcher>) {
+    let group = adw::PreferencesGroup::builder()
+        .description(gettext(
+            "A notification is shown when a line of the output matches one of these regular expressions.",
+        ))
+        .build();
+    let add_row = adw::EntryRow::builder()
+        .title(gettext("Add Regular Expression"))
+        .show_apply_button(true)
+        .build();
+    group.add(&add_row);
+
+    let rule_rows: Rc<RefCell<Vec<adw::ActionRow>>> = Rc::default();
+    update_rule_rows(&group, &rule_rows, &watcher);
+
+    add_row.connect_apply(glib::clone!(
+        #[weak]
+        group,
+        move |row| {
+            let pattern = row.text();
+            if pattern.is_empty() {
+                return;
+            }
+            match TermWatchRule::new(&pattern) {
+                Ok(rule) => {
+                    row.remove_css_class("error");
+                    row.set_text("");
+                    watcher.add_rule(rule);
+                    update_rule_rows(&group, &rule_rows, &watcher);
+                }
+                Err(err) => {
+                    warn!("invalid watch rule: {err}");
+                    row.add_css_class("error");
+                }
+            }
+        }
+    ));
+
+    let page = adw::PreferencesPage::new();
+    page.add(&group);
+    let view = adw::ToolbarView::new();
+    view.add_top_bar(&adw::HeaderBar::new());
+    view.set_content(Some(&page));
+
+    let dialog = adw::Dialog::builder()
+        .title(gettext("Watch Output"))
+        .content_width(450)
+        .content_height(400)
+        .child(&view)
+        .build();
+    dialog.present(Some(parent));
+}
+
+fn update_rule_rows(
+    group: &adw::PreferencesGroup,
+    rule_rows: &Rc<RefCell<Vec<adw::ActionRow>>>,
+    watcher: &Rc<TermWatcher>,
+) {
+    for row in rule_rows.take() {
+        group.remove(&row);
+    }
+
+    let mut rows = Vec::new();
+    for pattern in watcher.patterns() {
+        let row = adw::ActionRow::builder()
+            .title(glib::markup_escape_text(&pattern))
+            .css_classes(["monospace"])
+            .build();
+        let remove_button = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text(gettext("Remove Rule"))
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+        remove_button.connect_clicked(glib::clone!(
+            #[weak]
+            group,
+            #[strong]
+            rule_rows,
+            #[strong]
+            watcher,
+            move |_| {
+                watcher.remove_rule(&pattern);
+                update_rule_rows(&group, &rule_rows, &watcher);
+            }
+        ));
+        row.add_suffix(&remove_button);
+        group.add(&row);
+        rows.push(row);
+    }
+    rule_rows.replace(rows);
+}
@@ -0,0 +1,408 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Overview of all connections: status of their servers, open sessions, failed actions and
+//! recent failures.
+use crate::application::FieldMonitorApplication;
+use crate::audit_log;
+use crate::failed_actions::FailedAction;
+use crate::widget::connection_view::FieldMonitorServerScreen;
+use crate::widget::preferences::make_audit_log_row;
+use crate::widget::window::FieldMonitorWindow;
+use crate::APP;
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+use libfieldmonitor::connection::*;
+use libfieldmonitor::i18n::{format_date_time, format_relative_time, gettext_f};
+use log::warn;
+use std::cell::{Cell, RefCell};
+
+/// Number of failures from the activity log that are shown.
+const MAX_FAILURE_ROWS: usize = 5;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate, glib::Properties)]
+    #[properties(wrapper_type = super::FieldMonitorDashboard)]
+    #[template(resource = "/de/capypara/FieldMonitor/widget/dashboard.ui")]
+    pub struct FieldMonitorDashboard {
+        #[template_child]
+        pub online_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub offline_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub unknown_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub sessions_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub failed_actions_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub failures_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub connections_list: TemplateChild<gtk::ListBox>,
+        #[property(get, construct_only)]
+        pub application: RefCell<Option<FieldMonitorApplication>>,
+        /// Set while a refresh is running.
+        pub refreshing: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for FieldMonitorDashboard {
+        const NAME: &'static str = "FieldMonitorDashboard";
+        type Type = super::FieldMonitorDashboard;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for FieldMonitorDashboard {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+            let mut app_brw = self.application.borrow_mut();
+
+            if app_brw.is_none() {
+                *app_brw = Some(APP.with_borrow(|app| app.clone().unwrap()))
+            }
+
+            let app = app_brw.clone().unwrap();
+            drop(app_brw);
+
+            obj.connect_map(|slf| slf.refresh());
+            for signal in ["connection-updated", "connection-removed"] {
+                app.connect_local(
+                    signal,
+                    false,
+                    glib::clone!(
+                        #[weak]
+                        obj,
+                        #[upgrade_or_default]
+                        move |_| {
+                            if obj.is_mapped() {
+                                obj.refresh();
+                            }
+                            None
+                        }
+                    ),
+                );
+            }
+            app.connect_local(
+                "failed-actions-changed",
+                false,
+                glib::clone!(
+                    #[weak]
+                    obj,
+                    #[upgrade_or_default]
+                    move |_| {
+                        if obj.is_mapped() {
+                            obj.update_failed_actions();
+                            obj.update_failures();
+                        }
+                        None
+                    }
+                ),
+            );
+            if let Some(poller) = app.status_poller() {
+                poller.subscribe(
+                    &*obj,
+                    glib::clone!(
+                        #[weak]
+                        obj,
+                        move || obj.refresh()
+                    ),
+                );
+            }
+        }
+    }
+    impl WidgetImpl for FieldMonitorDashboard {}
+    impl BinImpl for FieldMonitorDashboard {}
+}
+
+glib::wrapper! {
+    pub struct FieldMonitorDashboard(ObjectSubclass<imp::FieldMonitorDashboard>)
+        @extends gtk::Widget, adw::Bin;
+}
+
+impl FieldMonitorDashboard {
+    /// Updates the dashboard, unless an update is already running.
+    pub fn refresh(&self) {
+        if self.imp().refreshing.replace(true) {
+            return;
+        }
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move {
+                slf.update_sessions();
+                slf.update_failed_actions();
+                slf.update_failures();
+                slf.update_connections().await;
+                slf.imp().refreshing.set(false);
+            }
+        ));
+    }
+
+    fn update_sessions(&self) {
+        let list = &self.imp().sessions_list;
+        list.remove_all();
+        let Some(app) = self.application() else {
+            return;
+        };
+        let screens = app
+            .windows()
+            .into_iter()
+            .filter_map(|window| window.downcast::<FieldMonitorWindow>().ok())
+            .flat_map(|window| window.tab_view().screens())
+            .collect::<Vec<_>>();
+        if screens.is_empty() {
+            list.append(&placeholder_row(&gettext("No open sessions")));
+        }
+        for screen in screens {
+            list.append(&session_row(&screen));
+        }
+    }
+
+    fn update_failed_actions(&self) {
+        let list = &self.imp().failed_actions_list;
+        list.remove_all();
+        let actions = self
+            .application()
+            .map(|app| app.failed_actions())
+            .unwrap_or_default();
+        if actions.is_empty() {
+            list.append(&placeholder_row(&gettext("No failed actions")));
+        }
+        for action in actions {
+            list.append(&self.failed_action_row(action));
+        }
+    }
+
+    fn update_failures(&self) {
+        let list = &self.imp().failures_list;
+        list.remove_all();
+        let entries = audit_log::read_entries().unwrap_or_else(|err| {
+            warn!("failed to read audit log: {err}");
+            Vec::new()
+        });
+        let failures = entries
+            .iter()
+            .rev()
+            .filter(|entry| !entry.success)
+            .take(MAX_FAILURE_ROWS)
+            .collect::<Vec<_>>();
+        if failures.is_empty() {
+            list.append(&placeholder_row(&gettext("No recent failures")));
+        }
+        for entry in failures {
+            list.append(&make_audit_log_row(entry));
+        }
+    }
+
+    async fn update_connections(&self) {
+        let Some(app) = self.application() else {
+            return;
+        };
+        let mut total = ServerCounts::default();
+        let mut rows = Vec::new();
+        for connection in app.connections() {
+            let (counts, result) = if connection.loaded() {
+                let mut counts = ServerCounts::default();
+                let result = match connection.servers().await {
+                    Ok(servers) => counts.add_all(servers).await,
+                    Err(err) => Err(err),
+                };
+                (counts, result)
+            } else {
+                (ServerCounts::default(), Ok(()))
+            };
+            total.online += counts.online;
+            total.offline += counts.offline;
+            total.unknown += counts.unknown;
+            rows.push(connection_row(&connection, &counts, result));
+        }
+
+        let imp = self.imp();
+        imp.online_label.set_label(&total.online.to_string());
+        imp.offline_label.set_label(&total.offline.to_string());
+        imp.unknown_label.set_label(&total.unknown.to_string());
+
+        // The list is only replaced once all connections are loaded, so it does not flicker.
+        let list = &imp.connections_list;
+        list.remove_all();
+        if rows.is_empty() {
+            list.append(&placeholder_row(&gettext("No connections")));
+        }
+        for row in rows {
+            list.append(&row);
+        }
+    }
+
+    fn failed_action_row(&self, action: FailedAction) -> adw::ActionRow {
+        let subtitle = match &action.time {
+            Some(time) => format!("{} · {}", format_relative_time(time), action.message),
+            None => action.message.clone(),
+        };
+        let row = adw::ActionRow::builder()
+            .title(format!("{}: {}", action.action_label, action.title))
+            .subtitle(subtitle)
+            .use_markup(false)
+            .build();
+        if let Some(time) = &action.time {
+            row.set_tooltip_text(Some(&format!(
+                "{}\n{}",
+                format_date_time(time),
+                action.path
+            )));
+        }
+        row.add_prefix(&gtk::Image::from_icon_name("dialog-error-symbolic"));
+
+        let retry_button = gtk::Button::builder()
+            .icon_name("view-refresh-symbolic")
+            .tooltip_text(gettext("Retry"))
+            .valign(gtk::Align::Center)
+            .action_name("app.perform-connection-action")
+            .action_target(&action.action_target())
+            .css_classes(["flat"])
+            .build();
+        row.add_suffix(&retry_button);
+
+        let dismiss_button = gtk::Button::builder()
+            .icon_name("window-close-symbolic")
+            .tooltip_text(gettext("Dismiss"))
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+        dismiss_button.connect_clicked(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            move |_| {
+                if let Some(app) = slf.application() {
+                    app.dismiss_failed_action(&action.path, &action.action_id);
+                }
+            }
+        ));
+        row.add_suffix(&dismiss_button);
+        row
+    }
+}
+
+#[derive(Default)]
+struct ServerCounts {
+    online: usize,
+    offline: usize,
+    unknown: usize,
+}
+
+impl ServerCounts {
+    /// Counts `servers` and all of their sub-servers.
+    async fn add_all(&mut self, servers: ServerMap) -> ConnectionResult<()> {
+        let mut pending = vec![servers];
+        while let Some(servers) = pending.pop() {
+            for server in servers.values() {
+                match server.metadata().is_online {
+                    Some(true) => self.online += 1,
+                    Some(false) => self.offline += 1,
+                    None => self.unknown += 1,
+                }
+                pending.push(server.servers().await?);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn placeholder_row(title: &str) -> adw::ActionRow {
+    adw::ActionRow::builder()
+        .sensitive(false)
+        .title(title)
+        .build()
+}
+
+fn session_row(screen: &FieldMonitorServerScreen) -> adw::ActionRow {
+    let state = if screen.is_connected() {
+        gettext("Connected")
+    } else {
+        gettext("Not Connected")
+    };
+    let subtitle = [screen.subtitle(), state]
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" · ");
+    let row = adw::ActionRow::builder()
+        .title(screen.title())
+        .subtitle(subtitle)
+        .use_markup(false)
+        .activatable(true)
+        .build();
+    row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+    row.connect_activated(glib::clone!(
+        #[weak]
+        screen,
+        move |_| {
+            if let Some(window) = screen.window() {
+                window.present();
+                window.focus_connection_view(&screen.server_path(), &screen.adapter_id());
+            }
+        }
+    ));
+    row
+}
+
+fn connection_row(
+    connection: &ConnectionInstance,
+    counts: &ServerCounts,
+    result: ConnectionResult<()>,
+) -> adw::ActionRow {
+    let subtitle = match result {
+        _ if !connection.loaded() => gettext("Not loaded"),
+        Err(ConnectionError::AuthFailed(..)) => gettext("Authentication required"),
+        Err(ConnectionError::General(..)) => gettext("Failed to load"),
+        Ok(()) if counts.online + counts.offline == 0 => gettext("Status unknown"),
+        Ok(()) => gettext_f(
+            "{online} of {total} servers online",
+            &[
+                ("online", &counts.online.to_string()),
+                ("total", &(counts.online + counts.offline).to_string()),
+            ],
+        ),
+    };
+    let row = adw::ActionRow::builder()
+        .title(connection.title())
+        .subtitle(subtitle)
+        .use_markup(false)
+        .activatable(true)
+        .build();
+    row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+    let connection_id = connection.connection_id();
+    row.connect_activated(move |row| {
+        if let Some(window) = row.root().and_downcast::<FieldMonitorWindow>() {
+            window.show_connection(&connection_id);
+        }
+    });
+    row
+}
@@ -0,0 +1,157 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Dialog for configuring the external tools servers of a connection can be opened with.
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+use libfieldmonitor::i18n::gettext_f;
+
+use crate::settings::ExternalTool;
+
+struct ToolRows {
+    expander: adw::ExpanderRow,
+    name: adw::EntryRow,
+    url: adw::EntryRow,
+}
+
+impl ToolRows {
+    fn tool(&self) -> Option<ExternalTool> {
+        let name = self.name.text().trim().to_string();
+        let url = self.url.text().trim().to_string();
+        (!name.is_empty() && !url.is_empty()).then_some(ExternalTool { name, url })
+    }
+}
+
+fn add_tool_rows(
+    list: &gtk::ListBox,
+    rows: &Rc<RefCell<Vec<ToolRows>>>,
+    tool: &ExternalTool,
+) -> adw::ExpanderRow {
+    let expander = adw::ExpanderRow::builder()
+        .title(glib::markup_escape_text(&tool.name))
+        .subtitle(glib::markup_escape_text(&tool.url))
+        .build();
+    let name = adw::EntryRow::builder()
+        .title(gettext("Name"))
+        .text(&tool.name)
+        .build();
+    let url = adw::EntryRow::builder()
+        .title(gettext("URL"))
+        .text(&tool.url)
+        .input_purpose(gtk::InputPurpose::Url)
+        .build();
+    name.bind_property("text", &expander, "title")
+        .transform_to(|_, text: String| Some(glib::markup_escape_text(&text).to_string()))
+        .build();
+    url.bind_property("text", &expander, "subtitle")
+        .transform_to(|_, text: String| Some(glib::markup_escape_text(&text).to_string()))
+        .build();
+    expander.add_row(&name);
+    expander.add_row(&url);
+
+    let remove_button = gtk::Button::builder()
+        .icon_name("user-trash-symbolic")
+        .tooltip_text(gettext("Remove Tool"))
+        .valign(gtk::Align::Center)
+        .css_classes(["flat"])
+        .build();
+    remove_button.connect_clicked(glib::clone!(
+        #[weak]
+        list,
+        #[weak]
+        expander,
+        #[weak]
+        rows,
+        move |_| {
+            rows.borrow_mut().retain(|rows| rows.expander != expander);
+            list.remove(&expander);
+        }
+    ));
+    expander.add_suffix(&remove_button);
+
+    list.append(&expander);
+    rows.borrow_mut().push(ToolRows {
+        expander: expander.clone(),
+        name,
+        url,
+    });
+    expander
+}
+
+/// Lets the user edit the external `tools` of the connection titled `connection_title`.
+/// Returns the new list of tools, or `None` if the dialog was cancelled.
+pub async fn edit_external_tools(
+    connection_title: &str,
+    tools: &[ExternalTool],
+    window: Option<&gtk::Window>,
+) -> Option<Vec<ExternalTool>> {
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+    let rows = Rc::new(RefCell::new(Vec::with_capacity(tools.len())));
+    for tool in tools {
+        add_tool_rows(&list, &rows, tool);
+    }
+
+    let add_button = gtk::Button::builder()
+        .label(gettext("Add Tool"))
+        .halign(gtk::Align::Center)
+        .css_classes(["pill"])
+        .build();
+    add_button.connect_clicked(glib::clone!(
+        #[weak]
+        list,
+        #[weak]
+        rows,
+        move |_| {
+            add_tool_rows(&list, &rows, &ExternalTool::default()).set_expanded(true);
+        }
+    ));
+
+    let content = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(12)
+        .build();
+    content.append(&list);
+    content.append(&add_button);
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext_f(
+            "External Tools of {connection}",
+            &[("connection", connection_title)],
+        ))
+        .body(gettext(
+            "Tools are shown in the menu of each server and open the URL with the default application for it, eg. https://${host} in a browser or sftp://${host} in a file manager. ${host} and ${title} are replaced by the host and name of the server.",
+        ))
+        .extra_child(&content)
+        .close_response("cancel")
+        .default_response("save")
+        .build();
+    dialog.add_responses(&[("cancel", &gettext("Cancel")), ("save", &gettext("Save"))]);
+    dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+
+    if dialog.choose_future(window).await.as_str() != "save" {
+        return None;
+    }
+    let tools = rows.borrow().iter().filter_map(ToolRows::tool).collect();
+    Some(tools)
+}
@@ -21,8 +21,9 @@ use std::cell::RefCell;
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
+use gettextrs::gettext;
 use glib::WeakRef;
-use gtk::glib;
+use gtk::{gdk, glib};
 use log::debug;
 use rdw::DisplayExt;
 
@@ -36,6 +37,9 @@ mod imp {
     pub struct FieldMonitorFocusGrabber {
         #[property(get)]
         pub grabbed: Cell<bool>,
+        /// Whether input is grabbed when the window gets focused, instead of only on click.
+        #[property(get, set)]
+        pub grab_on_focus: Cell<bool>,
         pub display: RefCell<Option<WeakRef<rdw::Display>>>,
         pub display_signal_id: RefCell<Option<glib::SignalHandlerId>>,
     }
@@ -45,6 +49,10 @@ mod imp {
         const NAME: &'static str = "FieldMonitorFocusGrabber";
         type Type = super::FieldMonitorFocusGrabber;
         type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_accessible_role(gtk::AccessibleRole::Button);
+        }
     }
 
     #[glib::derived_properties]
@@ -53,6 +61,38 @@ mod imp {
             self.parent_constructed();
             let obj = self.obj();
             obj.set_visible(false);
+            // Keyboard users can focus the grabber and activate it like a button.
+            obj.set_focusable(true);
+            obj.update_property(&[
+                gtk::accessible::Property::Label(&gettext("Control Remote Screen")),
+                gtk::accessible::Property::Description(&gettext(
+                    "Sends mouse and keyboard input to the server",
+                )),
+            ]);
+
+            let key_controller = gtk::EventControllerKey::new();
+            key_controller.connect_key_pressed(glib::clone!(
+                #[weak]
+                obj,
+                #[upgrade_or]
+                glib::Propagation::Proceed,
+                move |_, key, _, modifiers| {
+                    let activates = matches!(
+                        key,
+                        gdk::Key::Return
+                            | gdk::Key::KP_Enter
+                            | gdk::Key::ISO_Enter
+                            | gdk::Key::space
+                    );
+                    if activates && modifiers.is_empty() {
+                        obj.grab();
+                        glib::Propagation::Stop
+                    } else {
+                        glib::Propagation::Proceed
+                    }
+                }
+            ));
+            obj.add_controller(key_controller);
 
             let controller = gtk::GestureClick::builder()
                 .propagation_phase(gtk::PropagationPhase::Capture)
@@ -78,6 +118,15 @@ mod imp {
         }
     }
     impl WidgetImpl for FieldMonitorFocusGrabber {
+        fn map(&self) {
+            self.parent_map();
+            // Switching to the tab of the display counts as focusing it.
+            let obj = self.obj();
+            if let Some(window) = obj.root().and_downcast::<gtk::Window>() {
+                obj.on_window_active(window.is_active());
+            }
+        }
+
         fn realize(&self) {
             self.parent_realize();
 
@@ -107,7 +156,8 @@ mod imp {
 
 glib::wrapper! {
     pub struct FieldMonitorFocusGrabber(ObjectSubclass<imp::FieldMonitorFocusGrabber>)
-        @extends gtk::Widget, adw::Bin;
+        @extends gtk::Widget, adw::Bin,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
 }
 
 impl FieldMonitorFocusGrabber {
@@ -136,7 +186,8 @@ impl FieldMonitorFocusGrabber {
         *display_opt = value.map(ObjectExt::downgrade);
     }
 
-    fn grab(&self) {
+    /// Sends mouse and keyboard input to the display, if any.
+    pub fn grab(&self) {
         let imp = self.imp();
         if imp.grabbed.get() {
             return;
@@ -219,6 +270,8 @@ impl FieldMonitorFocusGrabber {
     fn on_window_active(&self, is_active: bool) {
         if self.grabbed() && !is_active {
             self.ungrab();
+        } else if !self.grabbed() && is_active && self.grab_on_focus() && self.is_mapped() {
+            self.grab();
         }
     }
 }
@@ -0,0 +1,132 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Loading indicator that only animates if animations are enabled.
+//!
+//! If the user asked for reduced motion (animations are disabled in the GTK settings), a static
+//! icon is shown instead of a spinner.
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+
+const STATIC_ICON: &str = "content-loading-symbolic";
+
+fn animations_enabled() -> bool {
+    gtk::Settings::default().map_or(true, |settings| settings.is_gtk_enable_animations())
+}
+
+/// Calls `f` now and whenever animations are enabled or disabled, as long as `widget` exists.
+fn follow_animations_setting<W: IsA<gtk::Widget>>(widget: &W, f: impl Fn(&W, bool) + 'static) {
+    f(widget, animations_enabled());
+    if let Some(settings) = gtk::Settings::default() {
+        let widget = widget.downgrade();
+        settings.connect_gtk_enable_animations_notify(move |settings| {
+            if let Some(widget) = widget.upgrade() {
+                f(&widget, settings.is_gtk_enable_animations());
+            }
+        });
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct FieldMonitorLoadingIndicator {}
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for FieldMonitorLoadingIndicator {
+        const NAME: &'static str = "FieldMonitorLoadingIndicator";
+        type Type = super::FieldMonitorLoadingIndicator;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_accessible_role(gtk::AccessibleRole::ProgressBar);
+        }
+    }
+
+    impl ObjectImpl for FieldMonitorLoadingIndicator {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+
+            obj.update_property(&[gtk::accessible::Property::Label(&gettext("Loading"))]);
+            obj.update_state(&[gtk::accessible::State::Busy(true)]);
+            follow_animations_setting(&*obj, |obj, animate| {
+                let child: gtk::Widget = if animate {
+                    adw::Spinner::new().upcast()
+                } else {
+                    gtk::Image::builder()
+                        .icon_name(STATIC_ICON)
+                        .pixel_size(32)
+                        .halign(gtk::Align::Center)
+                        .valign(gtk::Align::Center)
+                        .css_classes(["dim-label"])
+                        .build()
+                        .upcast()
+                };
+                obj.set_child(Some(&child));
+            });
+        }
+    }
+    impl WidgetImpl for FieldMonitorLoadingIndicator {}
+    impl BinImpl for FieldMonitorLoadingIndicator {}
+}
+
+glib::wrapper! {
+    pub struct FieldMonitorLoadingIndicator(ObjectSubclass<imp::FieldMonitorLoadingIndicator>)
+        @extends gtk::Widget, adw::Bin,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+/// Shows a spinner on `status_page`, or a static icon if animations are disabled.
+pub fn set_loading_paintable(status_page: &adw::StatusPage) {
+    follow_animations_setting(status_page, |status_page, animate| {
+        if animate {
+            status_page.set_paintable(Some(&adw::SpinnerPaintable::new(Some(status_page))));
+        } else {
+            status_page.set_icon_name(Some(STATIC_ICON));
+        }
+    });
+}
+
+/// Small loading indicator to be shown next to other widgets, such as the buttons of a row.
+pub fn inline_loading_indicator() -> adw::Bin {
+    let bin = adw::Bin::builder()
+        .valign(gtk::Align::Center)
+        .accessible_role(gtk::AccessibleRole::ProgressBar)
+        .build();
+    bin.update_state(&[gtk::accessible::State::Busy(true)]);
+    follow_animations_setting(&bin, |bin, animate| {
+        let child: gtk::Widget = if animate {
+            adw::Spinner::builder()
+                .width_request(16)
+                .height_request(16)
+                .build()
+                .upcast()
+        } else {
+            gtk::Image::builder()
+                .icon_name(STATIC_ICON)
+                .css_classes(["dim-label"])
+                .build()
+                .upcast()
+        };
+        bin.set_child(Some(&child));
+    });
+    bin
+}
@@ -17,13 +17,19 @@
  */
 
 pub mod add_connection_dialog;
+pub mod appearance_dialog;
 pub mod authenticate_connection_dialog;
-mod close_warning_dialog;
+pub mod close_warning_dialog;
 pub mod connection_list;
 pub mod connection_view;
+mod dashboard;
+pub mod external_tools_dialog;
 mod foucs_grabber;
 mod grab_note;
+mod loading_indicator;
 mod navbar_row;
 pub mod preferences;
+pub mod server_tags_dialog;
+mod status_indicator;
 pub mod update_connection_dialog;
 pub mod window;
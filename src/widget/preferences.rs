@@ -17,15 +17,31 @@
  */
 
 use crate::application::FieldMonitorApplication;
-use crate::settings::{SettingHeaderBarBehavior, SettingSharpWindowCorners};
+use crate::audit_log::{self, AuditEntry};
+use crate::event_sinks::SinkEventKind;
+use crate::settings::{
+    ConfigurableShortcut, FieldMonitorSettings, SessionToolbarItem, SettingDisplayRendering,
+    SettingHeaderBarBehavior, SettingScalingFilter, SettingSharpWindowCorners,
+    SettingTermColorScheme, SettingTermCursorShape, SmartFolder, SmartFolderOnlineState, Snippet,
+    SnippetKind,
+};
+use crate::widget::window::FieldMonitorWindow;
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use gettextrs::gettext;
-use gtk::glib;
+use gtk::{gdk, glib, pango};
+use libfieldmonitor::i18n::{format_date_time, format_relative_time, gettext_f};
+use libfieldmonitor::template;
+use log::{error, warn};
 use std::cell::Cell;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// Shown in the font chooser while the system monospace font is used.
+const DEFAULT_TERM_FONT: &str = "Monospace 11";
+/// Number of the most recent audit log entries shown in the preferences.
+const MAX_AUDIT_LOG_ROWS: usize = 500;
+
 mod imp {
     use super::*;
 
@@ -37,6 +53,45 @@ mod imp {
         pub sharp_window_corners_label: TemplateChild<gtk::Label>,
         #[template_child]
         pub header_bar_behavior_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub term_font_button: TemplateChild<gtk::FontDialogButton>,
+        #[template_child]
+        pub term_font_reset_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub term_color_scheme_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub term_cursor_shape_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub display_rendering_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub display_scaling_filter_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub active_renderer_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub kiosk_sessions_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub session_toolbar_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub printers_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub event_sinks_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub shortcuts_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub term_shortcuts_group: TemplateChild<adw::PreferencesGroup>,
+        pub shortcut_rows: RefCell<Vec<adw::ActionRow>>,
+        #[template_child]
+        pub template_variables_group: TemplateChild<adw::PreferencesGroup>,
+        pub template_variable_rows: RefCell<Vec<gtk::Widget>>,
+        #[template_child]
+        pub snippets_group: TemplateChild<adw::PreferencesGroup>,
+        pub snippet_rows: RefCell<Vec<gtk::Widget>>,
+        #[template_child]
+        pub workspaces_group: TemplateChild<adw::PreferencesGroup>,
+        pub workspace_rows: RefCell<Vec<gtk::Widget>>,
+        #[template_child]
+        pub smart_folders_group: TemplateChild<adw::PreferencesGroup>,
+        pub smart_folder_rows: RefCell<Vec<gtk::Widget>>,
 
         #[property(get, construct_only)]
         pub application: RefCell<Option<FieldMonitorApplication>>,
@@ -46,6 +101,48 @@ mod imp {
         pub header_bar_behavior: RefCell<SettingHeaderBarBehavior>,
         #[property(get, set)]
         pub open_in_new_window: Cell<bool>,
+        #[property(get, set)]
+        pub allow_multiple_sessions: Cell<bool>,
+        #[property(get, set)]
+        pub confirm_close_sessions: Cell<bool>,
+        #[property(get, set)]
+        pub inhibit_suspend: Cell<bool>,
+        #[property(get, set)]
+        pub kiosk_mode: Cell<bool>,
+        #[property(get, set)]
+        pub status_poll_interval: Cell<u32>,
+        #[property(get, set)]
+        pub load_active_workspace_only: Cell<bool>,
+        #[property(get, set)]
+        pub term_scrollback_lines: Cell<u32>,
+        #[property(get, set)]
+        pub term_scrollback_unlimited: Cell<bool>,
+        #[property(get, set)]
+        pub term_font: RefCell<String>,
+        #[property(get, set)]
+        pub term_cursor_shape: RefCell<SettingTermCursorShape>,
+        #[property(get, set)]
+        pub term_color_scheme: RefCell<SettingTermColorScheme>,
+        #[property(get, set)]
+        pub term_paste_protection: Cell<bool>,
+        #[property(get, set)]
+        pub display_rendering: RefCell<SettingDisplayRendering>,
+        #[property(get, set)]
+        pub display_max_fps: Cell<u32>,
+        #[property(get, set)]
+        pub display_scaling_filter: RefCell<SettingScalingFilter>,
+        #[property(get, set)]
+        pub recording_max_size: Cell<u32>,
+        #[property(get, set)]
+        pub audit_log_enabled: Cell<bool>,
+        #[property(get, set)]
+        pub audit_log_retention_days: Cell<u32>,
+        #[property(get, set)]
+        pub event_webhook_url: RefCell<String>,
+        #[property(get, set)]
+        pub event_command: RefCell<String>,
+        #[property(get, set)]
+        pub encrypt_connections: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -99,13 +196,980 @@ impl FieldMonitorPreferencesDialog {
             .bidirectional()
             .sync_create()
             .build();
+        settings
+            .bind_property("allow-multiple-sessions", &slf, "allow-multiple-sessions")
+            .bidirectional()
+            .sync_create()
+            .build();
+        settings
+            .bind_property("confirm-close-sessions", &slf, "confirm-close-sessions")
+            .bidirectional()
+            .sync_create()
+            .build();
+        settings
+            .bind_property("inhibit-suspend", &slf, "inhibit-suspend")
+            .bidirectional()
+            .sync_create()
+            .build();
+        settings
+            .bind_property(
+                "load-active-workspace-only",
+                &slf,
+                "load-active-workspace-only",
+            )
+            .bidirectional()
+            .sync_create()
+            .build();
+
+        for property in [
+            "term-scrollback-lines",
+            "term-scrollback-unlimited",
+            "term-font",
+            "term-cursor-shape",
+            "term-color-scheme",
+            "term-paste-protection",
+            "display-rendering",
+            "display-max-fps",
+            "display-scaling-filter",
+            "recording-max-size",
+            "audit-log-enabled",
+            "audit-log-retention-days",
+            "event-webhook-url",
+            "event-command",
+            "kiosk-mode",
+            "status-poll-interval",
+            "encrypt-connections",
+        ] {
+            settings
+                .bind_property(property, &slf, property)
+                .bidirectional()
+                .sync_create()
+                .build();
+        }
 
         slf.on_self_sharp_window_corners_changed();
         slf.on_self_header_bar_behavior_changed();
+        slf.on_self_term_font_changed();
+        slf.on_self_term_cursor_shape_changed();
+        slf.on_self_term_color_scheme_changed();
+        slf.on_self_display_rendering_changed();
+        slf.on_self_display_scaling_filter_changed();
+        slf.update_template_variable_rows();
+        slf.update_workspace_rows();
+        slf.update_snippet_rows();
+        slf.update_smart_folder_rows();
+        slf.update_kiosk_sessions_row();
+        slf.add_session_toolbar_rows();
+        slf.add_printer_rows();
+        slf.add_event_sink_rows();
+        slf.update_shortcut_rows();
+        settings.connect_shortcuts_changed(glib::clone!(
+            #[weak]
+            slf,
+            move |_| slf.update_shortcut_rows()
+        ));
 
         slf
     }
 
+    fn app_settings(&self) -> FieldMonitorSettings {
+        self.application().unwrap().settings().unwrap()
+    }
+
+    fn update_template_variable_rows(&self) {
+        let imp = self.imp();
+        for row in imp.template_variable_rows.take() {
+            imp.template_variables_group.remove(&row);
+        }
+
+        let variables = self.app_settings().template_variables();
+        let mut rows = Vec::with_capacity(variables.len().max(1));
+        if variables.is_empty() {
+            rows.push(
+                adw::ActionRow::builder()
+                    .sensitive(false)
+                    .title(gettext("No variables defined yet."))
+                    .build()
+                    .upcast(),
+            );
+        }
+        for (name, value) in variables {
+            let row = adw::EntryRow::builder()
+                .title(format!("${{{name}}}"))
+                .text(value)
+                .show_apply_button(true)
+                .build();
+            let name = Rc::new(name);
+            row.connect_apply(glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                #[strong]
+                name,
+                move |row| {
+                    let mut variables = slf.app_settings().template_variables();
+                    variables.insert((*name).clone(), row.text().to_string());
+                    slf.app_settings().set_template_variables(&variables);
+                }
+            ));
+
+            let remove_button = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text(gettext("Remove Variable"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            remove_button.connect_clicked(glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_| {
+                    let mut variables = slf.app_settings().template_variables();
+                    variables.remove(&*name);
+                    slf.app_settings().set_template_variables(&variables);
+                    slf.update_template_variable_rows();
+                }
+            ));
+            row.add_suffix(&remove_button);
+            rows.push(row.upcast());
+        }
+
+        for row in &rows {
+            imp.template_variables_group.add(row);
+        }
+        imp.template_variable_rows.replace(rows);
+    }
+
+    fn update_workspace_rows(&self) {
+        let imp = self.imp();
+        for row in imp.workspace_rows.take() {
+            imp.workspaces_group.remove(&row);
+        }
+
+        let workspaces = self.app_settings().workspaces();
+        let mut rows = Vec::with_capacity(workspaces.len());
+        for name in workspaces {
+            let row = adw::ActionRow::builder().title(name.as_str()).build();
+            let remove_button = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text(gettext("Remove Workspace"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            remove_button.connect_clicked(glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_| {
+                    slf.app_settings().remove_workspace(&name);
+                    slf.update_workspace_rows();
+                }
+            ));
+            row.add_suffix(&remove_button);
+            rows.push(row.upcast());
+        }
+
+        for row in &rows {
+            imp.workspaces_group.add(row);
+        }
+        imp.workspace_rows.replace(rows);
+    }
+
+    fn add_session_toolbar_rows(&self) {
+        let shown = self.app_settings().session_toolbar_items();
+        for item in SessionToolbarItem::ALL {
+            let (title, subtitle) = match item {
+                SessionToolbarItem::CtrlAltDel => (
+                    gettext("Send Ctrl+Alt+Del"),
+                    gettext("Graphical sessions only"),
+                ),
+                SessionToolbarItem::ServerActions => (
+                    gettext("Server actions"),
+                    gettext("Such as rebooting or shutting down the server"),
+                ),
+                SessionToolbarItem::Screenshot => (gettext("Save screenshot"), String::new()),
+                SessionToolbarItem::ViewOnly => (gettext("View only"), String::new()),
+            };
+            let row = adw::SwitchRow::builder()
+                .title(title)
+                .subtitle(subtitle)
+                .active(shown.contains(&item))
+                .build();
+            row.connect_active_notify(glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |row| slf
+                    .app_settings()
+                    .set_session_toolbar_item(item, row.is_active())
+            ));
+            self.imp().session_toolbar_group.add(&row);
+        }
+    }
+
+    fn update_shortcut_rows(&self) {
+        let imp = self.imp();
+        for row in imp.shortcut_rows.take() {
+            if let Some(group) = row.ancestor(adw::PreferencesGroup::static_type()) {
+                group
+                    .downcast::<adw::PreferencesGroup>()
+                    .unwrap()
+                    .remove(&row);
+            }
+        }
+
+        let settings = self.app_settings();
+        let mut rows = Vec::with_capacity(ConfigurableShortcut::ALL.len());
+        for shortcut in ConfigurableShortcut::ALL {
+            let row = adw::ActionRow::builder()
+                .title(shortcut_title(shortcut.action))
+                .activatable(true)
+                .build();
+            let accel = settings.shortcut(&shortcut);
+            if accel.is_empty() {
+                row.add_suffix(
+                    &gtk::Label::builder()
+                        .label(gettext("Disabled"))
+                        .css_classes(["dim-label"])
+                        .build(),
+                );
+            } else {
+                row.add_suffix(
+                    &gtk::ShortcutLabel::builder()
+                        .accelerator(&accel)
+                        .valign(gtk::Align::Center)
+                        .build(),
+                );
+            }
+            if settings.is_shortcut_customized(&shortcut) {
+                let reset_button = gtk::Button::builder()
+                    .icon_name("edit-undo-symbolic")
+                    .tooltip_text(gettext("Reset to Default"))
+                    .valign(gtk::Align::Center)
+                    .css_classes(["flat"])
+                    .build();
+                reset_button.connect_clicked(glib::clone!(
+                    #[weak(rename_to = slf)]
+                    self,
+                    move |_| slf.app_settings().set_shortcut(&shortcut, None)
+                ));
+                row.add_suffix(&reset_button);
+            }
+            row.connect_activated(glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_| slf.present_shortcut_dialog(shortcut)
+            ));
+
+            if shortcut.action.starts_with("view.term-") {
+                imp.term_shortcuts_group.add(&row);
+            } else {
+                imp.shortcuts_group.add(&row);
+            }
+            rows.push(row);
+        }
+        imp.shortcut_rows.replace(rows);
+    }
+
+    /// Asks the user to press the new shortcut for `shortcut`. Shortcuts already used by
+    /// another action are refused.
+    fn present_shortcut_dialog(&self, shortcut: ConfigurableShortcut) {
+        let dialog = adw::AlertDialog::builder()
+            .heading(gettext("Set Shortcut"))
+            .body(gettext_f(
+                "Press the new shortcut for “{action}”, or Backspace to disable it.",
+                &[("action", &shortcut_title(shortcut.action))],
+            ))
+            .close_response("cancel")
+            .build();
+        dialog.add_response("cancel", &gettext("Cancel"));
+
+        let key_controller = gtk::EventControllerKey::new();
+        key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+        key_controller.connect_key_pressed(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            #[weak]
+            dialog,
+            #[upgrade_or]
+            glib::Propagation::Proceed,
+            move |_, keyval, _, state| {
+                let keyval = keyval.to_lower();
+                let state = state & gtk::accelerator_get_default_mod_mask();
+                let accel = match keyval {
+                    gdk::Key::Escape if state.is_empty() => return glib::Propagation::Proceed,
+                    gdk::Key::BackSpace if state.is_empty() => String::new(),
+                    // Without modifiers, only function keys are accepted, other keys are
+                    // needed for typing.
+                    _ if state.is_empty() && !is_function_key(keyval) => {
+                        return glib::Propagation::Stop
+                    }
+                    _ if !gtk::accelerator_valid(keyval, state) => return glib::Propagation::Stop,
+                    _ => gtk::accelerator_name(keyval, state).to_string(),
+                };
+
+                let settings = slf.app_settings();
+                let conflict = ConfigurableShortcut::ALL.into_iter().find(|other| {
+                    other.action != shortcut.action
+                        && !accel.is_empty()
+                        && gtk::accelerator_parse(&settings.shortcut(other))
+                            == Some((keyval, state))
+                });
+                if let Some(other) = conflict {
+                    dialog.set_body(&gettext_f(
+                        "{shortcut} is already used for “{action}”. Press another shortcut.",
+                        &[
+                            ("shortcut", &gtk::accelerator_get_label(keyval, state)),
+                            ("action", &shortcut_title(other.action)),
+                        ],
+                    ));
+                    return glib::Propagation::Stop;
+                }
+
+                settings.set_shortcut(&shortcut, Some(&accel));
+                dialog.close();
+                glib::Propagation::Stop
+            }
+        ));
+        dialog.add_controller(key_controller);
+        dialog.present(Some(self));
+    }
+
+    fn add_printer_rows(&self) {
+        let shared = self.app_settings().shared_printers();
+        let empty_row = adw::ActionRow::builder()
+            .sensitive(false)
+            .title(gettext("No printers found."))
+            .build();
+        self.imp().printers_group.add(&empty_row);
+
+        gtk::enumerate_printers(
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                #[upgrade_or]
+                true,
+                move |printer| {
+                    if printer.is_virtual() {
+                        return false;
+                    }
+                    let name = printer.name().to_string();
+                    let row = adw::SwitchRow::builder()
+                        .title(name.as_str())
+                        .subtitle(printer.location().unwrap_or_default())
+                        .active(shared.contains(&name))
+                        .build();
+                    row.connect_active_notify(glib::clone!(
+                        #[weak]
+                        slf,
+                        move |row| slf
+                            .app_settings()
+                            .set_printer_shared(&name, row.is_active())
+                    ));
+                    empty_row.set_visible(false);
+                    slf.imp().printers_group.add(&row);
+                    false
+                }
+            ),
+            false,
+        );
+    }
+
+    fn add_event_sink_rows(&self) {
+        let settings = self.app_settings();
+        for kind in SinkEventKind::ALL {
+            let row = adw::SwitchRow::builder()
+                .title(kind.label())
+                .active(settings.event_sink_enabled(kind))
+                .build();
+            row.connect_active_notify(glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |row| slf
+                    .app_settings()
+                    .set_event_sink_enabled(kind, row.is_active())
+            ));
+            self.imp().event_sinks_group.add(&row);
+        }
+    }
+
+    fn update_kiosk_sessions_row(&self) {
+        let count = self.app_settings().kiosk_sessions().len();
+        let subtitle = if count == 0 {
+            gettext("No sessions configured")
+        } else {
+            gettext_f(
+                "Sessions configured: {count}",
+                &[("count", &count.to_string())],
+            )
+        };
+        self.imp().kiosk_sessions_row.set_subtitle(&subtitle);
+    }
+
+    fn update_snippet_rows(&self) {
+        let imp = self.imp();
+        for row in imp.snippet_rows.take() {
+            imp.snippets_group.remove(&row);
+        }
+
+        let snippets = self.app_settings().snippets();
+        let mut rows = Vec::with_capacity(snippets.len().max(1));
+        if snippets.is_empty() {
+            rows.push(
+                adw::ActionRow::builder()
+                    .sensitive(false)
+                    .title(gettext("No snippets defined yet."))
+                    .build()
+                    .upcast(),
+            );
+        }
+        for (index, snippet) in snippets.into_iter().enumerate() {
+            let row = adw::ActionRow::builder()
+                .title(snippet.name.as_str())
+                .subtitle(snippet.content.as_str())
+                .use_markup(false)
+                .activatable(true)
+                .build();
+            row.connect_activated(glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_| slf.present_snippet_dialog(Some(index))
+            ));
+            if !snippet.shortcut.is_empty() {
+                row.add_suffix(
+                    &gtk::ShortcutLabel::builder()
+                        .accelerator(snippet.shortcut.as_str())
+                        .valign(gtk::Align::Center)
+                        .build(),
+                );
+            }
+
+            let remove_button = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text(gettext("Remove Snippet"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            remove_button.connect_clicked(glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_| {
+                    let mut snippets = slf.app_settings().snippets();
+                    if index < snippets.len() {
+                        snippets.remove(index);
+                    }
+                    slf.app_settings().set_snippets(&snippets);
+                    slf.update_snippet_rows();
+                }
+            ));
+            row.add_suffix(&remove_button);
+            rows.push(row.upcast());
+        }
+
+        for row in &rows {
+            imp.snippets_group.add(row);
+        }
+        imp.snippet_rows.replace(rows);
+    }
+
+    /// Presents a dialog for adding a snippet, or for editing the snippet at `index`.
+    fn present_snippet_dialog(&self, index: Option<usize>) {
+        let snippet = index
+            .and_then(|index| self.app_settings().snippets().into_iter().nth(index))
+            .unwrap_or_default();
+
+        let name_row = adw::EntryRow::builder()
+            .title(gettext("Name"))
+            .text(snippet.name.as_str())
+            .build();
+        let kind_row = adw::ComboRow::builder()
+            .title(gettext("Send To"))
+            .model(&gtk::StringList::new(&[
+                &gettext("Consoles"),
+                &gettext("Graphical Sessions"),
+            ]))
+            .selected(match snippet.kind {
+                SnippetKind::Text => 0,
+                SnippetKind::Keys => 1,
+            })
+            .build();
+        let content_row = adw::EntryRow::builder()
+            .title(gettext("Content"))
+            .text(snippet.content.as_str())
+            .build();
+        let shortcut_row = adw::EntryRow::builder()
+            .title(gettext("Shortcut"))
+            .text(snippet.shortcut.as_str())
+            .build();
+
+        let list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .build();
+        list.append(&name_row);
+        list.append(&kind_row);
+        list.append(&content_row);
+        list.append(&shortcut_row);
+
+        let dialog = adw::AlertDialog::builder()
+            .heading(match index {
+                None => gettext("Add Snippet"),
+                Some(_) => gettext("Edit Snippet"),
+            })
+            .body(gettext(
+                "Text for consoles is typed as is, use \\n for line breaks. For graphical sessions, enter key combinations separated by spaces, such as <Control><Alt>Delete. Shortcuts use the same format.",
+            ))
+            .extra_child(&list)
+            .build();
+        dialog.add_response("cancel", &gettext("Cancel"));
+        dialog.add_response("save", &gettext("Save"));
+        dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("save"));
+        dialog.set_close_response("cancel");
+
+        let read_snippet = Rc::new(glib::clone!(
+            #[weak]
+            name_row,
+            #[weak]
+            kind_row,
+            #[weak]
+            content_row,
+            #[weak]
+            shortcut_row,
+            #[upgrade_or_default]
+            move || Snippet {
+                name: name_row.text().trim().to_string(),
+                kind: match kind_row.selected() {
+                    1 => SnippetKind::Keys,
+                    _ => SnippetKind::Text,
+                },
+                content: content_row.text().to_string(),
+                shortcut: shortcut_row.text().trim().to_string(),
+            }
+        ));
+        let validate = Rc::new(glib::clone!(
+            #[weak]
+            dialog,
+            #[weak]
+            content_row,
+            #[weak]
+            shortcut_row,
+            #[strong]
+            read_snippet,
+            move || {
+                let snippet = read_snippet();
+                let content_valid = match snippet.kind {
+                    SnippetKind::Text => !snippet.content.is_empty(),
+                    SnippetKind::Keys => {
+                        snippet.keys().next().is_some()
+                            && snippet
+                                .keys()
+                                .all(|keys| gtk::accelerator_parse(keys).is_some())
+                    }
+                };
+                let shortcut_valid = snippet.shortcut.is_empty()
+                    || gtk::ShortcutTrigger::parse_string(&snippet.shortcut).is_some();
+                set_error_class(&content_row, content_valid || snippet.content.is_empty());
+                set_error_class(&shortcut_row, shortcut_valid);
+                dialog.set_response_enabled(
+                    "save",
+                    !snippet.name.is_empty() && content_valid && shortcut_valid,
+                );
+            }
+        ));
+        validate();
+        for row in [&name_row, &content_row, &shortcut_row] {
+            row.connect_changed(glib::clone!(
+                #[strong]
+                validate,
+                move |_| validate()
+            ));
+        }
+        kind_row.connect_selected_notify(glib::clone!(
+            #[strong]
+            validate,
+            move |_| validate()
+        ));
+
+        dialog.connect_response(
+            Some("save"),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_, _| {
+                    let snippet = read_snippet();
+                    let mut snippets = slf.app_settings().snippets();
+                    match index {
+                        Some(index) if index < snippets.len() => snippets[index] = snippet,
+                        _ => snippets.push(snippet),
+                    }
+                    slf.app_settings().set_snippets(&snippets);
+                    slf.update_snippet_rows();
+                }
+            ),
+        );
+        dialog.present(Some(self));
+    }
+
+    fn update_smart_folder_rows(&self) {
+        let imp = self.imp();
+        for row in imp.smart_folder_rows.take() {
+            imp.smart_folders_group.remove(&row);
+        }
+
+        let folders = self.app_settings().smart_folders();
+        let mut rows = Vec::with_capacity(folders.len().max(1));
+        if folders.is_empty() {
+            rows.push(
+                adw::ActionRow::builder()
+                    .sensitive(false)
+                    .title(gettext("No smart folders defined yet."))
+                    .build()
+                    .upcast(),
+            );
+        }
+        for (index, folder) in folders.into_iter().enumerate() {
+            let row = adw::ActionRow::builder()
+                .title(folder.name.as_str())
+                .subtitle(self.describe_smart_folder(&folder))
+                .use_markup(false)
+                .activatable(true)
+                .build();
+            row.connect_activated(glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_| slf.present_smart_folder_dialog(Some(index))
+            ));
+
+            let remove_button = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text(gettext("Remove Smart Folder"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            remove_button.connect_clicked(glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_| {
+                    let mut folders = slf.app_settings().smart_folders();
+                    if index < folders.len() {
+                        folders.remove(index);
+                    }
+                    slf.app_settings().set_smart_folders(&folders);
+                    slf.update_smart_folder_rows();
+                }
+            ));
+            row.add_suffix(&remove_button);
+            rows.push(row.upcast());
+        }
+
+        for row in &rows {
+            imp.smart_folders_group.add(row);
+        }
+        imp.smart_folder_rows.replace(rows);
+    }
+
+    /// Lists the criteria of the query of `folder` for its row.
+    fn describe_smart_folder(&self, folder: &SmartFolder) -> String {
+        let mut criteria = Vec::new();
+        if !folder.provider.is_empty() {
+            let provider_title = self
+                .application()
+                .into_iter()
+                .flat_map(|app| app.connection_providers())
+                .find(|provider| provider.tag() == folder.provider)
+                .map(|provider| provider.title().into_owned())
+                .unwrap_or_else(|| folder.provider.clone());
+            criteria.push(provider_title);
+        }
+        if !folder.title_pattern.trim().is_empty() {
+            criteria.push(folder.title_pattern.trim().to_string());
+        }
+        match folder.online {
+            SmartFolderOnlineState::Any => {}
+            SmartFolderOnlineState::Online => criteria.push(gettext("Online")),
+            SmartFolderOnlineState::Offline => criteria.push(gettext("Offline")),
+        }
+        if !folder.tag.is_empty() {
+            criteria.push(gettext_f("Tag: {tag}", &[("tag", &folder.tag)]));
+        }
+        if criteria.is_empty() {
+            gettext("All servers")
+        } else {
+            criteria.join(" · ")
+        }
+    }
+
+    /// Presents a dialog for adding a smart folder, or for editing the folder at `index`.
+    fn present_smart_folder_dialog(&self, index: Option<usize>) {
+        let folder = index
+            .and_then(|index| self.app_settings().smart_folders().into_iter().nth(index))
+            .unwrap_or_default();
+
+        let mut providers = self
+            .application()
+            .into_iter()
+            .flat_map(|app| app.connection_providers())
+            .map(|provider| (provider.tag().to_string(), provider.title().into_owned()))
+            .collect::<Vec<_>>();
+        providers.sort_by(|(_, a), (_, b)| a.cmp(b));
+        let provider_titles = std::iter::once(gettext("Any"))
+            .chain(providers.iter().map(|(_, title)| title.clone()))
+            .collect::<Vec<_>>();
+        let provider_titles = provider_titles
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+
+        let name_row = adw::EntryRow::builder()
+            .title(gettext("Name"))
+            .text(folder.name.as_str())
+            .build();
+        let provider_row = adw::ComboRow::builder()
+            .title(gettext("Connection Type"))
+            .model(&gtk::StringList::new(&provider_titles))
+            .selected(
+                providers
+                    .iter()
+                    .position(|(tag, _)| *tag == folder.provider)
+                    .map_or(0, |position| position as u32 + 1),
+            )
+            .build();
+        let pattern_row = adw::EntryRow::builder()
+            .title(gettext("Server Name"))
+            .text(folder.title_pattern.as_str())
+            .build();
+        let online_row = adw::ComboRow::builder()
+            .title(gettext("Status"))
+            .model(&gtk::StringList::new(&[
+                &gettext("Any"),
+                &gettext("Online"),
+                &gettext("Offline"),
+            ]))
+            .selected(match folder.online {
+                SmartFolderOnlineState::Any => 0,
+                SmartFolderOnlineState::Online => 1,
+                SmartFolderOnlineState::Offline => 2,
+            })
+            .build();
+        let tag_row = adw::EntryRow::builder()
+            .title(gettext("Tag"))
+            .text(folder.tag.as_str())
+            .build();
+
+        let list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .build();
+        list.append(&name_row);
+        list.append(&provider_row);
+        list.append(&pattern_row);
+        list.append(&online_row);
+        list.append(&tag_row);
+
+        let dialog = adw::AlertDialog::builder()
+            .heading(match index {
+                None => gettext("Add Smart Folder"),
+                Some(_) => gettext("Edit Smart Folder"),
+            })
+            .body(gettext(
+                "Servers of all connections that match every given criterion are shown. In server names, * matches any text and ? a single character.",
+            ))
+            .extra_child(&list)
+            .build();
+        dialog.add_response("cancel", &gettext("Cancel"));
+        dialog.add_response("save", &gettext("Save"));
+        dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("save"));
+        dialog.set_close_response("cancel");
+
+        let read_folder = Rc::new(glib::clone!(
+            #[weak]
+            name_row,
+            #[weak]
+            provider_row,
+            #[weak]
+            pattern_row,
+            #[weak]
+            online_row,
+            #[weak]
+            tag_row,
+            #[upgrade_or_default]
+            move || SmartFolder {
+                name: name_row.text().trim().to_string(),
+                provider: (provider_row.selected() as usize)
+                    .checked_sub(1)
+                    .and_then(|position| providers.get(position))
+                    .map(|(tag, _)| tag.clone())
+                    .unwrap_or_default(),
+                title_pattern: pattern_row.text().trim().to_string(),
+                online: match online_row.selected() {
+                    1 => SmartFolderOnlineState::Online,
+                    2 => SmartFolderOnlineState::Offline,
+                    _ => SmartFolderOnlineState::Any,
+                },
+                tag: tag_row.text().trim().to_string(),
+            }
+        ));
+        let validate = Rc::new(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            #[weak]
+            dialog,
+            #[weak]
+            name_row,
+            #[strong]
+            read_folder,
+            move || {
+                let name = read_folder().name;
+                // The name identifies the folder in the sidebar, so it must be unique.
+                let name_taken = slf
+                    .app_settings()
+                    .smart_folders()
+                    .iter()
+                    .enumerate()
+                    .any(|(i, folder)| Some(i) != index && folder.name == name);
+                set_error_class(&name_row, !name_taken);
+                dialog.set_response_enabled("save", !name.is_empty() && !name_taken);
+            }
+        ));
+        validate();
+        name_row.connect_changed(glib::clone!(
+            #[strong]
+            validate,
+            move |_| validate()
+        ));
+
+        dialog.connect_response(
+            Some("save"),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_, _| {
+                    let folder = read_folder();
+                    let mut folders = slf.app_settings().smart_folders();
+                    match index {
+                        Some(index) if index < folders.len() => folders[index] = folder,
+                        _ => folders.push(folder),
+                    }
+                    slf.app_settings().set_smart_folders(&folders);
+                    slf.update_smart_folder_rows();
+                }
+            ),
+        );
+        dialog.present(Some(self));
+    }
+
+    fn make_audit_log_subpage(&self) -> adw::NavigationPage {
+        let entries = audit_log::read_entries().unwrap_or_else(|err| {
+            warn!("failed to read audit log: {err}");
+            Vec::new()
+        });
+
+        let pref_group = adw::PreferencesGroup::new();
+        if entries.is_empty() {
+            pref_group.add(
+                &adw::ActionRow::builder()
+                    .sensitive(false)
+                    .title(gettext("No activity recorded yet."))
+                    .build(),
+            );
+        }
+        for entry in entries.iter().rev().take(MAX_AUDIT_LOG_ROWS) {
+            pref_group.add(&make_audit_log_row(entry));
+        }
+
+        let pref_page = adw::PreferencesPage::new();
+        pref_page.add(&pref_group);
+
+        let clear_button = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text(gettext("Clear Log"))
+            .sensitive(!entries.is_empty())
+            .build();
+        clear_button.connect_clicked(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            move |_| slf.present_clear_audit_log_dialog()
+        ));
+        let header_bar = adw::HeaderBar::new();
+        header_bar.pack_end(&clear_button);
+
+        let toolbar = adw::ToolbarView::new();
+        toolbar.add_top_bar(&header_bar);
+        toolbar.set_content(Some(&pref_page));
+
+        adw::NavigationPage::new(&toolbar, &gettext("Activity Log"))
+    }
+
+    fn present_clear_audit_log_dialog(&self) {
+        let dialog = adw::AlertDialog::builder()
+            .heading(gettext("Clear Activity Log?"))
+            .body(gettext("All recorded entries will be permanently removed."))
+            .build();
+        dialog.add_response("cancel", &gettext("Cancel"));
+        dialog.add_response("clear", &gettext("Clear"));
+        dialog.set_response_appearance("clear", adw::ResponseAppearance::Destructive);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+
+        dialog.connect_response(
+            Some("clear"),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                move |_, _| {
+                    if let Err(err) = audit_log::clear() {
+                        error!("failed to clear audit log: {err}");
+                        slf.add_toast(adw::Toast::new(&gettext("Failed to clear the log.")));
+                        return;
+                    }
+                    slf.pop_subpage();
+                }
+            ),
+        );
+        dialog.present(Some(self));
+    }
+
+    async fn prune_secrets(&self) {
+        let app = self.application().unwrap();
+        let connection_ids = match app.orphaned_secret_connection_ids().await {
+            Ok(connection_ids) => connection_ids,
+            Err(err) => {
+                error!("failed to list unused secrets: {err}");
+                self.add_toast(adw::Toast::new(&gettext(
+                    "Failed to read passwords from the keyring.",
+                )));
+                return;
+            }
+        };
+        if connection_ids.is_empty() {
+            self.add_toast(adw::Toast::new(&gettext("No unused passwords found.")));
+            return;
+        }
+
+        let dialog = adw::AlertDialog::builder()
+            .heading(gettext("Remove Unused Passwords?"))
+            .body(gettext_f(
+                "Stored passwords of {count} removed connection(s) will be permanently deleted.",
+                &[("count", &connection_ids.len().to_string())],
+            ))
+            .build();
+        dialog.add_response("cancel", &gettext("Cancel"));
+        dialog.add_response("remove", &gettext("Remove"));
+        dialog.set_response_appearance("remove", adw::ResponseAppearance::Destructive);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+
+        if dialog.choose_future(self).await != "remove" {
+            return;
+        }
+        let toast = match app.remove_connection_secrets(&connection_ids).await {
+            Ok(()) => gettext("Unused passwords removed."),
+            Err(err) => {
+                error!("failed to remove unused secrets: {err}");
+                gettext("Failed to remove some passwords.")
+            }
+        };
+        self.add_toast(adw::Toast::new(&toast));
+    }
+
     fn make_radio_subpage(
         &self,
         initial_value: usize,
@@ -215,6 +1279,405 @@ impl FieldMonitorPreferencesDialog {
         )))
     }
 
+    #[template_callback]
+    pub fn on_self_term_font_changed(&self) {
+        let imp = self.imp();
+        let font = self.term_font();
+        imp.term_font_reset_button.set_visible(!font.is_empty());
+        let font_desc = pango::FontDescription::from_string(if font.is_empty() {
+            DEFAULT_TERM_FONT
+        } else {
+            &font
+        });
+        if imp.term_font_button.font_desc().as_ref() != Some(&font_desc) {
+            imp.term_font_button.set_font_desc(&font_desc);
+        }
+    }
+
+    #[template_callback]
+    pub fn on_term_font_button_changed(&self) {
+        let Some(font_desc) = self.imp().term_font_button.font_desc() else {
+            return;
+        };
+        let font = font_desc.to_string();
+        if self.term_font().is_empty() && font == DEFAULT_TERM_FONT {
+            return;
+        }
+        if font != self.term_font() {
+            self.set_term_font(font);
+        }
+    }
+
+    #[template_callback]
+    pub fn on_term_font_reset_clicked(&self) {
+        self.set_term_font(String::new());
+    }
+
+    #[template_callback]
+    pub fn on_self_term_cursor_shape_changed(&self) {
+        self.imp()
+            .term_cursor_shape_label
+            .set_text(&match self.term_cursor_shape() {
+                SettingTermCursorShape::Block => gettext("Block"),
+                SettingTermCursorShape::Ibeam => gettext("I-Beam"),
+                SettingTermCursorShape::Underline => gettext("Underline"),
+            })
+    }
+
+    #[template_callback]
+    pub fn on_self_term_color_scheme_changed(&self) {
+        self.imp()
+            .term_color_scheme_label
+            .set_text(&match self.term_color_scheme() {
+                SettingTermColorScheme::Auto => gettext("Follow System"),
+                SettingTermColorScheme::Light => gettext("Light"),
+                SettingTermColorScheme::Dark => gettext("Dark"),
+                SettingTermColorScheme::SolarizedLight => gettext("Solarized Light"),
+                SettingTermColorScheme::SolarizedDark => gettext("Solarized Dark"),
+            })
+    }
+
+    #[template_callback]
+    pub fn on_self_display_rendering_changed(&self) {
+        self.imp()
+            .display_rendering_label
+            .set_text(&match self.display_rendering() {
+                SettingDisplayRendering::Auto => gettext("Automatic"),
+                SettingDisplayRendering::Gl => gettext("Hardware"),
+                SettingDisplayRendering::Software => gettext("Software"),
+            })
+    }
+
+    #[template_callback]
+    pub fn on_self_display_scaling_filter_changed(&self) {
+        self.imp()
+            .display_scaling_filter_label
+            .set_text(&match self.display_scaling_filter() {
+                SettingScalingFilter::Default => gettext("Default"),
+                SettingScalingFilter::Nearest => gettext("Pixelated"),
+                SettingScalingFilter::Trilinear => gettext("Smooth"),
+            })
+    }
+
+    /// Shows which renderer GTK actually uses, which may differ from the setting until the next
+    /// start or if GL is not available.
+    #[template_callback]
+    pub fn on_self_map(&self) {
+        let renderer = self
+            .native()
+            .and_then(|native| native.renderer())
+            .map(|renderer| renderer.type_().name());
+        let gl_available = gdk::Display::default()
+            .map(|display| display.create_gl_context().is_ok())
+            .unwrap_or_default();
+        let description = match renderer {
+            Some("GskCairoRenderer") => gettext("Software (Cairo)"),
+            Some("GskVulkanRenderer") => gettext("Hardware (Vulkan)"),
+            Some(name) if name.contains("GL") => gettext("Hardware (OpenGL)"),
+            Some(name) => name.to_string(),
+            None => gettext("Unknown"),
+        };
+        let gl_note = if gl_available {
+            gettext("OpenGL is available for graphical sessions")
+        } else {
+            gettext("OpenGL is not available, graphical sessions are drawn in software")
+        };
+        self.imp()
+            .active_renderer_row
+            .set_subtitle(&format!("{description}\n{gl_note}"));
+    }
+
+    #[template_callback]
+    pub fn on_display_rendering_row_activated(&self) {
+        self.push_subpage(&self.make_radio_subpage(
+            match self.display_rendering() {
+                SettingDisplayRendering::Auto => 0,
+                SettingDisplayRendering::Gl => 1,
+                SettingDisplayRendering::Software => 2,
+            },
+            gettext("Rendering"),
+            Some(gettext(
+                "How remote desktops are drawn. Takes effect after restarting Field Monitor.",
+            )),
+            &[
+                (
+                    gettext("Automatic"),
+                    Some(gettext("Let GTK choose the best available renderer.")),
+                ),
+                (
+                    gettext("Hardware"),
+                    Some(gettext("Always use OpenGL.")),
+                ),
+                (
+                    gettext("Software"),
+                    Some(gettext(
+                        "Disable OpenGL, Vulkan and DMA-BUF. Slower, but avoids artifacts with some graphics drivers.",
+                    )),
+                ),
+            ],
+            Rc::new(glib::clone!(
+                #[weak(rename_to=slf)]
+                self,
+                move |option_idx| {
+                    slf.set_display_rendering(match option_idx {
+                        0 => SettingDisplayRendering::Auto,
+                        1 => SettingDisplayRendering::Gl,
+                        2 => SettingDisplayRendering::Software,
+                        _ => unreachable!(),
+                    });
+                }
+            )),
+        ))
+    }
+
+    #[template_callback]
+    pub fn on_display_scaling_filter_row_activated(&self) {
+        self.push_subpage(&self.make_radio_subpage(
+            match self.display_scaling_filter() {
+                SettingScalingFilter::Default => 0,
+                SettingScalingFilter::Nearest => 1,
+                SettingScalingFilter::Trilinear => 2,
+            },
+            gettext("Scaling"),
+            Some(gettext(
+                "How graphical sessions are drawn when they are shown larger or smaller than their resolution.",
+            )),
+            &[
+                (
+                    gettext("Default"),
+                    Some(gettext("Let GTK choose the filter.")),
+                ),
+                (
+                    gettext("Pixelated"),
+                    Some(gettext("Uses the nearest pixel. Needs the least CPU, but looks blocky.")),
+                ),
+                (
+                    gettext("Smooth"),
+                    Some(gettext(
+                        "Uses mipmaps. Looks best when the session is scaled down, but needs more CPU.",
+                    )),
+                ),
+            ],
+            Rc::new(glib::clone!(
+                #[weak(rename_to=slf)]
+                self,
+                move |option_idx| {
+                    slf.set_display_scaling_filter(match option_idx {
+                        0 => SettingScalingFilter::Default,
+                        1 => SettingScalingFilter::Nearest,
+                        2 => SettingScalingFilter::Trilinear,
+                        _ => unreachable!(),
+                    });
+                }
+            )),
+        ))
+    }
+
+    #[template_callback]
+    pub fn on_term_cursor_shape_row_activated(&self) {
+        self.push_subpage(&self.make_radio_subpage(
+            match self.term_cursor_shape() {
+                SettingTermCursorShape::Block => 0,
+                SettingTermCursorShape::Ibeam => 1,
+                SettingTermCursorShape::Underline => 2,
+            },
+            gettext("Cursor shape"),
+            None,
+            &[
+                (gettext("Block"), None),
+                (gettext("I-Beam"), None),
+                (gettext("Underline"), None),
+            ],
+            Rc::new(glib::clone!(
+                #[weak(rename_to=slf)]
+                self,
+                move |option_idx| {
+                    slf.set_term_cursor_shape(match option_idx {
+                        0 => SettingTermCursorShape::Block,
+                        1 => SettingTermCursorShape::Ibeam,
+                        2 => SettingTermCursorShape::Underline,
+                        _ => unreachable!(),
+                    });
+                }
+            )),
+        ))
+    }
+
+    #[template_callback]
+    pub fn on_term_color_scheme_row_activated(&self) {
+        self.push_subpage(&self.make_radio_subpage(
+            match self.term_color_scheme() {
+                SettingTermColorScheme::Auto => 0,
+                SettingTermColorScheme::Light => 1,
+                SettingTermColorScheme::Dark => 2,
+                SettingTermColorScheme::SolarizedLight => 3,
+                SettingTermColorScheme::SolarizedDark => 4,
+            },
+            gettext("Color scheme"),
+            Some(gettext("Colors used by terminal consoles.")),
+            &[
+                (
+                    gettext("Follow System"),
+                    Some(gettext(
+                        "Use light or dark colors depending on the system style.",
+                    )),
+                ),
+                (gettext("Light"), None),
+                (gettext("Dark"), None),
+                (gettext("Solarized Light"), None),
+                (gettext("Solarized Dark"), None),
+            ],
+            Rc::new(glib::clone!(
+                #[weak(rename_to=slf)]
+                self,
+                move |option_idx| {
+                    slf.set_term_color_scheme(match option_idx {
+                        0 => SettingTermColorScheme::Auto,
+                        1 => SettingTermColorScheme::Light,
+                        2 => SettingTermColorScheme::Dark,
+                        3 => SettingTermColorScheme::SolarizedLight,
+                        4 => SettingTermColorScheme::SolarizedDark,
+                        _ => unreachable!(),
+                    });
+                }
+            )),
+        ))
+    }
+
+    #[template_callback]
+    pub fn on_add_template_variable_clicked(&self) {
+        let name_entry = gtk::Entry::builder()
+            .placeholder_text(gettext("Name"))
+            .activates_default(true)
+            .build();
+        let dialog = adw::AlertDialog::builder()
+            .heading(gettext("Add Variable"))
+            .body(gettext(
+                "Variable names may only contain letters, digits and underscores.",
+            ))
+            .extra_child(&name_entry)
+            .build();
+        dialog.add_response("cancel", &gettext("Cancel"));
+        dialog.add_response("add", &gettext("Add"));
+        dialog.set_response_appearance("add", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("add"));
+        dialog.set_close_response("cancel");
+        dialog.set_response_enabled("add", false);
+
+        name_entry.connect_changed(glib::clone!(
+            #[weak]
+            dialog,
+            move |entry| {
+                let valid = template::is_valid_variable_name(&entry.text());
+                dialog.set_response_enabled("add", valid);
+                if valid || entry.text().is_empty() {
+                    entry.remove_css_class("error");
+                } else {
+                    entry.add_css_class("error");
+                }
+            }
+        ));
+
+        dialog.connect_response(
+            Some("add"),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                #[weak]
+                name_entry,
+                move |_, _| {
+                    let mut variables = slf.app_settings().template_variables();
+                    variables.entry(name_entry.text().to_string()).or_default();
+                    slf.app_settings().set_template_variables(&variables);
+                    slf.update_template_variable_rows();
+                }
+            ),
+        );
+        dialog.present(Some(self));
+    }
+
+    #[template_callback]
+    pub fn on_view_audit_log_activated(&self) {
+        self.push_subpage(&self.make_audit_log_subpage());
+    }
+
+    #[template_callback]
+    pub fn on_kiosk_use_open_sessions_clicked(&self) {
+        let sessions: Vec<_> = self
+            .application()
+            .unwrap()
+            .windows()
+            .into_iter()
+            .filter_map(|window| window.downcast::<FieldMonitorWindow>().ok())
+            .flat_map(|window| window.tab_view().sessions())
+            .collect();
+        self.app_settings().set_kiosk_sessions(&sessions);
+        self.update_kiosk_sessions_row();
+    }
+
+    #[template_callback]
+    pub fn on_prune_secrets_activated(&self) {
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = slf)]
+            self,
+            async move { slf.prune_secrets().await }
+        ));
+    }
+
+    #[template_callback]
+    pub fn on_reset_shortcuts_clicked(&self) {
+        self.app_settings().reset_shortcuts();
+    }
+
+    #[template_callback]
+    pub fn on_add_snippet_clicked(&self) {
+        self.present_snippet_dialog(None);
+    }
+
+    #[template_callback]
+    pub fn on_add_smart_folder_clicked(&self) {
+        self.present_smart_folder_dialog(None);
+    }
+
+    #[template_callback]
+    pub fn on_add_workspace_clicked(&self) {
+        let name_entry = gtk::Entry::builder()
+            .placeholder_text(gettext("Name"))
+            .activates_default(true)
+            .build();
+        let dialog = adw::AlertDialog::builder()
+            .heading(gettext("Add Workspace"))
+            .extra_child(&name_entry)
+            .build();
+        dialog.add_response("cancel", &gettext("Cancel"));
+        dialog.add_response("add", &gettext("Add"));
+        dialog.set_response_appearance("add", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("add"));
+        dialog.set_close_response("cancel");
+        dialog.set_response_enabled("add", false);
+
+        name_entry.connect_changed(glib::clone!(
+            #[weak]
+            dialog,
+            move |entry| dialog.set_response_enabled("add", !entry.text().trim().is_empty())
+        ));
+
+        dialog.connect_response(
+            Some("add"),
+            glib::clone!(
+                #[weak(rename_to = slf)]
+                self,
+                #[weak]
+                name_entry,
+                move |_, _| {
+                    slf.app_settings().add_workspace(name_entry.text().trim());
+                    slf.update_workspace_rows();
+                }
+            ),
+        );
+        dialog.present(Some(self));
+    }
+
     #[template_callback]
     pub fn on_header_bar_behavior_row_activated(&self) {
         self.push_subpage(&self.make_radio_subpage(
@@ -245,3 +1708,61 @@ impl FieldMonitorPreferencesDialog {
         )))
     }
 }
+
+pub(crate) fn make_audit_log_row(entry: &AuditEntry) -> adw::ActionRow {
+    let (time, exact_time) = entry.date_time().map_or_else(
+        || (entry.time.clone(), entry.time.clone()),
+        |time| (format_relative_time(&time), format_date_time(&time)),
+    );
+    let mut subtitle = format!("{time} · {}", entry.user);
+    if let Some(detail) = &entry.detail {
+        subtitle.push_str(" · ");
+        subtitle.push_str(detail);
+    }
+
+    let row = adw::ActionRow::builder()
+        .title(format!("{}: {}", entry.event.label(), entry.title))
+        .subtitle(subtitle)
+        .tooltip_text(format!("{exact_time}\n{}", entry.target))
+        .use_markup(false)
+        .build();
+    if !entry.success {
+        row.add_prefix(&gtk::Image::from_icon_name("dialog-error-symbolic"));
+    }
+    row
+}
+
+fn shortcut_title(action: &str) -> String {
+    match action {
+        "app.new-window" => gettext("New window"),
+        "window.close" => gettext("Close window"),
+        "app.preferences" => gettext("Preferences"),
+        "app.reload-connections" => gettext("Reload connections"),
+        "win.show-help-overlay" => gettext("Show keyboard shortcuts"),
+        "win.fullscreen" => gettext("Toggle fullscreen"),
+        "win.show-sidebar" => gettext("Show navigation"),
+        "view.close" => gettext("Close connection"),
+        "view.term-copy" => gettext("Copy"),
+        "view.term-paste" => gettext("Paste"),
+        "view.term-select-all" => gettext("Select all"),
+        "view.term-zoom-in" => gettext("Zoom in"),
+        "view.term-zoom-out" => gettext("Zoom out"),
+        "view.term-zoom-reset" => gettext("Reset zoom"),
+        "view.term-find" => gettext("Search output"),
+        _ => action.to_string(),
+    }
+}
+
+fn is_function_key(keyval: gdk::Key) -> bool {
+    keyval.name().is_some_and(|name| {
+        name.len() > 1 && name.starts_with('F') && name[1..].chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+fn set_error_class(widget: &impl IsA<gtk::Widget>, valid: bool) {
+    if valid {
+        widget.remove_css_class("error");
+    } else {
+        widget.add_css_class("error");
+    }
+}
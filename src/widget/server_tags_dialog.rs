@@ -0,0 +1,63 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Dialog for editing the user tags of a server.
+use adw::prelude::*;
+use gettextrs::gettext;
+use libfieldmonitor::i18n::gettext_f;
+
+/// Lets the user edit the comma separated `tags` of the server titled `server_title`. Returns
+/// the new tags, or `None` if the dialog was cancelled.
+pub async fn edit_server_tags(
+    server_title: &str,
+    tags: &[String],
+    window: Option<&gtk::Window>,
+) -> Option<Vec<String>> {
+    let tags_row = adw::EntryRow::builder()
+        .title(gettext("Tags, separated by commas"))
+        .text(tags.join(", "))
+        .activates_default(true)
+        .build();
+    let list = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+    list.append(&tags_row);
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext_f("Tags of {server}", &[("server", server_title)]))
+        .body(gettext("Smart folders can collect servers by their tags."))
+        .extra_child(&list)
+        .close_response("cancel")
+        .default_response("save")
+        .build();
+    dialog.add_responses(&[("cancel", &gettext("Cancel")), ("save", &gettext("Save"))]);
+    dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+
+    if dialog.choose_future(window).await.as_str() != "save" {
+        return None;
+    }
+    Some(
+        tags_row
+            .text()
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(ToString::to_string)
+            .collect(),
+    )
+}
@@ -0,0 +1,233 @@
+/* Copyright 2024 Marco Köpcke
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+//! Indicator for whether a server is online.
+//!
+//! The states differ in shape as well as color and are exposed to assistive technologies, so
+//! they can be told apart without seeing colors.
+//!
+//! If the path of the server is known, the tooltip also shows its recorded availability history.
+use crate::availability_history::{self, Sample};
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::glib;
+use libfieldmonitor::i18n::{format_date_time, format_relative_time, gettext_f};
+use std::cell::{Cell, RefCell};
+
+/// Width of the indicator, also used as a placeholder if the status is unknown.
+const INDICATOR_SIZE: i32 = 8;
+
+/// Number of the most recent samples shown in the sparkline of the tooltip.
+const SPARKLINE_SAMPLES: usize = 48;
+/// Width of a single sample in the sparkline, including the gap to the next one.
+const SPARKLINE_BAR_WIDTH: i32 = 3;
+const SPARKLINE_HEIGHT: i32 = 16;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct FieldMonitorStatusIndicator {
+        pub online: Cell<Option<bool>>,
+        /// Path of the server, used to look up its availability history.
+        pub history_path: RefCell<Option<String>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for FieldMonitorStatusIndicator {
+        const NAME: &'static str = "FieldMonitorStatusIndicator";
+        type Type = super::FieldMonitorStatusIndicator;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_accessible_role(gtk::AccessibleRole::Img);
+        }
+    }
+
+    impl ObjectImpl for FieldMonitorStatusIndicator {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+
+            obj.set_valign(gtk::Align::Center);
+            obj.set_width_request(INDICATOR_SIZE);
+            obj.add_css_class("fm-status-indicator");
+            obj.connect_query_tooltip(|obj, _, _, _, tooltip| obj.query_tooltip(tooltip));
+        }
+    }
+    impl WidgetImpl for FieldMonitorStatusIndicator {}
+    impl BinImpl for FieldMonitorStatusIndicator {}
+}
+
+glib::wrapper! {
+    pub struct FieldMonitorStatusIndicator(ObjectSubclass<imp::FieldMonitorStatusIndicator>)
+        @extends gtk::Widget, adw::Bin,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl FieldMonitorStatusIndicator {
+    pub fn new(is_online: Option<bool>) -> Self {
+        let slf: Self = glib::Object::new();
+        slf.set_online(is_online);
+        slf
+    }
+
+    /// Shows the availability history of the server at `path` in the tooltip.
+    pub fn set_history_path(&self, path: &str) {
+        self.imp().history_path.replace(Some(path.to_string()));
+        self.set_online(self.imp().online.get());
+    }
+
+    /// Shows whether the server is online. If `None`, the status is unknown and nothing is
+    /// shown.
+    pub fn set_online(&self, is_online: Option<bool>) {
+        self.imp().online.set(is_online);
+        self.remove_css_class("online");
+        self.remove_css_class("offline");
+
+        let Some(is_online) = is_online else {
+            self.set_child(None::<&gtk::Widget>);
+            self.set_tooltip_text(None);
+            self.update_state(&[gtk::accessible::State::Hidden(true)]);
+            return;
+        };
+
+        let (class, label, icon_name) = if is_online {
+            ("online", gettext("Online"), "circle-filled-symbolic")
+        } else {
+            (
+                "offline",
+                gettext("Offline"),
+                "circle-outline-thick-symbolic",
+            )
+        };
+        self.add_css_class(class);
+        self.set_child(Some(
+            &gtk::Image::builder()
+                .pixel_size(INDICATOR_SIZE)
+                .icon_name(icon_name)
+                .accessible_role(gtk::AccessibleRole::Presentation)
+                .build(),
+        ));
+        self.set_tooltip_text(Some(&label));
+        self.update_state(&[gtk::accessible::State::Hidden(false)]);
+        let description = match self.last_seen_online_text() {
+            Some(last_seen) if !is_online => last_seen,
+            _ => gettext("Server status"),
+        };
+        self.update_property(&[
+            gtk::accessible::Property::Label(&label),
+            gtk::accessible::Property::Description(&description),
+        ]);
+    }
+
+    fn last_seen_online_text(&self) -> Option<String> {
+        let path = self.imp().history_path.borrow();
+        let time = availability_history::last_seen_online(path.as_deref()?)?;
+        Some(gettext_f(
+            "Last seen online: {time}",
+            &[("time", &format_relative_time(&time))],
+        ))
+    }
+
+    /// Shows the status, and the availability history if known, in the tooltip.
+    fn query_tooltip(&self, tooltip: &gtk::Tooltip) -> bool {
+        let Some(is_online) = self.imp().online.get() else {
+            return false;
+        };
+        let Some(path) = self.imp().history_path.borrow().clone() else {
+            return false;
+        };
+        let samples = availability_history::samples(&path);
+
+        let content = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(6)
+            .build();
+        content.append(
+            &gtk::Label::builder()
+                .label(if is_online {
+                    gettext("Online")
+                } else {
+                    gettext("Offline")
+                })
+                .xalign(0.0)
+                .build(),
+        );
+        if samples.len() > 1 {
+            content.append(&make_sparkline(&samples));
+            if let Some(since) = samples.first().and_then(Sample::date_time) {
+                content.append(
+                    &gtk::Label::builder()
+                        .label(gettext_f(
+                            "Recorded since {time}",
+                            &[("time", &format_date_time(&since))],
+                        ))
+                        .xalign(0.0)
+                        .css_classes(["caption", "dim-label"])
+                        .build(),
+                );
+            }
+        }
+        if !is_online {
+            let last_seen = self
+                .last_seen_online_text()
+                .unwrap_or_else(|| gettext("Not seen online yet"));
+            content.append(&gtk::Label::builder().label(last_seen).xalign(0.0).build());
+        }
+        tooltip.set_custom(Some(&content));
+        true
+    }
+}
+
+/// Bars for the most recent `samples`, oldest first. Online samples are drawn as full bars,
+/// offline samples as short bars, so they can be told apart without colors.
+fn make_sparkline(samples: &[Sample]) -> gtk::DrawingArea {
+    let samples = samples[samples.len().saturating_sub(SPARKLINE_SAMPLES)..].to_vec();
+    let area = gtk::DrawingArea::builder()
+        .content_width(SPARKLINE_SAMPLES as i32 * SPARKLINE_BAR_WIDTH)
+        .content_height(SPARKLINE_HEIGHT)
+        .halign(gtk::Align::Start)
+        .css_classes(["fm-availability-sparkline"])
+        .build();
+    area.set_draw_func(move |area, cr, _, height| {
+        let color = area.color();
+        let height = f64::from(height);
+        for (i, sample) in samples.iter().enumerate() {
+            let (bar_height, alpha) = if sample.online {
+                (height, 1.0)
+            } else {
+                (height / 4.0, 0.55)
+            };
+            cr.set_source_rgba(
+                color.red().into(),
+                color.green().into(),
+                color.blue().into(),
+                f64::from(color.alpha()) * alpha,
+            );
+            cr.rectangle(
+                f64::from(i as i32 * SPARKLINE_BAR_WIDTH),
+                height - bar_height,
+                f64::from(SPARKLINE_BAR_WIDTH - 1),
+                bar_height,
+            );
+            let _ = cr.fill();
+        }
+    });
+    area
+}
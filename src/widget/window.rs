@@ -18,14 +18,18 @@
 
 use crate::application::FieldMonitorApplication;
 use crate::connection_loader::ConnectionLoader;
-use crate::settings::{SettingHeaderBarBehavior, SettingSharpWindowCorners};
-use crate::widget::close_warning_dialog::FieldMonitorCloseWarningDialog;
+use crate::settings::{
+    FieldMonitorSettings, SettingHeaderBarBehavior, SettingSharpWindowCorners, WindowState,
+};
+use crate::widget::close_warning_dialog::{CloseWarningKind, FieldMonitorCloseWarningDialog};
 use crate::widget::connection_list::{
-    FieldMonitorConnectionStack, FieldMonitorNavbarConnectionList,
+    FieldMonitorConnectionStack, FieldMonitorNavbarConnectionList, SMART_FOLDER_PAGE_PREFIX,
 };
 use crate::widget::connection_view::{
     FieldMonitorConnectionTabView, FieldMonitorNavbarConnectionView, FieldMonitorServerScreen,
 };
+use crate::widget::dashboard::FieldMonitorDashboard;
+use crate::widget::loading_indicator::FieldMonitorLoadingIndicator;
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use async_std::task::sleep;
@@ -34,6 +38,7 @@ use gtk::{gdk, gio, glib};
 use log::debug;
 use std::cell::Cell;
 use std::cell::RefCell;
+use std::iter;
 use std::time::Duration;
 
 mod imp {
@@ -79,11 +84,37 @@ mod imp {
         pub welcome_button_box: TemplateChild<gtk::Box>,
         #[template_child]
         pub welcome_window_title: TemplateChild<adw::WindowTitle>,
+        #[template_child]
+        pub workspace_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub workspace_model: TemplateChild<gtk::StringList>,
         #[property(get, set)]
         pub quick_connect_visible: Cell<bool>,
+        #[property(get, set)]
+        pub dashboard_visible: Cell<bool>,
+        /// Whether the window only shows its connection views, see the `kiosk-mode` setting.
+        #[property(get, set)]
+        pub kiosk: Cell<bool>,
+        /// Whether a connection view of the window grabbed mouse and keyboard input.
+        #[property(get, set)]
+        pub input_grabbed: Cell<bool>,
+        /// Set while the window is fullscreen and input is grabbed. Toasts that are not of high
+        /// priority and desktop notifications are then held back, so they don't interrupt the
+        /// session.
+        #[property(get)]
+        pub focus_mode: Cell<bool>,
+        /// Toasts held back during focus mode.
+        pub queued_toasts: RefCell<Vec<adw::Toast>>,
         pub tab_title_notify_binding: RefCell<Option<(gtk::Widget, glib::SignalHandlerId)>>,
         pub force_close: Cell<bool>,
         pub inhibit_possible_sidebar_click: Cell<bool>,
+        /// Set while the workspace drop-down is being rebuilt, to ignore its selection changes.
+        pub updating_workspaces: Cell<bool>,
+        /// Position of the window among the open windows, its navigation state is stored under.
+        pub slot: Cell<usize>,
+        pub navigation_state: RefCell<WindowState>,
+        /// The page to show once the connections are listed.
+        pub pending_page: RefCell<Option<String>>,
     }
 
     #[glib::object_subclass]
@@ -93,6 +124,8 @@ mod imp {
         type ParentType = adw::ApplicationWindow;
 
         fn class_init(klass: &mut Self::Class) {
+            FieldMonitorLoadingIndicator::ensure_type();
+            FieldMonitorDashboard::ensure_type();
             Self::bind_template(klass);
             Self::Type::bind_template_callbacks(klass);
         }
@@ -184,6 +217,8 @@ impl FieldMonitorWindow {
             ),
         );
 
+        slf.connect_input_grabbed_notify(Self::update_focus_mode);
+
         slf.on_app_loading_connections_changed(application);
         slf.on_app_starting_changed(application);
 
@@ -202,14 +237,108 @@ impl FieldMonitorWindow {
                 move |settings| slf
                     .on_settings_header_bar_behavior_changed(settings.header_bar_behavior())
             ));
+            slf.update_workspaces(&settings);
+            settings.connect_workspaces_changed(glib::clone!(
+                #[weak]
+                slf,
+                move |settings| slf.update_workspaces(settings)
+            ));
+            settings.connect_active_workspace_notify(glib::clone!(
+                #[weak]
+                slf,
+                move |settings| slf.on_settings_active_workspace_changed(settings)
+            ));
+            settings.connect_appearances_changed(glib::clone!(
+                #[weak]
+                slf,
+                move |_| slf.imp().navbar_connection_list.refresh()
+            ));
         } else {
             slf.on_settings_sharp_window_corners_changed(Default::default());
             slf.on_settings_header_bar_behavior_changed(Default::default());
         }
 
+        slf.load_navigation_state(application);
+
         slf
     }
 
+    /// Takes the lowest slot not used by another window and restores the navigation state
+    /// stored for it.
+    fn load_navigation_state(&self, application: &FieldMonitorApplication) {
+        let imp = self.imp();
+        let used_slots = application
+            .windows()
+            .into_iter()
+            .filter_map(|window| window.downcast::<FieldMonitorWindow>().ok())
+            .filter(|window| window != self)
+            .map(|window| window.imp().slot.get())
+            .collect::<Vec<_>>();
+        let slot = (0..).find(|slot| !used_slots.contains(slot)).unwrap();
+        imp.slot.set(slot);
+
+        let Some(settings) = application.settings() else {
+            return;
+        };
+        let state = settings.window_state(slot);
+        imp.pending_page.replace(Some(state.page.clone()));
+        imp.navigation_state.replace(state);
+        if !application.loading_connections() {
+            self.restore_navigation_page();
+        }
+    }
+
+    /// Shows the page that was last shown in the slot of the window, once.
+    fn restore_navigation_page(&self) {
+        let imp = self.imp();
+        let Some(page) = imp.pending_page.take() else {
+            return;
+        };
+        if imp.connection_list_stack.is_empty() || self.kiosk() {
+            return;
+        }
+        match page.as_str() {
+            "" => {}
+            WindowState::PAGE_DASHBOARD => self.set_dashboard_visible(true),
+            WindowState::PAGE_QUICK_CONNECT => self.set_quick_connect_visible(true),
+            connection_id => {
+                // Smart folders are shown in all workspaces.
+                let in_workspace = connection_id.starts_with(SMART_FOLDER_PAGE_PREFIX)
+                    || self
+                        .application()
+                        .and_downcast::<FieldMonitorApplication>()
+                        .and_then(|app| app.settings())
+                        .map_or(true, |settings| {
+                            settings.is_in_active_workspace(connection_id)
+                        });
+                if in_workspace && imp.connection_list_stack.has_connection(connection_id) {
+                    imp.connection_list_stack.select_connection(connection_id);
+                }
+            }
+        }
+    }
+
+    /// Remembers `page` as the page last shown from the sidebar, if given, and stores the
+    /// navigation state of the window.
+    fn save_navigation_state(&self, page: Option<&str>) {
+        let imp = self.imp();
+        if self.kiosk() {
+            return;
+        }
+        if let Some(page) = page {
+            // A page chosen before the connections were listed replaces the one to restore.
+            imp.pending_page.replace(None);
+            imp.navigation_state.borrow_mut().page = page.to_string();
+        }
+        if let Some(settings) = self
+            .application()
+            .and_downcast::<FieldMonitorApplication>()
+            .and_then(|app| app.settings())
+        {
+            settings.set_window_state(imp.slot.get(), &imp.navigation_state.borrow());
+        }
+    }
+
     fn setup_actions(&self) {
         self.add_action(&gio::PropertyAction::new(
             "fullscreen",
@@ -221,6 +350,11 @@ impl FieldMonitorWindow {
             self,
             "quick-connect-visible",
         ));
+        self.add_action(&gio::PropertyAction::new(
+            "dashboard",
+            self,
+            "dashboard-visible",
+        ));
         self.add_action_entries([gio::ActionEntry::builder("show-sidebar")
             .activate(glib::clone!(
                 #[weak(rename_to=slf)]
@@ -235,9 +369,35 @@ impl FieldMonitorWindow {
     }
 
     pub fn toast(&self, msg: &str) {
-        self.imp()
-            .toast_overlay
-            .add_toast(adw::Toast::builder().title(msg).timeout(5).build())
+        self.add_toast(adw::Toast::builder().title(msg).timeout(5).build())
+    }
+
+    /// Shows `toast`. In focus mode it is held back until focus mode ends, unless it has a
+    /// high priority.
+    pub fn add_toast(&self, toast: adw::Toast) {
+        if self.focus_mode() && toast.priority() != adw::ToastPriority::High {
+            self.imp().queued_toasts.borrow_mut().push(toast);
+        } else {
+            self.imp().toast_overlay.add_toast(toast);
+        }
+    }
+
+    fn update_focus_mode(&self) {
+        let imp = self.imp();
+        let focus_mode = self.is_fullscreen() && self.input_grabbed();
+        if imp.focus_mode.replace(focus_mode) == focus_mode {
+            return;
+        }
+        debug!("focus mode: {focus_mode}");
+        self.notify_focus_mode();
+        if !focus_mode {
+            for toast in imp.queued_toasts.take() {
+                imp.toast_overlay.add_toast(toast);
+            }
+            if let Some(app) = self.application().and_downcast::<FieldMonitorApplication>() {
+                app.flush_queued_notifications();
+            }
+        }
     }
 
     pub fn toast_overlay(&self) -> &adw::ToastOverlay {
@@ -347,6 +507,13 @@ impl FieldMonitorWindow {
         }
     }
 
+    /// Shows the server list of a connection.
+    pub fn show_connection(&self, connection_id: &str) {
+        self.imp()
+            .connection_list_stack
+            .select_connection(connection_id);
+    }
+
     pub(crate) fn tab_view(&self) -> FieldMonitorConnectionTabView {
         self.imp().active_connection_tab_view.get()
     }
@@ -361,16 +528,23 @@ impl FieldMonitorWindow {
             // User has forced the window to close.
 
             false
+        } else if self.kiosk() {
+            // Kiosk windows stay open until the application is quit.
+
+            true
         } else if imp.active_connection_tab_view.n_pages() > 0 {
             // Handle still open connections and ask user to confirm.
 
             let open_connection_descs = imp.active_connection_tab_view.describe_active();
 
-            if open_connection_descs.is_empty() {
+            if open_connection_descs.is_empty() || !FieldMonitorCloseWarningDialog::is_enabled() {
                 return false;
             }
 
-            let dialog = FieldMonitorCloseWarningDialog::new(open_connection_descs);
+            let dialog = FieldMonitorCloseWarningDialog::new(
+                CloseWarningKind::Window,
+                open_connection_descs,
+            );
 
             dialog.connect_closure(
                 "response",
@@ -410,6 +584,23 @@ impl FieldMonitorWindow {
         }
     }
 
+    #[template_callback]
+    fn on_self_kiosk_changed(&self) {
+        let imp = self.imp();
+        let kiosk = self.kiosk();
+        if kiosk {
+            imp.connection_view_split_view.set_show_sidebar(false);
+        }
+        imp.connection_view_split_view
+            .set_enable_show_gesture(!kiosk);
+        if let Some(action) = self
+            .lookup_action("show-sidebar")
+            .and_downcast::<gio::SimpleAction>()
+        {
+            action.set_enabled(!kiosk);
+        }
+    }
+
     #[template_callback]
     fn on_self_fullscreened_changed(&self) {
         if self.is_fullscreen() {
@@ -421,6 +612,7 @@ impl FieldMonitorWindow {
                 .button_fullscreen
                 .set_icon_name("arrows-pointing-outward-symbolic");
         }
+        self.update_focus_mode();
     }
 
     #[template_callback]
@@ -431,6 +623,38 @@ impl FieldMonitorWindow {
         }
     }
 
+    #[template_callback]
+    fn on_workspace_dropdown_selected_changed(&self) {
+        let imp = self.imp();
+        if imp.updating_workspaces.get() {
+            return;
+        }
+        let Some(settings) = self
+            .application()
+            .and_downcast::<FieldMonitorApplication>()
+            .and_then(|app| app.settings())
+        else {
+            return;
+        };
+
+        let workspace = match imp.workspace_dropdown.selected() {
+            0 | gtk::INVALID_LIST_POSITION => String::new(),
+            i => imp
+                .workspace_model
+                .string(i)
+                .map(String::from)
+                .unwrap_or_default(),
+        };
+        if let Some(connection_id) = imp.connection_list_stack.visible_connection_id() {
+            imp.navigation_state
+                .borrow_mut()
+                .workspace_selections
+                .insert(settings.active_workspace(), connection_id);
+            self.save_navigation_state(None);
+        }
+        settings.set_active_workspace(workspace);
+    }
+
     #[template_callback]
     fn on_connection_list_visible_connection_id_changed(&self) {
         let imp = self.imp();
@@ -447,13 +671,15 @@ impl FieldMonitorWindow {
         if connection_id.is_none() && connection_list_visible {
             debug!("switching to welcome view");
             imp.inner_list_stack.set_visible_child_name("welcome");
-        } else if connection_id.is_some() {
+        } else if let Some(connection_id) = connection_id {
+            self.save_navigation_state(Some(&connection_id));
             if !connection_list_visible {
                 debug!("switching to connection list");
                 self.unselect_connection_view();
                 imp.inner_list_stack
                     .set_visible_child_name("connection-list");
                 self.unselect_quick_connect();
+                self.unselect_dashboard();
                 self.maybe_disable_no_sidebar_mode();
             }
             self.maybe_clicked_item_on_sidebar();
@@ -467,8 +693,10 @@ impl FieldMonitorWindow {
 
         if self.quick_connect_visible() {
             imp.inner_list_stack.set_visible_child_name("quick-connect");
+            self.save_navigation_state(Some(WindowState::PAGE_QUICK_CONNECT));
             self.unselect_connection_view();
             self.unselect_connection_list();
+            self.unselect_dashboard();
             self.maybe_clicked_item_on_sidebar();
             self.maybe_disable_no_sidebar_mode();
         } else if imp.inner_list_stack.visible_child_name().as_deref() == Some("quick-connect") {
@@ -477,6 +705,25 @@ impl FieldMonitorWindow {
         }
     }
 
+    #[template_callback]
+    fn on_dashboard_visible_changed(&self) {
+        let imp = self.imp();
+        debug!("dashboard visible changed");
+
+        if self.dashboard_visible() {
+            imp.inner_list_stack.set_visible_child_name("dashboard");
+            self.save_navigation_state(Some(WindowState::PAGE_DASHBOARD));
+            self.unselect_connection_view();
+            self.unselect_connection_list();
+            self.unselect_quick_connect();
+            self.maybe_clicked_item_on_sidebar();
+            self.maybe_disable_no_sidebar_mode();
+        } else if imp.inner_list_stack.visible_child_name().as_deref() == Some("dashboard") {
+            // do not allow disabling if stack still visible
+            self.set_dashboard_visible(true);
+        }
+    }
+
     #[template_callback]
     fn on_active_connection_tab_view_visible_page_changed(&self) {
         let imp = self.imp();
@@ -522,6 +769,7 @@ impl FieldMonitorWindow {
                     slf.unselect_connection_view();
                     slf.unselect_connection_list();
                     slf.unselect_quick_connect();
+                    slf.unselect_dashboard();
                     imp.inhibit_possible_sidebar_click.set(false);
                 }
             ));
@@ -554,6 +802,7 @@ impl FieldMonitorWindow {
                 }
             } else {
                 self.maybe_disable_no_sidebar_mode();
+                self.restore_navigation_page();
             }
         }
     }
@@ -585,6 +834,53 @@ impl FieldMonitorWindow {
         }
     }
 
+    fn update_workspaces(&self, settings: &FieldMonitorSettings) {
+        let imp = self.imp();
+        let workspaces = settings.workspaces();
+        let active = settings.active_workspace();
+
+        let all_connections = gettext("All Connections");
+        let names = iter::once(all_connections.as_str())
+            .chain(workspaces.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+
+        imp.updating_workspaces.set(true);
+        imp.workspace_model
+            .splice(0, imp.workspace_model.n_items(), &names);
+        let selected = workspaces
+            .iter()
+            .position(|w| *w == active)
+            .map_or(0, |i| i + 1);
+        imp.workspace_dropdown.set_selected(selected as u32);
+        imp.updating_workspaces.set(false);
+
+        imp.workspace_dropdown.set_visible(!workspaces.is_empty());
+        imp.navbar_connection_list.refresh();
+    }
+
+    fn on_settings_active_workspace_changed(&self, settings: &FieldMonitorSettings) {
+        let imp = self.imp();
+        let active = settings.active_workspace();
+        self.update_workspaces(settings);
+
+        // Restore the connection that was last selected in this workspace.
+        let selection = imp
+            .navigation_state
+            .borrow()
+            .workspace_selections
+            .get(&active)
+            .cloned();
+        match selection {
+            Some(connection_id)
+                if settings.is_in_active_workspace(&connection_id)
+                    && imp.connection_list_stack.has_connection(&connection_id) =>
+            {
+                imp.connection_list_stack.select_connection(&connection_id)
+            }
+            _ => self.unselect_connection_list(),
+        }
+    }
+
     fn on_settings_header_bar_behavior_changed(&self, value: SettingHeaderBarBehavior) {
         if matches!(value, SettingHeaderBarBehavior::Overlay) {
             self.add_css_class("overlay-headerbar");
@@ -596,6 +892,7 @@ impl FieldMonitorWindow {
     pub(crate) fn select_connection_view(&self) {
         self.unselect_connection_list();
         self.unselect_quick_connect();
+        self.unselect_dashboard();
         self.imp()
             .inner_stack
             .set_visible_child_name("connection-view");
@@ -620,6 +917,10 @@ impl FieldMonitorWindow {
         self.set_quick_connect_visible(false);
     }
 
+    fn unselect_dashboard(&self) {
+        self.set_dashboard_visible(false);
+    }
+
     fn maybe_clicked_item_on_sidebar(&self) {
         let imp = self.imp();
         if !imp.inhibit_possible_sidebar_click.get() {
@@ -16,10 +16,13 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::FromRawFd;
 use std::process::exit;
 
 use crate::dbus_client::{make_dbus_client, VtePtyProcMonProxy};
-use crate::{debug, DBUS_KEY_ENV_VAR};
+use crate::{debug, KEY_FD};
 
 pub struct PtyClient {
     extra_args: Vec<String>,
@@ -28,7 +31,7 @@ pub struct PtyClient {
 
 impl PtyClient {
     async fn new_from_env() -> zbus::Result<Self> {
-        let extra_args_key = env::var(DBUS_KEY_ENV_VAR).unwrap_or_default();
+        let extra_args_key = read_key()?;
         let name = env::args().nth(1).unwrap_or_default();
         let dbus_client = make_dbus_client(&name).await?;
         let extra_args = dbus_client.extra_arguments(&extra_args_key).await?;
@@ -70,6 +73,16 @@ impl PtyClient {
     }
 }
 
+/// Reads the key from the inherited file descriptor and closes it.
+fn read_key() -> zbus::Result<String> {
+    // SAFETY: The fd is set up by the server for this process and not used anywhere else.
+    let mut file = unsafe { File::from_raw_fd(KEY_FD) };
+    let mut key = String::new();
+    file.read_to_string(&mut key)
+        .map_err(|err| zbus::Error::Failure(format!("failed to read key: {err}")))?;
+    Ok(key)
+}
+
 pub async fn setup_driver() -> PtyClient {
     match PtyClient::new_from_env().await {
         Ok(dri) => dri,
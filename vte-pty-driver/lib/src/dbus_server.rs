@@ -39,9 +39,12 @@ pub struct RunningVtePtyProcMon {
 
 #[interface(name = "de.capypara.FieldMonitor.VtePtyProcMon1")]
 impl VtePtyProcMon {
-    fn extra_arguments(&mut self, fm_key: &str) -> zbus::fdo::Result<&[String]> {
-        if self.fm_key == fm_key {
-            Ok(&self.extra_args)
+    /// Returns the arguments once. Later calls are denied, so they can not be retrieved by anyone
+    /// else after the driver has started.
+    fn extra_arguments(&mut self, fm_key: &str) -> zbus::fdo::Result<Vec<String>> {
+        if !self.fm_key.is_empty() && self.fm_key == fm_key {
+            self.fm_key.clear();
+            Ok(std::mem::take(&mut self.extra_args))
         } else {
             Err(zbus::fdo::Error::AccessDenied(String::new()))
         }
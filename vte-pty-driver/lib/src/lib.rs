@@ -19,7 +19,10 @@
 #[cfg(feature = "client")]
 pub use client::*;
 
-pub const DBUS_KEY_ENV_VAR: &str = "FM_KEY";
+/// File descriptor the driver process inherits, from which it reads the key for retrieving its
+/// arguments. The key is not passed via the environment or argv, as those are readable by other
+/// processes.
+pub const KEY_FD: i32 = 3;
 pub const DBUS_PATH: &str = "/de/capypara/FieldMonitor/VtePtyProcMon";
 pub const DBUS_INTERFACE: &str = "de.capypara.FieldMonitor.VtePtyProcMon1";
 
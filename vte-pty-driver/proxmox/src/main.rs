@@ -20,19 +20,17 @@ use async_tungstenite::tokio::client_async_tls_with_connector;
 use async_tungstenite::tungstenite;
 use async_tungstenite::tungstenite::client::IntoClientRequest;
 use async_tungstenite::tungstenite::handshake::client::generate_key;
-use async_tungstenite::tungstenite::http::Uri;
 use async_tungstenite::tungstenite::Message;
+use field_monitor_core::net::{http_client, tls_connect_configuration, NetConfig, TlsVerification};
 use field_monitor_vte_driver_lib::{args, debug, error, setup_driver, PtyClient};
 use futures::prelude::*;
 use http::HeaderValue;
 use nix::libc;
 use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
 use nix::sys::termios::{cfmakeraw, tcgetattr, tcsetattr, SetArg};
-use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
-use proxmox_api::{NodeId, ProxmoxApiClient, Termproxy, VmId, VmType, VncwebsocketInput};
+use proxmox_api::{ClientConfig, NodeId, Termproxy, VmId, VmType, VncwebsocketInput};
 use std::error::Error;
 use std::mem;
-use std::ops::Deref;
 use std::os::fd::{AsFd, AsRawFd, RawFd};
 use std::pin::Pin;
 use std::process::exit;
@@ -43,7 +41,7 @@ use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
 use tokio::select;
 use tokio::sync::{Mutex, Notify};
-use tokio::time::sleep;
+use tokio::time::{sleep, timeout};
 use tokio_util::io::ReaderStream;
 pub static CHANGED_WINSIZE_NOTIFY: Notify = Notify::const_new();
 
@@ -92,8 +90,11 @@ async fn run_console(client: &Arc<PtyClient>) -> Result<(), anyhow::Error> {
         node_id,
         vm_type,
         vm_id,
-        termproxy_str
+        termproxy_str,
+        login_user
     ));
+    // Only set for containers, whose console shows a login prompt.
+    let login_user = (!login_user.is_empty()).then(|| login_user.to_string());
     let (vncwebsocket_user, vncwebsocket) = {
         let termproxy: Termproxy = serde_json::from_str(termproxy_str)?;
         (
@@ -104,7 +105,16 @@ async fn run_console(client: &Arc<PtyClient>) -> Result<(), anyhow::Error> {
             },
         )
     };
-    let root = Uri::from_str(root)?;
+    let config = ClientConfig::from_args(&[
+        connection_type,
+        root,
+        user_tokenid,
+        pass_apikey,
+        ignore_ssl_errors,
+    ])?;
+    let net_config = NetConfig::default().with_tls(TlsVerification::from_ignore_errors(
+        config.ignore_ssl_errors,
+    ));
     let node_id = NodeId::from_str(node_id)?;
     let vm = if vm_id.is_empty() {
         None
@@ -154,26 +164,7 @@ async fn run_console(client: &Arc<PtyClient>) -> Result<(), anyhow::Error> {
 
     debug!(&client, "setup sigaction");
 
-    let proxmox_client = match connection_type.deref() {
-        "apikey" => {
-            ProxmoxApiClient::connect_with_apikey(
-                &root,
-                user_tokenid,
-                pass_apikey.into(),
-                ignore_ssl_errors == "1",
-            )
-            .await?
-        }
-        _ => {
-            ProxmoxApiClient::connect_with_ticket(
-                &root,
-                user_tokenid,
-                pass_apikey.into(),
-                ignore_ssl_errors == "1",
-            )
-            .await?
-        }
-    };
+    let proxmox_client = config.http_client(http_client(&net_config)?).connect()?;
 
     debug!(&client, "client connected");
 
@@ -202,14 +193,12 @@ async fn run_console(client: &Arc<PtyClient>) -> Result<(), anyhow::Error> {
     let domain = domain(&request)?;
     let port = port(&request)?;
 
-    let socket = TcpStream::connect((domain.as_str(), port)).await?;
-    let mut connector_builder = SslConnector::builder(SslMethod::tls())?;
-    connector_builder.set_verify(if ignore_ssl_errors == "1" {
-        SslVerifyMode::NONE
-    } else {
-        SslVerifyMode::PEER
-    });
-    let connector = connector_builder.build().configure()?;
+    let socket = timeout(
+        net_config.connect_timeout,
+        TcpStream::connect((domain.as_str(), port)),
+    )
+    .await??;
+    let connector = tls_connect_configuration(&net_config)?;
     let mut ws = match client_async_tls_with_connector(request, socket, Some(connector)).await {
         Ok((ws, _)) => ws,
         Err(err) => {
@@ -246,12 +235,17 @@ async fn run_console(client: &Arc<PtyClient>) -> Result<(), anyhow::Error> {
 
     debug!(&client, "answer received. starting.");
 
+    if login_user.is_some() {
+        // The login prompt may have been shown before we connected, this shows it again.
+        send_input(&sink, b"\r").await?;
+    }
+
     select!(
         r = watch_stdin(client.clone(), sink.clone()) => {
             debug!(&client, "error in watch_stdin");
             r
         },
-        r = watch_ws(client.clone(), Box::pin(stream)) => {
+        r = watch_ws(client.clone(), Box::pin(stream), sink.clone(), login_user) => {
             debug!(&client, "error in watch_ws");
             r
         },
@@ -278,21 +272,31 @@ where
     let mut stdin = ReaderStream::new(tokio::io::stdin());
     while let Some(data) = stdin.try_next().await? {
         debug!(&client, "watch_stdin: got data");
-        sink.lock()
-            .await
-            .send(Message::Binary(
-                format!("0:{}:", data.len())
-                    .into_bytes()
-                    .into_iter()
-                    .chain(data.into_iter())
-                    .collect::<Vec<_>>(),
-            ))
-            .await?;
+        send_input(&sink, &data).await?;
         debug!(&client, "watch_stdin: sent data");
     }
     Ok(())
 }
 
+/// Sends `data` to the remote terminal, as if it was typed.
+async fn send_input<S>(sink: &Mutex<Pin<Box<S>>>, data: &[u8]) -> Result<(), anyhow::Error>
+where
+    S: Sink<Message> + Send + Sync,
+    S::Error: Send + Sync + Error + 'static,
+{
+    sink.lock()
+        .await
+        .send(Message::Binary(
+            format!("0:{}:", data.len())
+                .into_bytes()
+                .into_iter()
+                .chain(data.iter().copied())
+                .collect::<Vec<_>>(),
+        ))
+        .await?;
+    Ok(())
+}
+
 async fn keep_alive<S>(
     client: Arc<PtyClient>,
     sink: Arc<Mutex<Pin<Box<S>>>>,
@@ -335,9 +339,18 @@ where
     }
 }
 
-async fn watch_ws<S>(client: Arc<PtyClient>, mut stream: Pin<Box<S>>) -> Result<(), anyhow::Error>
+/// Writes the output of the remote terminal to stdout. If `login_user` is set, it is entered
+/// at the first login prompt.
+async fn watch_ws<S, K>(
+    client: Arc<PtyClient>,
+    mut stream: Pin<Box<S>>,
+    sink: Arc<Mutex<Pin<Box<K>>>>,
+    mut login_user: Option<String>,
+) -> Result<(), anyhow::Error>
 where
     S: Stream<Item = tungstenite::Result<Message>>,
+    K: Sink<Message> + Send + Sync,
+    K::Error: Send + Sync + Error + 'static,
 {
     debug!(&client, "starting watch_ws");
     let mut stdout = tokio::io::stdout();
@@ -352,10 +365,23 @@ where
         stdout.write_all(&data).await?;
         stdout.flush().await?;
         debug!(&client, "watch_ws: sent data");
+
+        if login_user.is_some() && is_login_prompt(&data) {
+            let user = login_user.take().unwrap();
+            debug!(&client, "watch_ws: entering login user");
+            send_input(&sink, format!("{user}\r").as_bytes()).await?;
+        }
     }
     Ok(())
 }
 
+/// Whether `output` ends with a login prompt, eg. `debian login: `.
+fn is_login_prompt(output: &[u8]) -> bool {
+    String::from_utf8_lossy(output)
+        .trim_end()
+        .ends_with("login:")
+}
+
 // These utility functions are from async_tungstenite.
 
 /// Get a domain from an URL.